@@ -1,16 +1,20 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
 use std::io::Cursor;
 use std::ops::ControlFlow;
 use anyhow::{anyhow, bail, Context, Result};
+use java_string::{JavaStr, JavaString};
 use crate::class_constants::{attribute, opcode, type_annotation};
 use crate::class_reader::labels::Labels;
 use crate::class_reader::pool::{BootstrapMethodRead, PoolRead};
-use crate::{class_constants, ClassRead, jstring, OptionExpansion};
+use crate::{class_constants, ClassRead, ClassReadError, jstring, OptionExpansion, WhileReading};
 use crate::tree::annotation::Object;
-use crate::tree::class::{ClassAccess, ClassSignature, EnclosingMethod, InnerClass};
+use crate::tree::class::{ClassAccess, ClassName, ClassSignature, EnclosingMethod, InnerClass};
 use crate::tree::descriptor::ReturnDescriptor;
 use crate::tree::field::{FieldAccess, FieldDescriptor, FieldName, FieldSignature};
-use crate::tree::method::{MethodAccess, MethodDescriptor, MethodName, MethodParameter, MethodSignature, ParameterFlags};
+use crate::tree::method::{MethodAccess, MethodDescriptor, MethodName, MethodParameter, MethodRef, MethodSignature, ParameterFlags};
 use crate::tree::method::code::{ArrayType, Exception, Instruction, LocalVariableName, Lv, LvIndex};
+use crate::tree::method::raw_code::{RawCode, RawConstant, RawException, RawLv, RawStackMapTable};
 use crate::tree::module::{Module, ModuleExports, ModuleOpens, ModuleProvides, ModuleRequires};
 use crate::tree::record::RecordName;
 use crate::tree::type_annotation::{TargetInfoClass, TargetInfoCode, TargetInfoField, TargetInfoMethod, TypePath, TypePathKind};
@@ -19,7 +23,7 @@ use crate::visitor::annotation::{NamedElementValuesVisitor, UnnamedElementValues
 use crate::visitor::attribute::UnknownAttributeVisitor;
 use crate::visitor::class::ClassVisitor;
 use crate::visitor::field::FieldVisitor;
-use crate::visitor::method::code::{CodeVisitor, StackMapData, VerificationTypeInfo};
+use crate::visitor::method::code::{CodeInterests, CodeVisitor, StackMapData, VerificationTypeInfo};
 use crate::visitor::method::MethodVisitor;
 use crate::visitor::MultiClassVisitor;
 use crate::visitor::record::RecordComponentVisitor;
@@ -48,46 +52,170 @@ fn skip_attributes(reader: &mut impl ClassRead) -> Result<()> {
 //TODO: MultiClassVisitor should be changed into a two part thing like with NamedElementValue**s**Visitor and NamedElementValue****Visitor
 // this would allow us to have a visitor that "can return max 1 class" and a subtrait that also specifies "and can be called more often"
 pub(crate) fn read<V: MultiClassVisitor>(reader: &mut impl ClassRead, visitor: V) -> Result<V> {
-	let magic = reader.read_u32()?;
+	read_impl(reader, visitor, None, None, crate::MAX_ELEMENT_VALUE_DEPTH, CodeReadOptions::default())
+}
+
+/// Reads a class file like [`read`], but with a different limit for the nesting of annotations and arrays in element values.
+pub(crate) fn read_with_max_element_value_depth<V: MultiClassVisitor>(reader: &mut impl ClassRead, visitor: V, max_depth: usize) -> Result<V> {
+	read_impl(reader, visitor, None, None, max_depth, CodeReadOptions::default())
+}
+
+/// Reads a class file like [`read`], but skips all attributes not allowed by the filter.
+pub(crate) fn read_filtered<V: MultiClassVisitor>(reader: &mut impl ClassRead, visitor: V, filter: &AttributeFilter) -> Result<V> {
+	read_impl(reader, visitor, None, Some(filter), crate::MAX_ELEMENT_VALUE_DEPTH, CodeReadOptions::default())
+}
+
+/// Reads a class file like [`read`], but doesn't fail on unknown opcodes.
+///
+/// Instead, the unknown opcode is recorded, and the `Code` attribute of the method is given to the method visitor as an unknown
+/// attribute.
+pub(crate) fn read_lenient<V: MultiClassVisitor>(reader: &mut impl ClassRead, visitor: V) -> Result<(V, Vec<UnknownOpcode>)> {
+	let mut unknown_opcodes = Vec::new();
+	let visitor = read_impl(reader, visitor, Some(&mut unknown_opcodes), None, crate::MAX_ELEMENT_VALUE_DEPTH, CodeReadOptions::default())?;
+	Ok((visitor, unknown_opcodes))
+}
+
+/// Reads a class file like [`read`], but reads the code of all methods as [`RawCode`], as if each code visitor had the
+/// [`raw_code_only`][crate::visitor::method::code::CodeInterests::raw_code_only] interest.
+pub(crate) fn read_raw_code<V: MultiClassVisitor>(reader: &mut impl ClassRead, visitor: V) -> Result<V> {
+	read_impl(reader, visitor, None, None, crate::MAX_ELEMENT_VALUE_DEPTH, CodeReadOptions { raw_code: true, ..CodeReadOptions::default() })
+}
+
+/// Reads a class file like [`read`], but gives the bytecode offset of each instruction to the code visitors, as if each code
+/// visitor had the [`instruction_offsets`][crate::visitor::method::code::CodeInterests::instruction_offsets] interest.
+pub(crate) fn read_with_instruction_offsets<V: MultiClassVisitor>(reader: &mut impl ClassRead, visitor: V) -> Result<V> {
+	read_impl(reader, visitor, None, None, crate::MAX_ELEMENT_VALUE_DEPTH, CodeReadOptions { instruction_offsets: true, ..CodeReadOptions::default() })
+}
+
+/// How to read the code of methods, in addition to what the [`CodeInterests`] of the code visitors ask for.
+#[derive(Debug, Clone, Copy, Default)]
+struct CodeReadOptions {
+	/// Read all code as [`RawCode`].
+	raw_code: bool,
+	/// Give the bytecode offsets of the instructions to the code visitors.
+	instruction_offsets: bool,
+}
+
+/// A set of attribute names to read, skipping all other attributes.
+///
+/// Use this with [`read_class_filtered`][crate::read_class_filtered] to cheaply scan classes for specific attributes. The
+/// attributes not in the set are skipped without reading their content, even if the visitor is interested in them. This
+/// applies to the attributes of the class, its fields, methods, record components and the `Code` attribute alike: to read
+/// the `LineNumberTable`, both `Code` and `LineNumberTable` must be in the set.
+///
+/// The `BootstrapMethods` attribute is always read, as it's needed to read the `invokedynamic` instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeFilter {
+	names: HashSet<JavaString>,
+}
+
+impl AttributeFilter {
+	/// Creates a filter allowing the attributes with the given names.
+	pub fn new<'a>(names: impl IntoIterator<Item=&'a str>) -> AttributeFilter {
+		AttributeFilter {
+			names: names.into_iter().map(JavaString::from).collect(),
+		}
+	}
+
+	/// Checks if the attribute with the given name is read.
+	pub fn allows(&self, name: &JavaStr) -> bool {
+		name == attribute::BOOTSTRAP_METHODS || self.names.contains(name)
+	}
+}
+
+/// Checks if the attribute should be skipped because of the filter.
+fn is_filtered_out(filter: Option<&AttributeFilter>, name: &JavaStr) -> bool {
+	filter.is_some_and(|filter| !filter.allows(name))
+}
+
+/// An unknown (or unsupported) opcode found while reading a class with [`read_class_lenient`][crate::read_class_lenient].
+///
+/// Since the length of an unknown instruction can't be known, only the first unknown opcode of each method is found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownOpcode {
+	/// The method containing the unknown opcode.
+	pub method: MethodRef,
+	/// The offset of the opcode in the bytecode of the method.
+	pub offset: u16,
+	/// The opcode itself.
+	pub opcode: u8,
+}
+
+/// The error returned by [`read_code`] if it encounters an unknown opcode.
+#[derive(Debug)]
+struct UnknownOpcodeError {
+	offset: u16,
+	opcode: u8,
+}
+
+impl Display for UnknownOpcodeError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let name = match self.opcode {
+			opcode::BREAKPOINT => " breakpoint",
+			opcode::IMPDEP1 => " impdep1",
+			opcode::IMPDEP2 => " impdep2",
+			_ => "",
+		};
+		write!(f, "unknown opcode {:#x}{name} at bytecode offset {}", self.opcode, self.offset)
+	}
+}
+
+impl std::error::Error for UnknownOpcodeError {}
+
+fn read_impl<V: MultiClassVisitor>(
+	reader: &mut impl ClassRead,
+	visitor: V,
+	mut unknown_opcodes: Option<&mut Vec<UnknownOpcode>>,
+	filter: Option<&AttributeFilter>,
+	max_depth: usize,
+	code_options: CodeReadOptions,
+) -> Result<V> {
+	let magic = reader.read_u32().while_reading("class header")?;
 	if magic != class_constants::MAGIC {
 		bail!("wrong magic: got {magic:#x}, expected 0xCAFEBABE");
 	}
 
-	let minor = reader.read_u16()?;
-	let major = reader.read_u16()?;
+	let minor = reader.read_u16().while_reading("class header")?;
+	let major = reader.read_u16().while_reading("class header")?;
 	let version = Version::new(major, minor);
 
-	if version > Version::V23 {
+	if version > Version::NEWEST_SUPPORTED {
 		bail!("unsupported class file version: {version:?}");
 	}
 
-	let pool_ = PoolRead::read(reader)?;
+	let pool_ = PoolRead::read(reader).while_reading("constant pool").context("failed to read constant pool")?;
 	let pool = &pool_;
 
-	let access_flags: ClassAccess = reader.read_u16()?.into();
-	let this_class = pool.get_class(reader.read_u16()?)?;
-	let super_class = pool.get_optional(reader.read_u16()?, PoolRead::get_class)?;
+	let access_flags: ClassAccess = reader.read_u16().while_reading("class header")?.into();
+	let this_class = pool.get_class(reader.read_u16().while_reading("class header")?)?;
+	let super_class = pool.get_optional(reader.read_u16().while_reading("class header")?, PoolRead::get_class)?;
 	let interfaces = reader.read_vec(
 		|r| r.read_u16_as_usize(),
 		|r| pool.get_class(r.read_u16()?)
-	)?;
+	).while_reading("class header")?;
 
 	// We take a reference to the start of the fields and methods items so that we can read them after we've visited the attributes of the class itself.
 	let fields_start = reader.marker()?;
 
 	// We skip the fields.
-	for _ in 0..reader.read_u16()? {
-		// Per field we skip 2 bytes for the access flags, another 2 for the name, and another 2 for the descriptor.
-		reader.skip(2 + 2 + 2)?;
+	(|| -> Result<()> {
+		for _ in 0..reader.read_u16()? {
+			// Per field we skip 2 bytes for the access flags, another 2 for the name, and another 2 for the descriptor.
+			reader.skip(2 + 2 + 2)?;
 
-		skip_attributes(reader)?;
-	}
+			skip_attributes(reader)?;
+		}
+		Ok(())
+	})().while_reading("field").context("failed to skip fields")?;
 	// Methods have the same structure as fields.
-	for _ in 0..reader.read_u16()? {
-		reader.skip(2 + 2 + 2)?;
+	(|| -> Result<()> {
+		for _ in 0..reader.read_u16()? {
+			reader.skip(2 + 2 + 2)?;
 
-		skip_attributes(reader)?;
-	}
+			skip_attributes(reader)?;
+		}
+		Ok(())
+	})().while_reading("method").context("failed to skip methods")?;
 
 	match visitor.visit_class(version, access_flags, this_class.clone(), super_class, interfaces)? {
 		ControlFlow::Continue((visitor, mut class_visitor)) => {
@@ -116,6 +244,7 @@ pub(crate) fn read<V: MultiClassVisitor>(reader: &mut impl ClassRead, visitor: V
 				let length = reader.read_u32()?;
 
 				match attribute_name.as_java_str() {
+					name if is_filtered_out(filter, name) => reader.skip(length as i64)?,
 					name if name == attribute::DEPRECATED => {
 						is_deprecated = true;
 					},
@@ -163,30 +292,30 @@ pub(crate) fn read<V: MultiClassVisitor>(reader: &mut impl ClassRead, visitor: V
 					name if name == attribute::RUNTIME_VISIBLE_ANNOTATIONS && !interests.runtime_visible_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_VISIBLE_ANNOTATIONS => {
 						let (visitor, annotations_visitor) = class_visitor.visit_annotations(true)?;
-						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool)?;
+						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool, max_depth)?;
 						class_visitor = ClassVisitor::finish_annotations(visitor, annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_INVISIBLE_ANNOTATIONS && !interests.runtime_invisible_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_INVISIBLE_ANNOTATIONS => {
 						let (visitor, annotations_visitor) = class_visitor.visit_annotations(false)?;
-						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool)?;
+						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool, max_depth)?;
 						class_visitor = ClassVisitor::finish_annotations(visitor, annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS && !interests.runtime_visible_type_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS => {
 						let (visitor, type_annotations_visitor) = class_visitor.visit_type_annotations(true)?;
-						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool)?;
+						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool, max_depth)?;
 						class_visitor = ClassVisitor::finish_type_annotations(visitor, type_annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS && !interests.runtime_invisible_type_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS => {
 						let (visitor, type_annotations_visitor) = class_visitor.visit_type_annotations(false)?;
-						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool)?;
+						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool, max_depth)?;
 						class_visitor = ClassVisitor::finish_type_annotations(visitor, type_annotations_visitor)?;
 					},
 					name if name == attribute::MODULE && !interests.module => reader.skip(length as i64)?,
 					name if name == attribute::MODULE => {
-						let module = read_module(reader, pool)?;
+						let module = read_module(reader, pool).while_reading("Module attribute")?;
 						class_visitor.visit_module(module)?;
 					},
 					name if name == attribute::MODULE_PACKAGES && !interests.module_packages => reader.skip(length as i64)?,
@@ -232,7 +361,7 @@ pub(crate) fn read<V: MultiClassVisitor>(reader: &mut impl ClassRead, visitor: V
 
 						let components_length = reader.read_u16()?;
 						for _ in 0..components_length {
-							class_visitor = read_record_component(reader, class_visitor, pool)?;
+							class_visitor = read_record_component(reader, class_visitor, pool, filter, max_depth).while_reading("record component")?;
 						}
 					},
 					name if name == attribute::BOOTSTRAP_METHODS => {
@@ -260,13 +389,15 @@ pub(crate) fn read<V: MultiClassVisitor>(reader: &mut impl ClassRead, visitor: V
 			reader.with_pos(fields_start, |reader| {
 				let fields_count = reader.read_u16()?;
 				for _ in 0..fields_count {
-					class_visitor = read_field(reader, class_visitor, pool)
+					class_visitor = read_field(reader, class_visitor, pool, filter, max_depth)
+						.while_reading("field")
 						.with_context(|| anyhow!("failed to read field of class {this_class:?}"))?;
 				}
 
 				let methods_count = reader.read_u16()?;
 				for _ in 0..methods_count {
-					class_visitor = read_method(reader, class_visitor, pool, &bootstrap_methods)
+					class_visitor = read_method(reader, class_visitor, pool, &bootstrap_methods, &this_class, unknown_opcodes.as_deref_mut(), filter, max_depth, code_options)
+						.while_reading("method")
 						.with_context(|| anyhow!("failed to read method of class {this_class:?}"))?;
 				}
 
@@ -280,7 +411,7 @@ pub(crate) fn read<V: MultiClassVisitor>(reader: &mut impl ClassRead, visitor: V
 	}
 }
 
-fn read_field<C: ClassVisitor>(reader: &mut impl ClassRead, visitor: C, pool: &PoolRead) -> Result<C> {
+fn read_field<C: ClassVisitor>(reader: &mut impl ClassRead, visitor: C, pool: &PoolRead, filter: Option<&AttributeFilter>, max_depth: usize) -> Result<C> {
 	let access = FieldAccess::from(reader.read_u16()?);
 	let name = FieldName::try_from(pool.get_utf8(reader.read_u16()?)?)?;
 	let descriptor = FieldDescriptor::try_from(pool.get_utf8(reader.read_u16()?)?)?;
@@ -297,6 +428,7 @@ fn read_field<C: ClassVisitor>(reader: &mut impl ClassRead, visitor: C, pool: &P
 				let length = reader.read_u32()?;
 
 				match attribute_name.as_java_str() {
+					name if is_filtered_out(filter, name) => reader.skip(length as i64)?,
 					name if name == attribute::DEPRECATED => {
 						is_deprecated = true;
 					},
@@ -316,25 +448,25 @@ fn read_field<C: ClassVisitor>(reader: &mut impl ClassRead, visitor: C, pool: &P
 					name if name == attribute::RUNTIME_VISIBLE_ANNOTATIONS && !interests.runtime_visible_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_VISIBLE_ANNOTATIONS => {
 						let (visitor, annotations_visitor) = field_visitor.visit_annotations(true)?;
-						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool)?;
+						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool, max_depth)?;
 						field_visitor = FieldVisitor::finish_annotations(visitor, annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_INVISIBLE_ANNOTATIONS && !interests.runtime_invisible_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_INVISIBLE_ANNOTATIONS => {
 						let (visitor, annotations_visitor) = field_visitor.visit_annotations(false)?;
-						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool)?;
+						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool, max_depth)?;
 						field_visitor = FieldVisitor::finish_annotations(visitor, annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS && !interests.runtime_visible_type_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS => {
 						let (visitor, type_annotations_visitor) = field_visitor.visit_type_annotations(true)?;
-						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool)?;
+						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool, max_depth)?;
 						field_visitor = FieldVisitor::finish_type_annotations(visitor, type_annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS && !interests.runtime_invisible_type_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS => {
 						let (visitor, type_annotations_visitor) = field_visitor.visit_type_annotations(false)?;
-						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool)?;
+						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool, max_depth)?;
 						field_visitor = FieldVisitor::finish_type_annotations(visitor, type_annotations_visitor)?;
 					},
 					_ if !interests.unknown_attributes => reader.skip(length as i64)?,
@@ -357,7 +489,18 @@ fn read_field<C: ClassVisitor>(reader: &mut impl ClassRead, visitor: C, pool: &P
 	}
 }
 
-fn read_method<C: ClassVisitor>(reader: &mut impl ClassRead, visitor: C, pool: &PoolRead, bootstrap_methods: &Option<Vec<BootstrapMethodRead>>) -> Result<C> {
+#[allow(clippy::too_many_arguments)]
+fn read_method<C: ClassVisitor>(
+	reader: &mut impl ClassRead,
+	visitor: C,
+	pool: &PoolRead,
+	bootstrap_methods: &Option<Vec<BootstrapMethodRead>>,
+	class: &ClassName,
+	mut unknown_opcodes: Option<&mut Vec<UnknownOpcode>>,
+	filter: Option<&AttributeFilter>,
+	max_depth: usize,
+	code_options: CodeReadOptions,
+) -> Result<C> {
 	let access = MethodAccess::from(reader.read_u16()?);
 	let name = MethodName::try_from(pool.get_utf8(reader.read_u16()?)?)?;
 	let descriptor = MethodDescriptor::try_from(pool.get_utf8(reader.read_u16()?)?)?;
@@ -366,6 +509,9 @@ fn read_method<C: ClassVisitor>(reader: &mut impl ClassRead, visitor: C, pool: &
 		ControlFlow::Continue((visitor, mut method_visitor)) => {
 			let interests = method_visitor.interests();
 
+			// The attribute name shadows the `name` of the method below.
+			let method_name = &name;
+
 			let (mut is_deprecated, mut is_synthetic) = (false, false);
 
 			let attributes_count = reader.read_u16()?;
@@ -374,6 +520,7 @@ fn read_method<C: ClassVisitor>(reader: &mut impl ClassRead, visitor: C, pool: &
 				let length = reader.read_u32()?;
 
 				match attribute_name.as_java_str() {
+					name if is_filtered_out(filter, name) => reader.skip(length as i64)?,
 					name if name == attribute::DEPRECATED => {
 						is_deprecated = true;
 					},
@@ -383,9 +530,32 @@ fn read_method<C: ClassVisitor>(reader: &mut impl ClassRead, visitor: C, pool: &
 					name if name == attribute::CODE && !interests.code => reader.skip(length as i64)?,
 					name if name == attribute::CODE => {
 						if let Some(code_visitor) = method_visitor.visit_code()? {
-							let code_visitor = read_code(reader, code_visitor, pool, bootstrap_methods)
-								.with_context(|| anyhow!("failed to read code of method {name:?} {descriptor:?}"))?;
-							method_visitor.finish_code(code_visitor)?;
+							let code_start = reader.marker()?;
+							match read_code(reader, code_visitor, pool, bootstrap_methods, filter, max_depth, code_options).while_reading("Code attribute") {
+								Ok(code_visitor) => method_visitor.finish_code(code_visitor)?,
+								Err(e) => {
+									let Some((unknown_opcodes, error)) = unknown_opcodes.as_mut()
+										.and_then(|unknown_opcodes| Some((unknown_opcodes, e.downcast_ref::<UnknownOpcodeError>()?))) else {
+										return Err(e.context(anyhow!("failed to read code of method {method_name:?} {descriptor:?}")));
+									};
+
+									unknown_opcodes.push(UnknownOpcode {
+										method: MethodRef { class: class.clone(), name: method_name.clone(), desc: descriptor.clone() },
+										offset: error.offset,
+										opcode: error.opcode,
+									});
+
+									// Keep the code as it is, by giving it to the visitor as an unknown attribute.
+									reader.goto(code_start)?;
+									if interests.unknown_attributes {
+										let vec = reader.read_u8_vec(length as usize)?;
+										let attribute = UnknownAttributeVisitor::read(attribute_name.clone(), vec, pool)?;
+										method_visitor.visit_unknown_attribute(attribute)?;
+									} else {
+										reader.skip(length as i64)?;
+									}
+								},
+							}
 						}
 					},
 					name if name == attribute::EXCEPTIONS && !interests.exceptions => reader.skip(length as i64)?,
@@ -404,25 +574,25 @@ fn read_method<C: ClassVisitor>(reader: &mut impl ClassRead, visitor: C, pool: &
 					name if name == attribute::RUNTIME_VISIBLE_ANNOTATIONS && !interests.runtime_visible_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_VISIBLE_ANNOTATIONS => {
 						let (visitor, annotations_visitor) = method_visitor.visit_annotations(true)?;
-						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool)?;
+						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool, max_depth)?;
 						method_visitor = MethodVisitor::finish_annotations(visitor, annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_INVISIBLE_ANNOTATIONS && !interests.runtime_invisible_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_INVISIBLE_ANNOTATIONS => {
 						let (visitor, annotations_visitor) = method_visitor.visit_annotations(false)?;
-						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool)?;
+						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool, max_depth)?;
 						method_visitor = MethodVisitor::finish_annotations(visitor, annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS && !interests.runtime_visible_type_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS => {
 						let (visitor, type_annotations_visitor) = method_visitor.visit_type_annotations(true)?;
-						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool)?;
+						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool, max_depth)?;
 						method_visitor = MethodVisitor::finish_type_annotations(visitor, type_annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS && !interests.runtime_invisible_type_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS => {
 						let (visitor, type_annotations_visitor) = method_visitor.visit_type_annotations(false)?;
-						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool)?;
+						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool, max_depth)?;
 						method_visitor = MethodVisitor::finish_type_annotations(visitor, type_annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_VISIBLE_PARAMETER_ANNOTATIONS && !interests.runtime_visible_parameter_annotations => reader.skip(length as i64)?,
@@ -438,7 +608,7 @@ fn read_method<C: ClassVisitor>(reader: &mut impl ClassRead, visitor: C, pool: &
 					name if name == attribute::ANNOTATION_DEFAULT && !interests.annotation_default => reader.skip(length as i64)?,
 					name if name == attribute::ANNOTATION_DEFAULT => {
 						let (visitor, x) = method_visitor.visit_annotation_default()?;
-						let x = read_element_value_unnamed(reader, pool, x)?;
+						let x = read_element_value_unnamed(reader, pool, x, max_depth)?;
 						method_visitor = MethodVisitor::finish_annotation_default(visitor, x)?;
 					},
 					name if name == attribute::METHOD_PARAMETERS && !interests.method_parameters => reader.skip(length as i64)?,
@@ -516,9 +686,19 @@ fn read_code<C: CodeVisitor>(
 	reader: &mut impl ClassRead,
 	mut code_visitor: C,
 	pool: &PoolRead,
-	bootstrap_methods: &Option<Vec<BootstrapMethodRead>>
+	bootstrap_methods: &Option<Vec<BootstrapMethodRead>>,
+	filter: Option<&AttributeFilter>,
+	max_depth: usize,
+	code_options: CodeReadOptions,
 ) -> Result<C> {
-	let interests = code_visitor.interests();
+	let mut interests = code_visitor.interests();
+	interests.instruction_offsets |= code_options.instruction_offsets;
+
+	if code_options.raw_code || interests.raw_code_only {
+		let raw_code = read_raw_code_attribute(reader, pool, bootstrap_methods, filter, &interests)?;
+		code_visitor.visit_raw_code(raw_code)?;
+		return Ok(code_visitor);
+	}
 
 	let max_stack = reader.read_u16()?;
 	let max_locals = reader.read_u16()?;
@@ -639,10 +819,7 @@ fn read_code<C: CodeVisitor>(
 							labels.create(r.read_i32_as_branch_target_label(opcode_pos)?)?;
 						}
 					},
-					opcode::BREAKPOINT => bail!("unknown opcode breakpoint"),
-					opcode::IMPDEP1 => bail!("unknown opcode impdep1"),
-					opcode::IMPDEP2 => bail!("unknown opcode impdep2"),
-					opcode => bail!("unknown opcode {opcode:x?}"),
+					opcode => return Err(UnknownOpcodeError { offset: opcode_pos, opcode }.into()),
 				};
 				Ok(())
 			})()
@@ -672,10 +849,15 @@ fn read_code<C: CodeVisitor>(
 		let length = reader.read_u32()?;
 
 		match attribute_name.as_java_str() {
+			name if is_filtered_out(filter, name) => reader.skip(length as i64)?,
 			name if name == attribute::STACK_MAP_TABLE && !interests.stack_map_table => reader.skip(length as i64)?,
 			name if name == attribute::STACK_MAP_TABLE => {
+				// Only read within the attribute, so that a wrong `number_of_entries` doesn't read the following attributes.
+				let data = reader.read_u8_vec(length as usize)?;
+				let mut r = Cursor::new(data.as_slice());
+
 				let mut offset = 0;
-				let number_of_entries = reader.read_u16_as_usize()?;
+				let number_of_entries = r.read_u16_as_usize()?;
 				let mut frames = std::collections::VecDeque::with_capacity(number_of_entries);
 				for i in 0..number_of_entries {
 					fn read_stack_map_frame(reader: &mut impl ClassRead, pool: &PoolRead, labels: &mut Labels) -> Result<(u16, StackMapData)> {
@@ -717,7 +899,8 @@ fn read_code<C: CodeVisitor>(
 						})
 					}
 
-					let (offset_delta, frame_data) = read_stack_map_frame(reader, pool, &mut labels)?;
+					let (offset_delta, frame_data) = read_stack_map_frame(&mut r, pool, &mut labels)
+						.map_err(|e| stack_map_entries_error(e, name, number_of_entries, length))?;
 
 					offset += offset_delta + (if i == 0 { 0 } else { 1 });
 
@@ -725,6 +908,7 @@ fn read_code<C: CodeVisitor>(
 
 					frames.push_back((label, frame_data));
 				}
+				check_stack_map_end(&r, name, number_of_entries)?;
 				stack_map_frame.insert_if_empty(frames).context("only one StackMapTable attribute is allowed")?;
 			},
 			name if name == attribute::STACK_MAP && !interests.stack_map_table => reader.skip(length as i64)?, // Skip it as well, it's just "another format" of StackMapFrame
@@ -732,27 +916,34 @@ fn read_code<C: CodeVisitor>(
 				// See https://docs.oracle.com/javame/8.0/api/cldc/api/Appendix1-verifier.pdf for a definition of it.
 				// Our bytecode length, our maximum number of local variables and our maximum size of the operand stack fit into an `u16`,
 				// this means that `uoffset`, `ulocalvar` and `ustack` are all `u2` (for us `u16`).
-				let number_of_entries = reader.read_u16_as_usize()?;
+				let data = reader.read_u8_vec(length as usize)?;
+				let mut r = Cursor::new(data.as_slice());
+
+				let number_of_entries = r.read_u16_as_usize()?;
 				let mut frames = Vec::with_capacity(number_of_entries);
 				for _ in 0..number_of_entries {
-					let offset = reader.read_u16()?;
-					// Turns out to be the same as a StackMapTable frame of type 255.
-					let locals = reader.read_vec(
-						|r| r.read_u16_as_usize(),
-						|r| read_verification_type_info(r, pool, &mut labels),
-					)?;
+					let (offset, frame_data) = (|| -> Result<_> {
+						let offset = r.read_u16()?;
+						// Turns out to be the same as a StackMapTable frame of type 255.
+						let locals = r.read_vec(
+							|r| r.read_u16_as_usize(),
+							|r| read_verification_type_info(r, pool, &mut labels),
+						)?;
 
-					let stack = reader.read_vec(
-						|r| r.read_u16_as_usize(),
-						|r| read_verification_type_info(r, pool, &mut labels),
-					)?;
+						let stack = r.read_vec(
+							|r| r.read_u16_as_usize(),
+							|r| read_verification_type_info(r, pool, &mut labels),
+						)?;
 
-					let frame_data = StackMapData::Full { locals, stack };
+						Ok((offset, StackMapData::Full { locals, stack }))
+					})()
+						.map_err(|e| stack_map_entries_error(e, name, number_of_entries, length))?;
 
 					let label = labels.get_or_create(offset)?;
 
 					frames.push((label, frame_data));
 				}
+				check_stack_map_end(&r, name, number_of_entries)?;
 
 				// The format of the StackMap attribute doesn't guarantee ordered elements.
 				frames.sort_by_key(|&(label, _)| label);
@@ -819,13 +1010,13 @@ fn read_code<C: CodeVisitor>(
 			name if name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS && !interests.runtime_visible_type_annotations => reader.skip(length as i64)?,
 			name if name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS => {
 				let (visitor, type_annotations_visitor) = code_visitor.visit_type_annotations(true)?;
-				let type_annotations_visitor = read_type_annotations_attribute_code(reader, type_annotations_visitor, pool, &mut labels)?;
+				let type_annotations_visitor = read_type_annotations_attribute_code(reader, type_annotations_visitor, pool, &mut labels, max_depth)?;
 				code_visitor = CodeVisitor::finish_type_annotations(visitor, type_annotations_visitor)?;
 			},
 			name if name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS && !interests.runtime_invisible_type_annotations => reader.skip(length as i64)?,
 			name if name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS => {
 				let (visitor, type_annotations_visitor) = code_visitor.visit_type_annotations(false)?;
-				let type_annotations_visitor = read_type_annotations_attribute_code(reader, type_annotations_visitor, pool, &mut labels)?;
+				let type_annotations_visitor = read_type_annotations_attribute_code(reader, type_annotations_visitor, pool, &mut labels, max_depth)?;
 				code_visitor = CodeVisitor::finish_type_annotations(visitor, type_annotations_visitor)?;
 			},
 			_ if !interests.unknown_attributes => reader.skip(length as i64)?,
@@ -1026,7 +1217,7 @@ fn read_code<C: CodeVisitor>(
 
 				let n = (high - low + 1) as u32; // always >= 1
 
-				let mut table = Vec::with_capacity(n as usize);
+				let mut table = Vec::with_capacity((n as usize).min(crate::MAX_PREALLOCATION));
 				for _ in 0..n {
 					let entry = labels.try_get(r.read_i32_as_branch_target_label(opcode_pos)?)?;
 					table.push(entry);
@@ -1043,7 +1234,7 @@ fn read_code<C: CodeVisitor>(
 				if n < 0 { bail!("in lookupswitch the `npairs` must be positive, it's npairs={n:?}"); }
 				let n = n as u32;
 
-				let mut pairs = Vec::with_capacity(n as usize);
+				let mut pairs = Vec::with_capacity((n as usize).min(crate::MAX_PREALLOCATION));
 				for _ in 0..n {
 					let key = r.read_i32()?;
 					let value = labels.try_get(r.read_i32_as_branch_target_label(opcode_pos)?)?;
@@ -1142,7 +1333,11 @@ fn read_code<C: CodeVisitor>(
 			}
 		}
 
-		code_visitor.visit_instruction(label, frame, instruction)?;
+		if interests.instruction_offsets {
+			code_visitor.visit_instruction_with_offset(opcode_pos, label, frame, instruction)?;
+		} else {
+			code_visitor.visit_instruction(label, frame, instruction)?;
+		}
 	}
 	if let Some(last_label) = labels.get(bytecode.len() as u16) {
 		code_visitor.visit_last_label(last_label)?;
@@ -1153,10 +1348,286 @@ fn read_code<C: CodeVisitor>(
 	if let Some(table) = line_number_table {
 		code_visitor.visit_line_numbers(table)?;
 	}
+	if let Some(table) = local_variable_table {
+		code_visitor.visit_local_variables(table)?;
+	}
 
 	Ok(code_visitor)
 }
 
+/// Reads the `Code` attribute as [`RawCode`], without creating any labels.
+///
+/// The bytecode is walked once, to resolve the constant pool references of the instructions.
+fn read_raw_code_attribute(
+	reader: &mut impl ClassRead,
+	pool: &PoolRead,
+	bootstrap_methods: &Option<Vec<BootstrapMethodRead>>,
+	filter: Option<&AttributeFilter>,
+	interests: &CodeInterests,
+) -> Result<RawCode> {
+	let max_stack = reader.read_u16()?;
+	let max_locals = reader.read_u16()?;
+
+	let code_length = reader.read_u32()?;
+	if code_length == 0 || code_length > u16::MAX as u32 {
+		bail!("`code_length` must be greater than zero and less than 65536, got {code_length:?}");
+	}
+
+	let bytecode = reader.read_u8_vec(code_length as usize)?;
+
+	let mut constants = Vec::new();
+	{
+		// We do this so that we can't read more than the bytecode
+		let mut r = Cursor::new(&bytecode);
+		// Skipping may move past the end, so we can't slice the rest of the bytecode here.
+		while (r.position() as usize) < bytecode.len() {
+			// See `read_code` for why we may do this.
+			let opcode_pos = r.position() as u16;
+			let index_pos = opcode_pos + 1;
+
+			(|| {
+				match r.read_u8()? {
+					opcode::NOP..=opcode::DCONST_1 |
+					opcode::ILOAD_0..=opcode::SALOAD |
+					opcode::ISTORE_0..=opcode::LXOR |
+					opcode::I2L..=opcode::DCMPG |
+					opcode::IRETURN..=opcode::RETURN |
+					opcode::ARRAYLENGHT |
+					opcode::ATHROW |
+					opcode::MONITORENTER |
+					opcode::MONITOREXIT => {},
+					opcode::BIPUSH |
+					opcode::ILOAD..=opcode::ALOAD |
+					opcode::ISTORE..=opcode::ASTORE |
+					opcode::RET |
+					opcode::NEWARRAY => r.skip(1)?,
+					opcode::SIPUSH |
+					opcode::IINC |
+					opcode::IFEQ..=opcode::JSR |
+					opcode::IFNULL |
+					opcode::IFNONNULL => r.skip(2)?,
+					opcode::GOTO_W |
+					opcode::JSR_W => r.skip(4)?,
+					opcode::LDC => {
+						let loadable = pool.get_loadable(r.read_u8()? as u16, bootstrap_methods)?;
+						constants.push((index_pos, RawConstant::Loadable(loadable)));
+					},
+					opcode::LDC_W |
+					opcode::LDC2_W => {
+						let loadable = pool.get_loadable(r.read_u16()?, bootstrap_methods)?;
+						constants.push((index_pos, RawConstant::Loadable(loadable)));
+					},
+					opcode::GETSTATIC..=opcode::PUTFIELD => {
+						constants.push((index_pos, RawConstant::Field(pool.get_field_ref(r.read_u16()?)?)));
+					},
+					opcode::INVOKEVIRTUAL => {
+						constants.push((index_pos, RawConstant::Method(pool.get_method_ref(r.read_u16()?)?, false)));
+					},
+					opcode::INVOKESPECIAL |
+					opcode::INVOKESTATIC => {
+						let (method_ref, is_interface) = pool.get_method_ref_or_interface_method_ref(r.read_u16()?)?;
+						constants.push((index_pos, RawConstant::Method(method_ref, is_interface)));
+					},
+					opcode::INVOKEINTERFACE => {
+						constants.push((index_pos, RawConstant::Method(pool.get_interface_method_ref(r.read_u16()?)?, true)));
+						r.skip(2)?;
+					},
+					opcode::INVOKEDYNAMIC => {
+						let invoke_dynamic = pool.get_invoke_dynamic(r.read_u16()?, bootstrap_methods)?;
+						constants.push((index_pos, RawConstant::InvokeDynamic(invoke_dynamic)));
+						r.skip(2)?;
+					},
+					opcode::NEW |
+					opcode::ANEWARRAY |
+					opcode::CHECKCAST |
+					opcode::INSTANCEOF => {
+						constants.push((index_pos, RawConstant::Class(pool.get_class(r.read_u16()?)?)));
+					},
+					opcode::MULTIANEWARRAY => {
+						constants.push((index_pos, RawConstant::Class(pool.get_class(r.read_u16()?)?)));
+						r.skip(1)?;
+					},
+					opcode::WIDE => {
+						match r.read_u8()? {
+							opcode::ILOAD..=opcode::ALOAD |
+							opcode::ISTORE..=opcode::ASTORE |
+							opcode::RET => r.skip(2)?,
+							opcode::IINC => r.skip(4)?,
+							wide_opcode => bail!("unknown wide opcode {wide_opcode:x?}"),
+						}
+					},
+					opcode::TABLESWITCH => {
+						align_to_4_byte_boundary(&mut r)?;
+						let _default = r.read_i32()?;
+						let low = r.read_i32()?;
+						let high = r.read_i32()?;
+						if low > high { bail!("in tableswitch `low` must be lower or equal to `high`, it's low={low:?} and high={high:?}"); }
+						r.skip((high as i64 - low as i64 + 1) * 4)?;
+					},
+					opcode::LOOKUPSWITCH => {
+						align_to_4_byte_boundary(&mut r)?;
+						let _default = r.read_i32()?;
+						let n = r.read_i32()?;
+						if n < 0 { bail!("in lookupswitch the `npairs` must be positive, it's npairs={n:?}"); }
+						r.skip(n as i64 * 8)?;
+					},
+					opcode => return Err(UnknownOpcodeError { offset: opcode_pos, opcode }.into()),
+				}
+				Ok(())
+			})()
+				.with_context(|| anyhow!("at bytecode offset {}", opcode_pos))?;
+		}
+
+		if r.position() as usize > bytecode.len() {
+			bail!("the last instruction exceeds the end of the code");
+		}
+	}
+
+	let exception_table = reader.read_vec(
+		|r| r.read_u16_as_usize(),
+		|r| Ok(RawException {
+			start: r.read_u16()?,
+			end: r.read_u16()?,
+			handler: r.read_u16()?,
+			catch: pool.get_optional(r.read_u16()?, PoolRead::get_class)?,
+		})
+	)?;
+
+	let mut raw_code = RawCode {
+		max_stack,
+		max_locals,
+		bytecode,
+		constants,
+		exception_table,
+		stack_map_table: None,
+		line_numbers: None,
+		local_variables: None,
+	};
+
+	let attribute_count = reader.read_u16()?;
+	for _ in 0..attribute_count {
+		let attribute_name = pool.get_utf8_ref(reader.read_u16()?)?;
+		let length = reader.read_u32()?;
+
+		match attribute_name.as_java_str() {
+			name if is_filtered_out(filter, name) => reader.skip(length as i64)?,
+			name if name == attribute::STACK_MAP_TABLE && interests.stack_map_table => {
+				let data = reader.read_u8_vec(length as usize)?;
+				let classes = read_raw_stack_map_table_classes(&data, pool)?;
+				raw_code.stack_map_table.insert_if_empty(RawStackMapTable { data, classes })
+					.context("only one StackMapTable attribute is allowed")?;
+			},
+			name if name == attribute::STACK_MAP && interests.stack_map_table => {
+				bail!("the old `StackMap` attribute can't be read as raw code");
+			},
+			name if name == attribute::LINE_NUMBER_TABLE && interests.line_number_table => {
+				let table = raw_code.line_numbers.get_or_insert_with(Vec::new);
+				let line_number_table_length = reader.read_u16()?;
+				for _ in 0..line_number_table_length {
+					table.push((reader.read_u16()?, reader.read_u16()?));
+				}
+			},
+			name if name == attribute::LOCAL_VARIABLE_TABLE && interests.local_variable_table ||
+				name == attribute::LOCAL_VARIABLE_TYPE_TABLE && interests.local_variable_type_table => {
+				let is_type_table = name == attribute::LOCAL_VARIABLE_TYPE_TABLE;
+				let table = raw_code.local_variables.get_or_insert_with(Vec::new);
+				let table_length = reader.read_u16()?;
+				for _ in 0..table_length {
+					let start = reader.read_u16()?;
+					let length = reader.read_u16()?;
+					let name = LocalVariableName::try_from(pool.get_utf8(reader.read_u16()?)?)?;
+					let descriptor_or_signature = pool.get_utf8(reader.read_u16()?)?;
+					let index = reader.read_u16_as_local_variable()?;
+					let (descriptor, signature) = if is_type_table {
+						(None, Some(FieldSignature::try_from(descriptor_or_signature)?))
+					} else {
+						(Some(FieldDescriptor::try_from(descriptor_or_signature)?), None)
+					};
+					table.push(RawLv { start, length, name, descriptor, signature, index });
+				}
+			},
+			name if name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS && interests.runtime_visible_type_annotations ||
+				name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS && interests.runtime_invisible_type_annotations => {
+				bail!("type annotations in the code can't be read as raw code, got a {name:?} attribute");
+			},
+			name if name == attribute::STACK_MAP_TABLE || name == attribute::STACK_MAP || name == attribute::LINE_NUMBER_TABLE ||
+				name == attribute::LOCAL_VARIABLE_TABLE || name == attribute::LOCAL_VARIABLE_TYPE_TABLE ||
+				name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS || name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS ||
+				!interests.unknown_attributes => reader.skip(length as i64)?,
+			name => bail!("unknown attribute {name:?} of the `Code` attribute can't be read as raw code"),
+		}
+	}
+
+	Ok(raw_code)
+}
+
+/// Finds the constant pool indices of the `Object_variable_info`s in the data of a `StackMapTable` attribute.
+fn read_raw_stack_map_table_classes(data: &[u8], pool: &PoolRead) -> Result<Vec<(usize, ClassName)>> {
+	fn read_verification_type_infos(r: &mut Cursor<&[u8]>, pool: &PoolRead, count: usize, classes: &mut Vec<(usize, ClassName)>) -> Result<()> {
+		for _ in 0..count {
+			match r.read_u8()? {
+				0..=6 => {},
+				7 => {
+					let pos = r.position() as usize;
+					classes.push((pos, pool.get_class(r.read_u16()?)?));
+				},
+				8 => r.skip(2)?,
+				tag => bail!("unknown verification_type_info tag {tag}"),
+			}
+		}
+		Ok(())
+	}
+
+	let mut classes = Vec::new();
+	let mut r = Cursor::new(data);
+	let number_of_entries = r.read_u16()?;
+	for _ in 0..number_of_entries {
+		match r.read_u8()? {
+			0..=63 => {},
+			64..=127 => read_verification_type_infos(&mut r, pool, 1, &mut classes)?,
+			frame_type @ 128..=246 => bail!("unknown stack map frame type {frame_type}"),
+			247 => {
+				r.skip(2)?;
+				read_verification_type_infos(&mut r, pool, 1, &mut classes)?;
+			},
+			248..=251 => r.skip(2)?,
+			frame_type @ 252..=254 => {
+				r.skip(2)?;
+				read_verification_type_infos(&mut r, pool, frame_type as usize - 251, &mut classes)?;
+			},
+			255 => {
+				r.skip(2)?;
+				let locals = r.read_u16_as_usize()?;
+				read_verification_type_infos(&mut r, pool, locals, &mut classes)?;
+				let stack = r.read_u16_as_usize()?;
+				read_verification_type_infos(&mut r, pool, stack, &mut classes)?;
+			},
+		}
+	}
+	Ok(classes)
+}
+
+/// Explains running out of the bytes of a `StackMapTable` or `StackMap` attribute with the `number_of_entries` being too big.
+///
+/// The class file itself isn't truncated then, so this replaces the [`ClassReadError::Truncated`] with an error about the
+/// invalid attribute.
+fn stack_map_entries_error(error: anyhow::Error, attribute_name: &JavaStr, number_of_entries: usize, length: u32) -> anyhow::Error {
+	if error.downcast_ref::<ClassReadError>().is_some() {
+		anyhow!("{attribute_name} claims {number_of_entries} entries but only {length} bytes available")
+	} else {
+		error
+	}
+}
+
+/// Checks that all the entries of a `StackMapTable` or `StackMap` attribute were read, with no bytes left over.
+fn check_stack_map_end(r: &Cursor<&[u8]>, attribute_name: &JavaStr, number_of_entries: usize) -> Result<()> {
+	let left_over = r.get_ref().len() - r.position() as usize;
+	if left_over != 0 {
+		bail!("{attribute_name} has {left_over} bytes left over after reading its {number_of_entries} entries");
+	}
+	Ok(())
+}
+
 fn read_verification_type_info(reader: &mut impl ClassRead, pool: &PoolRead, labels: &mut Labels) -> Result<VerificationTypeInfo> {
 	Ok(match reader.read_u8()? {
 		0 => VerificationTypeInfo::Top,
@@ -1178,7 +1649,7 @@ fn read_verification_type_info(reader: &mut impl ClassRead, pool: &PoolRead, lab
 	})
 }
 
-fn read_record_component<C: ClassVisitor>(reader: &mut impl ClassRead, class_visitor: C, pool: &PoolRead) -> Result<C> {
+fn read_record_component<C: ClassVisitor>(reader: &mut impl ClassRead, class_visitor: C, pool: &PoolRead, filter: Option<&AttributeFilter>, max_depth: usize) -> Result<C> {
 	let name = RecordName::try_from(pool.get_utf8(reader.read_u16()?)?)?;
 	let descriptor = FieldDescriptor::try_from(pool.get_utf8(reader.read_u16()?)?)?;
 
@@ -1192,6 +1663,7 @@ fn read_record_component<C: ClassVisitor>(reader: &mut impl ClassRead, class_vis
 				let length = reader.read_u32()?;
 
 				match attribute_name.as_java_str() {
+					name if is_filtered_out(filter, name) => reader.skip(length as i64)?,
 					name if name == attribute::SIGNATURE && !interests.signature => reader.skip(length as i64)?,
 					name if name == attribute::SIGNATURE => {
 						let signature = FieldSignature::try_from(pool.get_utf8(reader.read_u16()?)?)?;
@@ -1200,25 +1672,25 @@ fn read_record_component<C: ClassVisitor>(reader: &mut impl ClassRead, class_vis
 					name if name == attribute::RUNTIME_VISIBLE_ANNOTATIONS && !interests.runtime_visible_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_VISIBLE_ANNOTATIONS => {
 						let (visitor, annotations_visitor) = record_component_visitor.visit_annotations(true)?;
-						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool)?;
+						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool, max_depth)?;
 						record_component_visitor = RecordComponentVisitor::finish_annotations(visitor, annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_INVISIBLE_ANNOTATIONS && !interests.runtime_invisible_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_INVISIBLE_ANNOTATIONS => {
 						let (visitor, annotations_visitor) = record_component_visitor.visit_annotations(false)?;
-						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool)?;
+						let annotations_visitor = read_annotations_attribute(reader, annotations_visitor, pool, max_depth)?;
 						record_component_visitor = RecordComponentVisitor::finish_annotations(visitor, annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS && !interests.runtime_visible_type_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS => {
 						let (visitor, type_annotations_visitor) = record_component_visitor.visit_type_annotations(true)?;
-						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool)?;
+						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool, max_depth)?;
 						record_component_visitor = RecordComponentVisitor::finish_type_annotations(visitor, type_annotations_visitor)?;
 					},
 					name if name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS && !interests.runtime_invisible_type_annotations => reader.skip(length as i64)?,
 					name if name == attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS => {
 						let (visitor, type_annotations_visitor) = record_component_visitor.visit_type_annotations(false)?;
-						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool)?;
+						let type_annotations_visitor = read_type_annotations_attribute(reader, type_annotations_visitor, pool, max_depth)?;
 						record_component_visitor = RecordComponentVisitor::finish_type_annotations(visitor, type_annotations_visitor)?;
 					},
 					_ if !interests.unknown_attributes => reader.skip(length as i64)?,
@@ -1239,14 +1711,14 @@ fn read_record_component<C: ClassVisitor>(reader: &mut impl ClassRead, class_vis
 	}
 }
 
-fn read_annotations_attribute<A: AnnotationsVisitor>(reader: &mut impl ClassRead, mut annotations_visitor: A, pool: &PoolRead) -> Result<A> {
+fn read_annotations_attribute<A: AnnotationsVisitor>(reader: &mut impl ClassRead, mut annotations_visitor: A, pool: &PoolRead, max_depth: usize) -> Result<A> {
 	let num_annotations = reader.read_u16()?;
 	for _ in 0..num_annotations {
 		let annotation_descriptor = FieldDescriptor::try_from(pool.get_utf8(reader.read_u16()?)?)?;
 
 		let (visitor, named_element_values_visitor) = annotations_visitor.visit_annotation(annotation_descriptor)?;
 
-		let named_element_values_visitor = read_element_values_named(reader, pool, named_element_values_visitor)?;
+		let named_element_values_visitor = read_element_values_named(reader, pool, named_element_values_visitor, max_depth)?;
 
 		annotations_visitor = AnnotationsVisitor::finish_annotation(visitor, named_element_values_visitor)?;
 	}
@@ -1254,7 +1726,15 @@ fn read_annotations_attribute<A: AnnotationsVisitor>(reader: &mut impl ClassRead
 	Ok(annotations_visitor)
 }
 
-fn read_element_values_named<A: NamedElementValuesVisitor>(reader: &mut impl ClassRead, pool: &PoolRead, mut outer: A) -> Result<A> {
+/// Decreases the remaining depth for reading an annotation or array nested in an element value.
+///
+/// This limits the recursion of reading element values, so that crafted classes can't overflow the stack.
+fn nested_depth(max_depth: usize) -> Result<usize> {
+	max_depth.checked_sub(1)
+		.context("element values are nested too deeply: reached the maximum depth for annotations and arrays")
+}
+
+fn read_element_values_named<A: NamedElementValuesVisitor>(reader: &mut impl ClassRead, pool: &PoolRead, mut outer: A, max_depth: usize) -> Result<A> {
 	for _ in 0..reader.read_u16()? {
 		let name = pool.get_utf8(reader.read_u16()?)?;
 		match reader.read_u8()? {
@@ -1315,12 +1795,12 @@ fn read_element_values_named<A: NamedElementValuesVisitor>(reader: &mut impl Cla
 			b'@' => {
 				let annotation_descriptor = FieldDescriptor::try_from(pool.get_utf8(reader.read_u16()?)?)?;
 				let (visitor, inner) = outer.visit_annotation(name, annotation_descriptor)?;
-				let inner = read_element_values_named(reader, pool, inner)?;
+				let inner = read_element_values_named(reader, pool, inner, nested_depth(max_depth)?)?;
 				outer = A::finish_annotation(visitor, inner)?;
 			},
 			b'[' => {
 				let (visitor, inner) = outer.visit_array(name)?;
-				let inner = read_element_values_unnamed(reader, pool, inner)?;
+				let inner = read_element_values_unnamed(reader, pool, inner, nested_depth(max_depth)?)?;
 				outer = A::finish_array(visitor, inner)?;
 			},
 			tag => bail!("unknown element_value tag {tag:?}"),
@@ -1330,15 +1810,15 @@ fn read_element_values_named<A: NamedElementValuesVisitor>(reader: &mut impl Cla
 	Ok(outer)
 }
 
-fn read_element_values_unnamed<A: UnnamedElementValuesVisitor>(reader: &mut impl ClassRead, pool: &PoolRead, mut outer: A) -> Result<A> {
+fn read_element_values_unnamed<A: UnnamedElementValuesVisitor>(reader: &mut impl ClassRead, pool: &PoolRead, mut outer: A, max_depth: usize) -> Result<A> {
 	for _ in 0..reader.read_u16()? {
-		outer = read_element_value_unnamed(reader, pool, outer)?;
+		outer = read_element_value_unnamed(reader, pool, outer, max_depth)?;
 	}
 
 	Ok(outer)
 }
 
-fn read_element_value_unnamed<A: UnnamedElementValueVisitor>(reader: &mut impl ClassRead, pool: &PoolRead, mut outer: A) -> Result<A> {
+fn read_element_value_unnamed<A: UnnamedElementValueVisitor>(reader: &mut impl ClassRead, pool: &PoolRead, mut outer: A, max_depth: usize) -> Result<A> {
 	match reader.read_u8()? {
 		b'B' => {
 			let const_value_index = reader.read_u16()?;
@@ -1397,12 +1877,12 @@ fn read_element_value_unnamed<A: UnnamedElementValueVisitor>(reader: &mut impl C
 		b'@' => {
 			let annotation_descriptor = FieldDescriptor::try_from(pool.get_utf8(reader.read_u16()?)?)?;
 			let (visitor, inner) = outer.visit_annotation(annotation_descriptor)?;
-			let inner = read_element_values_named(reader, pool, inner)?;
+			let inner = read_element_values_named(reader, pool, inner, nested_depth(max_depth)?)?;
 			outer = A::finish_annotation(visitor, inner)?;
 		},
 		b'[' => {
 			let (visitor, inner) = outer.visit_array()?;
-			let inner = read_element_values_unnamed(reader, pool, inner)?;
+			let inner = read_element_values_unnamed(reader, pool, inner, nested_depth(max_depth)?)?;
 			outer = A::finish_array(visitor, inner)?;
 		},
 		tag => bail!("unknown `element_value` tag {tag:?}"),
@@ -1414,7 +1894,8 @@ fn read_element_value_unnamed<A: UnnamedElementValueVisitor>(reader: &mut impl C
 fn read_type_annotations_attribute<A: TypeAnnotationsVisitor<T>, T: TargetInfoRead>(
 	reader: &mut impl ClassRead,
 	mut type_annotations_visitor: A,
-	pool: &PoolRead
+	pool: &PoolRead,
+	max_depth: usize,
 ) -> Result<A> {
 	let num_annotations = reader.read_u16()?;
 	for _ in 0..num_annotations {
@@ -1424,7 +1905,7 @@ fn read_type_annotations_attribute<A: TypeAnnotationsVisitor<T>, T: TargetInfoRe
 
 		let (visitor, named_element_values_visitor) = type_annotations_visitor.visit_type_annotation(type_reference, type_path, annotation_descriptor)?;
 
-		let named_element_values_visitor = read_element_values_named(reader, pool, named_element_values_visitor)?;
+		let named_element_values_visitor = read_element_values_named(reader, pool, named_element_values_visitor, max_depth)?;
 
 		type_annotations_visitor = TypeAnnotationsVisitor::finish_type_annotation(visitor, named_element_values_visitor)?;
 	}
@@ -1437,6 +1918,7 @@ fn read_type_annotations_attribute_code<A: TypeAnnotationsVisitor<TargetInfoCode
 	mut type_annotations_visitor: A,
 	pool: &PoolRead,
 	labels: &mut Labels,
+	max_depth: usize,
 ) -> Result<A> {
 	let num_annotations = reader.read_u16()?;
 	for _ in 0..num_annotations {
@@ -1446,7 +1928,7 @@ fn read_type_annotations_attribute_code<A: TypeAnnotationsVisitor<TargetInfoCode
 
 		let (visitor, named_element_values_visitor) = type_annotations_visitor.visit_type_annotation(type_reference, type_path, annotation_descriptor)?;
 
-		let named_element_values_visitor = read_element_values_named(reader, pool, named_element_values_visitor)?;
+		let named_element_values_visitor = read_element_values_named(reader, pool, named_element_values_visitor, max_depth)?;
 
 		type_annotations_visitor = TypeAnnotationsVisitor::finish_type_annotation(visitor, named_element_values_visitor)?;
 	}
@@ -1646,4 +2128,335 @@ fn read_module(reader: &mut impl ClassRead, pool: &PoolRead) -> Result<Module> {
 			})
 		)?,
 	})
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::{Context, Result};
+	use pretty_assertions::assert_eq;
+	use java_string::JavaStr;
+	use crate::{AttributeFilter, ClassReadError};
+	use crate::tree::annotation::{Annotation, ElementValue, ElementValuePair, Object};
+	use crate::tree::class::{ClassAccess, ClassFile, ClassName};
+	use crate::tree::field::FieldDescriptor;
+	use crate::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName};
+	use crate::tree::version::Version;
+
+	#[test]
+	fn huge_attribute_length_errors_on_truncation() -> Result<()> {
+		let mut class = ClassFile::new(Version::V1_8, ClassAccess::default(), ClassName::JAVA_LANG_OBJECT.to_owned(), None, Vec::new());
+		class.source_debug_extension = Some("x".into());
+
+		let mut bytes = Vec::new();
+		crate::write_class(&mut bytes, &class)?;
+
+		// The `SourceDebugExtension` is the last attribute, so the class ends with its length and its single byte.
+		let length = bytes.len() - 5;
+		assert_eq!(&bytes[length..], &[0, 0, 0, 1, b'x']);
+
+		// Claim almost 4 GiB of data, while there's only a single byte left.
+		bytes[length..length + 4].copy_from_slice(&0xFFFF_FFF0u32.to_be_bytes());
+
+		let error = crate::read_class(&mut Cursor::new(bytes)).err().context("expected an error")?;
+		assert!(format!("{error:?}").contains("unexpected end of data"), "{error:?}");
+
+		Ok(())
+	}
+
+	#[test]
+	fn read_class_with_bytes() -> Result<()> {
+		let class_bytes = include_bytes!("class_reader/test/Breakpoint.class");
+
+		let mut data = b"prefix".to_vec();
+		data.extend_from_slice(class_bytes);
+		data.extend_from_slice(b"trailing bytes");
+
+		let mut reader = Cursor::new(data);
+		reader.set_position(6);
+		let (class, bytes) = crate::read_class_with_bytes(&mut reader)?;
+
+		assert_eq!(bytes, class_bytes);
+		assert_eq!(reader.position(), 6 + class_bytes.len() as u64);
+		assert_eq!(crate::read_class(&mut Cursor::new(bytes))?, class);
+
+		Ok(())
+	}
+
+	#[test]
+	fn transform_class_identity() -> Result<()> {
+		let class_bytes = include_bytes!("tree/test/Point.class");
+
+		let bytes = crate::transform_class(class_bytes, |visitor| visitor, |visitor| visitor)?;
+
+		assert_eq!(crate::read_class(&mut Cursor::new(bytes))?, crate::read_class(&mut Cursor::new(class_bytes))?);
+
+		Ok(())
+	}
+
+	#[test]
+	fn read_class_filtered() -> Result<()> {
+		let class_bytes = include_bytes!("tree/test/StripDebugInfo.class");
+
+		let full = crate::read_class(&mut Cursor::new(class_bytes))?;
+		let method = full.methods.iter().find(|method| method.name.as_inner() == "sum").context("no method `sum`")?;
+		assert!(full.source_file.is_some());
+		assert!(method.code.as_ref().is_some_and(|code| code.line_numbers.is_some()));
+
+		let filter = AttributeFilter::new(["SourceFile"]);
+		let class = crate::read_class_filtered(&mut Cursor::new(class_bytes), &filter)?;
+		assert_eq!(class.source_file, full.source_file);
+		assert!(class.methods.iter().all(|method| method.code.is_none()));
+
+		// nested attributes need their parent attribute as well
+		let filter = AttributeFilter::new(["Code", "LineNumberTable"]);
+		let class = crate::read_class_filtered(&mut Cursor::new(class_bytes), &filter)?;
+		assert_eq!(class.source_file, None);
+		let method = class.methods.iter().find(|method| method.name.as_inner() == "sum").context("no method `sum`")?;
+		let code = method.code.as_ref().context("no code")?;
+		assert!(code.line_numbers.is_some());
+		assert_eq!(code.local_variables, None);
+		assert!(code.instructions.iter().all(|entry| entry.frame.is_none()));
+
+		Ok(())
+	}
+
+	#[test]
+	fn instruction_offsets() -> Result<()> {
+		let offsets = |class: &ClassFile| -> Result<Vec<Option<u16>>> {
+			let method = class.methods.iter().find(|method| method.name.as_inner() == "x").context("no method `x`")?;
+			let code = method.code.as_ref().context("no code")?;
+			Ok(code.instructions.iter().map(|entry| entry.offset).collect())
+		};
+
+		let class = crate::read_class_with_instruction_offsets(&mut Cursor::new(include_bytes!("tree/test/Point.class")))?;
+		// aload_0, getfield #index, ireturn
+		assert_eq!(offsets(&class)?, [Some(0), Some(1), Some(4)]);
+
+		// the offsets aren't read by default
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("tree/test/Point.class")))?;
+		assert_eq!(offsets(&class)?, [None, None, None]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn unknown_opcode_lenient() -> Result<()> {
+		let mut bytes = include_bytes!("class_reader/test/Breakpoint.class").to_vec();
+
+		// `code_length` of 1, and the `return` opcode: this is the code of `patched`
+		let code = [0, 0, 0, 1, 0xb1];
+		let position = bytes.windows(code.len()).position(|window| window == code).context("no code of `patched` found")?;
+		bytes[position + 4] = 0xca; // breakpoint
+
+		// reading it normally fails
+		let error = crate::read_class(&mut Cursor::new(&bytes)).err().context("expected an error")?;
+		assert!(format!("{error:?}").contains("unknown opcode 0xca breakpoint at bytecode offset 0"), "{error:?}");
+
+		let (class, unknown_opcodes) = crate::read_class_lenient(&mut Cursor::new(&bytes))?;
+
+		assert_eq!(unknown_opcodes.len(), 1);
+		assert_eq!(unknown_opcodes[0].method.class, class.name);
+		assert_eq!(unknown_opcodes[0].method.name.as_inner(), "patched");
+		assert_eq!(unknown_opcodes[0].offset, 0);
+		assert_eq!(unknown_opcodes[0].opcode, 0xca);
+
+		let patched = class.methods.iter().find(|method| method.name.as_inner() == "patched").context("no method `patched`")?;
+		assert!(patched.code.is_none());
+		let attribute = patched.attributes.iter().find(|attribute| attribute.name == "Code").context("no raw `Code` attribute")?;
+		assert!(attribute.bytes.ends_with(&[0, 0, 0, 1, 0xca, 0, 0, 0, 0]));
+
+		let untouched = class.methods.iter().find(|method| method.name.as_inner() == "untouched").context("no method `untouched`")?;
+		assert!(untouched.code.is_some());
+
+		Ok(())
+	}
+
+	#[test]
+	fn annotation_default_with_nested_annotations() -> Result<()> {
+		let class_bytes = include_bytes!("class_reader/test/NestedDefaults.class");
+		let class = crate::read_class(&mut Cursor::new(class_bytes))?;
+
+		let method = class.methods.iter().find(|method| method.name.as_inner() == "value").context("no method `value`")?;
+
+		let inner = |value: &str, deeper: Option<i32>| -> Result<ElementValue> {
+			let mut annotation = Annotation::new(FieldDescriptor::try_from(JavaStr::from_str("LNestedDefaults$Inner;"))?);
+			annotation.element_value_pairs.push(ElementValuePair {
+				name: "value".into(),
+				value: ElementValue::Object(Object::String(value.into())),
+			});
+			if let Some(number) = deeper {
+				let mut deeper = Annotation::new(FieldDescriptor::try_from(JavaStr::from_str("LNestedDefaults$Deeper;"))?);
+				deeper.element_value_pairs.push(ElementValuePair {
+					name: "numbers".into(),
+					value: ElementValue::ArrayType(vec![ElementValue::Object(Object::Integer(number))]),
+				});
+				annotation.element_value_pairs.push(ElementValuePair {
+					name: "deeper".into(),
+					value: ElementValue::AnnotationInterface(deeper),
+				});
+			}
+			Ok(ElementValue::AnnotationInterface(annotation))
+		};
+		assert_eq!(method.annotation_default, Some(ElementValue::ArrayType(vec![inner("a", None)?, inner("b", Some(3))?])));
+
+		let mut bytes = Vec::new();
+		crate::write_class(&mut bytes, &class)?;
+		assert_eq!(crate::read_class(&mut Cursor::new(bytes))?, class);
+
+		// the array, the `@Inner`, the `@Deeper` and its array are nested four levels deep
+		crate::read_class_with_max_element_value_depth(&mut Cursor::new(class_bytes), 4)?;
+		assert!(crate::read_class_with_max_element_value_depth(&mut Cursor::new(class_bytes), 3).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn deeply_nested_element_values() -> Result<()> {
+		let mut value = ElementValue::Object(Object::Integer(0));
+		for _ in 0..10_000 {
+			value = ElementValue::ArrayType(vec![value]);
+		}
+
+		let mut method = Method::new(
+			MethodAccess::from(0x0401),
+			MethodName::try_from(JavaStr::from_str("value"))?,
+			MethodDescriptor::try_from(JavaStr::from_str("()[I"))?,
+		);
+		method.annotation_default = Some(value);
+
+		let mut class = ClassFile::new(Version::V1_8, ClassAccess::from(0x2601), ClassName::try_from(JavaStr::from_str("Nested"))?, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		class.methods.push(method);
+
+		// run on a thread with a large stack, as writing and dropping such a value is recursive as well
+		let bytes = std::thread::Builder::new()
+			.stack_size(256 * 1024 * 1024)
+			.spawn(move || -> Result<Vec<u8>> {
+				let mut bytes = Vec::new();
+				crate::write_class(&mut bytes, &class)?;
+				Ok(bytes)
+			})?
+			.join()
+			.map_err(|_| anyhow::anyhow!("writing the class panicked"))??;
+
+		let error = crate::read_class(&mut Cursor::new(&bytes)).err().context("expected an error")?;
+		assert!(format!("{error:?}").contains("element values are nested too deeply"), "{error:?}");
+
+		Ok(())
+	}
+
+	#[test]
+	fn truncated() -> Result<()> {
+		let class_bytes = include_bytes!("tree/test/StripDebugInfo.class");
+
+		let error = |length: usize| crate::read_class(&mut Cursor::new(&class_bytes[..length])).err().context("expected an error");
+
+		// in the middle of the version
+		let truncated = error(6)?;
+		assert_eq!(truncated.downcast_ref::<ClassReadError>(), Some(&ClassReadError::Truncated { while_reading: "class header" }));
+
+		// in the middle of the constant pool, right in a `CONSTANT_Utf8`
+		let truncated = error(40)?;
+		assert_eq!(truncated.downcast_ref::<ClassReadError>(), Some(&ClassReadError::Truncated { while_reading: "constant pool" }));
+		assert!(format!("{truncated:#}").contains("failed to read constant pool"), "{truncated:#}");
+
+		// in the middle of the last method
+		let truncated = error(class_bytes.len() - 100)?;
+		assert_eq!(truncated.downcast_ref::<ClassReadError>(), Some(&ClassReadError::Truncated { while_reading: "method" }));
+		assert!(format!("{truncated:#}").contains("failed to skip methods"), "{truncated:#}");
+
+		// no matter where the class ends, it's always truncated
+		for length in 0..class_bytes.len() {
+			assert!(error(length)?.downcast_ref::<ClassReadError>().is_some(), "length {length}");
+		}
+
+		// while a wrong magic is invalid data
+		let mut invalid = class_bytes.to_vec();
+		invalid[0] = 0;
+		let invalid = crate::read_class(&mut Cursor::new(invalid)).err().context("expected an error")?;
+		assert_eq!(invalid.downcast_ref::<ClassReadError>(), None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn stack_map_table_with_wrong_number_of_entries() -> Result<()> {
+		let mut class_bytes = include_bytes!("tree/test/StripDebugInfo.class").to_vec();
+
+		// The `StackMapTable` of `sum`: name index 44, length 13 and 3 entries.
+		let attribute = [0x00, 0x2c, 0x00, 0x00, 0x00, 0x0d, 0x00, 0x03];
+		let position = class_bytes.windows(attribute.len())
+			.position(|window| window == attribute)
+			.context("no `StackMapTable` attribute found")?;
+
+		crate::read_class(&mut Cursor::new(&class_bytes))?;
+
+		// claim a fourth entry
+		class_bytes[position + 7] = 4;
+
+		// the class itself isn't truncated, only the attribute is wrong
+		let error = crate::read_class(&mut Cursor::new(&class_bytes)).err().context("expected an error")?;
+		let message = "StackMapTable claims 4 entries but only 13 bytes available";
+		assert!(error.chain().any(|cause| cause.to_string() == message), "{error:#}");
+		assert_eq!(error.downcast_ref::<ClassReadError>(), None);
+
+		// claim only two entries, leaving the third one unread
+		class_bytes[position + 7] = 2;
+
+		let error = crate::read_class(&mut Cursor::new(&class_bytes)).err().context("expected an error")?;
+		let message = "StackMapTable has 3 bytes left over after reading its 2 entries";
+		assert!(error.chain().any(|cause| cause.to_string() == message), "{error:#}");
+
+		Ok(())
+	}
+
+	#[test]
+	fn local_variable_table() -> Result<()> {
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("tree/test/StripDebugInfo.class")))?;
+		let method = class.methods.iter().find(|method| method.name.as_inner() == "sum").context("no method `sum`")?;
+		let code = method.code.as_ref().context("method without code")?;
+		let local_variables = code.local_variables.as_ref().context("no local variables read")?;
+
+		// the entries of the `LocalVariableTable` come before the ones of the `LocalVariableTypeTable`
+		let local_variables: Vec<(&str, Option<&str>, Option<&str>)> = local_variables.iter()
+			.map(|lv| Ok((
+				lv.name.as_inner().as_str()?,
+				lv.descriptor.as_ref().map(|descriptor| descriptor.as_inner().as_str()).transpose()?,
+				lv.signature.as_ref().map(|signature| signature.as_inner().as_str()).transpose()?,
+			)))
+			.collect::<Result<_>>()?;
+		assert_eq!(local_variables, [
+			("i", Some("I"), None),
+			("list", Some("Ljava/util/List;"), None),
+			("sum", Some("I"), None),
+			("list", None, Some("Ljava/util/List<Ljava/lang/Integer;>;")),
+		]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn raw_code_with_unknown_attribute() -> Result<()> {
+		let mut class_bytes = include_bytes!("tree/test/StripDebugInfo.class").to_vec();
+
+		// rename the `LineNumberTable` attributes of the code to an unknown attribute, with a name of the same length
+		let name = b"LineNumberTable";
+		let position = class_bytes.windows(name.len())
+			.position(|window| window == name)
+			.context("no `LineNumberTable` attribute name found")?;
+		class_bytes[position..position + name.len()].copy_from_slice(b"UnknownAttribut");
+
+		// the attribute could refer to the constant pool, so it can't be kept
+		let error = crate::read_class_raw_code(&mut Cursor::new(&class_bytes)).err().context("expected an error")?;
+		let message = "unknown attribute \"UnknownAttribut\" of the `Code` attribute can't be read as raw code";
+		assert!(error.chain().any(|cause| cause.to_string() == message), "{error:#}");
+
+		// reading it fully keeps it as an unknown attribute
+		let class = crate::read_class(&mut Cursor::new(&class_bytes))?;
+		let code = class.methods.iter().find_map(|method| method.code.as_ref()).context("no method with code")?;
+		let names: Vec<&JavaStr> = code.attributes.iter().map(|attribute| attribute.name.as_java_str()).collect();
+		assert_eq!(names, ["UnknownAttribut"]);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,9 @@
+// compile with `javac --release 8 -g:none Breakpoint.java`
+// The test replaces the `return` of `patched` with a `breakpoint` opcode.
+public class Breakpoint {
+	static void patched() {}
+
+	static int untouched(int a) {
+		return a + 1;
+	}
+}
@@ -0,0 +1,13 @@
+// compile with `javac --release 8 NestedDefaults.java`
+public @interface NestedDefaults {
+	Inner[] value() default { @Inner("a"), @Inner(value = "b", deeper = @Deeper(numbers = 3)) };
+
+	@interface Inner {
+		String value();
+		Deeper deeper() default @Deeper;
+	}
+
+	@interface Deeper {
+		int[] numbers() default { 1, 2 };
+	}
+}
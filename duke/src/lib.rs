@@ -10,10 +10,13 @@ mod simple_class_writer;
 mod macros;
 mod class_constants;
 
-use std::fmt::Debug;
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
 use anyhow::{anyhow, bail, Context, Result};
+use java_string::JavaStr;
 use crate::tree::class::ClassFile;
+use crate::tree::version::Version;
+pub use crate::class_reader::{AttributeFilter, UnknownOpcode};
 use crate::visitor::MultiClassVisitor;
 
 // TODO: add some traits like Hash, Eq, PartialEq, ... to most of the structs in tree:: !
@@ -37,8 +40,187 @@ pub fn read_class(reader: &mut (impl Read + Seek)) -> Result<ClassFile> {
         .map_err(|_| anyhow!("there was no class inside it"))
 }
 
+/// Reads a class file from the reader into the visitor, skipping all attributes not allowed by the filter.
+///
+/// See [`AttributeFilter`] for details.
+pub fn read_class_multi_filtered<V>(reader: &mut (impl Read + Seek), visitor: V, filter: &AttributeFilter) -> Result<V>
+where
+    V: MultiClassVisitor,
+{
+    class_reader::read_filtered(reader, visitor, filter)
+}
+
+/// Reads a single java class file from the reader, skipping all attributes not allowed by the filter.
+///
+/// See [`AttributeFilter`] for details.
+pub fn read_class_filtered(reader: &mut (impl Read + Seek), filter: &AttributeFilter) -> Result<ClassFile> {
+    class_reader::read_filtered(reader, Vec::new(), filter)?
+        .try_into()
+        .map(|[class]: [ClassFile; 1]| class)
+        .map_err(|_| anyhow!("there was no class inside it"))
+}
+
+/// Reads a single java class file from the reader, and returns it together with the exact bytes it was read from.
+///
+/// Reading starts at the current position of the reader. Only the bytes of the class are returned, any bytes after it are
+/// left in the reader, which is positioned right after the class. This allows storing the raw form of a class, and only
+/// writing it again once it's changed.
+pub fn read_class_with_bytes(reader: &mut (impl Read + Seek)) -> Result<(ClassFile, Vec<u8>)> {
+    let start = reader.stream_position()?;
+    let class = read_class(reader)?;
+    let end = reader.stream_position()?;
+
+    let length = end.checked_sub(start)
+        .with_context(|| anyhow!("reader is at {end}, before the start {start} of the class"))?;
+    let mut bytes = vec![0; usize::try_from(length)?];
+    reader.seek(SeekFrom::Start(start))?;
+    reader.read_exact(&mut bytes)
+        .context("failed to read the bytes of the class again")?;
+
+    Ok((class, bytes))
+}
+
+/// Reads a single java class file from the reader, without failing on unknown opcodes.
+///
+/// This is useful for scanning obfuscated or corrupt classes. If the code of a method contains an unknown opcode, it's
+/// recorded in the returned list, and the `Code` attribute of that method is kept as an unknown attribute in
+/// [`Method::attributes`][crate::tree::method::Method::attributes] instead. Note that the data of that attribute refers to
+/// the constant pool of the class read, so writing out such a method will not produce a valid `Code` attribute.
+///
+/// The deprecated `jsr` and `ret` opcodes are valid, and therefore never reported.
+pub fn read_class_lenient(reader: &mut (impl Read + Seek)) -> Result<(ClassFile, Vec<UnknownOpcode>)> {
+    let (classes, unknown_opcodes) = class_reader::read_lenient(reader, Vec::new())?;
+    let [class]: [ClassFile; 1] = classes.try_into()
+        .map_err(|_| anyhow!("there was no class inside it"))?;
+    Ok((class, unknown_opcodes))
+}
+
+/// Reads a single java class file from the reader, keeping the code of all methods as raw bytecode.
+///
+/// The code is stored in [`Code::raw`][crate::tree::method::code::Code::raw], and written back as is, with only the constant
+/// pool references changed. This skips resolving the branches of the code into labels, which makes it faster for
+/// transformations that only change the constants referenced, like remapping. See [`RawCode`][crate::tree::method::raw_code::RawCode]
+/// for what can be changed, and for why reading fails on type annotations and unknown attributes in the code.
+pub fn read_class_raw_code(reader: &mut (impl Read + Seek)) -> Result<ClassFile> {
+    class_reader::read_raw_code(reader, Vec::new())?
+        .try_into()
+        .map(|[class]: [ClassFile; 1]| class)
+        .map_err(|_| anyhow!("there was no class inside it"))
+}
+
+/// Reads a single java class file from the reader, recording the bytecode offset of each instruction.
+///
+/// The offsets are stored in [`InstructionListEntry::offset`][crate::tree::method::code::InstructionListEntry::offset]. They're
+/// not read by [`read_class`], as they make trees of the same code with a different bytecode layout compare unequal.
+pub fn read_class_with_instruction_offsets(reader: &mut (impl Read + Seek)) -> Result<ClassFile> {
+    class_reader::read_with_instruction_offsets(reader, Vec::new())?
+        .try_into()
+        .map(|[class]: [ClassFile; 1]| class)
+        .map_err(|_| anyhow!("there was no class inside it"))
+}
+
+/// Reads a single java class file from the reader, with a different limit for the nesting of element values.
+///
+/// Annotations and arrays in element values can be nested, and are read recursively. To not overflow the stack on crafted
+/// input, reading fails once they're nested more than `max_depth` levels deep. The other functions reading classes use
+/// [`MAX_ELEMENT_VALUE_DEPTH`].
+pub fn read_class_with_max_element_value_depth(reader: &mut (impl Read + Seek), max_depth: usize) -> Result<ClassFile> {
+    class_reader::read_with_max_element_value_depth(reader, Vec::new(), max_depth)?
+        .try_into()
+        .map(|[class]: [ClassFile; 1]| class)
+        .map_err(|_| anyhow!("there was no class inside it"))
+}
+
+/// Reads the class file version from the header of the bytes, without parsing the rest of the class.
+///
+/// Fails if the bytes don't start with the `0xCAFEBABE` magic, are too short to contain a version, if the version isn't one
+/// that's actually in use, see [`Version::new_checked`], or if it's newer than [`Version::NEWEST_SUPPORTED`].
+///
+/// Note that this doesn't check that the rest of the bytes form a valid class file.
+pub fn peek_version(bytes: &[u8]) -> Result<Version> {
+    let [0xCA, 0xFE, 0xBA, 0xBE, minor_1, minor_2, major_1, major_2, ..] = *bytes else {
+        bail!("not a class file: doesn't start with the 0xCAFEBABE magic followed by a version");
+    };
+    let minor = u16::from_be_bytes([minor_1, minor_2]);
+    let major = u16::from_be_bytes([major_1, major_2]);
+    let version = Version::new_checked(major, minor)?;
+    if version > Version::NEWEST_SUPPORTED {
+        bail!("unsupported class file version {major}.{minor}: the newest supported version is {}.{}",
+            Version::NEWEST_SUPPORTED.major(), Version::NEWEST_SUPPORTED.minor());
+    }
+    Ok(version)
+}
+
+/// Cheaply checks whether the bytes look like a class file, without parsing the class.
+///
+/// This checks the `0xCAFEBABE` magic and that the version is plausible, see [`peek_version`]. The version check rejects
+/// other files using the same magic, like Mach-O universal binaries, which store a small number of architectures there.
+///
+/// This is useful for classifying entries of a jar that don't end in `.class`. A `true` doesn't mean that [`read_class`]
+/// succeeds, as the rest of the bytes isn't looked at.
+pub fn is_class_file(bytes: &[u8]) -> bool {
+    peek_version(bytes).is_ok()
+}
+
 pub fn write_class(writer: &mut impl Write, class: &ClassFile) -> Result<()> {
-    simple_class_writer::write(writer, class)
+    simple_class_writer::write(writer, class, WriteOptions::default())
+}
+
+/// Options for [`write_class_with_options`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Whether to write code with branches or exception handlers but without stack map frames for class file version 50
+    /// or later.
+    ///
+    /// Writing such code fails by default, as the class would fail verification. Set this if the frames are computed
+    /// afterwards, or if the class isn't meant to be loaded, for example when the code was read without the
+    /// [`stack_map_table`][crate::visitor::method::code::CodeInterests::stack_map_table] interest, or built by hand.
+    pub skip_stack_map_check: bool,
+}
+
+/// Writes a class, like [`write_class`] does, but with the given options.
+pub fn write_class_with_options(writer: &mut impl Write, class: &ClassFile, options: WriteOptions) -> Result<()> {
+    simple_class_writer::write(writer, class, options)
+}
+
+/// Reads a single class from the bytes, runs it through a visitor adapter and writes the result back to bytes.
+///
+/// The adapter is created by `wrap`, from the visitor building the [`ClassFile`]. After reading, `unwrap` gets that visitor
+/// back out of the adapter. This makes simple transformations a single call, for example dropping synthetic members:
+/// ```
+/// # use anyhow::Result;
+/// use duke::visitor::filter::DropSyntheticMembers;
+///
+/// fn drop_synthetic_members(bytes: &[u8]) -> Result<Vec<u8>> {
+///     duke::transform_class(bytes, DropSyntheticMembers::new, DropSyntheticMembers::into_inner)
+/// }
+/// ```
+pub fn transform_class<M>(
+    bytes: &[u8],
+    wrap: impl FnOnce(Option<ClassFile>) -> M,
+    unwrap: impl FnOnce(M) -> Option<ClassFile>,
+) -> Result<Vec<u8>>
+where
+    M: MultiClassVisitor,
+{
+    let visitor = class_reader::read(&mut Cursor::new(bytes), wrap(None))?;
+    let class = unwrap(visitor)
+        .context("there was no class inside it, or the adapter dropped it")?;
+
+    let mut out = Vec::new();
+    write_class(&mut out, &class)?;
+    Ok(out)
+}
+
+/// Writes a class with the given (usually older) version, removing the attributes not yet defined in that version.
+///
+/// For example when targeting Java 8, this removes the `NestHost` and `NestMembers` attributes (added in Java 11), which
+/// an older JVM would reject. Returns the names of the attributes removed, so that you can warn about the data lost.
+///
+/// Note that this doesn't change the code of methods: the `Code` and `BootstrapMethods` attributes are written as is. Only
+/// the stack map frames are removed when targeting a version before 50, as the old verifier doesn't use them.
+pub fn write_class_targeting(writer: &mut impl Write, class: &ClassFile, version: Version) -> Result<Vec<&'static JavaStr>> {
+    simple_class_writer::write_targeting(writer, class, version)
 }
 
 trait OptionExpansion<T> {
@@ -55,6 +237,75 @@ impl<T> OptionExpansion<T> for Option<T> where T: Debug {
     }
 }
 
+/// The maximum capacity allocated up front for sizes read from a class file.
+///
+/// A class file can claim any length for an attribute or table, without containing that much data. Allocating more than
+/// this only happens as the data is actually read, so that such a class can't make us allocate gigabytes of memory.
+const MAX_PREALLOCATION: usize = 0x1_0000;
+
+/// The default for how deep annotations and arrays can be nested in element values, see
+/// [`read_class_with_max_element_value_depth`].
+///
+/// Real annotations rarely nest more than a few levels, so this only rejects crafted classes.
+pub const MAX_ELEMENT_VALUE_DEPTH: usize = 64;
+
+/// An error reading a class file, telling apart data that ended too early from data that's structurally wrong.
+///
+/// The functions reading classes return [`anyhow::Error`]s, with the context of what part of the class was read. Use
+/// [`downcast_ref`][anyhow::Error::downcast_ref] to get this error out of them:
+/// ```
+/// use std::io::Cursor;
+/// use duke::ClassReadError;
+///
+/// let error = duke::read_class(&mut Cursor::new([0xCA, 0xFE, 0xBA, 0xBE, 0, 0])).unwrap_err();
+/// assert_eq!(error.downcast_ref::<ClassReadError>(), Some(&ClassReadError::Truncated { while_reading: "class header" }));
+/// ```
+/// Any other error means that the data isn't a valid class file, and reading more of it won't help.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClassReadError {
+    /// The data ended in the middle of the class file.
+    ///
+    /// This might be recoverable, for example by getting more data, if the class is downloaded.
+    Truncated {
+        /// The innermost structure that was being read when the data ended, like `constant pool`, `method` or `Code attribute`.
+        ///
+        /// This is `class file` if the data ended outside of any more specific structure.
+        while_reading: &'static str,
+    },
+}
+
+impl Display for ClassReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClassReadError::Truncated { while_reading } => write!(f, "unexpected end of data while reading {while_reading}"),
+        }
+    }
+}
+
+impl std::error::Error for ClassReadError {}
+
+/// Names the structure that was being read in [`ClassReadError::Truncated`] errors.
+trait WhileReading {
+    /// Sets `while_reading` of a [`ClassReadError::Truncated`] to `structure`, unless an inner structure was already named.
+    fn while_reading(self, structure: &'static str) -> Self;
+}
+
+impl<T> WhileReading for Result<T> {
+    fn while_reading(self, structure: &'static str) -> Self {
+        self.map_err(|mut error| {
+            if let Some(ClassReadError::Truncated { while_reading }) = error.downcast_mut::<ClassReadError>() {
+                if *while_reading == TRUNCATED_CLASS_FILE {
+                    *while_reading = structure;
+                }
+            }
+            error
+        })
+    }
+}
+
+/// What [`ClassReadError::Truncated`] is created with, before [`WhileReading`] names the structure being read.
+const TRUNCATED_CLASS_FILE: &str = "class file";
+
 trait ClassRead {
     fn marker(&mut self) -> Result<u64>;
     fn skip(&mut self, n: i64) -> Result<()>;
@@ -67,30 +318,31 @@ trait ClassRead {
         Ok(r)
     }
 
+    /// Reads `N` bytes, returning [`ClassReadError::Truncated`] if the data ends before that.
     fn read_n<const N: usize>(&mut self) -> Result<[u8; N]>;
     fn read_u8(&mut self) -> Result<u8> {
-        Ok(u8::from_be_bytes(self.read_n().context("couldn't read u8, perhaps the data's end is reached?")?))
+        Ok(u8::from_be_bytes(self.read_n()?))
     }
     fn read_u16(&mut self) -> Result<u16> {
-        Ok(u16::from_be_bytes(self.read_n().context("couldn't read u16, perhaps the data's end is reached?")?))
+        Ok(u16::from_be_bytes(self.read_n()?))
     }
     fn read_u32(&mut self) -> Result<u32> {
-        Ok(u32::from_be_bytes(self.read_n().context("couldn't read u32, perhaps the data's end is reached?")?))
+        Ok(u32::from_be_bytes(self.read_n()?))
     }
     fn read_u64(&mut self) -> Result<u64> {
-        Ok(u64::from_be_bytes(self.read_n().context("couldn't read u64, perhaps the data's end is reached?")?))
+        Ok(u64::from_be_bytes(self.read_n()?))
     }
     fn read_i8(&mut self) -> Result<i8> {
-        Ok(i8::from_be_bytes(self.read_n().context("couldn't read i8, perhaps the data's end is reached?")?))
+        Ok(i8::from_be_bytes(self.read_n()?))
     }
     fn read_i16(&mut self) -> Result<i16> {
-        Ok(i16::from_be_bytes(self.read_n().context("couldn't read i16, perhaps the data's end is reached?")?))
+        Ok(i16::from_be_bytes(self.read_n()?))
     }
     fn read_i32(&mut self) -> Result<i32> {
-        Ok(i32::from_be_bytes(self.read_n().context("couldn't read i32, perhaps the data's end is reached?")?))
+        Ok(i32::from_be_bytes(self.read_n()?))
     }
     fn read_i64(&mut self) -> Result<i64> {
-        Ok(i64::from_be_bytes(self.read_n().context("couldn't read i64, perhaps the data's end is reached?")?))
+        Ok(i64::from_be_bytes(self.read_n()?))
     }
 
     fn read_u8_as_usize(&mut self) -> Result<usize> {
@@ -109,7 +361,7 @@ trait ClassRead {
             E: FnMut(&mut Self) -> Result<T>
     {
         let size = get_size(self)?;
-        let mut vec = Vec::with_capacity(size);
+        let mut vec = Vec::with_capacity(size.min(MAX_PREALLOCATION));
         for _ in 0..size {
             vec.push(get_element(self)?);
         }
@@ -131,12 +383,19 @@ impl<T: Read + Seek> ClassRead for T {
 
     fn read_n<const N: usize>(&mut self) -> Result<[u8; N]> {
         let mut buf = [0u8; N];
-        self.read_exact(&mut buf)?;
-        Ok(buf)
+        match self.read_exact(&mut buf) {
+            Ok(()) => Ok(buf),
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => Err(ClassReadError::Truncated { while_reading: TRUNCATED_CLASS_FILE }.into()),
+            Err(error) => Err(error.into()),
+        }
     }
     fn read_u8_vec(&mut self, size: usize) -> Result<Vec<u8>> {
-        let mut vec = std::vec::from_elem(0, size);
-        self.read_exact(&mut vec)?;
+        let mut vec = Vec::with_capacity(size.min(MAX_PREALLOCATION));
+        self.take(size as u64).read_to_end(&mut vec)?;
+        if vec.len() != size {
+            return Err(anyhow::Error::new(ClassReadError::Truncated { while_reading: TRUNCATED_CLASS_FILE })
+                .context(format!("expected {size} bytes, but only got {}", vec.len())));
+        }
         Ok(vec)
     }
 }
@@ -201,3 +460,35 @@ impl<T: Write> ClassWrite for T {
         self.write_all(buf).context("failed to write &[u8]")
     }
 }
+
+#[cfg(test)]
+mod testing {
+    use anyhow::{Context, Result};
+    use pretty_assertions::assert_eq;
+    use crate::tree::version::Version;
+
+    #[test]
+    fn is_class_file() -> Result<()> {
+        let class = include_bytes!("tree/test/Point.class");
+        assert!(crate::is_class_file(class));
+        assert_eq!(crate::peek_version(class)?, Version::V17);
+
+        // the start of a zip archive
+        let zip = b"PK\x03\x04\x14\x00\x08\x08\x08\x00";
+        assert!(!crate::is_class_file(zip));
+        assert!(crate::peek_version(zip).is_err());
+
+        // too short to contain the version
+        assert!(!crate::is_class_file(&class[..6]));
+        assert!(!crate::is_class_file(&[]));
+
+        // a Mach-O universal binary with two architectures
+        assert!(!crate::is_class_file(b"\xCA\xFE\xBA\xBE\x00\x00\x00\x02"));
+
+        // a version newer than the newest supported one
+        let error = crate::peek_version(b"\xCA\xFE\xBA\xBE\x00\x00\x00\x44").err().context("expected version 68 to be rejected")?;
+        assert_eq!(error.to_string(), "unsupported class file version 68.0: the newest supported version is 67.0");
+
+        Ok(())
+    }
+}
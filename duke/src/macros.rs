@@ -53,6 +53,14 @@ macro_rules! make_string_str_like {
 			pub fn is_valid($is_valid_param: &$borrowed_inner) -> anyhow::Result<()> {
 				$is_valid
 			}
+
+            #[doc = concat!("Constructs [`", stringify!($owned), "`] from [`",
+				stringify!($owned_inner), "`], checking that the content is valid.")]
+			///
+			/// This is the same as the [`TryFrom`] implementation, and the safe counterpart to `from_inner_unchecked`.
+			pub fn try_from_inner(s: $owned_inner) -> anyhow::Result<$owned> {
+				$owned::try_from(s)
+			}
 		}
 
 		impl $borrowed {
@@ -71,6 +79,15 @@ macro_rules! make_string_str_like {
 				// SAFETY: &'a $borrowed and &'a $borrowed_inner have the same layout.
 				unsafe { std::mem::transmute::<&'a $borrowed_inner, &'a $borrowed>(s) }
 			}
+
+            #[doc = concat!("Constructs [`&", stringify!($borrowed), "`][", stringify!($borrowed),
+				"] from [`&", stringify!($borrowed_inner), "`][", stringify!($borrowed_inner),
+				"], checking that the content is valid.")]
+			///
+			/// This is the same as the [`TryFrom`] implementation, and the safe counterpart to `from_inner_unchecked`.
+			pub fn try_from_inner(s: &$borrowed_inner) -> anyhow::Result<&$borrowed> {
+				<&$borrowed>::try_from(s)
+			}
 		}
 
 		impl AsRef<$borrowed_inner> for $borrowed {
@@ -204,4 +221,28 @@ macro_rules! make_string_str_like {
 	}
 }
 
-pub(crate) use {make_display, make_string_str_like};
\ No newline at end of file
+pub(crate) use {make_display, make_string_str_like};
+
+#[cfg(test)]
+mod testing {
+	use anyhow::Result;
+	use java_string::{JavaStr, JavaString};
+	use crate::tree::class::{ClassName, ClassNameSlice};
+	use crate::tree::method::{MethodDescriptor, MethodName};
+
+	#[test]
+	fn try_from_inner() -> Result<()> {
+		assert_eq!(ClassName::try_from_inner("java/lang/Object".into())?, ClassName::JAVA_LANG_OBJECT);
+		assert!(ClassName::try_from_inner("foo;".into()).is_err());
+		assert_eq!(ClassNameSlice::try_from_inner(JavaStr::from_str("java/lang/Object"))?, ClassName::JAVA_LANG_OBJECT);
+		assert!(ClassNameSlice::try_from_inner(JavaStr::from_str("foo;")).is_err());
+
+		assert_eq!(MethodName::try_from_inner(JavaString::from("foo"))?.as_inner(), "foo");
+		assert!(MethodName::try_from_inner(JavaString::from("foo;")).is_err());
+
+		assert_eq!(MethodDescriptor::try_from_inner(JavaString::from("(I)V"))?.as_inner(), "(I)V");
+		assert!(MethodDescriptor::try_from_inner(JavaString::from("foo;")).is_err());
+
+		Ok(())
+	}
+}
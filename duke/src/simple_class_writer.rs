@@ -1,7 +1,7 @@
 use std::collections::HashSet;
 use anyhow::{anyhow, bail, Context, Result};
 use java_string::JavaStr;
-use crate::{class_constants, ClassWrite, jstring};
+use crate::{class_constants, ClassWrite, jstring, WriteOptions};
 use crate::class_constants::{attribute, opcode, type_annotation};
 use crate::simple_class_writer::labels::{Labels};
 use crate::simple_class_writer::pool::PoolWrite;
@@ -9,10 +9,13 @@ use crate::tree::annotation::{Annotation, ElementValue, ElementValuePair, Object
 use crate::tree::class::ClassFile;
 use crate::tree::field::Field;
 use crate::tree::method::code::{Code, Instruction, Label, Loadable};
+use crate::tree::method::raw_code::{RawCode, RawConstant};
 use crate::tree::method::Method;
 use crate::tree::module::Module;
 use crate::tree::record::RecordComponent;
 use crate::tree::type_annotation::{TargetInfoClass, TargetInfoCode, TargetInfoField, TargetInfoMethod, TypeAnnotation, TypePath, TypePathKind};
+use crate::tree::version::Version;
+use crate::visitor::method::code::{StackMapData, VerificationTypeInfo};
 
 mod pool;
 mod labels;
@@ -38,17 +41,41 @@ fn write_attribute_fix_length<'a, 'b: 'a>(writer: &mut impl ClassWrite, pool: &m
 	writer.write_usize_as_u32(length).with_context(|| anyhow!("attribute {name:?} is too large"))
 }
 
-pub(crate) fn write(class_writer: &mut impl ClassWrite, class: &ClassFile) -> Result<()> {
+pub(crate) fn write(class_writer: &mut impl ClassWrite, class: &ClassFile, options: WriteOptions) -> Result<()> {
 	class_writer.write_u32(class_constants::MAGIC)?;
 
 	class_writer.write_u16(class.version.minor)?;
 	class_writer.write_u16(class.version.major)?;
 
 	// The constant pool. Any constant pool item is added to it.
-	let mut pool_: PoolWrite = PoolWrite::new();
-	let pool = &mut pool_;
-	// The buffer for the rest of the class file.
-	let mut writer = Vec::new();
+	let mut pool = PoolWrite::new();
+
+	// The `ldc` instructions of raw code only have a single byte for the constant pool index. We can't widen them, as that
+	// would move the instructions, so we put their constants into the pool first, to give them the lowest indices.
+	for raw_code in class.methods.iter().filter_map(|method| method.code.as_ref()?.raw.as_ref()) {
+		for loadable in raw_ldc_loadables(raw_code) {
+			pool.put_loadable(loadable)?;
+		}
+	}
+
+	// The constant pool comes before the rest of the class file, but it's only complete once all of the rest is written.
+	// So we write the rest into a buffer first, and write that after the pool.
+	let mut body = Vec::new();
+	write_body(&mut body, class, options, &mut pool)?;
+	pool.write(class_writer)?;
+	class_writer.write_u8_slice(&body)
+}
+
+#[cfg(test)]
+thread_local! {
+	/// Counts the calls of [`write_body`], so that tests can check that the body is only written once.
+	static WRITE_BODY_CALLS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Writes everything after the constant pool, putting all entries needed into the `pool`.
+fn write_body<'a>(writer: &mut impl ClassWrite, class: &'a ClassFile, options: WriteOptions, pool: &mut PoolWrite<'a>) -> Result<()> {
+	#[cfg(test)]
+	WRITE_BODY_CALLS.with(|calls| calls.set(calls.get() + 1));
 
 	writer.write_u16(class.access.into())?;
 	writer.write_u16(pool.put_class(&class.name)?)?;
@@ -79,7 +106,7 @@ pub(crate) fn write(class_writer: &mut impl ClassWrite, class: &ClassFile) -> Re
 	writer.write_slice(
 		&class.methods,
 		|w, size| w.write_usize_as_u16(size).with_context(|| anyhow!("failed to write the number of methods of class {:?}", class.name)),
-		|w, method| write_method(w, method, pool)
+		|w, method| write_method(w, method, class.version, options, pool)
 			.with_context(|| anyhow!("failed to write method of class {:?}", class.name))
 	)?;
 
@@ -218,7 +245,8 @@ pub(crate) fn write(class_writer: &mut impl ClassWrite, class: &ClassFile) -> Re
 		})?;
 	}
 
-	if let Some((bootstrap_methods, _)) = pool.bootstrap_methods.take() {
+	// Not taking them out of the pool, so that the second write still finds the bootstrap methods already put.
+	if let Some(bootstrap_methods) = pool.bootstrap_methods.as_ref().map(|(bootstrap_methods, _)| bootstrap_methods.clone()) {
 		attribute_count += 1;
 		write_attribute(&mut buffer, pool, attribute::BOOTSTRAP_METHODS, |w, pool| {
 			w.write_usize_as_u16(bootstrap_methods.len())?; // TODO: .context
@@ -242,14 +270,69 @@ pub(crate) fn write(class_writer: &mut impl ClassWrite, class: &ClassFile) -> Re
 
 	// Write the attribute count and then put the buffer containing the attributes.
 	writer.write_usize_as_u16(attribute_count).context("too many attributes on method")?;
-	writer.write_u8_slice(&buffer)?;
+	writer.write_u8_slice(&buffer)
+}
 
-	// IMPORTANT: Write the pool as the last thing, as any other writing can add pool entries.
-	pool_.write(class_writer)?;
-	// The rest of the class file comes after the constant pool.
-	class_writer.write_u8_slice(&writer)?;
+/// The first class file version each attribute is defined for, as given in table 4.7-B of the JVMS.
+///
+/// `Code`, `StackMapTable` and `BootstrapMethods` are missing here on purpose: they're needed for the code to work, so they
+/// can't simply be removed. Attributes defined since `45.3` are missing too, since there's no version before that.
+const ATTRIBUTE_MIN_VERSIONS: [(&JavaStr, Version); 19] = [
+	(attribute::ENCLOSING_METHOD, Version::V1_5),
+	(attribute::SOURCE_DEBUG_EXTENSION, Version::V1_5),
+	(attribute::SIGNATURE, Version::V1_5),
+	(attribute::LOCAL_VARIABLE_TYPE_TABLE, Version::V1_5),
+	(attribute::RUNTIME_VISIBLE_ANNOTATIONS, Version::V1_5),
+	(attribute::RUNTIME_INVISIBLE_ANNOTATIONS, Version::V1_5),
+	(attribute::RUNTIME_VISIBLE_PARAMETER_ANNOTATIONS, Version::V1_5),
+	(attribute::RUNTIME_INVISIBLE_PARAMETER_ANNOTATIONS, Version::V1_5),
+	(attribute::ANNOTATION_DEFAULT, Version::V1_5),
+	(attribute::METHOD_PARAMETERS, Version::V1_8),
+	(attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS, Version::V1_8),
+	(attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS, Version::V1_8),
+	(attribute::MODULE, Version::V9),
+	(attribute::MODULE_PACKAGES, Version::V9),
+	(attribute::MODULE_MAIN_CLASS, Version::V9),
+	(attribute::NEST_HOST, Version::V11),
+	(attribute::NEST_MEMBERS, Version::V11),
+	(attribute::RECORD, Version::V16),
+	(attribute::PERMITTED_SUBCLASSES, Version::V17),
+];
+
+/// Writes the class with the given version, removing all attributes not yet defined in that version.
+///
+/// Returns the names of the attributes that were present and got removed, so that the caller can warn about the lost data.
+/// The `Code` and `BootstrapMethods` attributes are never touched: this means that code using instructions not available
+/// in that version (like `invokedynamic`) is still written as is. The stack map frames are only removed when going from
+/// version 50 or later to an earlier one, as the old verifier doesn't use them.
+pub(crate) fn write_targeting(class_writer: &mut impl ClassWrite, class: &ClassFile, version: Version) -> Result<Vec<&'static JavaStr>> {
+	let mut class = class.clone();
+
+	let mut removed = Vec::new();
+	if class.version >= Version::V1_6 && version < Version::V1_6 {
+		let mut had_frames = false;
+		let entries = class.methods.iter_mut()
+			.filter_map(|method| method.code.as_mut())
+			.flat_map(|code| &mut code.instructions);
+		for entry in entries {
+			had_frames |= entry.frame.take().is_some();
+		}
+		if had_frames {
+			removed.push(attribute::STACK_MAP_TABLE);
+		}
+	}
 
-	Ok(())
+	class.version = version;
+
+	for (name, min_version) in ATTRIBUTE_MIN_VERSIONS {
+		if version < min_version && class.remove_attributes(|attribute| attribute == name) {
+			removed.push(name);
+		}
+	}
+
+	write(class_writer, &class, WriteOptions::default())?;
+
+	Ok(removed)
 }
 
 fn write_field<'a, 'b: 'a>(writer: &mut impl ClassWrite, field: &'b Field, pool: &mut PoolWrite<'a>) -> Result<()> {
@@ -320,7 +403,7 @@ fn write_field<'a, 'b: 'a>(writer: &mut impl ClassWrite, field: &'b Field, pool:
 	Ok(())
 }
 
-fn write_method<'a, 'b: 'a>(writer: &mut impl ClassWrite, method: &'b Method, pool: &mut PoolWrite<'a>) -> Result<()> {
+fn write_method<'a, 'b: 'a>(writer: &mut impl ClassWrite, method: &'b Method, version: Version, options: WriteOptions, pool: &mut PoolWrite<'a>) -> Result<()> {
 	writer.write_u16(method.access.into())?;
 	writer.write_u16(pool.put_utf8(method.name.as_inner())?)?;
 	writer.write_u16(pool.put_utf8(method.descriptor.as_inner())?)?;
@@ -341,7 +424,7 @@ fn write_method<'a, 'b: 'a>(writer: &mut impl ClassWrite, method: &'b Method, po
 	if let Some(code) = &method.code {
 		attribute_count += 1;
 		write_attribute(&mut buffer, pool, attribute::CODE, |w, pool| {
-			write_code(w, code, pool)
+			write_code(w, code, version, options, pool)
 				.with_context(|| anyhow!("failed to write `Code` attribute of method {:?} {:?}", method.name, method.descriptor))
 		})?;
 	}
@@ -583,6 +666,136 @@ fn switch_helper<'a, 'b: 'a>(w: &mut Vec<u8>,
 	w.write_i32(branch)
 }
 
+/// Checks that code with branches or exception handlers written for version 50 or later has stack map frames.
+///
+/// Starting with version 50, the JVM uses the type checking verifier, which needs a frame at each branch target and exception
+/// handler. Without any frames, the class fails with a `VerifyError` once loaded (version 50 still falls back to the old
+/// verifier, but later versions don't). Frames aren't computed here, so such code must come with the frames, for example
+/// from reading it with the [`stack_map_table`][crate::visitor::method::code::CodeInterests::stack_map_table] interest.
+///
+/// This is skipped with [`WriteOptions::skip_stack_map_check`].
+fn require_stack_map_for_modern_versions(code: &Code, frames: &[(u16, &StackMapData)], version: Version) -> Result<()> {
+	if version < Version::V1_6 || !frames.is_empty() {
+		return Ok(());
+	}
+
+	let branches = code.instructions.iter().any(|entry| matches!(entry.instruction,
+		Instruction::IfEq(_) | Instruction::IfNe(_) | Instruction::IfLt(_) | Instruction::IfGe(_) | Instruction::IfGt(_) | Instruction::IfLe(_) |
+		Instruction::IfICmpEq(_) | Instruction::IfICmpNe(_) | Instruction::IfICmpLt(_) | Instruction::IfICmpGe(_) | Instruction::IfICmpGt(_) | Instruction::IfICmpLe(_) |
+		Instruction::IfACmpEq(_) | Instruction::IfACmpNe(_) | Instruction::IfNull(_) | Instruction::IfNonNull(_) |
+		Instruction::Goto(_) | Instruction::Jsr(_) | Instruction::TableSwitch { .. } | Instruction::LookupSwitch { .. }
+	));
+	if branches || !code.exception_table.is_empty() {
+		bail!("code with branches or exception handlers needs stack map frames for class file version {version:?} (50 or later), \
+			but there are none: the class would fail verification");
+	}
+
+	Ok(())
+}
+
+fn write_verification_type_info<'a, 'b: 'a>(w: &mut Vec<u8>, pool: &mut PoolWrite<'a>, labels: &Labels, info: &'b VerificationTypeInfo) -> Result<()> {
+	match info {
+		VerificationTypeInfo::Top => w.write_u8(0),
+		VerificationTypeInfo::Integer => w.write_u8(1),
+		VerificationTypeInfo::Float => w.write_u8(2),
+		VerificationTypeInfo::Double => w.write_u8(3),
+		VerificationTypeInfo::Long => w.write_u8(4),
+		VerificationTypeInfo::Null => w.write_u8(5),
+		VerificationTypeInfo::UninitializedThis => w.write_u8(6),
+		VerificationTypeInfo::Object(class) => {
+			w.write_u8(7)?;
+			w.write_u16(pool.put_class(class)?)
+		},
+		VerificationTypeInfo::Uninitialized(label) => {
+			w.write_u8(8)?;
+			w.write_u16(labels.try_get(label)?)
+		},
+	}
+}
+
+fn write_verification_type_infos<'a, 'b: 'a>(w: &mut Vec<u8>, pool: &mut PoolWrite<'a>, labels: &Labels, infos: &'b [VerificationTypeInfo]) -> Result<()> {
+	w.write_slice(infos,
+		|w, len| w.write_usize_as_u16(len).context("too many verification types in stack map frame"),
+		|w, info| write_verification_type_info(w, pool, labels, info)
+	)
+}
+
+/// Writes the content of the `StackMapTable` attribute, choosing the shortest encoding for each frame.
+fn write_stack_map_table<'a, 'b: 'a>(w: &mut Vec<u8>, pool: &mut PoolWrite<'a>, labels: &Labels, frames: &[(u16, &'b StackMapData)]) -> Result<()> {
+	w.write_usize_as_u16(frames.len()).context("too many stack map frames")?;
+
+	let mut previous: Option<u16> = None;
+	for &(offset, frame) in frames {
+		let offset_delta = match previous {
+			None => offset,
+			Some(previous) if offset > previous => offset - previous - 1,
+			Some(previous) => bail!("stack map frame at offset {offset} doesn't come after the frame at offset {previous}"),
+		};
+		previous = Some(offset);
+
+		match frame {
+			StackMapData::Same if offset_delta <= 63 => w.write_u8(offset_delta as u8)?,
+			StackMapData::Same => {
+				w.write_u8(251)?;
+				w.write_u16(offset_delta)?;
+			},
+			StackMapData::SameLocals1StackItem { stack } => {
+				if offset_delta <= 63 {
+					w.write_u8(64 + offset_delta as u8)?;
+				} else {
+					w.write_u8(247)?;
+					w.write_u16(offset_delta)?;
+				}
+				write_verification_type_info(w, pool, labels, stack)?;
+			},
+			&StackMapData::Chop { k } => {
+				if !(1..=3).contains(&k) {
+					bail!("can only chop 1 to 3 locals in a stack map frame, got {k}");
+				}
+				w.write_u8(251 - k)?;
+				w.write_u16(offset_delta)?;
+			},
+			StackMapData::Append { locals } => {
+				if !(1..=3).contains(&locals.len()) {
+					bail!("can only append 1 to 3 locals in a stack map frame, got {}", locals.len());
+				}
+				w.write_u8(251 + locals.len() as u8)?;
+				w.write_u16(offset_delta)?;
+				for local in locals {
+					write_verification_type_info(w, pool, labels, local)?;
+				}
+			},
+			StackMapData::Full { locals, stack } => {
+				w.write_u8(255)?;
+				w.write_u16(offset_delta)?;
+				write_verification_type_infos(w, pool, labels, locals)?;
+				write_verification_type_infos(w, pool, labels, stack)?;
+			},
+		}
+	}
+
+	Ok(())
+}
+
+/// Writes the content of the `StackMap` attribute, used by CLDC for class files before version 50.
+///
+/// This format only knows full frames, with absolute offsets. As the other kinds of frames are relative to the previous
+/// frame, they can't be written here.
+fn write_stack_map<'a, 'b: 'a>(w: &mut Vec<u8>, pool: &mut PoolWrite<'a>, labels: &Labels, frames: &[(u16, &'b StackMapData)]) -> Result<()> {
+	w.write_usize_as_u16(frames.len()).context("too many stack map frames")?;
+
+	for &(offset, frame) in frames {
+		let StackMapData::Full { locals, stack } = frame else {
+			bail!("the `StackMap` attribute can only contain full frames, got {frame:?} at offset {offset}");
+		};
+		w.write_u16(offset)?;
+		write_verification_type_infos(w, pool, labels, locals)?;
+		write_verification_type_infos(w, pool, labels, stack)?;
+	}
+
+	Ok(())
+}
+
 /// Writes the content of the `Code` attribute to the writer.
 ///
 /// # Branch offset algorithm
@@ -634,7 +847,11 @@ fn switch_helper<'a, 'b: 'a>(w: &mut Vec<u8>,
 /// We then try this writing out as often as necessary, each time adding in the instruction the branch size exceeded the [`i16`]
 /// bounds.
 ///
-fn write_code<'a, 'b: 'a>(writer: &mut impl ClassWrite, code: &'b Code, pool: &mut PoolWrite<'a>) -> Result<()> {
+fn write_code<'a, 'b: 'a>(writer: &mut impl ClassWrite, code: &'b Code, version: Version, options: WriteOptions, pool: &mut PoolWrite<'a>) -> Result<()> {
+	if let Some(raw_code) = &code.raw {
+		return write_raw_code(writer, raw_code, pool);
+	}
+
 	if let (Some(max_stack), Some(max_locals)) = (code.max_stack, code.max_locals) {
 		writer.write_u16(max_stack)?;
 		writer.write_u16(max_locals)?;
@@ -1077,6 +1294,7 @@ fn write_code<'a, 'b: 'a>(writer: &mut impl ClassWrite, code: &'b Code, pool: &m
 				wide.insert(unwritten.instruction_index);
 
 				labels.next_attempt();
+				frames.clear();
 				w = Vec::with_capacity(w.len());
 				continue 'a;
 			}
@@ -1085,11 +1303,15 @@ fn write_code<'a, 'b: 'a>(writer: &mut impl ClassWrite, code: &'b Code, pool: &m
 		break;
 	}
 
-	let code_length = w.len() as u32;
-	if code_length == 0 || code_length > u16::MAX as u32 {
-		bail!("`code_length` must be greater than zero and less than 65536, got {code_length:?}");
+	if w.is_empty() {
+		bail!("`code_length` must be greater than zero, got 0");
 	}
-	writer.write_u32(code_length)?;
+	let code_length = u16::try_from(w.len())
+		.with_context(|| anyhow!("`code_length` must be less than 65536, got {}", w.len()))?;
+
+	check_labels(code, &labels, code_length)?;
+
+	writer.write_u32(code_length.into())?;
 	writer.write_u8_slice(&w)?;
 
 	writer.write_slice(&code.exception_table,
@@ -1106,17 +1328,36 @@ fn write_code<'a, 'b: 'a>(writer: &mut impl ClassWrite, code: &'b Code, pool: &m
 	let mut attribute_count = 0;
 	let mut buffer = Vec::new();
 
+	if !options.skip_stack_map_check {
+		require_stack_map_for_modern_versions(code, &frames, version)?;
+	}
+
 	if !frames.is_empty() {
-		// TODO: write stack map table
+		attribute_count += 1;
+		if version >= Version::V1_6 {
+			write_attribute(&mut buffer, pool, attribute::STACK_MAP_TABLE, |w, pool| {
+				write_stack_map_table(w, pool, &labels, &frames)
+			})?;
+		} else {
+			write_attribute(&mut buffer, pool, attribute::STACK_MAP, |w, pool| {
+				write_stack_map(w, pool, &labels, &frames)
+			})?;
+		}
 	}
 
 	if let Some(line_number_table) = &code.line_numbers {
 		attribute_count += 1;
+		// The entries are written sorted by their offset, keeping the order of entries with the same offset.
+		let mut line_number_table = line_number_table.iter()
+			.map(|&(ref start, line_number)| Ok((labels.try_get(start)?, line_number)))
+			.collect::<Result<Vec<_>>>()?;
+		line_number_table.sort_by_key(|&(start, _)| start);
+
 		write_attribute(&mut buffer, pool, attribute::LINE_NUMBER_TABLE, |w, _| {
-			w.write_slice(line_number_table,
+			w.write_slice(&line_number_table,
 				|w, len| w.write_usize_as_u16(len), // TODO: .context
-				|w, &(ref start, line_number)| {
-					w.write_u16(labels.try_get(start)?)?;
+				|w, &(start, line_number)| {
+					w.write_u16(start)?;
 					w.write_u16(line_number)
 				}
 			)
@@ -1187,6 +1428,187 @@ fn write_code<'a, 'b: 'a>(writer: &mut impl ClassWrite, code: &'b Code, pool: &m
 	Ok(())
 }
 
+/// Writes the [`RawCode`] as it was read, with only the constant pool indices changed to the ones of the pool written.
+/// Returns the constants loaded by the `ldc` instructions of the raw code.
+fn raw_ldc_loadables(raw_code: &RawCode) -> impl Iterator<Item=&Loadable> {
+	raw_code.constants.iter()
+		.filter(|(offset, _)| {
+			let opcode_pos = (*offset as usize).checked_sub(1);
+			opcode_pos.and_then(|opcode_pos| raw_code.bytecode.get(opcode_pos)) == Some(&opcode::LDC)
+		})
+		.filter_map(|(_, constant)| match constant {
+			RawConstant::Loadable(loadable) => Some(loadable),
+			_ => None,
+		})
+}
+
+fn write_raw_code<'a, 'b: 'a>(writer: &mut impl ClassWrite, raw_code: &'b RawCode, pool: &mut PoolWrite<'a>) -> Result<()> {
+	writer.write_u16(raw_code.max_stack)?;
+	writer.write_u16(raw_code.max_locals)?;
+
+	let mut bytecode = raw_code.bytecode.clone();
+	for (offset, constant) in &raw_code.constants {
+		let index = match constant {
+			RawConstant::Class(class) => pool.put_class(class)?,
+			RawConstant::Field(field_ref) => pool.put_field_ref(field_ref)?,
+			RawConstant::Method(method_ref, is_interface) => pool.put_method_ref_or_interface_method_ref((method_ref, *is_interface))?,
+			RawConstant::InvokeDynamic(invoke_dynamic) => pool.put_invoke_dynamic(invoke_dynamic)?,
+			RawConstant::Loadable(loadable) => pool.put_loadable(loadable)?,
+		};
+
+		let offset = *offset as usize;
+		let opcode = offset.checked_sub(1).and_then(|opcode_pos| bytecode.get(opcode_pos).copied());
+		if opcode == Some(opcode::LDC) {
+			let index = u8::try_from(index)
+				.with_context(|| anyhow!("constant pool index {index} of `ldc` at bytecode offset {} doesn't fit into a byte", offset - 1))?;
+			bytecode[offset] = index;
+		} else {
+			bytecode.get_mut(offset..offset + 2)
+				.with_context(|| anyhow!("constant pool index at bytecode offset {offset} is out of bounds"))?
+				.copy_from_slice(&index.to_be_bytes());
+		}
+	}
+
+	writer.write_usize_as_u32(bytecode.len())?;
+	writer.write_u8_slice(&bytecode)?;
+
+	writer.write_slice(&raw_code.exception_table,
+		|w, len| w.write_usize_as_u16(len).context("too many exception table entries"),
+		|w, exception| {
+			w.write_u16(exception.start)?;
+			w.write_u16(exception.end)?;
+			w.write_u16(exception.handler)?;
+			w.write_u16(pool.put_optional(exception.catch.as_ref(), PoolWrite::put_class)?)
+		}
+	)?;
+
+	// We write the attributes into a buffer and count them.
+	let mut attribute_count = 0;
+	let mut buffer = Vec::new();
+
+	if let Some(stack_map_table) = &raw_code.stack_map_table {
+		attribute_count += 1;
+		write_attribute(&mut buffer, pool, attribute::STACK_MAP_TABLE, |w, pool| {
+			let mut data = stack_map_table.data.clone();
+			for (offset, class) in &stack_map_table.classes {
+				data.get_mut(*offset..*offset + 2)
+					.with_context(|| anyhow!("constant pool index at offset {offset} of the stack map table is out of bounds"))?
+					.copy_from_slice(&pool.put_class(class)?.to_be_bytes());
+			}
+			w.write_u8_slice(&data)
+		})?;
+	}
+
+	if let Some(line_number_table) = &raw_code.line_numbers {
+		attribute_count += 1;
+		write_attribute(&mut buffer, pool, attribute::LINE_NUMBER_TABLE, |w, _| {
+			w.write_slice(line_number_table,
+				|w, len| w.write_usize_as_u16(len).context("too many line number table entries"),
+				|w, &(start, line_number)| {
+					w.write_u16(start)?;
+					w.write_u16(line_number)
+				}
+			)
+		})?;
+	}
+
+	if let Some(local_variables) = &raw_code.local_variables {
+		let descriptors: Vec<_> = local_variables.iter()
+			.filter_map(|lv| lv.descriptor.as_ref().map(|descriptor| (lv, descriptor.as_inner())))
+			.collect();
+		let signatures: Vec<_> = local_variables.iter()
+			.filter_map(|lv| lv.signature.as_ref().map(|signature| (lv, signature.as_inner())))
+			.collect();
+
+		for (name, table) in [(attribute::LOCAL_VARIABLE_TABLE, descriptors), (attribute::LOCAL_VARIABLE_TYPE_TABLE, signatures)] {
+			if !table.is_empty() {
+				attribute_count += 1;
+				write_attribute(&mut buffer, pool, name, |w, pool| {
+					w.write_slice(&table,
+						|w, len| w.write_usize_as_u16(len).with_context(|| anyhow!("too many entries in {name:?}")),
+						|w, &(lv, descriptor_or_signature)| {
+							w.write_u16(lv.start)?;
+							w.write_u16(lv.length)?;
+							w.write_u16(pool.put_utf8(lv.name.as_inner())?)?;
+							w.write_u16(pool.put_utf8(descriptor_or_signature)?)?;
+							w.write_u16(lv.index.index)
+						}
+					)
+				})?;
+			}
+		}
+	}
+
+	writer.write_usize_as_u16(attribute_count).context("too many attributes on code")?;
+	writer.write_u8_slice(&buffer)?;
+
+	Ok(())
+}
+
+/// Checks that all labels used by the code resolve to bytecode offsets inside the code.
+///
+/// Jump targets, handlers, line number starts and the labels of type annotations must point at an instruction, while the
+/// ends of exception ranges and local variable ranges may also point at the end of the code.
+fn check_labels(code: &Code, labels: &Labels, code_length: u16) -> Result<()> {
+	for entry in &code.instructions {
+		for target in entry.instruction.jump_targets() {
+			labels.try_get_before(target, code_length)
+				.with_context(|| anyhow!("invalid jump target of instruction {:?}", entry.instruction))?;
+		}
+	}
+
+	for exception in &code.exception_table {
+		(|| -> Result<()> {
+			let start = labels.try_get_before(&exception.start, code_length)?;
+			let end = labels.try_get_up_to(&exception.end, code_length)?;
+			if end <= start {
+				bail!("exception range from offset {start} to offset {end} is empty");
+			}
+			labels.try_get_before(&exception.handler, code_length)?;
+			Ok(())
+		})()
+			.with_context(|| anyhow!("invalid exception table entry {exception:?}"))?;
+	}
+
+	for (start, line_number) in code.line_numbers.iter().flatten() {
+		labels.try_get_before(start, code_length)
+			.with_context(|| anyhow!("invalid start of line number {line_number}"))?;
+	}
+
+	for lv in code.local_variables.iter().flatten() {
+		labels.check_range(&lv.range, code_length)
+			.with_context(|| anyhow!("invalid range of local variable {:?}", lv.name))?;
+	}
+
+	for type_annotation in code.runtime_visible_type_annotations.iter().chain(&code.runtime_invisible_type_annotations) {
+		(|| -> Result<()> {
+			match &type_annotation.type_reference {
+				TargetInfoCode::LocalVariable { table } | TargetInfoCode::ResourceVariable { table } => {
+					for (range, _) in table {
+						labels.check_range(range, code_length)?;
+					}
+				},
+				TargetInfoCode::ExceptionParameter { .. } => {},
+				TargetInfoCode::InstanceOf(label) |
+				TargetInfoCode::New(label) |
+				TargetInfoCode::ConstructorReference(label) |
+				TargetInfoCode::MethodReference(label) |
+				TargetInfoCode::Cast { label, .. } |
+				TargetInfoCode::ConstructorInvocationTypeArgument { label, .. } |
+				TargetInfoCode::MethodInvocationTypeArgument { label, .. } |
+				TargetInfoCode::ConstructorReferenceTypeArgument { label, .. } |
+				TargetInfoCode::MethodReferenceTypeArgument { label, .. } => {
+					labels.try_get_before(label, code_length)?;
+				},
+			}
+			Ok(())
+		})()
+			.with_context(|| anyhow!("invalid type annotation {:?}", type_annotation.annotation.annotation_type))?;
+	}
+
+	Ok(())
+}
+
 fn write_record_component<'a: 'b, 'b>(writer: &mut impl ClassWrite, record_component: &'a RecordComponent, pool: &mut PoolWrite<'b>) -> Result<()> {
 	writer.write_u16(pool.put_utf8(record_component.name.as_inner())?)?;
 	writer.write_u16(pool.put_utf8(record_component.descriptor.as_inner())?)?;
@@ -1579,4 +2001,218 @@ fn write_module<'a, 'b: 'a>(writer: &mut impl ClassWrite, pool: &mut PoolWrite<'
 			)
 		}
 	)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::{Context, Result};
+	use pretty_assertions::assert_eq;
+	use crate::simple_class_writer::labels::Labels;
+	use java_string::{JavaStr, JavaString};
+	use crate::tree::class::{ClassAccess, ClassFile, ClassName};
+	use crate::tree::field::{Field, FieldAccess, FieldDescriptor, FieldName};
+	use crate::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName};
+	use crate::tree::method::code::{Code, Instruction, InstructionListEntry, Label, LabelRange, Loadable, LvIndex};
+	use crate::tree::method::raw_code::{RawCode, RawConstant};
+	use crate::tree::version::Version;
+	use crate::visitor::method::code::{StackMapData, VerificationTypeInfo};
+	use crate::WriteOptions;
+
+	#[test]
+	fn write_targeting_java_8() -> Result<()> {
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("simple_class_writer/test/Nest$Inner.class")))?;
+		assert_eq!(class.version, Version::V11);
+		assert!(class.nest_host_class.is_some());
+
+		let mut bytes = Vec::new();
+		let removed = super::write_targeting(&mut bytes, &class, Version::V1_8)?;
+		assert_eq!(removed, ["NestHost"]);
+
+		let written = crate::read_class(&mut Cursor::new(bytes))?;
+		assert_eq!(written.version, Version::V1_8);
+		assert_eq!(written.nest_host_class, None);
+		assert_eq!(written.inner_classes, class.inner_classes);
+		assert_eq!(written.source_file, class.source_file);
+		assert_eq!(written.methods, class.methods);
+
+		// nothing to remove when targeting the same version
+		let mut bytes = Vec::new();
+		assert_eq!(super::write_targeting(&mut bytes, &class, Version::V11)?, Vec::<&str>::new());
+		assert_eq!(crate::read_class(&mut Cursor::new(bytes))?, class);
+
+		Ok(())
+	}
+
+	#[test]
+	fn write_body_only_once() -> Result<()> {
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("simple_class_writer/test/Nest$Inner.class")))?;
+
+		super::WRITE_BODY_CALLS.with(|calls| calls.set(0));
+		let mut bytes = Vec::new();
+		crate::write_class(&mut bytes, &class)?;
+		assert_eq!(super::WRITE_BODY_CALLS.with(|calls| calls.get()), 1);
+
+		assert_eq!(crate::read_class(&mut Cursor::new(bytes))?, class);
+
+		Ok(())
+	}
+
+	#[test]
+	fn jump_to_end_of_code() -> Result<()> {
+		let mut class = crate::read_class(&mut Cursor::new(include_bytes!("simple_class_writer/test/Nest$Inner.class")))?;
+		let method = class.methods.iter_mut().find(|method| method.code.is_some()).context("no method with code")?;
+
+		// `goto L1` is three bytes long, and L1 is placed right after it, at the end of the code
+		method.code = Some(Code {
+			max_stack: Some(0),
+			max_locals: Some(1),
+			instructions: vec![
+				InstructionListEntry { label: Some(Label { id: 0 }), frame: None, offset: None, instruction: Instruction::Goto(Label { id: 1 }) },
+			],
+			last_label: Some(Label { id: 1 }),
+			..Code::default()
+		});
+
+		let error = crate::write_class(&mut Vec::new(), &class).err().context("writing succeeded")?;
+		assert!(format!("{error:?}").contains("label L1 at offset 3 is not before the end of the code, code_length is 3"), "{error:?}");
+
+		Ok(())
+	}
+
+	#[test]
+	fn raw_code_ldc_with_large_constant_pool() -> Result<()> {
+		let mut class = crate::read_class(&mut Cursor::new(include_bytes!("simple_class_writer/test/Nest$Inner.class")))?;
+
+		// the names of the fields are put into the constant pool before the code of the methods is written
+		for i in 0..300 {
+			let name = FieldName::try_from(JavaString::from(format!("field{i}")))?;
+			let descriptor = FieldDescriptor::try_from(JavaStr::from_str("I"))?;
+			class.fields.push(Field::new(FieldAccess::from(0), name, descriptor));
+		}
+
+		let method = class.methods.iter_mut().find(|method| method.code.is_some()).context("no method with code")?;
+		method.code = Some(Code {
+			raw: Some(RawCode {
+				max_stack: 1,
+				max_locals: 1,
+				// ldc #0, areturn
+				bytecode: vec![0x12, 0x00, 0xb0],
+				constants: vec![(1, RawConstant::Loadable(Loadable::String(JavaString::from("hello"))))],
+				exception_table: Vec::new(),
+				stack_map_table: None,
+				line_numbers: None,
+				local_variables: None,
+			}),
+			..Code::default()
+		});
+
+		let mut bytes = Vec::new();
+		crate::write_class(&mut bytes, &class)?;
+
+		let class = crate::read_class(&mut Cursor::new(bytes))?;
+		let method = class.methods.iter().find(|method| method.code.is_some()).context("no method with code")?;
+		let instructions: Vec<_> = method.code.iter()
+			.flat_map(|code| &code.instructions)
+			.map(|entry| &entry.instruction)
+			.collect();
+		assert_eq!(instructions, [&Instruction::Ldc(Loadable::String(JavaString::from("hello"))), &Instruction::AReturn]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn range_after_end_of_code() -> Result<()> {
+		let mut labels = Labels::new();
+		labels.add_opcode_pos_label(Label { id: 0 }, 0);
+		labels.add_opcode_pos_label(Label { id: 5 }, 700);
+
+		let range = LabelRange { start: Label { id: 0 }, end: Label { id: 5 } };
+		let error = labels.check_range(&range, 512).err().context("check succeeded")?;
+		assert_eq!(error.to_string(), "label L5 at offset 700 exceeds code_length 512");
+
+		let range = LabelRange { start: Label { id: 0 }, end: Label { id: 6 } };
+		let error = labels.check_range(&range, 512).err().context("check succeeded")?;
+		assert_eq!(error.to_string(), "label L6 is not placed on any instruction");
+
+		Ok(())
+	}
+
+	/// A class with `static void branch(int i) { if (i == 0) {} }`, with the frame given at the target of the branch.
+	fn branching_class(version: Version, frame: Option<StackMapData>) -> Result<ClassFile> {
+		let name = ClassName::try_from_inner("Branching".into())?;
+		let mut class = ClassFile::new(version, ClassAccess::from(0x0021), name, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+
+		let name = MethodName::try_from_inner("branch".into())?;
+		let desc = MethodDescriptor::try_from_inner("(I)V".into())?;
+		let mut method = Method::new(MethodAccess::from(0x0008), name, desc);
+		method.code = Some(Code {
+			max_stack: Some(1),
+			max_locals: Some(1),
+			instructions: vec![
+				InstructionListEntry { label: None, frame: None, offset: None, instruction: Instruction::ILoad(LvIndex { index: 0 }) },
+				InstructionListEntry { label: None, frame: None, offset: None, instruction: Instruction::IfEq(Label { id: 0 }) },
+				InstructionListEntry { label: Some(Label { id: 0 }), frame, offset: None, instruction: Instruction::Return },
+			],
+			..Code::default()
+		});
+		class.methods.push(method);
+
+		Ok(class)
+	}
+
+	#[test]
+	fn branching_method_without_frames() -> Result<()> {
+		let class = branching_class(Version::V1_8, None)?;
+
+		let error = crate::write_class(&mut Vec::new(), &class).err().context("writing succeeded")?;
+		assert!(format!("{error:?}").contains("code with branches or exception handlers needs stack map frames"), "{error:?}");
+
+		// the old verifier doesn't need any frames
+		crate::write_class(&mut Vec::new(), &branching_class(Version::V1_5, None)?)?;
+
+		// the check can be skipped
+		let mut bytes = Vec::new();
+		crate::write_class_with_options(&mut bytes, &class, WriteOptions { skip_stack_map_check: true })?;
+		assert_eq!(crate::read_class(&mut Cursor::new(bytes))?, class);
+
+		Ok(())
+	}
+
+	#[test]
+	fn write_stack_map_table() -> Result<()> {
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("tree/test/StripDebugInfo.class")))?;
+		let frames = class.methods.iter()
+			.filter_map(|method| method.code.as_ref())
+			.flat_map(|code| &code.instructions)
+			.filter(|entry| entry.frame.is_some())
+			.count();
+		assert_eq!(frames, 3);
+
+		let mut bytes = Vec::new();
+		crate::write_class(&mut bytes, &class)?;
+		assert_eq!(crate::read_class(&mut Cursor::new(bytes))?, class);
+
+		Ok(())
+	}
+
+	#[test]
+	fn write_stack_map() -> Result<()> {
+		let frame = StackMapData::Full { locals: vec![VerificationTypeInfo::Integer], stack: Vec::new() };
+		let class = branching_class(Version::V1_4, Some(frame.clone()))?;
+
+		let mut bytes = Vec::new();
+		crate::write_class(&mut bytes, &class)?;
+		let written = crate::read_class(&mut Cursor::new(bytes))?;
+		let code = written.methods[0].code.as_ref().context("no code")?;
+		let frames: Vec<_> = code.instructions.iter().map(|entry| entry.frame.as_ref()).collect();
+		assert_eq!(frames, [None, None, Some(&frame)]);
+
+		// only full frames can be written to the `StackMap` attribute
+		let class = branching_class(Version::V1_4, Some(StackMapData::Same))?;
+		let error = crate::write_class(&mut Vec::new(), &class).err().context("writing succeeded")?;
+		assert!(format!("{error:?}").contains("the `StackMap` attribute can only contain full frames"), "{error:?}");
+
+		Ok(())
+	}
+}
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use crate::tree::method::code::{Label, LabelRange};
 
 /// A helper struct for writing [`Label`]s as bytecode offsets.
@@ -41,6 +41,36 @@ impl Labels {
 		Ok((start, end - start))
 	}
 
+	/// Gets the bytecode offset of a [`Label`] that must point at an instruction, so it must be less than `code_length`.
+	pub(crate) fn try_get_before(&self, target: &Label, code_length: u16) -> Result<u16> {
+		let offset = self.get(target).with_context(|| anyhow!("label L{} is not placed on any instruction", target.id))?;
+		if offset >= code_length {
+			bail!("label L{} at offset {offset} is not before the end of the code, code_length is {code_length}", target.id);
+		}
+		Ok(offset)
+	}
+
+	/// Gets the bytecode offset of a [`Label`] that may also point at the end of the code, so it must be less than or
+	/// equal to `code_length`.
+	pub(crate) fn try_get_up_to(&self, target: &Label, code_length: u16) -> Result<u16> {
+		let offset = self.get(target).with_context(|| anyhow!("label L{} is not placed on any instruction", target.id))?;
+		if offset > code_length {
+			bail!("label L{} at offset {offset} exceeds code_length {code_length}", target.id);
+		}
+		Ok(offset)
+	}
+
+	/// Checks that the start of the [`LabelRange`] points at an instruction, and that the end is not before the start and
+	/// not after the end of the code.
+	pub(crate) fn check_range(&self, range: &LabelRange, code_length: u16) -> Result<()> {
+		let start = self.try_get_before(&range.start, code_length)?;
+		let end = self.try_get_up_to(&range.end, code_length)?;
+		if end < start {
+			bail!("range from label L{} at offset {start} to label L{} at offset {end} ends before it starts", range.start.id, range.end.id);
+		}
+		Ok(())
+	}
+
 	pub(crate) fn next_attempt(&mut self) {
 		self.index_to_offset = HashMap::with_capacity(self.index_to_offset.len());
 		self.labels = HashMap::with_capacity(self.labels.len());
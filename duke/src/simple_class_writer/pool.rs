@@ -206,11 +206,11 @@ impl PoolWrite<'_> {
 	}
 
 	/// Writes the constant pool to the specified writer. The first thing written is an `u16` specifying the size of the constant pool.
-	pub(crate) fn write(self, writer: &mut impl ClassWrite) -> Result<()> {
+	pub(crate) fn write(&self, writer: &mut impl ClassWrite) -> Result<()> {
 		writer.write_u16(self.count)?;
 
-		for entry in self.inner {
-			match entry {
+		for entry in &self.inner {
+			match *entry {
 				PoolEntry::Utf8 { string } => {
 					writer.write_u8(pool::UTF8)?;
 					let vec = jstring::from_string_to_vec(string);
@@ -0,0 +1,10 @@
+// compile with `javac --release 11 Nest.java`
+class Nest {
+	private int value;
+
+	class Inner {
+		int get() {
+			return value;
+		}
+	}
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use std::fmt::{Debug, Formatter};
-use java_string::JavaString;
+use java_string::{JavaStr, JavaString};
 use crate::tree::descriptor::ReturnDescriptor;
 use crate::tree::field::FieldDescriptor;
 use crate::visitor::annotation::{AnnotationsVisitor, NamedElementValuesVisitor, UnnamedElementValuesVisitor, UnnamedElementValueVisitor};
@@ -19,6 +19,15 @@ impl Annotation {
 		}
 	}
 
+	/// Gets the value of the element `name`.
+	///
+	/// Note that elements with their default value are usually not stored in the annotation.
+	pub fn get(&self, name: &str) -> Option<&ElementValue> {
+		self.element_value_pairs.iter()
+			.find(|pair| pair.name == name)
+			.map(|pair| &pair.value)
+	}
+
 	pub fn accept<A: AnnotationsVisitor>(self, visitor: A) -> Result<A> {
 		let (visitor, mut pairs_visitor) = visitor.visit_annotation(self.annotation_type)?;
 
@@ -65,6 +74,110 @@ fn accept_element_values_unnamed<A: UnnamedElementValuesVisitor>(mut outer: A, e
 }
 
 impl ElementValue {
+	/// Gets the value if it's a `String` constant.
+	pub fn as_string(&self) -> Option<&JavaStr> {
+		match self {
+			ElementValue::Object(Object::String(string)) => Some(string),
+			_ => None,
+		}
+	}
+
+	/// Gets the value if it's a `byte` constant.
+	pub fn as_byte(&self) -> Option<i8> {
+		match self {
+			ElementValue::Object(Object::Byte(byte)) => Some(*byte),
+			_ => None,
+		}
+	}
+
+	/// Gets the value if it's a `char` constant, as a UTF-16 code unit.
+	pub fn as_char(&self) -> Option<u16> {
+		match self {
+			ElementValue::Object(Object::Char(char)) => Some(*char),
+			_ => None,
+		}
+	}
+
+	/// Gets the value if it's a `double` constant.
+	pub fn as_double(&self) -> Option<f64> {
+		match self {
+			ElementValue::Object(Object::Double(double)) => Some(*double),
+			_ => None,
+		}
+	}
+
+	/// Gets the value if it's a `float` constant.
+	pub fn as_float(&self) -> Option<f32> {
+		match self {
+			ElementValue::Object(Object::Float(float)) => Some(*float),
+			_ => None,
+		}
+	}
+
+	/// Gets the value if it's an `int` constant.
+	pub fn as_int(&self) -> Option<i32> {
+		match self {
+			ElementValue::Object(Object::Integer(int)) => Some(*int),
+			_ => None,
+		}
+	}
+
+	/// Gets the value if it's a `long` constant.
+	pub fn as_long(&self) -> Option<i64> {
+		match self {
+			ElementValue::Object(Object::Long(long)) => Some(*long),
+			_ => None,
+		}
+	}
+
+	/// Gets the value if it's a `short` constant.
+	pub fn as_short(&self) -> Option<i16> {
+		match self {
+			ElementValue::Object(Object::Short(short)) => Some(*short),
+			_ => None,
+		}
+	}
+
+	/// Gets the value if it's a `boolean` constant.
+	pub fn as_boolean(&self) -> Option<bool> {
+		match self {
+			ElementValue::Object(Object::Boolean(boolean)) => Some(*boolean),
+			_ => None,
+		}
+	}
+
+	/// Gets the type and the name of the constant if it's an enum constant.
+	pub fn as_enum(&self) -> Option<(&FieldDescriptor, &JavaStr)> {
+		match self {
+			ElementValue::Enum { type_name, const_name } => Some((type_name, const_name)),
+			_ => None,
+		}
+	}
+
+	/// Gets the class if it's a class literal.
+	pub fn as_class(&self) -> Option<&ReturnDescriptor> {
+		match self {
+			ElementValue::Class(class) => Some(class),
+			_ => None,
+		}
+	}
+
+	/// Gets the annotation if it's a nested annotation.
+	pub fn as_annotation(&self) -> Option<&Annotation> {
+		match self {
+			ElementValue::AnnotationInterface(annotation) => Some(annotation),
+			_ => None,
+		}
+	}
+
+	/// Gets the elements if it's an array.
+	pub fn as_array(&self) -> Option<&[ElementValue]> {
+		match self {
+			ElementValue::ArrayType(element_values) => Some(element_values),
+			_ => None,
+		}
+	}
+
 	pub fn accept<A: UnnamedElementValueVisitor>(self, mut outer: A) -> Result<A> {
 		match self {
 			ElementValue::Object(object) => {
@@ -134,4 +247,24 @@ pub enum Object {
 	Short(i16),
 	Boolean(bool),
 	String(JavaString),
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod testing {
+	use pretty_assertions::assert_eq;
+	use crate::tree::annotation::{ElementValue, Object};
+
+	#[test]
+	fn primitive_accessors() {
+		assert_eq!(ElementValue::Object(Object::Byte(-1)).as_byte(), Some(-1));
+		assert_eq!(ElementValue::Object(Object::Char(0x41)).as_char(), Some(0x41));
+		assert_eq!(ElementValue::Object(Object::Double(0.5)).as_double(), Some(0.5));
+		assert_eq!(ElementValue::Object(Object::Float(1.5)).as_float(), Some(1.5));
+		assert_eq!(ElementValue::Object(Object::Short(300)).as_short(), Some(300));
+
+		// no conversions between the types
+		assert_eq!(ElementValue::Object(Object::Integer(1)).as_byte(), None);
+		assert_eq!(ElementValue::Object(Object::Float(1.5)).as_double(), None);
+		assert_eq!(ElementValue::Object(Object::Byte(1)).as_short(), None);
+	}
+}
@@ -2,13 +2,14 @@ use std::fmt::{Debug, Display, Formatter};
 use anyhow::{bail, Result};
 use std::ops::ControlFlow;
 use java_string::{JavaStr, JavaString};
+use crate::class_constants::attribute;
 use crate::macros::{make_display, make_string_str_like};
 use crate::tree::annotation::Annotation;
 use crate::tree::attribute::Attribute;
-use crate::tree::field::Field;
-use crate::tree::method::{Method, MethodNameAndDesc};
+use crate::tree::field::{Field, FieldDescriptorSlice, FieldNameAndDesc};
+use crate::tree::method::{Method, MethodDescriptor, MethodName, MethodNameAndDesc};
 use crate::tree::module::{Module, PackageName};
-use crate::tree::record::RecordComponent;
+use crate::tree::record::{RecordComponent, RecordNameSlice};
 use crate::tree::type_annotation::{TargetInfoClass, TypeAnnotation};
 use crate::tree::version::Version;
 use crate::visitor::attribute::UnknownAttributeVisitor;
@@ -54,6 +55,17 @@ pub struct ClassFile {
 	pub attributes: Vec<Attribute>,
 }
 
+/// A part of a class with the `Deprecated` attribute, but without a `@java.lang.Deprecated` annotation.
+///
+/// See [`ClassFile::sync_deprecated_from_annotations`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeprecatedWithoutAnnotation {
+	/// The class itself.
+	Class,
+	Field(FieldNameAndDesc),
+	Method(MethodNameAndDesc),
+}
+
 impl ClassFile {
 	pub fn new(version: Version, access: ClassAccess, name: ClassName, super_class: Option<ClassName>, interfaces: Vec<ClassName>) -> ClassFile {
 		ClassFile {
@@ -95,6 +107,282 @@ impl ClassFile {
 		}
 	}
 
+	/// The version of the class file, see [`Version::major`], [`Version::minor`] and [`Version::is_preview`].
+	pub fn version(&self) -> Version {
+		self.version
+	}
+
+	/// Interprets the [`enclosing_method`][ClassFile::enclosing_method] of this class.
+	///
+	/// Only local and anonymous classes have an `EnclosingMethod` attribute. If such a class is declared inside a method (or
+	/// constructor), this returns [`EnclosingInfo::InMethod`]. If it's declared in an initializer (like a field initializer or a
+	/// static initializer block), this returns [`EnclosingInfo::InClass`].
+	///
+	/// Returns [`None`] if there's no `EnclosingMethod` attribute, like for top level classes and member classes (such as static
+	/// nested classes). To get information about those, look at the [`inner_classes`][ClassFile::inner_classes] instead.
+	pub fn enclosing_info(&self) -> Option<EnclosingInfo<'_>> {
+		self.enclosing_method.as_ref().map(|enclosing_method| match &enclosing_method.method {
+			Some(method) => EnclosingInfo::InMethod {
+				class: &enclosing_method.class,
+				name: &method.name,
+				desc: &method.desc,
+			},
+			None => EnclosingInfo::InClass {
+				class: &enclosing_method.class,
+			},
+		})
+	}
+
+	/// Gets all the annotations on this class, both the runtime visible and the runtime invisible ones.
+	pub fn annotations(&self) -> impl Iterator<Item=&Annotation> {
+		self.runtime_visible_annotations.iter().chain(&self.runtime_invisible_annotations)
+	}
+
+	/// Sets the `Deprecated` attribute on the class, fields and methods annotated with `@java.lang.Deprecated`.
+	///
+	/// javac emits both the annotation and the attribute, so this keeps generated classes consistent with it. The attribute
+	/// isn't removed from the ones without the annotation, as older compilers only emit the attribute. These are returned
+	/// instead, in the order of the class, its fields and its methods.
+	pub fn sync_deprecated_from_annotations(&mut self) -> Vec<DeprecatedWithoutAnnotation> {
+		fn sync<'a>(annotations: impl IntoIterator<Item=&'a Annotation>, has_deprecated_attribute: &mut bool) -> bool {
+			let is_annotated = annotations.into_iter()
+				.any(|annotation| annotation.annotation_type.as_inner() == "Ljava/lang/Deprecated;");
+			if is_annotated {
+				*has_deprecated_attribute = true;
+			}
+			*has_deprecated_attribute && !is_annotated
+		}
+
+		let mut without_annotation = Vec::new();
+
+		let annotations = self.runtime_visible_annotations.iter().chain(&self.runtime_invisible_annotations);
+		if sync(annotations, &mut self.has_deprecated_attribute) {
+			without_annotation.push(DeprecatedWithoutAnnotation::Class);
+		}
+		for field in &mut self.fields {
+			let annotations = field.runtime_visible_annotations.iter().chain(&field.runtime_invisible_annotations);
+			if sync(annotations, &mut field.has_deprecated_attribute) {
+				without_annotation.push(DeprecatedWithoutAnnotation::Field(field.as_name_and_desc()));
+			}
+		}
+		for method in &mut self.methods {
+			let annotations = method.runtime_visible_annotations.iter().chain(&method.runtime_invisible_annotations);
+			if sync(annotations, &mut method.has_deprecated_attribute) {
+				without_annotation.push(DeprecatedWithoutAnnotation::Method(method.as_name_and_desc()));
+			}
+		}
+
+		without_annotation
+	}
+
+	/// Checks if this class is a record class, by checking if it extends `java.lang.Record`.
+	///
+	/// Note that a record class without any components has no [`record_components`][ClassFile::record_components], so
+	/// these can't be used to tell if a class is a record.
+	pub fn is_record(&self) -> bool {
+		self.super_class.as_deref() == Some(ClassName::JAVA_LANG_RECORD)
+	}
+
+	/// Gets the record component with the given name.
+	pub fn record_component(&self, name: &RecordNameSlice) -> Option<&RecordComponent> {
+		self.record_components.iter().find(|component| *component.name == *name)
+	}
+
+	/// Adds a record component at the end of the [`record_components`][ClassFile::record_components].
+	///
+	/// Returns an error if there's already a record component with the same name.
+	pub fn add_record_component(&mut self, component: RecordComponent) -> Result<()> {
+		if self.record_component(&component.name).is_some() {
+			bail!("class {} already has a record component named {:?}", self.name, component.name);
+		}
+		self.record_components.push(component);
+		Ok(())
+	}
+
+	/// Removes the record component with the given name, returning it.
+	///
+	/// This keeps the order of the other record components.
+	pub fn remove_record_component(&mut self, name: &RecordNameSlice) -> Option<RecordComponent> {
+		let index = self.record_components.iter().position(|component| *component.name == *name)?;
+		Some(self.record_components.remove(index))
+	}
+
+	/// Checks that only a [record][ClassFile::is_record] class has [`record_components`][ClassFile::record_components].
+	///
+	/// The JVM only allows the `Record` attribute on classes extending `java.lang.Record`.
+	pub fn check_record_components(&self) -> Result<()> {
+		if !self.record_components.is_empty() && !self.is_record() {
+			bail!("class {} has record components, but doesn't extend {}", self.name, ClassName::JAVA_LANG_RECORD);
+		}
+		Ok(())
+	}
+
+	/// Checks if this class is sealed, by checking if it has a `PermittedSubclasses` attribute.
+	pub fn is_sealed(&self) -> bool {
+		self.permitted_subclasses.is_some()
+	}
+
+	/// Gets the classes that are allowed to directly extend or implement this class.
+	///
+	/// Returns [`None`] if this class isn't [sealed][ClassFile::is_sealed].
+	pub fn permitted_subclasses(&self) -> Option<&[ClassName]> {
+		self.permitted_subclasses.as_deref()
+	}
+
+	/// Checks that each of the [`permitted_subclasses`][ClassFile::permitted_subclasses] actually extends or implements this
+	/// class.
+	///
+	/// The `get_class` function is used to look up the permitted subclasses. Permitted subclasses for which it returns
+	/// [`None`] (for example because they're not in the same jar) are skipped.
+	pub fn check_permitted_subclasses<'a>(&self, mut get_class: impl FnMut(&ClassName) -> Option<&'a ClassFile>) -> Result<()> {
+		for subclass_name in self.permitted_subclasses().unwrap_or_default() {
+			if let Some(subclass) = get_class(subclass_name) {
+				if subclass.super_class.as_ref() != Some(&self.name) && !subclass.interfaces.contains(&self.name) {
+					bail!("permitted subclass {subclass_name} of sealed class {} doesn't extend or implement it", self.name);
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Removes all attributes for which `predicate` returns `true`, on the class, and on all its fields, methods (including their
+	/// code) and record components.
+	///
+	/// The predicate is called with the name of the attribute, like `SourceFile`. This works both for the attributes stored in
+	/// their own fields, as well as for the unknown [`attributes`][ClassFile::attributes].
+	///
+	/// Attributes that aren't stored in the tree can't be removed: these are `Code` (see [`Method::code`]), `StackMapTable`
+	/// (see [`InstructionListEntry::frame`][crate::tree::method::code::InstructionListEntry::frame]), and `BootstrapMethods`
+	/// (generated when writing the class).
+	///
+	/// Returns whether any attribute was removed.
+	pub fn remove_attributes(&mut self, predicate: impl Fn(&JavaStr) -> bool) -> bool {
+		let mut removed = false;
+
+		if predicate(attribute::DEPRECATED) {
+			removed |= std::mem::take(&mut self.has_deprecated_attribute);
+		}
+		if predicate(attribute::SYNTHETIC) {
+			removed |= std::mem::take(&mut self.has_synthetic_attribute);
+		}
+		if predicate(attribute::INNER_CLASSES) {
+			removed |= self.inner_classes.take().is_some();
+		}
+		if predicate(attribute::ENCLOSING_METHOD) {
+			removed |= self.enclosing_method.take().is_some();
+		}
+		if predicate(attribute::SIGNATURE) {
+			removed |= self.signature.take().is_some();
+		}
+		if predicate(attribute::SOURCE_FILE) {
+			removed |= self.source_file.take().is_some();
+		}
+		if predicate(attribute::SOURCE_DEBUG_EXTENSION) {
+			removed |= self.source_debug_extension.take().is_some();
+		}
+		if predicate(attribute::RUNTIME_VISIBLE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_visible_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_INVISIBLE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_invisible_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_visible_type_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_invisible_type_annotations).is_empty();
+		}
+		if predicate(attribute::MODULE) {
+			removed |= self.module.take().is_some();
+		}
+		if predicate(attribute::MODULE_PACKAGES) {
+			removed |= self.module_packages.take().is_some();
+		}
+		if predicate(attribute::MODULE_MAIN_CLASS) {
+			removed |= self.module_main_class.take().is_some();
+		}
+		if predicate(attribute::NEST_HOST) {
+			removed |= self.nest_host_class.take().is_some();
+		}
+		if predicate(attribute::NEST_MEMBERS) {
+			removed |= self.nest_members.take().is_some();
+		}
+		if predicate(attribute::PERMITTED_SUBCLASSES) {
+			removed |= self.permitted_subclasses.take().is_some();
+		}
+		if predicate(attribute::RECORD) {
+			removed |= !std::mem::take(&mut self.record_components).is_empty();
+		}
+		let len = self.attributes.len();
+		self.attributes.retain(|attribute| !predicate(&attribute.name));
+		removed |= self.attributes.len() != len;
+
+		for field in &mut self.fields {
+			removed |= field.remove_attributes(&predicate);
+		}
+		for method in &mut self.methods {
+			removed |= method.remove_attributes(&predicate);
+		}
+		for record_component in &mut self.record_components {
+			removed |= record_component.remove_attributes(&predicate);
+		}
+
+		removed
+	}
+
+	/// Changes how the class, its fields and its methods are marked as synthetic.
+	///
+	/// Something is synthetic if either the `ACC_SYNTHETIC` flag or the `Synthetic` attribute is present. Afterwards, it's
+	/// marked as given by the `encoding`. This allows writing classes for versions before Java 5 (class file version 49),
+	/// which only know the attribute.
+	///
+	/// There's no such choice for the `Deprecated` attribute: there's no flag for it, so it's always written as an attribute.
+	pub fn set_synthetic_encoding(&mut self, encoding: SyntheticEncoding) {
+		encoding.apply(&mut self.access.is_synthetic, &mut self.has_synthetic_attribute);
+		for field in &mut self.fields {
+			encoding.apply(&mut field.access.is_synthetic, &mut field.has_synthetic_attribute);
+		}
+		for method in &mut self.methods {
+			encoding.apply(&mut method.access.is_synthetic, &mut method.has_synthetic_attribute);
+		}
+	}
+
+	/// Normalizes the access flags of the class, returning warnings about flags that were left alone.
+	///
+	/// Since class file version 50 (Java 6), every class (but not interfaces and modules) should have the `ACC_SUPER` flag,
+	/// which changes how `invokespecial` looks up methods of superclasses. It's added if it's missing. Interfaces having
+	/// `ACC_SUPER` are reported as a warning.
+	///
+	/// Fails if the class is both `ACC_ABSTRACT` and `ACC_FINAL`.
+	pub fn normalize_access_flags(&mut self) -> Result<Vec<String>> {
+		if self.access.is_abstract && self.access.is_final {
+			bail!("class {:?} is both abstract and final", self.name);
+		}
+
+		let mut warnings = Vec::new();
+		if self.access.is_interface {
+			if self.access.is_super {
+				warnings.push(format!("interface {:?} has the `ACC_SUPER` flag set", self.name));
+			}
+		} else if !self.access.is_module && self.version >= Version::V1_6 {
+			self.access.is_super = true;
+		}
+		Ok(warnings)
+	}
+
+	/// Removes the debug information from the class.
+	///
+	/// This removes the `SourceFile`, `LineNumberTable`, `LocalVariableTable` and `LocalVariableTypeTable` attributes. The
+	/// `StackMapTable` is kept, since it's needed for verification.
+	pub fn strip_debug_info(&mut self) {
+		self.remove_attributes(|name| {
+			name == attribute::SOURCE_FILE ||
+				name == attribute::LINE_NUMBER_TABLE ||
+				name == attribute::LOCAL_VARIABLE_TABLE ||
+				name == attribute::LOCAL_VARIABLE_TYPE_TABLE
+		});
+	}
+
 	pub fn accept<V: MultiClassVisitor>(self, visitor: V) -> Result<V> {
 		match visitor.visit_class(self.version, self.access, self.name, self.super_class, self.interfaces)? {
 			ControlFlow::Continue((visitor, mut class_visitor)) => {
@@ -222,6 +510,38 @@ impl ClassFile {
 	}
 }
 
+/// How a class, field or method is marked as synthetic, see [`ClassFile::set_synthetic_encoding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticEncoding {
+	/// Only the `ACC_SYNTHETIC` flag is set.
+	Flag,
+	/// Only the `Synthetic` attribute is present. This is the only form known before Java 5.
+	Attribute,
+	/// Both the flag is set and the attribute is present.
+	Both,
+}
+
+impl SyntheticEncoding {
+	fn apply(self, flag: &mut bool, attribute: &mut bool) {
+		let is_synthetic = *flag || *attribute;
+		*flag = is_synthetic && self != SyntheticEncoding::Attribute;
+		*attribute = is_synthetic && self != SyntheticEncoding::Flag;
+	}
+}
+
+/// The start of a class file, up to and including the interfaces.
+///
+/// This is the information given to [`MultiClassVisitor::visit_class`], which can be read without reading any fields,
+/// methods or attributes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassHeader {
+	pub version: Version,
+	pub access: ClassAccess,
+	pub name: ClassName,
+	pub super_class: Option<ClassName>,
+	pub interfaces: Vec<ClassName>,
+}
+
 /// Represents the access flags a class can have.
 ///
 /// Take a look at the [Java Virtual Machine Specification](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-4.html#jvms-4.1-200-E.1), for
@@ -330,6 +650,18 @@ impl ClassName {
 		unsafe { ClassNameSlice::from_inner_unchecked(JavaStr::from_str("java/lang/Object")) }
 	};
 
+	/// A constant holding the class name of `String`.
+	pub const JAVA_LANG_STRING: &'static ClassNameSlice = {
+		// SAFETY: `java/lang/String` is a valid class name.
+		unsafe { ClassNameSlice::from_inner_unchecked(JavaStr::from_str("java/lang/String")) }
+	};
+
+	/// A constant holding the class name of `Record`, the super class of all record classes.
+	pub const JAVA_LANG_RECORD: &'static ClassNameSlice = {
+		// SAFETY: `java/lang/Record` is a valid class name.
+		unsafe { ClassNameSlice::from_inner_unchecked(JavaStr::from_str("java/lang/Record")) }
+	};
+
 	/// Creates a class name for joining together an inner class parent name and an inner class name.
 	///
 	/// ```
@@ -373,12 +705,60 @@ impl ClassNameSlice {
 	///
 	/// assert_eq!(array.is_array(), true);
 	///
+	/// // SAFETY: This is a valid class name.
+	/// let primitive_array = unsafe { ClassNameSlice::from_inner_unchecked("[I".into()) };
+	///
+	/// assert_eq!(primitive_array.is_array(), true);
+	///
 	/// assert_eq!(ClassName::JAVA_LANG_OBJECT.is_array(), false);
 	/// ```
 	pub fn is_array(&self) -> bool {
 		self.as_inner().starts_with('[')
 	}
 
+	/// Gets this class name if it's not an array class.
+	///
+	/// This is useful for filtering out array classes, for example for owners of method references.
+	///
+	/// ```
+	/// # use pretty_assertions::assert_eq;
+	/// use duke::tree::class::{ClassName, ClassNameSlice};
+	///
+	/// assert_eq!(ClassName::JAVA_LANG_OBJECT.as_obj(), Some(ClassName::JAVA_LANG_OBJECT));
+	///
+	/// // SAFETY: These are valid class names.
+	/// let primitive_array = unsafe { ClassNameSlice::from_inner_unchecked("[I".into()) };
+	/// let object_array = unsafe { ClassNameSlice::from_inner_unchecked("[Ljava/lang/String;".into()) };
+	///
+	/// assert_eq!(primitive_array.as_obj(), None);
+	/// assert_eq!(object_array.as_obj(), None);
+	/// ```
+	pub fn as_obj(&self) -> Option<&ClassNameSlice> {
+		(!self.is_array()).then_some(self)
+	}
+
+	/// Gets this class name as a field descriptor, if it's an array class.
+	///
+	/// Array class names are written as the field descriptor of the array type, see JVMS 4.2.1.
+	///
+	/// ```
+	/// # use pretty_assertions::assert_eq;
+	/// use duke::tree::class::{ClassName, ClassNameSlice};
+	///
+	/// assert_eq!(ClassName::JAVA_LANG_OBJECT.as_arr(), None);
+	///
+	/// // SAFETY: These are valid class names.
+	/// let primitive_array = unsafe { ClassNameSlice::from_inner_unchecked("[I".into()) };
+	/// let object_array = unsafe { ClassNameSlice::from_inner_unchecked("[Ljava/lang/String;".into()) };
+	///
+	/// assert_eq!(primitive_array.as_arr().map(|x| x.as_inner()), Some("[I".into()));
+	/// assert_eq!(object_array.as_arr().map(|x| x.as_inner()), Some("[Ljava/lang/String;".into()));
+	/// ```
+	pub fn as_arr(&self) -> Option<&FieldDescriptorSlice> {
+		// SAFETY: An array class name is the field descriptor of the array type, see JVMS 4.2.1.
+		self.is_array().then(|| unsafe { FieldDescriptorSlice::from_inner_unchecked(self.as_inner()) })
+	}
+
 	/// Gets the simple name from a class name.
 	pub fn get_simple_name(&self) -> &ClassNameSlice {
 		assert!(!self.is_array());
@@ -543,4 +923,262 @@ impl From<InnerClassFlags> for u16 {
 pub struct EnclosingMethod {
 	pub class: ClassName,
 	pub method: Option<MethodNameAndDesc>,
-}
\ No newline at end of file
+}
+
+/// The interpretation of an [`EnclosingMethod`], see [`ClassFile::enclosing_info`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnclosingInfo<'a> {
+	/// The class is a local or anonymous class declared inside the method `name` with the descriptor `desc` of `class`.
+	InMethod {
+		class: &'a ClassName,
+		name: &'a MethodName,
+		desc: &'a MethodDescriptor,
+	},
+	/// The class is a local or anonymous class declared in `class`, but not inside a method, like in a field initializer.
+	InClass {
+		class: &'a ClassName,
+	},
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::{Context, Result};
+	use java_string::JavaStr;
+	use pretty_assertions::assert_eq;
+	use crate::tree::annotation::Annotation;
+	use crate::tree::class::{ClassAccess, ClassFile, ClassName, DeprecatedWithoutAnnotation, EnclosingInfo, SyntheticEncoding};
+	use crate::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName};
+	use crate::tree::record::{RecordComponent, RecordName};
+	use crate::tree::version::Version;
+
+	#[test]
+	fn normalize_access_flags() -> Result<()> {
+		let name = ClassName::try_from(JavaStr::from_str("Test"))?;
+
+		let mut class = ClassFile::new(Version::V1_8, ClassAccess::from(0x0001), name.clone(), Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		assert!(class.normalize_access_flags()?.is_empty());
+		assert!(class.access.is_super);
+
+		let mut old = ClassFile::new(Version::V1_4, ClassAccess::from(0x0001), name.clone(), Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		assert!(old.normalize_access_flags()?.is_empty());
+		assert!(!old.access.is_super);
+
+		let mut interface = ClassFile::new(Version::V1_8, ClassAccess::from(0x0601), name.clone(), Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		assert!(interface.normalize_access_flags()?.is_empty());
+		assert!(!interface.access.is_super);
+
+		interface.access.is_super = true;
+		assert_eq!(interface.normalize_access_flags()?.len(), 1);
+
+		let mut abstract_final = ClassFile::new(Version::V1_8, ClassAccess::from(0x0431), name, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		assert!(abstract_final.normalize_access_flags().is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn synthetic_encoding() -> Result<()> {
+		let mut class = ClassFile::new(Version::V1_4, ClassAccess::from(0x0021), ClassName::try_from(JavaStr::from_str("Test"))?, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		let method = Method::new(
+			MethodAccess::from(0x1401), // public abstract synthetic
+			MethodName::try_from(JavaStr::from_str("access$000"))?,
+			MethodDescriptor::try_from(JavaStr::from_str("()V"))?,
+		);
+		class.methods.push(method);
+
+		class.set_synthetic_encoding(SyntheticEncoding::Attribute);
+
+		let mut bytes = Vec::new();
+		crate::write_class(&mut bytes, &class)?;
+		assert!(bytes.windows(9).any(|window| window == b"Synthetic"));
+
+		let mut class = crate::read_class(&mut Cursor::new(bytes))?;
+		assert!(class.methods[0].has_synthetic_attribute);
+		assert!(!class.methods[0].access.is_synthetic);
+		// the class itself isn't synthetic
+		assert!(!class.has_synthetic_attribute);
+		assert!(!class.access.is_synthetic);
+
+		class.set_synthetic_encoding(SyntheticEncoding::Both);
+		assert!(class.methods[0].has_synthetic_attribute);
+		assert!(class.methods[0].access.is_synthetic);
+
+		class.set_synthetic_encoding(SyntheticEncoding::Flag);
+		assert!(!class.methods[0].has_synthetic_attribute);
+		assert!(class.methods[0].access.is_synthetic);
+
+		Ok(())
+	}
+
+	#[test]
+	fn enclosing_info() -> Result<()> {
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("test/EnclosingInfo$1.class")))?;
+		let Some(EnclosingInfo::InMethod { class, name, desc }) = class.enclosing_info() else {
+			panic!("expected `InMethod`, got {:?}", class.enclosing_info());
+		};
+		assert_eq!(class.as_inner(), "EnclosingInfo");
+		assert_eq!(name.as_inner(), "anonymous");
+		assert_eq!(desc.as_inner(), "()Ljava/lang/Runnable;");
+
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("test/EnclosingInfo$2.class")))?;
+		let Some(EnclosingInfo::InClass { class }) = class.enclosing_info() else {
+			panic!("expected `InClass`, got {:?}", class.enclosing_info());
+		};
+		assert_eq!(class.as_inner(), "EnclosingInfo");
+
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("test/EnclosingInfo$Nested.class")))?;
+		assert_eq!(class.enclosing_info(), None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn strip_debug_info() -> Result<()> {
+		let mut class = crate::read_class(&mut Cursor::new(include_bytes!("test/StripDebugInfo.class")))?;
+		assert!(class.source_file.is_some());
+		let method = class.methods.iter().find(|method| method.name.as_inner() == "sum").context("no method `sum`")?;
+		let code = method.code.as_ref().context("no code")?;
+		assert!(code.line_numbers.is_some());
+		assert!(code.local_variables.is_some());
+		let frames = code.instructions.iter().filter(|entry| entry.frame.is_some()).count();
+		assert_eq!(frames, 3);
+
+		class.strip_debug_info();
+
+		let method = class.methods.iter().find(|method| method.name.as_inner() == "sum").context("no method `sum`")?;
+		let code = method.code.as_ref().context("no code")?;
+		let frames = code.instructions.iter().filter(|entry| entry.frame.is_some()).count();
+		assert_eq!(frames, 3, "the stack map frames must be kept");
+		let instructions = code.instructions.len();
+
+		assert_eq!(class.source_file, None);
+		for method in &class.methods {
+			let code = method.code.as_ref().context("no code")?;
+			assert_eq!(code.line_numbers, None);
+			assert_eq!(code.local_variables, None);
+		}
+
+		let mut buf = Vec::new();
+		crate::write_class(&mut buf, &class)?;
+		let read = crate::read_class(&mut Cursor::new(buf))?;
+		assert_eq!(read.source_file, None);
+		let method = read.methods.iter().find(|method| method.name.as_inner() == "sum").context("no method `sum`")?;
+		let code = method.code.as_ref().context("no code")?;
+		assert_eq!(code.line_numbers, None);
+		assert_eq!(code.local_variables, None);
+		assert_eq!(code.instructions.len(), instructions);
+
+		Ok(())
+	}
+
+	#[test]
+	fn record_components() -> Result<()> {
+		let mut class = crate::read_class(&mut Cursor::new(include_bytes!("test/Point.class")))?;
+		assert!(class.is_record());
+		class.check_record_components()?;
+
+		let components: Vec<(&JavaStr, &JavaStr)> = class.record_components.iter()
+			.map(|component| (component.name.as_inner(), component.descriptor.as_inner()))
+			.collect();
+		assert_eq!(components, [("x", "I"), ("y", "I"), ("labels", "Ljava/util/List;")].map(|(name, desc)| (JavaStr::from_str(name), JavaStr::from_str(desc))));
+
+		let labels = class.record_component(&RecordName::try_from(JavaStr::from_str("labels"))?).context("no component `labels`")?;
+		let signature = labels.signature.as_ref().context("no signature on `labels`")?;
+		assert_eq!(signature.as_inner(), "Ljava/util/List<Ljava/lang/String;>;");
+
+		let y = class.remove_record_component(&RecordName::try_from(JavaStr::from_str("y"))?).context("no component `y`")?;
+		assert!(class.add_record_component(RecordComponent::new(RecordName::try_from(JavaStr::from_str("x"))?, y.descriptor.clone())).is_err());
+		class.add_record_component(y)?;
+		let names: Vec<&JavaStr> = class.record_components.iter().map(|component| component.name.as_inner()).collect();
+		assert_eq!(names, ["x", "labels", "y"]);
+
+		class.super_class = Some(ClassName::JAVA_LANG_OBJECT.to_owned());
+		assert!(!class.is_record());
+		assert!(class.check_record_components().is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn permitted_subclasses() -> Result<()> {
+		let shape = crate::read_class(&mut Cursor::new(include_bytes!("test/Shape.class")))?;
+		let circle = crate::read_class(&mut Cursor::new(include_bytes!("test/Shape$Circle.class")))?;
+		let square = crate::read_class(&mut Cursor::new(include_bytes!("test/Shape$Square.class")))?;
+
+		assert!(shape.is_sealed());
+		assert!(!circle.is_sealed());
+		assert_eq!(circle.permitted_subclasses(), None);
+
+		let permitted: Vec<&JavaStr> = shape.permitted_subclasses().context("no permitted subclasses")?.iter()
+			.map(|class| class.as_inner())
+			.collect();
+		assert_eq!(permitted, ["Shape$Circle", "Shape$Square"]);
+
+		let mut classes = [circle, square];
+		shape.check_permitted_subclasses(|name| classes.iter().find(|class| class.name == *name))?;
+		// classes not available aren't checked
+		shape.check_permitted_subclasses(|_| None)?;
+
+		classes[1].interfaces.clear();
+		assert!(shape.check_permitted_subclasses(|name| classes.iter().find(|class| class.name == *name)).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn sync_deprecated_from_annotations() -> Result<()> {
+		let mut class = crate::read_class(&mut Cursor::new(include_bytes!("test/Annotated.class")))?;
+		let deprecated = class.annotations()
+			.find(|annotation| annotation.annotation_type.as_inner() == "Ljava/lang/Deprecated;")
+			.context("no @Deprecated annotation")?
+			.clone();
+
+		assert!(class.has_deprecated_attribute);
+		assert!(class.sync_deprecated_from_annotations().is_empty());
+
+		// a method annotated with @Deprecated, but without the attribute, and a field with it the other way around
+		class.has_deprecated_attribute = false;
+		class.methods[1].runtime_visible_annotations.push(deprecated);
+		class.fields[0].has_deprecated_attribute = true;
+
+		let without_annotation = class.sync_deprecated_from_annotations();
+		assert_eq!(without_annotation, [DeprecatedWithoutAnnotation::Field(class.fields[0].as_name_and_desc())]);
+		assert!(class.has_deprecated_attribute);
+		assert_eq!(class.methods[1].name.as_inner(), "method");
+		assert!(class.methods[1].has_deprecated_attribute);
+		assert!(!class.methods[0].has_deprecated_attribute);
+		assert!(class.fields[0].has_deprecated_attribute);
+
+		Ok(())
+	}
+
+	#[test]
+	fn annotations() -> Result<()> {
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("test/Annotated.class")))?;
+
+		let types: Vec<&JavaStr> = class.annotations().map(|annotation| annotation.annotation_type.as_inner()).collect();
+		assert_eq!(types, ["LAnnotated$Info;", "Ljava/lang/Deprecated;"]);
+
+		fn info(annotations: Vec<&Annotation>) -> Result<(&JavaStr, i32)> {
+			let [annotation] = annotations[..] else {
+				panic!("expected exactly one annotation, got {annotations:?}");
+			};
+			assert_eq!(annotation.annotation_type.as_inner(), "LAnnotated$Info;");
+			let name = annotation.get("name").and_then(|value| value.as_string()).context("no string element `name`")?;
+			let value = annotation.get("value").and_then(|value| value.as_int()).context("no int element `value`")?;
+			assert_eq!(annotation.get("other"), None);
+			Ok((name, value))
+		}
+
+		assert_eq!(info(class.annotations().take(1).collect())?, (JavaStr::from_str("class"), 1));
+
+		let field = class.fields.iter().find(|field| field.name.as_inner() == "field").context("no field `field`")?;
+		assert_eq!(info(field.annotations().collect())?, (JavaStr::from_str("field"), 2));
+
+		let method = class.methods.iter().find(|method| method.name.as_inner() == "method").context("no method `method`")?;
+		assert_eq!(info(method.annotations().collect())?, (JavaStr::from_str("method"), 3));
+
+		Ok(())
+	}
+}
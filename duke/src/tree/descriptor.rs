@@ -549,6 +549,169 @@ impl FieldDescriptor {
 	}
 }
 
+/// Checks the field type starting at `start` in `bytes`, and returns the index right after it.
+///
+/// This is a `const fn` version of [`read_field_type`], for checking descriptors at compile time. Returns [`None`] if there's
+/// no valid field type at `start`.
+const fn skip_field_type(bytes: &[u8], start: usize) -> Option<usize> {
+	let mut i = start;
+	while i < bytes.len() && bytes[i] == b'[' {
+		i += 1;
+	}
+	if i - start > 255 || i >= bytes.len() {
+		return None;
+	}
+
+	match bytes[i] {
+		b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => Some(i + 1),
+		b'L' => {
+			let class_start = i + 1;
+			let mut j = class_start;
+			while j < bytes.len() && bytes[j] != b';' {
+				match bytes[j] {
+					b'.' | b'[' => return None,
+					// no empty package or class names
+					b'/' if j == class_start || bytes[j - 1] == b'/' => return None,
+					_ => {},
+				}
+				j += 1;
+			}
+			if j >= bytes.len() || j == class_start || bytes[j - 1] == b'/' {
+				return None;
+			}
+			Some(j + 1)
+		},
+		_ => None,
+	}
+}
+
+/// Checks if `desc` is a valid field descriptor, like `I` or `[Ljava/lang/String;`.
+///
+/// This only checks the grammar of the descriptor, and is usable in `const` contexts, see [`field_descriptor!`][crate::field_descriptor].
+///
+/// ```
+/// use duke::tree::descriptor::is_valid_field_descriptor;
+/// assert!(is_valid_field_descriptor("[Ljava/lang/String;"));
+/// assert!(!is_valid_field_descriptor("Ljava/lang/String"));
+/// assert!(!is_valid_field_descriptor("V"));
+/// ```
+pub const fn is_valid_field_descriptor(desc: &str) -> bool {
+	is_valid_field_descriptor_bytes(desc.as_bytes())
+}
+
+/// Checks if the bytes are a valid field descriptor, see [`is_valid_field_descriptor`].
+///
+/// This doesn't allocate, and also works on the bytes of a [`JavaStr`].
+pub(crate) const fn is_valid_field_descriptor_bytes(bytes: &[u8]) -> bool {
+	matches!(skip_field_type(bytes, 0), Some(end) if end == bytes.len())
+}
+
+/// Checks if `desc` is a valid method descriptor, like `()V` or `(Ljava/lang/String;I)Z`.
+///
+/// This only checks the grammar of the descriptor, and is usable in `const` contexts, see [`descriptor!`][crate::descriptor].
+///
+/// ```
+/// use duke::tree::descriptor::is_valid_method_descriptor;
+/// assert!(is_valid_method_descriptor("(Ljava/lang/String;I)V"));
+/// assert!(!is_valid_method_descriptor("(Ljava/lang/String)V"));
+/// assert!(!is_valid_method_descriptor("(V)V"));
+/// ```
+pub const fn is_valid_method_descriptor(desc: &str) -> bool {
+	is_valid_method_descriptor_bytes(desc.as_bytes())
+}
+
+/// Checks if the bytes are a valid method descriptor, see [`is_valid_method_descriptor`].
+///
+/// This doesn't allocate, and also works on the bytes of a [`JavaStr`].
+pub(crate) const fn is_valid_method_descriptor_bytes(bytes: &[u8]) -> bool {
+	if bytes.is_empty() || bytes[0] != b'(' {
+		return false;
+	}
+
+	let mut i = 1;
+	while i < bytes.len() && bytes[i] != b')' {
+		match skip_field_type(bytes, i) {
+			Some(end) => i = end,
+			None => return false,
+		}
+	}
+	if i >= bytes.len() {
+		return false;
+	}
+	i += 1; // the `)`
+
+	if i + 1 == bytes.len() && bytes[i] == b'V' {
+		return true;
+	}
+	matches!(skip_field_type(bytes, i), Some(end) if end == bytes.len())
+}
+
+#[doc(hidden)]
+pub const fn method_descriptor_checked(desc: &'static str) -> &'static MethodDescriptorSlice {
+	if !is_valid_method_descriptor(desc) {
+		panic!("invalid method descriptor");
+	}
+	// SAFETY: We just checked that `desc` is a valid method descriptor.
+	unsafe { MethodDescriptorSlice::from_inner_unchecked(JavaStr::from_str(desc)) }
+}
+
+#[doc(hidden)]
+pub const fn field_descriptor_checked(desc: &'static str) -> &'static FieldDescriptorSlice {
+	if !is_valid_field_descriptor(desc) {
+		panic!("invalid field descriptor");
+	}
+	// SAFETY: We just checked that `desc` is a valid field descriptor.
+	unsafe { FieldDescriptorSlice::from_inner_unchecked(JavaStr::from_str(desc)) }
+}
+
+/// Creates a `&'static` [`MethodDescriptorSlice`] from a string literal, checking it at compile time.
+///
+/// ```
+/// use duke::descriptor;
+/// use duke::tree::method::MethodDescriptorSlice;
+///
+/// let desc: &MethodDescriptorSlice = descriptor!("(Ljava/lang/String;I)V");
+/// assert_eq!(desc.as_inner(), "(Ljava/lang/String;I)V");
+/// ```
+///
+/// A malformed descriptor, like one missing the `;` after a class name, doesn't compile:
+/// ```compile_fail
+/// let desc = duke::descriptor!("(Ljava/lang/String)V");
+/// ```
+#[macro_export]
+macro_rules! descriptor {
+	($desc:literal) => {
+		{
+			const DESC: &'static $crate::tree::method::MethodDescriptorSlice = $crate::tree::descriptor::method_descriptor_checked($desc);
+			DESC
+		}
+	};
+}
+
+/// Creates a `&'static` [`FieldDescriptorSlice`] from a string literal, checking it at compile time.
+///
+/// ```
+/// use duke::field_descriptor;
+/// use duke::tree::field::FieldDescriptorSlice;
+///
+/// let desc: &FieldDescriptorSlice = field_descriptor!("[Ljava/lang/String;");
+/// assert_eq!(desc.as_inner(), "[Ljava/lang/String;");
+/// ```
+///
+/// A malformed descriptor, like `V` (which is only valid as a return descriptor), doesn't compile:
+/// ```compile_fail
+/// let desc = duke::field_descriptor!("V");
+/// ```
+#[macro_export]
+macro_rules! field_descriptor {
+	($desc:literal) => {
+		{
+			const DESC: &'static $crate::tree::field::FieldDescriptorSlice = $crate::tree::descriptor::field_descriptor_checked($desc);
+			DESC
+		}
+	};
+}
+
 #[cfg(test)]
 mod testing {
 	use pretty_assertions::assert_eq;
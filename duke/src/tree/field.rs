@@ -2,10 +2,12 @@ use anyhow::{bail, Result};
 use std::fmt::{Debug, Display, Formatter};
 use std::ops::ControlFlow;
 use java_string::{JavaStr, JavaString};
+use crate::class_constants::attribute;
 use crate::macros::{make_display, make_string_str_like};
 use crate::tree::annotation::Annotation;
 use crate::tree::attribute::Attribute;
 use crate::tree::class::ClassName;
+use crate::tree::descriptor::{ParsedFieldDescriptor, Type};
 use crate::tree::type_annotation::{TargetInfoField, TypeAnnotation};
 use crate::visitor::attribute::UnknownAttributeVisitor;
 use crate::visitor::class::ClassVisitor;
@@ -53,6 +55,82 @@ impl Field {
 		}
 	}
 
+	/// Gets all the annotations on this field, both the runtime visible and the runtime invisible ones.
+	pub fn annotations(&self) -> impl Iterator<Item=&Annotation> {
+		self.runtime_visible_annotations.iter().chain(&self.runtime_invisible_annotations)
+	}
+
+	/// Gets the [`ConstantValue`] of this field, as a [`TypedConstantValue`] according to the field descriptor.
+	///
+	/// The constant pool stores the values of `boolean`, `byte`, `char` and `short` fields as an `Integer`. These are
+	/// narrowed to the type of the field like the JVM does, so a `boolean` is `true` if the lowest bit is set, and the others
+	/// keep the lowest bits of the value.
+	///
+	/// Returns an error if the constant value doesn't fit the field descriptor, like a `Long` for an `int` field, or a
+	/// `String` for a field that isn't of type `java.lang.String`.
+	pub fn typed_constant_value(&self) -> Result<Option<TypedConstantValue>> {
+		let Some(constant_value) = &self.constant_value else {
+			return Ok(None);
+		};
+
+		let ParsedFieldDescriptor(descriptor) = self.descriptor.parse()?;
+		let value = match (descriptor, constant_value) {
+			(Type::Z, &ConstantValue::Integer(value)) => TypedConstantValue::Boolean(value & 1 != 0),
+			(Type::B, &ConstantValue::Integer(value)) => TypedConstantValue::Byte(value as i8),
+			(Type::C, &ConstantValue::Integer(value)) => TypedConstantValue::Char(value as u16),
+			(Type::S, &ConstantValue::Integer(value)) => TypedConstantValue::Short(value as i16),
+			(Type::I, &ConstantValue::Integer(value)) => TypedConstantValue::Integer(value),
+			(Type::J, &ConstantValue::Long(value)) => TypedConstantValue::Long(value),
+			(Type::F, &ConstantValue::Float(value)) => TypedConstantValue::Float(value),
+			(Type::D, &ConstantValue::Double(value)) => TypedConstantValue::Double(value),
+			(Type::Object(class), ConstantValue::String(value)) if class == ClassName::JAVA_LANG_STRING => {
+				TypedConstantValue::String(value.clone())
+			},
+			(_, constant_value) => bail!("constant value {constant_value:?} doesn't fit the descriptor {:?} of field {:?}", self.descriptor, self.name),
+		};
+
+		Ok(Some(value))
+	}
+
+	/// Removes all attributes for which `predicate` returns `true`.
+	///
+	/// See [`ClassFile::remove_attributes`][crate::tree::class::ClassFile::remove_attributes].
+	///
+	/// Returns whether any attribute was removed.
+	pub fn remove_attributes(&mut self, predicate: impl Fn(&JavaStr) -> bool) -> bool {
+		let mut removed = false;
+
+		if predicate(attribute::DEPRECATED) {
+			removed |= std::mem::take(&mut self.has_deprecated_attribute);
+		}
+		if predicate(attribute::SYNTHETIC) {
+			removed |= std::mem::take(&mut self.has_synthetic_attribute);
+		}
+		if predicate(attribute::CONSTANT_VALUE) {
+			removed |= self.constant_value.take().is_some();
+		}
+		if predicate(attribute::SIGNATURE) {
+			removed |= self.signature.take().is_some();
+		}
+		if predicate(attribute::RUNTIME_VISIBLE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_visible_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_INVISIBLE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_invisible_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_visible_type_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_invisible_type_annotations).is_empty();
+		}
+		let len = self.attributes.len();
+		self.attributes.retain(|attribute| !predicate(&attribute.name));
+		removed |= self.attributes.len() != len;
+
+		removed
+	}
+
 	pub fn accept<C: ClassVisitor>(self, visitor: C) -> Result<C> {
 		match visitor.visit_field(self.access, self.name, self.descriptor)? {
 			ControlFlow::Continue((visitor, mut field_visitor)) => {
@@ -223,7 +301,11 @@ make_display!(FieldName, FieldNameSlice);
 make_string_str_like!(
 	pub FieldDescriptor(JavaString);
 	pub FieldDescriptorSlice(JavaStr);
-	is_valid(__) = Ok(()); // TODO: parse the desc and fail if invalid
+	is_valid(s) = if crate::tree::descriptor::is_valid_field_descriptor_bytes(s.as_bytes()) {
+		Ok(())
+	} else {
+		bail!("invalid field descriptor: must be a base type, an object type like `Ljava/lang/Object;` or an array of at most 255 dimensions")
+	};
 );
 make_display!(FieldDescriptor, FieldDescriptorSlice);
 
@@ -242,3 +324,51 @@ pub enum ConstantValue {
 	Double(f64),
 	String(JavaString),
 }
+
+/// The value of a [`ConstantValue`], with the type of the field it's on, as returned by [`Field::typed_constant_value`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedConstantValue {
+	Boolean(bool),
+	Byte(i8),
+	Char(u16),
+	Short(i16),
+	Integer(i32),
+	Long(i64),
+	Float(f32),
+	Double(f64),
+	String(JavaString),
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::{Context, Result};
+	use pretty_assertions::assert_eq;
+	use java_string::{JavaStr, JavaString};
+	use crate::tree::field::{ConstantValue, Field, FieldAccess, FieldDescriptor, FieldName, TypedConstantValue};
+
+	#[test]
+	fn typed_constant_value() -> Result<()> {
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("test/Constants.class")))?;
+
+		let constant = |name: &str| -> Result<Option<TypedConstantValue>> {
+			class.fields.iter()
+				.find(|field| field.name.as_inner() == name)
+				.with_context(|| format!("no field {name:?}"))?
+				.typed_constant_value()
+		};
+
+		assert_eq!(constant("X")?, Some(TypedConstantValue::Integer(42)));
+		assert_eq!(constant("S")?, Some(TypedConstantValue::String(JavaString::from("hi"))));
+		assert_eq!(constant("B")?, Some(TypedConstantValue::Boolean(true)));
+		assert_eq!(constant("C")?, Some(TypedConstantValue::Char(u16::from(b'c'))));
+		assert_eq!(constant("L")?, Some(TypedConstantValue::Long(-1)));
+
+		let mut field = Field::new(FieldAccess::from(0x0019), FieldName::try_from(JavaStr::from_str("Y"))?, FieldDescriptor::try_from(JavaStr::from_str("I"))?);
+		assert_eq!(field.typed_constant_value()?, None);
+		field.constant_value = Some(ConstantValue::Long(1));
+		assert!(field.typed_constant_value().is_err());
+
+		Ok(())
+	}
+}
@@ -1,15 +1,20 @@
+pub mod builder;
 pub mod code;
+pub mod operands;
+pub mod raw_code;
+pub mod subroutines;
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use std::fmt::{Debug, Display, Formatter};
 use std::ops::ControlFlow;
 use java_string::{JavaStr, JavaString};
+use crate::class_constants::attribute;
 use crate::macros::{make_display, make_string_str_like};
 use crate::tree::annotation::{Annotation, ElementValue};
 use crate::tree::attribute::Attribute;
 use crate::tree::class::ClassName;
-use crate::tree::method::code::Code;
-use crate::tree::type_annotation::{TargetInfoMethod, TypeAnnotation};
+use crate::tree::method::code::{Code, Instruction, InstructionListEntry, Label};
+use crate::tree::type_annotation::{MethodTypeAnnotationTarget, ResolvedTypeAnnotation, TargetInfoMethod, TypeAnnotation};
 use crate::visitor::attribute::UnknownAttributeVisitor;
 use crate::visitor::class::ClassVisitor;
 use crate::visitor::method::MethodVisitor;
@@ -65,6 +70,246 @@ impl Method {
 		}
 	}
 
+	/// Gets all the annotations on this method, both the runtime visible and the runtime invisible ones.
+	pub fn annotations(&self) -> impl Iterator<Item=&Annotation> {
+		self.runtime_visible_annotations.iter().chain(&self.runtime_invisible_annotations)
+	}
+
+	/// Gets all the type annotations on this method, both the runtime visible and the runtime invisible ones.
+	///
+	/// Indices into the [`exceptions`][Method::exceptions] are resolved. Type annotations inside the code aren't included.
+	pub fn type_annotations(&self) -> Vec<ResolvedTypeAnnotation<'_>> {
+		let visible = self.runtime_visible_type_annotations.iter().map(|annotation| (annotation, true));
+		let invisible = self.runtime_invisible_type_annotations.iter().map(|annotation| (annotation, false));
+
+		visible.chain(invisible)
+			.map(|(type_annotation, visible)| {
+				let target = match type_annotation.type_reference {
+					TargetInfoMethod::MethodTypeParameter { index } => MethodTypeAnnotationTarget::TypeParameter { index },
+					TargetInfoMethod::MethodTypeParameterBound { type_parameter_index, bound_index } =>
+						MethodTypeAnnotationTarget::TypeParameterBound { type_parameter_index, bound_index },
+					TargetInfoMethod::Return => MethodTypeAnnotationTarget::Return,
+					TargetInfoMethod::Receiver => MethodTypeAnnotationTarget::Receiver,
+					TargetInfoMethod::FormalParameter { index } => MethodTypeAnnotationTarget::Parameter { index },
+					TargetInfoMethod::Throws { index } => MethodTypeAnnotationTarget::Throws {
+						index,
+						exception: self.exceptions.as_ref().and_then(|exceptions| exceptions.get(index as usize)),
+					},
+				};
+
+				ResolvedTypeAnnotation {
+					target,
+					path: type_annotation.type_path.steps(),
+					annotation: &type_annotation.annotation,
+					visible,
+				}
+			})
+			.collect()
+	}
+
+	/// Removes all attributes for which `predicate` returns `true`, on the method and its code.
+	///
+	/// See [`ClassFile::remove_attributes`][crate::tree::class::ClassFile::remove_attributes].
+	///
+	/// Returns whether any attribute was removed.
+	pub fn remove_attributes(&mut self, predicate: impl Fn(&JavaStr) -> bool) -> bool {
+		let mut removed = false;
+
+		if predicate(attribute::DEPRECATED) {
+			removed |= std::mem::take(&mut self.has_deprecated_attribute);
+		}
+		if predicate(attribute::SYNTHETIC) {
+			removed |= std::mem::take(&mut self.has_synthetic_attribute);
+		}
+		if predicate(attribute::EXCEPTIONS) {
+			removed |= self.exceptions.take().is_some();
+		}
+		if predicate(attribute::SIGNATURE) {
+			removed |= self.signature.take().is_some();
+		}
+		if predicate(attribute::RUNTIME_VISIBLE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_visible_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_INVISIBLE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_invisible_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_visible_type_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_invisible_type_annotations).is_empty();
+		}
+		if predicate(attribute::ANNOTATION_DEFAULT) {
+			removed |= self.annotation_default.take().is_some();
+		}
+		if predicate(attribute::METHOD_PARAMETERS) {
+			removed |= self.method_parameters.take().is_some();
+		}
+		let len = self.attributes.len();
+		self.attributes.retain(|attribute| !predicate(&attribute.name));
+		removed |= self.attributes.len() != len;
+
+		if let Some(code) = &mut self.code {
+			removed |= code.remove_attributes(predicate);
+		}
+
+		removed
+	}
+
+	/// Inserts instructions at the start of the code of this method.
+	///
+	/// Labels of the first instruction stay on it, so jumps to the start of the original code don't run the inserted
+	/// instructions again. Local variables starting at the first instruction (like the parameters) are extended to start at
+	/// the first inserted instruction instead. The exception table isn't changed, so the inserted instructions aren't covered
+	/// by any exception handler. See [`append_before_return`][Method::append_before_return] on what the inserted instructions
+	/// may do, and how the `max_stack` is adjusted.
+	///
+	/// Returns an error if the method has no code, or if the instructions can't be inserted.
+	pub fn prepend_instructions(&mut self, instructions: &[Instruction]) -> Result<()> {
+		let code = self.code_for_splicing(instructions)?;
+
+		let Some(new_start) = instructions.first() else {
+			return Ok(());
+		};
+		let old_start = code.instructions.first().and_then(|entry| entry.label);
+		let label = Label { id: code.next_label_id()? };
+
+		if let (Some(old_start), Some(local_variables)) = (old_start, &mut code.local_variables) {
+			for lv in local_variables {
+				if lv.range.start == old_start {
+					lv.range.start = label;
+				}
+			}
+		}
+
+		let mut entries = Vec::with_capacity(instructions.len());
+		entries.push(InstructionListEntry { label: Some(label), frame: None, offset: None, instruction: new_start.clone() });
+		entries.extend(instructions[1..].iter().map(|instruction| InstructionListEntry {
+			label: None,
+			frame: None,
+			offset: None,
+			instruction: instruction.clone(),
+		}));
+		code.instructions.splice(0..0, entries);
+
+		Ok(())
+	}
+
+	/// Inserts instructions before each return instruction (`ireturn`, `lreturn`, `freturn`, `dreturn`, `areturn` and `return`)
+	/// in the code of this method.
+	///
+	/// The label and the [`frame`][InstructionListEntry::frame] of each return instruction are moved to the first inserted
+	/// instruction, so that jumps to a return instruction run the inserted instructions as well.
+	///
+	/// The frames aren't computed again, but are kept as they are. For them to stay correct, the inserted instructions are
+	/// rejected if they:
+	/// - branch (jumps, switches, `jsr` and `ret`), return or throw,
+	/// - pop values they didn't push themselves, or leave values on the operand stack, or
+	/// - store into a local variable below the `max_locals` of the original code.
+	///
+	/// The `max_stack` is increased by the most slots the inserted instructions have on the operand stack at once, and the
+	/// `max_locals` to include the local variables they store into.
+	///
+	/// Returns an error if the method has no code, or if the instructions can't be inserted.
+	pub fn append_before_return(&mut self, instructions: &[Instruction]) -> Result<()> {
+		let code = self.code_for_splicing(instructions)?;
+
+		let old_instructions = std::mem::take(&mut code.instructions);
+		for mut entry in old_instructions {
+			let is_return = matches!(entry.instruction,
+				Instruction::IReturn | Instruction::LReturn | Instruction::FReturn | Instruction::DReturn | Instruction::AReturn |
+				Instruction::Return
+			);
+			if is_return && !instructions.is_empty() {
+				let mut label = entry.label.take();
+				let mut frame = entry.frame.take();
+				for instruction in instructions {
+					code.instructions.push(InstructionListEntry {
+						label: label.take(),
+						frame: frame.take(),
+						offset: None,
+						instruction: instruction.clone(),
+					});
+				}
+			}
+			code.instructions.push(entry);
+		}
+
+		Ok(())
+	}
+
+	/// Sets the `LineNumberTable` of the code of this method, replacing the one already there.
+	///
+	/// See [`Code::set_line_numbers`] for details. Returns an error if the method has no code, or if setting the line
+	/// numbers of the code fails.
+	pub fn set_line_numbers(&mut self, entries: &[(Label, u16)]) -> Result<()> {
+		let Some(code) = &mut self.code else {
+			bail!("method {:?} {:?} has no code to set the line numbers of", self.name, self.descriptor);
+		};
+
+		code.set_line_numbers(entries)
+			.with_context(|| anyhow!("failed to set the line numbers of method {:?} {:?}", self.name, self.descriptor))
+	}
+
+	/// Gets the code for inserting the instructions, checking that they can be inserted, and increasing the `max_stack` and the
+	/// `max_locals`.
+	fn code_for_splicing(&mut self, instructions: &[Instruction]) -> Result<&mut Code> {
+		let Some(code) = &mut self.code else {
+			bail!("method {:?} {:?} has no code to insert instructions into", self.name, self.descriptor);
+		};
+		if code.raw.is_some() {
+			bail!("cannot insert instructions into the raw code of method {:?} {:?}", self.name, self.descriptor);
+		}
+
+		// The frames are kept as they are, so the instructions must not change anything they describe.
+		let mut depth: u16 = 0;
+		let mut max_depth: u16 = 0;
+		let mut max_locals = code.max_locals;
+		for instruction in instructions {
+			if instruction.is_branch() {
+				bail!("cannot insert the branching instruction {instruction:?} into method {:?} {:?}: the frames would no longer \
+					be correct", self.name, self.descriptor);
+			}
+			if matches!(instruction,
+				Instruction::IReturn | Instruction::LReturn | Instruction::FReturn | Instruction::DReturn | Instruction::AReturn |
+				Instruction::Return | Instruction::AThrow
+			) {
+				bail!("cannot insert {instruction:?} into method {:?} {:?}: the code after it would be unreachable, and have no \
+					frames", self.name, self.descriptor);
+			}
+
+			let stored = match instruction {
+				Instruction::IStore(lv) | Instruction::FStore(lv) | Instruction::AStore(lv) => Some((lv.index, 1)),
+				Instruction::LStore(lv) | Instruction::DStore(lv) => Some((lv.index, 2)),
+				_ => None,
+			};
+			if let (Some((index, size)), Some(original_max_locals)) = (stored, code.max_locals) {
+				if index < original_max_locals {
+					bail!("cannot insert {instruction:?} into method {:?} {:?}: it stores into a local variable used by the \
+						original code, so the frames would no longer be correct", self.name, self.descriptor);
+				}
+				max_locals = max_locals.map(|max_locals| max_locals.max(index.saturating_add(size)));
+			}
+
+			let (pops, pushes) = instruction.stack_effect()
+				.with_context(|| anyhow!("failed to get the stack effect of {instruction:?}"))?;
+			depth = depth.checked_sub(pops)
+				.with_context(|| anyhow!("cannot insert {instruction:?} into method {:?} {:?}: it pops values not pushed by the \
+					inserted instructions, so the frames would no longer be correct", self.name, self.descriptor))?;
+			depth = depth.saturating_add(pushes);
+			max_depth = max_depth.max(depth);
+		}
+		if depth != 0 {
+			bail!("cannot insert the instructions into method {:?} {:?}: they leave {depth} slots on the operand stack, so the \
+				frames would no longer be correct", self.name, self.descriptor);
+		}
+
+		code.max_stack = code.max_stack.map(|max_stack| max_stack.saturating_add(max_depth));
+		code.max_locals = max_locals;
+
+		Ok(code)
+	}
+
 	pub fn accept<C: ClassVisitor>(self, visitor: C) -> Result<C> {
 		match visitor.visit_method(self.access, self.name, self.descriptor)? {
 			ControlFlow::Continue((visitor, mut method_visitor)) => {
@@ -277,7 +522,11 @@ impl MethodName {
 make_string_str_like!(
 	pub MethodDescriptor(JavaString);
 	pub MethodDescriptorSlice(JavaStr);
-	is_valid(s) = Ok(()); // TODO: parse the desc and fail if invalid
+	is_valid(s) = if crate::tree::descriptor::is_valid_method_descriptor_bytes(s.as_bytes()) {
+		Ok(())
+	} else {
+		bail!("invalid method descriptor: must be the parameter types in parentheses, followed by the return type or `V`")
+	};
 );
 make_display!(MethodDescriptor, MethodDescriptorSlice);
 
@@ -337,4 +586,217 @@ impl From<ParameterFlags> for u16 {
 		(if value.is_synthetic { 0x1000 } else { 0 }) |
 		(if value.is_mandated  { 0x8000 } else { 0 })
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::{Context, Result};
+	use pretty_assertions::assert_eq;
+	use java_string::{JavaStr, JavaString};
+	use crate::tree::class::{ClassAccess, ClassFile, ClassName};
+	use crate::tree::field::{FieldDescriptor, FieldName, FieldRef};
+	use crate::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName, MethodRef};
+	use crate::tree::method::builder::CodeBuilder;
+	use crate::tree::method::code::{Instruction, Label, Loadable, LvIndex};
+	use crate::tree::version::Version;
+	use crate::tree::type_annotation::{MethodTypeAnnotationTarget, TypePathKind};
+
+	fn hello_world() -> Result<Vec<Instruction>> {
+		Ok(vec![
+			Instruction::GetStatic(FieldRef {
+				class: ClassName::try_from(JavaStr::from_str("java/lang/System"))?,
+				name: FieldName::try_from(JavaStr::from_str("out"))?,
+				desc: FieldDescriptor::try_from(JavaStr::from_str("Ljava/io/PrintStream;"))?,
+			}),
+			Instruction::Ldc(Loadable::String(JavaString::from("Hello, World!"))),
+			Instruction::InvokeVirtual(MethodRef {
+				class: ClassName::try_from(JavaStr::from_str("java/io/PrintStream"))?,
+				name: MethodName::try_from(JavaStr::from_str("println"))?,
+				desc: MethodDescriptor::try_from(JavaStr::from_str("(Ljava/lang/String;)V"))?,
+			}),
+		])
+	}
+
+	fn write_and_read(class: &ClassFile) -> Result<ClassFile> {
+		let mut bytes = Vec::new();
+		crate::write_class(&mut bytes, class)?;
+		crate::read_class(&mut Cursor::new(bytes))
+	}
+
+	fn instructions(class: &ClassFile, name: &str) -> Result<Vec<Instruction>> {
+		let method = class.methods.iter()
+			.find(|method| method.name.as_inner() == name)
+			.context("no such method")?;
+		let code = method.code.as_ref().context("no code")?;
+		Ok(code.instructions.iter().map(|entry| entry.instruction.clone()).collect())
+	}
+
+	#[test]
+	fn prepend_instructions() -> Result<()> {
+		let mut class = crate::read_class(&mut Cursor::new(include_bytes!("test/Point.class")))?;
+		let before = instructions(&class, "x")?;
+
+		let method = class.methods.iter_mut()
+			.find(|method| method.name.as_inner() == "x")
+			.context("no method x")?;
+		method.prepend_instructions(&hello_world()?)?;
+
+		let class = write_and_read(&class)?;
+		let after = instructions(&class, "x")?;
+		assert_eq!(after[..3], hello_world()?);
+		assert_eq!(after[3..], before);
+
+		Ok(())
+	}
+
+	#[test]
+	fn append_before_return() -> Result<()> {
+		let mut class = crate::read_class(&mut Cursor::new(include_bytes!("test/Point.class")))?;
+		let before = instructions(&class, "x")?;
+
+		let method = class.methods.iter_mut()
+			.find(|method| method.name.as_inner() == "x")
+			.context("no method x")?;
+		method.append_before_return(&hello_world()?)?;
+
+		let class = write_and_read(&class)?;
+		let after = instructions(&class, "x")?;
+		let mut expected = before.clone();
+		let last = expected.pop().context("no instructions")?;
+		assert_eq!(last, Instruction::IReturn);
+		expected.extend(hello_world()?);
+		expected.push(last);
+		assert_eq!(after, expected);
+
+		Ok(())
+	}
+
+	#[test]
+	fn splice_into_method_with_branches() -> Result<()> {
+		let mut class = crate::read_class(&mut Cursor::new(include_bytes!("test/StripDebugInfo.class")))?;
+		let before = class.methods.iter().find(|method| method.name.as_inner() == "sum").context("no method sum")?.clone();
+		let before_code = before.code.as_ref().context("no code")?;
+		let frames_before: Vec<_> = before_code.instructions.iter().filter_map(|entry| entry.frame.clone()).collect();
+		assert!(!frames_before.is_empty());
+
+		let method = class.methods.iter_mut()
+			.find(|method| method.name.as_inner() == "sum")
+			.context("no method sum")?;
+		method.prepend_instructions(&hello_world()?)?;
+		method.append_before_return(&hello_world()?)?;
+
+		// the class is written with the frames, as it's version 52
+		let class = write_and_read(&class)?;
+		let method = class.methods.iter().find(|method| method.name.as_inner() == "sum").context("no method sum")?;
+		let code = method.code.as_ref().context("no code")?;
+
+		let frames: Vec<_> = code.instructions.iter().filter_map(|entry| entry.frame.clone()).collect();
+		assert_eq!(frames, frames_before);
+
+		let instructions: Vec<_> = code.instructions.iter().map(|entry| entry.instruction.clone()).collect();
+		assert_eq!(instructions[..3], hello_world()?);
+		assert_eq!(instructions.len(), before_code.instructions.len() + 6);
+
+		// the parameter is still a local variable from the start of the code on
+		let local_variables = code.local_variables.as_ref().context("no local variables")?;
+		let list = local_variables.iter().find(|lv| lv.name.as_inner() == "list").context("no local variable list")?;
+		assert_eq!(code.instructions[0].label, Some(list.range.start));
+
+		// the inserted instructions are right before the return
+		let return_index = code.instructions.iter().position(|entry| entry.instruction == Instruction::IReturn).context("no ireturn")?;
+		assert_eq!(instructions[return_index - 3..return_index], hello_world()?);
+
+		// branching instructions can't be inserted
+		let mut class = crate::read_class(&mut Cursor::new(include_bytes!("test/StripDebugInfo.class")))?;
+		let method = class.methods.iter_mut()
+			.find(|method| method.name.as_inner() == "sum")
+			.context("no method sum")?;
+		assert!(method.prepend_instructions(&[Instruction::Goto(Label { id: 0 })]).is_err());
+
+		// neither can instructions changing the operand stack or the local variables of the original code
+		assert!(method.prepend_instructions(&[Instruction::IConst0]).is_err());
+		assert!(method.append_before_return(&[Instruction::Pop]).is_err());
+		assert!(method.prepend_instructions(&[Instruction::AConstNull, Instruction::AStore(LvIndex { index: 0 })]).is_err());
+		assert!(method.prepend_instructions(&[Instruction::Return]).is_err());
+
+		// but storing into a new local variable works, and increases the `max_locals`
+		let code = method.code.as_ref().context("no code")?;
+		let (max_stack, max_locals) = (code.max_stack.context("no max_stack")?, code.max_locals.context("no max_locals")?);
+		method.prepend_instructions(&[Instruction::LConst0, Instruction::LStore(LvIndex { index: max_locals })])?;
+		let code = method.code.as_ref().context("no code")?;
+		assert_eq!(code.max_locals, Some(max_locals + 2));
+		assert_eq!(code.max_stack, Some(max_stack + 2));
+
+		Ok(())
+	}
+
+	#[test]
+	fn type_annotations() -> Result<()> {
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("test/NonNullTypes.class")))?;
+		let method = class.methods.iter()
+			.find(|method| method.name.as_inner() == "method")
+			.context("no such method")?;
+
+		let non_null = FieldDescriptor::try_from(JavaStr::from_str("LNonNullTypes$NonNull;"))?;
+		let exception = ClassName::try_from(JavaStr::from_str("java/lang/Exception"))?;
+
+		let type_annotations = method.type_annotations();
+		assert!(type_annotations.iter().all(|type_annotation| type_annotation.visible && type_annotation.annotation.annotation_type == non_null));
+
+		let targets: Vec<_> = type_annotations.iter()
+			.map(|type_annotation| (type_annotation.target, type_annotation.path))
+			.collect();
+		assert_eq!(targets, [
+			// throws @NonNull Exception
+			(MethodTypeAnnotationTarget::Throws { index: 0, exception: Some(&exception) }, [].as_slice()),
+			// @NonNull String[]
+			(MethodTypeAnnotationTarget::Return, &[TypePathKind::ArrayDeeper]),
+			// Map<String, @NonNull List<String>>
+			(MethodTypeAnnotationTarget::Parameter { index: 0 }, &[TypePathKind::TypeArgument { index: 1 }]),
+		]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn set_line_numbers() -> Result<()> {
+		// int i = 0; (line 10)
+		// return; (line 12)
+		let mut builder = CodeBuilder::new();
+		let labels = [builder.new_label()?, builder.new_label()?, builder.new_label()?];
+		builder.bind_label(labels[0])?;
+		builder.push(Instruction::IConst0);
+		builder.bind_label(labels[1])?;
+		builder.push(Instruction::IStore(LvIndex { index: 0 }));
+		builder.bind_label(labels[2])?;
+		builder.push(Instruction::Return);
+
+		let mut method = Method::new(
+			MethodAccess::from(0x0009),
+			MethodName::try_from(JavaStr::from_str("lines"))?,
+			MethodDescriptor::try_from(JavaStr::from_str("()V"))?,
+		);
+		method.code = Some(builder.max_stack(1).max_locals(1).build()?);
+
+		assert!(method.set_line_numbers(&[(Label { id: 7 }, 1)]).is_err());
+		assert!(method.set_line_numbers(&[(labels[0], 1), (labels[0], 2)]).is_err());
+
+		method.set_line_numbers(&[(labels[2], 12), (labels[0], 10), (labels[1], 10)])?;
+
+		let mut class = ClassFile::new(Version::V1_8, ClassAccess::from(0x0021), ClassName::try_from(JavaStr::from_str("Lines"))?, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		class.methods.push(method);
+
+		let class = write_and_read(&class)?;
+		let code = class.methods[0].code.as_ref().context("no code")?;
+		let line_numbers = code.line_numbers.as_ref().context("no line numbers")?.iter()
+			.map(|&(label, line_number)| {
+				let index = code.instructions.iter().position(|entry| entry.label == Some(label)).context("label not on an instruction")?;
+				Ok((index, line_number))
+			})
+			.collect::<Result<Vec<_>>>()?;
+		assert_eq!(line_numbers, [(0, 10), (1, 10), (2, 12)]);
+
+		Ok(())
+	}
+}
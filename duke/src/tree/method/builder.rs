@@ -0,0 +1,283 @@
+//! Building [`Code`] by hand.
+//!
+//! See [`CodeBuilder`] for an example.
+
+use std::collections::{HashMap, HashSet};
+use anyhow::{anyhow, bail, Context, Result};
+use crate::tree::method::code::{Code, Exception, Instruction, InstructionListEntry, Label};
+use crate::visitor::method::code::StackMapData;
+
+/// A builder for the [`Code`] of a method.
+///
+/// Labels are created with [`new_label`][CodeBuilder::new_label], and can be used in instructions and exception handlers
+/// right away. Each label must be bound exactly once with [`bind_label`][CodeBuilder::bind_label], which places it in
+/// front of the next instruction added. This allows jumping both forwards and backwards:
+/// ```
+/// use duke::tree::method::builder::CodeBuilder;
+/// use duke::tree::method::code::{Instruction, LvIndex};
+///
+/// let mut builder = CodeBuilder::new();
+/// let start = builder.new_label()?;
+/// let end = builder.new_label()?;
+///
+/// // while (i != 0) { i--; }
+/// builder.bind_label(start)?;
+/// builder.push(Instruction::ILoad(LvIndex { index: 0 }));
+/// builder.push(Instruction::IfEq(end));
+/// builder.push(Instruction::IInc(LvIndex { index: 0 }, -1));
+/// builder.push(Instruction::Goto(start));
+/// builder.bind_label(end)?;
+/// builder.push(Instruction::Return);
+///
+/// let code = builder.max_stack(1).max_locals(1).build()?;
+/// assert_eq!(code.instructions.len(), 5);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// The offsets of the labels are only computed when writing the class.
+#[derive(Debug, Default, Clone)]
+pub struct CodeBuilder {
+	max_stack: Option<u16>,
+	max_locals: Option<u16>,
+	instructions: Vec<InstructionListEntry>,
+	exception_table: Vec<Exception>,
+	next_label: u16,
+	bound: HashSet<Label>,
+	/// The label bound in front of the next instruction.
+	pending: Option<Label>,
+	/// The stack map frame for the next instruction.
+	pending_frame: Option<StackMapData>,
+	/// Labels bound at the same position as another label, mapped to that label.
+	aliases: HashMap<Label, Label>,
+	line_numbers: Vec<(Label, u16)>,
+}
+
+impl CodeBuilder {
+	pub fn new() -> CodeBuilder {
+		CodeBuilder::default()
+	}
+
+	/// Sets the maximum depth of the operand stack.
+	pub fn max_stack(&mut self, max_stack: u16) -> &mut Self {
+		self.max_stack = Some(max_stack);
+		self
+	}
+
+	/// Sets the number of local variables, where `long` and `double` values take up two.
+	pub fn max_locals(&mut self, max_locals: u16) -> &mut Self {
+		self.max_locals = Some(max_locals);
+		self
+	}
+
+	/// Creates a new label, which isn't yet bound to a position.
+	pub fn new_label(&mut self) -> Result<Label> {
+		let id = self.next_label;
+		self.next_label = self.next_label.checked_add(1).context("too many labels")?;
+		Ok(Label { id })
+	}
+
+	/// Binds the label to the current position, so that it refers to the next instruction added.
+	///
+	/// If no instruction is added afterwards, the label refers to the end of the code, see [`Code::last_label`].
+	pub fn bind_label(&mut self, label: Label) -> Result<()> {
+		if label.id >= self.next_label {
+			bail!("label {label:?} wasn't created by this builder");
+		}
+		if !self.bound.insert(label) {
+			bail!("label {label:?} is already bound");
+		}
+
+		if let Some(pending) = self.pending {
+			self.aliases.insert(label, pending);
+		} else {
+			self.pending = Some(label);
+		}
+		Ok(())
+	}
+
+	/// Sets the stack map frame of the next instruction added.
+	///
+	/// Code for class file version 50 or later needs a frame at each branch target and exception handler, as frames aren't
+	/// computed when writing the class.
+	pub fn frame(&mut self, frame: StackMapData) -> &mut Self {
+		self.pending_frame = Some(frame);
+		self
+	}
+
+	/// Adds an instruction at the current position.
+	pub fn push(&mut self, instruction: Instruction) -> &mut Self {
+		self.instructions.push(InstructionListEntry {
+			label: self.pending.take(),
+			frame: self.pending_frame.take(),
+			offset: None,
+			instruction,
+		});
+		self
+	}
+
+	/// Adds an entry to the exception table.
+	///
+	/// Entries added earlier take precedence, as the JVM searches the exception table in order.
+	pub fn add_exception(&mut self, exception: Exception) -> &mut Self {
+		self.exception_table.push(exception);
+		self
+	}
+
+	/// Sets the line number of the instructions starting at the label.
+	///
+	/// Unlike [`Code::set_line_numbers`], this also accepts labels bound at the same position as another label. See there
+	/// for details.
+	pub fn line_number(&mut self, label: Label, line_number: u16) -> &mut Self {
+		self.line_numbers.push((label, line_number));
+		self
+	}
+
+	fn resolve(&self, label: &mut Label) -> Result<()> {
+		if !self.bound.contains(label) {
+			bail!("label {label:?} is used, but never bound");
+		}
+		if let Some(&target) = self.aliases.get(label) {
+			*label = target;
+		}
+		Ok(())
+	}
+
+	/// Builds the code, checking that every label used is also bound.
+	pub fn build(&self) -> Result<Code> {
+		let mut instructions = self.instructions.clone();
+		for (index, entry) in instructions.iter_mut().enumerate() {
+			for label in entry.instruction.jump_targets_mut() {
+				self.resolve(label)
+					.with_context(|| anyhow!("in instruction {index}"))?;
+			}
+		}
+
+		let mut exception_table = self.exception_table.clone();
+		for (index, Exception { start, end, handler, .. }) in exception_table.iter_mut().enumerate() {
+			for label in [start, end, handler] {
+				self.resolve(label)
+					.with_context(|| anyhow!("in exception table entry {index}"))?;
+			}
+		}
+
+		let mut line_numbers = self.line_numbers.clone();
+		for (label, line_number) in &mut line_numbers {
+			self.resolve(label)
+				.with_context(|| anyhow!("in line number {line_number}"))?;
+		}
+
+		let mut code = Code {
+			max_stack: self.max_stack,
+			max_locals: self.max_locals,
+			instructions,
+			exception_table,
+			last_label: self.pending,
+			..Code::default()
+		};
+		code.set_line_numbers(&line_numbers)?;
+		Ok(code)
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::{Context, Result};
+	use pretty_assertions::assert_eq;
+	use java_string::JavaStr;
+	use crate::tree::class::{ClassAccess, ClassFile, ClassName};
+	use crate::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName};
+	use crate::tree::method::builder::CodeBuilder;
+	use crate::tree::method::code::{Instruction, LvIndex};
+	use crate::tree::version::Version;
+	use crate::visitor::method::code::{StackMapData, VerificationTypeInfo};
+
+	#[test]
+	fn forward_and_backward_branches() -> Result<()> {
+		// for (int i = 0; i < 10; i++) {}
+		let mut builder = CodeBuilder::new();
+		let condition = builder.new_label()?;
+		let end = builder.new_label()?;
+		let also_end = builder.new_label()?;
+
+		builder.push(Instruction::IConst0);
+		builder.push(Instruction::IStore(LvIndex { index: 0 }));
+		builder.bind_label(condition)?;
+		builder.frame(StackMapData::Append { locals: vec![VerificationTypeInfo::Integer] });
+		builder.push(Instruction::ILoad(LvIndex { index: 0 }));
+		builder.push(Instruction::BiPush(10));
+		builder.push(Instruction::IfICmpGe(also_end));
+		builder.push(Instruction::IInc(LvIndex { index: 0 }, 1));
+		builder.push(Instruction::Goto(condition));
+		builder.bind_label(end)?;
+		builder.bind_label(also_end)?;
+		builder.frame(StackMapData::Same);
+		builder.push(Instruction::Return);
+
+		let mut method = Method::new(
+			MethodAccess::from(0x0009),
+			MethodName::try_from(JavaStr::from_str("loop"))?,
+			MethodDescriptor::try_from(JavaStr::from_str("()V"))?,
+		);
+		method.code = Some(builder.max_stack(2).max_locals(1).build()?);
+
+		let mut class = ClassFile::new(Version::V1_6, ClassAccess::default(), ClassName::try_from(JavaStr::from_str("Loop"))?, None, Vec::new());
+		class.methods.push(method);
+
+		let mut bytes = Vec::new();
+		crate::write_class(&mut bytes, &class)?;
+		let class = crate::read_class(&mut Cursor::new(bytes))?;
+
+		let code = class.methods[0].code.as_ref().context("no code")?;
+		let label_at = |index: usize| code.instructions[index].label.context("no label");
+
+		assert_eq!(code.instructions[4].instruction, Instruction::IfICmpGe(label_at(7)?));
+		assert_eq!(code.instructions[6].instruction, Instruction::Goto(label_at(2)?));
+		assert_eq!(code.instructions[2].frame, Some(StackMapData::Append { locals: vec![VerificationTypeInfo::Integer] }));
+		assert_eq!(code.instructions[7].frame, Some(StackMapData::Same));
+
+		Ok(())
+	}
+
+	#[test]
+	fn unbound_label() -> Result<()> {
+		let mut builder = CodeBuilder::new();
+		let label = builder.new_label()?;
+		builder.push(Instruction::Goto(label));
+		assert!(builder.build().is_err());
+
+		builder.bind_label(label)?;
+		assert!(builder.bind_label(label).is_err());
+		assert_eq!(builder.build()?.last_label, Some(label));
+
+		Ok(())
+	}
+
+	#[test]
+	fn line_numbers() -> Result<()> {
+		let mut builder = CodeBuilder::new();
+		let start = builder.new_label()?;
+		let also_start = builder.new_label()?;
+		let end = builder.new_label()?;
+		builder.bind_label(start)?;
+		builder.bind_label(also_start)?;
+		builder.push(Instruction::IConst0);
+		builder.push(Instruction::IStore(LvIndex { index: 0 }));
+		builder.bind_label(end)?;
+		builder.push(Instruction::Return);
+
+		builder.line_number(end, 2).line_number(also_start, 1);
+		let code = builder.build()?;
+		assert_eq!(code.line_numbers, Some(vec![(start, 1), (end, 2)]));
+
+		// without the builder, only the label placed on the instruction can be used
+		let mut code = code;
+		assert!(code.set_line_numbers(&[(also_start, 1)]).is_err());
+
+		// the same position can't get two different lines
+		builder.line_number(start, 3);
+		assert!(builder.build().is_err());
+
+		Ok(())
+	}
+}
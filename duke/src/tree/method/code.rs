@@ -1,12 +1,15 @@
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use java_string::{JavaStr, JavaString};
-use crate::class_constants::atype;
+use crate::class_constants::{atype, attribute};
 use crate::macros::{make_display, make_string_str_like};
 use crate::tree::attribute::Attribute;
 use crate::tree::class::ClassName;
+use crate::tree::descriptor::Type;
 use crate::tree::field::{FieldDescriptor, FieldName, FieldRef, FieldSignature};
 use crate::tree::method::{MethodDescriptor, MethodName, MethodRef};
+use crate::tree::method::raw_code::RawCode;
 use crate::tree::type_annotation::{TargetInfoCode, TypeAnnotation};
 use crate::visitor::attribute::UnknownAttributeVisitor;
 use crate::visitor::method::code::{CodeVisitor, StackMapData};
@@ -16,6 +19,11 @@ use crate::visitor::method::MethodVisitor;
 pub struct InstructionListEntry {
 	pub label: Option<Label>,
 	pub frame: Option<StackMapData>,
+	/// The offset of the instruction in the bytecode it was read from.
+	///
+	/// This is only present if the code was read with the [`instruction_offsets`][crate::visitor::method::code::CodeInterests::instruction_offsets]
+	/// interest, and is [`None`] for instructions added afterwards. The writer ignores it, as it computes the offsets again.
+	pub offset: Option<u16>,
 	pub instruction: Instruction,
 }
 
@@ -36,9 +44,120 @@ pub struct Code {
 	pub runtime_invisible_type_annotations: Vec<TypeAnnotation<TargetInfoCode>>,
 
 	pub attributes: Vec<Attribute>,
+
+	/// The code as read with the [`raw_code_only`][crate::visitor::method::code::CodeInterests::raw_code_only] interest.
+	///
+	/// If this is set, it's written instead of all the other fields. See [`RawCode`] for details.
+	pub raw: Option<RawCode>,
 }
 
 impl Code {
+	/// Gets a label id not used by any instruction of this code.
+	///
+	/// All labels used by the code, like the targets of jumps or the ranges of local variables, must be placed on an
+	/// instruction or be the [`last_label`][Code::last_label], so a label after all of these is unused.
+	pub(crate) fn next_label_id(&self) -> Result<u16> {
+		let max = self.instructions.iter()
+			.filter_map(|entry| entry.label)
+			.chain(self.last_label)
+			.map(|label| label.id)
+			.max();
+		match max {
+			Some(max) => max.checked_add(1).context("no unused label left"),
+			None => Ok(0),
+		}
+	}
+
+	/// Sets the `LineNumberTable` of this code, replacing the one already there.
+	///
+	/// Each entry gives the line number of the instructions starting at the instruction with the label. Multiple labels can
+	/// have the same line number, for example if the instructions of a line are split up by a jump target. The entries are
+	/// stored in the order of the instructions, and an empty slice removes the `LineNumberTable`.
+	///
+	/// The labels must be placed on instructions. A label a [`CodeBuilder`][crate::tree::method::builder::CodeBuilder] bound
+	/// at the same position as another label isn't placed on any instruction of the built code, so use
+	/// [`CodeBuilder::line_number`][crate::tree::method::builder::CodeBuilder::line_number] for such labels instead.
+	///
+	/// Returns an error if this is [raw code][RawCode], if a label isn't placed on an instruction, or if a label is given
+	/// different line numbers.
+	pub fn set_line_numbers(&mut self, entries: &[(Label, u16)]) -> Result<()> {
+		if self.raw.is_some() {
+			bail!("cannot set the line numbers of raw code");
+		}
+
+		let indices: HashMap<Label, usize> = self.instructions.iter()
+			.enumerate()
+			.filter_map(|(index, entry)| entry.label.map(|label| (label, index)))
+			.collect();
+
+		let mut line_numbers: HashMap<Label, (usize, u16)> = HashMap::with_capacity(entries.len());
+		for &(label, line_number) in entries {
+			let &index = indices.get(&label)
+				.with_context(|| anyhow!("label {label:?} for line {line_number} isn't placed on any instruction"))?;
+
+			if let Some(&(_, other_line_number)) = line_numbers.get(&label) {
+				if other_line_number != line_number {
+					bail!("label {label:?} is given both line {other_line_number} and line {line_number}");
+				}
+			}
+			line_numbers.insert(label, (index, line_number));
+		}
+
+		let mut line_numbers: Vec<_> = line_numbers.into_iter().collect();
+		line_numbers.sort_by_key(|&(_, (index, _))| index);
+
+		self.line_numbers = if line_numbers.is_empty() {
+			None
+		} else {
+			Some(line_numbers.into_iter().map(|(label, (_, line_number))| (label, line_number)).collect())
+		};
+
+		Ok(())
+	}
+
+	/// Removes all attributes for which `predicate` returns `true`.
+	///
+	/// The `StackMapTable` attribute is stored in the [`frame`][InstructionListEntry::frame]s of the instructions, and can't be
+	/// removed this way. Since the [`local_variables`][Code::local_variables] store both the `LocalVariableTable` and the
+	/// `LocalVariableTypeTable`, removing one of them only removes the descriptor or signature of each entry.
+	///
+	/// Returns whether any attribute was removed.
+	pub fn remove_attributes(&mut self, predicate: impl Fn(&JavaStr) -> bool) -> bool {
+		let mut removed = false;
+
+		if predicate(attribute::LINE_NUMBER_TABLE) {
+			removed |= self.line_numbers.take().is_some();
+		}
+		if let Some(local_variables) = &mut self.local_variables {
+			let remove_descriptors = predicate(attribute::LOCAL_VARIABLE_TABLE);
+			let remove_signatures = predicate(attribute::LOCAL_VARIABLE_TYPE_TABLE);
+
+			for lv in local_variables.iter_mut() {
+				if remove_descriptors {
+					removed |= lv.descriptor.take().is_some();
+				}
+				if remove_signatures {
+					removed |= lv.signature.take().is_some();
+				}
+			}
+			local_variables.retain(|lv| lv.descriptor.is_some() || lv.signature.is_some());
+			if local_variables.is_empty() {
+				self.local_variables = None;
+			}
+		}
+		if predicate(attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_visible_type_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_invisible_type_annotations).is_empty();
+		}
+		let len = self.attributes.len();
+		self.attributes.retain(|attribute| !predicate(&attribute.name));
+		removed |= self.attributes.len() != len;
+
+		removed
+	}
+
 	pub(crate) fn accept<M>(self, mut visitor: M) -> Result<M>
 	where
 		M: MethodVisitor,
@@ -46,6 +165,13 @@ impl Code {
 		if let Some(mut code_visitor) = visitor.visit_code()? {
 			let interests = code_visitor.interests();
 
+			// Raw code can't be turned into instructions without resolving it against the constant pool it was read from.
+			if let Some(raw) = self.raw {
+				code_visitor.visit_raw_code(raw)?;
+				visitor.finish_code(code_visitor)?;
+				return Ok(visitor);
+			}
+
 			if let (Some(max_stack), Some(max_locals)) = (self.max_stack, self.max_locals) {
 				code_visitor.visit_max_stack_and_max_locals(max_stack, max_locals)?;
 			}
@@ -53,7 +179,12 @@ impl Code {
 			// TODO: interests.stack_map_table
 			//  also the question: should CodeInterests have a field for "instructions"?
 			for instruction in self.instructions {
-				code_visitor.visit_instruction(instruction.label, instruction.frame, instruction.instruction)?;
+				match instruction.offset {
+					Some(offset) if interests.instruction_offsets => {
+						code_visitor.visit_instruction_with_offset(offset, instruction.label, instruction.frame, instruction.instruction)?;
+					},
+					_ => code_visitor.visit_instruction(instruction.label, instruction.frame, instruction.instruction)?,
+				}
 			}
 			code_visitor.visit_exception_table(self.exception_table)?;
 			if let Some(last_label) = self.last_label {
@@ -166,6 +297,13 @@ pub struct Label {
 	pub(crate) id: u16,
 }
 
+/// Formats the label as `L` followed by its id, like `L3`.
+impl Display for Label {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "L{}", self.id)
+	}
+}
+
 /// Represents a range of bytecode offsets.
 #[derive(Debug, Clone, PartialEq)]
 pub struct LabelRange {
@@ -268,6 +406,370 @@ pub enum Instruction {
 	IfNull(Label), IfNonNull(Label),
 }
 
+impl Instruction {
+	/// Gets all the labels an instruction jumps to.
+	pub(crate) fn jump_targets(&self) -> Vec<&Label> {
+		match self {
+			Instruction::IfEq(label) | Instruction::IfNe(label) | Instruction::IfLt(label) |
+			Instruction::IfGe(label) | Instruction::IfGt(label) | Instruction::IfLe(label) |
+			Instruction::IfICmpEq(label) | Instruction::IfICmpNe(label) | Instruction::IfICmpLt(label) |
+			Instruction::IfICmpGe(label) | Instruction::IfICmpGt(label) | Instruction::IfICmpLe(label) |
+			Instruction::IfACmpEq(label) | Instruction::IfACmpNe(label) |
+			Instruction::Goto(label) | Instruction::Jsr(label) |
+			Instruction::IfNull(label) | Instruction::IfNonNull(label) => vec![label],
+			Instruction::TableSwitch { default, table, .. } => std::iter::once(default).chain(table).collect(),
+			Instruction::LookupSwitch { default, pairs } => std::iter::once(default).chain(pairs.iter().map(|(_, label)| label)).collect(),
+			_ => Vec::new(),
+		}
+	}
+
+	/// Gets mutable references to all the labels an instruction jumps to.
+	pub(crate) fn jump_targets_mut(&mut self) -> Vec<&mut Label> {
+		match self {
+			Instruction::IfEq(label) | Instruction::IfNe(label) | Instruction::IfLt(label) |
+			Instruction::IfGe(label) | Instruction::IfGt(label) | Instruction::IfLe(label) |
+			Instruction::IfICmpEq(label) | Instruction::IfICmpNe(label) | Instruction::IfICmpLt(label) |
+			Instruction::IfICmpGe(label) | Instruction::IfICmpGt(label) | Instruction::IfICmpLe(label) |
+			Instruction::IfACmpEq(label) | Instruction::IfACmpNe(label) |
+			Instruction::Goto(label) | Instruction::Jsr(label) |
+			Instruction::IfNull(label) | Instruction::IfNonNull(label) => vec![label],
+			Instruction::TableSwitch { default, table, .. } => std::iter::once(default).chain(table).collect(),
+			Instruction::LookupSwitch { default, pairs } => std::iter::once(default).chain(pairs.iter_mut().map(|(_, label)| label)).collect(),
+			_ => Vec::new(),
+		}
+	}
+
+	/// Checks if this instruction branches, that is if it's a jump, a switch, `jsr` or `ret`.
+	pub fn is_branch(&self) -> bool {
+		matches!(self,
+			Instruction::IfEq(_) | Instruction::IfNe(_) | Instruction::IfLt(_) | Instruction::IfGe(_) | Instruction::IfGt(_) | Instruction::IfLe(_) |
+			Instruction::IfICmpEq(_) | Instruction::IfICmpNe(_) | Instruction::IfICmpLt(_) | Instruction::IfICmpGe(_) | Instruction::IfICmpGt(_) | Instruction::IfICmpLe(_) |
+			Instruction::IfACmpEq(_) | Instruction::IfACmpNe(_) | Instruction::IfNull(_) | Instruction::IfNonNull(_) |
+			Instruction::Goto(_) | Instruction::Jsr(_) | Instruction::Ret(_) | Instruction::TableSwitch { .. } | Instruction::LookupSwitch { .. }
+		)
+	}
+
+	/// Gets the number of operand stack slots this instruction pops, and the number it pushes afterwards.
+	///
+	/// A `long` or a `double` takes two slots, everything else one. For instructions referencing a field or a method, the
+	/// descriptor is parsed, which fails if it's invalid. The `athrow` instruction is counted as only popping the exception,
+	/// and `jsr` as pushing the return address.
+	pub(crate) fn stack_effect(&self) -> Result<(u16, u16)> {
+		fn size(descriptor: &Type) -> u16 {
+			if matches!(descriptor, Type::J | Type::D) { 2 } else { 1 }
+		}
+		fn method_effect(desc: &MethodDescriptor, receiver: u16) -> Result<(u16, u16)> {
+			let desc = desc.parse()?;
+			let arguments: u16 = desc.parameter_descriptors.iter().map(size).sum();
+			Ok((receiver + arguments, desc.return_descriptor.as_ref().map_or(0, size)))
+		}
+		fn field_size(desc: &FieldDescriptor) -> Result<u16> {
+			Ok(size(&desc.parse()?.0))
+		}
+
+		Ok(match self {
+			Instruction::Nop | Instruction::IInc(..) | Instruction::Goto(_) | Instruction::Ret(_) | Instruction::Return => (0, 0),
+			Instruction::AConstNull |
+			Instruction::IConstM1 | Instruction::IConst0 | Instruction::IConst1 | Instruction::IConst2 | Instruction::IConst3 |
+			Instruction::IConst4 | Instruction::IConst5 | Instruction::FConst0 | Instruction::FConst1 | Instruction::FConst2 |
+			Instruction::BiPush(_) | Instruction::SiPush(_) |
+			Instruction::ILoad(_) | Instruction::FLoad(_) | Instruction::ALoad(_) |
+			Instruction::Jsr(_) | Instruction::New(_) => (0, 1),
+			Instruction::LConst0 | Instruction::LConst1 | Instruction::DConst0 | Instruction::DConst1 |
+			Instruction::LLoad(_) | Instruction::DLoad(_) => (0, 2),
+			Instruction::Ldc(Loadable::Long(_) | Loadable::Double(_)) => (0, 2),
+			Instruction::Ldc(Loadable::Dynamic(dynamic)) => (0, field_size(&dynamic.descriptor)?),
+			Instruction::Ldc(_) => (0, 1),
+			Instruction::IALoad | Instruction::FALoad | Instruction::AALoad | Instruction::BALoad | Instruction::CALoad |
+			Instruction::SALoad => (2, 1),
+			Instruction::LALoad | Instruction::DALoad => (2, 2),
+			Instruction::IStore(_) | Instruction::FStore(_) | Instruction::AStore(_) => (1, 0),
+			Instruction::LStore(_) | Instruction::DStore(_) => (2, 0),
+			Instruction::IAStore | Instruction::FAStore | Instruction::AAStore | Instruction::BAStore | Instruction::CAStore |
+			Instruction::SAStore => (3, 0),
+			Instruction::LAStore | Instruction::DAStore => (4, 0),
+			Instruction::Pop => (1, 0),
+			Instruction::Pop2 => (2, 0),
+			Instruction::Dup => (1, 2),
+			Instruction::DupX1 => (2, 3),
+			Instruction::DupX2 => (3, 4),
+			Instruction::Dup2 => (2, 4),
+			Instruction::Dup2X1 => (3, 5),
+			Instruction::Dup2X2 => (4, 6),
+			Instruction::Swap => (2, 2),
+			Instruction::IAdd | Instruction::ISub | Instruction::IMul | Instruction::IDiv | Instruction::IRem |
+			Instruction::IShl | Instruction::IShr | Instruction::IUShr | Instruction::IAnd | Instruction::IOr | Instruction::IXor |
+			Instruction::FAdd | Instruction::FSub | Instruction::FMul | Instruction::FDiv | Instruction::FRem |
+			Instruction::FCmpL | Instruction::FCmpG => (2, 1),
+			Instruction::LAdd | Instruction::LSub | Instruction::LMul | Instruction::LDiv | Instruction::LRem |
+			Instruction::LAnd | Instruction::LOr | Instruction::LXor |
+			Instruction::DAdd | Instruction::DSub | Instruction::DMul | Instruction::DDiv | Instruction::DRem => (4, 2),
+			Instruction::LShl | Instruction::LShr | Instruction::LUShr => (3, 2),
+			Instruction::INeg | Instruction::FNeg | Instruction::I2F | Instruction::F2I |
+			Instruction::I2B | Instruction::I2C | Instruction::I2S => (1, 1),
+			Instruction::LNeg | Instruction::DNeg | Instruction::L2D | Instruction::D2L => (2, 2),
+			Instruction::I2L | Instruction::I2D | Instruction::F2L | Instruction::F2D => (1, 2),
+			Instruction::L2I | Instruction::L2F | Instruction::D2I | Instruction::D2F => (2, 1),
+			Instruction::LCmp | Instruction::DCmpL | Instruction::DCmpG => (4, 1),
+			Instruction::IfEq(_) | Instruction::IfNe(_) | Instruction::IfLt(_) | Instruction::IfGe(_) | Instruction::IfGt(_) |
+			Instruction::IfLe(_) | Instruction::IfNull(_) | Instruction::IfNonNull(_) |
+			Instruction::TableSwitch { .. } | Instruction::LookupSwitch { .. } => (1, 0),
+			Instruction::IfICmpEq(_) | Instruction::IfICmpNe(_) | Instruction::IfICmpLt(_) | Instruction::IfICmpGe(_) |
+			Instruction::IfICmpGt(_) | Instruction::IfICmpLe(_) | Instruction::IfACmpEq(_) | Instruction::IfACmpNe(_) => (2, 0),
+			Instruction::IReturn | Instruction::FReturn | Instruction::AReturn | Instruction::AThrow => (1, 0),
+			Instruction::LReturn | Instruction::DReturn => (2, 0),
+			Instruction::GetStatic(field) => (0, field_size(&field.desc)?),
+			Instruction::PutStatic(field) => (field_size(&field.desc)?, 0),
+			Instruction::GetField(field) => (1, field_size(&field.desc)?),
+			Instruction::PutField(field) => (1 + field_size(&field.desc)?, 0),
+			Instruction::InvokeVirtual(method) | Instruction::InvokeSpecial(method, _) |
+			Instruction::InvokeInterface(method) => method_effect(&method.desc, 1)?,
+			Instruction::InvokeStatic(method, _) => method_effect(&method.desc, 0)?,
+			Instruction::InvokeDynamic(invoke_dynamic) => method_effect(&invoke_dynamic.descriptor, 0)?,
+			Instruction::NewArray(_) | Instruction::ANewArray(_) | Instruction::ArrayLength | Instruction::CheckCast(_) |
+			Instruction::InstanceOf(_) => (1, 1),
+			Instruction::MonitorEnter | Instruction::MonitorExit => (1, 0),
+			Instruction::MultiANewArray(_, dimensions) => ((*dimensions).into(), 1),
+		})
+	}
+
+	/// Gets the name of the opcode of this instruction, as used by `javap`.
+	///
+	/// Instructions using a local variable are always given with the general opcode, so [`Instruction::ILoad`] gives
+	/// `iload`, and not `iload_0`. The same goes for `ldc`, which is never given as `ldc_w` or `ldc2_w`.
+	pub fn mnemonic(&self) -> &'static str {
+		use Instruction::*;
+		match self {
+			Nop => "nop",
+			AConstNull => "aconst_null",
+			IConstM1 => "iconst_m1",
+			IConst0 => "iconst_0",
+			IConst1 => "iconst_1",
+			IConst2 => "iconst_2",
+			IConst3 => "iconst_3",
+			IConst4 => "iconst_4",
+			IConst5 => "iconst_5",
+			LConst0 => "lconst_0",
+			LConst1 => "lconst_1",
+			FConst0 => "fconst_0",
+			FConst1 => "fconst_1",
+			FConst2 => "fconst_2",
+			DConst0 => "dconst_0",
+			DConst1 => "dconst_1",
+			BiPush(_) => "bipush",
+			SiPush(_) => "sipush",
+			Ldc(_) => "ldc",
+			ILoad(_) => "iload",
+			LLoad(_) => "lload",
+			FLoad(_) => "fload",
+			DLoad(_) => "dload",
+			ALoad(_) => "aload",
+			IALoad => "iaload",
+			LALoad => "laload",
+			FALoad => "faload",
+			DALoad => "daload",
+			AALoad => "aaload",
+			BALoad => "baload",
+			CALoad => "caload",
+			SALoad => "saload",
+			IStore(_) => "istore",
+			LStore(_) => "lstore",
+			FStore(_) => "fstore",
+			DStore(_) => "dstore",
+			AStore(_) => "astore",
+			IAStore => "iastore",
+			LAStore => "lastore",
+			FAStore => "fastore",
+			DAStore => "dastore",
+			AAStore => "aastore",
+			BAStore => "bastore",
+			CAStore => "castore",
+			SAStore => "sastore",
+			Pop => "pop",
+			Pop2 => "pop2",
+			Dup => "dup",
+			DupX1 => "dup_x1",
+			DupX2 => "dup_x2",
+			Dup2 => "dup2",
+			Dup2X1 => "dup2_x1",
+			Dup2X2 => "dup2_x2",
+			Swap => "swap",
+			IAdd => "iadd",
+			LAdd => "ladd",
+			FAdd => "fadd",
+			DAdd => "dadd",
+			ISub => "isub",
+			LSub => "lsub",
+			FSub => "fsub",
+			DSub => "dsub",
+			IMul => "imul",
+			LMul => "lmul",
+			FMul => "fmul",
+			DMul => "dmul",
+			IDiv => "idiv",
+			LDiv => "ldiv",
+			FDiv => "fdiv",
+			DDiv => "ddiv",
+			IRem => "irem",
+			LRem => "lrem",
+			FRem => "frem",
+			DRem => "drem",
+			INeg => "ineg",
+			LNeg => "lneg",
+			FNeg => "fneg",
+			DNeg => "dneg",
+			IShl => "ishl",
+			LShl => "lshl",
+			IShr => "ishr",
+			LShr => "lshr",
+			IUShr => "iushr",
+			LUShr => "lushr",
+			IAnd => "iand",
+			LAnd => "land",
+			IOr => "ior",
+			LOr => "lor",
+			IXor => "ixor",
+			LXor => "lxor",
+			IInc(_, _) => "iinc",
+			I2L => "i2l",
+			I2F => "i2f",
+			I2D => "i2d",
+			L2I => "l2i",
+			L2F => "l2f",
+			L2D => "l2d",
+			F2I => "f2i",
+			F2L => "f2l",
+			F2D => "f2d",
+			D2I => "d2i",
+			D2L => "d2l",
+			D2F => "d2f",
+			I2B => "i2b",
+			I2C => "i2c",
+			I2S => "i2s",
+			LCmp => "lcmp",
+			FCmpL => "fcmpl",
+			FCmpG => "fcmpg",
+			DCmpL => "dcmpl",
+			DCmpG => "dcmpg",
+			IfEq(_) => "ifeq",
+			IfNe(_) => "ifne",
+			IfLt(_) => "iflt",
+			IfGe(_) => "ifge",
+			IfGt(_) => "ifgt",
+			IfLe(_) => "ifle",
+			IfICmpEq(_) => "if_icmpeq",
+			IfICmpNe(_) => "if_icmpne",
+			IfICmpLt(_) => "if_icmplt",
+			IfICmpGe(_) => "if_icmpge",
+			IfICmpGt(_) => "if_icmpgt",
+			IfICmpLe(_) => "if_icmple",
+			IfACmpEq(_) => "if_acmpeq",
+			IfACmpNe(_) => "if_acmpne",
+			Goto(_) => "goto",
+			Jsr(_) => "jsr",
+			Ret(_) => "ret",
+			TableSwitch { .. } => "tableswitch",
+			LookupSwitch { .. } => "lookupswitch",
+			IReturn => "ireturn",
+			LReturn => "lreturn",
+			FReturn => "freturn",
+			DReturn => "dreturn",
+			AReturn => "areturn",
+			Return => "return",
+			GetStatic(_) => "getstatic",
+			PutStatic(_) => "putstatic",
+			GetField(_) => "getfield",
+			PutField(_) => "putfield",
+			InvokeVirtual(_) => "invokevirtual",
+			InvokeSpecial(_, _) => "invokespecial",
+			InvokeStatic(_, _) => "invokestatic",
+			InvokeInterface(_) => "invokeinterface",
+			InvokeDynamic(_) => "invokedynamic",
+			New(_) => "new",
+			NewArray(_) => "newarray",
+			ANewArray(_) => "anewarray",
+			ArrayLength => "arraylength",
+			AThrow => "athrow",
+			CheckCast(_) => "checkcast",
+			InstanceOf(_) => "instanceof",
+			MonitorEnter => "monitorenter",
+			MonitorExit => "monitorexit",
+			MultiANewArray(_, _) => "multianewarray",
+			IfNull(_) => "ifnull",
+			IfNonNull(_) => "ifnonnull",
+		}
+	}
+}
+
+/// Formats the instruction similar to `javap`, as the [mnemonic][Instruction::mnemonic] followed by the operands.
+///
+/// Labels are written as `L` followed by their id, see [`Label`]. Field and method references are written as the owner,
+/// followed by a `.`, the name, and the descriptor, like `java/lang/Object.<init>()V`.
+impl Display for Instruction {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		use Instruction::*;
+		f.write_str(self.mnemonic())?;
+		match self {
+			BiPush(value) => write!(f, " {value}"),
+			SiPush(value) => write!(f, " {value}"),
+			Ldc(loadable) => write!(f, " {loadable}"),
+			ILoad(lv) | LLoad(lv) | FLoad(lv) | DLoad(lv) | ALoad(lv) |
+			IStore(lv) | LStore(lv) | FStore(lv) | DStore(lv) | AStore(lv) |
+			Ret(lv) => write!(f, " {}", lv.index),
+			IInc(lv, value) => write!(f, " {} {value}", lv.index),
+			IfEq(label) | IfNe(label) | IfLt(label) | IfGe(label) | IfGt(label) | IfLe(label) |
+			IfICmpEq(label) | IfICmpNe(label) | IfICmpLt(label) | IfICmpGe(label) | IfICmpGt(label) | IfICmpLe(label) |
+			IfACmpEq(label) | IfACmpNe(label) |
+			Goto(label) | Jsr(label) |
+			IfNull(label) | IfNonNull(label) => write!(f, " {label}"),
+			TableSwitch { default, low, high, table } => {
+				write!(f, " {{ {low}..={high}:")?;
+				for label in table {
+					write!(f, " {label}")?;
+				}
+				write!(f, ", default: {default} }}")
+			},
+			LookupSwitch { default, pairs } => {
+				f.write_str(" {")?;
+				for (key, label) in pairs {
+					write!(f, " {key}: {label},")?;
+				}
+				write!(f, " default: {default} }}")
+			},
+			GetStatic(field_ref) | PutStatic(field_ref) | GetField(field_ref) | PutField(field_ref) => {
+				write!(f, " {}.{}:{}", field_ref.class, field_ref.name, field_ref.desc)
+			},
+			InvokeVirtual(method_ref) | InvokeSpecial(method_ref, _) | InvokeStatic(method_ref, _) | InvokeInterface(method_ref) => {
+				write!(f, " {}.{}{}", method_ref.class, method_ref.name, method_ref.desc)
+			},
+			InvokeDynamic(invoke_dynamic) => {
+				write!(f, " {}{} {}", invoke_dynamic.name, invoke_dynamic.descriptor, invoke_dynamic.handle)
+			},
+			New(class) | ANewArray(class) | CheckCast(class) | InstanceOf(class) => write!(f, " {class}"),
+			NewArray(array_type) => write!(f, " {}", array_type.name()),
+			MultiANewArray(class, dimensions) => write!(f, " {class} {dimensions}"),
+			_ => Ok(()),
+		}
+	}
+}
+
+/// Formats the value like in `ldc` instructions, with strings in quotes.
+impl Display for Loadable {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Loadable::Integer(value) => write!(f, "{value}"),
+			Loadable::Float(value) => write!(f, "{value}f"),
+			Loadable::Long(value) => write!(f, "{value}l"),
+			Loadable::Double(value) => write!(f, "{value}d"),
+			Loadable::Class(class) => write!(f, "class {class}"),
+			Loadable::String(string) => write!(f, "{string:?}"),
+			Loadable::MethodHandle(handle) => write!(f, "{handle}"),
+			Loadable::MethodType(desc) => write!(f, "{desc}"),
+			Loadable::Dynamic(dynamic) => write!(f, "dynamic {}:{} {}", dynamic.name, dynamic.descriptor, dynamic.handle),
+		}
+	}
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Loadable {
 	Integer(i32),
@@ -295,6 +797,34 @@ pub enum Handle {
 	InvokeInterface(MethodRef),
 }
 
+/// Formats the handle as its kind, followed by the referenced field or method.
+impl Display for Handle {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Handle::GetField(field_ref) | Handle::GetStatic(field_ref) | Handle::PutField(field_ref) | Handle::PutStatic(field_ref) => {
+				let kind = match self {
+					Handle::GetField(_) => "getfield",
+					Handle::GetStatic(_) => "getstatic",
+					Handle::PutField(_) => "putfield",
+					_ => "putstatic",
+				};
+				write!(f, "{kind} {}.{}:{}", field_ref.class, field_ref.name, field_ref.desc)
+			},
+			Handle::InvokeVirtual(method_ref) | Handle::InvokeStatic(method_ref, _) | Handle::InvokeSpecial(method_ref, _) |
+			Handle::NewInvokeSpecial(method_ref) | Handle::InvokeInterface(method_ref) => {
+				let kind = match self {
+					Handle::InvokeVirtual(_) => "invokevirtual",
+					Handle::InvokeStatic(_, _) => "invokestatic",
+					Handle::InvokeSpecial(_, _) => "invokespecial",
+					Handle::NewInvokeSpecial(_) => "newinvokespecial",
+					_ => "invokeinterface",
+				};
+				write!(f, "{kind} {}.{}{}", method_ref.class, method_ref.name, method_ref.desc)
+			},
+		}
+	}
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ConstantDynamic {
 	pub name: FieldName,
@@ -311,6 +841,67 @@ pub struct InvokeDynamic {
 	pub arguments: Vec<Loadable>,
 }
 
+impl InvokeDynamic {
+	/// Checks if this `invokedynamic` creates a lambda (or a method reference), by checking if the bootstrap method is
+	/// `LambdaMetafactory.metafactory` or `LambdaMetafactory.altMetafactory`.
+	pub fn is_lambda(&self) -> bool {
+		match &self.handle {
+			Handle::InvokeStatic(method_ref, false) => {
+				method_ref.class.as_inner() == "java/lang/invoke/LambdaMetafactory" &&
+					(method_ref.name.as_inner() == "metafactory" || method_ref.name.as_inner() == "altMetafactory")
+			},
+			_ => false,
+		}
+	}
+
+	/// Returns the functional interface a lambda implements, from the return type of the [`descriptor`][InvokeDynamic::descriptor].
+	///
+	/// Returns [`None`] if this isn't a [lambda][InvokeDynamic::is_lambda].
+	pub fn lambda_interface(&self) -> Result<Option<ClassName>> {
+		if !self.is_lambda() {
+			return Ok(None);
+		}
+		match self.descriptor.parse()?.return_descriptor {
+			Some(Type::Object(class_name)) => Ok(Some(class_name)),
+			return_descriptor => bail!("expected lambda to return an object, got {return_descriptor:?} for {self:?}"),
+		}
+	}
+
+	/// Returns the method type of the method a lambda implements, from the first bootstrap argument.
+	///
+	/// Returns [`None`] if this isn't a [lambda][InvokeDynamic::is_lambda], or if the argument isn't a method type.
+	pub fn lambda_method_type(&self) -> Option<&MethodDescriptor> {
+		match self.arguments.first() {
+			Some(Loadable::MethodType(desc)) if self.is_lambda() => Some(desc),
+			_ => None,
+		}
+	}
+
+	/// Returns the method handle implementing a lambda, from the second bootstrap argument.
+	///
+	/// For the lambda `() -> foo()` this is a handle to the synthetic method containing `foo()`, for the method reference
+	/// `Foo::bar` this is a handle to `Foo.bar`.
+	///
+	/// Returns [`None`] if this isn't a [lambda][InvokeDynamic::is_lambda], or if the argument isn't a method handle.
+	pub fn lambda_implementation(&self) -> Option<&Handle> {
+		match self.arguments.get(1) {
+			Some(Loadable::MethodHandle(handle)) if self.is_lambda() => Some(handle),
+			_ => None,
+		}
+	}
+
+	/// Like [`lambda_implementation`][InvokeDynamic::lambda_implementation], but allows editing the method handle.
+	pub fn lambda_implementation_mut(&mut self) -> Option<&mut Handle> {
+		if !self.is_lambda() {
+			return None;
+		}
+		match self.arguments.get_mut(1) {
+			Some(Loadable::MethodHandle(handle)) => Some(handle),
+			_ => None,
+		}
+	}
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ArrayType {
 	Boolean,
@@ -338,6 +929,20 @@ impl ArrayType {
 		}
 	}
 
+	/// Gets the name of the primitive type, as used by `javap` for the `newarray` instruction.
+	pub fn name(self) -> &'static str {
+		match self {
+			ArrayType::Boolean => "boolean",
+			ArrayType::Char    => "char",
+			ArrayType::Float   => "float",
+			ArrayType::Double  => "double",
+			ArrayType::Byte    => "byte",
+			ArrayType::Short   => "short",
+			ArrayType::Int     => "int",
+			ArrayType::Long    => "long",
+		}
+	}
+
 	pub(crate) fn to_atype(self) -> u8 {
 		match self {
 			ArrayType::Boolean => atype::T_BOOLEAN,
@@ -351,3 +956,42 @@ impl ArrayType {
 		}
 	}
 }
+
+#[cfg(test)]
+mod testing {
+	use java_string::JavaString;
+	use pretty_assertions::assert_eq;
+	use crate::tree::class::ClassName;
+	use crate::tree::method::code::{ArrayType, Instruction, Label, Loadable, LvIndex};
+
+	#[test]
+	fn mnemonic_and_display() {
+		let cases = [
+			(Instruction::Nop, "nop", "nop"),
+			(Instruction::BiPush(-5), "bipush", "bipush -5"),
+			(Instruction::ALoad(LvIndex { index: 0 }), "aload", "aload 0"),
+			(Instruction::IInc(LvIndex { index: 2 }, -1), "iinc", "iinc 2 -1"),
+			(Instruction::Ldc(Loadable::Long(3)), "ldc", "ldc 3l"),
+			(Instruction::Ldc(Loadable::String(JavaString::from("a\"b"))), "ldc", "ldc \"a\\\"b\""),
+			(Instruction::Ldc(Loadable::Class(ClassName::JAVA_LANG_OBJECT.to_owned())), "ldc", "ldc class java/lang/Object"),
+			(Instruction::Goto(Label { id: 3 }), "goto", "goto L3"),
+			(
+				Instruction::TableSwitch { default: Label { id: 0 }, low: 1, high: 2, table: vec![Label { id: 1 }, Label { id: 2 }] },
+				"tableswitch",
+				"tableswitch { 1..=2: L1 L2, default: L0 }",
+			),
+			(
+				Instruction::LookupSwitch { default: Label { id: 0 }, pairs: vec![(-1, Label { id: 1 })] },
+				"lookupswitch",
+				"lookupswitch { -1: L1, default: L0 }",
+			),
+			(Instruction::NewArray(ArrayType::Int), "newarray", "newarray int"),
+			(Instruction::MultiANewArray(ClassName::JAVA_LANG_OBJECT.to_owned(), 2), "multianewarray", "multianewarray java/lang/Object 2"),
+		];
+
+		for (instruction, mnemonic, display) in cases {
+			assert_eq!(instruction.mnemonic(), mnemonic);
+			assert_eq!(instruction.to_string(), display);
+		}
+	}
+}
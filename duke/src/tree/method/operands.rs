@@ -0,0 +1,255 @@
+//! Walking the constants an instruction refers to.
+//!
+//! Instructions like `getfield`, `invokedynamic` or `ldc` refer to classes, fields, methods and other constants, which in
+//! turn may contain more of them: an `invokedynamic` has a bootstrap method handle and bootstrap arguments, which may be
+//! method handles or dynamic constants themselves. An [`OperandVisitor`] is given all of them, in the order they appear.
+//!
+//! Use [`Code::accept_operands`] to visit all instructions of some code, including the constants of the
+//! [raw code][crate::tree::method::raw_code::RawCode], or [`Instruction::accept_operands`] for a single instruction. The
+//! [`OperandVisitorMut`] allows changing the constants, with [`Code::accept_operands_mut`] and
+//! [`Instruction::accept_operands_mut`].
+
+use crate::tree::class::ClassName;
+use crate::tree::field::FieldRef;
+use crate::tree::method::code::{Code, ConstantDynamic, Handle, Instruction, InvokeDynamic, Loadable};
+use crate::tree::method::raw_code::RawConstant;
+use crate::tree::method::{MethodDescriptor, MethodRef};
+
+/// Generates the visitor trait and the `accept` methods walking the operands, once for shared references and once (with
+/// `mut` given) for mutable references.
+macro_rules! operand_visitor {
+	($(#[$doc:meta])* $visitor:ident, $accept:ident $(, $mut_:tt)?) => {
+		$(#[$doc])*
+		pub trait $visitor {
+			type Error;
+
+			/// Visits a class, from `new`, `anewarray`, `checkcast`, `instanceof` or `multianewarray`, or from a loaded
+			/// class constant.
+			fn visit_class(&mut self, class: &$($mut_)? ClassName) -> Result<(), Self::Error> {
+				let _ = class;
+				Ok(())
+			}
+
+			/// Visits a field, from `getstatic`, `putstatic`, `getfield` or `putfield`, or from a method handle.
+			fn visit_field_ref(&mut self, field_ref: &$($mut_)? FieldRef) -> Result<(), Self::Error> {
+				let _ = field_ref;
+				Ok(())
+			}
+
+			/// Visits a method, from `invokevirtual`, `invokespecial`, `invokestatic` or `invokeinterface`, or from a method
+			/// handle.
+			fn visit_method_ref(&mut self, method_ref: &$($mut_)? MethodRef) -> Result<(), Self::Error> {
+				let _ = method_ref;
+				Ok(())
+			}
+
+			/// Visits a loaded method type constant.
+			fn visit_method_type(&mut self, descriptor: &$($mut_)? MethodDescriptor) -> Result<(), Self::Error> {
+				let _ = descriptor;
+				Ok(())
+			}
+
+			/// Visits a method handle, from a loaded constant or from a bootstrap method.
+			///
+			/// By default, this visits the field or method of the handle.
+			fn visit_handle(&mut self, handle: &$($mut_)? Handle) -> Result<(), Self::Error> {
+				match handle {
+					Handle::GetField(field_ref) |
+					Handle::GetStatic(field_ref) |
+					Handle::PutField(field_ref) |
+					Handle::PutStatic(field_ref) => self.visit_field_ref(field_ref),
+					Handle::InvokeVirtual(method_ref) |
+					Handle::InvokeStatic(method_ref, _) |
+					Handle::InvokeSpecial(method_ref, _) |
+					Handle::NewInvokeSpecial(method_ref) |
+					Handle::InvokeInterface(method_ref) => self.visit_method_ref(method_ref),
+				}
+			}
+
+			/// Visits a loaded constant, from `ldc` or from the bootstrap arguments.
+			///
+			/// This is called before the class, method handle, method type or dynamic constant it contains is visited.
+			fn visit_loadable(&mut self, loadable: &$($mut_)? Loadable) -> Result<(), Self::Error> {
+				let _ = loadable;
+				Ok(())
+			}
+
+			/// Visits an `invokedynamic` call site.
+			///
+			/// This is called before its bootstrap method handle and bootstrap arguments are visited.
+			fn visit_invoke_dynamic(&mut self, invoke_dynamic: &$($mut_)? InvokeDynamic) -> Result<(), Self::Error> {
+				let _ = invoke_dynamic;
+				Ok(())
+			}
+
+			/// Visits a dynamic constant.
+			///
+			/// This is called before its bootstrap method handle and bootstrap arguments are visited.
+			fn visit_constant_dynamic(&mut self, constant_dynamic: &$($mut_)? ConstantDynamic) -> Result<(), Self::Error> {
+				let _ = constant_dynamic;
+				Ok(())
+			}
+		}
+
+		impl Code {
+			/// Visits the constants referred to by all instructions, and by the [`raw`][Code::raw] code, if present.
+			pub fn $accept<V: $visitor>(&$($mut_)? self, visitor: &mut V) -> Result<(), V::Error> {
+				for entry in &$($mut_)? self.instructions {
+					entry.instruction.$accept(visitor)?;
+				}
+				if let Some(raw) = &$($mut_)? self.raw {
+					for (_, constant) in &$($mut_)? raw.constants {
+						constant.$accept(visitor)?;
+					}
+				}
+				Ok(())
+			}
+		}
+
+		impl Instruction {
+			/// Visits the constants this instruction refers to.
+			pub fn $accept<V: $visitor>(&$($mut_)? self, visitor: &mut V) -> Result<(), V::Error> {
+				match self {
+					Instruction::Ldc(loadable) => loadable.$accept(visitor),
+					Instruction::GetStatic(field_ref) |
+					Instruction::PutStatic(field_ref) |
+					Instruction::GetField(field_ref) |
+					Instruction::PutField(field_ref) => visitor.visit_field_ref(field_ref),
+					Instruction::InvokeVirtual(method_ref) |
+					Instruction::InvokeSpecial(method_ref, _) |
+					Instruction::InvokeStatic(method_ref, _) |
+					Instruction::InvokeInterface(method_ref) => visitor.visit_method_ref(method_ref),
+					Instruction::InvokeDynamic(invoke_dynamic) => invoke_dynamic.$accept(visitor),
+					Instruction::New(class) |
+					Instruction::ANewArray(class) |
+					Instruction::CheckCast(class) |
+					Instruction::InstanceOf(class) |
+					Instruction::MultiANewArray(class, _) => visitor.visit_class(class),
+					_ => Ok(()),
+				}
+			}
+		}
+
+		impl RawConstant {
+			fn $accept<V: $visitor>(&$($mut_)? self, visitor: &mut V) -> Result<(), V::Error> {
+				match self {
+					RawConstant::Class(class) => visitor.visit_class(class),
+					RawConstant::Field(field_ref) => visitor.visit_field_ref(field_ref),
+					RawConstant::Method(method_ref, _) => visitor.visit_method_ref(method_ref),
+					RawConstant::InvokeDynamic(invoke_dynamic) => invoke_dynamic.$accept(visitor),
+					RawConstant::Loadable(loadable) => loadable.$accept(visitor),
+				}
+			}
+		}
+
+		impl InvokeDynamic {
+			fn $accept<V: $visitor>(&$($mut_)? self, visitor: &mut V) -> Result<(), V::Error> {
+				visitor.visit_invoke_dynamic(self)?;
+				visitor.visit_handle(&$($mut_)? self.handle)?;
+				for argument in &$($mut_)? self.arguments {
+					argument.$accept(visitor)?;
+				}
+				Ok(())
+			}
+		}
+
+		impl Loadable {
+			fn $accept<V: $visitor>(&$($mut_)? self, visitor: &mut V) -> Result<(), V::Error> {
+				visitor.visit_loadable(self)?;
+				match self {
+					Loadable::Class(class) => visitor.visit_class(class),
+					Loadable::MethodHandle(handle) => visitor.visit_handle(handle),
+					Loadable::MethodType(descriptor) => visitor.visit_method_type(descriptor),
+					Loadable::Dynamic(constant_dynamic) => {
+						visitor.visit_constant_dynamic(constant_dynamic)?;
+						visitor.visit_handle(&$($mut_)? constant_dynamic.handle)?;
+						for argument in &$($mut_)? constant_dynamic.arguments {
+							argument.$accept(visitor)?;
+						}
+						Ok(())
+					},
+					_ => Ok(()),
+				}
+			}
+		}
+	}
+}
+
+operand_visitor!(
+	/// A visitor for the classes, fields, methods and other constants instructions refer to.
+	///
+	/// All methods do nothing by default, apart from [`visit_handle`][OperandVisitor::visit_handle]. Return an error to stop
+	/// visiting.
+	OperandVisitor, accept_operands
+);
+operand_visitor!(
+	/// Like [`OperandVisitor`], but allows changing the constants.
+	OperandVisitorMut, accept_operands_mut, mut
+);
+
+#[cfg(test)]
+mod testing {
+	use std::convert::Infallible;
+	use std::io::Cursor;
+	use anyhow::{Context, Result};
+	use pretty_assertions::assert_eq;
+	use crate::tree::method::code::{InvokeDynamic, Loadable};
+	use crate::tree::method::operands::OperandVisitor;
+	use crate::tree::method::{MethodDescriptor, MethodRef};
+
+	/// Records what's visited.
+	#[derive(Default)]
+	struct Recorder(Vec<String>);
+
+	impl OperandVisitor for Recorder {
+		type Error = Infallible;
+
+		fn visit_method_ref(&mut self, method_ref: &MethodRef) -> Result<(), Infallible> {
+			self.0.push(format!("method {}.{}", method_ref.class, method_ref.name));
+			Ok(())
+		}
+
+		fn visit_method_type(&mut self, descriptor: &MethodDescriptor) -> Result<(), Infallible> {
+			self.0.push(format!("method type {descriptor}"));
+			Ok(())
+		}
+
+		fn visit_loadable(&mut self, _loadable: &Loadable) -> Result<(), Infallible> {
+			self.0.push("loadable".to_owned());
+			Ok(())
+		}
+
+		fn visit_invoke_dynamic(&mut self, invoke_dynamic: &InvokeDynamic) -> Result<(), Infallible> {
+			self.0.push(format!("invokedynamic {}", invoke_dynamic.name));
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn lambda() -> Result<()> {
+		let expected = [
+			"invokedynamic get",
+			"method java/lang/invoke/LambdaMetafactory.metafactory",
+			"loadable",
+			"method type ()Ljava/lang/Object;",
+			"loadable",
+			"method Lambdas.lambda$supplier$0",
+			"loadable",
+			"method type ()Ljava/lang/String;",
+		];
+
+		for class in [
+			crate::read_class(&mut Cursor::new(include_bytes!("../test/Lambdas.class")))?,
+			crate::read_class_raw_code(&mut Cursor::new(include_bytes!("../test/Lambdas.class")))?,
+		] {
+			let method = class.methods.iter().find(|method| method.name.as_inner() == "supplier").context("no method `supplier`")?;
+			let code = method.code.as_ref().context("no code")?;
+
+			let mut recorder = Recorder::default();
+			let Ok(()) = code.accept_operands(&mut recorder);
+			assert_eq!(recorder.0, expected);
+		}
+
+		Ok(())
+	}
+}
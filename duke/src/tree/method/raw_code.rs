@@ -0,0 +1,86 @@
+use crate::tree::class::ClassName;
+use crate::tree::field::{FieldDescriptor, FieldRef, FieldSignature};
+use crate::tree::method::code::{InvokeDynamic, Loadable, LocalVariableName, LvIndex};
+use crate::tree::method::MethodRef;
+
+/// The code of a method, with the bytecode kept as it was read.
+///
+/// This is read instead of the instructions if the [`raw_code_only`][crate::visitor::method::code::CodeInterests::raw_code_only]
+/// interest is set, or if the class is read with [`read_class_raw_code`][crate::read_class_raw_code]. No labels are created
+/// for it, and branches aren't resolved: the bytecode is only walked once to find the constant pool references.
+///
+/// All offsets are bytecode offsets. The constant pool indices in the [`bytecode`][RawCode::bytecode] and the
+/// [`stack_map_table`][RawStackMapTable::data] refer to the constant pool of the class read. Changing the [`constants`][RawCode::constants]
+/// or [`classes`][RawStackMapTable::classes] changes what's written there, but the bytecode itself must not be changed in
+/// a way that moves instructions. This makes it suitable for remapping, but not for anything changing the control flow.
+///
+/// Type annotations in the code and unknown attributes of the `Code` attribute can't be kept, as they may refer to the constant
+/// pool in ways we can't fix up. Reading fails if one of them is present and its interest is set. Unset the interest to skip
+/// them instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawCode {
+	pub max_stack: u16,
+	pub max_locals: u16,
+
+	/// The bytecode, as stored in the `code` item of the `Code` attribute.
+	pub bytecode: Vec<u8>,
+	/// The constants referenced by the instructions, each with the offset of its constant pool index in the bytecode.
+	///
+	/// For `ldc` the index is a single byte, for all other instructions it's two bytes.
+	pub constants: Vec<(u16, RawConstant)>,
+
+	pub exception_table: Vec<RawException>,
+
+	pub stack_map_table: Option<RawStackMapTable>,
+	/// The line number table, with the start offset and the line number of each entry.
+	pub line_numbers: Option<Vec<(u16, u16)>>,
+	pub local_variables: Option<Vec<RawLv>>,
+}
+
+/// A constant referenced by an instruction in [`RawCode`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawConstant {
+	/// From `new`, `anewarray`, `checkcast`, `instanceof` and `multianewarray`.
+	Class(ClassName),
+	/// From `getstatic`, `putstatic`, `getfield` and `putfield`.
+	Field(FieldRef),
+	/// From `invokevirtual`, `invokespecial`, `invokestatic` and `invokeinterface`, with whether it's an interface method.
+	Method(MethodRef, bool),
+	/// From `invokedynamic`.
+	InvokeDynamic(InvokeDynamic),
+	/// From `ldc`, `ldc_w` and `ldc2_w`.
+	Loadable(Loadable),
+}
+
+/// An entry of the exception table of [`RawCode`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawException {
+	pub start: u16,
+	/// The exclusive end of the range the handler is active for.
+	pub end: u16,
+	pub handler: u16,
+	pub catch: Option<ClassName>,
+}
+
+/// The content of the `StackMapTable` attribute of [`RawCode`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawStackMapTable {
+	/// The data of the attribute.
+	pub data: Vec<u8>,
+	/// The classes of the `Object_variable_info`s, each with the offset of its constant pool index in the data.
+	pub classes: Vec<(usize, ClassName)>,
+}
+
+/// An entry of the `LocalVariableTable` or `LocalVariableTypeTable` attribute of [`RawCode`].
+///
+/// Exactly one of the [`descriptor`][RawLv::descriptor] and the [`signature`][RawLv::signature] is set, depending on which
+/// attribute the entry was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawLv {
+	pub start: u16,
+	pub length: u16,
+	pub name: LocalVariableName,
+	pub descriptor: Option<FieldDescriptor>,
+	pub signature: Option<FieldSignature>,
+	pub index: LvIndex,
+}
@@ -0,0 +1,447 @@
+//! Inlining of subroutines.
+//!
+//! Before Java 6, `javac` compiled `finally` blocks into subroutines: a `jsr` instruction jumps to the subroutine and pushes
+//! the return address, which the subroutine stores in a local variable and later returns to with a `ret` instruction.
+//! Subroutines aren't allowed in class files of version 51 (Java 7) and above, and many tools can't handle them.
+//!
+//! [`inline_subroutines`] removes all `jsr` and `ret` instructions from a method, by copying the code of the subroutine to
+//! each place calling it.
+
+use std::collections::HashMap;
+use anyhow::{anyhow, bail, Context, Result};
+use crate::tree::method::code::{Code, Exception, Instruction, InstructionListEntry, Label, LabelRange, Lv};
+use crate::tree::method::Method;
+
+/// The code of the method, with labels resolved to instruction indices.
+struct Flow<'a> {
+	code: &'a Code,
+	/// From [`Label`] to the index of the instruction it's on. The [`last_label`][Code::last_label] maps to the length of
+	/// the instructions.
+	labels: HashMap<Label, usize>,
+}
+
+impl Flow<'_> {
+	fn index(&self, label: &Label) -> Result<usize> {
+		self.labels.get(label).copied().with_context(|| anyhow!("label {label:?} is not placed on any instruction"))
+	}
+
+	/// Gets the indices of the instructions that can run after the instruction at `index`.
+	///
+	/// A `jsr` is treated like it returns normally, so the next instruction follows it.
+	fn successors(&self, index: usize) -> Result<Vec<usize>> {
+		let mut instruction = self.code.instructions[index].instruction.clone();
+		let falls_through = !matches!(instruction,
+			Instruction::Goto(_) | Instruction::Ret(_) | Instruction::AThrow |
+			Instruction::TableSwitch { .. } | Instruction::LookupSwitch { .. } |
+			Instruction::IReturn | Instruction::LReturn | Instruction::FReturn | Instruction::DReturn | Instruction::AReturn |
+			Instruction::Return
+		);
+
+		let mut successors = Vec::new();
+		if falls_through {
+			if index + 1 >= self.code.instructions.len() {
+				bail!("execution falls off the end of the code after instruction {index}");
+			}
+			successors.push(index + 1);
+		}
+		if !matches!(instruction, Instruction::Jsr(_)) {
+			for target in instruction.jump_targets_mut() {
+				successors.push(self.index(target)?);
+			}
+		}
+		Ok(successors)
+	}
+
+	/// Finds the instructions reachable from `start`, including exception handlers for the instructions found.
+	fn body(&self, start: usize) -> Result<Vec<bool>> {
+		let mut body = vec![false; self.code.instructions.len()];
+		let mut queue = vec![start];
+
+		loop {
+			while let Some(index) = queue.pop() {
+				if !body[index] {
+					body[index] = true;
+					queue.extend(self.successors(index)?);
+				}
+			}
+
+			for exception in &self.code.exception_table {
+				let handler = self.index(&exception.handler)?;
+				if !body[handler] && (self.index(&exception.start)?..self.index(&exception.end)?).any(|index| body[index]) {
+					queue.push(handler);
+				}
+			}
+			if queue.is_empty() {
+				return Ok(body);
+			}
+		}
+	}
+}
+
+/// One copy of a subroutine, or of the main code.
+struct Instance {
+	/// For each instruction, if it's part of this instance.
+	body: Vec<bool>,
+	/// The new labels of the instructions of this instance.
+	labels: HashMap<usize, Label>,
+	/// The instance that called this one, and the label to return to in it. This is [`None`] for the main code.
+	caller: Option<(usize, Label)>,
+	/// The index of the first instruction of the subroutine, or [`None`] for the main code.
+	entry: Option<usize>,
+}
+
+struct Inliner<'a> {
+	flow: Flow<'a>,
+	instances: Vec<Instance>,
+	next_label: u16,
+}
+
+impl Inliner<'_> {
+	fn new_label(&mut self) -> Result<Label> {
+		let id = self.next_label;
+		self.next_label = self.next_label.checked_add(1).context("too many labels needed for inlining subroutines")?;
+		Ok(Label { id })
+	}
+
+	fn add_instance(&mut self, entry: Option<usize>, caller: Option<(usize, Label)>) -> Result<usize> {
+		let body = self.flow.body(entry.unwrap_or(0))?;
+		let mut labels = HashMap::new();
+		for (index, _) in body.iter().enumerate().filter(|(_, &in_body)| in_body) {
+			labels.insert(index, self.new_label()?);
+		}
+		self.instances.push(Instance { body, labels, caller, entry });
+		Ok(self.instances.len() - 1)
+	}
+
+	/// Gets the new label of the instruction at `index` as seen from the given instance.
+	///
+	/// If the instruction isn't part of the instance, the instances calling it are searched.
+	fn label(&self, mut instance: usize, index: usize) -> Result<Label> {
+		loop {
+			if let Some(&label) = self.instances[instance].labels.get(&index) {
+				return Ok(label);
+			}
+			instance = self.instances[instance].caller
+				.with_context(|| anyhow!("instruction {index} is not reachable"))?.0;
+		}
+	}
+
+	fn emit(&mut self, instance: usize, instructions: &mut Vec<InstructionListEntry>) -> Result<()> {
+		for index in 0..self.flow.code.instructions.len() {
+			if !self.instances[instance].body[index] {
+				continue;
+			}
+
+			let entry = &self.flow.code.instructions[index];
+			let label = Some(self.instances[instance].labels[&index]);
+
+			match &entry.instruction {
+				Instruction::Jsr(target) => {
+					let target = self.flow.index(target)?;
+
+					// Subroutines can't call themselves, not even indirectly.
+					let mut caller = Some(instance);
+					while let Some(i) = caller {
+						if self.instances[i].entry == Some(target) {
+							bail!("subroutine at instruction {target} calls itself");
+						}
+						caller = self.instances[i].caller.map(|(caller, _)| caller);
+					}
+
+					let return_label = self.label(instance, index + 1)?;
+					let called = self.add_instance(Some(target), Some((instance, return_label)))?;
+					let called_label = self.instances[called].labels[&target];
+
+					// The subroutine stores the return address, so we push something to store in place of it.
+					instructions.push(InstructionListEntry { label, frame: None, offset: None, instruction: Instruction::AConstNull });
+					instructions.push(InstructionListEntry { label: None, frame: None, offset: None, instruction: Instruction::Goto(called_label) });
+				},
+				Instruction::Ret(_) => {
+					let (_, return_label) = self.instances[instance].caller
+						.with_context(|| anyhow!("instruction {index} is a `ret` outside of any subroutine"))?;
+
+					instructions.push(InstructionListEntry { label, frame: None, offset: None, instruction: Instruction::Goto(return_label) });
+				},
+				instruction => {
+					let mut instruction = instruction.clone();
+					for target in instruction.jump_targets_mut() {
+						*target = self.label(instance, self.flow.index(target)?)?;
+					}
+
+					instructions.push(InstructionListEntry { label, frame: None, offset: None, instruction });
+				},
+			}
+		}
+		Ok(())
+	}
+
+	/// Gets the ranges of new labels covering the instructions from `start` (inclusive) to `end` (exclusive) in all instances.
+	///
+	/// Each instance may contribute multiple ranges, if only some of the instructions are part of it. The `ends` are the
+	/// labels just after the last instruction of each instance.
+	fn ranges(&self, start: usize, end: usize, ends: &[Label]) -> Vec<(usize, Label, Label)> {
+		let mut ranges = Vec::new();
+		for (i, instance) in self.instances.iter().enumerate() {
+			let mut open = None;
+			for index in 0..self.flow.code.instructions.len() {
+				if !instance.body[index] {
+					continue;
+				}
+				let covered = (start..end).contains(&index);
+				match open {
+					None if covered => open = Some(instance.labels[&index]),
+					Some(range_start) if !covered => {
+						ranges.push((i, range_start, instance.labels[&index]));
+						open = None;
+					},
+					_ => {},
+				}
+			}
+			if let Some(range_start) = open {
+				ranges.push((i, range_start, ends[i]));
+			}
+		}
+		ranges
+	}
+}
+
+/// Removes all `jsr` and `ret` instructions of a method, by inlining the subroutines.
+///
+/// Each `jsr` is replaced by an `aconst_null`, followed by a `goto` to a copy of the subroutine made for that `jsr` only. The
+/// `aconst_null` takes the place of the return address, which the subroutine usually stores into a local variable right away.
+/// Each `ret` in such a copy is replaced by a `goto` to the instruction after the `jsr`. Subroutines called from within other
+/// subroutines are copied as well. The copies are placed after the main code of the method.
+///
+/// The exception table, the line numbers and the local variables are updated to cover the copies as well. All instructions
+/// get new labels, and all [`frame`][InstructionListEntry::frame]s are removed, as these aren't valid anymore. These aren't
+/// needed for class files of versions allowing `jsr` anyway. The same is true for type annotations in code, so this fails if
+/// there are any of them.
+///
+/// Methods without code, or without any `jsr` or `ret` instruction aren't changed.
+pub fn inline_subroutines(method: &mut Method) -> Result<()> {
+	let Some(code) = &mut method.code else {
+		return Ok(());
+	};
+	if !code.instructions.iter().any(|entry| matches!(entry.instruction, Instruction::Jsr(_) | Instruction::Ret(_))) {
+		return Ok(());
+	}
+	if !code.runtime_visible_type_annotations.is_empty() || !code.runtime_invisible_type_annotations.is_empty() {
+		bail!("can't inline subroutines of method {:?} {:?} with type annotations in its code", method.name, method.descriptor);
+	}
+
+	let mut labels: HashMap<Label, usize> = code.instructions.iter().enumerate()
+		.filter_map(|(index, entry)| entry.label.map(|label| (label, index)))
+		.collect();
+	if let Some(last_label) = code.last_label {
+		labels.insert(last_label, code.instructions.len());
+	}
+
+	let mut inliner = Inliner {
+		flow: Flow { code, labels },
+		instances: Vec::new(),
+		next_label: 0,
+	};
+
+	(|| -> Result<Code> {
+		let mut instructions = Vec::new();
+		inliner.add_instance(None, None)?;
+		// New instances are added while emitting, these are emitted in the next iterations.
+		let mut instance = 0;
+		let mut ends = Vec::new();
+		while instance < inliner.instances.len() {
+			inliner.emit(instance, &mut instructions)?;
+			instance += 1;
+			ends.push(instance);
+		}
+
+		// The label after the last instruction of an instance is the label of the first instruction of the next one.
+		let last_label = inliner.new_label()?;
+		let ends: Vec<Label> = ends.into_iter()
+			.map(|next| inliner.instances.get(next)
+				.and_then(|next| next.body.iter().position(|&in_body| in_body).map(|first| next.labels[&first]))
+				.unwrap_or(last_label)
+			)
+			.collect();
+
+		let flow = &inliner.flow;
+
+		let mut exception_table = Vec::new();
+		for exception in &flow.code.exception_table {
+			let handler = flow.index(&exception.handler)?;
+			for (instance, start, end) in inliner.ranges(flow.index(&exception.start)?, flow.index(&exception.end)?, &ends) {
+				exception_table.push(Exception {
+					start,
+					end,
+					handler: inliner.label(instance, handler)?,
+					catch: exception.catch.clone(),
+				});
+			}
+		}
+
+		let line_numbers = flow.code.line_numbers.as_ref()
+			.map(|line_numbers| -> Result<_> {
+				let mut new_line_numbers = Vec::new();
+				for (label, line_number) in line_numbers {
+					let index = flow.index(label)?;
+					for instance in &inliner.instances {
+						if let Some(&label) = instance.labels.get(&index) {
+							new_line_numbers.push((label, *line_number));
+						}
+					}
+				}
+				Ok(new_line_numbers)
+			})
+			.transpose()?;
+
+		let local_variables = flow.code.local_variables.as_ref()
+			.map(|local_variables| -> Result<_> {
+				let mut new_local_variables = Vec::new();
+				for lv in local_variables {
+					for (_, start, end) in inliner.ranges(flow.index(&lv.range.start)?, flow.index(&lv.range.end)?, &ends) {
+						new_local_variables.push(Lv {
+							range: LabelRange { start, end },
+							..lv.clone()
+						});
+					}
+				}
+				Ok(new_local_variables)
+			})
+			.transpose()?;
+
+		Ok(Code {
+			instructions,
+			exception_table,
+			last_label: Some(last_label),
+			line_numbers,
+			local_variables,
+			..flow.code.clone()
+		})
+	})()
+		.with_context(|| anyhow!("failed to inline subroutines of method {:?} {:?}", method.name, method.descriptor))
+		.map(|new_code| method.code = Some(new_code))
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::{Context, Result};
+	use pretty_assertions::assert_eq;
+	use java_string::JavaStr;
+	use crate::tree::class::{ClassAccess, ClassFile, ClassName};
+	use crate::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName};
+	use crate::tree::method::code::{Code, Exception, Instruction, InstructionListEntry, Label, LvIndex};
+	use crate::tree::method::subroutines::inline_subroutines;
+	use crate::tree::version::Version;
+
+	fn entry(label: Option<u16>, instruction: Instruction) -> InstructionListEntry {
+		InstructionListEntry { label: label.map(|id| Label { id }), frame: None, offset: None, instruction }
+	}
+
+	/// The code `javac` 1.4 generates for
+	/// ```java
+	/// static int f(int x) {
+	///     try {
+	///         return x;
+	///     } finally {
+	///         x++;
+	///     }
+	/// }
+	/// ```
+	fn try_finally() -> Result<Method> {
+		let lv = |index| LvIndex { index };
+		let mut method = Method::new(
+			MethodAccess::from(0x0008),
+			MethodName::try_from(JavaStr::from_str("f"))?,
+			MethodDescriptor::try_from(JavaStr::from_str("(I)I"))?,
+		);
+		method.code = Some(Code {
+			max_stack: Some(1),
+			max_locals: Some(4),
+			instructions: vec![
+				entry(Some(0), Instruction::ILoad(lv(0))),
+				entry(None, Instruction::IStore(lv(1))),
+				entry(None, Instruction::Jsr(Label { id: 3 })),
+				entry(Some(1), Instruction::ILoad(lv(1))),
+				entry(None, Instruction::IReturn),
+				entry(Some(2), Instruction::AStore(lv(2))),
+				entry(None, Instruction::Jsr(Label { id: 3 })),
+				entry(None, Instruction::ALoad(lv(2))),
+				entry(None, Instruction::AThrow),
+				entry(Some(3), Instruction::AStore(lv(3))),
+				entry(None, Instruction::IInc(lv(0), 1)),
+				entry(None, Instruction::Ret(lv(3))),
+			],
+			exception_table: vec![
+				Exception { start: Label { id: 0 }, end: Label { id: 1 }, handler: Label { id: 2 }, catch: None },
+			],
+			..Code::default()
+		});
+		Ok(method)
+	}
+
+	#[test]
+	fn inline_try_finally() -> Result<()> {
+		let mut method = try_finally()?;
+		inline_subroutines(&mut method)?;
+		let code = method.code.clone().context("no code")?;
+
+		let instructions: Vec<Instruction> = code.instructions.iter().map(|entry| entry.instruction.clone()).collect();
+		assert!(!instructions.iter().any(|instruction| matches!(instruction, Instruction::Jsr(_) | Instruction::Ret(_))));
+
+		let label = |index: usize| code.instructions[index].label.context("no label");
+		let lv = |index| LvIndex { index };
+		assert_eq!(instructions, [
+			// the main code
+			Instruction::ILoad(lv(0)),
+			Instruction::IStore(lv(1)),
+			Instruction::AConstNull,
+			Instruction::Goto(label(11)?),
+			Instruction::ILoad(lv(1)),
+			Instruction::IReturn,
+			Instruction::AStore(lv(2)),
+			Instruction::AConstNull,
+			Instruction::Goto(label(14)?),
+			Instruction::ALoad(lv(2)),
+			Instruction::AThrow,
+			// the subroutine, for the first call
+			Instruction::AStore(lv(3)),
+			Instruction::IInc(lv(0), 1),
+			Instruction::Goto(label(4)?),
+			// the subroutine, for the second call
+			Instruction::AStore(lv(3)),
+			Instruction::IInc(lv(0), 1),
+			Instruction::Goto(label(9)?),
+		]);
+
+		assert_eq!(code.exception_table, [
+			Exception { start: label(0)?, end: label(4)?, handler: label(6)?, catch: None },
+		]);
+
+		// the result can be written and read back
+		let mut class = ClassFile::new(Version::V1_4, ClassAccess::from(0x0021), ClassName::try_from(JavaStr::from_str("Test"))?, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		class.methods.push(method);
+		let mut bytes = Vec::new();
+		crate::write_class(&mut bytes, &class)?;
+		let class = crate::read_class(&mut Cursor::new(bytes))?;
+		let code = class.methods[0].code.as_ref().context("no code")?;
+		assert_eq!(code.instructions.len(), instructions.len());
+		assert_eq!(code.exception_table.len(), 1);
+
+		Ok(())
+	}
+
+	#[test]
+	fn no_subroutines() -> Result<()> {
+		let mut method = try_finally()?;
+		let code = method.code.as_mut().context("no code")?;
+		code.instructions.truncate(5);
+		code.instructions[2].instruction = Instruction::Nop;
+		code.exception_table.clear();
+
+		let before = method.clone();
+		inline_subroutines(&mut method)?;
+		assert_eq!(method, before);
+
+		Ok(())
+	}
+}
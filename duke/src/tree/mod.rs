@@ -8,6 +8,9 @@ pub mod annotation;
 pub mod descriptor;
 pub mod record;
 pub mod type_annotation;
+pub mod signature;
+pub mod references;
+pub mod required_version;
 
 mod names {
 	use java_string::{JavaCodePoint, JavaStr};
@@ -18,6 +21,9 @@ mod names {
 	const SLASH: JavaCodePoint = JavaCodePoint::from_char('/');
 	const LESS_THAN: JavaCodePoint = JavaCodePoint::from_char('<');
 	const GREATER_THAN: JavaCodePoint = JavaCodePoint::from_char('>');
+	const BACKSLASH: JavaCodePoint = JavaCodePoint::from_char('\\');
+	const COLON: JavaCodePoint = JavaCodePoint::from_char(':');
+	const AT: JavaCodePoint = JavaCodePoint::from_char('@');
 
 	/// Checks if a class name is valid according to JVMS 4.2.1 (also accepting array class names).
 	pub(super) fn is_valid_class_name(x: &JavaStr) -> bool {
@@ -54,7 +60,33 @@ mod names {
 		)
 	}
 
-	// TODO: 4.2.3 module and package names
+	/// Checks if a package name is valid according to JVMS 4.2.3
+	///
+	/// Package names are in internal form, like class names, so they're a list of unqualified names split by `/`.
+	pub(super) fn is_valid_package_name(x: &JavaStr) -> bool {
+		x.split('/').all(is_valid_unqualified_name)
+	}
+
+	/// Checks if a module name is valid according to JVMS 4.2.3
+	///
+	/// A module name must not contain the code points `\u0000` to `\u001F`. The `\` is an escape character, and may only
+	/// be followed by another `\`, a `:` or an `@`. The `:` and `@` must not appear unescaped.
+	pub(super) fn is_valid_module_name(x: &JavaStr) -> bool {
+		if x.is_empty() {
+			return false;
+		}
+
+		let mut chars = x.chars();
+		while let Some(c) = chars.next() {
+			match c {
+				c if c.as_u32() < 0x20 => return false,
+				BACKSLASH if !chars.next().is_some_and(|escaped| matches!(escaped, BACKSLASH | COLON | AT)) => return false,
+				COLON | AT => return false,
+				_ => {},
+			}
+		}
+		true
+	}
 
 	#[cfg(test)]
 	mod testing {
@@ -113,5 +145,35 @@ mod names {
 			assert!(!is_valid_method_name(JavaStr::from_str("<")));
 			assert!(!is_valid_method_name(JavaStr::from_str(">")));
 		}
+
+		#[test]
+		fn package_names() {
+			assert!(is_valid_package_name(JavaStr::from_str("java/lang")));
+			assert!(is_valid_package_name(JavaStr::from_str("org")));
+			assert!(is_valid_package_name(JavaStr::from_str("org/example/$internal")));
+
+			assert!(!is_valid_package_name(JavaStr::from_str("")));
+			assert!(!is_valid_package_name(JavaStr::from_str("java.lang")));
+			assert!(!is_valid_package_name(JavaStr::from_str("java/lang;")));
+			assert!(!is_valid_package_name(JavaStr::from_str("java//lang")));
+			assert!(!is_valid_package_name(JavaStr::from_str("/java")));
+			assert!(!is_valid_package_name(JavaStr::from_str("java/")));
+			assert!(!is_valid_package_name(JavaStr::from_str("[I")));
+		}
+
+		#[test]
+		fn module_names() {
+			assert!(is_valid_module_name(JavaStr::from_str("java.base")));
+			assert!(is_valid_module_name(JavaStr::from_str("org/example;[]")));
+			assert!(is_valid_module_name(JavaStr::from_str("back\\\\slash")));
+			assert!(is_valid_module_name(JavaStr::from_str("escaped\\:colon\\@at")));
+
+			assert!(!is_valid_module_name(JavaStr::from_str("")));
+			assert!(!is_valid_module_name(JavaStr::from_str("back\\slash")));
+			assert!(!is_valid_module_name(JavaStr::from_str("ends\\")));
+			assert!(!is_valid_module_name(JavaStr::from_str("a:b")));
+			assert!(!is_valid_module_name(JavaStr::from_str("a@b")));
+			assert!(!is_valid_module_name(JavaStr::from_str("new\nline")));
+		}
 	}
 }
\ No newline at end of file
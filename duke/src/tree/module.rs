@@ -1,4 +1,5 @@
 use std::fmt::{Debug, Display, Formatter};
+use anyhow::bail;
 use java_string::{JavaStr, JavaString};
 use crate::macros::{make_display, make_string_str_like};
 use crate::tree::class::ClassName;
@@ -21,14 +22,22 @@ pub struct Module {
 make_string_str_like!(
 	pub ModuleName(JavaString);
 	pub ModuleNameSlice(JavaStr);
-	is_valid(s) = Ok(()); // TODO: see JVMS 4.2.3
+	is_valid(s) = if crate::tree::names::is_valid_module_name(s) {
+		Ok(())
+	} else {
+		bail!("invalid module name: must be non-empty, not contain any of `\\u0000` to `\\u001F`, and only contain `\\`, `:` and `@` if escaped by a `\\`");
+	};
 );
 make_display!(ModuleName, ModuleNameSlice);
 
 make_string_str_like!(
 	pub PackageName(JavaString);
 	pub PackageNameSlice(JavaStr);
-	is_valid(s) = Ok(()); // TODO: see JVMS 4.2.3
+	is_valid(s) = if crate::tree::names::is_valid_package_name(s) {
+		Ok(())
+	} else {
+		bail!("invalid package name: must be a list of non-empty names split by `/`, not containing any of `.`, `;` and `[`");
+	};
 );
 
 #[derive(Copy, Clone, PartialEq)]
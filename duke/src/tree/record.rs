@@ -2,6 +2,7 @@ use anyhow::Result;
 use std::fmt::{Debug, Display, Formatter};
 use std::ops::ControlFlow;
 use java_string::{JavaStr, JavaString};
+use crate::class_constants::attribute;
 use crate::macros::{make_display, make_string_str_like};
 use crate::tree::annotation::Annotation;
 use crate::tree::attribute::Attribute;
@@ -16,14 +17,14 @@ pub struct RecordComponent {
 	pub name: RecordName,
 	pub descriptor: FieldDescriptor,
 
-	pub(crate) signature: Option<FieldSignature>,
+	pub signature: Option<FieldSignature>,
 
-	pub(crate) runtime_visible_annotations: Vec<Annotation>,
-	pub(crate) runtime_invisible_annotations: Vec<Annotation>,
-	pub(crate) runtime_visible_type_annotations: Vec<TypeAnnotation<TargetInfoField>>,
-	pub(crate) runtime_invisible_type_annotations: Vec<TypeAnnotation<TargetInfoField>>,
+	pub runtime_visible_annotations: Vec<Annotation>,
+	pub runtime_invisible_annotations: Vec<Annotation>,
+	pub runtime_visible_type_annotations: Vec<TypeAnnotation<TargetInfoField>>,
+	pub runtime_invisible_type_annotations: Vec<TypeAnnotation<TargetInfoField>>,
 
-	pub(crate) attributes: Vec<Attribute>,
+	pub attributes: Vec<Attribute>,
 }
 
 impl RecordComponent {
@@ -43,6 +44,41 @@ impl RecordComponent {
 		}
 	}
 
+	/// Gets all the annotations on this record component, both the runtime visible and the runtime invisible ones.
+	pub fn annotations(&self) -> impl Iterator<Item=&Annotation> {
+		self.runtime_visible_annotations.iter().chain(&self.runtime_invisible_annotations)
+	}
+
+	/// Removes all attributes for which `predicate` returns `true`.
+	///
+	/// See [`ClassFile::remove_attributes`][crate::tree::class::ClassFile::remove_attributes].
+	///
+	/// Returns whether any attribute was removed.
+	pub fn remove_attributes(&mut self, predicate: impl Fn(&JavaStr) -> bool) -> bool {
+		let mut removed = false;
+
+		if predicate(attribute::SIGNATURE) {
+			removed |= self.signature.take().is_some();
+		}
+		if predicate(attribute::RUNTIME_VISIBLE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_visible_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_INVISIBLE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_invisible_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_VISIBLE_TYPE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_visible_type_annotations).is_empty();
+		}
+		if predicate(attribute::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS) {
+			removed |= !std::mem::take(&mut self.runtime_invisible_type_annotations).is_empty();
+		}
+		let len = self.attributes.len();
+		self.attributes.retain(|attribute| !predicate(&attribute.name));
+		removed |= self.attributes.len() != len;
+
+		removed
+	}
+
 	pub fn accept<C: ClassVisitor>(self, visitor: C) -> Result<C> {
 		match visitor.visit_record_component(self.name, self.descriptor)? {
 			ControlFlow::Continue((visitor, mut record_component_visitor)) => {
@@ -0,0 +1,362 @@
+//! Collecting the classes, fields and methods a class refers to.
+
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use anyhow::Result;
+use crate::tree::class::{ClassFile, ClassName, ClassNameSlice};
+use crate::tree::descriptor::{ArrayType, Type};
+use crate::tree::field::{FieldDescriptorSlice, FieldRef};
+use crate::tree::method::{MethodDescriptor, MethodDescriptorSlice, MethodNameAndDesc, MethodRef};
+use crate::tree::method::code::{ConstantDynamic, Handle, InvokeDynamic};
+use crate::tree::method::operands::OperandVisitor;
+
+/// A set of class names, that adds the element class instead of array classes.
+#[derive(Default)]
+struct References(BTreeSet<ClassName>);
+
+impl References {
+	fn add(&mut self, class: &ClassNameSlice) -> Result<()> {
+		if let Some(descriptor) = class.as_arr() {
+			self.add_field_descriptor(descriptor)
+		} else {
+			if !self.0.contains(class) {
+				self.0.insert(class.to_owned());
+			}
+			Ok(())
+		}
+	}
+
+	fn add_type(&mut self, t: Type) -> Result<()> {
+		match t {
+			Type::Object(class) | Type::Array(_, ArrayType::Object(class)) => self.add(&class),
+			_ => Ok(()),
+		}
+	}
+
+	fn add_field_descriptor(&mut self, descriptor: &FieldDescriptorSlice) -> Result<()> {
+		self.add_type(descriptor.parse()?.0)
+	}
+
+	fn add_method_descriptor(&mut self, descriptor: &MethodDescriptorSlice) -> Result<()> {
+		let descriptor = descriptor.parse()?;
+		for parameter in descriptor.parameter_descriptors {
+			self.add_type(parameter)?;
+		}
+		if let Some(return_descriptor) = descriptor.return_descriptor {
+			self.add_type(return_descriptor)?;
+		}
+		Ok(())
+	}
+}
+
+impl OperandVisitor for References {
+	type Error = anyhow::Error;
+
+	fn visit_class(&mut self, class: &ClassName) -> Result<()> {
+		self.add(class)
+	}
+
+	fn visit_field_ref(&mut self, field_ref: &FieldRef) -> Result<()> {
+		self.add(&field_ref.class)?;
+		self.add_field_descriptor(&field_ref.desc)
+	}
+
+	fn visit_method_ref(&mut self, method_ref: &MethodRef) -> Result<()> {
+		self.add(&method_ref.class)?;
+		self.add_method_descriptor(&method_ref.desc)
+	}
+
+	fn visit_method_type(&mut self, descriptor: &MethodDescriptor) -> Result<()> {
+		self.add_method_descriptor(descriptor)
+	}
+
+	fn visit_invoke_dynamic(&mut self, invoke_dynamic: &InvokeDynamic) -> Result<()> {
+		self.add_method_descriptor(&invoke_dynamic.descriptor)
+	}
+
+	fn visit_constant_dynamic(&mut self, constant_dynamic: &ConstantDynamic) -> Result<()> {
+		self.add_field_descriptor(&constant_dynamic.descriptor)
+	}
+}
+
+/// A reference to either a field or a method.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum MemberRef {
+	Field(FieldRef),
+	Method(MethodRef),
+}
+
+impl MemberRef {
+	/// Returns the class owning the referenced field or method.
+	pub fn class(&self) -> &ClassName {
+		match self {
+			MemberRef::Field(field_ref) => &field_ref.class,
+			MemberRef::Method(method_ref) => &method_ref.class,
+		}
+	}
+}
+
+/// A set of field and method references.
+#[derive(Default)]
+struct MemberReferences(BTreeSet<MemberRef>);
+
+impl OperandVisitor for MemberReferences {
+	type Error = Infallible;
+
+	fn visit_field_ref(&mut self, field_ref: &FieldRef) -> Result<(), Infallible> {
+		self.0.insert(MemberRef::Field(field_ref.clone()));
+		Ok(())
+	}
+
+	fn visit_method_ref(&mut self, method_ref: &MethodRef) -> Result<(), Infallible> {
+		self.0.insert(MemberRef::Method(method_ref.clone()));
+		Ok(())
+	}
+}
+
+/// Collects the fields accessed by instructions, but not the ones in method handles.
+#[derive(Default)]
+struct FieldReferences(Vec<FieldRef>);
+
+impl OperandVisitor for FieldReferences {
+	type Error = Infallible;
+
+	fn visit_field_ref(&mut self, field_ref: &FieldRef) -> Result<(), Infallible> {
+		// fields on array classes don't exist
+		if !field_ref.class.is_array() {
+			self.0.push(field_ref.clone());
+		}
+		Ok(())
+	}
+
+	fn visit_handle(&mut self, _handle: &Handle) -> Result<(), Infallible> {
+		Ok(())
+	}
+}
+
+/// Collects the `invokedynamic` call sites.
+#[derive(Default)]
+struct InvokeDynamics(Vec<InvokeDynamic>);
+
+impl OperandVisitor for InvokeDynamics {
+	type Error = Infallible;
+
+	fn visit_invoke_dynamic(&mut self, invoke_dynamic: &InvokeDynamic) -> Result<(), Infallible> {
+		self.0.push(invoke_dynamic.clone());
+		Ok(())
+	}
+}
+
+/// An `invokedynamic` instruction, together with the method containing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndyCallSite {
+	/// The method the `invokedynamic` instruction is in.
+	pub method: MethodNameAndDesc,
+	/// The name, descriptor, bootstrap method and bootstrap arguments of the call site.
+	pub invoke_dynamic: InvokeDynamic,
+}
+
+impl ClassFile {
+	/// Collects the classes this class refers to.
+	///
+	/// These are the classes needed to load, link and run the code of this class: the super class and interfaces, the
+	/// classes in the descriptors of fields and methods, the exceptions declared by methods, the `NestHost`, the class of the
+	/// `EnclosingMethod`, and all classes referenced from the code of the methods (including the classes in the descriptors
+	/// of referenced fields and methods). For array classes, the class of the elements is collected.
+	///
+	/// Classes only mentioned in the `InnerClasses` attribute, in signatures or in annotations are not collected. The class
+	/// itself isn't collected, unless it refers to itself (for example by calling one of its own methods).
+	pub fn referenced_classes(&self) -> Result<BTreeSet<ClassName>> {
+		let mut references = References::default();
+
+		if let Some(super_class) = &self.super_class {
+			references.add(super_class)?;
+		}
+		for interface in &self.interfaces {
+			references.add(interface)?;
+		}
+		if let Some(nest_host_class) = &self.nest_host_class {
+			references.add(nest_host_class)?;
+		}
+		if let Some(enclosing_method) = &self.enclosing_method {
+			references.add(&enclosing_method.class)?;
+		}
+
+		for field in &self.fields {
+			references.add_field_descriptor(&field.descriptor)?;
+		}
+		for method in &self.methods {
+			references.add_method_descriptor(&method.descriptor)?;
+			for exception in method.exceptions.iter().flatten() {
+				references.add(exception)?;
+			}
+
+			if let Some(code) = &method.code {
+				code.accept_operands(&mut references)?;
+				for exception in &code.exception_table {
+					if let Some(catch) = &exception.catch {
+						references.add(catch)?;
+					}
+				}
+			}
+		}
+
+		Ok(references.0)
+	}
+
+	/// Collects the fields and methods referenced from the code of the methods of this class.
+	///
+	/// This includes the fields and methods accessed by instructions, as well as the ones in method handles, for example
+	/// the bootstrap methods and implementation methods of lambdas.
+	pub fn referenced_members(&self) -> BTreeSet<MemberRef> {
+		let mut references = MemberReferences::default();
+
+		for code in self.methods.iter().filter_map(|method| method.code.as_ref()) {
+			let Ok(()) = code.accept_operands(&mut references);
+		}
+
+		references.0
+	}
+
+	/// Collects the fields accessed by `getfield`, `putfield`, `getstatic` and `putstatic` instructions in the code of the
+	/// methods of this class, in the order they appear.
+	///
+	/// A field accessed multiple times is collected each time. Fields on array classes don't exist, so references to them
+	/// are left out. Unlike [`ClassFile::referenced_members`], fields only referenced by method handles are not collected.
+	pub fn field_references(&self) -> Vec<FieldRef> {
+		let mut field_references = FieldReferences::default();
+
+		for code in self.methods.iter().filter_map(|method| method.code.as_ref()) {
+			let Ok(()) = code.accept_operands(&mut field_references);
+		}
+
+		field_references.0
+	}
+
+	/// Collects all `invokedynamic` call sites in the code of the methods of this class, in the order they appear.
+	///
+	/// Use [`InvokeDynamic::is_lambda`] and [`InvokeDynamic::lambda_implementation`] on them to find the lambdas targeting
+	/// some method.
+	pub fn indy_call_sites(&self) -> Vec<IndyCallSite> {
+		let mut call_sites = Vec::new();
+
+		for method in &self.methods {
+			let Some(code) = &method.code else { continue };
+
+			let mut invoke_dynamics = InvokeDynamics::default();
+			let Ok(()) = code.accept_operands(&mut invoke_dynamics);
+
+			call_sites.extend(invoke_dynamics.0.into_iter().map(|invoke_dynamic| IndyCallSite {
+				method: method.as_name_and_desc(),
+				invoke_dynamic,
+			}));
+		}
+
+		call_sites
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::{Context, Result};
+	use java_string::JavaStr;
+	use pretty_assertions::assert_eq;
+	use crate::tree::class::ClassName;
+	use crate::tree::field::{FieldDescriptor, FieldName, FieldRef};
+	use crate::tree::method::code::{Handle, Instruction, InstructionListEntry, Loadable};
+	use crate::tree::references::MemberRef;
+
+	#[test]
+	fn referenced_classes() -> Result<()> {
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("test/Shape$Circle.class")))?;
+
+		let references = class.referenced_classes()?;
+		let references: Vec<&JavaStr> = references.iter().map(|class| class.as_inner()).collect();
+		assert_eq!(references, ["Shape", "java/lang/Object"]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn referenced_members() -> Result<()> {
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("test/Shape$Circle.class")))?;
+
+		let references: Vec<String> = class.referenced_members().iter()
+			.map(|reference| match reference {
+				MemberRef::Field(field_ref) => format!("{}.{}:{}", field_ref.class, field_ref.name, field_ref.desc),
+				MemberRef::Method(method_ref) => format!("{}.{}{}", method_ref.class, method_ref.name, method_ref.desc),
+			})
+			.collect();
+		assert_eq!(references, ["java/lang/Object.<init>()V"]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn field_references() -> Result<()> {
+		let mut class = crate::read_class(&mut Cursor::new(include_bytes!("test/FieldAccess.class")))?;
+
+		let format = |field_refs: &[FieldRef]| -> Vec<String> {
+			field_refs.iter()
+				.map(|field_ref| format!("{}.{}:{}", field_ref.class, field_ref.name, field_ref.desc))
+				.collect()
+		};
+
+		let expected = [
+			"java/lang/System.out:Ljava/io/PrintStream;",
+			"FieldAccess.count:I",
+			"FieldAccess.count:I",
+		];
+		assert_eq!(format(&class.field_references()), expected);
+
+		let raw = crate::read_class_raw_code(&mut Cursor::new(include_bytes!("test/FieldAccess.class")))?;
+		assert_eq!(format(&raw.field_references()), expected);
+
+		// fields of array classes are left out
+		let code = class.methods.iter_mut().find_map(|method| method.code.as_mut()).context("no code")?;
+		code.instructions.insert(0, InstructionListEntry {
+			label: None,
+			frame: None,
+			offset: None,
+			instruction: Instruction::GetField(FieldRef {
+				class: ClassName::try_from(JavaStr::from_str("[I"))?,
+				name: FieldName::try_from(JavaStr::from_str("length"))?,
+				desc: FieldDescriptor::try_from(JavaStr::from_str("I"))?,
+			}),
+		});
+		assert_eq!(format(&class.field_references()), expected);
+
+		Ok(())
+	}
+
+	#[test]
+	fn indy_call_sites() -> Result<()> {
+		let class = crate::read_class(&mut Cursor::new(include_bytes!("test/Lambdas.class")))?;
+
+		let call_sites = class.indy_call_sites();
+		assert_eq!(call_sites.len(), 2);
+
+		let lambda = &call_sites[0];
+		assert_eq!(lambda.method.name.as_inner(), "supplier");
+		assert_eq!(lambda.invoke_dynamic.name.as_inner(), "get");
+		assert_eq!(lambda.invoke_dynamic.descriptor.as_inner(), "()Ljava/util/function/Supplier;");
+		let Handle::InvokeStatic(bootstrap, false) = &lambda.invoke_dynamic.handle else { panic!("not an invokestatic handle") };
+		assert_eq!(bootstrap.class.as_inner(), "java/lang/invoke/LambdaMetafactory");
+		assert_eq!(bootstrap.name.as_inner(), "metafactory");
+		assert!(lambda.invoke_dynamic.is_lambda());
+		let Some(Handle::InvokeStatic(implementation, false)) = lambda.invoke_dynamic.lambda_implementation() else { panic!("no lambda implementation") };
+		assert_eq!(implementation.name.as_inner(), "lambda$supplier$0");
+
+		let concat = &call_sites[1];
+		assert_eq!(concat.method.name.as_inner(), "concat");
+		assert_eq!(concat.invoke_dynamic.name.as_inner(), "makeConcatWithConstants");
+		assert!(!concat.invoke_dynamic.is_lambda());
+		assert_eq!(concat.invoke_dynamic.arguments, [Loadable::String("\u{1}\u{1}".into())]);
+
+		let raw = crate::read_class_raw_code(&mut Cursor::new(include_bytes!("test/Lambdas.class")))?;
+		assert_eq!(raw.indy_call_sites(), call_sites);
+
+		Ok(())
+	}
+}
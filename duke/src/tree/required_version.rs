@@ -0,0 +1,147 @@
+//! Computing the class file version needed for the features a class uses.
+
+use std::convert::Infallible;
+use crate::tree::class::ClassFile;
+use crate::tree::field::Field;
+use crate::tree::method::code::{Code, Handle, Instruction, InvokeDynamic, Loadable};
+use crate::tree::method::operands::OperandVisitor;
+use crate::tree::method::{Method, MethodName};
+use crate::tree::version::Version;
+
+/// The highest version required so far.
+struct Required(Version);
+
+impl Required {
+	fn require(&mut self, version: Version) {
+		self.0 = self.0.max(version);
+	}
+
+	fn require_if(&mut self, condition: bool, version: Version) {
+		if condition {
+			self.require(version);
+		}
+	}
+
+	fn code(&mut self, code: &Code) {
+		for entry in &code.instructions {
+			self.require_if(entry.frame.is_some(), Version::V1_6);
+			// calling static or private methods of interfaces needs Java 8
+			self.require_if(matches!(entry.instruction, Instruction::InvokeStatic(_, true) | Instruction::InvokeSpecial(_, true)), Version::V1_8);
+		}
+		let Ok(()) = code.accept_operands(self);
+		if let Some(local_variables) = &code.local_variables {
+			self.require_if(local_variables.iter().any(|lv| lv.signature.is_some()), Version::V1_5);
+		}
+		self.require_if(!code.runtime_visible_type_annotations.is_empty() || !code.runtime_invisible_type_annotations.is_empty(), Version::V1_8);
+	}
+
+	fn field(&mut self, field: &Field) {
+		self.require_if(field.access.is_synthetic || field.access.is_enum, Version::V1_5);
+		self.require_if(field.signature.is_some(), Version::V1_5);
+		self.require_if(!field.runtime_visible_annotations.is_empty() || !field.runtime_invisible_annotations.is_empty(), Version::V1_5);
+		self.require_if(!field.runtime_visible_type_annotations.is_empty() || !field.runtime_invisible_type_annotations.is_empty(), Version::V1_8);
+	}
+
+	fn method(&mut self, method: &Method, in_interface: bool) {
+		self.require_if(method.access.is_synthetic || method.access.is_bridge || method.access.is_varargs, Version::V1_5);
+		self.require_if(method.signature.is_some(), Version::V1_5);
+		self.require_if(!method.runtime_visible_annotations.is_empty() || !method.runtime_invisible_annotations.is_empty(), Version::V1_5);
+		self.require_if(method.annotation_default.is_some(), Version::V1_5);
+		self.require_if(!method.runtime_visible_type_annotations.is_empty() || !method.runtime_invisible_type_annotations.is_empty(), Version::V1_8);
+		self.require_if(method.method_parameters.is_some(), Version::V1_8);
+
+		// default, static and private methods in interfaces, apart from the static initializer
+		self.require_if(in_interface && !method.access.is_abstract && method.name.as_slice() != MethodName::CLINIT, Version::V1_8);
+
+		if let Some(code) = &method.code {
+			self.code(code);
+		}
+	}
+}
+
+impl OperandVisitor for Required {
+	type Error = Infallible;
+
+	fn visit_handle(&mut self, handle: &Handle) -> Result<(), Infallible> {
+		// method handles referring to interface methods using `invokestatic` or `invokespecial` need Java 8
+		self.require_if(matches!(handle, Handle::InvokeStatic(_, true) | Handle::InvokeSpecial(_, true)), Version::V1_8);
+		Ok(())
+	}
+
+	fn visit_loadable(&mut self, loadable: &Loadable) -> Result<(), Infallible> {
+		match loadable {
+			Loadable::Class(_) => self.require(Version::V1_5),
+			Loadable::MethodHandle(_) | Loadable::MethodType(_) => self.require(Version::V1_7),
+			Loadable::Dynamic(_) => self.require(Version::V11),
+			_ => {},
+		}
+		Ok(())
+	}
+
+	fn visit_invoke_dynamic(&mut self, _invoke_dynamic: &InvokeDynamic) -> Result<(), Infallible> {
+		self.require(Version::V1_7);
+		Ok(())
+	}
+}
+
+impl ClassFile {
+	/// Computes the lowest class file version that supports all the features this class uses.
+	///
+	/// This looks at the access flags, the attributes and the instructions of the class. For example, the `invokedynamic`
+	/// instruction needs Java 7 (class file version 51), nest based access needs Java 11 (version 55) and records need Java 16
+	/// (version 60). A class without any such features gives [`Version::V1_1`].
+	///
+	/// If the [`version`][ClassFile::version] of the class is lower than this, the class is malformed. Unknown attributes
+	/// in [`attributes`][ClassFile::attributes] aren't considered.
+	pub fn minimum_required_version(&self) -> Version {
+		let mut required = Required(Version::V1_1);
+
+		required.require_if(self.access.is_synthetic || self.access.is_annotation || self.access.is_enum, Version::V1_5);
+		required.require_if(self.access.is_module, Version::V9);
+
+		required.require_if(self.enclosing_method.is_some(), Version::V1_5);
+		required.require_if(self.signature.is_some(), Version::V1_5);
+		required.require_if(self.source_debug_extension.is_some(), Version::V1_5);
+		required.require_if(!self.runtime_visible_annotations.is_empty() || !self.runtime_invisible_annotations.is_empty(), Version::V1_5);
+		required.require_if(!self.runtime_visible_type_annotations.is_empty() || !self.runtime_invisible_type_annotations.is_empty(), Version::V1_8);
+		required.require_if(self.module.is_some() || self.module_packages.is_some() || self.module_main_class.is_some(), Version::V9);
+		required.require_if(self.nest_host_class.is_some() || self.nest_members.is_some(), Version::V11);
+		required.require_if(!self.record_components.is_empty(), Version::V16);
+		required.require_if(self.permitted_subclasses.is_some(), Version::V17);
+
+		for field in &self.fields {
+			required.field(field);
+		}
+		for method in &self.methods {
+			required.method(method, self.access.is_interface);
+		}
+
+		required.0
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::Result;
+	use pretty_assertions::assert_eq;
+	use crate::tree::version::Version;
+
+	#[test]
+	fn minimum_required_version() -> Result<()> {
+		// `invokedynamic` for `toString`, `hashCode` and `equals`, but the `Record` attribute needs even more
+		let point = crate::read_class(&mut Cursor::new(include_bytes!("test/Point.class")))?;
+		assert!(point.minimum_required_version() >= Version::V1_7);
+		assert_eq!(point.minimum_required_version(), Version::V16);
+
+		// the `LocalVariableTypeTable` needs Java 5, the `StackMapTable` Java 6
+		let strip_debug_info = crate::read_class(&mut Cursor::new(include_bytes!("test/StripDebugInfo.class")))?;
+		assert_eq!(strip_debug_info.minimum_required_version(), Version::V1_6);
+
+		// no special features at all
+		let breakpoint = crate::read_class(&mut Cursor::new(include_bytes!("../class_reader/test/Breakpoint.class")))?;
+		assert_eq!(breakpoint.minimum_required_version(), Version::V1_1);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,777 @@
+use std::iter::Peekable;
+use anyhow::{anyhow, bail, Context, Result};
+use java_string::{Chars, JavaCodePoint, JavaString};
+use crate::tree::class::{ClassName, ClassSignature, ClassSignatureSlice};
+use crate::tree::field::{FieldSignature, FieldSignatureSlice};
+use crate::tree::method::{MethodSignature, MethodSignatureSlice};
+
+/// Represents a (possibly generic) type, as found in signatures.
+///
+/// This is the `JavaTypeSignature` of the Java Virtual Machine Specification.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum JavaTypeSignature {
+	/// A `byte`.
+	B,
+	/// A `char`.
+	C,
+	/// A `double`.
+	D,
+	/// A `float`.
+	F,
+	/// An `int`.
+	I,
+	/// A `long`.
+	J,
+	/// A `short`.
+	S,
+	/// A `boolean`.
+	Z,
+	/// A reference type, like a class, a type variable or an array.
+	Reference(ReferenceTypeSignature),
+}
+
+/// Represents a reference type, as found in signatures.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ReferenceTypeSignature {
+	/// A class type, possibly with type arguments, like `java.util.List<T>`.
+	Class(ClassTypeSignature),
+	/// A type variable, like `T`. This only stores the name of the type variable.
+	TypeVariable(JavaString),
+	/// An array, holding the component type.
+	Array(Box<JavaTypeSignature>),
+}
+
+/// Represents a class type, as found in signatures.
+///
+/// For the class `java.util.Map.Entry<K, V>` this would have `java/util/Map` as the [`class_name`][ClassTypeSignature::class_name]
+/// (with no type arguments), and one inner class of name `Entry` with the type arguments `K` and `V`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ClassTypeSignature {
+	/// The name of the outermost class, including the package.
+	pub class_name: ClassName,
+	/// The type arguments of the outermost class.
+	pub type_arguments: Vec<TypeArgument>,
+	/// The inner classes, from outermost to innermost.
+	pub inner_classes: Vec<InnerClassTypeSignature>,
+}
+
+/// Represents an inner class part of a [`ClassTypeSignature`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InnerClassTypeSignature {
+	/// The simple name of the inner class, i.e. the part that is joined with `$` to the outer class name.
+	pub name: JavaString,
+	pub type_arguments: Vec<TypeArgument>,
+}
+
+/// Represents a type argument of a class type.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TypeArgument {
+	/// The unbounded wildcard, `?` in java source code, or `*` in the signature.
+	Wildcard,
+	/// A wildcard with an upper bound, `? extends T` in java source code, or `+` in the signature.
+	Extends(ReferenceTypeSignature),
+	/// A wildcard with a lower bound, `? super T` in java source code, or `-` in the signature.
+	Super(ReferenceTypeSignature),
+	/// A type argument without a wildcard.
+	Exact(ReferenceTypeSignature),
+}
+
+/// Represents a type parameter of a generic class or method, like `T extends Comparable<T>`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TypeParameter {
+	pub name: JavaString,
+	/// The class bound, this is [`None`] if the class bound is empty, such as when there are only interface bounds.
+	pub class_bound: Option<ReferenceTypeSignature>,
+	pub interface_bounds: Vec<ReferenceTypeSignature>,
+}
+
+/// Represents a type that can be thrown, as found in method signatures.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ThrowsSignature {
+	Class(ClassTypeSignature),
+	TypeVariable(JavaString),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParsedClassSignature {
+	pub type_parameters: Vec<TypeParameter>,
+	pub super_class: ClassTypeSignature,
+	pub interfaces: Vec<ClassTypeSignature>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParsedMethodSignature {
+	pub type_parameters: Vec<TypeParameter>,
+	pub parameters: Vec<JavaTypeSignature>,
+	/// The return type, [`None`] represents `void`.
+	pub return_type: Option<JavaTypeSignature>,
+	pub throws: Vec<ThrowsSignature>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParsedFieldSignature(pub ReferenceTypeSignature);
+
+impl ClassTypeSignature {
+	/// Gets the class name of the class this type refers to, i.e. the type with all type arguments removed.
+	///
+	/// Inner classes are joined together with a `$`.
+	pub fn erasure(&self) -> ClassName {
+		let mut s = self.class_name.as_inner().to_owned();
+		for inner_class in &self.inner_classes {
+			s.push('$');
+			s.push_java_str(&inner_class.name);
+		}
+		// SAFETY: Joining a class name and non-empty identifiers with `$` creates a valid class name.
+		unsafe { ClassName::from_inner_unchecked(s) }
+	}
+}
+
+// The grammar for signatures is:
+//   JavaTypeSignature:
+//     ReferenceTypeSignature | "B" | "C" | "D" | "F" | "I" | "J" | "S" | "Z"
+//
+//   ReferenceTypeSignature:
+//     ClassTypeSignature | TypeVariableSignature | ArrayTypeSignature
+//
+//   ClassTypeSignature:
+//     "L" [PackageSpecifier] SimpleClassTypeSignature {"." SimpleClassTypeSignature} ";"
+//
+//   PackageSpecifier:
+//     Identifier "/" {PackageSpecifier}
+//
+//   SimpleClassTypeSignature:
+//     Identifier [TypeArguments]
+//
+//   TypeArguments:
+//     "<" TypeArgument {TypeArgument} ">"
+//
+//   TypeArgument:
+//     ["+" | "-"] ReferenceTypeSignature | "*"
+//
+//   TypeVariableSignature:
+//     "T" Identifier ";"
+//
+//   ArrayTypeSignature:
+//     "[" JavaTypeSignature
+//
+//   TypeParameters:
+//     "<" TypeParameter {TypeParameter} ">"
+//
+//   TypeParameter:
+//     Identifier ":" [ReferenceTypeSignature] {":" ReferenceTypeSignature}
+//
+//   ClassSignature:
+//     [TypeParameters] ClassTypeSignature {ClassTypeSignature}
+//
+//   MethodSignature:
+//     [TypeParameters] "(" {JavaTypeSignature} ")" (JavaTypeSignature | "V") {"^" (ClassTypeSignature | TypeVariableSignature)}
+//
+//   FieldSignature:
+//     ReferenceTypeSignature
+fn next(chars: &mut Peekable<Chars>) -> Result<JavaCodePoint> {
+	chars.next().ok_or_else(|| anyhow!("unexpected abrupt ending of signature"))
+}
+
+/// Reads an identifier, stopping (without consuming) at any of the `terminators`.
+fn read_identifier(chars: &mut Peekable<Chars>, terminators: &[char]) -> Result<JavaString> {
+	let mut s = JavaString::new();
+	while let Some(char) = chars.next_if(|c| !terminators.iter().any(|t| c == t)) {
+		if matches!(char.as_char(), Some('.' | ';' | '[' | '/' | '<' | '>' | ':')) {
+			bail!("unexpected char {char:?} in identifier {s:?} in signature");
+		}
+		s.push_java(char);
+	}
+	if s.is_empty() {
+		bail!("expected identifier in signature, got {:?}", chars.peek());
+	}
+	Ok(s)
+}
+
+fn read_java_type_signature(chars: &mut Peekable<Chars>) -> Result<JavaTypeSignature> {
+	Ok(match chars.peek().and_then(|c| c.as_char()) {
+		Some('B') => { chars.next(); JavaTypeSignature::B },
+		Some('C') => { chars.next(); JavaTypeSignature::C },
+		Some('D') => { chars.next(); JavaTypeSignature::D },
+		Some('F') => { chars.next(); JavaTypeSignature::F },
+		Some('I') => { chars.next(); JavaTypeSignature::I },
+		Some('J') => { chars.next(); JavaTypeSignature::J },
+		Some('S') => { chars.next(); JavaTypeSignature::S },
+		Some('Z') => { chars.next(); JavaTypeSignature::Z },
+		_ => JavaTypeSignature::Reference(read_reference_type_signature(chars)?),
+	})
+}
+
+fn read_reference_type_signature(chars: &mut Peekable<Chars>) -> Result<ReferenceTypeSignature> {
+	let char = next(chars)?;
+	Ok(match char.as_char() {
+		Some('L') => ReferenceTypeSignature::Class(read_class_type_signature_after_l(chars)?),
+		Some('T') => ReferenceTypeSignature::TypeVariable(read_type_variable_signature_after_t(chars)?),
+		Some('[') => {
+			// read all dimensions at once, so that nested arrays don't recurse
+			let mut dimensions = 1;
+			while chars.next_if(|c| c.as_char() == Some('[')).is_some() {
+				dimensions += 1;
+			}
+			// like for descriptors, see JVMS §4.3.2
+			if dimensions > 255 {
+				bail!("array type in signature has {dimensions} dimensions, more than the allowed 255");
+			}
+
+			let mut component = read_java_type_signature(chars)?;
+			for _ in 1..dimensions {
+				component = JavaTypeSignature::Reference(ReferenceTypeSignature::Array(Box::new(component)));
+			}
+			ReferenceTypeSignature::Array(Box::new(component))
+		},
+		_ => bail!("unexpected char {char:?} in signature, expected one of `L`, `T` or `[`"),
+	})
+}
+
+fn read_class_type_signature(chars: &mut Peekable<Chars>) -> Result<ClassTypeSignature> {
+	let char = next(chars)?;
+	if char != 'L' {
+		bail!("unexpected char {char:?} in signature, expected `L`");
+	}
+	read_class_type_signature_after_l(chars)
+}
+
+fn read_class_type_signature_after_l(chars: &mut Peekable<Chars>) -> Result<ClassTypeSignature> {
+	let mut class_name = read_identifier(chars, &['/', '<', '.', ';'])?;
+	while chars.next_if_eq(&'/').is_some() {
+		class_name.push('/');
+		class_name.push_java_str(&read_identifier(chars, &['/', '<', '.', ';'])?);
+	}
+	// SAFETY: A `/` separated list of non-empty identifiers is a valid class name.
+	let class_name = unsafe { ClassName::from_inner_unchecked(class_name) };
+
+	let type_arguments = read_type_arguments(chars)?;
+
+	let mut inner_classes = Vec::new();
+	while chars.next_if_eq(&'.').is_some() {
+		let name = read_identifier(chars, &['<', '.', ';'])?;
+		let type_arguments = read_type_arguments(chars)?;
+		inner_classes.push(InnerClassTypeSignature { name, type_arguments });
+	}
+
+	let char = next(chars)?;
+	if char != ';' {
+		bail!("unexpected char {char:?} in signature, expected `;` to end class type signature");
+	}
+
+	Ok(ClassTypeSignature { class_name, type_arguments, inner_classes })
+}
+
+fn read_type_arguments(chars: &mut Peekable<Chars>) -> Result<Vec<TypeArgument>> {
+	let mut type_arguments = Vec::new();
+	if chars.next_if_eq(&'<').is_some() {
+		loop {
+			let type_argument = if chars.next_if_eq(&'*').is_some() {
+				TypeArgument::Wildcard
+			} else if chars.next_if_eq(&'+').is_some() {
+				TypeArgument::Extends(read_reference_type_signature(chars)?)
+			} else if chars.next_if_eq(&'-').is_some() {
+				TypeArgument::Super(read_reference_type_signature(chars)?)
+			} else {
+				TypeArgument::Exact(read_reference_type_signature(chars)?)
+			};
+			type_arguments.push(type_argument);
+
+			if chars.next_if_eq(&'>').is_some() {
+				break;
+			}
+		}
+	}
+	Ok(type_arguments)
+}
+
+fn read_type_variable_signature_after_t(chars: &mut Peekable<Chars>) -> Result<JavaString> {
+	let name = read_identifier(chars, &[';'])?;
+	let char = next(chars)?;
+	if char != ';' {
+		bail!("unexpected char {char:?} in signature, expected `;` to end type variable signature");
+	}
+	Ok(name)
+}
+
+fn read_type_parameters(chars: &mut Peekable<Chars>) -> Result<Vec<TypeParameter>> {
+	let mut type_parameters = Vec::new();
+	if chars.next_if_eq(&'<').is_some() {
+		loop {
+			let name = read_identifier(chars, &[':'])?;
+
+			let char = next(chars)?;
+			if char != ':' {
+				bail!("unexpected char {char:?} in signature, expected `:` to start class bound of type parameter {name:?}");
+			}
+			let class_bound = if chars.peek().is_some_and(|&c| c == ':' || c == '>') {
+				None
+			} else {
+				Some(read_reference_type_signature(chars)?)
+			};
+
+			let mut interface_bounds = Vec::new();
+			while chars.next_if_eq(&':').is_some() {
+				interface_bounds.push(read_reference_type_signature(chars)?);
+			}
+
+			type_parameters.push(TypeParameter { name, class_bound, interface_bounds });
+
+			if chars.next_if_eq(&'>').is_some() {
+				break;
+			}
+		}
+	}
+	Ok(type_parameters)
+}
+
+fn expect_end(chars: Peekable<Chars>) -> Result<()> {
+	let remaining = JavaString::from_iter(chars);
+	if !remaining.is_empty() {
+		bail!("expected end of signature, got {remaining:?} remaining");
+	}
+	Ok(())
+}
+
+fn write_java_type_signature(t: &JavaTypeSignature, string: &mut JavaString) {
+	match t {
+		JavaTypeSignature::B => string.push('B'),
+		JavaTypeSignature::C => string.push('C'),
+		JavaTypeSignature::D => string.push('D'),
+		JavaTypeSignature::F => string.push('F'),
+		JavaTypeSignature::I => string.push('I'),
+		JavaTypeSignature::J => string.push('J'),
+		JavaTypeSignature::S => string.push('S'),
+		JavaTypeSignature::Z => string.push('Z'),
+		JavaTypeSignature::Reference(reference) => write_reference_type_signature(reference, string),
+	}
+}
+
+fn write_reference_type_signature(t: &ReferenceTypeSignature, string: &mut JavaString) {
+	match t {
+		ReferenceTypeSignature::Class(class) => write_class_type_signature(class, string),
+		ReferenceTypeSignature::TypeVariable(name) => write_type_variable_signature(name, string),
+		ReferenceTypeSignature::Array(component) => {
+			string.push('[');
+			write_java_type_signature(component, string);
+		},
+	}
+}
+
+fn write_class_type_signature(t: &ClassTypeSignature, string: &mut JavaString) {
+	string.push('L');
+	string.push_java_str(t.class_name.as_inner());
+	write_type_arguments(&t.type_arguments, string);
+	for inner_class in &t.inner_classes {
+		string.push('.');
+		string.push_java_str(&inner_class.name);
+		write_type_arguments(&inner_class.type_arguments, string);
+	}
+	string.push(';');
+}
+
+fn write_type_arguments(type_arguments: &[TypeArgument], string: &mut JavaString) {
+	if !type_arguments.is_empty() {
+		string.push('<');
+		for type_argument in type_arguments {
+			match type_argument {
+				TypeArgument::Wildcard => string.push('*'),
+				TypeArgument::Extends(t) => {
+					string.push('+');
+					write_reference_type_signature(t, string);
+				},
+				TypeArgument::Super(t) => {
+					string.push('-');
+					write_reference_type_signature(t, string);
+				},
+				TypeArgument::Exact(t) => write_reference_type_signature(t, string),
+			}
+		}
+		string.push('>');
+	}
+}
+
+fn write_type_variable_signature(name: &JavaString, string: &mut JavaString) {
+	string.push('T');
+	string.push_java_str(name);
+	string.push(';');
+}
+
+fn write_type_parameters(type_parameters: &[TypeParameter], string: &mut JavaString) {
+	if !type_parameters.is_empty() {
+		string.push('<');
+		for type_parameter in type_parameters {
+			string.push_java_str(&type_parameter.name);
+			string.push(':');
+			if let Some(class_bound) = &type_parameter.class_bound {
+				write_reference_type_signature(class_bound, string);
+			}
+			for interface_bound in &type_parameter.interface_bounds {
+				string.push(':');
+				write_reference_type_signature(interface_bound, string);
+			}
+		}
+		string.push('>');
+	}
+}
+
+impl ClassSignatureSlice {
+	/// Attempts to parse a class signature.
+	///
+	/// A class signature is defined by the [grammar](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-4.html#jvms-4.7.9.1) in the
+	/// Java Virtual Machine Specification.
+	///
+	/// The inverse of this function is [`ParsedClassSignature::write`].
+	pub fn parse(&self) -> Result<ParsedClassSignature> {
+		let mut chars = self.as_inner().chars().peekable();
+
+		let type_parameters = read_type_parameters(&mut chars)
+			.with_context(|| anyhow!("failed to read type parameters of class signature {self:?}"))?;
+		let super_class = read_class_type_signature(&mut chars)
+			.with_context(|| anyhow!("failed to read super class of class signature {self:?}"))?;
+		let mut interfaces = Vec::new();
+		while chars.peek().is_some() {
+			let interface = read_class_type_signature(&mut chars)
+				.with_context(|| anyhow!("failed to read interface of class signature {self:?}"))?;
+			interfaces.push(interface);
+		}
+
+		expect_end(chars).with_context(|| anyhow!("failed to read class signature {self:?}"))?;
+
+		Ok(ParsedClassSignature { type_parameters, super_class, interfaces })
+	}
+}
+
+impl ParsedClassSignature {
+	/// Writes a class signature.
+	///
+	/// The inverse of this function is [`ClassSignatureSlice::parse`].
+	pub fn write(&self) -> ClassSignature {
+		let mut s = JavaString::new();
+		write_type_parameters(&self.type_parameters, &mut s);
+		write_class_type_signature(&self.super_class, &mut s);
+		for interface in &self.interfaces {
+			write_class_type_signature(interface, &mut s);
+		}
+		// SAFETY: We just wrote a valid class signature to `s`.
+		unsafe { ClassSignature::from_inner_unchecked(s) }
+	}
+}
+
+impl MethodSignatureSlice {
+	/// Attempts to parse a method signature.
+	///
+	/// A method signature is defined by the [grammar](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-4.html#jvms-4.7.9.1) in the
+	/// Java Virtual Machine Specification.
+	///
+	/// The inverse of this function is [`ParsedMethodSignature::write`].
+	///
+	/// # Examples
+	/// ```
+	/// # use pretty_assertions::assert_eq;
+	/// use duke::tree::class::ClassName;
+	/// use duke::tree::method::MethodSignatureSlice;
+	/// use duke::tree::signature::{ClassTypeSignature, JavaTypeSignature, ParsedMethodSignature, ReferenceTypeSignature, TypeParameter};
+	///
+	/// // the signature of `<T> T identity(T t)`
+	/// let signature = unsafe { MethodSignatureSlice::from_inner_unchecked("<T:Ljava/lang/Object;>(TT;)TT;".into()) };
+	///
+	/// let t = JavaTypeSignature::Reference(ReferenceTypeSignature::TypeVariable("T".into()));
+	/// assert_eq!(
+	///     signature.parse().unwrap(),
+	///     ParsedMethodSignature {
+	///         type_parameters: vec![
+	///             TypeParameter {
+	///                 name: "T".into(),
+	///                 class_bound: Some(ReferenceTypeSignature::Class(ClassTypeSignature {
+	///                     class_name: ClassName::JAVA_LANG_OBJECT.to_owned(),
+	///                     type_arguments: vec![],
+	///                     inner_classes: vec![],
+	///                 })),
+	///                 interface_bounds: vec![],
+	///             },
+	///         ],
+	///         parameters: vec![t.clone()],
+	///         return_type: Some(t),
+	///         throws: vec![],
+	///     }
+	/// );
+	///
+	/// assert_eq!(signature, signature.parse().unwrap().write());
+	/// ```
+	pub fn parse(&self) -> Result<ParsedMethodSignature> {
+		let mut chars = self.as_inner().chars().peekable();
+
+		let type_parameters = read_type_parameters(&mut chars)
+			.with_context(|| anyhow!("failed to read type parameters of method signature {self:?}"))?;
+
+		if chars.next_if_eq(&'(').is_none() {
+			bail!("method signature {self:?} doesn't have '(' after the type parameters");
+		}
+
+		let mut parameters = Vec::new();
+		while chars.next_if_eq(&')').is_none() {
+			let parameter = read_java_type_signature(&mut chars)
+				.with_context(|| anyhow!("failed to read parameter of method signature {self:?}"))?;
+			parameters.push(parameter);
+		}
+
+		let return_type = if chars.next_if_eq(&'V').is_some() {
+			None
+		} else {
+			let return_type = read_java_type_signature(&mut chars)
+				.with_context(|| anyhow!("failed to read return type of method signature {self:?}"))?;
+			Some(return_type)
+		};
+
+		let mut throws = Vec::new();
+		while chars.next_if_eq(&'^').is_some() {
+			let throw = match read_reference_type_signature(&mut chars) {
+				Ok(ReferenceTypeSignature::Class(class)) => ThrowsSignature::Class(class),
+				Ok(ReferenceTypeSignature::TypeVariable(name)) => ThrowsSignature::TypeVariable(name),
+				Ok(ReferenceTypeSignature::Array(_)) => bail!("method signature {self:?} cannot throw an array"),
+				Err(e) => return Err(e).with_context(|| anyhow!("failed to read throws of method signature {self:?}")),
+			};
+			throws.push(throw);
+		}
+
+		expect_end(chars).with_context(|| anyhow!("failed to read method signature {self:?}"))?;
+
+		Ok(ParsedMethodSignature { type_parameters, parameters, return_type, throws })
+	}
+}
+
+impl ParsedMethodSignature {
+	/// Writes a method signature.
+	///
+	/// The inverse of this function is [`MethodSignatureSlice::parse`].
+	pub fn write(&self) -> MethodSignature {
+		let mut s = JavaString::new();
+		write_type_parameters(&self.type_parameters, &mut s);
+		s.push('(');
+		for parameter in &self.parameters {
+			write_java_type_signature(parameter, &mut s);
+		}
+		s.push(')');
+		if let Some(return_type) = &self.return_type {
+			write_java_type_signature(return_type, &mut s);
+		} else {
+			s.push('V');
+		}
+		for throw in &self.throws {
+			s.push('^');
+			match throw {
+				ThrowsSignature::Class(class) => write_class_type_signature(class, &mut s),
+				ThrowsSignature::TypeVariable(name) => write_type_variable_signature(name, &mut s),
+			}
+		}
+		// SAFETY: We just wrote a valid method signature to `s`.
+		unsafe { MethodSignature::from_inner_unchecked(s) }
+	}
+}
+
+impl FieldSignatureSlice {
+	/// Attempts to parse a field signature.
+	///
+	/// A field signature is defined by the [grammar](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-4.html#jvms-4.7.9.1) in the
+	/// Java Virtual Machine Specification.
+	///
+	/// The inverse of this function is [`ParsedFieldSignature::write`].
+	pub fn parse(&self) -> Result<ParsedFieldSignature> {
+		let mut chars = self.as_inner().chars().peekable();
+
+		let signature = read_reference_type_signature(&mut chars)
+			.with_context(|| anyhow!("failed to read field signature {self:?}"))?;
+
+		expect_end(chars).with_context(|| anyhow!("failed to read field signature {self:?}"))?;
+
+		Ok(ParsedFieldSignature(signature))
+	}
+}
+
+impl ParsedFieldSignature {
+	/// Writes a field signature.
+	///
+	/// The inverse of this function is [`FieldSignatureSlice::parse`].
+	pub fn write(&self) -> FieldSignature {
+		let mut s = JavaString::new();
+		write_reference_type_signature(&self.0, &mut s);
+		// SAFETY: We just wrote a valid field signature to `s`.
+		unsafe { FieldSignature::from_inner_unchecked(s) }
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use pretty_assertions::assert_eq;
+	use anyhow::Result;
+	use crate::tree::class::{ClassName, ClassNameSlice, ClassSignatureSlice};
+	use crate::tree::field::FieldSignatureSlice;
+	use crate::tree::method::MethodSignatureSlice;
+	use crate::tree::signature::{ClassTypeSignature, InnerClassTypeSignature, JavaTypeSignature, ParsedClassSignature, ParsedFieldSignature, ParsedMethodSignature, ReferenceTypeSignature, ThrowsSignature, TypeArgument, TypeParameter};
+
+	fn class(name: &str, type_arguments: Vec<TypeArgument>) -> ClassTypeSignature {
+		ClassTypeSignature {
+			// SAFETY: all of the class names below are valid class names.
+			class_name: unsafe { ClassNameSlice::from_inner_unchecked(name.into()) }.to_owned(),
+			type_arguments,
+			inner_classes: Vec::new(),
+		}
+	}
+
+	fn type_variable(name: &str) -> ReferenceTypeSignature {
+		ReferenceTypeSignature::TypeVariable(name.into())
+	}
+
+	#[test]
+	fn method_parse() -> Result<()> {
+		// SAFETY: This is a valid method signature.
+		let signature = unsafe { MethodSignatureSlice::from_inner_unchecked("<T:Ljava/lang/Object;>(TT;)TT;".into()) };
+		let parsed = ParsedMethodSignature {
+			type_parameters: vec![
+				TypeParameter {
+					name: "T".into(),
+					class_bound: Some(ReferenceTypeSignature::Class(class("java/lang/Object", vec![]))),
+					interface_bounds: vec![],
+				},
+			],
+			parameters: vec![
+				JavaTypeSignature::Reference(type_variable("T")),
+			],
+			return_type: Some(JavaTypeSignature::Reference(type_variable("T"))),
+			throws: vec![],
+		};
+		assert_eq!(signature.parse()?, parsed);
+		assert_eq!(parsed.write().as_inner(), "<T:Ljava/lang/Object;>(TT;)TT;");
+
+		// SAFETY: This is a valid method signature.
+		let signature = unsafe { MethodSignatureSlice::from_inner_unchecked("<E:Ljava/lang/Exception;>(I[Ljava/util/List<*>;)V^TE;^Ljava/io/IOException;".into()) };
+		let parsed = ParsedMethodSignature {
+			type_parameters: vec![
+				TypeParameter {
+					name: "E".into(),
+					class_bound: Some(ReferenceTypeSignature::Class(class("java/lang/Exception", vec![]))),
+					interface_bounds: vec![],
+				},
+			],
+			parameters: vec![
+				JavaTypeSignature::I,
+				JavaTypeSignature::Reference(ReferenceTypeSignature::Array(Box::new(
+					JavaTypeSignature::Reference(ReferenceTypeSignature::Class(class("java/util/List", vec![TypeArgument::Wildcard])))
+				))),
+			],
+			return_type: None,
+			throws: vec![
+				ThrowsSignature::TypeVariable("E".into()),
+				ThrowsSignature::Class(class("java/io/IOException", vec![])),
+			],
+		};
+		assert_eq!(signature.parse()?, parsed);
+		assert_eq!(parsed.write(), signature);
+
+		Ok(())
+	}
+
+	#[test]
+	fn method_parse_err() {
+		// SAFETY: These are all "valid" method signatures, as checking them isn't implemented.
+		unsafe {
+			assert!(MethodSignatureSlice::from_inner_unchecked("".into()).parse().is_err());
+			assert!(MethodSignatureSlice::from_inner_unchecked("(".into()).parse().is_err());
+			assert!(MethodSignatureSlice::from_inner_unchecked("()".into()).parse().is_err());
+			assert!(MethodSignatureSlice::from_inner_unchecked("<>()V".into()).parse().is_err());
+			assert!(MethodSignatureSlice::from_inner_unchecked("<T>()V".into()).parse().is_err());
+			assert!(MethodSignatureSlice::from_inner_unchecked("(TT)V".into()).parse().is_err());
+			assert!(MethodSignatureSlice::from_inner_unchecked("()V^[I".into()).parse().is_err());
+			assert!(MethodSignatureSlice::from_inner_unchecked("()VV".into()).parse().is_err());
+		}
+	}
+
+	#[test]
+	fn class_parse() -> Result<()> {
+		// the signature of `class Foo<K extends Comparable<K>, V> extends AbstractMap<K, V> implements Map.Entry<K, ? super V>`
+		// SAFETY: This is a valid class signature.
+		let signature = unsafe { ClassSignatureSlice::from_inner_unchecked(
+			"<K::Ljava/lang/Comparable<TK;>;V:Ljava/lang/Object;>Ljava/util/AbstractMap<TK;TV;>;Ljava/util/Map$Entry<TK;-TV;>;".into()
+		) };
+		let parsed = ParsedClassSignature {
+			type_parameters: vec![
+				TypeParameter {
+					name: "K".into(),
+					class_bound: None,
+					interface_bounds: vec![
+						ReferenceTypeSignature::Class(class("java/lang/Comparable", vec![TypeArgument::Exact(type_variable("K"))])),
+					],
+				},
+				TypeParameter {
+					name: "V".into(),
+					class_bound: Some(ReferenceTypeSignature::Class(class("java/lang/Object", vec![]))),
+					interface_bounds: vec![],
+				},
+			],
+			super_class: class("java/util/AbstractMap", vec![
+				TypeArgument::Exact(type_variable("K")),
+				TypeArgument::Exact(type_variable("V")),
+			]),
+			interfaces: vec![
+				class("java/util/Map$Entry", vec![
+					TypeArgument::Exact(type_variable("K")),
+					TypeArgument::Super(type_variable("V")),
+				]),
+			],
+		};
+		assert_eq!(signature.parse()?, parsed);
+		assert_eq!(parsed.write(), signature);
+
+		Ok(())
+	}
+
+	#[test]
+	fn field_parse() -> Result<()> {
+		// SAFETY: This is a valid field signature.
+		let signature = unsafe { FieldSignatureSlice::from_inner_unchecked("Ljava/util/Map<TK;TV;>.Entry<+Ljava/lang/Object;>;".into()) };
+		let parsed = ParsedFieldSignature(ReferenceTypeSignature::Class(ClassTypeSignature {
+			// SAFETY: `java/util/Map` is a valid class name.
+			class_name: unsafe { ClassNameSlice::from_inner_unchecked("java/util/Map".into()) }.to_owned(),
+			type_arguments: vec![
+				TypeArgument::Exact(type_variable("K")),
+				TypeArgument::Exact(type_variable("V")),
+			],
+			inner_classes: vec![
+				InnerClassTypeSignature {
+					name: "Entry".into(),
+					type_arguments: vec![
+						TypeArgument::Extends(ReferenceTypeSignature::Class(class("java/lang/Object", vec![]))),
+					],
+				},
+			],
+		}));
+		assert_eq!(signature.parse()?, parsed);
+		assert_eq!(parsed.write(), signature);
+
+		let ParsedFieldSignature(ReferenceTypeSignature::Class(class)) = parsed else { unreachable!() };
+		assert_eq!(class.erasure().as_inner(), "java/util/Map$Entry");
+		assert_eq!(class.inner_classes[0].type_arguments[0], TypeArgument::Extends(ReferenceTypeSignature::Class(ClassTypeSignature {
+			class_name: ClassName::JAVA_LANG_OBJECT.to_owned(),
+			type_arguments: vec![],
+			inner_classes: vec![],
+		})));
+
+		Ok(())
+	}
+
+	#[test]
+	fn field_parse_array_dimensions() -> Result<()> {
+		let signature = format!("{}TT;", "[".repeat(255));
+		// SAFETY: This is a valid field signature.
+		let signature = unsafe { FieldSignatureSlice::from_inner_unchecked(signature.as_str().into()) };
+		let parsed = signature.parse()?;
+		assert_eq!(parsed.write(), signature);
+
+		let signature = format!("{}TT;", "[".repeat(256));
+		// SAFETY: This is only invalid because of the number of dimensions, which isn't checked when creating it.
+		let signature = unsafe { FieldSignatureSlice::from_inner_unchecked(signature.as_str().into()) };
+		assert!(signature.parse().is_err());
+
+		Ok(())
+	}
+}
@@ -0,0 +1,19 @@
+// compile with `javac --release 8 Annotated.java`
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+
+@Annotated.Info(name = "class", value = 1)
+@Deprecated
+public class Annotated {
+	@Retention(RetentionPolicy.RUNTIME)
+	@interface Info {
+		String name();
+		int value();
+	}
+
+	@Info(name = "field", value = 2)
+	int field;
+
+	@Info(name = "method", value = 3)
+	void method() {}
+}
@@ -0,0 +1,8 @@
+// compile with `javac --release 8 Constants.java`
+public class Constants {
+	public static final int X = 42;
+	public static final String S = "hi";
+	public static final boolean B = true;
+	public static final char C = 'c';
+	public static final long L = -1L;
+}
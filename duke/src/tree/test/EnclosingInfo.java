@@ -0,0 +1,17 @@
+// compile with `javac --release 8 EnclosingInfo.java`
+class EnclosingInfo {
+	// compiled into `EnclosingInfo$1`, enclosed by the method `anonymous`
+	Runnable anonymous() {
+		return new Runnable() {
+			public void run() { }
+		};
+	}
+
+	// compiled into `EnclosingInfo$2`, enclosed by the class only, as it's in a field initializer
+	Runnable field = new Runnable() {
+		public void run() { }
+	};
+
+	// compiled into `EnclosingInfo$Nested`, has no `EnclosingMethod` attribute, as it's a member class
+	static class Nested { }
+}
@@ -0,0 +1,9 @@
+// compile with `javac --release 8 FieldAccess.java`
+public class FieldAccess {
+	private int count;
+
+	void print() {
+		System.out.println(count);
+		count = 1;
+	}
+}
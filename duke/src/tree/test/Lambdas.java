@@ -0,0 +1,12 @@
+// compile with `javac --release 17 Lambdas.java`
+import java.util.function.Supplier;
+
+public class Lambdas {
+	static Supplier<String> supplier() {
+		return () -> "hello";
+	}
+
+	static String concat(String a, int b) {
+		return a + b;
+	}
+}
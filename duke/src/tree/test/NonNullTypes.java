@@ -0,0 +1,17 @@
+// compile with `javac --release 8 NonNullTypes.java`
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+import java.util.List;
+import java.util.Map;
+
+public class NonNullTypes {
+	@Retention(RetentionPolicy.RUNTIME)
+	@Target(ElementType.TYPE_USE)
+	@interface NonNull {}
+
+	@NonNull String[] method(Map<String, @NonNull List<String>> map) throws @NonNull Exception {
+		return null;
+	}
+}
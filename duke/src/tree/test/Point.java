@@ -0,0 +1,5 @@
+// compile with `javac --release 17 Point.java`
+import java.util.List;
+
+public record Point(int x, int y, List<String> labels) {
+}
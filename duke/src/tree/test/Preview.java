@@ -0,0 +1,9 @@
+// compile with `javac --release 17 --enable-preview Preview.java`
+public class Preview {
+	static int length(Object o) {
+		return switch (o) {
+			case String s -> s.length();
+			default -> 0;
+		};
+	}
+}
@@ -0,0 +1,5 @@
+// compile with `javac --release 17 Shape.java`
+public sealed interface Shape permits Shape.Circle, Shape.Square {
+	final class Circle implements Shape {}
+	non-sealed class Square implements Shape {}
+}
@@ -0,0 +1,14 @@
+// compile with `javac --release 8 -g StripDebugInfo.java`
+import java.util.List;
+
+public class StripDebugInfo {
+	static int sum(List<Integer> list) {
+		int sum = 0;
+		for (int i : list) {
+			if (i > 0) {
+				sum += i;
+			}
+		}
+		return sum;
+	}
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use crate::tree::annotation::Annotation;
+use crate::tree::class::ClassName;
 use crate::tree::method::code::{Label, LabelRange, LvIndex};
 use crate::visitor::annotation::TypeAnnotationsVisitor;
 
@@ -159,12 +160,18 @@ pub enum TargetInfoCode {
 	},
 }
 
+/// A single step of a [`TypePath`], going one level deeper into a type.
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) enum TypePathKind {
+pub enum TypePathKind {
+	/// The annotation is deeper in the element type of an array type.
 	ArrayDeeper,
+	/// The annotation is deeper in a nested type, like on `B` in `A.B`.
 	NestedDeeper,
+	/// The annotation is on the bound of a wildcard type argument, like on `A` in `? extends A`.
 	WildcardBound,
+	/// The annotation is deeper in a type argument of a parameterized type.
 	TypeArgument {
+		/// Specifies the index of the type argument. `0` means the first type argument.
 		index: u8,
 	}
 }
@@ -175,6 +182,13 @@ pub struct TypePath {
 	pub(crate) path: Vec<TypePathKind>
 }
 
+impl TypePath {
+	/// Gets the steps to take, starting from the outermost type. No steps means the annotation is on the type itself.
+	pub fn steps(&self) -> &[TypePathKind] {
+		&self.path
+	}
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypeAnnotation<T> {
 	pub type_reference: T,
@@ -194,4 +208,47 @@ impl<T> TypeAnnotation<T> {
 
 		TypeAnnotationsVisitor::finish_type_annotation(visitor, pairs_visitor)
 	}
-}
\ No newline at end of file
+}
+
+/// The type a type annotation of a method is on, with the indices resolved where possible.
+///
+/// See [`ResolvedTypeAnnotation`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MethodTypeAnnotationTarget<'a> {
+	/// The annotation is on the `index`th type parameter of the method.
+	TypeParameter {
+		index: u8,
+	},
+	/// The annotation is on the `bound_index`th bound of the `type_parameter_index`th type parameter of the method.
+	TypeParameterBound {
+		type_parameter_index: u8,
+		bound_index: u8,
+	},
+	/// The annotation is on the return type, or for constructors on the type of the newly constructed object.
+	Return,
+	/// The annotation is on the receiver type.
+	Receiver,
+	/// The annotation is on the type of the `index`th formal parameter.
+	Parameter {
+		index: u8,
+	},
+	/// The annotation is on the `index`th entry of the `throws` clause.
+	Throws {
+		index: u16,
+		/// The class from the [`exceptions`][crate::tree::method::Method::exceptions] of the method at `index`, if
+		/// there is one.
+		exception: Option<&'a ClassName>,
+	},
+}
+
+/// A type annotation of a method, as given by [`Method::type_annotations`][crate::tree::method::Method::type_annotations].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedTypeAnnotation<'a> {
+	/// On which type of the method the annotation is.
+	pub target: MethodTypeAnnotationTarget<'a>,
+	/// Where in that type the annotation is, see [`TypePath::steps`].
+	pub path: &'a [TypePathKind],
+	pub annotation: &'a Annotation,
+	/// Whether the annotation is runtime visible.
+	pub visible: bool,
+}
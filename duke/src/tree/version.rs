@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use anyhow::{bail, Result};
 
 /// Represents a class file version.
 ///
@@ -59,9 +60,61 @@ impl Version {
 	#[allow(unused)]
 	pub const V23: Version = Version::new(67, 0);
 
+	/// The newest version that can be read. Class files with a newer version are rejected when reading them.
+	pub const NEWEST_SUPPORTED: Version = Version::V23;
+
+	/// The minor version used to mark a class file as depending on preview features.
+	pub const PREVIEW_MINOR: u16 = 0xFFFF;
+
 	pub(crate) const fn new(major: u16, minor: u16) -> Version {
 		Version { major, minor }
 	}
+
+	/// Creates a new version, checking that the combination of `major` and `minor` is one that's actually in use.
+	///
+	/// Reading a class file only rejects versions newer than [`NEWEST_SUPPORTED`][Version::NEWEST_SUPPORTED]. This also
+	/// rejects:
+	/// - a `major` below `45`,
+	/// - a `minor` other than `0`, except for the [preview minor][Version::PREVIEW_MINOR] for a `major` of at least `56`.
+	///
+	/// As an exception, `45` accepts any `minor`, as this was used by [Java 1.1][Version::V1_1] and earlier.
+	///
+	/// ```
+	/// use duke::tree::version::Version;
+	///
+	/// assert_eq!(Version::new_checked(52, 0).ok(), Some(Version::V1_8));
+	/// assert!(Version::new_checked(65, Version::PREVIEW_MINOR).is_ok());
+	/// assert!(Version::new_checked(52, 5).is_err());
+	/// ```
+	pub fn new_checked(major: u16, minor: u16) -> Result<Version> {
+		if major < 45 {
+			bail!("invalid class file version {major}.{minor}: major version must be at least 45");
+		}
+		if major != 45 && minor != 0 {
+			if minor != Version::PREVIEW_MINOR {
+				bail!("invalid class file version {major}.{minor}: minor version must be 0, or {} for preview features", Version::PREVIEW_MINOR);
+			}
+			if major < 56 {
+				bail!("invalid class file version {major}.{minor}: preview features require a major version of at least 56");
+			}
+		}
+		Ok(Version { major, minor })
+	}
+
+	/// The major version, like `52` for [Java 8][Version::V1_8].
+	pub fn major(&self) -> u16 {
+		self.major
+	}
+
+	/// The minor version. This is `0` for almost all class files, see [`Version::PREVIEW_MINOR`] and [`Version::V1_1`].
+	pub fn minor(&self) -> u16 {
+		self.minor
+	}
+
+	/// Checks if the class file depends on preview features, by having the minor version [`Version::PREVIEW_MINOR`].
+	pub fn is_preview(&self) -> bool {
+		self.minor == Version::PREVIEW_MINOR
+	}
 }
 
 impl PartialOrd for Version {
@@ -79,6 +132,7 @@ impl Ord for Version {
 
 #[cfg(test)]
 mod testing {
+	use anyhow::Result;
 	use crate::tree::version::Version;
 
 	#[test]
@@ -93,5 +147,31 @@ mod testing {
 		assert!(Version::V22 > Version::new(65, 1));
 		assert!(Version::new(65, 2) > Version::new(65, 1));
 	}
+
+	#[test]
+	fn new_checked() -> Result<()> {
+		assert_eq!(Version::new_checked(52, 0)?, Version::V1_8);
+		assert_eq!(Version::new_checked(45, 3)?, Version::V1_1);
+		assert_eq!(Version::new_checked(65, 0xFFFF)?, Version::new(65, 0xFFFF));
+
+		assert!(Version::new_checked(52, 5).is_err());
+		assert!(Version::new_checked(52, 0xFFFF).is_err());
+		assert!(Version::new_checked(44, 0).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn preview() -> Result<()> {
+		let class = crate::read_class(&mut std::io::Cursor::new(include_bytes!("test/Preview.class")))?;
+
+		let version = class.version();
+		assert!(version.is_preview());
+		assert_eq!(version.major(), Version::V17.major());
+		assert_eq!(version.minor(), Version::PREVIEW_MINOR);
+
+		assert!(!Version::V17.is_preview());
+		assert_eq!(Version::V1_1.minor(), 3);
+		Ok(())
+	}
 }
 
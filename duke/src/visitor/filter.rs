@@ -0,0 +1,297 @@
+//! Visitor adapters that filter what reaches the wrapped visitor.
+
+use std::ops::ControlFlow;
+use anyhow::Result;
+use java_string::JavaString;
+use crate::tree::class::{ClassAccess, ClassName, ClassSignature, EnclosingMethod, InnerClass};
+use crate::tree::field::{FieldAccess, FieldDescriptor, FieldName};
+use crate::tree::method::{MethodAccess, MethodDescriptor, MethodName};
+use crate::tree::module::{Module, PackageName};
+use crate::tree::record::RecordName;
+use crate::tree::version::Version;
+use crate::visitor::class::{ClassInterests, ClassVisitor};
+use crate::visitor::MultiClassVisitor;
+
+/// Drops synthetic fields, and synthetic or bridge methods, so that they never reach the wrapped visitor.
+///
+/// This can wrap both a [`MultiClassVisitor`] (see [`DropSyntheticMembers::new`]) and a [`ClassVisitor`] (see
+/// [`DropSyntheticMembers::for_class`]).
+///
+/// Members are only recognized by their access flags. Members only marked with a `Synthetic` attribute (as emitted by very
+/// old compilers) are kept, since the attributes are only visited after the decision to drop a member is made.
+///
+/// The synthetic `$VALUES` field and `$values` method of enums are kept by default, as they make up the implementation
+/// of the `values()` method. Use [`DropSyntheticMembers::drop_enum_values`] to drop them as well.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DropSyntheticMembers<V> {
+	inner: V,
+	drop_enum_values: bool,
+	is_enum: bool,
+}
+
+impl<V> DropSyntheticMembers<V> {
+	/// Wraps a [`MultiClassVisitor`], dropping the synthetic members of all classes visited.
+	pub fn new(inner: V) -> DropSyntheticMembers<V> {
+		DropSyntheticMembers {
+			inner,
+			drop_enum_values: false,
+			is_enum: false,
+		}
+	}
+
+	/// Wraps a [`ClassVisitor`] for a class with the given access flags, dropping the synthetic members of it.
+	///
+	/// The access flags are needed to recognize enum classes.
+	pub fn for_class(inner: V, access: ClassAccess) -> DropSyntheticMembers<V> {
+		DropSyntheticMembers {
+			inner,
+			drop_enum_values: false,
+			is_enum: access.is_enum,
+		}
+	}
+
+	/// Sets whether the synthetic `$VALUES` field and `$values` method of enums should be dropped as well.
+	pub fn drop_enum_values(mut self, drop_enum_values: bool) -> DropSyntheticMembers<V> {
+		self.drop_enum_values = drop_enum_values;
+		self
+	}
+
+	/// Gets the wrapped visitor back.
+	pub fn into_inner(self) -> V {
+		self.inner
+	}
+
+	fn wrap<T>(&self, inner: T) -> DropSyntheticMembers<T> {
+		DropSyntheticMembers {
+			inner,
+			drop_enum_values: self.drop_enum_values,
+			is_enum: self.is_enum,
+		}
+	}
+
+	fn keeps_enum_values(&self) -> bool {
+		self.is_enum && !self.drop_enum_values
+	}
+}
+
+impl<M: MultiClassVisitor> MultiClassVisitor for DropSyntheticMembers<M> {
+	type ClassVisitor = DropSyntheticMembers<M::ClassVisitor>;
+	type ClassResidual = DropSyntheticMembers<M::ClassResidual>;
+
+	fn visit_class(self, version: Version, access: ClassAccess, name: ClassName, super_class: Option<ClassName>, interfaces: Vec<ClassName>)
+			-> Result<ControlFlow<Self, (Self::ClassResidual, Self::ClassVisitor)>> {
+		let wrapper = self.wrap(());
+		Ok(match self.inner.visit_class(version, access, name, super_class, interfaces)? {
+			ControlFlow::Continue((residual, class_visitor)) => {
+				let class_visitor = DropSyntheticMembers::for_class(class_visitor, access).drop_enum_values(self.drop_enum_values);
+				ControlFlow::Continue((wrapper.wrap(residual), class_visitor))
+			},
+			ControlFlow::Break(inner) => ControlFlow::Break(wrapper.wrap(inner)),
+		})
+	}
+
+	fn finish_class(this: Self::ClassResidual, class_visitor: Self::ClassVisitor) -> Result<Self> {
+		let wrapper = this.wrap(());
+		let inner = M::finish_class(this.inner, class_visitor.inner)?;
+		Ok(wrapper.wrap(inner))
+	}
+}
+
+impl<C: ClassVisitor> ClassVisitor for DropSyntheticMembers<C> {
+	type AnnotationsVisitor = C::AnnotationsVisitor;
+	type AnnotationsResidual = DropSyntheticMembers<C::AnnotationsResidual>;
+	type TypeAnnotationsVisitor = C::TypeAnnotationsVisitor;
+	type TypeAnnotationsResidual = DropSyntheticMembers<C::TypeAnnotationsResidual>;
+	type RecordComponentVisitor = C::RecordComponentVisitor;
+	type RecordComponentResidual = DropSyntheticMembers<C::RecordComponentResidual>;
+	type FieldVisitor = C::FieldVisitor;
+	type FieldResidual = DropSyntheticMembers<C::FieldResidual>;
+	type MethodVisitor = C::MethodVisitor;
+	type MethodResidual = DropSyntheticMembers<C::MethodResidual>;
+	type UnknownAttribute = C::UnknownAttribute;
+
+	fn interests(&self) -> ClassInterests {
+		self.inner.interests()
+	}
+
+	fn visit_deprecated_and_synthetic_attribute(&mut self, deprecated: bool, synthetic: bool) -> Result<()> {
+		self.inner.visit_deprecated_and_synthetic_attribute(deprecated, synthetic)
+	}
+
+	fn visit_inner_classes(&mut self, inner_classes: Vec<InnerClass>) -> Result<()> {
+		self.inner.visit_inner_classes(inner_classes)
+	}
+
+	fn visit_enclosing_method(&mut self, enclosing_method: EnclosingMethod) -> Result<()> {
+		self.inner.visit_enclosing_method(enclosing_method)
+	}
+
+	fn visit_signature(&mut self, signature: ClassSignature) -> Result<()> {
+		self.inner.visit_signature(signature)
+	}
+
+	fn visit_source_file(&mut self, source_file: JavaString) -> Result<()> {
+		self.inner.visit_source_file(source_file)
+	}
+
+	fn visit_source_debug_extension(&mut self, source_debug_extension: JavaString) -> Result<()> {
+		self.inner.visit_source_debug_extension(source_debug_extension)
+	}
+
+	fn visit_annotations(self, visible: bool) -> Result<(Self::AnnotationsResidual, Self::AnnotationsVisitor)> {
+		let wrapper = self.wrap(());
+		let (residual, annotations_visitor) = self.inner.visit_annotations(visible)?;
+		Ok((wrapper.wrap(residual), annotations_visitor))
+	}
+
+	fn finish_annotations(this: Self::AnnotationsResidual, annotations_visitor: Self::AnnotationsVisitor) -> Result<Self> {
+		let wrapper = this.wrap(());
+		let inner = C::finish_annotations(this.inner, annotations_visitor)?;
+		Ok(wrapper.wrap(inner))
+	}
+
+	fn visit_type_annotations(self, visible: bool) -> Result<(Self::TypeAnnotationsResidual, Self::TypeAnnotationsVisitor)> {
+		let wrapper = self.wrap(());
+		let (residual, type_annotations_visitor) = self.inner.visit_type_annotations(visible)?;
+		Ok((wrapper.wrap(residual), type_annotations_visitor))
+	}
+
+	fn finish_type_annotations(this: Self::TypeAnnotationsResidual, type_annotations_visitor: Self::TypeAnnotationsVisitor) -> Result<Self> {
+		let wrapper = this.wrap(());
+		let inner = C::finish_type_annotations(this.inner, type_annotations_visitor)?;
+		Ok(wrapper.wrap(inner))
+	}
+
+	fn visit_module(&mut self, module: Module) -> Result<()> {
+		self.inner.visit_module(module)
+	}
+
+	fn visit_module_packages(&mut self, module_packages: Vec<PackageName>) -> Result<()> {
+		self.inner.visit_module_packages(module_packages)
+	}
+
+	fn visit_module_main_class(&mut self, module_main_class: ClassName) -> Result<()> {
+		self.inner.visit_module_main_class(module_main_class)
+	}
+
+	fn visit_nest_host_class(&mut self, nest_host_class: ClassName) -> Result<()> {
+		self.inner.visit_nest_host_class(nest_host_class)
+	}
+
+	fn visit_nest_members(&mut self, nest_members: Vec<ClassName>) -> Result<()> {
+		self.inner.visit_nest_members(nest_members)
+	}
+
+	fn visit_permitted_subclasses(&mut self, permitted_subclasses: Vec<ClassName>) -> Result<()> {
+		self.inner.visit_permitted_subclasses(permitted_subclasses)
+	}
+
+	fn visit_record_component(self, name: RecordName, descriptor: FieldDescriptor)
+			-> Result<ControlFlow<Self, (Self::RecordComponentResidual, Self::RecordComponentVisitor)>> {
+		let wrapper = self.wrap(());
+		Ok(match self.inner.visit_record_component(name, descriptor)? {
+			ControlFlow::Continue((residual, record_component_visitor)) => ControlFlow::Continue((wrapper.wrap(residual), record_component_visitor)),
+			ControlFlow::Break(inner) => ControlFlow::Break(wrapper.wrap(inner)),
+		})
+	}
+
+	fn finish_record_component(this: Self::RecordComponentResidual, record_component_visitor: Self::RecordComponentVisitor) -> Result<Self> {
+		let wrapper = this.wrap(());
+		let inner = C::finish_record_component(this.inner, record_component_visitor)?;
+		Ok(wrapper.wrap(inner))
+	}
+
+	fn visit_unknown_attribute(&mut self, unknown_attribute: Self::UnknownAttribute) -> Result<()> {
+		self.inner.visit_unknown_attribute(unknown_attribute)
+	}
+
+	fn visit_field(self, access: FieldAccess, name: FieldName, descriptor: FieldDescriptor)
+			-> Result<ControlFlow<Self, (Self::FieldResidual, Self::FieldVisitor)>> {
+		if access.is_synthetic && !(self.keeps_enum_values() && name.as_inner() == "$VALUES") {
+			return Ok(ControlFlow::Break(self));
+		}
+
+		let wrapper = self.wrap(());
+		Ok(match self.inner.visit_field(access, name, descriptor)? {
+			ControlFlow::Continue((residual, field_visitor)) => ControlFlow::Continue((wrapper.wrap(residual), field_visitor)),
+			ControlFlow::Break(inner) => ControlFlow::Break(wrapper.wrap(inner)),
+		})
+	}
+
+	fn finish_field(this: Self::FieldResidual, field_visitor: Self::FieldVisitor) -> Result<Self> {
+		let wrapper = this.wrap(());
+		let inner = C::finish_field(this.inner, field_visitor)?;
+		Ok(wrapper.wrap(inner))
+	}
+
+	fn visit_method(self, access: MethodAccess, name: MethodName, descriptor: MethodDescriptor)
+			-> Result<ControlFlow<Self, (Self::MethodResidual, Self::MethodVisitor)>> {
+		if access.is_bridge || (access.is_synthetic && !(self.keeps_enum_values() && name.as_inner() == "$values")) {
+			return Ok(ControlFlow::Break(self));
+		}
+
+		let wrapper = self.wrap(());
+		Ok(match self.inner.visit_method(access, name, descriptor)? {
+			ControlFlow::Continue((residual, method_visitor)) => ControlFlow::Continue((wrapper.wrap(residual), method_visitor)),
+			ControlFlow::Break(inner) => ControlFlow::Break(wrapper.wrap(inner)),
+		})
+	}
+
+	fn finish_method(this: Self::MethodResidual, method_visitor: Self::MethodVisitor) -> Result<Self> {
+		let wrapper = this.wrap(());
+		let inner = C::finish_method(this.inner, method_visitor)?;
+		Ok(wrapper.wrap(inner))
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::{Context, Result};
+	use pretty_assertions::assert_eq;
+	use crate::tree::class::ClassFile;
+	use crate::visitor::filter::DropSyntheticMembers;
+
+	fn read(bytes: &[u8], drop_enum_values: bool) -> Result<ClassFile> {
+		let visitor = DropSyntheticMembers::new(None).drop_enum_values(drop_enum_values);
+		let class = crate::read_class_multi(&mut Cursor::new(bytes), visitor)?.into_inner().context("no class read")?;
+
+		// round trip through the writer, to make sure the dropped members are gone from the output
+		let mut buf = Vec::new();
+		crate::write_class(&mut buf, &class)?;
+		crate::read_class(&mut Cursor::new(buf))
+	}
+
+	fn member_names(class: &ClassFile) -> (Vec<String>, Vec<String>) {
+		(
+			class.fields.iter().map(|field| field.name.to_string()).collect(),
+			class.methods.iter().map(|method| format!("{}{}", method.name, method.descriptor)).collect(),
+		)
+	}
+
+	#[test]
+	fn drop_bridge_method() -> Result<()> {
+		let class = read(include_bytes!("test/Bridge.class"), false)?;
+
+		let (_, methods) = member_names(&class);
+		assert_eq!(methods, ["<init>()V", "compareTo(LBridge;)I"]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn enum_values() -> Result<()> {
+		let class = read(include_bytes!("test/Bridge$Kind.class"), false)?;
+		let (fields, methods) = member_names(&class);
+		assert_eq!(fields, ["A", "B", "$VALUES"]);
+		assert!(methods.contains(&"$values()[LBridge$Kind;".to_owned()), "{methods:?}");
+
+		let class = read(include_bytes!("test/Bridge$Kind.class"), true)?;
+		let (fields, methods) = member_names(&class);
+		assert_eq!(fields, ["A", "B"]);
+		assert!(!methods.contains(&"$values()[LBridge$Kind;".to_owned()), "{methods:?}");
+		assert!(methods.contains(&"values()[LBridge$Kind;".to_owned()), "{methods:?}");
+
+		Ok(())
+	}
+}
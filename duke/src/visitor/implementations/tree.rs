@@ -10,6 +10,7 @@ use crate::tree::descriptor::ReturnDescriptor;
 use crate::tree::field::{ConstantValue, Field, FieldAccess, FieldDescriptor, FieldName, FieldSignature};
 use crate::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName, MethodParameter, MethodSignature};
 use crate::tree::method::code::{Code, Exception, Instruction, InstructionListEntry, Label, Lv};
+use crate::tree::method::raw_code::RawCode;
 use crate::tree::module::{Module, PackageName};
 use crate::tree::record::{RecordComponent, RecordName};
 use crate::tree::type_annotation::{TargetInfoClass, TargetInfoCode, TargetInfoField, TargetInfoMethod, TypeAnnotation, TypePath};
@@ -526,10 +527,22 @@ impl CodeVisitor for Code {
 		self.instructions.push(InstructionListEntry {
 			label,
 			frame,
+			offset: None,
 			instruction,
 		});
 		Ok(())
 	}
+
+	fn visit_instruction_with_offset(&mut self, offset: u16, label: Option<Label>, frame: Option<StackMapData>, instruction: Instruction) -> Result<()> {
+		self.instructions.push(InstructionListEntry {
+			label,
+			frame,
+			offset: Some(offset),
+			instruction,
+		});
+		Ok(())
+	}
+
 	fn visit_last_label(&mut self, last_label: Label) -> Result<()> {
 		self.last_label.insert_if_empty(last_label).context("you may only visit the last label once")
 	}
@@ -542,6 +555,10 @@ impl CodeVisitor for Code {
 		self.local_variables.insert_if_empty(local_variables).context("you may only visit the local variables once")
 	}
 
+	fn visit_raw_code(&mut self, raw_code: RawCode) -> Result<()> {
+		self.raw.insert_if_empty(raw_code).context("you may only visit the raw code once")
+	}
+
 	fn visit_type_annotations(self, visible: bool) -> Result<(Self::TypeAnnotationsResidual, Self::TypeAnnotationsVisitor)> {
 		Ok(((self, visible), Vec::new()))
 	}
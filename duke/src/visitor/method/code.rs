@@ -1,6 +1,7 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use crate::tree::class::ClassName;
 use crate::tree::method::code::{Exception, Instruction, Label, Lv};
+use crate::tree::method::raw_code::RawCode;
 use crate::tree::type_annotation::TargetInfoCode;
 use crate::visitor::annotation::TypeAnnotationsVisitor;
 use crate::visitor::attribute::UnknownAttributeVisitor;
@@ -28,6 +29,20 @@ where
 		// TODO: finalize this api...
 		Ok(())
 	}
+
+	/// Visits an instruction, together with its offset in the bytecode.
+	///
+	/// This is called instead of [`visit_instruction`][CodeVisitor::visit_instruction] if the visitor is interested in the
+	/// [`instruction_offsets`][CodeInterests::instruction_offsets]. By default, the offset is ignored.
+	fn visit_instruction_with_offset(&mut self,
+		offset: u16,
+		label: Option<Label>,
+		frame: Option<StackMapData>,
+		instruction: Instruction,
+	) -> Result<()> {
+		let _ = offset;
+		self.visit_instruction(label, frame, instruction)
+	}
 	/// Visits the last label.
 	///
 	/// We need to visit the "last" label (the one that's one after the end of the method),
@@ -41,6 +56,16 @@ where
 	fn finish_type_annotations(this: Self::TypeAnnotationsResidual, type_annotations_visitor: Self::TypeAnnotationsVisitor) -> Result<Self>;
 
 	fn visit_unknown_attribute(&mut self, unknown_attribute: Self::UnknownAttribute) -> Result<()>;
+
+	/// Visits the code as raw bytecode.
+	///
+	/// This is called instead of all the other methods if the visitor is interested in
+	/// [`raw_code_only`][CodeInterests::raw_code_only], or if the code visited already is raw code, see
+	/// [`Code::raw`][crate::tree::method::code::Code::raw]. By default, this fails.
+	fn visit_raw_code(&mut self, raw_code: RawCode) -> Result<()> {
+		let _ = raw_code;
+		bail!("this code visitor doesn't support raw code")
+	}
 }
 
 
@@ -58,6 +83,23 @@ pub struct CodeInterests {
 	pub runtime_invisible_type_annotations: bool,
 
 	pub unknown_attributes: bool,
+
+	// Other data:
+	/// Whether to call [`visit_instruction_with_offset`][CodeVisitor::visit_instruction_with_offset] with the offset of each
+	/// instruction in the bytecode.
+	///
+	/// The offsets depend on the layout of the bytecode, so trees read with them don't compare equal to trees of the same
+	/// code laid out differently. Like [`raw_code_only`][CodeInterests::raw_code_only], this isn't included in
+	/// [`all`][CodeInterests::all]. Use [`read_class_with_instruction_offsets`][crate::read_class_with_instruction_offsets]
+	/// to read the offsets into a tree.
+	pub instruction_offsets: bool,
+	/// Whether to read the code as [`RawCode`], given to [`visit_raw_code`][CodeVisitor::visit_raw_code], instead of
+	/// visiting the instructions.
+	///
+	/// This skips resolving the branches into labels, which is wasted work for visitors only looking at or changing the
+	/// constants referenced, like remapping. Unlike most other interests, this one changes how the code is visited, and so
+	/// it's not included in [`all`][CodeInterests::all].
+	pub raw_code_only: bool,
 }
 
 impl CodeInterests {
@@ -77,6 +119,10 @@ impl CodeInterests {
 			runtime_invisible_type_annotations: true,
 
 			unknown_attributes: true,
+
+			instruction_offsets: false,
+
+			raw_code_only: false,
 		}
 	}
 }
@@ -7,6 +7,7 @@ use crate::visitor::class::ClassVisitor;
 mod implementations;
 
 pub mod simple;
+pub mod filter;
 
 pub mod class;
 pub(crate) mod attribute;
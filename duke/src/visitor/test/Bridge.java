@@ -0,0 +1,11 @@
+// compile with `javac --release 8 Bridge.java`
+public class Bridge implements Comparable<Bridge> {
+	@Override
+	public int compareTo(Bridge other) {
+		return 0;
+	}
+
+	enum Kind {
+		A, B;
+	}
+}
@@ -0,0 +1,244 @@
+//! Reducing a jar to what's needed to compile against it.
+
+use anyhow::{anyhow, Context, Result};
+use indexmap::IndexMap;
+use java_string::JavaStr;
+use duke::tree::class::{ClassFile, ClassName};
+use duke::tree::descriptor::Type;
+use duke::tree::field::FieldAccess;
+use duke::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName, MethodRef};
+use duke::tree::method::code::{Code, Instruction, InstructionListEntry};
+use crate::storage::{ClassRepr, IsClass, IsOther, Jar, JarEntry, OpenedJar, ParsedJar, ParsedJarEntry};
+
+/// Options for [`abi_strip`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AbiStripOptions {
+	/// Whether to keep package-private fields and methods.
+	///
+	/// Only code in the same package can use them, so they're usually not part of the API. Private members are always
+	/// removed.
+	pub keep_package_private: bool,
+}
+
+/// Strips all the classes of a jar down to the declarations needed for compiling against it.
+///
+/// See [`abi_strip_class`] for what happens to each class. Other entries are kept as they are.
+pub fn abi_strip(jar: impl Jar, options: AbiStripOptions) -> Result<ParsedJar<ClassRepr, Vec<u8>>> {
+	let mut opened = jar.open()?;
+
+	let mut entries = IndexMap::new();
+
+	for key in opened.entry_keys() {
+		let entry = opened.by_entry_key(key)?;
+
+		let name = entry.name().to_owned();
+
+		let entry = ParsedJarEntry {
+			attr: entry.attrs(),
+			content: entry.to_jar_entry_enum()?
+				.try_map_both(
+					|class| {
+						let class = class.read()?;
+						let name = class.name.clone();
+						abi_strip_class(class, options)
+							.map(|class| ClassRepr::Parsed { class })
+							.with_context(|| anyhow!("failed to strip class {name}"))
+					},
+					|other| Ok(other.get_data_owned())
+				)?,
+		};
+
+		entries.insert(name, entry);
+	}
+
+	Ok(ParsedJar { entries })
+}
+
+/// Strips a class down to the declarations needed for compiling against it.
+///
+/// Private fields and methods, the static initializer and, unless [`AbiStripOptions::keep_package_private`] is set, package-private
+/// fields and methods are removed. The bodies of all remaining methods are replaced by a stub throwing an
+/// `UnsupportedOperationException`. The declarations themselves are kept with their annotations, signatures and
+/// exceptions, as are the `ConstantValue`s of fields, since the compiler inlines these.
+///
+/// Methods are not made `abstract` instead, as that would force subclasses to implement them.
+pub fn abi_strip_class(mut class: ClassFile, options: AbiStripOptions) -> Result<ClassFile> {
+	let is_api = |is_public: bool, is_protected: bool, is_private: bool| {
+		is_public || is_protected || (!is_private && options.keep_package_private)
+	};
+
+	class.fields.retain(|field| {
+		let FieldAccess { is_public, is_protected, is_private, .. } = field.access;
+		is_api(is_public, is_protected, is_private)
+	});
+
+	class.methods.retain(|method| {
+		let MethodAccess { is_public, is_protected, is_private, .. } = method.access;
+		method.name.as_slice() != MethodName::CLINIT && is_api(is_public, is_protected, is_private)
+	});
+
+	for method in &mut class.methods {
+		if method.code.is_some() {
+			method.code = Some(stub_code(method)
+				.with_context(|| anyhow!("failed to create stub for method {}{}", method.name, method.descriptor))?);
+		}
+	}
+
+	Ok(class)
+}
+
+/// Creates the body `throw new UnsupportedOperationException();` for the method.
+fn stub_code(method: &Method) -> Result<Code> {
+	let exception = ClassName::try_from(JavaStr::from_str("java/lang/UnsupportedOperationException"))?;
+
+	let parameters = method.descriptor.parse()?.parameter_descriptors;
+	let parameter_size: usize = parameters.iter()
+		.map(|parameter| match parameter {
+			Type::D | Type::J => 2,
+			_ => 1,
+		})
+		.sum();
+	let this_size = if method.access.is_static { 0 } else { 1 };
+
+	let instructions = [
+		Instruction::New(exception.clone()),
+		Instruction::Dup,
+		Instruction::InvokeSpecial(MethodRef {
+			class: exception,
+			name: MethodName::INIT.to_owned(),
+			desc: MethodDescriptor::try_from(JavaStr::from_str("()V"))?,
+		}, false),
+		Instruction::AThrow,
+	];
+
+	Ok(Code {
+		max_stack: Some(2),
+		max_locals: Some(u16::try_from(parameter_size + this_size)?),
+		instructions: instructions.into_iter()
+			.map(|instruction| InstructionListEntry { label: None, frame: None, offset: None, instruction })
+			.collect(),
+		..Code::default()
+	})
+}
+
+#[cfg(test)]
+mod testing {
+	use anyhow::{bail, Result};
+	use indexmap::IndexMap;
+	use pretty_assertions::assert_eq;
+	use java_string::JavaStr;
+	use duke::tree::class::{ClassAccess, ClassFile, ClassName};
+	use duke::tree::field::{ConstantValue, Field, FieldAccess, FieldDescriptor, FieldName};
+	use duke::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName, MethodSignature};
+	use duke::tree::method::code::{Code, Instruction, InstructionListEntry};
+	use duke::tree::version::Version;
+	use crate::abi_strip::{abi_strip, AbiStripOptions};
+	use crate::storage::{BasicFileAttributes, ClassRepr, IsClass, JarEntryEnum, ParsedJar, ParsedJarEntry};
+
+	fn field(access: u16, name: &str, desc: &str) -> Result<Field> {
+		Ok(Field::new(FieldAccess::from(access), FieldName::try_from(JavaStr::from_str(name))?, FieldDescriptor::try_from(JavaStr::from_str(desc))?))
+	}
+
+	fn method(access: u16, name: &str, desc: &str) -> Result<Method> {
+		let mut method = Method::new(MethodAccess::from(access), MethodName::try_from(JavaStr::from_str(name))?, MethodDescriptor::try_from(JavaStr::from_str(desc))?);
+		method.code = Some(Code {
+			max_stack: Some(1),
+			max_locals: Some(4),
+			instructions: [Instruction::IConst0, Instruction::IReturn].into_iter()
+				.map(|instruction| InstructionListEntry { label: None, frame: None, offset: None, instruction })
+				.collect(),
+			..Code::default()
+		});
+		Ok(method)
+	}
+
+	fn jar() -> Result<ParsedJar<ClassRepr, Vec<u8>>> {
+		let mut class = ClassFile::new(Version::V1_8, ClassAccess::from(0x0021), ClassName::try_from(JavaStr::from_str("com/example/Api"))?, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+
+		let mut constant = field(0x0019, "CONSTANT", "I")?;
+		constant.constant_value = Some(ConstantValue::Integer(42));
+		class.fields.push(constant);
+		class.fields.push(field(0x0002, "secret", "I")?);
+		class.fields.push(field(0x0000, "internal", "I")?);
+
+		let mut compute = method(0x0001, "compute", "(JI)I")?;
+		compute.signature = Some(MethodSignature::try_from(JavaStr::from_str("(JI)I"))?);
+		class.methods.push(compute);
+		class.methods.push(method(0x0009, "create", "(D)I")?);
+		class.methods.push(method(0x0002, "helper", "()I")?);
+		class.methods.push(method(0x0000, "internal", "()I")?);
+		class.methods.push(method(0x0008, "<clinit>", "()V")?);
+		let mut native = method(0x0101, "call", "()I")?;
+		native.code = None;
+		class.methods.push(native);
+
+		Ok(ParsedJar {
+			entries: IndexMap::from([
+				("com/example/Api.class".to_owned(), ParsedJarEntry {
+					attr: BasicFileAttributes::default(),
+					content: JarEntryEnum::Class(ClassRepr::Parsed { class }),
+				}),
+				("com/example/data.txt".to_owned(), ParsedJarEntry {
+					attr: BasicFileAttributes::default(),
+					content: JarEntryEnum::Other(b"data".to_vec()),
+				}),
+			]),
+		})
+	}
+
+	fn read_class(jar: &ParsedJar<ClassRepr, Vec<u8>>) -> Result<ClassFile> {
+		let JarEntryEnum::Class(class) = &jar.entries["com/example/Api.class"].content else {
+			bail!("expected a class");
+		};
+		// write and read it again, so that the stubs are known to be writable
+		duke::read_class(&mut std::io::Cursor::new(class.write()?.as_ref()))
+	}
+
+	#[test]
+	fn abi_strip_class() -> Result<()> {
+		let stripped = abi_strip(jar()?, AbiStripOptions::default())?;
+
+		let JarEntryEnum::Other(data) = &stripped.entries["com/example/data.txt"].content else {
+			bail!("expected a non-class entry");
+		};
+		assert_eq!(data, b"data");
+
+		let class = read_class(&stripped)?;
+
+		let fields: Vec<_> = class.fields.iter().map(|field| field.name.as_inner().to_string()).collect();
+		assert_eq!(fields, ["CONSTANT"]);
+		assert_eq!(class.fields[0].constant_value, Some(ConstantValue::Integer(42)));
+
+		let methods: Vec<_> = class.methods.iter()
+			.map(|method| format!("{}{}", method.name.as_inner(), method.descriptor.as_inner()))
+			.collect();
+		assert_eq!(methods, ["compute(JI)I", "create(D)I", "call()I"]);
+		assert_eq!(class.methods[0].signature, Some(MethodSignature::try_from(JavaStr::from_str("(JI)I"))?));
+		assert!(class.methods[2].code.is_none());
+
+		for (method, max_locals) in class.methods[..2].iter().zip([4, 2]) {
+			let Some(code) = &method.code else {
+				bail!("expected method {:?} to have code", method.name);
+			};
+			let instructions: Vec<_> = code.instructions.iter().map(|entry| entry.instruction.mnemonic()).collect();
+			assert_eq!(instructions, ["new", "dup", "invokespecial", "athrow"]);
+			assert_eq!(code.max_locals, Some(max_locals));
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn abi_strip_keep_package_private() -> Result<()> {
+		let stripped = abi_strip(jar()?, AbiStripOptions { keep_package_private: true })?;
+
+		let class = read_class(&stripped)?;
+
+		let fields: Vec<_> = class.fields.iter().map(|field| field.name.as_inner().to_string()).collect();
+		assert_eq!(fields, ["CONSTANT", "internal"]);
+		let methods: Vec<_> = class.methods.iter().map(|method| method.name.as_inner().to_string()).collect();
+		assert_eq!(methods, ["compute", "create", "internal", "call"]);
+
+		Ok(())
+	}
+}
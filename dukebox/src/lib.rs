@@ -1,5 +1,9 @@
-
+pub mod abi_strip;
 pub mod merge;
+pub mod relocate;
 pub mod remap;
+pub mod resolve;
+
+pub mod storage;
 
-pub mod storage;
\ No newline at end of file
+pub mod zip;
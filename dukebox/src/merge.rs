@@ -8,7 +8,34 @@ use duke::tree::annotation::{Annotation, ElementValue, ElementValuePair};
 use duke::tree::class::{ClassFile, ClassName, ClassNameSlice};
 use duke::tree::field::{Field, FieldDescriptor};
 use duke::tree::method::Method;
-use crate::storage::{ClassRepr, IsClass, IsOther, Jar, JarEntry, JarEntryEnum, OpenedJar, ParsedJar, ParsedJarEntry};
+use duke::tree::version::Version;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use crate::storage::{BasicFileAttributes, ClassRepr, IsClass, IsOther, Jar, JarEntry, JarEntryEnum, OpenedJar, ParsedJar, ParsedJarEntry};
+
+/// Options for [`merge_with_options`] and [`merge_parallel_with_options`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeOptions {
+	/// Whether to give the classes present in both jars the higher of their two class file versions.
+	///
+	/// If this is not set, the merged class gets the version of the client class.
+	pub normalize_versions: bool,
+}
+
+/// A class present in both jars, with different major class file versions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionMismatch {
+	pub class: ClassName,
+	pub client: Version,
+	pub server: Version,
+}
+
+/// The result of [`merge_with_options`] and [`merge_parallel_with_options`].
+#[derive(Debug)]
+pub struct Merged {
+	pub jar: ParsedJar<ClassRepr, Vec<u8>>,
+	/// The classes present in both jars with different major versions, in the order of the entries.
+	pub version_mismatches: Vec<VersionMismatch>,
+}
 
 #[derive(Clone, Debug, PartialEq)]
 enum Side {
@@ -121,7 +148,7 @@ fn sided_annotation(side: Side) -> Annotation {
 	}
 }
 
-fn class_merger_merge(client: ClassFile, server: ClassFile) -> Result<ClassFile> {
+fn class_merger_merge(client: ClassFile, server: ClassFile, options: MergeOptions) -> Result<ClassFile> {
 	let interfaces: Vec<_> = merge_preserve_order(&client.interfaces, &server.interfaces).collect();
 
 	let mut ci = Vec::new();
@@ -135,7 +162,7 @@ fn class_merger_merge(client: ClassFile, server: ClassFile) -> Result<ClassFile>
 	}
 
 	Ok(ClassFile {
-		version: merge_from_client(&client.version, &server.version)?,
+		version: if options.normalize_versions { client.version.max(server.version) } else { client.version },
 		access: merge_from_client(&client.access, &server.access)?,
 		name: merge_eq(&client.name, &server.name)?,
 		super_class: merge_eq(&client.super_class, &server.super_class)?,
@@ -283,16 +310,40 @@ fn visit_sided_annotation(class: impl IsClass, side: Side) -> Result<ClassFile>
 	Ok(class_node)
 }
 
-// TODO: doc
-pub fn merge(client: impl Jar, server: impl Jar) -> Result<ParsedJar<ClassRepr, Vec<u8>>> {
-	let mut opened_a = client.open()?;
-	let mut opened_b = server.open()?;
+/// An entry read from either the client or the server jar.
+///
+/// This owns all of its data, so that it can be merged on any thread.
+struct ReadEntry {
+	attr: BasicFileAttributes,
+	content: JarEntryEnum<ClassRepr, Vec<u8>>,
+}
 
-	enum MergeCombination<C, S> {
-		Client(C),
-		Server(S),
-		Both(C, S),
+impl ReadEntry {
+	fn read<J: OpenedJar>(opened: &mut J, key: J::EntryKey) -> Result<ReadEntry> {
+		let entry = opened.by_entry_key(key)?;
+		Ok(ReadEntry {
+			attr: entry.attrs(),
+			content: entry.to_jar_entry_enum()?
+				.try_map_both(
+					|class| Ok(class.into_class_repr()),
+					|other| Ok(other.get_data_owned())
+				)?,
+		})
 	}
+}
+
+enum MergeCombination<C, S> {
+	Client(C),
+	Server(S),
+	Both(C, S),
+}
+
+/// Reads all entries of the client and server jar, and pairs up the entries with the same name.
+///
+/// The entries that are removed while merging (signature files, libraries bundled by the server) are not read.
+fn read_entries(client: impl Jar, server: impl Jar) -> Result<Vec<(String, MergeCombination<ReadEntry, ReadEntry>)>> {
+	let mut opened_a = client.open()?;
+	let mut opened_b = server.open()?;
 
 	let keys = {
 		enum MergeSide<C, S> {
@@ -332,104 +383,338 @@ pub fn merge(client: impl Jar, server: impl Jar) -> Result<ParsedJar<ClassRepr,
 			}
 		}
 
-		keys.into_iter()
+		keys
 	};
 
-	let mut resulting_entries = IndexMap::new();
+	let mut entries = Vec::with_capacity(keys.len());
 	for (key, merge_combination) in keys {
-		let result = match key.as_str() {
-			"META-INF/MANIFEST.MF" => ParsedJarEntry {
-				attr: match merge_combination {
-					MergeCombination::Client(c) => opened_a.by_entry_key(c)?.attrs(),
-					MergeCombination::Server(s) => opened_b.by_entry_key(s)?.attrs(),
-					MergeCombination::Both(c, _) => opened_a.by_entry_key(c)?.attrs(), // TODO: this ignores the server attrs...
-				},
-				content: JarEntryEnum::Other(b"Manifest-Version: 1.0\nMain-Class: net.minecraft.client.Main\n".to_vec()),
-			},
-			name if name.starts_with("META-INF/") && (name.ends_with(".SF") || name.ends_with(".RSA")) => {
-				// remove these from the jar
+		if key.starts_with("META-INF/") && (key.ends_with(".SF") || key.ends_with(".RSA")) {
+			// remove these from the jar
+			continue;
+		}
+		if let MergeCombination::Server(_) = merge_combination {
+			// skip the libraries the server bundles
+			if key.ends_with(".class") && !key.starts_with("net/minecraft/") && key.contains('/') {
 				continue;
+			}
+		}
+
+		let merge_combination = match merge_combination {
+			MergeCombination::Client(c) => MergeCombination::Client(ReadEntry::read(&mut opened_a, c)?),
+			MergeCombination::Server(s) => MergeCombination::Server(ReadEntry::read(&mut opened_b, s)?),
+			MergeCombination::Both(c, s) => MergeCombination::Both(ReadEntry::read(&mut opened_a, c)?, ReadEntry::read(&mut opened_b, s)?),
+		};
+
+		entries.push((key, merge_combination));
+	}
+
+	Ok(entries)
+}
+
+type MergedEntry = (ParsedJarEntry<ClassRepr, Vec<u8>>, Option<VersionMismatch>);
+
+/// Merges a single entry, that is present in the client jar, the server jar, or both of them.
+///
+/// If the entry is a class present in both jars with different major versions, this is also returned.
+fn merge_entry(name: &str, merge_combination: MergeCombination<ReadEntry, ReadEntry>, options: MergeOptions) -> Result<MergedEntry> {
+	let mut version_mismatch = None;
+
+	let entry = match name {
+		"META-INF/MANIFEST.MF" => ParsedJarEntry {
+			attr: match merge_combination {
+				MergeCombination::Client(c) => c.attr,
+				MergeCombination::Server(s) => s.attr,
+				MergeCombination::Both(c, _) => c.attr, // TODO: this ignores the server attrs...
 			},
-			name => match merge_combination {
-				MergeCombination::Client(c) => {
-					let client = opened_a.by_entry_key(c)?;
-					ParsedJarEntry {
-						attr: client.attrs(),
-						content: client.to_jar_entry_enum()?
-							.try_map_both(
-								|class| {
-									let class = visit_sided_annotation(class, Side::Client)?;
-									Ok(ClassRepr::Parsed { class })
-								},
-								|other| Ok(other.get_data_owned())
-							)?,
-					}
-				},
-				MergeCombination::Server(s) => {
-					// skip the libraries the server bundles
-					if name.ends_with(".class") && !name.starts_with("net/minecraft/") && name.contains('/') {
-						continue;
-					}
-
-					let server = opened_b.by_entry_key(s)?;
-					ParsedJarEntry {
-						attr: server.attrs(),
-						content: server.to_jar_entry_enum()?
-							.try_map_both(
-								|class| {
-									let class = visit_sided_annotation(class, Side::Server)?;
-									Ok(ClassRepr::Parsed { class })
-								},
-								|other| Ok(other.get_data_owned())
-							)?,
-					}
-				},
-				MergeCombination::Both(c, s) => {
-					let client = opened_a.by_entry_key(c)?;
-					let client_attr = client.attrs();
-
-					let server = opened_b.by_entry_key(s)?;
-					let server_attr = server.attrs();
-
-					use JarEntryEnum::*;
-					ParsedJarEntry {
-						attr: client_attr, // TODO: also handle the server attr!
-						content: match (client.to_jar_entry_enum()?, server.to_jar_entry_enum()?) {
-							(Dir, Dir) => Dir,
-							(Class(client), Class(server)) => {
-								let c_written = client.write()?;
-								let s_written = server.write()?;
-
-								if c_written.as_ref() == s_written.as_ref() {
-									drop(c_written);
-									drop(s_written);
-									Class(client.into_class_repr())
-								} else {
-									drop(c_written);
-									drop(s_written);
-									let class = class_merger_merge(client.read()?, server.read()?)?;
-									Class(ClassRepr::Parsed { class })
-								}
-							},
-							(Other(client), Other(server)) => {
-								if client.get_data() == server.get_data() {
-									Other(client.get_data_owned())
-								} else {
-									eprintln!("warn: merging {name:?} from both client and server not implemented, taking client version");
-									Other(client.get_data_owned())
-								}
+			content: JarEntryEnum::Other(b"Manifest-Version: 1.0\nMain-Class: net.minecraft.client.Main\n".to_vec()),
+		},
+		name => match merge_combination {
+			MergeCombination::Client(client) => {
+				ParsedJarEntry {
+					attr: client.attr,
+					content: client.content
+						.try_map_both(
+							|class| {
+								let class = visit_sided_annotation(class, Side::Client)?;
+								Ok(ClassRepr::Parsed { class })
 							},
-							(c, s) => {
-								bail!("types don't match {c:?} and {s:?}")
+							Ok
+						)?,
+				}
+			},
+			MergeCombination::Server(server) => {
+				ParsedJarEntry {
+					attr: server.attr,
+					content: server.content
+						.try_map_both(
+							|class| {
+								let class = visit_sided_annotation(class, Side::Server)?;
+								Ok(ClassRepr::Parsed { class })
 							},
+							Ok
+						)?,
+				}
+			},
+			MergeCombination::Both(client, server) => {
+				use JarEntryEnum::*;
+				ParsedJarEntry {
+					attr: client.attr, // TODO: also handle the server attr!
+					content: match (client.content, server.content) {
+						(Dir, Dir) => Dir,
+						(Class(client), Class(server)) => {
+							let c_written = client.write()?;
+							let s_written = server.write()?;
+
+							if c_written.as_ref() == s_written.as_ref() {
+								drop(c_written);
+								drop(s_written);
+								Class(client)
+							} else {
+								drop(c_written);
+								drop(s_written);
+								let client = client.read()?;
+								let server = server.read()?;
+								if client.version.major() != server.version.major() {
+									version_mismatch = Some(VersionMismatch {
+										class: client.name.clone(),
+										client: client.version,
+										server: server.version,
+									});
+								}
+								let class = class_merger_merge(client, server, options)?;
+								Class(ClassRepr::Parsed { class })
+							}
 						},
-					}
-				},
+						(Other(client), Other(server)) => {
+							if client == server {
+								Other(client)
+							} else {
+								eprintln!("warn: merging {name:?} from both client and server not implemented, taking client version");
+								Other(client)
+							}
+						},
+						(c, s) => {
+							bail!("types don't match {c:?} and {s:?}")
+						},
+					},
+				}
 			},
-		};
+		},
+	};
+
+	Ok((entry, version_mismatch))
+}
+
+fn warn_version_mismatches(version_mismatches: &[VersionMismatch]) {
+	for VersionMismatch { class, client, server } in version_mismatches {
+		eprintln!("warn: class {class} has version {}.{} in the client, but {}.{} in the server",
+			client.major(), client.minor(), server.major(), server.minor());
+	}
+}
+
+/// Merges the client and the server jar into a single jar.
+///
+/// Classes and members present in only one of the jars are marked with an `@Environment` annotation for that side, and
+/// interfaces implemented on only one side with an `@EnvironmentInterface` one. Classes present in both jars are merged,
+/// see [`MergeOptions`] for how their version is chosen. The signature files and the
+/// libraries bundled by the server are dropped, and the manifest is replaced.
+///
+/// Classes present in both jars with different major versions are warned about. Use [`merge_with_options`] to get them
+/// returned instead, and to pass other options.
+pub fn merge(client: impl Jar, server: impl Jar) -> Result<ParsedJar<ClassRepr, Vec<u8>>> {
+	let merged = merge_with_options(client, server, MergeOptions::default())?;
+	warn_version_mismatches(&merged.version_mismatches);
+	Ok(merged.jar)
+}
+
+/// Merges the client and server jar, like [`merge`] does, but with the given options.
+///
+/// Instead of warning about them, this returns the classes present in both jars with different major versions.
+pub fn merge_with_options(client: impl Jar, server: impl Jar, options: MergeOptions) -> Result<Merged> {
+	let entries = read_entries(client, server)?;
+
+	let mut resulting_entries = IndexMap::with_capacity(entries.len());
+	let mut version_mismatches = Vec::new();
+	for (key, merge_combination) in entries {
+		let (result, version_mismatch) = merge_entry(&key, merge_combination, options)?;
 
 		resulting_entries.insert(key, result);
+		version_mismatches.extend(version_mismatch);
+	}
+
+	Ok(Merged { jar: ParsedJar { entries: resulting_entries }, version_mismatches })
+}
+
+/// Merges the client and server jar, like [`merge`] does, but merges the entries in parallel.
+///
+/// The entries are read sequentially from both jars first, only merging them is done in parallel. The order of the entries
+/// in the resulting jar is the same as the one [`merge`] produces.
+pub fn merge_parallel(client: impl Jar, server: impl Jar) -> Result<ParsedJar<ClassRepr, Vec<u8>>> {
+	let merged = merge_parallel_with_options(client, server, MergeOptions::default())?;
+	warn_version_mismatches(&merged.version_mismatches);
+	Ok(merged.jar)
+}
+
+/// Merges the client and server jar in parallel, like [`merge_parallel`] does, but with the given options.
+///
+/// See [`merge_with_options`] for the returned version mismatches.
+pub fn merge_parallel_with_options(client: impl Jar, server: impl Jar, options: MergeOptions) -> Result<Merged> {
+	let entries = read_entries(client, server)?;
+
+	let merged: Vec<_> = entries.into_par_iter()
+		.map(|(key, merge_combination)| {
+			let (result, version_mismatch) = merge_entry(&key, merge_combination, options)?;
+			Ok(((key, result), version_mismatch))
+		})
+		.collect::<Result<_>>()?;
+
+	let (resulting_entries, version_mismatches): (IndexMap<_, _>, Vec<_>) = merged.into_iter().unzip();
+
+	Ok(Merged {
+		jar: ParsedJar { entries: resulting_entries },
+		version_mismatches: version_mismatches.into_iter().flatten().collect(),
+	})
+}
+
+#[cfg(test)]
+mod testing {
+	use anyhow::{bail, Result};
+	use indexmap::IndexMap;
+	use pretty_assertions::assert_eq;
+	use java_string::JavaStr;
+	use duke::tree::class::{ClassAccess, ClassFile, ClassName};
+	use duke::tree::field::{Field, FieldAccess, FieldDescriptor, FieldName};
+	use duke::tree::version::Version;
+	use crate::merge::{merge_parallel_with_options, merge_with_options, MergeOptions, VersionMismatch};
+	use crate::storage::{BasicFileAttributes, ClassRepr, IsClass, JarEntryEnum, ParsedJar, ParsedJarEntry};
+
+	fn class(name: &str, fields: &[&str]) -> (String, ParsedJarEntry<ClassRepr, Vec<u8>>) {
+		class_with_version(name, fields, Version::V1_8)
+	}
+
+	fn class_with_version(name: &str, fields: &[&str], version: Version) -> (String, ParsedJarEntry<ClassRepr, Vec<u8>>) {
+		// SAFETY: all of the class names below are valid class names.
+		let class_name = unsafe { ClassName::from_inner_unchecked(name.into()) };
+		let mut class = ClassFile::new(version, ClassAccess::default(), class_name, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		for &field in fields {
+			// SAFETY: all of the field names below are valid field names.
+			let name = unsafe { FieldName::from_inner_unchecked(field.into()) };
+			// SAFETY: `I` is a valid field descriptor.
+			let descriptor = unsafe { FieldDescriptor::from_inner_unchecked("I".into()) };
+			class.fields.push(Field::new(FieldAccess::from(0x0001), name, descriptor));
+		}
+		ParsedJarEntry::from_class(class)
 	}
 
-	Ok(ParsedJar { entries: resulting_entries })
-}
\ No newline at end of file
+	fn other(name: &str, data: &[u8]) -> (String, ParsedJarEntry<ClassRepr, Vec<u8>>) {
+		(name.to_owned(), ParsedJarEntry {
+			attr: BasicFileAttributes::default(),
+			content: JarEntryEnum::Other(data.to_vec()),
+		})
+	}
+
+	fn client() -> ParsedJar<ClassRepr, Vec<u8>> {
+		ParsedJar {
+			entries: IndexMap::from([
+				other("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\n"),
+				other("META-INF/MOJANGCS.SF", b""),
+				class("net/minecraft/Both", &["a"]),
+				class("net/minecraft/Differs", &["a", "client"]),
+				class("net/minecraft/Client", &[]),
+				other("data.txt", b"data"),
+			]),
+		}
+	}
+
+	fn server() -> ParsedJar<ClassRepr, Vec<u8>> {
+		ParsedJar {
+			entries: IndexMap::from([
+				other("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\n"),
+				class("net/minecraft/Both", &["a"]),
+				class("net/minecraft/Differs", &["a", "server"]),
+				class("net/minecraft/Server", &[]),
+				class("com/example/library/Library", &[]),
+				other("data.txt", b"data"),
+			]),
+		}
+	}
+
+	/// Turns the jar into something comparable, by writing out all the classes.
+	fn written(jar: ParsedJar<ClassRepr, Vec<u8>>) -> Result<Vec<(String, Option<Vec<u8>>)>> {
+		jar.entries.into_iter()
+			.map(|(name, entry)| Ok((name, match entry.content {
+				JarEntryEnum::Dir => None,
+				JarEntryEnum::Class(class) => Some(class.write()?.into_owned()),
+				JarEntryEnum::Other(data) => Some(data),
+			})))
+			.collect()
+	}
+
+	#[test]
+	fn merge_parallel_equals_merge() -> Result<()> {
+		let sequential = super::merge(client(), server())?;
+		let parallel = super::merge_parallel(client(), server())?;
+
+		assert_eq!(
+			sequential.entries.keys().collect::<Vec<_>>(),
+			[
+				"META-INF/MANIFEST.MF",
+				"net/minecraft/Both.class",
+				"net/minecraft/Differs.class",
+				"net/minecraft/Client.class",
+				"data.txt",
+				"net/minecraft/Server.class",
+			]
+		);
+
+		assert_eq!(written(sequential)?, written(parallel)?);
+
+		Ok(())
+	}
+
+	#[test]
+	fn merge_version_mismatch() -> Result<()> {
+		let client = || ParsedJar {
+			entries: IndexMap::from([
+				class("net/minecraft/Both", &["a"]),
+				class_with_version("net/minecraft/Newer", &["a"], Version::V1_8),
+			]),
+		};
+		let server = || ParsedJar {
+			entries: IndexMap::from([
+				class("net/minecraft/Both", &["a"]),
+				class_with_version("net/minecraft/Newer", &["a"], Version::V16),
+			]),
+		};
+
+		let expected_mismatches = [
+			VersionMismatch {
+				class: ClassName::try_from(JavaStr::from_str("net/minecraft/Newer"))?,
+				client: Version::V1_8,
+				server: Version::V16,
+			},
+		];
+
+		let version = |jar: &ParsedJar<ClassRepr, Vec<u8>>| -> Result<Version> {
+			let JarEntryEnum::Class(class) = &jar.entries["net/minecraft/Newer.class"].content else {
+				bail!("expected a class");
+			};
+			Ok(class.read()?.version)
+		};
+
+		let merged = merge_with_options(client(), server(), MergeOptions::default())?;
+		assert_eq!(merged.version_mismatches, expected_mismatches);
+		assert_eq!(version(&merged.jar)?, Version::V1_8);
+
+		let options = MergeOptions { normalize_versions: true };
+		let merged = merge_with_options(client(), server(), options)?;
+		assert_eq!(merged.version_mismatches, expected_mismatches);
+		assert_eq!(version(&merged.jar)?, Version::V16);
+
+		let merged = merge_parallel_with_options(client(), server(), options)?;
+		assert_eq!(merged.version_mismatches, expected_mismatches);
+		assert_eq!(version(&merged.jar)?, Version::V16);
+
+		Ok(())
+	}
+}
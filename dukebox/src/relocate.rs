@@ -0,0 +1,219 @@
+//! Relocating (also called "shading") packages of a jar.
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use java_string::{JavaStr, JavaString};
+use duke::tree::class::{ClassFile, ClassName, ClassNameSlice};
+use duke::tree::field::ConstantValue;
+use duke::tree::method::code::{Instruction, Loadable};
+use quill::remapper::{ARemapper, ARemapperAsBRemapper};
+use crate::storage::{ClassRepr, Jar, JarEntryEnum, ParsedJar};
+
+/// An [`ARemapper`] replacing the first matching prefix of a class name.
+#[derive(Debug, Clone, Copy)]
+struct PrefixRemapper<'a> {
+	rules: &'a [(&'a str, &'a str)],
+}
+
+impl PrefixRemapper<'_> {
+	fn relocate(&self, name: &JavaStr) -> Option<JavaString> {
+		self.rules.iter()
+			.find_map(|&(from, to)| name.strip_prefix(from).map(|rest| JavaString::from(to) + rest))
+	}
+
+	/// Like [`PrefixRemapper::relocate`], but also matches the prefixes with `.` instead of `/`, keeping the separator.
+	fn relocate_string(&self, string: &JavaStr) -> Option<JavaString> {
+		self.relocate(string).or_else(|| {
+			self.rules.iter().find_map(|&(from, to)| {
+				string.strip_prefix(from.replace('/', ".").as_str())
+					.map(|rest| JavaString::from(to.replace('/', ".")) + rest)
+			})
+		})
+	}
+}
+
+impl ARemapper for PrefixRemapper<'_> {
+	fn map_class_fail(&self, class: &ClassNameSlice) -> Result<Option<ClassName>> {
+		self.relocate(class.as_inner())
+			.map(ClassName::try_from)
+			.transpose()
+	}
+}
+
+/// Moves all classes and other entries starting with a prefix in the jar to start with another prefix instead.
+///
+/// The `rules` are pairs of the old and the new prefix, like `("com/example/", "shaded/com/example/")`. Only the first
+/// matching rule is applied. All the references to relocated classes are updated, as well as the names of the entries.
+///
+/// String constants that happen to start with an old prefix, or with the old prefix with `.` instead of `/`, are only
+/// changed if `rewrite_strings` is set. This is useful for classes loaded by name, but may change unrelated strings.
+pub fn relocate(jar: impl Jar, rules: &[(&str, &str)], rewrite_strings: bool) -> Result<ParsedJar<ClassRepr, Vec<u8>>> {
+	let remapper = PrefixRemapper { rules };
+
+	let remapped = crate::remap::remap(jar, ARemapperAsBRemapper(remapper))?;
+
+	let mut entries = IndexMap::with_capacity(remapped.entries.len());
+	for (name, mut entry) in remapped.entries {
+		let name = match &mut entry.content {
+			JarEntryEnum::Class(class) => {
+				if rewrite_strings {
+					if let ClassRepr::Parsed { class } = class {
+						relocate_strings(class, &remapper);
+					}
+				}
+				// class entries are already renamed by the remapping
+				name
+			},
+			JarEntryEnum::Dir | JarEntryEnum::Other(_) => {
+				match remapper.relocate(JavaStr::from_str(&name)) {
+					Some(relocated) => relocated.into_string()?,
+					None => name,
+				}
+			},
+		};
+
+		entries.insert(name, entry);
+	}
+
+	Ok(ParsedJar { entries })
+}
+
+fn relocate_strings(class: &mut ClassFile, remapper: &PrefixRemapper) {
+	let relocate = |string: &mut JavaString| {
+		if let Some(relocated) = remapper.relocate_string(string) {
+			*string = relocated;
+		}
+	};
+
+	for field in &mut class.fields {
+		if let Some(ConstantValue::String(string)) = &mut field.constant_value {
+			relocate(string);
+		}
+	}
+
+	for method in &mut class.methods {
+		if let Some(code) = &mut method.code {
+			for entry in &mut code.instructions {
+				if let Instruction::Ldc(Loadable::String(string)) = &mut entry.instruction {
+					relocate(string);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use anyhow::{bail, Result};
+	use indexmap::IndexMap;
+	use pretty_assertions::assert_eq;
+	use java_string::{JavaStr, JavaString};
+	use duke::tree::class::{ClassAccess, ClassFile, ClassName};
+	use duke::tree::field::{Field, FieldAccess, FieldDescriptor, FieldName, FieldRef};
+	use duke::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName};
+	use duke::tree::method::code::{Code, Instruction, InstructionListEntry, Loadable};
+	use duke::tree::version::Version;
+	use crate::relocate::relocate;
+	use crate::storage::{BasicFileAttributes, ClassRepr, IsClass, JarEntryEnum, ParsedJar, ParsedJarEntry};
+
+	fn class_name(name: &str) -> Result<ClassName> {
+		ClassName::try_from(JavaStr::from_str(name))
+	}
+
+	fn entry(content: JarEntryEnum<ClassRepr, Vec<u8>>) -> ParsedJarEntry<ClassRepr, Vec<u8>> {
+		ParsedJarEntry { attr: BasicFileAttributes::default(), content }
+	}
+
+	fn jar() -> Result<ParsedJar<ClassRepr, Vec<u8>>> {
+		let foo = ClassFile::new(Version::V1_8, ClassAccess::from(0x0021), class_name("com/example/Foo")?, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+
+		let mut user = ClassFile::new(Version::V1_8, ClassAccess::from(0x0021), class_name("org/other/User")?, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		user.fields.push(Field::new(
+			FieldAccess::from(0x0009),
+			FieldName::try_from(JavaStr::from_str("foo"))?,
+			FieldDescriptor::try_from(JavaStr::from_str("Lcom/example/Foo;"))?,
+		));
+		let mut method = Method::new(
+			MethodAccess::from(0x0009),
+			MethodName::try_from(JavaStr::from_str("run"))?,
+			MethodDescriptor::try_from(JavaStr::from_str("()V"))?,
+		);
+		let instructions = [
+			Instruction::GetStatic(FieldRef {
+				class: class_name("org/other/User")?,
+				name: FieldName::try_from(JavaStr::from_str("foo"))?,
+				desc: FieldDescriptor::try_from(JavaStr::from_str("Lcom/example/Foo;"))?,
+			}),
+			Instruction::Pop,
+			Instruction::Ldc(Loadable::String(JavaString::from("com.example.Foo"))),
+			Instruction::Pop,
+			Instruction::Return,
+		];
+		method.code = Some(Code {
+			max_stack: Some(1),
+			max_locals: Some(0),
+			instructions: instructions.into_iter()
+				.map(|instruction| InstructionListEntry { label: None, frame: None, offset: None, instruction })
+				.collect(),
+			..Code::default()
+		});
+		user.methods.push(method);
+
+		Ok(ParsedJar {
+			entries: IndexMap::from([
+				("com/example/".to_owned(), entry(JarEntryEnum::Dir)),
+				ParsedJarEntry::from_class(foo),
+				("com/example/data.txt".to_owned(), entry(JarEntryEnum::Other(b"data".to_vec()))),
+				ParsedJarEntry::from_class(user),
+			]),
+		})
+	}
+
+	fn read_user(jar: &ParsedJar<ClassRepr, Vec<u8>>) -> Result<ClassFile> {
+		let JarEntryEnum::Class(class) = &jar.entries["org/other/User.class"].content else {
+			bail!("expected a class");
+		};
+		class.read()
+	}
+
+	#[test]
+	fn relocate_package() -> Result<()> {
+		let rules = [("com/example/", "shaded/com/example/")];
+
+		let relocated = relocate(jar()?, &rules, false)?;
+		assert_eq!(relocated.entries.keys().collect::<Vec<_>>(), [
+			"shaded/com/example/",
+			"shaded/com/example/Foo.class",
+			"shaded/com/example/data.txt",
+			"org/other/User.class",
+		]);
+
+		let JarEntryEnum::Class(foo) = &relocated.entries["shaded/com/example/Foo.class"].content else {
+			bail!("expected a class");
+		};
+		assert_eq!(foo.read()?.name, class_name("shaded/com/example/Foo")?);
+
+		let user = read_user(&relocated)?;
+		assert_eq!(user.fields[0].descriptor.as_inner(), "Lshaded/com/example/Foo;");
+		let instructions: Vec<_> = user.methods[0].code.iter()
+			.flat_map(|code| &code.instructions)
+			.map(|entry| &entry.instruction)
+			.collect();
+		let Instruction::GetStatic(field_ref) = instructions[0] else {
+			bail!("expected a getstatic instruction");
+		};
+		assert_eq!(field_ref.desc.as_inner(), "Lshaded/com/example/Foo;");
+		// strings stay the same unless asked for
+		assert_eq!(instructions[2], &Instruction::Ldc(Loadable::String(JavaString::from("com.example.Foo"))));
+
+		let relocated = relocate(jar()?, &rules, true)?;
+		let user = read_user(&relocated)?;
+		let code = user.methods[0].code.as_ref();
+		assert_eq!(
+			code.map(|code| &code.instructions[2].instruction),
+			Some(&Instruction::Ldc(Loadable::String(JavaString::from("shaded.com.example.Foo")))),
+		);
+
+		Ok(())
+	}
+}
@@ -1,19 +1,43 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use indexmap::IndexMap;
 use java_string::{JavaStr, JavaString};
-use duke::tree::annotation::{Annotation, ElementValue, ElementValuePair};
+use duke::tree::annotation::{Annotation, ElementValue, ElementValuePair, Object};
 use duke::tree::class::{ClassFile, ClassName, ClassNameSlice, ClassSignature, EnclosingMethod, InnerClass};
+use duke::tree::descriptor::ReturnDescriptor;
 use duke::tree::field::{Field, FieldDescriptor, FieldRef, FieldSignature};
 use duke::tree::method::{Method, MethodDescriptor, MethodParameter, MethodRef, MethodSignature};
 use duke::tree::method::code::{Code, ConstantDynamic, Exception, Handle, Instruction, InstructionListEntry, InvokeDynamic, Loadable, Lv};
+use duke::tree::method::raw_code::{RawCode, RawConstant, RawException, RawLv, RawStackMapTable};
 use duke::tree::type_annotation::TypeAnnotation;
 use duke::visitor::method::code::{StackMapData, VerificationTypeInfo};
-use quill::remapper::BRemapper;
-use crate::storage::{ClassRepr, IsClass, IsOther, Jar, JarEntry, OpenedJar, ParsedJar, ParsedJarEntry};
-
+use quill::remapper::{BRemapper, JarSuperProv};
+use quill::tree::mappings::Mappings;
+use quill::tree::mappings_diff::MappingsDiff;
+use crate::storage::{ClassRepr, IsClass, IsOther, Jar, JarEntry, JarEntryEnum, OpenedJar, ParsedJar, ParsedJarEntry};
+
+
+/// What to do with the `SourceFile` attribute of the classes when remapping.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SourceFilePolicy {
+	/// Keep the `SourceFile` attribute as it is.
+	#[default]
+	Keep,
+	/// Remove the `SourceFile` attribute.
+	Strip,
+	/// Set the `SourceFile` attribute to `<NewSimpleName>.java`, for the simple name of the remapped outermost class.
+	///
+	/// Classes without a `SourceFile` attribute don't get one.
+	Rewrite,
+}
 
 // TODO: doc
 pub fn remap(jar: impl Jar, remapper: impl BRemapper) -> Result<ParsedJar<ClassRepr, Vec<u8>>> {
+	remap_with_source_file_policy(jar, remapper, SourceFilePolicy::Keep)
+}
+
+/// Like [`remap`], but changes the `SourceFile` attribute of each class according to the `policy`.
+pub fn remap_with_source_file_policy(jar: impl Jar, remapper: impl BRemapper, policy: SourceFilePolicy)
+		-> Result<ParsedJar<ClassRepr, Vec<u8>>> {
 	let mut opened = jar.open()?;
 
 	let mut resulting_entries = IndexMap::new();
@@ -28,7 +52,7 @@ pub fn remap(jar: impl Jar, remapper: impl BRemapper) -> Result<ParsedJar<ClassR
 			content: entry.to_jar_entry_enum()?
 // TODO: don't do any directories and only after remapping figure out the directories for the classes
 				.try_map_both(
-					|class| Ok(ClassRepr::Parsed { class: remap_class(&remapper, class)? }),
+					|class| Ok(ClassRepr::Parsed { class: remap_class_with_source_file_policy(&remapper, class, policy)? }),
 					|other| remap_other(&remapper, other)
 				)?,
 		};
@@ -39,6 +63,58 @@ pub fn remap(jar: impl Jar, remapper: impl BRemapper) -> Result<ParsedJar<ClassR
 	Ok(ParsedJar { entries: resulting_entries })
 }
 
+/// Applies a diff to the names of a jar, by remapping it from the names `base` has to the names `base` has after applying
+/// the diff.
+///
+/// The jar must use the names of `namespace` of the `base` mappings. The diff is applied to the `namespace` of `base`
+/// like [`MappingsDiff::apply_to`] does, and then the jar is remapped from the old names to the new names, see
+/// [`Mappings::remapper_to`].
+pub fn apply_mapping_diff_to_jar<const N: usize>(jar: impl Jar, base: &Mappings<N>, diff: &MappingsDiff, namespace: &str)
+		-> Result<ParsedJar<ClassRepr, Vec<u8>>> {
+	let applied = diff.apply_to(base.clone(), namespace)
+		.with_context(|| anyhow!("failed to apply diff to namespace {namespace:?} of the mappings"))?;
+
+	// read the jar only once, collecting the super classes for the remapper on the way
+	let mut opened = jar.open()?;
+
+	let mut inheritance = JarSuperProv { super_classes: IndexMap::new() };
+	let mut entries = Vec::new();
+	for key in opened.entry_keys() {
+		let entry = opened.by_entry_key(key)?;
+
+		let name = entry.name().to_owned();
+		let attr = entry.attrs();
+		let content = entry.to_jar_entry_enum()?
+			.try_map_both(|class| class.read(), |other| Ok(other.get_data_owned()))?;
+
+		if let JarEntryEnum::Class(class) = &content {
+			let super_classes = class.super_class.iter().chain(&class.interfaces).cloned().collect();
+			inheritance.super_classes.insert(class.name.clone(), super_classes);
+		}
+
+		entries.push((name, attr, content));
+	}
+
+	let remapper = base.remapper_to(&applied, base.get_namespace(namespace)?, &inheritance)?;
+
+	let mut resulting_entries = IndexMap::new();
+	for (name, attr, content) in entries {
+		let name = remap_jar_entry_name(&name, &remapper)?;
+
+		let entry = ParsedJarEntry {
+			attr,
+			content: content.try_map_both(
+				|class| Ok(ClassRepr::Parsed { class: remap_class(&remapper, class)? }),
+				|other| remap_other(&remapper, other)
+			)?,
+		};
+
+		resulting_entries.insert(name, entry);
+	}
+
+	Ok(ParsedJar { entries: resulting_entries })
+}
+
 pub fn remap_jar_entry_name(name: &str, remapper: &impl BRemapper) -> Result<String> {
 	let name: &JavaStr = JavaStr::from_str(name);
 	let name = remap_jar_entry_name_java(name, remapper)?;
@@ -63,12 +139,94 @@ pub fn remap_class(remapper: &impl BRemapper, class: impl IsClass) -> Result<Cla
 	class.read()?.remap(remapper)
 }
 
+/// Remaps a class, and changes its `SourceFile` attribute according to the `policy`.
+pub fn remap_class_with_source_file_policy(remapper: &impl BRemapper, class: impl IsClass, policy: SourceFilePolicy) -> Result<ClassFile> {
+	let mut class = remap_class(remapper, class)?;
+
+	match policy {
+		SourceFilePolicy::Keep => {},
+		SourceFilePolicy::Strip => class.source_file = None,
+		SourceFilePolicy::Rewrite => if class.source_file.is_some() {
+			let simple_name = class.name.get_simple_name().as_inner();
+			// inner classes are in the source file of their outermost class
+			let outermost = match simple_name.split_once('$') {
+				Some((outermost, _)) if !outermost.is_empty() => outermost,
+				_ => simple_name,
+			};
+			class.source_file = Some(format!("{outermost}.java").into());
+		},
+	}
+
+	Ok(class)
+}
+
 pub fn remap_other(remapper: &impl BRemapper, other: impl IsOther) -> Result<Vec<u8>> {
 	let data = other.get_data_owned();
 	// TODO: at least warn about it
 	Ok(data)
 }
 
+/// Remaps an `invokedynamic` instruction, including the class and member references in its bootstrap arguments.
+///
+/// For [lambdas][InvokeDynamic::is_lambda], this also remaps the name of the implemented method of the functional interface.
+/// The `this_class` is the (not yet remapped) name of the class containing the instruction.
+pub fn remap_invoke_dynamic(remapper: &impl BRemapper, invoke_dynamic: InvokeDynamic, this_class: &ClassName) -> Result<InvokeDynamic> {
+	invoke_dynamic.remap_with_class_name(remapper, this_class)
+}
+
+/// Remaps a loadable constant, like a bootstrap argument, or the constant of an `ldc` instruction.
+///
+/// The `this_class` is the (not yet remapped) name of the class containing the constant.
+pub fn remap_loadable(remapper: &impl BRemapper, loadable: Loadable, this_class: &ClassName) -> Result<Loadable> {
+	loadable.remap_with_class_name(remapper, this_class)
+}
+
+/// Rewrites the `String` and class values of an annotation, including the ones in nested annotations and arrays.
+///
+/// Unlike remapping the annotation with a remapper, this allows changing the values in any way, like rewriting a string
+/// constant naming a class, or redirecting the class values to other classes. Other values are left as they are.
+pub fn remap_annotation_constants(
+	annotation: Annotation,
+	mut map_string: impl FnMut(JavaString) -> Result<JavaString>,
+	mut map_class: impl FnMut(ReturnDescriptor) -> Result<ReturnDescriptor>,
+) -> Result<Annotation> {
+	fn annotation_constants(
+		annotation: Annotation,
+		map_string: &mut impl FnMut(JavaString) -> Result<JavaString>,
+		map_class: &mut impl FnMut(ReturnDescriptor) -> Result<ReturnDescriptor>,
+	) -> Result<Annotation> {
+		Ok(Annotation {
+			annotation_type: annotation.annotation_type,
+			element_value_pairs: annotation.element_value_pairs.into_iter()
+				.map(|pair| Ok(ElementValuePair {
+					name: pair.name,
+					value: element_value_constants(pair.value, map_string, map_class)?,
+				}))
+				.collect::<Result<_>>()?,
+		})
+	}
+
+	fn element_value_constants(
+		value: ElementValue,
+		map_string: &mut impl FnMut(JavaString) -> Result<JavaString>,
+		map_class: &mut impl FnMut(ReturnDescriptor) -> Result<ReturnDescriptor>,
+	) -> Result<ElementValue> {
+		Ok(match value {
+			ElementValue::Object(Object::String(string)) => ElementValue::Object(Object::String(map_string(string)?)),
+			ElementValue::Class(class) => ElementValue::Class(map_class(class)?),
+			ElementValue::AnnotationInterface(annotation) => {
+				ElementValue::AnnotationInterface(annotation_constants(annotation, map_string, map_class)?)
+			},
+			ElementValue::ArrayType(values) => ElementValue::ArrayType(values.into_iter()
+				.map(|value| element_value_constants(value, map_string, map_class))
+				.collect::<Result<_>>()?),
+			value @ (ElementValue::Object(_) | ElementValue::Enum { .. }) => value,
+		})
+	}
+
+	annotation_constants(annotation, &mut map_string, &mut map_class)
+}
+
 trait Mappable<Output = Self>: Sized {
 	fn remap(self, remapper: &impl BRemapper) -> Result<Output>;
 }
@@ -349,6 +507,48 @@ impl MappableWithClassName for Code {
 			runtime_invisible_type_annotations: self.runtime_invisible_type_annotations.remap(remapper)?,
 
 			attributes: Vec::new(), // TODO:
+
+			raw: self.raw.remap_with_class_name(remapper, this_class)?,
+		})
+	}
+}
+
+impl MappableWithClassName for RawCode {
+	fn remap_with_class_name(self, remapper: &impl BRemapper, this_class: &ClassName) -> Result<Self> {
+		Ok(RawCode {
+			max_stack: self.max_stack,
+			max_locals: self.max_locals,
+
+			bytecode: self.bytecode,
+			constants: self.constants.into_iter()
+				.map(|(offset, constant)| Ok((offset, constant.remap_with_class_name(remapper, this_class)?)))
+				.collect::<Result<_>>()?,
+
+			exception_table: self.exception_table.remap(remapper)?,
+
+			stack_map_table: self.stack_map_table.map(|stack_map_table| -> Result<_> {
+				Ok(RawStackMapTable {
+					data: stack_map_table.data,
+					classes: stack_map_table.classes.into_iter()
+						.map(|(offset, class)| Ok((offset, class.remap(remapper)?)))
+						.collect::<Result<_>>()?,
+				})
+			}).transpose()?,
+			line_numbers: self.line_numbers,
+			local_variables: self.local_variables.remap(remapper)?,
+		})
+	}
+}
+
+impl MappableWithClassName for RawConstant {
+	fn remap_with_class_name(self, remapper: &impl BRemapper, this_class: &ClassName) -> Result<Self> {
+		use RawConstant::*;
+		Ok(match self {
+			Class(class_name) => Class(class_name.remap(remapper)?),
+			Field(field_ref) => Field(field_ref.remap(remapper)?),
+			Method(method_ref, is_interface) => Method(method_ref.remap(remapper)?, is_interface),
+			InvokeDynamic(invoke_dynamic) => InvokeDynamic(invoke_dynamic.remap_with_class_name(remapper, this_class)?),
+			Loadable(loadable) => Loadable(loadable.remap_with_class_name(remapper, this_class)?),
 		})
 	}
 }
@@ -358,6 +558,7 @@ impl MappableWithClassName for InstructionListEntry {
 		Ok(InstructionListEntry {
 			label: self.label,
 			frame: self.frame.remap(remapper)?,
+			offset: self.offset,
 			instruction: self.instruction.remap_with_class_name(remapper, this_class)?,
 		})
 	}
@@ -521,9 +722,15 @@ impl MappableWithClassName for ConstantDynamic {
 
 impl MappableWithClassName for InvokeDynamic {
 	fn remap_with_class_name(self, remapper: &impl BRemapper, this_class: &ClassName) -> Result<Self> {
+		// for lambdas, the name is the name of the method of the functional interface
+		let name = match (self.lambda_interface()?, self.lambda_method_type()) {
+			(Some(interface), Some(method_type)) => remapper.map_method(&interface, &self.name, method_type)?.name,
+			_ => self.name, // TODO: remap for other bootstrap methods
+		};
+
 		Ok(InvokeDynamic {
-			name: self.name, // TODO: remap
-			descriptor: self.descriptor, // TODO: remap
+			name,
+			descriptor: self.descriptor.remap(remapper)?,
 			handle: self.handle.remap(remapper)?,
 			arguments: self.arguments.remap_with_class_name(remapper, this_class)?,
 		})
@@ -541,6 +748,30 @@ impl Mappable for Exception {
 	}
 }
 
+impl Mappable for RawException {
+	fn remap(self, remapper: &impl BRemapper) -> Result<Self> {
+		Ok(RawException {
+			start: self.start,
+			end: self.end,
+			handler: self.handler,
+			catch: self.catch.remap(remapper)?,
+		})
+	}
+}
+
+impl Mappable for RawLv {
+	fn remap(self, remapper: &impl BRemapper) -> Result<Self> {
+		Ok(RawLv {
+			start: self.start,
+			length: self.length,
+			name: self.name, // TODO: lv name remapping
+			descriptor: self.descriptor.remap(remapper)?,
+			signature: self.signature.remap(remapper)?,
+			index: self.index,
+		})
+	}
+}
+
 impl Mappable for Lv {
 	fn remap(self, remapper: &impl BRemapper) -> Result<Self> {
 		Ok(Lv {
@@ -558,4 +789,247 @@ impl Mappable for MethodParameter {
 		// TODO: remapper doesn't support parameter names yet!
 		Ok(self)
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::{bail, Context, Result};
+	use indexmap::IndexMap;
+	use pretty_assertions::assert_eq;
+	use java_string::JavaStr;
+	use duke::tree::annotation::{Annotation, ElementValue, ElementValuePair, Object};
+	use duke::tree::class::{ClassAccess, ClassFile, ClassName};
+	use duke::tree::descriptor::ReturnDescriptor;
+	use duke::tree::field::FieldDescriptor;
+	use duke::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName, MethodRef};
+	use duke::tree::method::code::{Code, Handle, Instruction, InstructionListEntry, InvokeDynamic, Loadable, LvIndex};
+	use duke::tree::version::Version;
+	use quill::remapper::{ARemapper, NoSuperClassProvider};
+	use quill::tree::mappings_builder::MappingsBuilder;
+	use quill::tree::mappings_diff::MappingsDiff;
+	use crate::remap::{remap_annotation_constants, remap_class, remap_class_with_source_file_policy, remap_invoke_dynamic, SourceFilePolicy};
+	use crate::storage::{BasicFileAttributes, ClassRepr, IsClass, JarEntryEnum, ParsedJar, ParsedJarEntry};
+
+	fn method_ref(class: &str, name: &str, desc: &str) -> MethodRef {
+		// SAFETY: all of the strings passed in below are valid.
+		unsafe {
+			MethodRef {
+				class: ClassName::from_inner_unchecked(class.into()),
+				name: MethodName::from_inner_unchecked(name.into()),
+				desc: MethodDescriptor::from_inner_unchecked(desc.into()),
+			}
+		}
+	}
+
+	fn method_descriptor(desc: &str) -> MethodDescriptor {
+		// SAFETY: all of the descriptors passed in below are valid.
+		unsafe { MethodDescriptor::from_inner_unchecked(desc.into()) }
+	}
+
+	#[test]
+	fn remap_lambda() -> Result<()> {
+		let mappings = quill::tiny_v2::read::<2>("\
+tiny	2	0	official	named
+c	a	org/example/Foo
+	m	()V	b	bar
+c	c	org/example/Task
+	m	()V	d	run
+".as_bytes())?;
+		let remapper = mappings.remapper_b_first_to_second(NoSuperClassProvider::new())?;
+
+		let metafactory = Handle::InvokeStatic(method_ref(
+			"java/lang/invoke/LambdaMetafactory",
+			"metafactory",
+			"(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;Ljava/lang/invoke/MethodType;Ljava/lang/invoke/MethodHandle;Ljava/lang/invoke/MethodType;)Ljava/lang/invoke/CallSite;",
+		), false);
+
+		// the method reference `a::b` as a `c`
+		let invoke_dynamic = InvokeDynamic {
+			// SAFETY: `d` is a valid method name.
+			name: unsafe { MethodName::from_inner_unchecked("d".into()) },
+			descriptor: method_descriptor("()Lc;"),
+			handle: metafactory.clone(),
+			arguments: vec![
+				Loadable::MethodType(method_descriptor("()V")),
+				Loadable::MethodHandle(Handle::InvokeStatic(method_ref("a", "b", "()V"), false)),
+				Loadable::MethodType(method_descriptor("()V")),
+			],
+		};
+		assert!(invoke_dynamic.is_lambda());
+
+		// SAFETY: `e` is a valid class name.
+		let this_class = unsafe { ClassName::from_inner_unchecked("e".into()) };
+		let remapped = remap_invoke_dynamic(&remapper, invoke_dynamic, &this_class)?;
+
+		assert_eq!(remapped.lambda_implementation(), Some(&Handle::InvokeStatic(method_ref("org/example/Foo", "bar", "()V"), false)));
+		assert_eq!(remapped.name.as_inner(), "run");
+		assert_eq!(remapped.descriptor, method_descriptor("()Lorg/example/Task;"));
+		assert_eq!(remapped.handle, metafactory);
+
+		Ok(())
+	}
+
+	#[test]
+	fn remap_source_file() -> Result<()> {
+		let mappings = quill::tiny_v2::read::<2>("\
+tiny	2	0	official	named
+c	a	org/example/Foo
+c	a$b	org/example/Foo$Inner
+".as_bytes())?;
+		let remapper = mappings.remapper_b_first_to_second(NoSuperClassProvider::new())?;
+
+		let class = |name: &str| -> Result<ClassFile> {
+			let name = ClassName::try_from_inner(name.into())?;
+			let mut class = ClassFile::new(Version::V1_8, ClassAccess::from(0x0021), name, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+			class.source_file = Some("SourceFile.java".into());
+			Ok(class)
+		};
+
+		let remapped = remap_class_with_source_file_policy(&remapper, class("a")?, SourceFilePolicy::Rewrite)?;
+		assert_eq!(remapped.name.as_inner(), "org/example/Foo");
+		assert_eq!(remapped.source_file.as_deref(), Some(JavaStr::from_str("Foo.java")));
+
+		let remapped = remap_class_with_source_file_policy(&remapper, class("a$b")?, SourceFilePolicy::Rewrite)?;
+		assert_eq!(remapped.source_file.as_deref(), Some(JavaStr::from_str("Foo.java")));
+
+		let remapped = remap_class_with_source_file_policy(&remapper, class("a")?, SourceFilePolicy::Strip)?;
+		assert_eq!(remapped.source_file, None);
+
+		let remapped = remap_class_with_source_file_policy(&remapper, class("a")?, SourceFilePolicy::Keep)?;
+		assert_eq!(remapped.source_file.as_deref(), Some(JavaStr::from_str("SourceFile.java")));
+
+		Ok(())
+	}
+
+	#[test]
+	fn remap_annotation_class_constant() -> Result<()> {
+		let mappings = quill::tiny_v2::read::<2>("\
+tiny	2	0	official	named
+c	a	org/example/Foo
+".as_bytes())?;
+		let remapper = mappings.remapper_b_first_to_second(NoSuperClassProvider::new())?;
+
+		let return_descriptor = |desc: &str| ReturnDescriptor::try_from_inner(desc.into());
+
+		// `@SomeAnno(type = a.class, names = { "a" })`
+		let mut annotation = Annotation::new(FieldDescriptor::try_from_inner("Lorg/example/SomeAnno;".into())?);
+		annotation.element_value_pairs.push(ElementValuePair {
+			name: "type".into(),
+			value: ElementValue::Class(return_descriptor("La;")?),
+		});
+		annotation.element_value_pairs.push(ElementValuePair {
+			name: "names".into(),
+			value: ElementValue::ArrayType(vec![ElementValue::Object(Object::String("a".into()))]),
+		});
+
+		let remapped = remap_annotation_constants(
+			annotation,
+			|string| Ok(format!("{string}!").into()),
+			|class| remapper.map_return_desc(&class),
+		)?;
+
+		assert_eq!(remapped.get("type"), Some(&ElementValue::Class(return_descriptor("Lorg/example/Foo;")?)));
+		assert_eq!(remapped.get("names"), Some(&ElementValue::ArrayType(vec![ElementValue::Object(Object::String("a!".into()))])));
+		assert_eq!(remapped.annotation_type.as_inner(), "Lorg/example/SomeAnno;");
+
+		Ok(())
+	}
+
+	#[test]
+	fn remap_raw_code() -> Result<()> {
+		let mappings = quill::tiny_v2::read::<2>("\
+tiny	2	0	official	named
+c	StripDebugInfo	org/example/Sum
+	m	(Ljava/util/List;)I	sum	sumPositive
+c	java/lang/Integer	org/example/Int
+".as_bytes())?;
+		let remapper = mappings.remapper_b_first_to_second(NoSuperClassProvider::new())?;
+
+		let bytes = include_bytes!("../../duke/src/tree/test/StripDebugInfo.class");
+
+		let raw = duke::read_class_raw_code(&mut Cursor::new(bytes))?;
+		for method in &raw.methods {
+			let code = method.code.as_ref().context("method without code")?;
+			assert!(code.raw.is_some());
+			assert!(code.instructions.is_empty(), "no labels and instructions are built for raw code");
+		}
+
+		let mut written = Vec::new();
+		duke::write_class(&mut written, &remap_class(&remapper, raw)?)?;
+
+		// reading the class fully checks the bytecode, and must give the same as remapping the fully read class
+		let remapped = duke::read_class(&mut Cursor::new(&written))?;
+		let expected = remap_class(&remapper, duke::read_class(&mut Cursor::new(bytes))?)?;
+
+		assert_eq!(remapped.name.as_inner(), "org/example/Sum");
+		let names: Vec<&JavaStr> = remapped.methods.iter().map(|method| method.name.as_inner()).collect();
+		assert_eq!(names, ["<init>", "sumPositive"]);
+		for (method, expected) in remapped.methods.iter().zip(&expected.methods) {
+			let code = method.code.as_ref().context("method without code")?;
+			let expected = expected.code.as_ref().context("method without code")?;
+			assert_eq!(code.instructions, expected.instructions);
+			assert_eq!(code.local_variables, expected.local_variables);
+			assert_eq!(code.line_numbers, expected.line_numbers);
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn apply_mapping_diff_to_jar() -> Result<()> {
+		let mappings = |class: &str, method: &str| {
+			MappingsBuilder::new()
+				.namespaces(["official", "named"])
+				.add_class(["a", class])
+				.add_method("a", "()V", ["b", method])
+				.build()
+		};
+		let base = mappings("com/example/Foo", "run")?;
+		let diff = MappingsDiff::diff(&base, &mappings("com/example/Bar", "execute")?)?;
+
+		let foo = ClassName::try_from(JavaStr::from_str("com/example/Foo"))?;
+		let mut class = ClassFile::new(Version::V1_8, ClassAccess::from(0x0021), foo.clone(), Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		let mut method = Method::new(MethodAccess::from(0x0001), MethodName::try_from(JavaStr::from_str("run"))?, MethodDescriptor::try_from(JavaStr::from_str("()V"))?);
+		let instructions = [
+			Instruction::ALoad(LvIndex { index: 0 }),
+			Instruction::InvokeVirtual(method_ref("com/example/Foo", "run", "()V")),
+			Instruction::Return,
+		];
+		method.code = Some(Code {
+			max_stack: Some(1),
+			max_locals: Some(1),
+			instructions: instructions.into_iter()
+				.map(|instruction| InstructionListEntry { label: None, frame: None, offset: None, instruction })
+				.collect(),
+			..Code::default()
+		});
+		class.methods.push(method);
+
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				("com/example/Foo.class".to_owned(), ParsedJarEntry {
+					attr: BasicFileAttributes::default(),
+					content: JarEntryEnum::Class(ClassRepr::Parsed { class }),
+				}),
+			]),
+		};
+
+		let remapped = super::apply_mapping_diff_to_jar(jar, &base, &diff, "named")?;
+
+		assert_eq!(remapped.entries.keys().collect::<Vec<_>>(), ["com/example/Bar.class"]);
+		let JarEntryEnum::Class(class) = &remapped.entries["com/example/Bar.class"].content else {
+			bail!("expected a class");
+		};
+		let class = class.read()?;
+		assert_eq!(class.name.as_inner(), "com/example/Bar");
+		assert_eq!(class.methods[0].name.as_inner(), "execute");
+		let code = class.methods[0].code.as_ref().context("no code")?;
+		assert_eq!(code.instructions[1].instruction, Instruction::InvokeVirtual(method_ref("com/example/Bar", "execute", "()V")));
+
+		// the first namespace can't be changed this way
+		assert!(super::apply_mapping_diff_to_jar(ParsedJar::<ClassRepr, Vec<u8>> { entries: IndexMap::new() }, &base, &diff, "official").is_err());
+
+		Ok(())
+	}
+}
@@ -0,0 +1,217 @@
+//! Resolving member references against the classes of a jar.
+//!
+//! A member reference like `(class, name, desc)` doesn't need to name the class that declares the member, it may also name
+//! a subclass of it. The functions here find the class that actually declares the member, similar to the resolution done by
+//! the JVM (see JVMS §5.4.3.2 and §5.4.3.3).
+
+use std::collections::{HashSet, VecDeque};
+use anyhow::{anyhow, Context, Result};
+use duke::tree::class::{ClassFile, ClassName, ClassNameSlice};
+use duke::tree::field::{FieldDescriptorSlice, FieldNameSlice};
+use duke::tree::method::{MethodDescriptorSlice, MethodNameSlice};
+use quill::remapper::SuperClassProvider;
+use crate::storage::{IsClass, JarEntry, JarEntryEnum, OpenedJar};
+
+/// Finds the class that declares the method `name` with the descriptor `desc`, starting the search at `class`.
+///
+/// First `class` and its superclasses are searched, and then all the superinterfaces of these. The members of classes not
+/// in the jar (like the ones of a library) can't be searched, but their supertypes are still taken from `inheritance`, which
+/// must give the super class of a class first, followed by its interfaces, like
+/// [`get_super_classes_provider`][OpenedJar::get_super_classes_provider] does. Classes found in neither (like
+/// `java/lang/Object`) are skipped.
+///
+/// Returns [`None`] if no class in the jar declares such a method.
+pub fn resolve_method(
+	jar: &mut impl OpenedJar,
+	inheritance: &impl SuperClassProvider,
+	class: &ClassNameSlice,
+	name: &MethodNameSlice,
+	desc: &MethodDescriptorSlice,
+) -> Result<Option<ClassName>> {
+	resolve(jar, inheritance, class, |class| class.methods.iter().any(|method| *method.name == *name && *method.descriptor == *desc))
+		.with_context(|| anyhow!("failed to resolve method {class}.{name}{desc}"))
+}
+
+/// Finds the class that declares the field `name` with the descriptor `desc`, starting the search at `class`.
+///
+/// This searches in the same order as [`resolve_method`].
+///
+/// Returns [`None`] if no class in the jar declares such a field.
+pub fn resolve_field(
+	jar: &mut impl OpenedJar,
+	inheritance: &impl SuperClassProvider,
+	class: &ClassNameSlice,
+	name: &FieldNameSlice,
+	desc: &FieldDescriptorSlice,
+) -> Result<Option<ClassName>> {
+	resolve(jar, inheritance, class, |class| class.fields.iter().any(|field| *field.name == *name && *field.descriptor == *desc))
+		.with_context(|| anyhow!("failed to resolve field {class}.{name}:{desc}"))
+}
+
+fn resolve(
+	jar: &mut impl OpenedJar,
+	inheritance: &impl SuperClassProvider,
+	class: &ClassNameSlice,
+	declares: impl Fn(&ClassFile) -> bool,
+) -> Result<Option<ClassName>> {
+	let mut visited = HashSet::new();
+	let mut interfaces = VecDeque::new();
+
+	// first the class and its superclasses
+	let mut current = Some(class.to_owned());
+	while let Some(name) = current.take() {
+		if !visited.insert(name.clone()) {
+			break;
+		}
+		let Some(class) = read_class(jar, &name)? else {
+			// a library class, continue with its supertypes
+			if let Some(super_classes) = inheritance.get_super_classes(&name)? {
+				let mut super_classes = super_classes.iter().cloned();
+				current = super_classes.next();
+				interfaces.extend(super_classes);
+			}
+			continue;
+		};
+
+		if declares(&class) {
+			return Ok(Some(name));
+		}
+
+		interfaces.extend(class.interfaces);
+		current = class.super_class;
+	}
+
+	// then all the superinterfaces, breadth first
+	while let Some(name) = interfaces.pop_front() {
+		if !visited.insert(name.clone()) {
+			continue;
+		}
+		let Some(class) = read_class(jar, &name)? else {
+			if let Some(super_classes) = inheritance.get_super_classes(&name)? {
+				interfaces.extend(super_classes.iter().cloned());
+			}
+			continue;
+		};
+
+		if declares(&class) {
+			return Ok(Some(name));
+		}
+
+		interfaces.extend(class.interfaces);
+	}
+
+	Ok(None)
+}
+
+fn read_class(jar: &mut impl OpenedJar, name: &ClassNameSlice) -> Result<Option<ClassFile>> {
+	let Some(entry) = jar.by_name(&format!("{name}.class"))? else {
+		return Ok(None);
+	};
+
+	match entry.to_jar_entry_enum()? {
+		JarEntryEnum::Class(class) => class.read().map(Some),
+		_ => Ok(None),
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use anyhow::Result;
+	use indexmap::{IndexMap, IndexSet};
+	use pretty_assertions::assert_eq;
+	use duke::tree::class::{ClassAccess, ClassFile, ClassName};
+	use duke::tree::field::{Field, FieldAccess, FieldDescriptor, FieldName};
+	use duke::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName};
+	use duke::tree::version::Version;
+	use quill::remapper::{JarSuperProv, NoSuperClassProvider};
+	use crate::resolve::{resolve_field, resolve_method};
+	use crate::storage::{ClassRepr, ParsedJar, ParsedJarEntry};
+
+	fn class_name(name: &str) -> ClassName {
+		// SAFETY: all of the class names below are valid class names.
+		unsafe { ClassName::from_inner_unchecked(name.into()) }
+	}
+
+	#[test]
+	fn resolve_inherited() -> Result<()> {
+		// SAFETY: `foo` is a valid method name.
+		let foo = unsafe { MethodName::from_inner_unchecked("foo".into()) };
+		// SAFETY: `()V` is a valid method descriptor.
+		let desc = unsafe { MethodDescriptor::from_inner_unchecked("()V".into()) };
+		// SAFETY: `bar` is a valid method name.
+		let bar = unsafe { MethodName::from_inner_unchecked("bar".into()) };
+		// SAFETY: `field` is a valid field name.
+		let field = unsafe { FieldName::from_inner_unchecked("field".into()) };
+		// SAFETY: `I` is a valid field descriptor.
+		let field_desc = unsafe { FieldDescriptor::from_inner_unchecked("I".into()) };
+
+		let object = Some(ClassName::JAVA_LANG_OBJECT.to_owned());
+
+		let mut parent = ClassFile::new(Version::V1_8, ClassAccess::default(), class_name("Parent"), object.clone(), vec![class_name("Interface")]);
+		parent.methods.push(Method::new(MethodAccess::from(0x0001), foo.clone(), desc.clone()));
+		parent.fields.push(Field::new(FieldAccess::from(0x0001), field.clone(), field_desc.clone()));
+
+		let child = ClassFile::new(Version::V1_8, ClassAccess::default(), class_name("Child"), Some(class_name("Parent")), Vec::new());
+
+		let mut interface = ClassFile::new(Version::V1_8, ClassAccess::default(), class_name("Interface"), object, Vec::new());
+		interface.methods.push(Method::new(MethodAccess::from(0x0401), bar.clone(), desc.clone()));
+
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([ParsedJarEntry::from_class(parent), ParsedJarEntry::from_class(child), ParsedJarEntry::from_class(interface)]),
+		};
+		let mut jar = &jar;
+		let no_inheritance = NoSuperClassProvider::new();
+
+		assert_eq!(resolve_method(&mut jar, no_inheritance, &class_name("Child"), &foo, &desc)?, Some(class_name("Parent")));
+		assert_eq!(resolve_method(&mut jar, no_inheritance, &class_name("Parent"), &foo, &desc)?, Some(class_name("Parent")));
+		assert_eq!(resolve_method(&mut jar, no_inheritance, &class_name("Child"), &bar, &desc)?, Some(class_name("Interface")));
+		assert_eq!(resolve_field(&mut jar, no_inheritance, &class_name("Child"), &field, &field_desc)?, Some(class_name("Parent")));
+
+		// SAFETY: `(I)V` is a valid method descriptor.
+		let other_desc = unsafe { MethodDescriptor::from_inner_unchecked("(I)V".into()) };
+		assert_eq!(resolve_method(&mut jar, no_inheritance, &class_name("Child"), &foo, &other_desc)?, None);
+		assert_eq!(resolve_method(&mut jar, no_inheritance, &class_name("Missing"), &foo, &desc)?, None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn resolve_through_library() -> Result<()> {
+		// SAFETY: `foo` is a valid method name.
+		let foo = unsafe { MethodName::from_inner_unchecked("foo".into()) };
+		// SAFETY: `bar` is a valid method name.
+		let bar = unsafe { MethodName::from_inner_unchecked("bar".into()) };
+		// SAFETY: `()V` is a valid method descriptor.
+		let desc = unsafe { MethodDescriptor::from_inner_unchecked("()V".into()) };
+
+		let object = Some(ClassName::JAVA_LANG_OBJECT.to_owned());
+
+		// `Child` extends the library class `Library`, which extends `Parent` and implements `Interface`
+		let child = ClassFile::new(Version::V1_8, ClassAccess::default(), class_name("Child"), Some(class_name("Library")), Vec::new());
+
+		let mut parent = ClassFile::new(Version::V1_8, ClassAccess::default(), class_name("Parent"), object.clone(), Vec::new());
+		parent.methods.push(Method::new(MethodAccess::from(0x0001), foo.clone(), desc.clone()));
+
+		let mut interface = ClassFile::new(Version::V1_8, ClassAccess::default(), class_name("Interface"), object, Vec::new());
+		interface.methods.push(Method::new(MethodAccess::from(0x0401), bar.clone(), desc.clone()));
+
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([ParsedJarEntry::from_class(child), ParsedJarEntry::from_class(parent), ParsedJarEntry::from_class(interface)]),
+		};
+		let mut jar = &jar;
+
+		let library = JarSuperProv {
+			super_classes: IndexMap::from([
+				(class_name("Library"), IndexSet::from([class_name("Parent"), class_name("Interface")])),
+			]),
+		};
+
+		assert_eq!(resolve_method(&mut jar, &library, &class_name("Child"), &foo, &desc)?, Some(class_name("Parent")));
+		assert_eq!(resolve_method(&mut jar, &library, &class_name("Child"), &bar, &desc)?, Some(class_name("Interface")));
+
+		// without knowing the supertypes of `Library`, the search stops there
+		assert_eq!(resolve_method(&mut jar, NoSuperClassProvider::new(), &class_name("Child"), &foo, &desc)?, None);
+
+		Ok(())
+	}
+}
@@ -16,12 +16,26 @@ pub use jar_entry::{JarEntry, JarEntryEnum};
 mod lazy_class_file;
 pub use lazy_class_file::ClassRepr;
 
+mod manifest;
+pub use manifest::{Attributes, Manifest};
+
 mod opened_jar;
-pub use opened_jar::OpenedJar;
+pub use opened_jar::{OpenedJar, TransitiveClosure};
+
+mod overlay;
+pub use overlay::{OpenedOverlayJar, OverlayClass, OverlayEntry, OverlayJar, OverlayOther};
 
 mod parsed;
 pub use parsed::{ParsedJar, ParsedJarEntry};
 
+mod recover;
+pub use recover::{RecoverReader, RecoveredZip, SkippedEntry};
+
+mod security;
+pub use security::SecurityFinding;
+
+mod zip_directory;
+
 mod zip_file;
 pub use zip_file::FileJar;
 
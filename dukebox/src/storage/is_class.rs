@@ -1,4 +1,4 @@
-use std::io::Cursor;
+use std::io::{Cursor, Write};
 use anyhow::Result;
 use duke::tree::class::ClassFile;
 use duke::visitor::MultiClassVisitor;
@@ -9,7 +9,7 @@ use crate::storage::ClassRepr;
 /// A class entry can be [`read`][IsClass::read] into a [`ClassFile`].
 /// It can also be accepted into a visitor with [`IsClass::visit`].
 /// Lastly, it can be written into something that can be looked at as a `&[u8]`, with
-/// the [IsClass::write] method.
+/// the [IsClass::write] method, or directly into a writer with [IsClass::write_to].
 ///
 /// `Vec<u8>` does not implement [`IsClass`], use [`VecClass`] instead.
 pub trait IsClass {
@@ -20,6 +20,14 @@ pub trait IsClass {
 	type Written<'a>: AsRef<[u8]> where Self: 'a;
 	fn write(&self) -> Result<Self::Written<'_>>;
 
+	/// Writes the class into the writer.
+	///
+	/// Unlike [`write`][IsClass::write], this doesn't need to create a buffer for a parsed class first.
+	fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+		writer.write_all(self.write()?.as_ref())?;
+		Ok(())
+	}
+
 	// TODO: remove?
 	fn into_class_repr(self) -> ClassRepr;
 }
@@ -60,11 +68,43 @@ impl IsClass for ClassFile {
 	type Written<'a> = Vec<u8> where Self: 'a;
 	fn write(&self) -> Result<Self::Written<'_>> {
 		let mut buf = Vec::new();
-		duke::write_class(&mut buf, self)?;
+		self.write_to(&mut buf)?;
 		Ok(buf)
 	}
 
+	fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+		duke::write_class(writer, self)
+	}
+
 	fn into_class_repr(self) -> ClassRepr {
 		ClassRepr::Parsed { class: self }
 	}
 }
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::Result;
+	use pretty_assertions::assert_eq;
+	use java_string::JavaStr;
+	use duke::tree::class::{ClassAccess, ClassFile, ClassName};
+	use duke::tree::version::Version;
+	use crate::storage::{ClassRepr, IsClass};
+
+	#[test]
+	fn write_to() -> Result<()> {
+		let class = ClassFile::new(Version::V1_8, ClassAccess::from(0x0021), ClassName::try_from(JavaStr::from_str("Test"))?, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+
+		let mut buf = Vec::new();
+		class.write_to(&mut buf)?;
+		assert_eq!(buf, class.write()?);
+		assert_eq!(duke::read_class(&mut Cursor::new(&buf))?, class);
+
+		let repr = ClassRepr::Vec { data: buf.clone() };
+		let mut repr_buf = Vec::new();
+		repr.write_to(&mut repr_buf)?;
+		assert_eq!(repr_buf, buf);
+
+		Ok(())
+	}
+}
@@ -1,7 +1,8 @@
 use std::path::Path;
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
 use quill::remapper::JarSuperProv;
-use crate::storage::OpenedJar;
+use crate::storage::{IsClass, IsOther, JarEntry, JarEntryEnum, OpenedJar, SecurityFinding};
 
 /// Represents a `.jar` in some form.
 ///
@@ -30,4 +31,66 @@ pub trait Jar {
 	fn get_super_classes_provider(&self) -> Result<JarSuperProv> {
 		self.open()?.get_super_classes_provider()
 	}
+
+	/// Scans the names of the entries for duplicates and for names escaping the directory the jar is extracted to.
+	///
+	/// Jars stored as zip archives read the names from the central directory directly, as reading the jar normally only
+	/// shows one entry for each name. Other jars can't contain duplicate entries.
+	fn scan_security(&self) -> Result<Vec<SecurityFinding>> {
+		let opened = self.open()?;
+		Ok(crate::storage::security::scan_names(opened.names().map(|(_, name)| name)))
+	}
+
+	/// Computes a SHA-256 hash of the contents of the jar, for detecting whether a jar changed.
+	///
+	/// The hash is computed over the names, kinds and contents of all entries, sorted by name. It doesn't depend on the order
+	/// of the entries, and ignores the file attributes, like timestamps. Classes are hashed as [written][IsClass::write], so
+	/// a parsed class may not have the same hash as the bytes it was read from.
+	fn content_hash(&self) -> Result<[u8; 32]> {
+		let mut opened = self.open()?;
+
+		let mut names: Vec<_> = opened.names()
+			.map(|(key, name)| (name.to_owned(), key))
+			.collect();
+		names.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+		fn update_with_len(hasher: &mut Sha256, data: &[u8]) {
+			hasher.update((data.len() as u64).to_le_bytes());
+			hasher.update(data);
+		}
+
+		let mut hasher = Sha256::new();
+		for (name, key) in names {
+			update_with_len(&mut hasher, name.as_bytes());
+
+			let entry = opened.by_entry_key(key)?;
+			match entry.to_jar_entry_enum()? {
+				JarEntryEnum::Dir => hasher.update([0]),
+				JarEntryEnum::Class(class) => {
+					hasher.update([1]);
+					let written = class.write()
+						.with_context(|| anyhow!("failed to write class entry {name:?} for hashing"))?;
+					update_with_len(&mut hasher, written.as_ref());
+				},
+				JarEntryEnum::Other(other) => {
+					hasher.update([2]);
+					update_with_len(&mut hasher, other.get_data());
+				},
+			}
+		}
+
+		Ok(hasher.finalize().into())
+	}
+
+	/// Returns the names that are used by more than one entry.
+	///
+	/// See [`scan_security`][Jar::scan_security].
+	fn duplicate_entries(&self) -> Result<Vec<String>> {
+		Ok(self.scan_security()?.into_iter()
+			.filter_map(|finding| match finding {
+				SecurityFinding::DuplicateEntry(name) => Some(name),
+				_ => None,
+			})
+			.collect())
+	}
 }
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 use std::fmt::{Debug, Formatter};
-use std::io::Cursor;
+use std::io::{Cursor, Write};
 use anyhow::Result;
 use duke::tree::class::ClassFile;
 use duke::visitor::MultiClassVisitor;
@@ -56,6 +56,13 @@ impl IsClass for ClassRepr {
 		}
 	}
 
+	fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+		match self {
+			ClassRepr::Parsed { class } => duke::write_class(writer, class),
+			ClassRepr::Vec { data } => Ok(writer.write_all(data)?),
+		}
+	}
+
 	fn into_class_repr(self) -> ClassRepr {
 		self
 	}
@@ -88,6 +95,13 @@ impl IsClass for &ClassRepr {
 		}
 	}
 
+	fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+		match self {
+			ClassRepr::Parsed { class } => duke::write_class(writer, class),
+			ClassRepr::Vec { data } => Ok(writer.write_all(data)?),
+		}
+	}
+
 	fn into_class_repr(self) -> ClassRepr {
 		self.clone()
 	}
@@ -0,0 +1,233 @@
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::IndexMap;
+
+/// The attributes of one section of a [`Manifest`].
+///
+/// Attribute names are compared ignoring the case, as the manifest format requires. The names are kept as they are
+/// written in the manifest, in the order they appear.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Attributes {
+	pub entries: IndexMap<String, String>,
+}
+
+impl Attributes {
+	/// Gets the value of the attribute with the given name, ignoring the case of the name.
+	pub fn get(&self, name: &str) -> Option<&str> {
+		self.entries.iter()
+			.find(|(key, _)| key.eq_ignore_ascii_case(name))
+			.map(|(_, value)| value.as_str())
+	}
+
+	/// Sets the value of the attribute with the given name, replacing the value of an attribute with the same name.
+	pub fn insert(&mut self, name: String, value: String) {
+		match self.entries.keys().position(|key| key.eq_ignore_ascii_case(&name)) {
+			Some(index) => self.entries[index] = value,
+			None => {
+				self.entries.insert(name, value);
+			},
+		}
+	}
+}
+
+/// A parsed `META-INF/MANIFEST.MF` file.
+///
+/// See [the jar file specification](https://docs.oracle.com/en/java/javase/21/docs/specs/jar/jar.html#jar-manifest) for the
+/// format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+	/// The attributes of the main section, like `Manifest-Version` or `Main-Class`.
+	pub main_attributes: Attributes,
+	/// The per-entry sections, by the value of their `Name` attribute.
+	///
+	/// The `Name` attribute itself is not part of the attributes of the section.
+	pub sections: IndexMap<String, Attributes>,
+}
+
+impl Manifest {
+	/// Parses a manifest.
+	///
+	/// Lines may end in `\r\n`, `\n` or `\r`. A line starting with a space continues the value of the previous line. This is
+	/// used for values that don't fit into the 72 bytes a line may have. Continuing lines are joined before decoding them as
+	/// UTF-8, as the wrapping may happen in the middle of a character.
+	pub fn parse(bytes: &[u8]) -> Result<Manifest> {
+		let mut manifest = Manifest::default();
+
+		// The per-entry section currently read, with its `Name` attribute once that's found.
+		let mut section: Option<(Option<String>, Attributes)> = None;
+		let mut in_main_section = true;
+
+		for (line_number, line) in logical_lines(bytes) {
+			if line.is_empty() {
+				// A section ends.
+				if let Some((name, attributes)) = section.take() {
+					let name = name.with_context(|| anyhow!("section ending before line {line_number} has no `Name` attribute"))?;
+					manifest.add_section(name, attributes);
+				}
+				in_main_section = false;
+				continue;
+			}
+
+			let line = String::from_utf8(line)
+				.with_context(|| anyhow!("line {line_number} is not valid UTF-8"))?;
+			let Some((name, value)) = line.split_once(": ") else {
+				bail!("line {line_number} is not of the form `name: value`: {line:?}");
+			};
+			if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') {
+				bail!("invalid attribute name {name:?} in line {line_number}");
+			}
+
+			if in_main_section {
+				manifest.main_attributes.insert(name.to_owned(), value.to_owned());
+			} else {
+				let (section_name, attributes) = section.get_or_insert_with(|| (None, Attributes::default()));
+				if name.eq_ignore_ascii_case("Name") {
+					if section_name.is_some() {
+						bail!("second `Name` attribute in the same section in line {line_number}");
+					}
+					*section_name = Some(value.to_owned());
+				} else {
+					attributes.insert(name.to_owned(), value.to_owned());
+				}
+			}
+		}
+
+		if let Some((name, attributes)) = section {
+			let name = name.context("last section has no `Name` attribute")?;
+			manifest.add_section(name, attributes);
+		}
+
+		Ok(manifest)
+	}
+
+	/// Adds the attributes of a section, merging them with the ones of an earlier section of the same name.
+	fn add_section(&mut self, name: String, attributes: Attributes) {
+		let section = self.sections.entry(name).or_default();
+		for (name, value) in attributes.entries {
+			section.insert(name, value);
+		}
+	}
+
+	/// Gets the `Main-Class` attribute of the main section.
+	pub fn main_class(&self) -> Option<&str> {
+		self.main_attributes.get("Main-Class")
+	}
+
+	/// Gets the space separated urls of the `Class-Path` attribute of the main section.
+	pub fn class_path(&self) -> Vec<&str> {
+		self.main_attributes.get("Class-Path")
+			.map(|class_path| class_path.split_whitespace().collect())
+			.unwrap_or_default()
+	}
+
+	/// Checks if the main section has the `Multi-Release: true` attribute.
+	pub fn is_multi_release(&self) -> bool {
+		self.main_attributes.get("Multi-Release")
+			.is_some_and(|value| value.eq_ignore_ascii_case("true"))
+	}
+}
+
+/// Splits the manifest into lines, joining continued lines, and giving each the (one based) number of its first line.
+fn logical_lines(bytes: &[u8]) -> Vec<(usize, Vec<u8>)> {
+	let mut physical_lines = Vec::new();
+	let mut rest = bytes;
+	while !rest.is_empty() {
+		let end = rest.iter().position(|&b| b == b'\r' || b == b'\n').unwrap_or(rest.len());
+		physical_lines.push(&rest[..end]);
+
+		let line_break = match rest[end..] {
+			[b'\r', b'\n', ..] => 2,
+			[_, ..] => 1,
+			[] => 0,
+		};
+		rest = &rest[end + line_break..];
+	}
+
+	let mut lines: Vec<(usize, Vec<u8>)> = Vec::new();
+	for (index, line) in physical_lines.into_iter().enumerate() {
+		match (line.strip_prefix(b" "), lines.last_mut()) {
+			(Some(continuation), Some((_, previous))) if !previous.is_empty() => previous.extend_from_slice(continuation),
+			_ => lines.push((index + 1, line.to_vec())),
+		}
+	}
+	lines
+}
+
+#[cfg(test)]
+mod testing {
+	use anyhow::{Context, Result};
+	use indexmap::IndexMap;
+	use pretty_assertions::assert_eq;
+	use crate::storage::{BasicFileAttributes, ClassRepr, Jar, JarEntryEnum, Manifest, OpenedJar, ParsedJar, ParsedJarEntry};
+
+	const MANIFEST: &[u8] = b"\
+Manifest-Version: 1.0\r\n\
+Main-Class: com.example.Main\r\n\
+Class-Path: libs/first-library-with-a-long-name-1.0.0.jar libs/second-li\r\n \
+brary-2.0.0.jar libs/third.jar\r\n\
+Multi-Release: true\r\n\
+\r\n\
+Name: com/example/Main.class\r\n\
+SHA-256-Digest: abc\r\n\
+\r\n\
+Name: com/example/\r\n\
+Sealed: true\r\n\
+\r\n";
+
+	#[test]
+	fn parse() -> Result<()> {
+		// the line is wrapped at 72 bytes
+		assert_eq!(MANIFEST.split(|&b| b == b'\n').nth(2).map(|line| line.len()), Some(72 + 1));
+
+		let manifest = Manifest::parse(MANIFEST)?;
+
+		assert_eq!(manifest.main_class(), Some("com.example.Main"));
+		assert_eq!(manifest.main_attributes.get("main-class"), Some("com.example.Main"));
+		assert_eq!(manifest.class_path(), [
+			"libs/first-library-with-a-long-name-1.0.0.jar",
+			"libs/second-library-2.0.0.jar",
+			"libs/third.jar",
+		]);
+		assert!(manifest.is_multi_release());
+
+		assert_eq!(manifest.sections.keys().collect::<Vec<_>>(), ["com/example/Main.class", "com/example/"]);
+		assert_eq!(manifest.sections["com/example/Main.class"].get("SHA-256-Digest"), Some("abc"));
+		assert_eq!(manifest.sections["com/example/"].get("Sealed"), Some("true"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn parse_wrapped_utf8() -> Result<()> {
+		// "ä" is split between the two lines
+		let manifest = Manifest::parse(b"Implementation-Title: \xc3\n \xa4\n")?;
+		assert_eq!(manifest.main_attributes.get("Implementation-Title"), Some("ä"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn parse_invalid() {
+		assert!(Manifest::parse(b"Manifest-Version 1.0\n").is_err());
+		assert!(Manifest::parse(b"Manifest-Version: 1.0\n\nSealed: true\n").is_err());
+	}
+
+	#[test]
+	fn manifest() -> Result<()> {
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				("META-INF/MANIFEST.MF".to_owned(), ParsedJarEntry {
+					attr: BasicFileAttributes::default(),
+					content: JarEntryEnum::Other(MANIFEST.to_vec()),
+				}),
+			]),
+		};
+
+		let manifest = jar.open()?.manifest()?.context("expected a manifest")?;
+		assert_eq!(manifest.main_class(), Some("com.example.Main"));
+
+		let empty: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar { entries: IndexMap::new() };
+		assert_eq!(empty.open()?.manifest()?, None);
+
+		Ok(())
+	}
+}
@@ -1,12 +1,16 @@
+use std::collections::HashSet;
 use std::convert::Infallible;
 use std::ops::ControlFlow;
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 use indexmap::{IndexMap, IndexSet};
-use duke::tree::class::{ClassAccess, ClassName};
+use java_string::JavaStr;
+use duke::tree::class::{ClassAccess, ClassFile, ClassHeader, ClassName, ClassNameSlice};
+use duke::tree::descriptor::{ArrayType, Type};
+use duke::tree::references::MemberRef;
 use duke::tree::version::Version;
 use duke::visitor::MultiClassVisitor;
 use quill::remapper::JarSuperProv;
-use crate::storage::{IsClass, JarEntry, JarEntryEnum};
+use crate::storage::{IsClass, IsOther, JarEntry, JarEntryEnum, Manifest};
 
 /// Represents an opened jar.
 ///
@@ -33,20 +37,335 @@ pub trait OpenedJar {
 
 	fn by_name(&mut self, name: &str) -> Result<Option<Self::Entry<'_>>>;
 
+	/// Gets the entry with the given name, as seen by a multi-release jar aware reader running on the given java release.
+	///
+	/// Multi-release jars store classes and resources for specific java releases under `META-INF/versions/<n>/`. Of these
+	/// versioned entries, the one with the highest `<n>` at or below `release` is returned. If there's none, this falls back
+	/// to the base entry, like [`by_name`][OpenedJar::by_name] does. Like the JDK, only versions `9` and above are considered.
+	///
+	/// Note that this doesn't check for the `Multi-Release: true` attribute in the manifest.
+	fn by_name_for_release(&mut self, name: &str, release: u16) -> Result<Option<Self::Entry<'_>>> {
+		let versioned = self.names()
+			.filter_map(|(key, entry_name)| {
+				let (version, rest) = entry_name.strip_prefix("META-INF/versions/")?.split_once('/')?;
+				let version: u16 = version.parse().ok()?;
+				(rest == name && (9..=release).contains(&version)).then_some((version, key))
+			})
+			.max_by_key(|&(version, _)| version)
+			.map(|(_, key)| key);
+
+		match versioned {
+			Some(key) => self.by_entry_key(key).map(Some),
+			None => self.by_name(name),
+		}
+	}
+
+	/// Checks if the jar has no entries at all, not even directories.
+	fn is_empty(&self) -> bool {
+		self.names().next().is_none()
+	}
+
+	/// Checks if the jar has any class entries.
+	///
+	/// This only looks at the entry names, so no entry is read. A jar without classes is fine to use with all the methods
+	/// reading classes, these then just don't read any.
+	fn has_classes(&self) -> bool {
+		self.names().any(|(_, name)| name.ends_with(".class"))
+	}
+
+	/// Reads the bytes of the entry with the given name.
+	///
+	/// This works for both class and other entries. Classes are written back into their bytes, see [`IsClass::write_to`].
+	/// Directories have no data, so an empty `Vec` is returned for them. Returns `None` if there's no entry with that name.
+	fn read_bytes_by_name(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+		let Some(entry) = self.by_name(name)? else {
+			return Ok(None);
+		};
+
+		let bytes = match entry.to_jar_entry_enum()
+			.with_context(|| anyhow!("while reading entry {name:?}"))?
+		{
+			JarEntryEnum::Dir => Vec::new(),
+			JarEntryEnum::Class(class) => {
+				let mut bytes = Vec::new();
+				class.write_to(&mut bytes)
+					.with_context(|| anyhow!("while writing class entry {name:?}"))?;
+				bytes
+			},
+			JarEntryEnum::Other(other) => other.get_data_owned(),
+		};
+
+		Ok(Some(bytes))
+	}
+
+	/// Reads and parses the `META-INF/MANIFEST.MF` entry.
+	///
+	/// Returns `None` if there's no such entry. See [`Manifest::parse`] for details on parsing it.
+	fn manifest(&mut self) -> Result<Option<Manifest>> {
+		self.read_bytes_by_name("META-INF/MANIFEST.MF")?
+			.map(|bytes| Manifest::parse(&bytes).context("failed to parse manifest"))
+			.transpose()
+	}
+
 	/// Visits all the classes into the multi class visitor.
+	///
+	/// Errors are annotated with the name of the class entry that failed.
 	fn read_classes_into<V: MultiClassVisitor>(&mut self, mut visitor: V) -> Result<V> {
 		let keys = self.entry_keys();
 		for key in keys {
 			let entry = self.by_entry_key(key)?;
+			let name = entry.name().to_owned();
 
 			if let JarEntryEnum::Class(class) = entry.to_jar_entry_enum()? {
-				visitor = class.visit(visitor)?;
+				visitor = class.visit(visitor)
+					.with_context(|| anyhow!("while reading class entry {name:?}"))?;
 			}
 		}
 
 		Ok(visitor)
 	}
 
+	/// Visits all the classes into the multi class visitor, skipping classes that fail to be read.
+	///
+	/// Unlike [`read_classes_into`][OpenedJar::read_classes_into], a class that can't be read doesn't abort reading the
+	/// whole jar. Instead, the name of the entry and the error are collected and returned alongside the visitor.
+	///
+	/// Each class is fully read before it's given to the visitor, so that a corrupt class never reaches it. Errors
+	/// returned by the visitor itself are not collected, but returned as usual.
+	fn read_classes_into_lenient<V: MultiClassVisitor>(&mut self, mut visitor: V) -> Result<(V, Vec<(String, anyhow::Error)>)> {
+		let mut errors = Vec::new();
+
+		let keys = self.entry_keys();
+		for key in keys {
+			let entry = self.by_entry_key(key)?;
+			let name = entry.name().to_owned();
+
+			let class = entry.to_jar_entry_enum()
+				.and_then(|entry| match entry {
+					JarEntryEnum::Class(class) => class.read().map(Some),
+					_ => Ok(None),
+				})
+				.with_context(|| anyhow!("while reading class entry {name:?}"));
+
+			match class {
+				Ok(Some(class)) => {
+					visitor = class.accept(visitor)
+						.with_context(|| anyhow!("while visiting class entry {name:?}"))?;
+				},
+				Ok(None) => {},
+				Err(e) => errors.push((name, e)),
+			}
+		}
+
+		Ok((visitor, errors))
+	}
+
+	/// Visits all the classes in the package `prefix` (or any of its subpackages) into the multi class visitor.
+	///
+	/// This filters on the entry names (as given by [`names`][OpenedJar::names]), before reading any classes, so that
+	/// classes outside the package aren't parsed at all.
+	///
+	/// The package must match on `/`-boundaries, so the prefix `org/example` matches the class `org/example/Foo`, but not the class
+	/// `org/examples/Foo`. A trailing `/` on the prefix is ignored. An empty prefix matches all classes.
+	fn read_classes_in_package_into<V: MultiClassVisitor>(&mut self, prefix: &str, mut visitor: V) -> Result<V> {
+		let prefix = prefix.strip_suffix('/').unwrap_or(prefix);
+
+		let keys: Vec<_> = self.names()
+			.filter(|(_, name)| name.ends_with(".class"))
+			.filter(|(_, name)| prefix.is_empty() || name.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/')))
+			.map(|(key, _)| key)
+			.collect();
+
+		for key in keys {
+			let entry = self.by_entry_key(key)?;
+			let name = entry.name().to_owned();
+
+			if let JarEntryEnum::Class(class) = entry.to_jar_entry_enum()? {
+				visitor = class.visit(visitor)
+					.with_context(|| anyhow!("while reading class entry {name:?}"))?;
+			}
+		}
+
+		Ok(visitor)
+	}
+
+	/// Finds the first class for which the `predicate` returns `true`.
+	///
+	/// For each class, only the [`ClassHeader`] is read and given to the predicate. Only the class matching is fully read and
+	/// returned. Classes after it aren't looked at.
+	///
+	/// Returns [`None`] if no class matches.
+	fn find_class(&mut self, mut predicate: impl FnMut(&ClassHeader) -> bool) -> Result<Option<ClassFile>> {
+		let keys = self.entry_keys();
+		for key in keys {
+			let entry = self.by_entry_key(key)?;
+			let name = entry.name().to_owned();
+
+			let matches = if let JarEntryEnum::Class(class) = entry.to_jar_entry_enum()? {
+				let header = read_header(class, &name)?;
+
+				predicate(&header)
+			} else {
+				false
+			};
+
+			if matches {
+				// The entry was consumed for reading the header, so we get it again.
+				let JarEntryEnum::Class(class) = self.by_entry_key(key)?.to_jar_entry_enum()? else {
+					bail!("entry {name:?} is no longer a class");
+				};
+				let class = class.read()
+					.with_context(|| anyhow!("while reading class entry {name:?}"))?;
+				return Ok(Some(class));
+			}
+		}
+
+		Ok(None)
+	}
+
+	/// Counts the classes in each package of this jar.
+	///
+	/// The package of a class is taken from its entry name, so that for most classes nothing is read. Only for entries whose
+	/// name isn't a valid class name (or that are inside `META-INF/`, like the classes of a multi-release jar) the
+	/// [`ClassHeader`] is read to get the name of the class. Classes in the default package are counted under the empty
+	/// string.
+	///
+	/// The packages are sorted by the number of classes in them, descending. Packages with the same number of classes stay
+	/// in the order they were first seen in.
+	fn package_histogram(&mut self) -> Result<IndexMap<String, usize>> {
+		fn package_of(class_name: &str) -> &str {
+			class_name.rsplit_once('/').map_or("", |(package, _)| package)
+		}
+
+		let entries: Vec<_> = self.names()
+			.filter(|(_, name)| name.ends_with(".class"))
+			.map(|(key, name)| (key, name.to_owned()))
+			.collect();
+
+		let mut histogram: IndexMap<String, usize> = IndexMap::new();
+		for (key, name) in entries {
+			let from_entry_name = name.strip_suffix(".class")
+				.filter(|stem| !stem.starts_with("META-INF/"))
+				.filter(|stem| <&ClassNameSlice>::try_from(JavaStr::from_str(stem)).is_ok_and(|class_name| !class_name.is_array()));
+
+			let package = match from_entry_name {
+				Some(stem) => package_of(stem).to_owned(),
+				None => {
+					let JarEntryEnum::Class(class) = self.by_entry_key(key)?.to_jar_entry_enum()? else {
+						continue;
+					};
+					let header = read_header(class, &name)?;
+					package_of(&header.name.as_inner().to_string()).to_owned()
+				},
+			};
+
+			*histogram.entry(package).or_default() += 1;
+		}
+
+		histogram.sort_by(|_, a, _, b| b.cmp(a));
+
+		Ok(histogram)
+	}
+
+	/// Computes the classes in this jar that the `roots` (transitively) refer to.
+	///
+	/// Starting from the root classes, this follows the [referenced classes][duke::tree::class::ClassFile::referenced_classes]
+	/// that are present in this jar, until no new classes are found. The returned
+	/// [`classes`][TransitiveClosure::classes] include the roots. Referenced classes not present in this jar are collected
+	/// into the [`external`][TransitiveClosure::external] dependencies, and aren't followed further.
+	///
+	/// Returns an error if one of the roots isn't in this jar.
+	fn transitive_closure(&mut self, roots: &[ClassName]) -> Result<TransitiveClosure> {
+		let mut classes = IndexSet::new();
+		let mut external = IndexSet::new();
+
+		for root in roots {
+			if self.by_name(&format!("{root}.class"))?.is_none() {
+				bail!("root class {root} is not in the jar");
+			}
+			classes.insert(root.clone());
+		}
+
+		let mut index = 0;
+		while let Some(name) = classes.get_index(index).cloned() {
+			index += 1;
+
+			let entry_name = format!("{name}.class");
+			let entry = self.by_name(&entry_name)?
+				.with_context(|| anyhow!("class {name} is not in the jar"))?;
+			let JarEntryEnum::Class(class) = entry.to_jar_entry_enum()? else {
+				bail!("entry {entry_name:?} is not a class");
+			};
+			let class = class.read()
+				.with_context(|| anyhow!("while reading class entry {entry_name:?}"))?;
+
+			for referenced in class.referenced_classes()
+				.with_context(|| anyhow!("while collecting references of class {name}"))?
+			{
+				if classes.contains(&referenced) || external.contains(&referenced) {
+					continue;
+				}
+				if self.by_name(&format!("{referenced}.class"))?.is_some() {
+					classes.insert(referenced);
+				} else {
+					external.insert(referenced);
+				}
+			}
+		}
+
+		Ok(TransitiveClosure { classes, external })
+	}
+
+	/// Collects the field and method references of the classes in this jar whose owner class can't be found.
+	///
+	/// The owner class of a reference is found if it's in this jar, or in one of the `providers` (which can be created with
+	/// [`get_super_classes_provider`][OpenedJar::get_super_classes_provider] on the libraries). For references on array
+	/// classes (like `clone` on `[Ljava/lang/String;`), the class of the elements must be found instead. Note that classes of
+	/// the Java standard library are only found if they're in one of the providers as well.
+	///
+	/// See [`referenced_members`][duke::tree::class::ClassFile::referenced_members] for which references are collected. The
+	/// references are returned in the order they're found, without duplicates.
+	fn unresolved_references(&mut self, providers: &[JarSuperProv]) -> Result<Vec<MemberRef>> {
+		let present: HashSet<String> = self.names()
+			.filter_map(|(_, name)| name.strip_suffix(".class"))
+			.map(|name| name.to_owned())
+			.collect();
+
+		let is_found = |class: &ClassName| -> Result<bool> {
+			let class = if let Some(descriptor) = class.as_arr() {
+				match descriptor.parse()?.0 {
+					Type::Array(_, ArrayType::Object(class)) => class,
+					_ => return Ok(true),
+				}
+			} else {
+				class.clone()
+			};
+
+			Ok(present.contains(&class.to_string()) || providers.iter().any(|provider| provider.super_classes.contains_key(&class)))
+		};
+
+		let mut unresolved = IndexSet::new();
+
+		let keys = self.entry_keys();
+		for key in keys {
+			let entry = self.by_entry_key(key)?;
+			let name = entry.name().to_owned();
+
+			if let JarEntryEnum::Class(class) = entry.to_jar_entry_enum()? {
+				let class = class.read()
+					.with_context(|| anyhow!("while reading class entry {name:?}"))?;
+
+				for reference in class.referenced_members() {
+					if !unresolved.contains(&reference) && !is_found(reference.class())? {
+						unresolved.insert(reference);
+					}
+				}
+			}
+		}
+
+		Ok(unresolved.into_iter().collect())
+	}
+
 	fn get_super_classes_provider(&mut self) -> Result<JarSuperProv> {
 		struct MyJarSuperProv(JarSuperProv);
 		impl MultiClassVisitor for MyJarSuperProv {
@@ -75,3 +394,465 @@ pub trait OpenedJar {
 		Ok(self.read_classes_into(MyJarSuperProv(JarSuperProv { super_classes: IndexMap::new() }))?.0)
 	}
 }
+
+/// Reads only the [`ClassHeader`] of the class of the entry with the given name.
+fn read_header(class: impl IsClass, name: &str) -> Result<ClassHeader> {
+	struct HeaderReader(Option<ClassHeader>);
+	impl MultiClassVisitor for HeaderReader {
+		type ClassVisitor = Infallible;
+		type ClassResidual = Infallible;
+
+		fn visit_class(mut self, version: Version, access: ClassAccess, name: ClassName, super_class: Option<ClassName>, interfaces: Vec<ClassName>)
+			-> Result<ControlFlow<Self, (Self::ClassResidual, Self::ClassVisitor)>>
+		{
+			self.0 = Some(ClassHeader { version, access, name, super_class, interfaces });
+			Ok(ControlFlow::Break(self))
+		}
+
+		fn finish_class(_this: Self::ClassResidual, _class_visitor: Self::ClassVisitor) -> Result<Self> {
+			unreachable!()
+		}
+	}
+
+	class.visit(HeaderReader(None))
+		.with_context(|| anyhow!("while reading the header of class entry {name:?}"))?
+		.0.with_context(|| anyhow!("no header read for class entry {name:?}"))
+}
+
+/// The result of [`OpenedJar::transitive_closure`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransitiveClosure {
+	/// The classes present in the jar, including the roots, in the order they were found.
+	pub classes: IndexSet<ClassName>,
+	/// The classes referenced, but not present in the jar, in the order they were found.
+	pub external: IndexSet<ClassName>,
+}
+
+#[cfg(test)]
+mod testing {
+	use std::cell::Cell;
+	use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+	use anyhow::{Context, Result};
+	use indexmap::{IndexMap, IndexSet};
+	use pretty_assertions::assert_eq;
+	use java_string::JavaStr;
+	use duke::tree::class::{ClassAccess, ClassFile, ClassName};
+	use duke::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName, MethodRef};
+	use duke::tree::method::code::{Code, Instruction, InstructionListEntry};
+	use duke::tree::references::MemberRef;
+	use duke::tree::version::Version;
+	use duke::visitor::MultiClassVisitor;
+	use quill::remapper::JarSuperProv;
+	use zip::ZipWriter;
+	use zip::write::SimpleFileOptions;
+	use crate::storage::{BasicFileAttributes, ClassRepr, FileJar, IsClass, Jar, JarEntry, JarEntryEnum, OpenedJar, ParsedJar, ParsedJarEntry, UnnamedMemJar};
+
+	fn class_entry(name: &str) -> (String, ParsedJarEntry<ClassRepr, Vec<u8>>) {
+		// SAFETY: all of the class names below are valid class names.
+		let class_name = unsafe { ClassName::from_inner_unchecked(name.into()) };
+		let class = ClassFile::new(Version::V1_8, ClassAccess::default(), class_name, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		ParsedJarEntry::from_class(class)
+	}
+
+	#[test]
+	fn by_name_for_release() -> Result<()> {
+		let other_entry = |name: &str| (name.to_owned(), ParsedJarEntry {
+			attr: BasicFileAttributes::default(),
+			content: JarEntryEnum::Other(name.as_bytes().to_vec()),
+		});
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				other_entry("org/example/Foo.class"),
+				other_entry("META-INF/versions/11/org/example/Foo.class"),
+				other_entry("META-INF/versions/17/org/example/Foo.class"),
+				other_entry("META-INF/versions/11/org/example/Bar.class"),
+			]),
+		};
+
+		let name_for = |name: &str, release: u16| -> Result<Option<String>> {
+			Ok((&jar).by_name_for_release(name, release)?.map(|entry| entry.name().to_owned()))
+		};
+
+		assert_eq!(name_for("org/example/Foo.class", 8)?.as_deref(), Some("org/example/Foo.class"));
+		assert_eq!(name_for("org/example/Foo.class", 11)?.as_deref(), Some("META-INF/versions/11/org/example/Foo.class"));
+		assert_eq!(name_for("org/example/Foo.class", 16)?.as_deref(), Some("META-INF/versions/11/org/example/Foo.class"));
+		assert_eq!(name_for("org/example/Foo.class", 21)?.as_deref(), Some("META-INF/versions/17/org/example/Foo.class"));
+		// there's no base entry
+		assert_eq!(name_for("org/example/Bar.class", 8)?, None);
+		assert_eq!(name_for("org/example/Bar.class", 11)?.as_deref(), Some("META-INF/versions/11/org/example/Bar.class"));
+		assert_eq!(name_for("org/example/Baz.class", 11)?, None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn no_classes() -> Result<()> {
+		let mut zip_out = ZipWriter::new(Cursor::new(Vec::new()));
+		zip_out.add_directory("META-INF/", SimpleFileOptions::default())?;
+		zip_out.start_file("META-INF/MANIFEST.MF", SimpleFileOptions::default())?;
+		zip_out.write_all(b"Manifest-Version: 1.0\r\n")?;
+		let jar = UnnamedMemJar { data: zip_out.finish()?.into_inner() };
+
+		let mut opened = jar.open()?;
+		assert!(!opened.is_empty());
+		assert!(!opened.has_classes());
+
+		let (_, entry) = class_entry("org/example/A");
+		let JarEntryEnum::Class(class) = entry.content else {
+			panic!("`class_entry` should create a class entry");
+		};
+		let visitor = vec![class.read()?];
+		assert_eq!(opened.read_classes_into(visitor.clone())?, visitor);
+		assert_eq!(opened.read_classes_in_package_into("", visitor.clone())?, visitor);
+		let (lenient, errors) = opened.read_classes_into_lenient(visitor.clone())?;
+		assert_eq!(lenient, visitor);
+		assert!(errors.is_empty());
+
+		assert_eq!(opened.find_class(|_| true)?, None);
+		assert!(opened.unresolved_references(&[])?.is_empty());
+		assert!(opened.get_super_classes_provider()?.super_classes.is_empty());
+		assert!(opened.transitive_closure(&[])?.classes.is_empty());
+
+		// a zip archive without any entries
+		let jar = UnnamedMemJar { data: ZipWriter::new(Cursor::new(Vec::new())).finish()?.into_inner() };
+		let mut opened = jar.open()?;
+		assert!(opened.is_empty());
+		assert!(!opened.has_classes());
+		assert_eq!(opened.read_classes_into(visitor.clone())?, visitor);
+		assert!(opened.get_super_classes_provider()?.super_classes.is_empty());
+
+		// zero bytes aren't a zip archive, no matter where they're stored
+		let error = UnnamedMemJar { data: Vec::new() }.open().err().context("expected an error")?;
+		assert!(format!("{error:#}").contains("zero bytes"), "{error:#}");
+
+		let file = tempfile::NamedTempFile::new()?;
+		let error = FileJar { path: file.path().to_owned() }.open().err().context("expected an error")?;
+		assert!(format!("{error:#}").contains("zero bytes"), "{error:#}");
+
+		Ok(())
+	}
+
+	#[test]
+	fn read_bytes_by_name() -> Result<()> {
+		let mut zip_out = ZipWriter::new(Cursor::new(Vec::new()));
+		zip_out.add_directory("META-INF/", SimpleFileOptions::default())?;
+		zip_out.start_file("META-INF/MANIFEST.MF", SimpleFileOptions::default())?;
+		zip_out.write_all(b"Manifest-Version: 1.0\r\n")?;
+		let jar = UnnamedMemJar { data: zip_out.finish()?.into_inner() };
+
+		let mut opened = jar.open()?;
+		assert_eq!(opened.read_bytes_by_name("META-INF/MANIFEST.MF")?.as_deref(), Some(b"Manifest-Version: 1.0\r\n".as_slice()));
+		assert_eq!(opened.read_bytes_by_name("META-INF/")?, Some(Vec::new()));
+		assert_eq!(opened.read_bytes_by_name("META-INF/MANIFEST")?, None);
+
+		// classes are returned as their bytes too
+		let (name, entry) = class_entry("org/example/A");
+		let JarEntryEnum::Class(class) = &entry.content else {
+			panic!("`class_entry` should create a class entry");
+		};
+		let expected = class.write()?.as_ref().to_vec();
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([(name, entry)]),
+		};
+		assert_eq!((&jar).read_bytes_by_name("org/example/A.class")?, Some(expected));
+
+		Ok(())
+	}
+
+	#[test]
+	fn read_classes_in_package_into() -> Result<()> {
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				class_entry("org/example/A"),
+				class_entry("org/examples/B"),
+				class_entry("org/example/sub/C"),
+				class_entry("org/other/D"),
+				("org/example/data.txt".to_owned(), ParsedJarEntry {
+					attr: BasicFileAttributes::default(),
+					content: JarEntryEnum::Other(Vec::new()),
+				}),
+			]),
+		};
+
+		let names = |prefix: &str| -> Result<Vec<String>> {
+			let classes: Vec<ClassFile> = (&jar).read_classes_in_package_into(prefix, Vec::new())?;
+			Ok(classes.into_iter().map(|class| class.name.to_string()).collect())
+		};
+
+		assert_eq!(names("org/example")?, ["org/example/A", "org/example/sub/C"]);
+		assert_eq!(names("org/example/")?, ["org/example/A", "org/example/sub/C"]);
+		assert_eq!(names("org/other")?, ["org/other/D"]);
+		assert_eq!(names("org/exam")?, Vec::<String>::new());
+		assert_eq!(names("")?, ["org/example/A", "org/examples/B", "org/example/sub/C", "org/other/D"]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn package_histogram() -> Result<()> {
+		// the name of this entry is not a class name, so the package comes from the class itself
+		let (_, odd_entry) = class_entry("org/other/E");
+
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				class_entry("org/other/A"),
+				class_entry("org/example/B"),
+				class_entry("org/example/C"),
+				class_entry("org/example/sub/D"),
+				class_entry("org/example/F"),
+				("org/other/E.obf.class".to_owned(), odd_entry),
+				class_entry("Main"),
+				("org/example/data.txt".to_owned(), ParsedJarEntry {
+					attr: BasicFileAttributes::default(),
+					content: JarEntryEnum::Other(Vec::new()),
+				}),
+			]),
+		};
+
+		let histogram = (&jar).package_histogram()?;
+		let histogram: Vec<_> = histogram.iter().map(|(package, count)| (package.as_str(), *count)).collect();
+		assert_eq!(histogram, [("org/example", 3), ("org/other", 2), ("org/example/sub", 1), ("", 1)]);
+
+		Ok(())
+	}
+
+	fn truncated_jar() -> Result<ParsedJar<ClassRepr, Vec<u8>>> {
+		let (name, entry) = class_entry("org/example/Broken");
+		let JarEntryEnum::Class(class) = entry.content else {
+			panic!("`class_entry` should create a class entry");
+		};
+		let mut data = class.read()?.write()?;
+		data.truncate(data.len() / 2);
+
+		Ok(ParsedJar {
+			entries: IndexMap::from([
+				class_entry("org/example/A"),
+				(name, ParsedJarEntry {
+					attr: BasicFileAttributes::default(),
+					content: JarEntryEnum::Class(ClassRepr::Vec { data }),
+				}),
+				class_entry("org/example/B"),
+			]),
+		})
+	}
+
+	#[test]
+	fn read_classes_into_names_failing_entry() -> Result<()> {
+		let jar = truncated_jar()?;
+
+		let error = (&jar).read_classes_into(Vec::<ClassFile>::new()).err().context("expected an error")?;
+		assert_eq!(error.to_string(), "while reading class entry \"org/example/Broken.class\"");
+
+		Ok(())
+	}
+
+	#[test]
+	fn read_classes_into_lenient() -> Result<()> {
+		let jar = truncated_jar()?;
+
+		let (classes, errors) = (&jar).read_classes_into_lenient(Vec::<ClassFile>::new())?;
+
+		let names: Vec<String> = classes.into_iter().map(|class| class.name.to_string()).collect();
+		assert_eq!(names, ["org/example/A", "org/example/B"]);
+
+		let errors: Vec<&str> = errors.iter().map(|(name, _)| name.as_str()).collect();
+		assert_eq!(errors, ["org/example/Broken.class"]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn transitive_closure() -> Result<()> {
+		let class_extending = |name: &str, super_class: &str| -> Result<(String, ParsedJarEntry<ClassRepr, Vec<u8>>)> {
+			let (entry_name, mut entry) = class_entry(name);
+			let JarEntryEnum::Class(ClassRepr::Parsed { class }) = &mut entry.content else {
+				panic!("`class_entry` should create a class entry");
+			};
+			class.super_class = Some(ClassName::try_from(JavaStr::from_str(super_class))?);
+			Ok((entry_name, entry))
+		};
+
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				class_extending("org/example/A", "org/example/B")?,
+				class_extending("org/example/B", "org/example/C")?,
+				class_extending("org/example/C", "org/other/External")?,
+				class_entry("org/example/D"),
+			]),
+		};
+
+		let class_names = |names: &[&str]| -> Result<IndexSet<ClassName>> {
+			names.iter().map(|name| ClassName::try_from(JavaStr::from_str(name))).collect()
+		};
+
+		let closure = (&jar).transitive_closure(&[ClassName::try_from(JavaStr::from_str("org/example/A"))?])?;
+		assert_eq!(closure.classes, class_names(&["org/example/A", "org/example/B", "org/example/C"])?);
+		assert_eq!(closure.external, class_names(&["org/other/External"])?);
+
+		let closure = (&jar).transitive_closure(&[ClassName::try_from(JavaStr::from_str("org/example/D"))?])?;
+		assert_eq!(closure.classes, class_names(&["org/example/D"])?);
+		assert_eq!(closure.external, class_names(&["java/lang/Object"])?);
+
+		assert!((&jar).transitive_closure(&[ClassName::try_from(JavaStr::from_str("org/example/E"))?]).is_err());
+
+		Ok(())
+	}
+
+	/// A class that counts the bytes read from it.
+	struct CountingClass {
+		data: Vec<u8>,
+		bytes_read: Cell<usize>,
+	}
+
+	impl CountingClass {
+		fn reader(&self) -> CountingReader<'_> {
+			CountingReader { inner: Cursor::new(&self.data), bytes_read: &self.bytes_read }
+		}
+
+		/// Whether the class was fully read at least once. Bytes skipped over aren't counted, so reading only the header
+		/// reads less than all the bytes.
+		fn fully_read(&self) -> bool {
+			self.bytes_read.get() >= self.data.len()
+		}
+	}
+
+	/// Counts the bytes read, but not the ones skipped over by seeking.
+	struct CountingReader<'a> {
+		inner: Cursor<&'a Vec<u8>>,
+		bytes_read: &'a Cell<usize>,
+	}
+
+	impl Read for CountingReader<'_> {
+		fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+			let n = self.inner.read(buf)?;
+			self.bytes_read.set(self.bytes_read.get() + n);
+			Ok(n)
+		}
+	}
+
+	impl Seek for CountingReader<'_> {
+		fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+			self.inner.seek(pos)
+		}
+	}
+
+	impl IsClass for &CountingClass {
+		fn read(self) -> Result<ClassFile> {
+			duke::read_class(&mut self.reader())
+		}
+
+		fn visit<M: MultiClassVisitor>(self, visitor: M) -> Result<M> {
+			duke::read_class_multi(&mut self.reader(), visitor)
+		}
+
+		type Written<'a> = &'a [u8] where Self: 'a;
+		fn write(&self) -> Result<Self::Written<'_>> {
+			Ok(&self.data)
+		}
+
+		fn into_class_repr(self) -> ClassRepr {
+			ClassRepr::Vec { data: self.data.clone() }
+		}
+	}
+
+	#[test]
+	fn find_class() -> Result<()> {
+		let counting_entry = |name: &str| -> Result<(String, ParsedJarEntry<CountingClass, Vec<u8>>)> {
+			let (entry_name, entry) = class_entry(name);
+			let JarEntryEnum::Class(class) = entry.content else {
+				panic!("class_entry should give a class entry, got {:?}", entry.content);
+			};
+			let mut class = class.read()?;
+			// a method, so that there's more to read after the header
+			class.methods.push(Method::new(MethodAccess::from(0x0009), MethodName::try_from(JavaStr::from_str("run"))?, MethodDescriptor::try_from(JavaStr::from_str("()V"))?));
+			let data = ClassRepr::Parsed { class }.write()?.into_owned();
+			Ok((entry_name, ParsedJarEntry {
+				attr: BasicFileAttributes::default(),
+				content: JarEntryEnum::Class(CountingClass { data, bytes_read: Cell::new(0) }),
+			}))
+		};
+
+		let jar: ParsedJar<CountingClass, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				counting_entry("org/example/A")?,
+				counting_entry("org/example/B")?,
+				counting_entry("org/example/C")?,
+			]),
+		};
+		let classes = || jar.entries.values().map(|entry| match &entry.content {
+			JarEntryEnum::Class(class) => class,
+			content => panic!("expected only class entries, got {content:?}"),
+		});
+
+		let class = (&jar).find_class(|header| header.name.as_inner() == "org/example/B")?;
+		assert_eq!(class.context("no class found")?.name.as_inner(), "org/example/B");
+
+		// only the header of `A` is read, and `C` isn't looked at
+		let [a, b, c] = [0, 1, 2].map(|index| classes().nth(index).unwrap_or_else(|| panic!("no class {index}")));
+		assert!(a.bytes_read.get() > 0 && !a.fully_read());
+		assert!(b.fully_read());
+		assert_eq!(c.bytes_read.get(), 0);
+
+		for class in classes() {
+			class.bytes_read.set(0);
+		}
+
+		let class = (&jar).find_class(|header| header.name.as_inner() == "org/example/D")?;
+		assert_eq!(class, None);
+		assert!(classes().all(|class| class.bytes_read.get() > 0 && !class.fully_read()));
+
+		Ok(())
+	}
+
+	#[test]
+	fn unresolved_references() -> Result<()> {
+		let method_ref = |class: &str, name: &str, desc: &str| -> Result<MethodRef> {
+			Ok(MethodRef {
+				class: ClassName::try_from(JavaStr::from_str(class))?,
+				name: MethodName::try_from(JavaStr::from_str(name))?,
+				desc: MethodDescriptor::try_from(JavaStr::from_str(desc))?,
+			})
+		};
+
+		let (entry_name, mut entry) = class_entry("org/example/A");
+		let JarEntryEnum::Class(ClassRepr::Parsed { class }) = &mut entry.content else {
+			panic!("`class_entry` should create a class entry");
+		};
+		let mut method = Method::new(MethodAccess::from(0x0009), MethodName::try_from(JavaStr::from_str("run"))?, MethodDescriptor::try_from(JavaStr::from_str("()V"))?);
+		method.code = Some(Code {
+			instructions: [
+				method_ref("org/example/B", "run", "()V")?,
+				method_ref("org/other/Missing", "run", "()V")?,
+				method_ref("java/lang/Object", "hashCode", "()I")?,
+				method_ref("[Lorg/other/Missing;", "clone", "()Ljava/lang/Object;")?,
+				method_ref("[I", "clone", "()Ljava/lang/Object;")?,
+			].into_iter()
+				.map(|method_ref| InstructionListEntry { label: None, frame: None, offset: None, instruction: Instruction::InvokeStatic(method_ref, false) })
+				.collect(),
+			..Code::default()
+		});
+		class.methods.push(method);
+
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				(entry_name, entry),
+				class_entry("org/example/B"),
+			]),
+		};
+
+		let java_lang_object = JarSuperProv {
+			super_classes: IndexMap::from([(ClassName::JAVA_LANG_OBJECT.to_owned(), IndexSet::new())]),
+		};
+
+		let unresolved = (&jar).unresolved_references(&[java_lang_object])?;
+		assert_eq!(unresolved, [
+			MemberRef::Method(method_ref("[Lorg/other/Missing;", "clone", "()Ljava/lang/Object;")?),
+			MemberRef::Method(method_ref("org/other/Missing", "run", "()V")?),
+		]);
+
+		// without any providers, `java/lang/Object` can't be found either
+		assert_eq!((&jar).unresolved_references(&[])?.len(), 3);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,363 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{Seek, Write};
+use std::path::Path;
+use anyhow::{anyhow, Context, Result};
+use indexmap::IndexMap;
+use zip::ZipWriter;
+use duke::tree::class::ClassFile;
+use duke::visitor::MultiClassVisitor;
+use crate::storage::{BasicFileAttributes, ClassRepr, IsClass, IsOther, Jar, JarEntry, JarEntryEnum, OpenedJar, ParsedJarEntry};
+
+/// A jar that applies some changes over another jar, without copying it.
+///
+/// The [`overrides`][OverlayJar::overrides] map from entry names to the new entry. An entry name present in the base
+/// jar replaces that entry (keeping its position), an entry name not present in the base jar is added after all the
+/// entries of the base jar. An override of [`None`] removes the entry from the base jar.
+///
+/// Reading an entry that's not overridden reads it from the base jar. This also applies when writing the jar: the entries
+/// of the base jar are only read one by one, while writing them.
+#[derive(Debug)]
+pub struct OverlayJar<J> {
+	pub base: J,
+	pub overrides: IndexMap<String, Option<ParsedJarEntry<ClassRepr, Vec<u8>>>>,
+}
+
+impl<J> OverlayJar<J> {
+	/// Creates an overlay jar without any changes to the base jar.
+	pub fn new(base: J) -> OverlayJar<J> {
+		OverlayJar { base, overrides: IndexMap::new() }
+	}
+
+	/// Replaces or adds the entry with the given name.
+	pub fn put(&mut self, name: impl Into<String>, entry: ParsedJarEntry<ClassRepr, Vec<u8>>) {
+		self.overrides.insert(name.into(), Some(entry));
+	}
+
+	/// Replaces or adds the entry for the class, named after the class name.
+	pub fn put_class(&mut self, class: ClassFile) {
+		let (name, entry) = ParsedJarEntry::from_class(class);
+		self.put(name, entry);
+	}
+
+	/// Removes the entry with the given name.
+	pub fn remove(&mut self, name: impl Into<String>) {
+		self.overrides.insert(name.into(), None);
+	}
+}
+
+impl<J: Jar> OverlayJar<J> {
+	fn write<W: Write + Seek>(&self, writer: W) -> Result<W> {
+		let mut jar = self.open()?;
+		let mut zip_out = ZipWriter::new(writer);
+
+		for key in jar.entry_keys() {
+			let entry = jar.by_entry_key(key)?;
+			let name = entry.name().to_owned();
+			let attr = entry.attrs();
+
+			use JarEntryEnum::*;
+			match entry.to_jar_entry_enum().with_context(|| anyhow!("failed to read entry {name:?}"))? {
+				Dir => zip_out.add_directory(name.as_str(), attr.to_file_options())?,
+				Class(class) => {
+					zip_out.start_file(name.as_str(), attr.to_file_options())?;
+					class.write_to(&mut zip_out)
+						.with_context(|| anyhow!("failed to write class entry {name:?}"))?;
+				},
+				Other(data) => {
+					zip_out.start_file(name.as_str(), attr.to_file_options())?;
+					zip_out.write_all(data.get_data())?;
+				},
+			}
+		}
+
+		Ok(zip_out.finish()?)
+	}
+}
+
+impl<J: Jar> Jar for OverlayJar<J> {
+	type Opened<'a> = OpenedOverlayJar<'a, J::Opened<'a>> where Self: 'a;
+
+	fn open(&self) -> Result<Self::Opened<'_>> {
+		let base = self.base.open()?;
+
+		let mut keys = Vec::new();
+		let mut replaced = vec![false; self.overrides.len()];
+		for (key, name) in base.names() {
+			match self.overrides.get_full(name) {
+				None => keys.push(OverlayEntryKey::Base(key)),
+				Some((index, _, entry)) => {
+					replaced[index] = true;
+					if entry.is_some() {
+						keys.push(OverlayEntryKey::Override(index));
+					}
+				},
+			}
+		}
+		let base_count = keys.len();
+		for (index, entry) in self.overrides.values().enumerate() {
+			if entry.is_some() && !replaced[index] {
+				keys.push(OverlayEntryKey::Override(index));
+			}
+		}
+
+		Ok(OpenedOverlayJar { base, overrides: &self.overrides, keys, base_count })
+	}
+
+	fn put_to_file<'a>(&'a self, suggested: &'a Path) -> Result<&'a Path> {
+		let writer = File::create(suggested)
+			.with_context(|| anyhow!("failed to open {suggested:?} for writing overlay jar"))?;
+
+		self.write(writer)
+			.with_context(|| anyhow!("failed to write overlay jar to {suggested:?}"))?;
+
+		Ok(suggested)
+	}
+}
+
+/// An opened [`OverlayJar`].
+///
+/// The entry keys are indices into the list of all entries, with the entries of the base jar first.
+pub struct OpenedOverlayJar<'a, O: OpenedJar> {
+	base: O,
+	overrides: &'a IndexMap<String, Option<ParsedJarEntry<ClassRepr, Vec<u8>>>>,
+	/// Where to find each of the entries, in order.
+	keys: Vec<OverlayEntryKey<O::EntryKey>>,
+	/// The number of entries at the start of `keys` that are at the position of an entry of the base jar.
+	base_count: usize,
+}
+
+#[derive(Clone, Copy)]
+enum OverlayEntryKey<K> {
+	/// An entry of the base jar, given by its key.
+	Base(K),
+	/// An entry of the overrides, given by its index.
+	Override(usize),
+}
+
+impl<'a, O: OpenedJar> OpenedJar for OpenedOverlayJar<'a, O> {
+	type EntryKey = usize;
+
+	type Entry<'b> = OverlayEntry<'b, O::Entry<'b>> where Self: 'b;
+
+	fn entry_keys(&self) -> impl Iterator<Item=Self::EntryKey> + 'static {
+		0..self.keys.len()
+	}
+
+	fn by_entry_key(&mut self, key: Self::EntryKey) -> Result<Self::Entry<'_>> {
+		match self.keys.get(key) {
+			Some(&OverlayEntryKey::Base(key)) => Ok(OverlayEntry::Base(self.base.by_entry_key(key)?)),
+			Some(&OverlayEntryKey::Override(index)) => match self.overrides.get_index(index) {
+				Some((name, Some(entry))) => Ok(OverlayEntry::Override(name, entry)),
+				_ => Err(anyhow!("no override for index {index:?}")),
+			},
+			None => Err(anyhow!("no entry for index {key:?}")),
+		}
+	}
+
+	fn names(&self) -> impl Iterator<Item=(Self::EntryKey, &'_ str)> {
+		// This visits the entries in the same order as `OverlayJar::open` does to build the `keys`.
+		let overrides = self.overrides;
+		let base = self.base.names()
+			.filter_map(move |(_, name)| match overrides.get(name) {
+				Some(None) => None,
+				_ => Some(name),
+			});
+		let added = self.keys[self.base_count..].iter()
+			.filter_map(move |key| match *key {
+				OverlayEntryKey::Override(index) => overrides.get_index(index).map(|(name, _)| name.as_str()),
+				OverlayEntryKey::Base(_) => None,
+			});
+		base.chain(added).enumerate()
+	}
+
+	fn by_name(&mut self, name: &str) -> Result<Option<Self::Entry<'_>>> {
+		match self.overrides.get_key_value(name) {
+			Some((name, Some(entry))) => Ok(Some(OverlayEntry::Override(name, entry))),
+			Some((_, None)) => Ok(None),
+			None => Ok(self.base.by_name(name)?.map(OverlayEntry::Base)),
+		}
+	}
+}
+
+/// The [`Entry`][OpenedJar::Entry] of an [`OpenedOverlayJar`].
+pub enum OverlayEntry<'a, E> {
+	Base(E),
+	Override(&'a str, &'a ParsedJarEntry<ClassRepr, Vec<u8>>),
+}
+
+impl<'a, E: JarEntry> JarEntry for OverlayEntry<'a, E> {
+	fn name(&self) -> &str {
+		match self {
+			OverlayEntry::Base(entry) => entry.name(),
+			OverlayEntry::Override(name, _) => name,
+		}
+	}
+
+	fn attrs(&self) -> BasicFileAttributes {
+		match self {
+			OverlayEntry::Base(entry) => entry.attrs(),
+			OverlayEntry::Override(_, entry) => entry.attr,
+		}
+	}
+
+	type Class = OverlayClass<'a, E::Class>;
+	type Other = OverlayOther<'a, E::Other>;
+	fn to_jar_entry_enum(self) -> Result<JarEntryEnum<Self::Class, Self::Other>> {
+		use JarEntryEnum::*;
+		Ok(match self {
+			OverlayEntry::Base(entry) => match entry.to_jar_entry_enum()? {
+				Dir => Dir,
+				Class(class) => Class(OverlayClass::Base(class)),
+				Other(other) => Other(OverlayOther::Base(other)),
+			},
+			OverlayEntry::Override(_, entry) => match &entry.content {
+				Dir => Dir,
+				Class(class) => Class(OverlayClass::Override(class)),
+				Other(other) => Other(OverlayOther::Override(other)),
+			},
+		})
+	}
+}
+
+/// A class entry of an [`OpenedOverlayJar`].
+pub enum OverlayClass<'a, C> {
+	Base(C),
+	Override(&'a ClassRepr),
+}
+
+impl<C: IsClass> IsClass for OverlayClass<'_, C> {
+	fn read(self) -> Result<ClassFile> {
+		match self {
+			OverlayClass::Base(class) => class.read(),
+			OverlayClass::Override(class) => class.read(),
+		}
+	}
+
+	fn visit<M: MultiClassVisitor>(self, visitor: M) -> Result<M> {
+		match self {
+			OverlayClass::Base(class) => class.visit(visitor),
+			OverlayClass::Override(class) => class.visit(visitor),
+		}
+	}
+
+	type Written<'b> = Cow<'b, [u8]> where Self: 'b;
+	fn write(&self) -> Result<Self::Written<'_>> {
+		match self {
+			OverlayClass::Base(class) => Ok(Cow::Owned(class.write()?.as_ref().to_vec())),
+			OverlayClass::Override(class) => IsClass::write(class),
+		}
+	}
+
+	fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+		match self {
+			OverlayClass::Base(class) => class.write_to(writer),
+			OverlayClass::Override(class) => IsClass::write_to(class, writer),
+		}
+	}
+
+	fn into_class_repr(self) -> ClassRepr {
+		match self {
+			OverlayClass::Base(class) => class.into_class_repr(),
+			OverlayClass::Override(class) => class.clone(),
+		}
+	}
+}
+
+/// A non-class entry of an [`OpenedOverlayJar`].
+pub enum OverlayOther<'a, O> {
+	Base(O),
+	Override(&'a Vec<u8>),
+}
+
+impl<O: IsOther> IsOther for OverlayOther<'_, O> {
+	fn get_data(&self) -> &[u8] {
+		match self {
+			OverlayOther::Base(other) => other.get_data(),
+			OverlayOther::Override(other) => other,
+		}
+	}
+
+	fn get_data_owned(self) -> Vec<u8> {
+		match self {
+			OverlayOther::Base(other) => other.get_data_owned(),
+			OverlayOther::Override(other) => other.clone(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::{Context, Result};
+	use indexmap::IndexMap;
+	use java_string::JavaStr;
+	use pretty_assertions::assert_eq;
+	use duke::tree::class::{ClassAccess, ClassFile, ClassName};
+	use duke::tree::version::Version;
+	use crate::storage::{ClassRepr, IsClass, Jar, JarEntry, JarEntryEnum, OpenedJar, OverlayJar, ParsedJar, ParsedJarEntry, UnnamedMemJar};
+
+	fn class(name: &str, version: Version) -> Result<ClassFile> {
+		let name = ClassName::try_from(JavaStr::from_str(name))?;
+		Ok(ClassFile::new(version, ClassAccess::default(), name, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new()))
+	}
+
+	fn versions(jar: &impl Jar) -> Result<Vec<(String, Version)>> {
+		let classes: Vec<ClassFile> = jar.open()?.read_classes_into(Vec::new())?;
+		Ok(classes.into_iter().map(|class| (class.name.to_string(), class.version)).collect())
+	}
+
+	#[test]
+	fn overlay() -> Result<()> {
+		let base: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				ParsedJarEntry::from_class(class("org/example/A", Version::V1_8)?),
+				ParsedJarEntry::from_class(class("org/example/B", Version::V1_8)?),
+			]),
+		};
+
+		let mut jar = OverlayJar::new(base);
+		jar.put_class(class("org/example/A", Version::V17)?);
+
+		let expected = [("org/example/A".to_owned(), Version::V17), ("org/example/B".to_owned(), Version::V1_8)];
+		assert_eq!(versions(&jar)?, expected);
+
+		let names: Vec<(usize, String)> = jar.open()?.names().map(|(key, name)| (key, name.to_owned())).collect();
+		assert_eq!(names, [(0, "org/example/A.class".to_owned()), (1, "org/example/B.class".to_owned())]);
+
+		let mut opened = jar.open()?;
+		let entry = opened.by_name("org/example/B.class")?.context("no entry for B")?;
+		let JarEntryEnum::Class(b) = entry.to_jar_entry_enum()? else { panic!("B isn't a class") };
+		assert_eq!(b.read()?.version, Version::V1_8);
+
+		// the base jar is unchanged
+		assert_eq!(versions(&jar.base)?[0], ("org/example/A".to_owned(), Version::V1_8));
+
+		let data = jar.write(Cursor::new(Vec::new()))?.into_inner();
+		assert_eq!(versions(&UnnamedMemJar { data })?, expected);
+
+		Ok(())
+	}
+
+	#[test]
+	fn overlay_add_and_remove() -> Result<()> {
+		let base: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				ParsedJarEntry::from_class(class("org/example/A", Version::V1_8)?),
+				ParsedJarEntry::from_class(class("org/example/B", Version::V1_8)?),
+			]),
+		};
+
+		let mut jar = OverlayJar::new(base);
+		jar.put_class(class("org/example/C", Version::V17)?);
+		jar.remove("org/example/A.class");
+
+		assert_eq!(versions(&jar)?, [("org/example/B".to_owned(), Version::V1_8), ("org/example/C".to_owned(), Version::V17)]);
+		assert!(jar.open()?.by_name("org/example/A.class")?.is_none());
+
+		let names: Vec<(usize, String)> = jar.open()?.names().map(|(key, name)| (key, name.to_owned())).collect();
+		assert_eq!(names, [(0, "org/example/B.class".to_owned()), (1, "org/example/C.class".to_owned())]);
+
+		Ok(())
+	}
+}
@@ -1,8 +1,9 @@
 use std::fs::File;
 use std::io::{Cursor, Seek, Write};
 use std::path::Path;
-use anyhow::{anyhow, Context, Result};
-use indexmap::IndexMap;
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::{IndexMap, IndexSet};
+use duke::tree::class::{ClassFile, ClassNameSlice};
 use zip::write::FileOptions;
 use zip::{DateTime, ZipWriter};
 use crate::storage::{BasicFileAttributes, ClassRepr, IsClass, IsOther, Jar, JarEntry, JarEntryEnum, OpenedJar, UnnamedMemJar};
@@ -111,17 +112,34 @@ impl<Class, Other> ParsedJar<Class, Other>
 	}
 
 	fn write<W: Write + Seek>(&self, writer: W) -> Result<W> {
+		Self::write_entries(self.entries.iter(), writer)
+	}
+
+	/// Writes the jar as a zip file, with the entries in the order of [`entries_sorted`][ParsedJar::entries_sorted].
+	///
+	/// Unlike writing the entries in the order they were inserted in, this gives the same zip file for the same entries,
+	/// no matter how the jar was constructed.
+	pub fn write_sorted<W: Write + Seek>(&self, writer: W) -> Result<W> {
+		Self::write_entries(self.entries_sorted(), writer)
+	}
+
+	fn write_entries<'a, W: Write + Seek>(
+		entries: impl Iterator<Item=(&'a String, &'a ParsedJarEntry<Class, Other>)>,
+		writer: W,
+	) -> Result<W>
+	where
+		Class: 'a,
+		Other: 'a,
+	{
 		let mut zip_out = ZipWriter::new(writer);
 
-		for (name, entry) in &self.entries {
+		for (name, entry) in entries {
 			use JarEntryEnum::*;
 			match &entry.content {
 				Dir => zip_out.add_directory(name.as_str(), entry.attr.to_file_options())?,
 				Class(class) => {
-					let data = class.write()?;
-
 					zip_out.start_file(name.as_str(), entry.attr.to_file_options())?;
-					zip_out.write_all(data.as_ref())?;
+					class.write_to(&mut zip_out)?;
 				},
 				Other(data) => {
 					zip_out.start_file(name.as_str(), entry.attr.to_file_options())?;
@@ -141,12 +159,112 @@ impl<Class, Other> ParsedJar<Class, Other>
 	}
 }
 
+impl<Class, Other> ParsedJar<Class, Other> {
+	/// Generates the contents of a `META-INF/INDEX.LIST` for this jar, using `jar_name` as the name of the jar.
+	///
+	/// The index has a single section for this jar, listing the package (directory) of each class or other file, in the
+	/// order they first appear in the jar. Files at the root of the jar have no package, and are listed by their name
+	/// instead. Entries in `META-INF` are not listed.
+	///
+	/// ```text
+	/// JarIndex-Version: 1.0
+	///
+	/// example.jar
+	/// org/example
+	/// org/example/sub
+	///
+	/// ```
+	pub fn index_list(&self, jar_name: &str) -> String {
+		let packages: IndexSet<&str> = self.entries.iter()
+			.filter(|(_, entry)| !matches!(entry.content, JarEntryEnum::Dir))
+			.map(|(name, _)| name.rsplit_once('/').map_or(name.as_str(), |(package, _)| package))
+			.filter(|package| *package != "META-INF" && !package.starts_with("META-INF/"))
+			.collect();
+
+		let mut index = format!("JarIndex-Version: 1.0\n\n{jar_name}\n");
+		for package in packages {
+			index.push_str(package);
+			index.push('\n');
+		}
+		index.push('\n');
+		index
+	}
+
+	/// Iterates over the entries in a defined order, independent of the order they were inserted in.
+	///
+	/// Directories come first, followed by all other entries. Both are sorted lexically by their name.
+	pub fn entries_sorted(&self) -> impl Iterator<Item=(&String, &ParsedJarEntry<Class, Other>)> {
+		let mut entries: Vec<_> = self.entries.iter().collect();
+		entries.sort_by(|(a_name, a), (b_name, b)| {
+			let a_is_dir = matches!(a.content, JarEntryEnum::Dir);
+			let b_is_dir = matches!(b.content, JarEntryEnum::Dir);
+			b_is_dir.cmp(&a_is_dir).then_with(|| a_name.cmp(b_name))
+		});
+		entries.into_iter()
+	}
+}
+
+impl<Other> ParsedJar<ClassRepr, Other> {
+	/// Renames the class `from` to `to`.
+	///
+	/// This renames the jar entry of the class (keeping its position in the jar), and changes the name of the
+	/// class itself, so that the entry name and the class name stay consistent.
+	///
+	/// Note that references to the class from other classes (or from the class itself, other than its own name)
+	/// are not changed. For that, use a separate remapping step, like [`remap`][crate::remap::remap].
+	///
+	/// Fails if there's no class entry for `from`, or if there's already an entry for `to`. If renaming fails, the jar
+	/// is left unchanged, except that the class of `from` might have been parsed.
+	pub fn rename_class(&mut self, from: &ClassNameSlice, to: &ClassNameSlice) -> Result<()> {
+		let from_name = format!("{from}.class");
+		let to_name = format!("{to}.class");
+
+		if self.entries.contains_key(&to_name) {
+			bail!("cannot rename class {from:?} to {to:?}: there's already an entry {to_name:?}");
+		}
+
+		let Some(entry) = self.entries.get_mut(&from_name) else {
+			bail!("cannot rename class {from:?} to {to:?}: there's no entry {from_name:?}");
+		};
+		let JarEntryEnum::Class(class) = &mut entry.content else {
+			bail!("cannot rename class {from:?} to {to:?}: entry isn't a class, but {:?}", entry.content);
+		};
+
+		// Parse the class before touching the entries, so that a failure doesn't remove it from the jar.
+		if let ClassRepr::Vec { data } = class {
+			let parsed = duke::read_class(&mut Cursor::new(data))
+				.with_context(|| anyhow!("failed to read class {from:?} for renaming it to {to:?}"))?;
+			*class = ClassRepr::Parsed { class: parsed };
+		}
+		if let ClassRepr::Parsed { class } = class {
+			class.name = to.to_owned();
+		}
+
+		if let Some((index, _, entry)) = self.entries.shift_remove_full(&from_name) {
+			self.entries.shift_insert(index, to_name, entry);
+		}
+		Ok(())
+	}
+}
+
 #[derive(Debug)]
 pub struct ParsedJarEntry<Class, Other> {
 	pub attr: BasicFileAttributes,
 	pub content: JarEntryEnum<Class, Other>,
 }
 
+impl<Other> ParsedJarEntry<ClassRepr, Other> {
+	/// Creates an entry for the class, with default attributes.
+	///
+	/// Returns the entry together with its name, the class name followed by `.class`.
+	pub fn from_class(class: ClassFile) -> (String, ParsedJarEntry<ClassRepr, Other>) {
+		(format!("{}.class", class.name), ParsedJarEntry {
+			attr: BasicFileAttributes::default(),
+			content: JarEntryEnum::Class(ClassRepr::Parsed { class }),
+		})
+	}
+}
+
 impl<'name, 'entry, Class, Other> JarEntry for (&'name String, &'entry ParsedJarEntry<Class, Other>)
 where
 	for<'a> &'a Class: IsClass,
@@ -170,4 +288,173 @@ where
 			Other(other) => Other(other),
 		})
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::Cursor;
+	use anyhow::Result;
+	use zip::ZipArchive;
+	use indexmap::IndexMap;
+	use pretty_assertions::assert_eq;
+	use duke::tree::class::{ClassAccess, ClassFile, ClassName, ClassNameSlice};
+	use duke::tree::version::Version;
+	use crate::storage::{BasicFileAttributes, ClassRepr, IsClass, Jar, JarEntryEnum, ParsedJar, ParsedJarEntry};
+
+	fn class_entry(name: &ClassNameSlice) -> (String, ParsedJarEntry<ClassRepr, Vec<u8>>) {
+		let class = ClassFile::new(Version::V1_8, ClassAccess::default(), name.to_owned(), Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+		ParsedJarEntry::from_class(class)
+	}
+
+	#[test]
+	fn rename_class() -> Result<()> {
+		// SAFETY: is a valid class name
+		let a_b = unsafe { ClassNameSlice::from_inner_unchecked("a/B".into()) };
+		// SAFETY: is a valid class name
+		let c_d = unsafe { ClassNameSlice::from_inner_unchecked("c/D".into()) };
+		// SAFETY: is a valid class name
+		let e_f = unsafe { ClassNameSlice::from_inner_unchecked("e/F".into()) };
+
+		let mut jar = ParsedJar {
+			entries: IndexMap::from([
+				class_entry(a_b),
+				class_entry(e_f),
+			]),
+		};
+
+		jar.rename_class(a_b, c_d)?;
+
+		assert_eq!(jar.entries.keys().collect::<Vec<_>>(), ["c/D.class", "e/F.class"]);
+		let JarEntryEnum::Class(class) = &jar.entries["c/D.class"].content else {
+			panic!("renamed entry should be a class");
+		};
+		assert_eq!(class.read()?.name, c_d);
+
+		// colliding with an existing entry leaves the jar untouched
+		assert!(jar.rename_class(c_d, e_f).is_err());
+		assert_eq!(jar.entries.keys().collect::<Vec<_>>(), ["c/D.class", "e/F.class"]);
+
+		// renaming a class that doesn't exist fails
+		assert!(jar.rename_class(a_b, c_d).is_err());
+
+		// a class that fails to be read stays in the jar
+		jar.entries.insert("a/B.class".to_owned(), ParsedJarEntry {
+			attr: BasicFileAttributes::default(),
+			content: JarEntryEnum::Class(ClassRepr::Vec { data: vec![0xca, 0xfe] }),
+		});
+		assert!(jar.rename_class(a_b, c_d.get_simple_name()).is_err());
+		assert_eq!(jar.entries.keys().collect::<Vec<_>>(), ["c/D.class", "e/F.class", "a/B.class"]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn index_list() {
+		// SAFETY: are valid class names
+		let [a, b, c] = ["org/example/A", "org/example/B", "org/other/C"]
+			.map(|name| unsafe { ClassNameSlice::from_inner_unchecked(name.into()) });
+
+		let other = |data: &[u8]| ParsedJarEntry {
+			attr: BasicFileAttributes::default(),
+			content: JarEntryEnum::Other(data.to_vec()),
+		};
+
+		let jar = ParsedJar {
+			entries: IndexMap::from([
+				("META-INF/MANIFEST.MF".to_owned(), other(b"Manifest-Version: 1.0\r\n")),
+				("org/".to_owned(), ParsedJarEntry { attr: BasicFileAttributes::default(), content: JarEntryEnum::Dir }),
+				class_entry(a),
+				class_entry(c),
+				class_entry(b),
+				("org/example/data.txt".to_owned(), other(b"data")),
+			]),
+		};
+
+		assert_eq!(jar.index_list("example.jar"), "JarIndex-Version: 1.0\n\nexample.jar\norg/example\norg/other\n\n");
+	}
+
+	#[test]
+	fn entries_sorted() -> Result<()> {
+		// SAFETY: are valid class names
+		let [a, b] = ["org/example/A", "org/example/b/B"]
+			.map(|name| unsafe { ClassNameSlice::from_inner_unchecked(name.into()) });
+
+		let dir = || ParsedJarEntry {
+			attr: BasicFileAttributes::default(),
+			content: JarEntryEnum::Dir,
+		};
+		let other = || ParsedJarEntry {
+			attr: BasicFileAttributes::default(),
+			content: JarEntryEnum::Other(Vec::new()),
+		};
+
+		let jar = ParsedJar {
+			entries: IndexMap::from([
+				class_entry(b),
+				("org/example/b/".to_owned(), dir()),
+				class_entry(a),
+				("META-INF/MANIFEST.MF".to_owned(), other()),
+				("org/example/".to_owned(), dir()),
+				("META-INF/".to_owned(), dir()),
+			]),
+		};
+
+		let names: Vec<_> = jar.entries_sorted().map(|(name, _)| name.as_str()).collect();
+		assert_eq!(names, [
+			"META-INF/",
+			"org/example/",
+			"org/example/b/",
+			"META-INF/MANIFEST.MF",
+			"org/example/A.class",
+			"org/example/b/B.class",
+		]);
+
+		let written = jar.write_sorted(Cursor::new(Vec::new()))?.into_inner();
+		let mut zip = ZipArchive::new(Cursor::new(written))?;
+		let zip_names: Vec<_> = (0..zip.len())
+			.map(|index| Ok(zip.by_index(index)?.name().to_owned()))
+			.collect::<Result<_>>()?;
+		assert_eq!(zip_names, names);
+
+		Ok(())
+	}
+
+	#[test]
+	fn content_hash() -> Result<()> {
+		// SAFETY: are valid class names
+		let [a, b] = ["org/example/A", "org/example/B"]
+			.map(|name| unsafe { ClassNameSlice::from_inner_unchecked(name.into()) });
+
+		let other = |data: &[u8], mtime| ParsedJarEntry {
+			attr: BasicFileAttributes { mtime, ..BasicFileAttributes::default() },
+			content: JarEntryEnum::Other(data.to_vec()),
+		};
+
+		let jar_a = ParsedJar {
+			entries: IndexMap::from([
+				("META-INF/MANIFEST.MF".to_owned(), other(b"Manifest-Version: 1.0\r\n", Some(1))),
+				class_entry(a),
+				class_entry(b),
+			]),
+		};
+		let jar_b = ParsedJar {
+			entries: IndexMap::from([
+				class_entry(b),
+				class_entry(a),
+				("META-INF/MANIFEST.MF".to_owned(), other(b"Manifest-Version: 1.0\r\n", Some(2))),
+			]),
+		};
+		let jar_c = ParsedJar {
+			entries: IndexMap::from([
+				("META-INF/MANIFEST.MF".to_owned(), other(b"Manifest-Version: 1.1\r\n", Some(1))),
+				class_entry(a),
+				class_entry(b),
+			]),
+		};
+
+		assert_eq!(jar_a.content_hash()?, jar_b.content_hash()?);
+		assert_ne!(jar_a.content_hash()?, jar_c.content_hash()?);
+
+		Ok(())
+	}
+}
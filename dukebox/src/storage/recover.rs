@@ -0,0 +1,325 @@
+use std::fmt::Debug;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use zip::ZipArchive;
+use crate::storage::zip_directory::{read_central_directory, u16_at, u32_at, CentralDirectory, CENTRAL_DIRECTORY_HEADER_SIGNATURE, END_OF_CENTRAL_DIRECTORY_SIGNATURE, LOCAL_FILE_HEADER_SIGNATURE};
+use crate::storage::zip_impls::open_zip_archive;
+
+/// An entry of a zip archive that was skipped when opening it in recover mode.
+#[derive(Debug)]
+pub struct SkippedEntry {
+	/// The index of the entry in the central directory.
+	pub index: usize,
+	/// The name of the entry, if it could still be read.
+	pub name: Option<String>,
+	/// The reason the entry was skipped.
+	pub error: anyhow::Error,
+}
+
+/// A reader for a zip archive opened in recover mode.
+///
+/// If the archive didn't need to be repaired, this just reads from the underlying reader. Otherwise, everything before the
+/// central directory is read from the underlying reader, and the central directory itself is replaced by one only
+/// containing the entries that look valid.
+pub struct RecoverReader<R>(RecoverReaderInner<R>);
+
+enum RecoverReaderInner<R> {
+	Plain(R),
+	Repaired {
+		inner: R,
+		/// The position of `inner`, if known. It's only sought to `pos` if it differs.
+		inner_pos: Option<u64>,
+		/// The offset of the central directory in `inner`.
+		offset: u64,
+		/// The repaired central directory, followed by the end of central directory record.
+		directory: Vec<u8>,
+		pos: u64,
+	},
+}
+
+impl<R: Read + Seek> Read for RecoverReader<R> {
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+		match &mut self.0 {
+			RecoverReaderInner::Plain(inner) => inner.read(buf),
+			RecoverReaderInner::Repaired { inner, inner_pos, offset, directory, pos } => {
+				let n = if *pos < *offset {
+					let len = (*offset - *pos).min(buf.len() as u64) as usize;
+					if *inner_pos != Some(*pos) {
+						// forget the position, in case the seek fails
+						*inner_pos = None;
+						inner.seek(SeekFrom::Start(*pos))?;
+					}
+					let n = inner.read(&mut buf[..len])?;
+					*inner_pos = Some(*pos + n as u64);
+					n
+				} else {
+					let start = ((*pos - *offset) as usize).min(directory.len());
+					(&directory[start..]).read(buf)?
+				};
+				*pos += n as u64;
+				Ok(n)
+			},
+		}
+	}
+}
+
+impl<R: Seek> Seek for RecoverReader<R> {
+	fn seek(&mut self, seek: SeekFrom) -> std::io::Result<u64> {
+		match &mut self.0 {
+			RecoverReaderInner::Plain(inner) => inner.seek(seek),
+			RecoverReaderInner::Repaired { offset, directory, pos, .. } => {
+				let new_pos = match seek {
+					SeekFrom::Start(pos) => Some(pos),
+					SeekFrom::End(delta) => (*offset + directory.len() as u64).checked_add_signed(delta),
+					SeekFrom::Current(delta) => pos.checked_add_signed(delta),
+				};
+				*pos = new_pos.ok_or_else(|| std::io::Error::new(ErrorKind::InvalidInput, "seek to a negative position"))?;
+				Ok(*pos)
+			},
+		}
+	}
+}
+
+/// A zip archive opened in recover mode, together with the entries that were skipped.
+pub type RecoveredZip<R> = (ZipArchive<RecoverReader<R>>, Vec<SkippedEntry>);
+
+/// Checks if there's a local file header at `offset`, which must be before the central directory.
+fn has_local_header(reader: &mut (impl Read + Seek), offset: u64, directory_offset: u64) -> Result<bool> {
+	if offset + 4 > directory_offset {
+		return Ok(false);
+	}
+	reader.seek(SeekFrom::Start(offset))?;
+	let mut signature = [0; 4];
+	reader.read_exact(&mut signature)?;
+	Ok(u32::from_le_bytes(signature) == LOCAL_FILE_HEADER_SIGNATURE)
+}
+
+/// Checks a single central directory header at `pos`, returning its length and name, and whether the entry can be read.
+///
+/// An `Err` means that the header itself is malformed. Then its length can't be trusted, and all following headers are lost
+/// too.
+fn check_header(directory: &[u8], pos: usize, reader: &mut (impl Read + Seek), directory_offset: u64) -> Result<(usize, String, Result<()>)> {
+	if u32_at(directory, pos)? != CENTRAL_DIRECTORY_HEADER_SIGNATURE {
+		bail!("invalid central directory header signature at {pos}");
+	}
+
+	let name_len = u16_at(directory, pos + 28)? as usize;
+	let extra_len = u16_at(directory, pos + 30)? as usize;
+	let comment_len = u16_at(directory, pos + 32)? as usize;
+	let local_header_offset = u32_at(directory, pos + 42)?;
+
+	let len = 46 + name_len + extra_len + comment_len;
+	if pos + len > directory.len() {
+		bail!("central directory header at {pos} is longer than the central directory");
+	}
+
+	let name = String::from_utf8_lossy(&directory[pos + 46..pos + 46 + name_len]).into_owned();
+
+	let result = if has_local_header(reader, local_header_offset.into(), directory_offset)? {
+		Ok(())
+	} else {
+		Err(anyhow!("no local file header at offset {local_header_offset}"))
+	};
+
+	Ok((len, name, result))
+}
+
+/// Builds a central directory with only the headers that look valid, followed by an end of central directory record.
+fn repair(reader: &mut (impl Read + Seek), directory: CentralDirectory, skipped: &mut Vec<SkippedEntry>) -> Result<Vec<u8>> {
+	let CentralDirectory { entries, offset, data } = directory;
+
+	let mut repaired = Vec::new();
+	let mut kept: u16 = 0;
+
+	let mut pos = 0;
+	for index in 0..entries as usize {
+		match check_header(&data, pos, reader, offset.into()) {
+			Ok((len, _, Ok(()))) => {
+				repaired.extend_from_slice(&data[pos..pos + len]);
+				kept += 1;
+				pos += len;
+			},
+			Ok((len, name, Err(error))) => {
+				skipped.push(SkippedEntry { index, name: Some(name), error });
+				pos += len;
+			},
+			Err(error) => {
+				// The length of this header is unknown, so none of the following headers can be found.
+				skipped.push(SkippedEntry { index, name: None, error });
+				skipped.extend((index + 1..entries as usize).map(|index| SkippedEntry {
+					index,
+					name: None,
+					error: anyhow!("central directory header follows an unreadable one"),
+				}));
+				break;
+			},
+		}
+	}
+
+	let size = repaired.len() as u32;
+	repaired.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+	repaired.extend_from_slice(&[0; 4]); // number of this disk, disk with the central directory
+	repaired.extend_from_slice(&kept.to_le_bytes());
+	repaired.extend_from_slice(&kept.to_le_bytes());
+	repaired.extend_from_slice(&size.to_le_bytes());
+	repaired.extend_from_slice(&offset.to_le_bytes());
+	repaired.extend_from_slice(&[0; 2]); // comment length
+
+	Ok(repaired)
+}
+
+/// Opens the zip archive of a jar, with `jar` in the error messages.
+///
+/// With `recover` set to `false`, this is just like [`open_zip_archive`], and any malformed central directory header makes
+/// this fail.
+///
+/// With `recover` set to `true`, the central directory is checked header by header instead, and entries that can't be read
+/// are logged and skipped, and returned. Only the entries that look valid are then visible in the archive. A header that
+/// is malformed itself also loses all following headers, as there's no way to find where they start. Only if the end of
+/// central directory record can't be found this still fails. Zip64 archives are not supported in recover mode.
+pub(crate) fn open_zip_archive_recover<R: Read + Seek>(mut reader: R, jar: &impl Debug, recover: bool) -> Result<RecoveredZip<R>> {
+	if !recover {
+		let reader = RecoverReader(RecoverReaderInner::Plain(reader));
+		return Ok((open_zip_archive(reader, jar)?, Vec::new()));
+	}
+
+	let directory = read_central_directory(&mut reader)
+		.with_context(|| anyhow!("failed to read central directory of {jar:?}"))?;
+	let offset = directory.offset.into();
+
+	let mut skipped = Vec::new();
+	let directory = repair(&mut reader, directory, &mut skipped)
+		.with_context(|| anyhow!("failed to repair central directory of {jar:?}"))?;
+
+	for entry in &skipped {
+		warn!("skipped unreadable zip entry {} ({:?}) of {jar:?}: {:#}", entry.index, entry.name, entry.error);
+	}
+
+	// the original central directory is only replaced if there's an entry to skip
+	let reader = if skipped.is_empty() {
+		RecoverReader(RecoverReaderInner::Plain(reader))
+	} else {
+		RecoverReader(RecoverReaderInner::Repaired { inner: reader, inner_pos: None, offset, directory, pos: 0 })
+	};
+	let archive = ZipArchive::new(reader)
+		.with_context(|| anyhow!("failed to read repaired zip archive from {jar:?}"))?;
+
+	Ok((archive, skipped))
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+	use anyhow::Result;
+	use pretty_assertions::assert_eq;
+	use zip::write::SimpleFileOptions;
+	use zip::ZipWriter;
+	use crate::storage::{OpenedJar, UnnamedMemJar};
+	use crate::storage::recover::{RecoverReader, RecoverReaderInner};
+
+	/// A reader counting the seeks done on it.
+	struct CountingSeeks {
+		inner: Cursor<Vec<u8>>,
+		seeks: usize,
+	}
+
+	impl Read for CountingSeeks {
+		fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+			self.inner.read(buf)
+		}
+	}
+
+	impl Seek for CountingSeeks {
+		fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+			self.seeks += 1;
+			self.inner.seek(pos)
+		}
+	}
+
+	fn jar() -> Result<(Vec<u8>, Vec<usize>)> {
+		let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+		for name in ["a.txt", "b.txt", "c.txt"] {
+			zip.start_file(name, SimpleFileOptions::default())?;
+			zip.write_all(name.as_bytes())?;
+		}
+		let data = zip.finish()?.into_inner();
+
+		let headers: Vec<usize> = (0..data.len() - 4)
+			.filter(|&i| data[i..i + 4] == [b'P', b'K', 1, 2])
+			.collect();
+		assert_eq!(headers.len(), 3);
+
+		Ok((data, headers))
+	}
+
+	#[test]
+	fn open_recover() -> Result<()> {
+		let (mut data, headers) = jar()?;
+		// let the local header offset of `b.txt` point into the central directory
+		data[headers[1] + 42..headers[1] + 46].copy_from_slice(&(headers[0] as u32).to_le_bytes());
+		let jar = UnnamedMemJar { data };
+
+		// the `zip` crate only notices this when reading the entry
+		let (mut opened, skipped) = jar.open_recover(false)?;
+		assert!(skipped.is_empty());
+		assert!(opened.read_bytes_by_name("b.txt").is_err());
+
+		let (mut opened, skipped) = jar.open_recover(true)?;
+
+		let names: Vec<&str> = opened.names().map(|(_, name)| name).collect();
+		assert_eq!(names, ["a.txt", "c.txt"]);
+		assert_eq!(opened.read_bytes_by_name("c.txt")?.as_deref(), Some(b"c.txt".as_slice()));
+
+		assert_eq!(skipped.len(), 1);
+		assert_eq!(skipped[0].index, 1);
+		assert_eq!(skipped[0].name.as_deref(), Some("b.txt"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn open_recover_bad_signature() -> Result<()> {
+		let (mut data, headers) = jar()?;
+		// break the signature of the central directory header of `b.txt`
+		data[headers[1] + 3] = 0xff;
+		let jar = UnnamedMemJar { data };
+
+		assert!(jar.open_recover(false).is_err());
+
+		let (opened, skipped) = jar.open_recover(true)?;
+
+		// the length of the broken header can't be trusted, so `c.txt` is lost too
+		let names: Vec<&str> = opened.names().map(|(_, name)| name).collect();
+		assert_eq!(names, ["a.txt"]);
+
+		let skipped: Vec<_> = skipped.iter().map(|entry| (entry.index, entry.name.as_deref())).collect();
+		assert_eq!(skipped, [(1, None), (2, None)]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn recover_reader_seeks_only_when_needed() -> Result<()> {
+		let inner = CountingSeeks { inner: Cursor::new(b"abcdefgh".to_vec()), seeks: 0 };
+		let mut reader = RecoverReader(RecoverReaderInner::Repaired { inner, inner_pos: None, offset: 6, directory: b"XY".to_vec(), pos: 0 });
+
+		let mut buf = [0; 2];
+		for expected in [b"ab", b"cd", b"ef", b"XY"] {
+			reader.read_exact(&mut buf)?;
+			assert_eq!(&buf, expected);
+		}
+
+		reader.seek(SeekFrom::Start(1))?;
+		reader.read_exact(&mut buf)?;
+		assert_eq!(&buf, b"bc");
+
+		let RecoverReaderInner::Repaired { inner, .. } = reader.0 else {
+			panic!("the reader should still be a repaired one");
+		};
+		// once at the start, and once after seeking back
+		assert_eq!(inner.seeks, 2);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+use std::io::{Read, Seek};
+use anyhow::{bail, Context, Result};
+use crate::storage::zip_directory::{read_central_directory, u16_at, u32_at, CentralDirectory, CENTRAL_DIRECTORY_HEADER_SIGNATURE};
+
+/// A possible security problem of a jar, found by [`scan_security`][crate::storage::Jar::scan_security].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecurityFinding {
+	/// There are multiple entries with this name.
+	///
+	/// Only one of these entries is visible when reading the jar, which one depends on the reader used.
+	DuplicateEntry(String),
+	/// The name of the entry contains a `..` path component, or is an absolute path.
+	///
+	/// Extracting such an entry may write to a file outside the directory extracted to, which is known as "zip slip".
+	PathTraversal(String),
+}
+
+fn is_path_traversal(name: &str) -> bool {
+	let is_absolute = name.starts_with('/') || name.starts_with('\\') ||
+		name.as_bytes().get(1) == Some(&b':') && name.as_bytes()[0].is_ascii_alphabetic();
+
+	is_absolute || name.split(['/', '\\']).any(|component| component == "..")
+}
+
+/// Checks the given entry names for duplicates and path traversals.
+///
+/// Each duplicate name is only reported once. The findings are in the order of the names.
+pub(crate) fn scan_names<'a>(names: impl IntoIterator<Item=&'a str>) -> Vec<SecurityFinding> {
+	let mut findings = Vec::new();
+	let mut seen = HashSet::new();
+	let mut duplicates = HashSet::new();
+
+	for name in names {
+		if !seen.insert(name) && duplicates.insert(name) {
+			findings.push(SecurityFinding::DuplicateEntry(name.to_owned()));
+		}
+		if is_path_traversal(name) && !duplicates.contains(name) {
+			findings.push(SecurityFinding::PathTraversal(name.to_owned()));
+		}
+	}
+
+	findings
+}
+
+/// Reads the names of all entries in the central directory of a zip archive, including duplicate names.
+///
+/// Zip readers (like the one of the `zip` crate) usually store the entries in a map, so that only one entry of each name
+/// is visible. This reads the central directory by itself, to find all of them. Zip64 archives are not supported.
+pub(crate) fn read_zip_entry_names(reader: &mut (impl Read + Seek)) -> Result<Vec<String>> {
+	let CentralDirectory { entries, offset, data: directory } = read_central_directory(reader)?;
+
+	let mut names = Vec::with_capacity(entries.into());
+	let mut pos = 0;
+	for _ in 0..entries {
+		if u32_at(&directory, pos)? != CENTRAL_DIRECTORY_HEADER_SIGNATURE {
+			bail!("invalid central directory header signature at offset {}", offset as usize + pos);
+		}
+		let name_len = u16_at(&directory, pos + 28)? as usize;
+		let extra_len = u16_at(&directory, pos + 30)? as usize;
+		let comment_len = u16_at(&directory, pos + 32)? as usize;
+
+		let name = directory.get(pos + 46..pos + 46 + name_len)
+			.context("unexpected end of zip central directory")?;
+		names.push(String::from_utf8_lossy(name).into_owned());
+
+		pos += 46 + name_len + extra_len + comment_len;
+	}
+
+	Ok(names)
+}
+
+#[cfg(test)]
+mod testing {
+	use std::io::{Cursor, Write};
+	use anyhow::Result;
+	use pretty_assertions::assert_eq;
+	use zip::write::SimpleFileOptions;
+	use zip::ZipWriter;
+	use crate::storage::{Jar, SecurityFinding, UnnamedMemJar};
+
+	/// Replaces all occurrences of `from` with `to`, which must have the same length.
+	fn replace(data: &mut [u8], from: &[u8], to: &[u8]) {
+		for i in 0..=data.len() - from.len() {
+			if &data[i..i + from.len()] == from {
+				data[i..i + from.len()].copy_from_slice(to);
+			}
+		}
+	}
+
+	#[test]
+	fn scan_security() -> Result<()> {
+		let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+		for name in ["a.txt", "b.txt", "xx/evil", "ok/../ok.txt", "c.txt"] {
+			zip.start_file(name, SimpleFileOptions::default())?;
+			zip.write_all(name.as_bytes())?;
+		}
+		let mut data = zip.finish()?.into_inner();
+
+		// The zip writer doesn't allow writing these names, so we change them afterwards.
+		replace(&mut data, b"b.txt", b"a.txt");
+		replace(&mut data, b"xx/evil", b"../evil");
+
+		let jar = UnnamedMemJar { data };
+		assert_eq!(jar.scan_security()?, [
+			SecurityFinding::DuplicateEntry("a.txt".to_owned()),
+			SecurityFinding::PathTraversal("../evil".to_owned()),
+			SecurityFinding::PathTraversal("ok/../ok.txt".to_owned()),
+		]);
+
+		assert_eq!(jar.duplicate_entries()?, ["a.txt"]);
+
+		// reading the jar normally only shows one of the entries named `a.txt`
+		assert_eq!(jar.open()?.len(), 4);
+
+		Ok(())
+	}
+
+	#[test]
+	fn scan_names() {
+		let findings = super::scan_names(["/etc/passwd", "C:\\Windows", "a/b/c", "a..b", "a\\..\\b", "a/b/c"]);
+		assert_eq!(findings, [
+			SecurityFinding::PathTraversal("/etc/passwd".to_owned()),
+			SecurityFinding::PathTraversal("C:\\Windows".to_owned()),
+			SecurityFinding::PathTraversal("a\\..\\b".to_owned()),
+			SecurityFinding::DuplicateEntry("a/b/c".to_owned()),
+		]);
+	}
+}
@@ -0,0 +1,58 @@
+use std::io::{Read, Seek, SeekFrom};
+use anyhow::{bail, Context, Result};
+
+pub(crate) const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+pub(crate) const CENTRAL_DIRECTORY_HEADER_SIGNATURE: u32 = 0x02014b50;
+pub(crate) const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+/// The size of the end of central directory record, without the comment.
+const END_OF_CENTRAL_DIRECTORY_SIZE: u64 = 22;
+
+pub(crate) fn u16_at(data: &[u8], pos: usize) -> Result<u16> {
+	data.get(pos..pos + 2)
+		.map(|x| u16::from_le_bytes([x[0], x[1]]))
+		.context("unexpected end of zip central directory")
+}
+
+pub(crate) fn u32_at(data: &[u8], pos: usize) -> Result<u32> {
+	data.get(pos..pos + 4)
+		.map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+		.context("unexpected end of zip central directory")
+}
+
+/// The central directory of a zip archive, as read by [`read_central_directory`].
+pub(crate) struct CentralDirectory {
+	/// The number of entries, as given by the end of central directory record.
+	pub(crate) entries: u16,
+	/// The offset of the central directory in the archive.
+	pub(crate) offset: u32,
+	/// The raw central directory headers.
+	pub(crate) data: Vec<u8>,
+}
+
+/// Finds the end of central directory record and reads the central directory it points to. Zip64 archives are not supported.
+pub(crate) fn read_central_directory(reader: &mut (impl Read + Seek)) -> Result<CentralDirectory> {
+	let len = reader.seek(SeekFrom::End(0))?;
+
+	// The end of central directory record is at the end, followed by a comment of up to u16::MAX bytes.
+	let tail_len = len.min(END_OF_CENTRAL_DIRECTORY_SIZE + u16::MAX as u64);
+	reader.seek(SeekFrom::Start(len - tail_len))?;
+	let mut tail = Vec::new();
+	reader.take(tail_len).read_to_end(&mut tail)?;
+
+	let end = (0..tail.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_SIZE as usize - 1)).rev()
+		.find(|&pos| u32_at(&tail, pos).is_ok_and(|signature| signature == END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+		.context("no end of central directory record found, not a zip archive")?;
+
+	let entries = u16_at(&tail, end + 10)?;
+	let size = u32_at(&tail, end + 12)?;
+	let offset = u32_at(&tail, end + 16)?;
+	if entries == u16::MAX || size == u32::MAX || offset == u32::MAX {
+		bail!("zip64 archives are not supported");
+	}
+
+	reader.seek(SeekFrom::Start(offset.into()))?;
+	let mut data = Vec::new();
+	reader.take(size.into()).read_to_end(&mut data)?;
+
+	Ok(CentralDirectory { entries, offset, data })
+}
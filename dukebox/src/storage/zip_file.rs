@@ -2,7 +2,9 @@ use std::fs::File;
 use std::path::{Path, PathBuf};
 use anyhow::{anyhow, Context, Result};
 use zip::ZipArchive;
-use crate::storage::Jar;
+use crate::storage::{Jar, RecoveredZip, SecurityFinding};
+use crate::storage::recover::open_zip_archive_recover;
+use crate::storage::zip_impls::open_zip_archive;
 
 /// A jar read from a path.
 #[derive(Debug)]
@@ -11,14 +13,25 @@ pub struct FileJar {
 	pub path: PathBuf,
 }
 
+impl FileJar {
+	/// Opens the jar for reading, like [`Jar::open`].
+	///
+	/// With `recover` set, entries that can't be read are logged and skipped instead of making this fail, and returned. See
+	/// [`SkippedEntry`][crate::storage::SkippedEntry] for more.
+	pub fn open_recover(&self, recover: bool) -> Result<RecoveredZip<File>> {
+		let file = File::open(&self.path)
+			.with_context(|| anyhow!("could not open file {self:?}"))?;
+		open_zip_archive_recover(file, self, recover)
+	}
+}
+
 impl Jar for FileJar {
 	type Opened<'a> = ZipArchive<File> where Self: 'a;
 
 	fn open(&self) -> Result<Self::Opened<'_>> {
 		let file = File::open(&self.path)
 			.with_context(|| anyhow!("could not open file {self:?}"))?;
-		ZipArchive::new(file)
-			.with_context(|| anyhow!("failed to read zip archive from {self:?}"))
+		open_zip_archive(file, self)
 	}
 
 	fn put_to_file<'a>(&'a self, suggested: &'a Path) -> Result<&'a Path> {
@@ -26,4 +39,12 @@ impl Jar for FileJar {
 		let _ = suggested;
 		Ok(&self.path)
 	}
+
+	fn scan_security(&self) -> Result<Vec<SecurityFinding>> {
+		let mut file = File::open(&self.path)
+			.with_context(|| anyhow!("could not open file {self:?}"))?;
+		let names = crate::storage::security::read_zip_entry_names(&mut file)
+			.with_context(|| anyhow!("failed to read entry names of file jar {self:?}"))?;
+		Ok(crate::storage::security::scan_names(names.iter().map(|name| name.as_str())))
+	}
 }
\ No newline at end of file
@@ -1,11 +1,28 @@
-use anyhow::{anyhow, Context, Result};
-use std::io::{Read, Seek};
+use anyhow::{anyhow, bail, Context, Result};
+use std::fmt::Debug;
+use std::io::{Read, Seek, SeekFrom};
 use log::info;
 use zip::read::ZipFile;
 use zip::result::ZipError;
 use zip::{ExtraField, ZipArchive};
 use crate::storage::{BasicFileAttributes, JarEntry, JarEntryEnum, OpenedJar, VecClass};
 
+/// Opens the zip archive of a jar, with `jar` in the error messages.
+///
+/// Reading from zero bytes gives a confusing error from the `zip` crate, so we check for that first.
+pub(crate) fn open_zip_archive<R: Read + Seek>(mut reader: R, jar: &impl Debug) -> Result<ZipArchive<R>> {
+	let length = reader.seek(SeekFrom::End(0))
+		.with_context(|| anyhow!("failed to get the length of {jar:?}"))?;
+	if length == 0 {
+		bail!("failed to read zip archive from {jar:?}: it has zero bytes, but even a zip archive without entries has some");
+	}
+	reader.rewind()
+		.with_context(|| anyhow!("failed to seek to the start of {jar:?}"))?;
+
+	ZipArchive::new(reader)
+		.with_context(|| anyhow!("failed to read zip archive from {jar:?}"))
+}
+
 impl<R: Read + Seek> OpenedJar for ZipArchive<R> {
 	type EntryKey = usize;
 
@@ -19,6 +36,11 @@ impl<R: Read + Seek> OpenedJar for ZipArchive<R> {
 		self.by_index(key).context("") // TODO: msg
 	}
 
+	/// Gets the names of all entries.
+	///
+	/// The names are taken from the central directory, which the [`ZipArchive`] already read when it was opened. So this
+	/// doesn't read from the underlying reader at all, and in particular doesn't decompress any entries. This makes it cheap
+	/// to build an index of the classes in many jars.
 	fn names(&self) -> impl Iterator<Item=(Self::EntryKey, &'_ str)> {
 		//TODO: suggest to `zip` crate to expose the `files` map of the `ZipArchive`, because I want to have both the names and the
 		// zip file indices to improve performance (as then I don't need to get with a string from a map!)
@@ -87,3 +109,61 @@ impl JarEntry for ZipFile<'_> {
 		})
 	}
 }
+
+#[cfg(test)]
+mod testing {
+	use std::cell::Cell;
+	use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+	use std::rc::Rc;
+	use anyhow::Result;
+	use pretty_assertions::assert_eq;
+	use zip::{CompressionMethod, ZipArchive, ZipWriter};
+	use zip::write::SimpleFileOptions;
+	use crate::storage::OpenedJar;
+
+	/// A reader counting the bytes read from it.
+	struct CountingReader<R> {
+		inner: R,
+		read: Rc<Cell<usize>>,
+	}
+
+	impl<R: Read> Read for CountingReader<R> {
+		fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+			let n = self.inner.read(buf)?;
+			self.read.set(self.read.get() + n);
+			Ok(n)
+		}
+	}
+
+	impl<R: Seek> Seek for CountingReader<R> {
+		fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+			self.inner.seek(pos)
+		}
+	}
+
+	#[test]
+	fn names_only_reads_central_directory() -> Result<()> {
+		let mut zip_out = ZipWriter::new(Cursor::new(Vec::new()));
+		let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+		for i in 0..200 {
+			zip_out.start_file(format!("org/example/Class{i}.class"), options)?;
+			// data that doesn't compress well, so that the jar is large
+			let data: Vec<u8> = (0..0x1000u32).map(|x| (x.wrapping_mul(2654435761).wrapping_add(i) >> 13) as u8).collect();
+			zip_out.write_all(&data)?;
+		}
+		let data = zip_out.finish()?.into_inner();
+
+		let read = Rc::new(Cell::new(0));
+		let archive = ZipArchive::new(CountingReader { inner: Cursor::new(&data), read: read.clone() })?;
+		let read_when_opening = read.get();
+		// opening only reads the central directory
+		assert!(read_when_opening < data.len() / 4, "read {read_when_opening} of {} bytes", data.len());
+
+		let names: Vec<&str> = archive.names().map(|(_, name)| name).collect();
+		assert_eq!(names.len(), 200);
+		assert_eq!(names[17], "org/example/Class17.class");
+		assert_eq!(read.get(), read_when_opening);
+
+		Ok(())
+	}
+}
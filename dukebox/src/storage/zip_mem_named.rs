@@ -3,7 +3,9 @@ use std::fmt::{Debug, Formatter};
 use std::io::Cursor;
 use std::path::Path;
 use zip::ZipArchive;
-use crate::storage::Jar;
+use crate::storage::{Jar, RecoveredZip, SecurityFinding};
+use crate::storage::recover::open_zip_archive_recover;
+use crate::storage::zip_impls::open_zip_archive;
 
 /// A named, in-memory jar.
 #[derive(Clone)]
@@ -26,12 +28,21 @@ impl Debug for NamedMemJar {
 	}
 }
 
+impl NamedMemJar {
+	/// Opens the jar for reading, like [`Jar::open`].
+	///
+	/// With `recover` set, entries that can't be read are logged and skipped instead of making this fail, and returned. See
+	/// [`SkippedEntry`][crate::storage::SkippedEntry] for more.
+	pub fn open_recover(&self, recover: bool) -> Result<RecoveredZip<Cursor<&Vec<u8>>>> {
+		open_zip_archive_recover(Cursor::new(&self.data), self, recover)
+	}
+}
+
 impl Jar for NamedMemJar {
 	type Opened<'a> = ZipArchive<Cursor<&'a Vec<u8>>> where Self: 'a;
 
 	fn open(&self) -> Result<Self::Opened<'_>> {
-		ZipArchive::new(Cursor::new(&self.data))
-			.with_context(|| anyhow!("failed to read zip archive from {self:?}"))
+		open_zip_archive(Cursor::new(&self.data), self)
 	}
 
 	fn put_to_file<'a>(&'a self, suggested: &'a Path) -> Result<&'a Path> {
@@ -39,4 +50,10 @@ impl Jar for NamedMemJar {
 			.map(|()| suggested)
 			.with_context(|| anyhow!("failed to write named ({:?}) in-memory jar to {suggested:?}", self.name))
 	}
+
+	fn scan_security(&self) -> Result<Vec<SecurityFinding>> {
+		let names = crate::storage::security::read_zip_entry_names(&mut Cursor::new(&self.data))
+			.with_context(|| anyhow!("failed to read entry names of named in-memory jar {self:?}"))?;
+		Ok(crate::storage::security::scan_names(names.iter().map(|name| name.as_str())))
+	}
 }
\ No newline at end of file
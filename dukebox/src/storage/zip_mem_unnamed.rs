@@ -3,7 +3,9 @@ use std::io::Cursor;
 use std::path::Path;
 use anyhow::{anyhow, Context, Result};
 use zip::ZipArchive;
-use crate::storage::Jar;
+use crate::storage::{Jar, RecoveredZip, SecurityFinding};
+use crate::storage::recover::open_zip_archive_recover;
+use crate::storage::zip_impls::open_zip_archive;
 
 /// An unnamed, in-memory jar.
 #[derive(Clone)]
@@ -23,12 +25,21 @@ impl Debug for UnnamedMemJar {
 	}
 }
 
+impl UnnamedMemJar {
+	/// Opens the jar for reading, like [`Jar::open`].
+	///
+	/// With `recover` set, entries that can't be read are logged and skipped instead of making this fail, and returned. See
+	/// [`SkippedEntry`][crate::storage::SkippedEntry] for more.
+	pub fn open_recover(&self, recover: bool) -> Result<RecoveredZip<Cursor<&Vec<u8>>>> {
+		open_zip_archive_recover(Cursor::new(&self.data), self, recover)
+	}
+}
+
 impl Jar for UnnamedMemJar {
 	type Opened<'a> = ZipArchive<Cursor<&'a Vec<u8>>> where Self: 'a;
 
 	fn open(&self) -> Result<Self::Opened<'_>> {
-		ZipArchive::new(Cursor::new(&self.data))
-			.with_context(|| anyhow!("failed to read zip archive from {self:?}"))
+		open_zip_archive(Cursor::new(&self.data), self)
 	}
 
 	fn put_to_file<'a>(&'a self, suggested: &'a Path) -> Result<&'a Path> {
@@ -36,4 +47,10 @@ impl Jar for UnnamedMemJar {
 			.map(|()| suggested)
 			.with_context(|| anyhow!("failed to write unnamed in-memory jar to {suggested:?}"))
 	}
+
+	fn scan_security(&self) -> Result<Vec<SecurityFinding>> {
+		let names = crate::storage::security::read_zip_entry_names(&mut Cursor::new(&self.data))
+			.with_context(|| anyhow!("failed to read entry names of unnamed in-memory jar {self:?}"))?;
+		Ok(crate::storage::security::scan_names(names.iter().map(|name| name.as_str())))
+	}
 }
@@ -0,0 +1,174 @@
+//! Changing zip archives on disk, without reading them into a [`ParsedJar`][crate::storage::ParsedJar] first.
+
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
+use anyhow::{anyhow, bail, Context, Result};
+use tempfile::NamedTempFile;
+use zip::{ZipArchive, ZipWriter};
+use crate::storage::BasicFileAttributes;
+
+/// What [`append_entries`] does with an entry that already exists in the archive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnDuplicate {
+	/// Fail without changing the archive.
+	#[default]
+	Reject,
+	/// Replace the existing entry with the new one.
+	///
+	/// As entries can't be removed from the central directory in place, this rewrites the whole archive. The other
+	/// entries are copied without decompressing them.
+	Replace,
+}
+
+/// Appends the given entries to the zip archive at `path`.
+///
+/// If none of the entries already exist in the archive, they are written after the existing entries, and only the
+/// central directory is rewritten. Otherwise `on_duplicate` decides what happens.
+///
+/// Giving the same name twice in `entries` is always an error.
+pub fn append_entries(path: &Path, entries: &[(String, Vec<u8>, BasicFileAttributes)], on_duplicate: OnDuplicate) -> Result<()> {
+	let mut names = HashSet::new();
+	for (name, _, _) in entries {
+		if !names.insert(name.as_str()) {
+			bail!("entry {name:?} is given more than once");
+		}
+	}
+
+	let mut file = OpenOptions::new().read(true).write(true).open(path)
+		.with_context(|| anyhow!("could not open file {path:?}"))?;
+
+	let mut archive = ZipArchive::new(&mut file)
+		.with_context(|| anyhow!("failed to read zip archive from {path:?}"))?;
+
+	let duplicates: Vec<_> = archive.file_names()
+		.filter(|name| names.contains(name))
+		.map(|name| name.to_owned())
+		.collect();
+
+	if duplicates.is_empty() {
+		drop(archive);
+
+		let mut zip_out = ZipWriter::new_append(file)
+			.with_context(|| anyhow!("failed to open zip archive {path:?} for appending"))?;
+		write_entries(&mut zip_out, entries)?;
+		zip_out.finish()
+			.with_context(|| anyhow!("failed to finish appending to zip archive {path:?}"))?;
+
+		return Ok(());
+	}
+
+	match on_duplicate {
+		OnDuplicate::Reject => bail!("zip archive {path:?} already contains the entries {duplicates:?}"),
+		OnDuplicate::Replace => {
+			// Write to a temporary file next to the archive, and only replace the archive once that succeeded, so that a
+			// failure doesn't leave a truncated archive behind.
+			let directory = path.parent()
+				.filter(|parent| !parent.as_os_str().is_empty())
+				.unwrap_or(Path::new("."));
+			let temp_file = NamedTempFile::new_in(directory)
+				.with_context(|| anyhow!("could not create a temporary file in {directory:?}"))?;
+
+			let mut zip_out = ZipWriter::new(BufWriter::new(temp_file));
+			for index in 0..archive.len() {
+				let entry = archive.by_index_raw(index)?;
+				if !names.contains(entry.name()) {
+					zip_out.raw_copy_file(entry)?;
+				}
+			}
+			write_entries(&mut zip_out, entries)?;
+			let temp_file = zip_out.finish()
+				.with_context(|| anyhow!("failed to finish writing the zip archive for {path:?}"))?
+				.into_inner()
+				.with_context(|| anyhow!("failed to write the zip archive for {path:?}"))?;
+
+			drop(archive);
+			drop(file);
+
+			temp_file.persist(path)
+				.with_context(|| anyhow!("could not replace {path:?} with the new zip archive"))?;
+
+			Ok(())
+		},
+	}
+}
+
+fn write_entries<W: Write + Seek>(zip_out: &mut ZipWriter<W>, entries: &[(String, Vec<u8>, BasicFileAttributes)]) -> Result<()> {
+	for (name, data, attr) in entries {
+		zip_out.start_file(name.as_str(), attr.to_file_options())
+			.with_context(|| anyhow!("failed to start entry {name:?}"))?;
+		zip_out.write_all(data)
+			.with_context(|| anyhow!("failed to write entry {name:?}"))?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod testing {
+	use std::fs::File;
+	use std::io::{Cursor, Read, Write};
+	use std::path::Path;
+	use anyhow::Result;
+	use pretty_assertions::assert_eq;
+	use zip::{ZipArchive, ZipWriter};
+	use zip::write::SimpleFileOptions;
+	use crate::storage::BasicFileAttributes;
+	use crate::zip::{append_entries, OnDuplicate};
+
+	fn write_fixture(path: &Path) -> Result<()> {
+		let mut zip_out = ZipWriter::new(Cursor::new(Vec::new()));
+		zip_out.start_file("META-INF/MANIFEST.MF", SimpleFileOptions::default())?;
+		zip_out.write_all(b"Manifest-Version: 1.0\r\n")?;
+		zip_out.start_file("org/example/data.txt", SimpleFileOptions::default())?;
+		zip_out.write_all(b"data")?;
+		std::fs::write(path, zip_out.finish()?.into_inner())?;
+		Ok(())
+	}
+
+	fn read_entries(path: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+		let mut archive = ZipArchive::new(File::open(path)?)?;
+		let mut entries = Vec::new();
+		for index in 0..archive.len() {
+			let mut entry = archive.by_index(index)?;
+			let mut data = Vec::new();
+			entry.read_to_end(&mut data)?;
+			entries.push((entry.name().to_owned(), data));
+		}
+		Ok(entries)
+	}
+
+	#[test]
+	fn append_entries_to_jar() -> Result<()> {
+		let dir = tempfile::tempdir()?;
+		let path = dir.path().join("example.jar");
+		write_fixture(&path)?;
+
+		let mod_json = ("fabric.mod.json".to_owned(), b"{}".to_vec(), BasicFileAttributes::default());
+		append_entries(&path, std::slice::from_ref(&mod_json), OnDuplicate::Reject)?;
+
+		assert_eq!(read_entries(&path)?, [
+			("META-INF/MANIFEST.MF".to_owned(), b"Manifest-Version: 1.0\r\n".to_vec()),
+			("org/example/data.txt".to_owned(), b"data".to_vec()),
+			("fabric.mod.json".to_owned(), b"{}".to_vec()),
+		]);
+
+		// appending it again is rejected and leaves the jar alone
+		assert!(append_entries(&path, &[mod_json], OnDuplicate::Reject).is_err());
+		assert_eq!(read_entries(&path)?.len(), 3);
+
+		let replacement = ("org/example/data.txt".to_owned(), b"other data".to_vec(), BasicFileAttributes::default());
+		append_entries(&path, &[replacement], OnDuplicate::Replace)?;
+
+		assert_eq!(read_entries(&path)?, [
+			("META-INF/MANIFEST.MF".to_owned(), b"Manifest-Version: 1.0\r\n".to_vec()),
+			("fabric.mod.json".to_owned(), b"{}".to_vec()),
+			("org/example/data.txt".to_owned(), b"other data".to_vec()),
+		]);
+
+		// the temporary file was renamed to the jar
+		assert_eq!(std::fs::read_dir(dir.path())?.count(), 1);
+
+		Ok(())
+	}
+}
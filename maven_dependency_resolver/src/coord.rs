@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 use anyhow::{anyhow, bail, Context, Error};
@@ -46,7 +47,19 @@ use crate::resolver::Resolver;
 /// assert_eq!(a, format!("{}", MavenCoord::from_str(a).unwrap()));
 /// ```
 /// This means that round trips with [Display] and [FromStr] are possible.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// [MavenCoord] implements [Ord], ordering by group, artifact, classifier, type and lastly version. Versions are compared
+/// like maven does, so that `1.9` comes before `1.10`.
+/// ```
+/// use std::str::FromStr;
+/// # use pretty_assertions::assert_eq;
+/// use maven_dependency_resolver::coord::MavenCoord;
+///
+/// let a = MavenCoord::from_str("org.example:artifact:1.9").unwrap();
+/// let b = MavenCoord::from_str("org.example:artifact:1.10").unwrap();
+/// assert!(a < b);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MavenCoord {
 	pub group: String,
 	pub artifact: String,
@@ -126,6 +139,27 @@ impl Display for MavenCoord {
 	}
 }
 
+impl PartialOrd for MavenCoord {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+/// Orders by group, artifact, classifier, type and then version, using [`compare_versions`] for the version.
+///
+/// Versions that maven considers equal (like `1.0` and `1`) are ordered by their string representation, so that this stays
+/// consistent with [`PartialEq`].
+impl Ord for MavenCoord {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.group.cmp(&other.group)
+			.then_with(|| self.artifact.cmp(&other.artifact))
+			.then_with(|| self.classifier.cmp(&other.classifier))
+			.then_with(|| self.type_.cmp(&other.type_))
+			.then_with(|| compare_versions(&self.version, &other.version))
+			.then_with(|| self.version.cmp(&other.version))
+	}
+}
+
 impl FromStr for MavenCoord {
 	type Err = Error;
 
@@ -168,6 +202,176 @@ pub(crate) struct DependencyCollisionId {
 	type_: String,
 }
 
+/// Compares two versions like maven does.
+///
+/// See [the maven documentation](https://maven.apache.org/pom.html#version-order-specification) for the exact rules. In short,
+/// versions are split into numeric and qualifier parts on `.`, `-` and transitions between digits and letters. Numeric parts
+/// compare as numbers, and the well-known qualifiers are ordered `alpha` < `beta` < `milestone` < `rc` < `snapshot` < (release)
+/// < `sp`, with unknown qualifiers coming after those, in lexical order.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+	version::parse(a).cmp_item(Some(&version::parse(b)))
+}
+
+/// An implementation of the `ComparableVersion` of maven.
+mod version {
+	use std::cmp::Ordering;
+
+	pub(super) enum Item {
+		/// A number, without leading zeros.
+		Int(String),
+		/// A qualifier, already made comparable, see [`comparable_qualifier`].
+		String(String),
+		List(Vec<Item>),
+	}
+
+	/// The well known qualifiers, in their order. The empty string represents a release.
+	const QUALIFIERS: [&str; 7] = ["alpha", "beta", "milestone", "rc", "snapshot", "", "sp"];
+
+	fn comparable_qualifier(qualifier: &str) -> String {
+		match QUALIFIERS.iter().position(|x| *x == qualifier) {
+			Some(index) => index.to_string(),
+			// unknown qualifiers come after the known ones
+			None => format!("{}-{qualifier}", QUALIFIERS.len()),
+		}
+	}
+
+	fn release() -> String {
+		comparable_qualifier("")
+	}
+
+	fn int_item(digits: &str) -> Item {
+		let digits = digits.trim_start_matches('0');
+		Item::Int(if digits.is_empty() { "0" } else { digits }.to_owned())
+	}
+
+	fn string_item(value: &str, followed_by_digit: bool) -> Item {
+		let value = match value {
+			"a" if followed_by_digit => "alpha",
+			"b" if followed_by_digit => "beta",
+			"m" if followed_by_digit => "milestone",
+			"ga" | "final" | "release" => "",
+			"cr" => "rc",
+			value => value,
+		};
+		Item::String(comparable_qualifier(value))
+	}
+
+	fn parse_item(is_digit: bool, value: &str) -> Item {
+		if is_digit { int_item(value) } else { string_item(value, false) }
+	}
+
+	/// Parses a version into a tree of items.
+	///
+	/// Each `-` (or transition between digits and letters) starts a new nested list at the end of the current one. The outer
+	/// lists are kept on a stack until the version is fully parsed.
+	pub(super) fn parse(version: &str) -> Item {
+		let version = version.to_lowercase();
+
+		let mut stack: Vec<Vec<Item>> = Vec::new();
+		let mut list = Vec::new();
+		let mut is_digit = false;
+		let mut start = 0;
+
+		for (i, c) in version.char_indices() {
+			match c {
+				'.' | '-' => {
+					if i == start {
+						list.push(int_item("0"));
+					} else {
+						list.push(parse_item(is_digit, &version[start..i]));
+					}
+					start = i + 1;
+					if c == '-' {
+						stack.push(std::mem::take(&mut list));
+					}
+				},
+				c if c.is_ascii_digit() => {
+					if !is_digit && i > start {
+						list.push(string_item(&version[start..i], true));
+						start = i;
+						stack.push(std::mem::take(&mut list));
+					}
+					is_digit = true;
+				},
+				_ => {
+					if is_digit && i > start {
+						list.push(parse_item(true, &version[start..i]));
+						start = i;
+						stack.push(std::mem::take(&mut list));
+					}
+					is_digit = false;
+				},
+			}
+		}
+
+		if version.len() > start {
+			list.push(parse_item(is_digit, &version[start..]));
+		}
+
+		// Close the nested lists, from the innermost one outwards, normalizing each.
+		while let Some(mut outer) = stack.pop() {
+			outer.push(Item::List(normalize(list)));
+			list = outer;
+		}
+		Item::List(normalize(list))
+	}
+
+	/// Removes the trailing "null" items, like `0`, or the release qualifier, stopping at the first other non-list item.
+	fn normalize(mut list: Vec<Item>) -> Vec<Item> {
+		let mut i = list.len();
+		while i > 0 {
+			i -= 1;
+			if list[i].is_null() {
+				list.remove(i);
+			} else if !matches!(list[i], Item::List(_)) {
+				break;
+			}
+		}
+		list
+	}
+
+	impl Item {
+		fn is_null(&self) -> bool {
+			match self {
+				Item::Int(int) => int == "0",
+				Item::String(string) => *string == release(),
+				Item::List(list) => list.is_empty(),
+			}
+		}
+
+		/// Compares this item to `other`, where `None` stands for a missing item (like the missing third part of `1.0`
+		/// when comparing to `1.0.1`).
+		pub(super) fn cmp_item(&self, other: Option<&Item>) -> Ordering {
+			match (self, other) {
+				(Item::Int(int), None) => if int == "0" { Ordering::Equal } else { Ordering::Greater },
+				(Item::Int(a), Some(Item::Int(b))) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+				(Item::Int(_), Some(Item::String(_) | Item::List(_))) => Ordering::Greater,
+
+				(Item::String(string), None) => string.cmp(&release()),
+				(Item::String(_), Some(Item::Int(_) | Item::List(_))) => Ordering::Less,
+				(Item::String(a), Some(Item::String(b))) => a.cmp(b),
+
+				(Item::List(list), None) => list.first().map_or(Ordering::Equal, |first| first.cmp_item(None)),
+				(Item::List(_), Some(Item::Int(_))) => Ordering::Less,
+				(Item::List(_), Some(Item::String(_))) => Ordering::Greater,
+				(Item::List(a), Some(Item::List(b))) => {
+					for i in 0..a.len().max(b.len()) {
+						let ordering = match (a.get(i), b.get(i)) {
+							(Some(a), b) => a.cmp_item(b),
+							(None, Some(b)) => b.cmp_item(None).reverse(),
+							(None, None) => unreachable!(),
+						};
+						if ordering != Ordering::Equal {
+							return ordering;
+						}
+					}
+					Ordering::Equal
+				},
+			}
+		}
+	}
+}
+
 fn to_snapshot_version(version: &str) -> Cow<str> {
 	// the pattern is: ^(.*)-(\d{8}.\d{6})-(\d+)$
 	version.rsplit_once('-')
@@ -295,10 +499,11 @@ impl Types {
 
 #[cfg(test)]
 mod testing {
+	use std::cmp::Ordering;
 	use std::str::FromStr;
 	use anyhow::Result;
 	use pretty_assertions::assert_eq;
-	use crate::coord::{MavenCoord, to_snapshot_version};
+	use crate::coord::{compare_versions, MavenCoord, to_snapshot_version};
 
 	#[test]
 	fn test_maven_coord_from_str() -> Result<()> {
@@ -323,6 +528,51 @@ mod testing {
 		Ok(())
 	}
 
+	#[test]
+	fn test_maven_coord_ord() -> Result<()> {
+		let mut coords = [
+			"org.example:b:1.0",
+			"org.example:a:1.10",
+			"org.example:a:1.9",
+			"org.example:a:jar:sources:1.0",
+			"org.example:a:1.9.1",
+			"com.example:z:2.0",
+		].map(MavenCoord::from_str).into_iter().collect::<Result<Vec<_>>>()?;
+
+		coords.sort();
+
+		let coords: Vec<String> = coords.iter().map(|coord| coord.to_string()).collect();
+		assert_eq!(coords, [
+			"com.example:z:jar:2.0",
+			"org.example:a:jar:1.9",
+			"org.example:a:jar:1.9.1",
+			"org.example:a:jar:1.10",
+			"org.example:a:jar:sources:1.0",
+			"org.example:b:jar:1.0",
+		]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_compare_versions() {
+		// each version is strictly less than the next one
+		let versions = [
+			"1-alpha", "1-alpha-1", "1-alpha-2", "1-a3", "1-beta", "1-milestone", "1-rc1", "1-SNAPSHOT",
+			"1", "1-sp", "1-sp2", "1-abc", "1.0.1", "1.1", "1.9", "1.10", "1.10.0.1", "2", "10",
+		];
+		for pair in versions.windows(2) {
+			assert_eq!(compare_versions(pair[0], pair[1]), Ordering::Less, "{:?} < {:?}", pair[0], pair[1]);
+			assert_eq!(compare_versions(pair[1], pair[0]), Ordering::Greater, "{:?} > {:?}", pair[1], pair[0]);
+		}
+
+		// these are all the same version
+		for version in ["1", "1.0", "1.0.0", "1-ga", "1-final", "1-release", "1.0-0", "01"] {
+			assert_eq!(compare_versions(version, "1"), Ordering::Equal, "{version:?} = \"1\"");
+		}
+		assert_eq!(compare_versions("1-cr1", "1-rc1"), Ordering::Equal);
+	}
+
 	#[test]
 	fn test_to_snapshot_version() {
 		assert_eq!(to_snapshot_version("vineflower-1.10.0"), "vineflower-1.10.0");
@@ -1,4 +1,5 @@
 pub mod coord;
+pub mod lockfile;
 pub mod maven_pom;
 mod maven_pom_done;
 pub mod resolver;
@@ -11,7 +12,7 @@ use std::str::FromStr;
 use anyhow::{anyhow, bail, Context, Error, Result};
 use serde::{Deserialize, Serialize};
 use crate::coord::MavenCoord;
-use crate::maven_pom::MavenPom;
+use crate::maven_pom::{ActivationContext, MavenPom};
 use crate::maven_pom_done::{get_merged_pom};
 use crate::resolver::Resolver;
 use crate::tree::{Forest, Tree};
@@ -77,16 +78,41 @@ impl MavenPom {
 			type_: "pom".to_owned()
 		})
 	}
+
+	/// Returns the relative path of the parent pom, if the parent pom should be looked up locally.
+	///
+	/// This is `../pom.xml` if no `<relativePath>` is given, and [`None`] if it's empty.
+	fn get_parent_relative_path(&self) -> Option<&str> {
+		self.parent.as_ref().and_then(|parent| match parent.relative_path.as_deref() {
+			None => Some("../pom.xml"),
+			Some(relative_path) if relative_path.trim().is_empty() => None,
+			Some(relative_path) => Some(relative_path.trim()),
+		})
+	}
+
+	/// Checks if this pom is the pom for the given coordinate, taking the inheritance of group and version into account.
+	fn is_pom_for(&self, coord: &MavenCoord) -> bool {
+		let group = self.group_id.as_ref().or(self.parent.as_ref().map(|parent| &parent.group_id));
+		let version = self.version.as_ref().or(self.parent.as_ref().map(|parent| &parent.version));
+
+		group == Some(&coord.group) && self.artifact_id == coord.artifact && version == Some(&coord.version)
+	}
 }
 
 // TODO: doc
 pub async fn get_maven_dependencies<'a>(downloader: &(impl Downloader + Sync), resolvers: &'a [Resolver<'_>],
 		dependencies_list: &[(MavenCoord, DependencyScope)]) -> Result<Vec<FoundDependency<'a>>> {
+	get_maven_dependencies_with_activation(downloader, resolvers, &ActivationContext::default(), dependencies_list).await
+}
+
+/// Like [`get_maven_dependencies`], but activates the profiles of the poms according to the given context.
+pub async fn get_maven_dependencies_with_activation<'a>(downloader: &(impl Downloader + Sync), resolvers: &'a [Resolver<'_>],
+		context: &ActivationContext, dependencies_list: &[(MavenCoord, DependencyScope)]) -> Result<Vec<FoundDependency<'a>>> {
 
 	let mut dependencies_forest = Vec::with_capacity(dependencies_list.len());
 
 	for (coord, sc) in dependencies_list {
-		let c = get_dependencies_tree(downloader, resolvers, coord, *sc).await?;
+		let c = get_dependencies_tree(downloader, resolvers, context, coord, *sc).await?;
 
 		dependencies_forest.push(c);
 	}
@@ -164,10 +190,10 @@ impl<'a> TryFrom<&'a str> for FoundDependency<'a> {
 
 // TODO: recursion limiter!
 #[async_recursion::async_recursion]
-async fn get_dependencies_tree<'a>(downloader: &(impl Downloader + Sync), resolvers: &'a [Resolver], coord: &MavenCoord, scope: DependencyScope)
+async fn get_dependencies_tree<'a>(downloader: &(impl Downloader + Sync), resolvers: &'a [Resolver], context: &ActivationContext, coord: &MavenCoord, scope: DependencyScope)
 		-> Result<Tree<FoundDependency<'a>>> {
 
-	let (resolver, pom) = get_merged_pom(downloader, resolvers, coord).await?;
+	let (resolver, pom) = get_merged_pom(downloader, resolvers, context, coord).await?;
 
 	let mut tree = Tree::new(FoundDependency {
 		resolver: resolver.clone(),
@@ -191,7 +217,7 @@ async fn get_dependencies_tree<'a>(downloader: &(impl Downloader + Sync), resolv
 
 			// this skips all the ones with None
 			if let Some(scope_after_table) = the_scope_table(scope, dependency_scope) {
-				let c = get_dependencies_tree(downloader, resolvers, &dependency.coord, scope_after_table).await?; // TODO: err msg
+				let c = get_dependencies_tree(downloader, resolvers, context, &dependency.coord, scope_after_table).await?; // TODO: err msg
 				tree.children.push(c);
 			}
 		}
@@ -212,7 +238,7 @@ mod testing {
 	use std::future::Future;
 	use anyhow::{Context, Result};
 	use crate::{Downloader, FoundDependency, get_dependencies_tree, MavenCoord, Resolver, DependencyScope};
-	use crate::maven_pom::{Dependencies, Dependency, MavenPom};
+	use crate::maven_pom::{ActivationContext, Dependencies, Dependency, MavenPom};
 
 	impl Downloader for HashMap<&'static str, MavenPom> {
 		// note: can't rewrite with async, bc of `+ Send`
@@ -260,6 +286,7 @@ mod testing {
 					],
 				}),
 				dependency_management: None,
+				profiles: None,
 			}),
 			("invalid://maven.example.com/foo/com/example/bar/0.2/bar-0.2.pom", MavenPom {
 				model_version: "4.0.0".to_string(),
@@ -270,6 +297,7 @@ mod testing {
 				packaging: None,
 				dependencies: None,
 				dependency_management: None,
+				profiles: None,
 			}),
 		]);
 
@@ -300,7 +328,7 @@ mod testing {
 
 		let wanted = MavenCoord::from_group_artifact_version("org.example", "foo", "0.1");
 
-		let x = get_dependencies_tree(&map, &resolvers, &wanted, DependencyScope::Runtime).await?;
+		let x = get_dependencies_tree(&map, &resolvers, &ActivationContext::default(), &wanted, DependencyScope::Runtime).await?;
 
 		let dependencies = x.into_breadth_first().collect::<Vec<_>>();
 
@@ -331,6 +359,168 @@ mod testing {
 
 		Ok(())
 	}
+
+	#[tokio::test]
+	async fn local_parent_via_relative_path() -> Result<()> {
+		const LOCAL: Resolver = Resolver::new("Local", "file:///repo");
+		let resolvers = [ LOCAL.clone() ];
+
+		// the parent isn't in the repository, it can only be found via the `<relativePath>`
+		let map = HashMap::from([
+			("file:///repo/org/example/child/0.1/child-0.1.pom", "<project>
+				<modelVersion>4.0.0</modelVersion>
+				<parent>
+					<groupId>org.example</groupId>
+					<artifactId>parent</artifactId>
+					<version>0.1</version>
+					<relativePath>../../parent</relativePath>
+				</parent>
+				<artifactId>child</artifactId>
+			</project>"),
+			("file:///repo/org/example/parent/pom.xml", "<project>
+				<modelVersion>4.0.0</modelVersion>
+				<groupId>org.example</groupId>
+				<artifactId>parent</artifactId>
+				<version>0.1</version>
+				<packaging>pom</packaging>
+				<dependencies>
+					<dependency>
+						<groupId>org.example</groupId>
+						<artifactId>bar</artifactId>
+						<version>0.2</version>
+					</dependency>
+				</dependencies>
+			</project>"),
+			("file:///repo/org/example/bar/0.2/bar-0.2.pom", "<project>
+				<modelVersion>4.0.0</modelVersion>
+				<groupId>org.example</groupId>
+				<artifactId>bar</artifactId>
+				<version>0.2</version>
+			</project>"),
+		]);
+
+		let wanted = MavenCoord::from_group_artifact_version("org.example", "child", "0.1");
+
+		let x = get_dependencies_tree(&map, &resolvers, &ActivationContext::default(), &wanted, DependencyScope::Runtime).await?;
+
+		let dependencies: Vec<_> = x.into_breadth_first()
+			.map(|dependency| dependency.coord.to_string())
+			.collect();
+
+		assert_eq!(dependencies, ["org.example:child:jar:0.1", "org.example:bar:jar:0.2"]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn broken_local_parent_falls_back_to_resolvers() -> Result<()> {
+		const LOCAL: Resolver = Resolver::new("Local", "file:///repo");
+		let resolvers = [ LOCAL.clone() ];
+
+		let map = HashMap::from([
+			("file:///repo/org/example/child/0.1/child-0.1.pom", "<project>
+				<modelVersion>4.0.0</modelVersion>
+				<parent>
+					<groupId>org.example</groupId>
+					<artifactId>parent</artifactId>
+					<version>0.1</version>
+				</parent>
+				<artifactId>child</artifactId>
+			</project>"),
+			// the default `<relativePath>` of `../pom.xml`, which isn't a pom
+			("file:///repo/org/example/child/pom.xml", "<project"),
+			("file:///repo/org/example/parent/0.1/parent-0.1.pom", "<project>
+				<modelVersion>4.0.0</modelVersion>
+				<groupId>org.example</groupId>
+				<artifactId>parent</artifactId>
+				<version>0.1</version>
+				<packaging>pom</packaging>
+				<dependencies>
+					<dependency>
+						<groupId>org.example</groupId>
+						<artifactId>bar</artifactId>
+						<version>0.2</version>
+					</dependency>
+				</dependencies>
+			</project>"),
+			("file:///repo/org/example/bar/0.2/bar-0.2.pom", "<project>
+				<modelVersion>4.0.0</modelVersion>
+				<groupId>org.example</groupId>
+				<artifactId>bar</artifactId>
+				<version>0.2</version>
+			</project>"),
+		]);
+
+		let wanted = MavenCoord::from_group_artifact_version("org.example", "child", "0.1");
+
+		let x = get_dependencies_tree(&map, &resolvers, &ActivationContext::default(), &wanted, DependencyScope::Runtime).await?;
+
+		let dependencies: Vec<_> = x.into_breadth_first()
+			.map(|dependency| dependency.coord.to_string())
+			.collect();
+
+		assert_eq!(dependencies, ["org.example:child:jar:0.1", "org.example:bar:jar:0.2"]);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn profile_activated_by_property() -> Result<()> {
+		const LOCAL: Resolver = Resolver::new("Local", "file:///repo");
+		let resolvers = [ LOCAL.clone() ];
+
+		let map = HashMap::from([
+			("file:///repo/org/example/foo/0.1/foo-0.1.pom", "<project>
+				<modelVersion>4.0.0</modelVersion>
+				<groupId>org.example</groupId>
+				<artifactId>foo</artifactId>
+				<version>0.1</version>
+				<profiles>
+					<profile>
+						<id>extra</id>
+						<activation>
+							<property>
+								<name>withExtra</name>
+							</property>
+						</activation>
+						<dependencies>
+							<dependency>
+								<groupId>org.example</groupId>
+								<artifactId>extra</artifactId>
+								<version>0.2</version>
+							</dependency>
+						</dependencies>
+					</profile>
+				</profiles>
+			</project>"),
+			("file:///repo/org/example/extra/0.2/extra-0.2.pom", "<project>
+				<modelVersion>4.0.0</modelVersion>
+				<groupId>org.example</groupId>
+				<artifactId>extra</artifactId>
+				<version>0.2</version>
+			</project>"),
+		]);
+
+		let wanted = MavenCoord::from_group_artifact_version("org.example", "foo", "0.1");
+
+		let without = get_dependencies_tree(&map, &resolvers, &ActivationContext::default(), &wanted, DependencyScope::Runtime).await?;
+		let without: Vec<_> = without.into_breadth_first()
+			.map(|dependency| dependency.coord.to_string())
+			.collect();
+		assert_eq!(without, ["org.example:foo:jar:0.1"]);
+
+		let context = ActivationContext {
+			jdk: None,
+			properties: HashMap::from([("withExtra".to_owned(), "true".to_owned())]),
+		};
+		let with = get_dependencies_tree(&map, &resolvers, &context, &wanted, DependencyScope::Runtime).await?;
+		let with: Vec<_> = with.into_breadth_first()
+			.map(|dependency| dependency.coord.to_string())
+			.collect();
+		assert_eq!(with, ["org.example:foo:jar:0.1", "org.example:extra:jar:0.2"]);
+
+		Ok(())
+	}
 }
 
 
@@ -0,0 +1,139 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use anyhow::{anyhow, bail, Context, Result};
+use crate::coord::MavenCoord;
+use crate::{DependencyScope, FoundDependency};
+
+/// A resolved set of dependencies, together with the dependencies that were requested to get it.
+///
+/// Storing this in a file allows to skip resolving the dependencies over the network.
+///
+/// [Lockfile] implements [Display], and can be read back with [Lockfile::read]. The format is line based:
+/// - each requested dependency is on a line `requested group:artifact:type[:classifier]:version:scope`,
+/// - each resolved dependency is on a line, in the format of [FoundDependency],
+/// - empty lines and lines starting with `#` are ignored.
+///
+/// Use [Lockfile::check_requested] to see if a lockfile read is still up to date.
+#[derive(Debug, PartialEq)]
+pub struct Lockfile<'a> {
+	pub requested: Vec<(MavenCoord, DependencyScope)>,
+	pub dependencies: Vec<FoundDependency<'a>>,
+}
+
+impl<'a> Lockfile<'a> {
+	/// Reads a lockfile, as written by the [Display] implementation.
+	///
+	/// Note: round trips remove the repositories name, see [FoundDependency].
+	pub fn read(s: &'a str) -> Result<Lockfile<'a>> {
+		let mut requested = Vec::new();
+		let mut dependencies = Vec::new();
+
+		for (line_number, line) in s.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			if let Some(coord) = line.strip_prefix("requested ") {
+				let (coord, scope) = coord.rsplit_once(':')
+					.with_context(|| anyhow!("expected \":\" to separate the scope from the coordinate: {line:?}"))
+					.and_then(|(coord, scope)| Ok((MavenCoord::from_str(coord)?, DependencyScope::from_str(scope)?)))
+					.with_context(|| anyhow!("failed to parse requested dependency on line {}", line_number + 1))?;
+				requested.push((coord, scope));
+			} else {
+				let dependency = FoundDependency::try_from(line)
+					.with_context(|| anyhow!("failed to parse resolved dependency on line {}", line_number + 1))?;
+				dependencies.push(dependency);
+			}
+		}
+
+		Ok(Lockfile { requested, dependencies })
+	}
+}
+
+impl Lockfile<'_> {
+	/// Checks that the lockfile was made for exactly the `requested` dependencies.
+	///
+	/// If this fails, the lockfile is stale, and the dependencies need to be resolved again.
+	pub fn check_requested(&self, requested: &[(MavenCoord, DependencyScope)]) -> Result<()> {
+		if self.requested != requested {
+			let format = |requested: &[(MavenCoord, DependencyScope)]| requested.iter()
+				.map(|(coord, scope)| format!("{coord}:{scope}"))
+				.collect::<Vec<_>>();
+
+			bail!("lockfile is stale: it was made for the dependencies {:?}, but {:?} were requested",
+				format(&self.requested), format(requested));
+		}
+		Ok(())
+	}
+}
+
+impl Display for Lockfile<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		for (coord, scope) in &self.requested {
+			writeln!(f, "requested {coord}:{scope}")?;
+		}
+		for dependency in &self.dependencies {
+			writeln!(f, "{dependency}")?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use pretty_assertions::assert_eq;
+	use anyhow::Result;
+	use crate::coord::MavenCoord;
+	use crate::{DependencyScope, FoundDependency};
+	use crate::lockfile::Lockfile;
+	use crate::resolver::Resolver;
+
+	#[test]
+	fn round_trip() -> Result<()> {
+		let requested = vec![
+			(MavenCoord::from_group_artifact_version("org.example", "foo", "0.1"), DependencyScope::Runtime),
+		];
+		let lockfile = Lockfile {
+			requested: requested.clone(),
+			dependencies: vec![
+				FoundDependency {
+					resolver: Resolver::new("invalid://maven.example.org", "invalid://maven.example.org"),
+					coord: MavenCoord::from_group_artifact_version("org.example", "foo", "0.1"),
+					scope: DependencyScope::Runtime,
+				},
+				FoundDependency {
+					resolver: Resolver::new("invalid://maven.example.com/foo/", "invalid://maven.example.com/foo/"),
+					coord: MavenCoord {
+						group: "com.example".to_owned(),
+						artifact: "bar".to_owned(),
+						version: "0.2".to_owned(),
+						classifier: Some("extra".to_owned()),
+						type_: "jar".to_owned(),
+					},
+					scope: DependencyScope::Runtime,
+				},
+			],
+		};
+
+		let written = lockfile.to_string();
+		assert_eq!(written, "\
+requested org.example:foo:jar:0.1:runtime
+org.example:foo:jar:0.1:runtime @ invalid://maven.example.org
+com.example:bar:jar:extra:0.2:runtime @ invalid://maven.example.com/foo/
+");
+
+		let read = Lockfile::read(&written)?;
+		assert_eq!(read, lockfile);
+
+		read.check_requested(&requested)?;
+
+		let changed = [
+			(MavenCoord::from_group_artifact_version("org.example", "foo", "0.2"), DependencyScope::Runtime),
+		];
+		assert!(read.check_requested(&changed).is_err());
+		assert!(read.check_requested(&[]).is_err());
+
+		Ok(())
+	}
+}
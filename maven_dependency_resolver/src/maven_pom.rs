@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Write};
+use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 use crate::DependencyScope;
 
@@ -11,7 +14,7 @@ use crate::DependencyScope;
 /// without this we wouldn't know if a value is explicitly overwritten or just the default. We couldn't distinguish between
 /// a default value of `false` and an explicit overwrite of a `true` with a `false`. (In the first case we'd want the effective
 /// value to be `true`, since that's inherited, and in the second case we'd want `false`).
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct MavenPom {
 	#[serde(rename = "modelVersion")]
 	pub(crate) model_version: String,
@@ -29,30 +32,374 @@ pub struct MavenPom {
 	#[serde(rename = "dependencyManagement")]
 	pub(crate) dependency_management: Option<DependencyManagement>,
 	pub(crate) dependencies: Option<Dependencies<DependencyScope>>,
+
+	pub(crate) profiles: Option<Profiles>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+impl MavenPom {
+	/// Writes this pom as xml, in the format of a `.pom` file.
+	///
+	/// The elements are written in the order given by the maven xsd. Absent optional elements aren't written.
+	pub fn to_xml(&self) -> Result<String> {
+		let mut w = XmlWriter::default();
+
+		w.s.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+		w.s.push_str("<project xmlns=\"http://maven.apache.org/POM/4.0.0\" \
+			xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+			xsi:schemaLocation=\"http://maven.apache.org/POM/4.0.0 https://maven.apache.org/xsd/maven-4.0.0.xsd\">\n");
+		w.depth += 1;
+
+		w.element("modelVersion", &self.model_version)?;
+		if let Some(parent) = &self.parent {
+			w.start("parent")?;
+			w.element("groupId", &parent.group_id)?;
+			w.element("artifactId", &parent.artifact_id)?;
+			w.element("version", &parent.version)?;
+			w.optional_element("relativePath", parent.relative_path.as_ref())?;
+			w.end("parent")?;
+		}
+		w.optional_element("groupId", self.group_id.as_ref())?;
+		w.element("artifactId", &self.artifact_id)?;
+		w.optional_element("version", self.version.as_ref())?;
+		w.optional_element("packaging", self.packaging.as_ref())?;
+		if let Some(dependency_management) = &self.dependency_management {
+			w.start("dependencyManagement")?;
+			if let Some(dependencies) = &dependency_management.dependencies {
+				w.dependencies(dependencies)?;
+			}
+			w.end("dependencyManagement")?;
+		}
+		if let Some(dependencies) = &self.dependencies {
+			w.dependencies(dependencies)?;
+		}
+		if let Some(profiles) = &self.profiles {
+			w.start("profiles")?;
+			for profile in &profiles.profile {
+				w.start("profile")?;
+				w.optional_element("id", profile.id.as_ref())?;
+				if let Some(activation) = &profile.activation {
+					w.start("activation")?;
+					w.optional_element("activeByDefault", activation.active_by_default.as_ref())?;
+					w.optional_element("jdk", activation.jdk.as_ref())?;
+					if let Some(property) = &activation.property {
+						w.start("property")?;
+						w.element("name", &property.name)?;
+						w.optional_element("value", property.value.as_ref())?;
+						w.end("property")?;
+					}
+					w.end("activation")?;
+				}
+				if let Some(dependency_management) = &profile.dependency_management {
+					w.start("dependencyManagement")?;
+					if let Some(dependencies) = &dependency_management.dependencies {
+						w.dependencies(dependencies)?;
+					}
+					w.end("dependencyManagement")?;
+				}
+				if let Some(dependencies) = &profile.dependencies {
+					w.dependencies(dependencies)?;
+				}
+				w.end("profile")?;
+			}
+			w.end("profiles")?;
+		}
+
+		w.depth -= 1;
+		w.s.push_str("</project>\n");
+
+		Ok(w.s)
+	}
+
+	/// Merges the dependencies and the dependency management of all profiles activated by the context into this pom.
+	///
+	/// The profiles are removed afterwards. Like maven, the profiles that are `activeByDefault` are only activated if no
+	/// other profile of this pom is activated by the context. See [`Activation`] for when a profile is activated.
+	pub(crate) fn apply_profiles(&mut self, context: &ActivationContext) -> Result<()> {
+		let Some(profiles) = self.profiles.take() else {
+			return Ok(());
+		};
+
+		let mut activated = Vec::new();
+		for profile in &profiles.profile {
+			let is_active = match &profile.activation {
+				Some(activation) => activation.is_active(context)
+					.with_context(|| anyhow!("failed to check activation of profile {:?}", profile.id))?,
+				None => false,
+			};
+			activated.push(is_active);
+		}
+
+		if !activated.contains(&true) {
+			activated = profiles.profile.iter()
+				.map(|profile| profile.activation.as_ref().and_then(|activation| activation.active_by_default).unwrap_or(false))
+				.collect();
+		}
+
+		for (profile, is_active) in profiles.profile.into_iter().zip(activated) {
+			if !is_active {
+				continue;
+			}
+
+			if let Some(dependencies) = profile.dependency_management.and_then(|management| management.dependencies) {
+				self.dependency_management.get_or_insert(DependencyManagement { dependencies: None })
+					.dependencies.get_or_insert(Dependencies { dependency: Vec::new() })
+					.dependency.extend(dependencies.dependency);
+			}
+			if let Some(dependencies) = profile.dependencies {
+				self.dependencies.get_or_insert(Dependencies { dependency: Vec::new() })
+					.dependency.extend(dependencies.dependency);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// The environment used for deciding which [`Profile`]s of a pom are activated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActivationContext {
+	/// The java version, like `1.8` or `17.0.2`.
+	///
+	/// If this is [`None`], no profile is activated by a `<jdk>` condition.
+	pub jdk: Option<String>,
+	/// The properties that are set, with their values.
+	pub properties: HashMap<String, String>,
+}
+
+/// A helper for writing indented xml.
+#[derive(Default)]
+struct XmlWriter {
+	s: String,
+	depth: usize,
+}
+
+impl XmlWriter {
+	fn indent(&mut self) {
+		for _ in 0..self.depth {
+			self.s.push('\t');
+		}
+	}
+
+	fn start(&mut self, name: &str) -> Result<()> {
+		self.indent();
+		writeln!(self.s, "<{name}>")?;
+		self.depth += 1;
+		Ok(())
+	}
+
+	fn end(&mut self, name: &str) -> Result<()> {
+		self.depth -= 1;
+		self.indent();
+		writeln!(self.s, "</{name}>")?;
+		Ok(())
+	}
+
+	fn element(&mut self, name: &str, value: &impl Display) -> Result<()> {
+		self.indent();
+		writeln!(self.s, "<{name}>{}</{name}>", Escaped(&value.to_string()))?;
+		Ok(())
+	}
+
+	fn optional_element(&mut self, name: &str, value: Option<&impl Display>) -> Result<()> {
+		if let Some(value) = value {
+			self.element(name, value)?;
+		}
+		Ok(())
+	}
+
+	fn dependencies<Scope: Display>(&mut self, dependencies: &Dependencies<Scope>) -> Result<()> {
+		self.start("dependencies")?;
+		for dependency in &dependencies.dependency {
+			self.start("dependency")?;
+			self.element("groupId", &dependency.group_id)?;
+			self.element("artifactId", &dependency.artifact_id)?;
+			self.optional_element("version", dependency.version.as_ref())?;
+			self.optional_element("type", dependency.type_.as_ref())?;
+			self.optional_element("classifier", dependency.classifier.as_ref())?;
+			self.optional_element("scope", dependency.scope.as_ref())?;
+			self.optional_element("optional", dependency.optional.as_ref())?;
+			self.end("dependency")?;
+		}
+		self.end("dependencies")
+	}
+}
+
+/// Escapes the characters with a special meaning in xml when displayed.
+struct Escaped<'a>(&'a str);
+
+impl Display for Escaped<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		for c in self.0.chars() {
+			match c {
+				'&' => f.write_str("&amp;")?,
+				'<' => f.write_str("&lt;")?,
+				'>' => f.write_str("&gt;")?,
+				'"' => f.write_str("&quot;")?,
+				'\'' => f.write_str("&apos;")?,
+				c => f.write_char(c)?,
+			}
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub(crate) struct Parent {
 	#[serde(rename = "groupId")]
 	pub(crate) group_id: String,
 	#[serde(rename = "artifactId")]
 	pub(crate) artifact_id: String,
 	pub(crate) version: String,
-	// TODO: relativePath
+	/// The path of the parent pom, relative to the child pom.
+	///
+	/// If absent, this defaults to `../pom.xml`. An empty value means that the parent is never looked up locally.
+	#[serde(rename = "relativePath")]
+	pub(crate) relative_path: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Profiles {
+	pub(crate) profile: Vec<Profile>,
+}
+
+/// A profile, which adds dependencies and dependency management to the pom if it's activated.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Profile {
+	pub(crate) id: Option<String>,
+	pub(crate) activation: Option<Activation>,
+
+	#[serde(rename = "dependencyManagement")]
+	pub(crate) dependency_management: Option<DependencyManagement>,
+	pub(crate) dependencies: Option<Dependencies<DependencyScope>>,
+}
+
+/// The conditions for activating a [`Profile`].
+///
+/// All the conditions given must be met for the profile to be activated. The `activeByDefault` isn't a condition, see
+/// [`MavenPom::apply_profiles`].
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Activation {
+	#[serde(rename = "activeByDefault")]
+	pub(crate) active_by_default: Option<bool>,
+	/// Either a prefix of the java version, like `1.8`, a negated prefix, like `!1.8`, or a version range, like `[11,)`.
+	pub(crate) jdk: Option<String>,
+	pub(crate) property: Option<ActivationProperty>,
+}
+
+/// A property condition for activating a [`Profile`].
+///
+/// Without a `value`, the property must be set. With a `value`, the property must have exactly that value. Both the `name`
+/// and the `value` can start with `!` to negate the condition.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct ActivationProperty {
+	pub(crate) name: String,
+	pub(crate) value: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+impl Activation {
+	/// Checks if the conditions of this activation are all met. Returns `false` if there are no conditions.
+	fn is_active(&self, context: &ActivationContext) -> Result<bool> {
+		if self.jdk.is_none() && self.property.is_none() {
+			return Ok(false);
+		}
+
+		if let Some(jdk) = &self.jdk {
+			let Some(java_version) = &context.jdk else {
+				return Ok(false);
+			};
+			if !jdk_matches(jdk.trim(), java_version)? {
+				return Ok(false);
+			}
+		}
+
+		if let Some(property) = &self.property {
+			let (negated, name) = match property.name.strip_prefix('!') {
+				Some(name) => (true, name),
+				None => (false, property.name.as_str()),
+			};
+			let actual = context.properties.get(name);
+
+			let matches = match &property.value {
+				// `!name` means that the property is not set
+				None => actual.is_some() != negated,
+				Some(value) => match value.strip_prefix('!') {
+					Some(value) => actual.is_none_or(|actual| actual != value),
+					None => actual.is_some_and(|actual| actual == value),
+				},
+			};
+			if !matches {
+				return Ok(false);
+			}
+		}
+
+		Ok(true)
+	}
+}
+
+/// Checks if the java version matches the `<jdk>` condition of a profile.
+fn jdk_matches(jdk: &str, java_version: &str) -> Result<bool> {
+	if let Some(prefix) = jdk.strip_prefix('!') {
+		return Ok(!java_version.starts_with(prefix.trim()));
+	}
+	if !jdk.starts_with(['[', '(']) {
+		return Ok(java_version.starts_with(jdk));
+	}
+
+	fn parse(version: &str) -> Vec<u32> {
+		version.split(['.', '_', '-'])
+			.map_while(|part| part.trim().parse().ok())
+			.collect()
+	}
+
+	let version = parse(java_version);
+
+	// a union of ranges, like `[1.5,1.8),[11,)`
+	let mut rest = jdk;
+	while !rest.is_empty() {
+		let end = rest.find([']', ')'])
+			.with_context(|| anyhow!("unclosed version range in jdk activation {jdk:?}"))?;
+		let (range, after) = rest.split_at(end + 1);
+		rest = after.trim_start_matches([',', ' ']);
+
+		let lower_inclusive = range.starts_with('[');
+		let upper_inclusive = range.ends_with(']');
+		let inner = &range[1..range.len() - 1];
+
+		let (lower, upper) = match inner.split_once(',') {
+			Some((lower, upper)) => (lower.trim(), upper.trim()),
+			// `[1.8]` is exactly that version
+			None => (inner.trim(), inner.trim()),
+		};
+
+		let above_lower = lower.is_empty() || {
+			let lower = parse(lower);
+			if lower_inclusive { version >= lower } else { version > lower }
+		};
+		let below_upper = upper.is_empty() || {
+			let upper = parse(upper);
+			if upper_inclusive { version <= upper } else { version < upper }
+		};
+
+		if above_lower && below_upper {
+			return Ok(true);
+		}
+	}
+
+	Ok(false)
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub(crate) struct DependencyManagement {
 	pub(crate) dependencies: Option<Dependencies<ManagementScope>>,
 }
 
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub(crate) struct Dependencies<Scope> {
 	pub(crate) dependency: Vec<Dependency<Scope>>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub(crate) struct Dependency<Scope> {
 	#[serde(rename = "groupId")]
 	pub(crate) group_id: String,
@@ -93,6 +440,15 @@ pub(crate) enum ManagementScope {
 	Import,
 }
 
+impl Display for ManagementScope {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self.into_scope() {
+			Some(scope) => Display::fmt(&scope, f),
+			None => Display::fmt("import", f),
+		}
+	}
+}
+
 impl ManagementScope {
 	/// Returns `None` for [ManagementScope::Import].
 	pub(crate) fn into_scope(self) -> Option<DependencyScope> {
@@ -106,3 +462,107 @@ impl ManagementScope {
 		}
 	}
 }
+
+#[cfg(test)]
+mod testing {
+	use anyhow::Result;
+	use pretty_assertions::assert_eq;
+	use crate::maven_pom::MavenPom;
+
+	#[test]
+	fn to_xml_round_trip() -> Result<()> {
+		let xml = "<project>
+			<modelVersion>4.0.0</modelVersion>
+			<groupId>org.example</groupId>
+			<artifactId>foo</artifactId>
+			<version>0.1</version>
+			<dependencies>
+				<dependency>
+					<groupId>com.example</groupId>
+					<artifactId>bar</artifactId>
+					<version>0.2</version>
+					<classifier>extra</classifier>
+				</dependency>
+			</dependencies>
+		</project>";
+		let pom: MavenPom = serde_xml_rs::from_str(xml)?;
+
+		let written = pom.to_xml()?;
+		assert_eq!(written, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<project xmlns=\"http://maven.apache.org/POM/4.0.0\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+xsi:schemaLocation=\"http://maven.apache.org/POM/4.0.0 https://maven.apache.org/xsd/maven-4.0.0.xsd\">
+	<modelVersion>4.0.0</modelVersion>
+	<groupId>org.example</groupId>
+	<artifactId>foo</artifactId>
+	<version>0.1</version>
+	<dependencies>
+		<dependency>
+			<groupId>com.example</groupId>
+			<artifactId>bar</artifactId>
+			<version>0.2</version>
+			<classifier>extra</classifier>
+		</dependency>
+	</dependencies>
+</project>
+");
+
+		let read: MavenPom = serde_xml_rs::from_str(&written)?;
+		assert_eq!(read, pom);
+
+		Ok(())
+	}
+
+	#[test]
+	fn to_xml_escapes_and_writes_management() -> Result<()> {
+		let xml = "<project>
+			<modelVersion>4.0.0</modelVersion>
+			<parent>
+				<groupId>org.example</groupId>
+				<artifactId>parent</artifactId>
+				<version>1</version>
+				<relativePath></relativePath>
+			</parent>
+			<artifactId>a&amp;b</artifactId>
+			<packaging>pom</packaging>
+			<dependencyManagement>
+				<dependencies>
+					<dependency>
+						<groupId>org.example</groupId>
+						<artifactId>bom</artifactId>
+						<version>2</version>
+						<type>pom</type>
+						<scope>import</scope>
+						<optional>true</optional>
+					</dependency>
+				</dependencies>
+			</dependencyManagement>
+		</project>";
+		let pom: MavenPom = serde_xml_rs::from_str(xml)?;
+
+		let written = pom.to_xml()?;
+		assert!(written.contains("<artifactId>a&amp;b</artifactId>"));
+		assert!(written.contains("<scope>import</scope>"));
+
+		let read: MavenPom = serde_xml_rs::from_str(&written)?;
+		assert_eq!(read, pom);
+
+		Ok(())
+	}
+
+	#[test]
+	fn jdk_activation() -> Result<()> {
+		use super::jdk_matches;
+
+		assert!(jdk_matches("1.8", "1.8.0_292")?);
+		assert!(!jdk_matches("1.8", "17.0.2")?);
+		assert!(jdk_matches("!1.8", "17.0.2")?);
+		assert!(jdk_matches("[11,)", "17.0.2")?);
+		assert!(!jdk_matches("[11,)", "1.8.0_292")?);
+		assert!(jdk_matches("[1.5,1.8)", "1.7")?);
+		assert!(!jdk_matches("[1.5,1.8)", "1.8")?);
+		assert!(jdk_matches("(,1.5],[11,)", "21")?);
+		assert!(jdk_matches("[1.5,1.8", "1.7").is_err());
+
+		Ok(())
+	}
+}
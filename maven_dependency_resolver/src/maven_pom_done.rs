@@ -1,8 +1,9 @@
 use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
 use crate::coord::{MavenCoord, Types};
-use crate::maven_pom::{Dependencies, DependencyManagement, MavenPom};
+use crate::maven_pom::{ActivationContext, Dependencies, DependencyManagement, MavenPom};
 use crate::{Downloader, DependencyScope};
-use crate::resolver::{Resolver, try_get_pom_for};
+use crate::resolver::{make_relative_pom_url, Resolver, try_get_pom_for};
 
 /// A maven pom after inheriting from the parent.
 #[derive(Debug, Clone)]
@@ -27,42 +28,72 @@ pub(crate) struct DependencyDone {
 /// See also the maven goal `help:effective-pom` (which can be run with `mvn help:effective-pom`).
 ///
 /// This includes inheriting from the parent poms and resolving `<scope>import</scope>` in `<dependencyManagement>`.
+///
+/// The profiles activated by the `context` are applied to each pom before inheriting, see [`MavenPom::apply_profiles`].
+///
+/// Resolvers with the same url as an earlier one are skipped, see [`Resolver`]'s equality.
 #[async_recursion::async_recursion]
-pub(crate) async fn get_merged_pom<'a>(downloader: &(impl Downloader + Sync), resolvers: &'a [Resolver], coord: &MavenCoord)
+pub(crate) async fn get_merged_pom<'a>(downloader: &(impl Downloader + Sync), resolvers: &'a [Resolver], context: &ActivationContext, coord: &MavenCoord)
 		-> Result<(&'a Resolver<'a>, MavenPomDone)> {
 
 	let (resolver, pom) = try_get_pom_for(downloader, resolvers, coord).await?;
 
 	let mut poms_stack = Vec::new();
 
-	let mut to_get = pom.get_parent_coord();
-	while let Some(coord) = to_get.take() {
-		let (_, pom) = try_get_pom_for(downloader, resolvers, &coord).await?;
-
-		to_get = pom.get_parent_coord();
-
-		poms_stack.push(pom);
+	let mut child_url = coord.make_pom_url(resolver);
+	let mut child = &pom;
+	while let Some((url, parent)) = get_parent_pom(downloader, resolvers, child, &child_url).await? {
+		child_url = url;
+		poms_stack.push(parent);
+		child = poms_stack.last().context("just pushed an element")?;
 	}
 
 	let mut parent = None;
 	for pom in poms_stack.into_iter().rev() {
 
-		let merged = merge_parent(downloader, resolvers, parent, pom).await
+		let merged = merge_parent(downloader, resolvers, context, parent, pom).await
 			.with_context(|| anyhow!("merging parent and child for child from {resolver:?} and {coord}"))?;
 
 		parent = Some(merged);
 	}
 
-	let merged = merge_parent(downloader, resolvers, parent, pom).await
+	let merged = merge_parent(downloader, resolvers, context, parent, pom).await
 		.with_context(|| anyhow!("merging parent and child for child from {resolver:?} and {coord}"))?;
 
 	Ok((resolver, merged))
 }
 
 
+/// Gets the parent pom of the pom `child` (which was read from `child_url`), together with the url it was read from.
+///
+/// If the `<relativePath>` of the parent points to a local pom for the parent, that one is used. Otherwise, the parent is
+/// taken from the resolvers. This includes the case of a local pom that can't be read, which is only warned about, like
+/// maven does.
+async fn get_parent_pom(downloader: &impl Downloader, resolvers: &[Resolver<'_>], child: &MavenPom, child_url: &str)
+		-> Result<Option<(String, MavenPom)>> {
+	let Some(coord) = child.get_parent_coord() else {
+		return Ok(None);
+	};
+
+	if let Some(url) = child.get_parent_relative_path().and_then(|path| make_relative_pom_url(child_url, path)) {
+		match downloader.get_maven_pom(&url).await {
+			// maven ignores local poms that don't match the parent coordinate
+			Ok(Some(pom)) if pom.is_pom_for(&coord) => return Ok(Some((url, pom))),
+			Ok(_) => {},
+			Err(e) => warn!("failed to read local parent pom {url:?} for {coord}, taking it from the resolvers instead: {e:#}"),
+		}
+	}
+
+	let (resolver, pom) = try_get_pom_for(downloader, resolvers, &coord).await?;
+	Ok(Some((coord.make_pom_url(resolver), pom)))
+}
+
 #[async_recursion::async_recursion]
-async fn merge_parent(downloader: &(impl Downloader + Sync), resolvers: &[Resolver], parent: Option<MavenPomDone>, child: MavenPom)
+async fn merge_parent(downloader: &(impl Downloader + Sync), resolvers: &[Resolver], context: &ActivationContext, parent: Option<MavenPomDone>, mut child: MavenPom)
 		-> Result<MavenPomDone> {
+	child.apply_profiles(context)
+		.with_context(|| anyhow!("while applying profiles of {}", child.artifact_id))?;
+
 	if let Some(parent) = parent {
 		let coord = MavenCoord {
 			group: child.group_id.unwrap_or(parent.coord.group),
@@ -78,7 +109,7 @@ async fn merge_parent(downloader: &(impl Downloader + Sync), resolvers: &[Resolv
 			},
 		};
 
-		let dependency_management = make_dependency_management(downloader, resolvers,
+		let dependency_management = make_dependency_management(downloader, resolvers, context,
 			child.dependency_management, Some(parent.dependency_management)
 		).await
 			.with_context(|| anyhow!("while creating `dependency_management` for {coord} (with a real parent)"))?;
@@ -101,7 +132,7 @@ async fn merge_parent(downloader: &(impl Downloader + Sync), resolvers: &[Resolv
 			type_: Types::packaging_to_type(child.packaging.as_deref().unwrap_or("jar")).to_owned(),
 		};
 
-		let dependency_management = make_dependency_management(downloader, resolvers,
+		let dependency_management = make_dependency_management(downloader, resolvers, context,
 			child.dependency_management, None
 		).await
 			.with_context(|| anyhow!("while creating `dependency_management` for {coord} (parent is super pom)"))?;
@@ -119,7 +150,7 @@ async fn merge_parent(downloader: &(impl Downloader + Sync), resolvers: &[Resolv
 
 
 #[async_recursion::async_recursion]
-async fn make_dependency_management(downloader: &(impl Downloader + Sync), resolvers: &[Resolver],
+async fn make_dependency_management(downloader: &(impl Downloader + Sync), resolvers: &[Resolver], context: &ActivationContext,
 	child_dependency_management: Option<DependencyManagement>,
 	parent_dependency_management: Option<Vec<DependencyDone>>,
 ) -> Result<Vec<DependencyDone>> {
@@ -145,7 +176,7 @@ async fn make_dependency_management(downloader: &(impl Downloader + Sync), resol
 			Some(Some(scope)) => Some(scope),
 			Some(None) => { // import scope
 				// TODO: put in a recursion limiter!
-				let target_pom: MavenPomDone = get_merged_pom(downloader, resolvers, &coord).await?.1;
+				let target_pom: MavenPomDone = get_merged_pom(downloader, resolvers, context, &coord).await?.1;
 
 				vec.extend(target_pom.dependency_management);
 
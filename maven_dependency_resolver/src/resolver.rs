@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::collections::HashSet;
 use anyhow::{anyhow, bail, Context, Result};
 use std::future::Future;
 use log::trace;
@@ -7,7 +8,7 @@ use crate::Downloader;
 use crate::maven_pom::MavenPom;
 
 /// Represents a maven repository.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Resolver<'a> {
 	pub name: Cow<'a, str>,
 	/// The url of the maven repo.
@@ -20,15 +21,42 @@ impl Resolver<'_> {
 	pub const fn new<'a>(name: &'a str, maven: &'a str) -> Resolver<'a> {
 		Resolver { name: Cow::Borrowed(name), maven: Cow::Borrowed(maven) }
 	}
+
+	/// Returns the url of the maven repo, with the scheme in lowercase and always ending with a `/`.
+	///
+	/// ```
+	/// # use maven_dependency_resolver::resolver::Resolver;
+	/// assert_eq!(Resolver::new("a", "HTTPS://maven.example.org").normalized_maven(), "https://maven.example.org/");
+	/// assert_eq!(Resolver::new("b", "https://maven.example.org/").normalized_maven(), "https://maven.example.org/");
+	/// ```
+	pub fn normalized_maven(&self) -> String {
+		let mut maven = match self.maven.split_once("://") {
+			Some((scheme, rest)) => format!("{}://{rest}", scheme.to_ascii_lowercase()),
+			None => self.maven.to_string(),
+		};
+		if !maven.ends_with('/') {
+			maven.push('/');
+		}
+		maven
+	}
 }
 
 /// Tries the given resolvers until one returns `Some(_)`.
+///
+/// Resolvers with the same [normalized url][Resolver::normalized_maven] as an earlier one are skipped, so that the same
+/// repository isn't queried twice.
 pub(crate) async fn try_resolvers<'a, T, F: Future<Output = Result<Option<T>>>>(
 	resolvers: &'a [Resolver<'a>],
 	url_maker: impl Fn(&Resolver) -> String,
 	downloader: impl Fn(String) -> F, // with HKT we'd use &str here, and we wouldn't need the .clone() below...
 ) -> Result<(&'a Resolver<'a>, T)> {
+	let mut tried = HashSet::new();
 	for resolver in resolvers {
+		if !tried.insert(resolver.normalized_maven()) {
+			trace!("skipping resolver {:?}, as it has the same url as an earlier one", resolver.name);
+			continue;
+		}
+
 		let url = url_maker(resolver);
 
 		trace!("trying resolver {:?} with {url:?}", resolver.name);
@@ -49,6 +77,36 @@ fn make_metadata_url(maven: &str, group: &str, artifact: &str) -> String {
 	)
 }
 
+/// Makes the url of a pom given by a path relative to the pom at `pom_url`, like the `<relativePath>` of a parent.
+///
+/// If the relative path doesn't end in `.xml` or `.pom`, it's considered to be a directory containing a `pom.xml`.
+///
+/// This only works for `file://` urls, since relative paths only make sense for local builds. For all other urls, this
+/// returns [`None`].
+pub(crate) fn make_relative_pom_url(pom_url: &str, relative_path: &str) -> Option<String> {
+	let path = pom_url.strip_prefix("file://")?;
+	let (directory, _) = path.rsplit_once('/')?;
+
+	let mut segments: Vec<&str> = directory.split('/').collect();
+	for segment in relative_path.split(['/', '\\']) {
+		match segment {
+			"" | "." => {},
+			".." => {
+				// keep the root, like for `/..`
+				if segments.len() > 1 {
+					segments.pop();
+				}
+			},
+			segment => segments.push(segment),
+		}
+	}
+	if !relative_path.ends_with(".xml") && !relative_path.ends_with(".pom") {
+		segments.push("pom.xml");
+	}
+
+	Some(format!("file://{}", segments.join("/")))
+}
+
 pub(crate) async fn try_get_pom_for<'a>(downloader: &impl Downloader, resolvers: &'a [Resolver<'a>], coord: &MavenCoord)
 		-> Result<(&'a Resolver<'a>, MavenPom)> {
 	try_resolvers(
@@ -70,10 +128,15 @@ pub(crate) async fn try_get_pom_for<'a>(downloader: &impl Downloader, resolvers:
 
 #[cfg(test)]
 mod testing {
-	use anyhow::{anyhow, Result};
+	use std::future::Future;
+	use std::sync::Mutex;
+	use anyhow::{anyhow, Context, Result};
 	use pretty_assertions::assert_eq;
-	use crate::Resolver;
-	use crate::resolver::try_resolvers;
+	use crate::{Downloader, Resolver};
+	use crate::coord::MavenCoord;
+	use crate::maven_pom::{ActivationContext, MavenPom};
+	use crate::maven_pom_done::get_merged_pom;
+	use crate::resolver::{make_relative_pom_url, try_resolvers};
 
 	/*#[test]
 	fn resolver_appends_missing_slash() {
@@ -82,6 +145,15 @@ mod testing {
 	}*/
 	// TODO: tests?
 
+	#[test]
+	fn relative_pom_url() {
+		assert_eq!(make_relative_pom_url("file:///project/child/pom.xml", "../pom.xml"), Some("file:///project/pom.xml".to_owned()));
+		assert_eq!(make_relative_pom_url("file:///project/child/pom.xml", ".."), Some("file:///project/pom.xml".to_owned()));
+		assert_eq!(make_relative_pom_url("file:///project/a/b/pom.xml", "../../parent/"), Some("file:///project/parent/pom.xml".to_owned()));
+		assert_eq!(make_relative_pom_url("file:///project/child/pom.xml", "./parent.pom"), Some("file:///project/child/parent.pom".to_owned()));
+		assert_eq!(make_relative_pom_url("https://maven.example.org/org/example/foo/0.1/foo-0.1.pom", "../pom.xml"), None);
+	}
+
 	#[tokio::test]
 	async fn test_try_resolvers() -> Result<()> {
 		let resolvers = [ Resolver::new("a", "a"), Resolver::new("b", "b"), Resolver::new("c", "c") ];
@@ -100,4 +172,73 @@ mod testing {
 
 		Ok(())
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn resolver_normalized_maven() {
+		let normalized = |maven| Resolver::new("a", maven).normalized_maven();
+		assert_eq!(normalized("https://maven.example.org"), normalized("https://maven.example.org/"));
+		assert_eq!(normalized("HTTPS://maven.example.org/"), normalized("https://maven.example.org/"));
+		assert_ne!(normalized("https://maven.example.org/"), normalized("https://maven.example.com/"));
+	}
+
+	/// A downloader recording the urls requested.
+	struct RecordingDownloader {
+		pom_url: &'static str,
+		pom: MavenPom,
+		requested: Mutex<Vec<String>>,
+	}
+
+	impl Downloader for RecordingDownloader {
+		// note: can't rewrite with async, bc of `+ Send`
+		#[allow(clippy::manual_async_fn)]
+		fn get_maven_pom(&self, url: &str) -> impl Future<Output=Result<Option<MavenPom>>> + Send {
+			if let Ok(mut requested) = self.requested.lock() {
+				requested.push(url.to_owned());
+			}
+			let pom = (url == self.pom_url).then(|| self.pom.clone());
+			async { Ok(pom) }
+		}
+	}
+
+	#[tokio::test]
+	async fn duplicate_resolvers() -> Result<()> {
+		let resolvers = [
+			Resolver::new("Example", "invalid://maven.example.org"),
+			Resolver::new("Example, again", "invalid://maven.example.org/"),
+			Resolver::new("Other", "invalid://maven.example.com/"),
+		];
+		let downloader = RecordingDownloader {
+			pom_url: "invalid://maven.example.com/org/example/foo/0.1/foo-0.1.pom",
+			pom: MavenPom {
+				model_version: "4.0.0".to_owned(),
+				parent: None,
+				group_id: Some("org.example".to_owned()),
+				artifact_id: "foo".to_owned(),
+				version: Some("0.1".to_owned()),
+				packaging: None,
+				dependencies: None,
+				dependency_management: None,
+				profiles: None,
+			},
+			requested: Mutex::new(Vec::new()),
+		};
+
+		let coord = MavenCoord {
+			group: "org.example".to_owned(),
+			artifact: "foo".to_owned(),
+			version: "0.1".to_owned(),
+			classifier: None,
+			type_: "pom".to_owned(),
+		};
+		let (resolver, _) = get_merged_pom(&downloader, &resolvers, &ActivationContext::default(), &coord).await?;
+		assert_eq!(resolver.name, "Other");
+
+		let requested = downloader.requested.into_inner().ok().context("poisoned")?;
+		assert_eq!(requested, [
+			"invalid://maven.example.org/org/example/foo/0.1/foo-0.1.pom",
+			"invalid://maven.example.com/org/example/foo/0.1/foo-0.1.pom",
+		]);
+
+		Ok(())
+	}
+}
@@ -3,8 +3,13 @@ pub(crate) mod diff_mappings;
 pub(crate) mod extend_inner_class_names;
 pub(crate) mod insert_dummy;
 pub(crate) mod merge;
+pub(crate) mod merge_over;
+pub(crate) mod name_map;
+pub(crate) mod orphaned_javadoc;
 pub(crate) mod remove_dummy;
 pub(crate) mod reorder;
+pub(crate) mod restrict_to_classes;
+pub(crate) mod three_way_merge;
 
 use anyhow::Result;
 use crate::tree::mappings::Mappings;
@@ -1,11 +1,15 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 use anyhow::{anyhow, bail, Context, Result};
 use indexmap::IndexMap;
-use crate::tree::mappings::{ClassNowodeMapping, FieldNowodeMapping, Mappings, MethodNowodeMapping, ParameterNowodeMapping};
+use duke::tree::class::ClassName;
+use duke::tree::field::{FieldName, FieldNameAndDesc};
+use duke::tree::method::{MethodName, MethodNameAndDesc};
+use crate::tree::mappings::{ClassIndex, ClassNowodeMapping, FieldNowodeMapping, Mappings, MethodNowodeMapping, ParameterKey, ParameterNowodeMapping};
 use crate::tree::mappings_diff::{Action, MappingsDiff};
 use crate::tree::names::Namespace;
-use crate::tree::{FromKey, GetNames, NodeInfo};
+use crate::tree::{FromKey, GetNames, NodeInfo, ToKey};
 
 pub(crate) fn apply_diff_option<T>(
 	diff: &Action<T>,
@@ -48,6 +52,46 @@ pub(crate) fn apply_diff_option<T>(
 	})
 }
 
+/// A key of a node, used to detect diffs that would change the descriptor of a member.
+trait DescriptorKey {
+	type Name: Hash + Eq;
+
+	/// Returns the name of the member, without the descriptor, or [`None`] if the key doesn't have a descriptor.
+	fn name_without_descriptor(&self) -> Option<&Self::Name>;
+}
+
+impl DescriptorKey for ClassName {
+	type Name = ();
+
+	fn name_without_descriptor(&self) -> Option<&()> {
+		None
+	}
+}
+
+impl DescriptorKey for FieldNameAndDesc {
+	type Name = FieldName;
+
+	fn name_without_descriptor(&self) -> Option<&FieldName> {
+		Some(&self.name)
+	}
+}
+
+impl DescriptorKey for MethodNameAndDesc {
+	type Name = MethodName;
+
+	fn name_without_descriptor(&self) -> Option<&MethodName> {
+		Some(&self.name)
+	}
+}
+
+impl DescriptorKey for ParameterKey {
+	type Name = ();
+
+	fn name_without_descriptor(&self) -> Option<&()> {
+		None
+	}
+}
+
 fn apply_diff_map<const N: usize, Key, Diff, Target, Name, Mapping>(
 	target_namespace: Namespace<N>,
 	diffs: &IndexMap<Key, Diff>,
@@ -55,12 +99,29 @@ fn apply_diff_map<const N: usize, Key, Diff, Target, Name, Mapping>(
 	apply_child: impl Fn(&Diff, Target) -> Result<Target>,
 ) -> Result<IndexMap<Key, Target>>
 	where
-		Key: Debug + Hash + Eq + Clone,
+		Key: Debug + Hash + Eq + Clone + DescriptorKey,
 		Diff: NodeInfo<Action<Name>>,
 		Target: NodeInfo<Mapping>,
 		Name: Debug + PartialEq + Clone,
-		Mapping: FromKey<Key> + GetNames<N, Name>,
+		Mapping: FromKey<Key> + ToKey<Key> + GetNames<N, Name>,
 {
+	// A diff changing a member must use the same key as the member, which includes its descriptor. A diff with a key
+	// differing only in the descriptor is malformed, and would otherwise only fail with a confusing error below.
+	let mut targets_by_name: HashMap<&Key::Name, &Key> = HashMap::new();
+	for target_key in targets.keys() {
+		if let Some(name) = target_key.name_without_descriptor() {
+			targets_by_name.entry(name).or_insert(target_key);
+		}
+	}
+	for (key, diff) in diffs {
+		if !matches!(diff.get_node_info(), Action::Add(_)) && !targets.contains_key(key) {
+			// the key isn't in the targets, so a target with the same name has a different descriptor
+			if let Some(target_key) = key.name_without_descriptor().and_then(|name| targets_by_name.get(name)) {
+				bail!("cannot apply diff for {key:?} to {target_key:?}: a diff must not change the descriptor of a member");
+			}
+		}
+	}
+
 	// There are four different cases:
 	// 1. A key is in targets and diffs.
 	// 2. A key is in targets, but not in diffs. As there is no diff action for this key, just copy the entry.
@@ -90,6 +151,7 @@ fn apply_diff_map<const N: usize, Key, Diff, Target, Name, Mapping>(
 						.get_names_mut()
 						.change_name(target_namespace, None, Some(b))
 						.with_context(|| anyhow!("cannot apply action {action:?} with same key {key:?}"))?;
+					check_key_unchanged(&key, &target)?;
 
 					// Run on the children and store
 					results.insert(key, apply_child(diff, target)?);
@@ -112,6 +174,7 @@ fn apply_diff_map<const N: usize, Key, Diff, Target, Name, Mapping>(
 						.get_names_mut()
 						.change_name(target_namespace, Some(a), Some(b))
 						.with_context(|| anyhow!("cannot apply action {action:?} with same key {key:?}"))?;
+					check_key_unchanged(&key, &target)?;
 
 					// Run on the children and store
 					results.insert(key, apply_child(diff, target)?);
@@ -159,6 +222,20 @@ fn apply_diff_map<const N: usize, Key, Diff, Target, Name, Mapping>(
 	Ok(results)
 }
 
+/// Checks that changing a name didn't change the key of the node, which would corrupt the map storing it.
+fn check_key_unchanged<Key, Target, Mapping>(key: &Key, target: &Target) -> Result<()>
+	where
+		Key: Debug + PartialEq,
+		Target: NodeInfo<Mapping>,
+		Mapping: ToKey<Key>,
+{
+	let new_key = target.get_node_info().get_key()?;
+	if &new_key != key {
+		bail!("cannot apply diff for {key:?}, as it would change the key to {new_key:?}");
+	}
+	Ok(())
+}
+
 impl MappingsDiff {
 	// TODO: docs
 	pub fn apply_to<const N: usize>(&self, target: Mappings<N>, namespace: &str) -> Result<Mappings<N>> {
@@ -210,6 +287,7 @@ impl MappingsDiff {
 					info: class.info,
 				})
 			)?,
+			class_index: ClassIndex::default(),
 		})
 	}
 }
@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Context, Result};
 use java_string::{JavaStr, JavaString};
 use duke::tree::class::{ClassName, ClassNameSlice};
-use crate::tree::mappings::{ClassMapping, ClassNowodeMapping, Mappings};
+use crate::tree::mappings::{ClassIndex, ClassMapping, ClassNowodeMapping, Mappings};
 use crate::tree::names::{Names, Namespace};
 
 fn map<const N: usize>(mappings: &Mappings<N>, namespace: Namespace<N>, name: &ClassNameSlice, mapped: &ClassNameSlice) -> Result<ClassName> {
@@ -51,6 +51,7 @@ impl<const N: usize> Mappings<N> {
 				})))
 				.collect::<Result<_>>()?,
 			javadoc: self.javadoc.clone(),
+			class_index: ClassIndex::default(),
 		})
 	}
 }
@@ -2,7 +2,7 @@ use std::fmt::Debug;
 use anyhow::{bail, Context, Result};
 use java_string::JavaStr;
 use crate::tree::names::{Names, Namespaces};
-use crate::tree::mappings::{ClassMapping, ClassNowodeMapping, FieldMapping, FieldNowodeMapping, MappingInfo, Mappings, MethodMapping, MethodNowodeMapping, ParameterMapping, ParameterNowodeMapping};
+use crate::tree::mappings::{ClassIndex, ClassMapping, ClassNowodeMapping, FieldMapping, FieldNowodeMapping, MappingInfo, Mappings, MethodMapping, MethodNowodeMapping, ParameterMapping, ParameterNowodeMapping};
 use crate::tree::NodeJavadocInfo;
 use super::diff_mappings::diff_and_merge::*;
 
@@ -111,6 +111,7 @@ impl Mappings<2> {
 				})
 			)?,
 			javadoc: merge_javadoc(ab).context("cannot merge mappings javadoc")?,
+			class_index: ClassIndex::default(),
 		})
 	}
 }
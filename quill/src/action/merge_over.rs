@@ -0,0 +1,127 @@
+use std::fmt::Debug;
+use anyhow::{bail, Context, Result};
+use java_string::JavaStr;
+use crate::tree::names::Names;
+use crate::tree::mappings::{ClassIndex, ClassMapping, ClassNowodeMapping, ConflictPolicy, FieldMapping, FieldNowodeMapping, JavadocMapping, Mappings, MethodMapping, MethodNowodeMapping, ParameterMapping, ParameterNowodeMapping};
+use crate::tree::NodeJavadocInfo;
+use super::diff_mappings::diff_and_merge::*;
+
+fn pick<T>(a: &Option<T>, b: &Option<T>, conflict: ConflictPolicy) -> Result<Option<T>>
+	where
+		T: Clone + Debug + PartialEq,
+{
+	Ok(match (a, b) {
+		(None, None) => None,
+		(Some(a), None) => Some(a.clone()),
+		(None, Some(b)) => Some(b.clone()),
+		(Some(a), Some(b)) if a == b => Some(a.clone()),
+		(Some(a), Some(b)) => match conflict {
+			ConflictPolicy::PreferSelf => Some(a.clone()),
+			ConflictPolicy::PreferOther => Some(b.clone()),
+			ConflictPolicy::Error => bail!("conflict: self has {a:?}, but other has {b:?}"),
+		},
+	})
+}
+
+/// Gets any side, for values that are the same on both sides, because they're part of the key.
+fn either<T>(ab: Combination<&T>) -> &T {
+	match ab {
+		Combination::A(a) => a,
+		Combination::B(b) => b,
+		Combination::AB(a, _) => a,
+	}
+}
+
+fn merge_names<const N: usize, Name>(ab: Combination<&Names<N, Name>>, conflict: ConflictPolicy) -> Result<Names<N, Name>>
+	where
+		Name: Debug + Clone + PartialEq + AsRef<JavaStr>,
+{
+	match ab {
+		Combination::A(a) => Ok(a.clone()),
+		Combination::B(b) => Ok(b.clone()),
+		Combination::AB(a, b) => {
+			let a: &[Option<Name>; N] = a.into();
+			let b: &[Option<Name>; N] = b.into();
+
+			let mut names = a.clone();
+			for (i, name) in names.iter_mut().enumerate() {
+				*name = pick(&a[i], &b[i], conflict)?;
+			}
+
+			names.try_into()
+		},
+	}
+}
+
+fn merge_javadoc<Target>(ab: Combination<&Target>, conflict: ConflictPolicy) -> Result<Option<JavadocMapping>>
+	where
+		Target: NodeJavadocInfo<Option<JavadocMapping>>,
+{
+	match ab.map(NodeJavadocInfo::get_node_javadoc_info) {
+		Combination::A(a) => Ok(a.clone()),
+		Combination::B(b) => Ok(b.clone()),
+		Combination::AB(a, b) => pick(a, b, conflict),
+	}
+}
+
+impl<const N: usize> Mappings<N> {
+	/// Combines two mappings with the same namespaces into one, containing all classes, fields, methods and parameters
+	/// of both.
+	///
+	/// Entries are matched up by their names in the first namespace (and their descriptors). If both mappings have a
+	/// (different) name for the same entry in some namespace, `conflict` decides which name is taken. The same applies
+	/// to javadoc: if only one side has a javadoc comment, it's kept, and if both have different ones, `conflict` decides.
+	///
+	/// Fails if the namespaces don't match, or if `conflict` is [`ConflictPolicy::Error`] and there's a conflict.
+	pub fn merge_over(self, other: Mappings<N>, conflict: ConflictPolicy) -> Result<Mappings<N>> {
+		if self.info.namespaces != other.info.namespaces {
+			bail!("cannot merge mappings with different namespaces: {:?} and {:?}", self.info.namespaces, other.info.namespaces);
+		}
+
+		let ab = Combination::AB(&self, &other);
+		Ok(Mappings {
+			info: self.info.clone(),
+			classes: zip_map_combination(
+				ab.map(|x| &x.classes),
+				|ab| Ok(ClassNowodeMapping {
+					info: ClassMapping {
+						names: merge_names(ab.map(|x| &x.info.names), conflict).context("cannot merge class names")?,
+					},
+					fields: zip_map_combination(
+						ab.map(|x| &x.fields),
+						|ab| Ok(FieldNowodeMapping {
+							info: FieldMapping {
+								desc: either(ab).info.desc.clone(),
+								names: merge_names(ab.map(|x| &x.info.names), conflict).context("cannot merge field names")?,
+							},
+							javadoc: merge_javadoc(ab, conflict).context("cannot merge field javadoc")?,
+						})
+					)?,
+					methods: zip_map_combination(
+						ab.map(|x| &x.methods),
+						|ab| Ok(MethodNowodeMapping {
+							info: MethodMapping {
+								desc: either(ab).info.desc.clone(),
+								names: merge_names(ab.map(|x| &x.info.names), conflict).context("cannot merge method names")?,
+							},
+							parameters: zip_map_combination(
+								ab.map(|x| &x.parameters),
+								|ab| Ok(ParameterNowodeMapping {
+									info: ParameterMapping {
+										index: either(ab).info.index,
+										names: merge_names(ab.map(|x| &x.info.names), conflict).context("cannot merge parameter names")?,
+									},
+									javadoc: merge_javadoc(ab, conflict).context("cannot merge parameter javadoc")?,
+								})
+							)?,
+							javadoc: merge_javadoc(ab, conflict).context("cannot merge method javadoc")?,
+						})
+					)?,
+					javadoc: merge_javadoc(ab, conflict).context("cannot merge class javadoc")?,
+				})
+			)?,
+			javadoc: merge_javadoc(ab, conflict).context("cannot merge mappings javadoc")?,
+			class_index: ClassIndex::default(),
+		})
+	}
+}
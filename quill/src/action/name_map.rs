@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use anyhow::Result;
+use duke::tree::class::ClassName;
+use duke::tree::field::{FieldName, FieldRef};
+use duke::tree::method::{MethodName, MethodRef};
+use crate::remapper::ARemapper;
+use crate::tree::mappings::Mappings;
+use crate::tree::names::Namespace;
+
+impl<const N: usize> Mappings<N> {
+	/// Creates a map of the class names in the namespace `from` to the class names in the namespace `to`.
+	///
+	/// Classes missing a name in either of the namespaces are skipped.
+	pub fn name_map(&self, from: &str, to: &str) -> Result<HashMap<ClassName, ClassName>> {
+		let from = self.get_namespace(from)?;
+		let to = self.get_namespace(to)?;
+
+		Ok(self.classes.values()
+			.filter_map(|class| Some((class.info.names[from].clone()?, class.info.names[to].clone()?)))
+			.collect())
+	}
+
+	/// Creates a map of the fields in the namespace `from` to their names in the namespace `to`.
+	///
+	/// The keys are the field references in the namespace `from`, that is the owning class, the name and the descriptor
+	/// are all given in that namespace. Fields missing a name in either of the namespaces are skipped, as well as all fields
+	/// of classes missing a name in the namespace `from`.
+	pub fn field_name_map(&self, from: &str, to: &str) -> Result<HashMap<FieldRef, FieldName>> {
+		let from = self.get_namespace(from)?;
+		let to = self.get_namespace(to)?;
+		let remapper = self.remapper_a(Namespace::new(0)?, from)?;
+
+		let mut map = HashMap::new();
+		for class in self.classes.values() {
+			let Some(class_name) = &class.info.names[from] else { continue };
+
+			for field in class.fields.values() {
+				if let (Some(from_name), Some(to_name)) = (&field.info.names[from], &field.info.names[to]) {
+					let field_ref = FieldRef {
+						class: class_name.clone(),
+						name: from_name.clone(),
+						desc: remapper.map_field_desc(&field.info.desc)?,
+					};
+					map.insert(field_ref, to_name.clone());
+				}
+			}
+		}
+		Ok(map)
+	}
+
+	/// Creates a map of the methods in the namespace `from` to their names in the namespace `to`.
+	///
+	/// The keys are the method references in the namespace `from`, that is the owning class, the name and the descriptor
+	/// are all given in that namespace. Methods missing a name in either of the namespaces are skipped, as well as all
+	/// methods of classes missing a name in the namespace `from`.
+	pub fn method_name_map(&self, from: &str, to: &str) -> Result<HashMap<MethodRef, MethodName>> {
+		let from = self.get_namespace(from)?;
+		let to = self.get_namespace(to)?;
+		let remapper = self.remapper_a(Namespace::new(0)?, from)?;
+
+		let mut map = HashMap::new();
+		for class in self.classes.values() {
+			let Some(class_name) = &class.info.names[from] else { continue };
+
+			for method in class.methods.values() {
+				if let (Some(from_name), Some(to_name)) = (&method.info.names[from], &method.info.names[to]) {
+					let method_ref = MethodRef {
+						class: class_name.clone(),
+						name: from_name.clone(),
+						desc: remapper.map_method_desc(&method.info.desc)?,
+					};
+					map.insert(method_ref, to_name.clone());
+				}
+			}
+		}
+		Ok(map)
+	}
+}
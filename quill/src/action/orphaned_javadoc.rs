@@ -0,0 +1,44 @@
+use duke::tree::method::MethodName;
+use crate::tree::mappings::{Location, Mappings};
+
+impl<const N: usize> Mappings<N> {
+	/// Finds javadoc attached to nodes that aren't mapped.
+	///
+	/// A node isn't mapped if its name is absent or the same as the name in the first namespace in all other namespaces.
+	/// Documenting such a node is usually a mistake, as the javadoc would be expected next to an actual mapping.
+	///
+	/// Constructors and static initializers are never reported, as these can't be renamed.
+	///
+	/// The locations are returned in the order of the mappings, with a class before its fields, methods and parameters.
+	pub fn find_orphaned_javadoc(&self) -> Vec<Location> {
+		let mut locations = Vec::new();
+
+		for (class_key, class) in &self.classes {
+			if class.javadoc.is_some() && class.info.names.is_identity() {
+				locations.push(Location::Class(class_key.clone()));
+			}
+
+			for (field_key, field) in &class.fields {
+				if field.javadoc.is_some() && field.info.names.is_identity() {
+					locations.push(Location::Field(class_key.clone(), field_key.clone()));
+				}
+			}
+
+			for (method_key, method) in &class.methods {
+				let can_be_renamed = method_key.name != MethodName::INIT && method_key.name != MethodName::CLINIT;
+
+				if method.javadoc.is_some() && method.info.names.is_identity() && can_be_renamed {
+					locations.push(Location::Method(class_key.clone(), method_key.clone()));
+				}
+
+				for (parameter_key, parameter) in &method.parameters {
+					if parameter.javadoc.is_some() && parameter.info.names.is_identity() {
+						locations.push(Location::Parameter(class_key.clone(), method_key.clone(), parameter_key.clone()));
+					}
+				}
+			}
+		}
+
+		locations
+	}
+}
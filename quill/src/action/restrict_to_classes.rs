@@ -0,0 +1,15 @@
+use indexmap::IndexSet;
+use duke::tree::class::ClassName;
+use crate::tree::mappings::Mappings;
+
+impl<const N: usize> Mappings<N> {
+	/// Removes the mappings for all classes not in `present`, keeping the classes that are in it, with all their members.
+	///
+	/// The class names in `present` are in the first namespace of the mappings. This allows using the mappings of a merged
+	/// jar for only the client or only the server jar, by passing in the classes of that jar.
+	pub fn restrict_to_classes(mut self, present: &IndexSet<ClassName>) -> Mappings<N> {
+		self.classes.retain(|class_key, _| present.contains(class_key));
+		self.invalidate_class_index();
+		self
+	}
+}
@@ -0,0 +1,236 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use anyhow::{anyhow, Context, Result};
+use indexmap::IndexMap;
+use duke::tree::class::ClassName;
+use duke::tree::field::FieldNameAndDesc;
+use duke::tree::method::MethodNameAndDesc;
+use crate::tree::mappings::{Location, Mappings, ParameterKey};
+use crate::tree::mappings_diff::{Action, ClassNowodeDiff, FieldNowodeDiff, MappingsDiff, MergeConflict, MergeResult, MethodNowodeDiff, ParameterNowodeDiff};
+use crate::tree::names::Namespace;
+
+/// A node of a [`MappingsDiff`] that can be merged with the same node of a diff of the other side.
+trait MergeNode {
+	type Key;
+	/// The keys of the nodes this node is a child of.
+	type Parent;
+	type Name: Debug + Clone + PartialEq;
+
+	/// The location of the node with the given key, for a [`MergeConflict`].
+	fn location(parent: &Self::Parent, key: &Self::Key) -> Location;
+
+	fn info_mut(&mut self) -> &mut Action<Self::Name>;
+
+	/// Whether this node, its javadoc or any of its children change anything.
+	fn has_diff(&self) -> bool;
+
+	/// Merges the javadoc and the children of the nodes.
+	fn merge_children(theirs: &mut Self, mine: &mut Self, parent: &Self::Parent, key: &Self::Key, conflicts: &mut Vec<MergeConflict>);
+}
+
+impl MergeNode for ClassNowodeDiff {
+	type Key = ClassName;
+	type Parent = ();
+	type Name = ClassName;
+
+	fn location(_: &(), key: &ClassName) -> Location {
+		Location::Class(key.clone())
+	}
+
+	fn info_mut(&mut self) -> &mut Action<ClassName> {
+		&mut self.info
+	}
+
+	fn has_diff(&self) -> bool {
+		self.info.is_diff() || self.javadoc.is_diff() ||
+			self.fields.values().any(MergeNode::has_diff) || self.methods.values().any(MergeNode::has_diff)
+	}
+
+	fn merge_children(theirs: &mut Self, mine: &mut Self, parent: &(), key: &ClassName, conflicts: &mut Vec<MergeConflict>) {
+		merge_action(&mut theirs.javadoc, &mut mine.javadoc, || format!("{}, javadoc", Self::location(parent, key)), conflicts);
+		merge_map(&mut theirs.fields, &mut mine.fields, key, conflicts);
+		merge_map(&mut theirs.methods, &mut mine.methods, key, conflicts);
+	}
+}
+
+impl MergeNode for FieldNowodeDiff {
+	type Key = FieldNameAndDesc;
+	type Parent = ClassName;
+	type Name = duke::tree::field::FieldName;
+
+	fn location(class: &ClassName, key: &FieldNameAndDesc) -> Location {
+		Location::Field(class.clone(), key.clone())
+	}
+
+	fn info_mut(&mut self) -> &mut Action<Self::Name> {
+		&mut self.info
+	}
+
+	fn has_diff(&self) -> bool {
+		self.info.is_diff() || self.javadoc.is_diff()
+	}
+
+	fn merge_children(theirs: &mut Self, mine: &mut Self, parent: &ClassName, key: &FieldNameAndDesc, conflicts: &mut Vec<MergeConflict>) {
+		merge_action(&mut theirs.javadoc, &mut mine.javadoc, || format!("{}, javadoc", Self::location(parent, key)), conflicts);
+	}
+}
+
+impl MergeNode for MethodNowodeDiff {
+	type Key = MethodNameAndDesc;
+	type Parent = ClassName;
+	type Name = duke::tree::method::MethodName;
+
+	fn location(class: &ClassName, key: &MethodNameAndDesc) -> Location {
+		Location::Method(class.clone(), key.clone())
+	}
+
+	fn info_mut(&mut self) -> &mut Action<Self::Name> {
+		&mut self.info
+	}
+
+	fn has_diff(&self) -> bool {
+		self.info.is_diff() || self.javadoc.is_diff() || self.parameters.values().any(MergeNode::has_diff)
+	}
+
+	fn merge_children(theirs: &mut Self, mine: &mut Self, parent: &ClassName, key: &MethodNameAndDesc, conflicts: &mut Vec<MergeConflict>) {
+		merge_action(&mut theirs.javadoc, &mut mine.javadoc, || format!("{}, javadoc", Self::location(parent, key)), conflicts);
+		merge_map(&mut theirs.parameters, &mut mine.parameters, &(parent.clone(), key.clone()), conflicts);
+	}
+}
+
+impl MergeNode for ParameterNowodeDiff {
+	type Key = ParameterKey;
+	type Parent = (ClassName, MethodNameAndDesc);
+	type Name = duke::tree::method::ParameterName;
+
+	fn location((class, method): &(ClassName, MethodNameAndDesc), key: &ParameterKey) -> Location {
+		Location::Parameter(class.clone(), method.clone(), key.clone())
+	}
+
+	fn info_mut(&mut self) -> &mut Action<Self::Name> {
+		&mut self.info
+	}
+
+	fn has_diff(&self) -> bool {
+		self.info.is_diff() || self.javadoc.is_diff()
+	}
+
+	fn merge_children(theirs: &mut Self, mine: &mut Self, parent: &Self::Parent, key: &ParameterKey, conflicts: &mut Vec<MergeConflict>) {
+		merge_action(&mut theirs.javadoc, &mut mine.javadoc, || format!("{}, javadoc", Self::location(parent, key)), conflicts);
+	}
+}
+
+/// Merges two actions on the same name or javadoc, so that `mine` can be applied after `theirs`.
+///
+/// If both actions change it differently, a conflict is reported and both actions are replaced by [`Action::None`],
+/// keeping the base version. Returns `false` in that case.
+fn merge_action<T>(theirs: &mut Action<T>, mine: &mut Action<T>, location: impl FnOnce() -> String, conflicts: &mut Vec<MergeConflict>) -> bool
+	where
+		T: Debug + Clone + PartialEq,
+{
+	if !mine.is_diff() {
+		// An unchanged `Edit(a, a)` would fail to apply if `theirs` changed it.
+		*mine = Action::None;
+		return true;
+	}
+	if !theirs.is_diff() {
+		return true;
+	}
+
+	if theirs.as_ref().to_tuple().1 == mine.as_ref().to_tuple().1 {
+		// both sides made the same change, so only apply it once
+		*mine = Action::None;
+		true
+	} else {
+		conflicts.push(MergeConflict {
+			location: location(),
+			theirs: format!("{theirs:?}"),
+			mine: format!("{mine:?}"),
+		});
+		*theirs = Action::None;
+		*mine = Action::None;
+		false
+	}
+}
+
+/// Merges the children of a node, so that the `mine` diffs can be applied after the `theirs` diffs.
+fn merge_map<Key, Node>(theirs: &mut IndexMap<Key, Node>, mine: &mut IndexMap<Key, Node>, parent: &Node::Parent, conflicts: &mut Vec<MergeConflict>)
+	where
+		Key: Hash + Eq + Clone,
+		Node: MergeNode<Key = Key>,
+{
+	let keys: Vec<Key> = theirs.keys().filter(|key| mine.contains_key(*key)).cloned().collect();
+
+	for key in keys {
+		let location = Node::location(parent, &key).to_string();
+
+		let (Some(t), Some(m)) = (theirs.get_mut(&key), mine.get_mut(&key)) else {
+			continue;
+		};
+
+		let theirs_removes = matches!(t.info_mut(), Action::Remove(_));
+		let mine_removes = matches!(m.info_mut(), Action::Remove(_));
+
+		match (theirs_removes, mine_removes) {
+			(true, true) => {
+				// both removed it
+				mine.shift_remove(&key);
+			},
+			(true, false) | (false, true) => {
+				let other_has_diff = if theirs_removes { m.has_diff() } else { t.has_diff() };
+				let describe = |removes| if removes { "removed".to_owned() } else { "changed".to_owned() };
+
+				if other_has_diff {
+					conflicts.push(MergeConflict {
+						location,
+						theirs: describe(theirs_removes),
+						mine: describe(mine_removes),
+					});
+					theirs.shift_remove(&key);
+					mine.shift_remove(&key);
+				} else if theirs_removes {
+					// the removal from their side is applied first, so there's nothing left for mine
+					mine.shift_remove(&key);
+				}
+			},
+			(false, false) => {
+				let both_add = matches!(t.info_mut(), Action::Add(_)) && matches!(m.info_mut(), Action::Add(_));
+
+				if !merge_action(t.info_mut(), m.info_mut(), || location.clone(), conflicts) && both_add {
+					// there's no base version to keep
+					theirs.shift_remove(&key);
+					mine.shift_remove(&key);
+				} else {
+					Node::merge_children(t, m, parent, &key, conflicts);
+				}
+			},
+		}
+	}
+}
+
+impl Mappings<2> {
+	/// Merges the changes from `base` to `theirs` and from `base` to `mine`.
+	///
+	/// This computes a [`MappingsDiff`] from `base` to each side, and applies both to `base`. Changes to the names in the
+	/// second namespace and to the javadoc that only one side made, or both sides made the same way, are merged cleanly.
+	/// If both sides changed the same name or javadoc differently, or if one side removed a node that the other side
+	/// changed, a [`MergeConflict`] is reported and the version from `base` is kept.
+	pub fn three_way_merge(base: &Mappings<2>, theirs: &Mappings<2>, mine: &Mappings<2>) -> Result<MergeResult> {
+		let mut theirs_diff = MappingsDiff::diff(base, theirs)
+			.context("failed to diff base and their mappings")?;
+		let mut mine_diff = MappingsDiff::diff(base, mine)
+			.context("failed to diff base and my mappings")?;
+
+		let mut conflicts = Vec::new();
+		merge_action(&mut theirs_diff.javadoc, &mut mine_diff.javadoc, || "javadoc".to_owned(), &mut conflicts);
+		merge_map(&mut theirs_diff.classes, &mut mine_diff.classes, &(), &mut conflicts);
+
+		let namespace = base.info.namespaces[Namespace::new(1)?].clone();
+		let mappings = theirs_diff.apply_to(base.clone(), &namespace)
+			.context("failed to apply the changes of their side")?;
+		let mappings = mine_diff.apply_to(mappings, &namespace)
+			.with_context(|| anyhow!("failed to apply the changes of my side, after applying the ones of their side"))?;
+
+		Ok(MergeResult { mappings, conflicts })
+	}
+}
@@ -10,6 +10,7 @@ use std::collections::VecDeque;
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use anyhow::{anyhow, bail, Context, Result};
+use crate::enigma_file::EnigmaWriteOptions;
 use crate::tree::mappings::{MappingInfo, Mappings};
 use crate::tree::names::Namespaces;
 use crate::tree::NodeInfo;
@@ -56,9 +57,14 @@ pub fn read(path: impl AsRef<Path>, namespaces: Namespaces<2>) -> Result<Mapping
 
 // TODO: doc
 pub fn write(mappings: &Mappings<2>, path: impl AsRef<Path>) -> Result<()> {
+	write_with_options(mappings, path, EnigmaWriteOptions::default())
+}
+
+/// Writes the mappings into the directory, like [`write`], but formats the files according to the options.
+pub fn write_with_options(mappings: &Mappings<2>, path: impl AsRef<Path>, options: EnigmaWriteOptions) -> Result<()> {
 	let path = path.as_ref();
 
-	crate::enigma_file::write_all_for_each(mappings, |file_name| {
+	crate::enigma_file::write_all_for_each(mappings, options, |file_name| {
 		if file_name.contains('.') {
 			bail!("class name (dst) {file_name:?} contains '.'");
 		}
@@ -73,11 +73,12 @@ pub fn read_file_into(path: impl AsRef<Path>, mappings: &mut Mappings<2>) -> Res
 /// );
 /// ```
 pub fn read_into(reader: impl Read, mappings: &mut Mappings<2>) -> Result<()> {
+	let mut spaces_per_level = None;
 	let mut lines = BufReader::new(reader)
 		.lines()
 		.enumerate()
 		.map(|(line_number, line)| -> Result<Option<EnigmaLine>> {
-			EnigmaLine::new(line_number + 1, &line?)
+			EnigmaLine::new(line_number + 1, &line?, &mut spaces_per_level)
 		})
 		.filter_map(|x| x.transpose())
 		.peekable();
@@ -224,7 +225,7 @@ fn insert_comment(javadoc: &mut Option<JavadocMapping>, line: EnigmaLine) -> Res
 }
 
 mod enigma_line {
-	use anyhow::{anyhow, Context, Result};
+	use anyhow::{anyhow, bail, Context, Result};
 	use crate::lines::Line;
 
 	#[derive(Debug)]
@@ -236,11 +237,15 @@ mod enigma_line {
 	}
 
 	impl EnigmaLine {
-		pub(crate) fn new(line_number: usize, line: &str) -> Result<Option<EnigmaLine>> {
-			let idents = line.chars().take_while(|x| *x == '\t').count();
-			// TODO: there was some other code (related to inner classes?) that did this better!
-			//  we may not use the count to index strings!, we must use the char_indices!
-			let line = &line[idents..];
+		/// Parses a line, indented either by tabs or by spaces.
+		///
+		/// For lines indented by spaces, the number of spaces of the first such line is taken as one level, and stored in
+		/// `spaces_per_level`.
+		pub(crate) fn new(line_number: usize, line: &str, spaces_per_level: &mut Option<usize>) -> Result<Option<EnigmaLine>> {
+			let indent_char = if line.starts_with(' ') { ' ' } else { '\t' };
+			// both are a single byte, so this can index the string
+			let indent = line.chars().take_while(|x| *x == indent_char).count();
+			let line = &line[indent..];
 
 			// if the line is a `COMMENT` then it may contain `#`, otherwise everything after `#` is a comment
 			let line = if line.starts_with(crate::enigma_file::COMMENT) {
@@ -261,6 +266,16 @@ mod enigma_line {
 			let first_field = fields.next()
 				.with_context(|| anyhow!("no first field in line {line_number}"))?;
 
+			let idents = if indent_char == ' ' {
+				let spaces_per_level = *spaces_per_level.get_or_insert(indent);
+				if indent % spaces_per_level != 0 {
+					bail!("indentation of {indent} spaces in line {line_number} isn't a multiple of the {spaces_per_level} spaces of the first indented line");
+				}
+				indent / spaces_per_level
+			} else {
+				indent
+			};
+
 			Ok(Some(EnigmaLine {
 				line_number,
 				idents,
@@ -280,8 +295,53 @@ mod enigma_line {
 	}
 }
 
-fn write_class(class_key: &ClassNameSlice, class: &ClassNowodeMapping<2>, w: &mut impl Write, indent: usize) -> Result<()> {
-	let indent = "\t".repeat(indent);
+/// The indentation used for writing the enigma format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Indent {
+	/// Indent with one tab per level.
+	#[default]
+	Tabs,
+	/// Indent with the given number of spaces per level.
+	///
+	/// Enigma itself only reads tabs, but [`read_into`] also reads this back.
+	Spaces(usize),
+}
+
+impl Indent {
+	fn get(self, depth: usize) -> String {
+		match self {
+			Indent::Tabs => "\t".repeat(depth),
+			Indent::Spaces(n) => " ".repeat(n * depth),
+		}
+	}
+}
+
+/// The order the members of a class are written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+	/// Sort the fields and methods by their names and descriptors, and the parameters by their index.
+	#[default]
+	Names,
+	/// Keep the order the fields, methods and parameters have in the mappings.
+	Mappings,
+}
+
+/// Options for writing the enigma format.
+///
+/// The [`Default`] is what [`write_all`] and [`write_one`] use.
+///
+/// Note that the classes are always written sorted, as the file names decide which classes go together.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EnigmaWriteOptions {
+	pub indent: Indent,
+	pub sort: SortOrder,
+}
+
+fn write_class(class_key: &ClassNameSlice, class: &ClassNowodeMapping<2>, w: &mut impl Write, depth: usize, options: EnigmaWriteOptions) -> Result<()> {
+	let indent = options.indent.get(depth);
+	let indent_1 = options.indent.get(depth + 1);
+	let indent_2 = options.indent.get(depth + 2);
+	let indent_3 = options.indent.get(depth + 3);
 
 	let [_, dst] = class.info.names.names();
 	// get to only the part after $ if it exists
@@ -298,14 +358,16 @@ fn write_class(class_key: &ClassNameSlice, class: &ClassNowodeMapping<2>, w: &mu
 
 	if let Some(javadoc) = &class.javadoc {
 		for line in javadoc.0.split('\n') {
-			writeln!(w, "{indent}\tCOMMENT {line}")?;
+			writeln!(w, "{indent_1}COMMENT {line}")?;
 		}
 	}
 
 	let mut fields: Vec<_> = class.fields.iter().collect();
-	fields.sort_by(|a, b| a.1.info.names.cmp(&b.1.info.names).then_with(|| a.1.info.desc.cmp(&b.1.info.desc)));
+	if options.sort == SortOrder::Names {
+		fields.sort_by(|a, b| a.1.info.names.cmp(&b.1.info.names).then_with(|| a.1.info.desc.cmp(&b.1.info.desc)));
+	}
 	for (FieldNameAndDesc { name, desc }, field) in fields {
-		write!(w, "{indent}\tFIELD {name}")?;
+		write!(w, "{indent_1}FIELD {name}")?;
 		let [_, dst] = field.info.names.names();
 		if let Some(dst) = dst {
 			write!(w, " {dst}")?;
@@ -314,15 +376,17 @@ fn write_class(class_key: &ClassNameSlice, class: &ClassNowodeMapping<2>, w: &mu
 
 		if let Some(javadoc) = &field.javadoc {
 			for line in javadoc.0.split('\n') {
-				writeln!(w, "{indent}\t\tCOMMENT {line}")?;
+				writeln!(w, "{indent_2}COMMENT {line}")?;
 			}
 		}
 	}
 
 	let mut methods: Vec<_> = class.methods.iter().collect();
-	methods.sort_by(|a, b| a.1.info.names.cmp(&b.1.info.names).then_with(|| a.1.info.desc.cmp(&b.1.info.desc)));
+	if options.sort == SortOrder::Names {
+		methods.sort_by(|a, b| a.1.info.names.cmp(&b.1.info.names).then_with(|| a.1.info.desc.cmp(&b.1.info.desc)));
+	}
 	for (method_key @ MethodNameAndDesc { name, desc }, method) in methods {
-		write!(w, "{indent}\tMETHOD {name}")?;
+		write!(w, "{indent_1}METHOD {name}")?;
 		let [_, dst] = method.info.names.names();
 		if let Some(dst) = dst.as_ref().filter(|&dst| dst != MethodName::INIT) {
 			write!(w, " {dst}")?;
@@ -331,23 +395,25 @@ fn write_class(class_key: &ClassNameSlice, class: &ClassNowodeMapping<2>, w: &mu
 
 		if let Some(javadoc) = &method.javadoc {
 			for line in javadoc.0.split('\n') {
-				writeln!(w, "{indent}\t\tCOMMENT {line}")?;
+				writeln!(w, "{indent_2}COMMENT {line}")?;
 			}
 		}
 
 		let mut parameters: Vec<_> = method.parameters.values().collect();
-		parameters.sort_by_key(|x| &x.info);
+		if options.sort == SortOrder::Names {
+			parameters.sort_by_key(|x| &x.info);
+		}
 		for parameter in parameters {
 			let index = parameter.info.index;
 			let [_, dst] = parameter.info.names.names();
 			let dst = dst.as_ref()
 				.with_context(|| anyhow!("no dst parameter name given for {:?} on method {:?} in class {:?}", parameter.info, method_key, class_key))?;
 
-			writeln!(w, "{indent}\t\tARG {index} {dst}")?;
+			writeln!(w, "{indent_2}ARG {index} {dst}")?;
 
 			if let Some(javadoc) = &parameter.javadoc {
 				for line in javadoc.0.split('\n') {
-					writeln!(w, "{indent}\t\t\tCOMMENT {line}")?;
+					writeln!(w, "{indent_3}COMMENT {line}")?;
 				}
 			}
 		}
@@ -439,7 +505,7 @@ fn figure_out_files(mappings: &Mappings<2>) -> Result<Placement<'_>> {
 /// 	CLASS C AnInnerClass
 /// 		COMMENT A multiline
 /// 		COMMENT comment.
-/// 	METHOD methodB methodBSecondName ()
+/// 	METHOD methodB methodBSecondName ()V
 /// 	METHOD methodXa b (I)V
 /// 	METHOD methodXb a (I)V
 /// 	METHOD methodYa a (I)V
@@ -463,7 +529,7 @@ fn figure_out_files(mappings: &Mappings<2>) -> Result<Placement<'_>> {
 /// 	FIELD bIsAfterAa d I
 /// 	FIELD bIsAfterAa d J
 /// 	METHOD methodA methodASecondName ()V
-/// 	METHOD methodB methodBSecondName ()
+/// 	METHOD methodB methodBSecondName ()V
 /// 	METHOD methodXa b (I)V
 /// 	METHOD methodXa b (J)V
 /// 	METHOD methodXb a (I)V
@@ -489,8 +555,8 @@ fn figure_out_files(mappings: &Mappings<2>) -> Result<Placement<'_>> {
 /// 	f	I	bIsAfterAa	d
 /// 	f	J	bIsAfterA	e
 /// 	f	J	bIsAfterAa	d
-/// 	m	()	methodB	methodBSecondName
 /// 	m	()V	methodA	methodASecondName
+/// 	m	()V	methodB	methodBSecondName
 /// 	m	(I)V	methodXa	b
 /// 	m	(I)V	methodXb	a
 /// 	m	(I)V	methodYa	a
@@ -504,11 +570,18 @@ fn figure_out_files(mappings: &Mappings<2>) -> Result<Placement<'_>> {
 /// assert_eq!(written, output);
 /// ```
 pub fn write_all(mappings: &Mappings<2>, w: &mut impl Write) -> Result<()> {
+	write_all_with_options(mappings, w, EnigmaWriteOptions::default())
+}
+
+/// Writes the complete mappings in the enigma format to the given writer, formatted according to the options.
+///
+/// See [`write_all`] for details.
+pub fn write_all_with_options(mappings: &Mappings<2>, w: &mut impl Write, options: EnigmaWriteOptions) -> Result<()> {
 	let f = figure_out_files(mappings)?;
 
 	for (file_name, node) in f.file_map {
 		writeln!(w, "#\n# {file_name}")?;
-		write_one_tree_starting_at(node, &f.child_map, w)?;
+		write_one_tree_starting_at(node, &f.child_map, w, options)?;
 	}
 
 	Ok(())
@@ -516,6 +589,7 @@ pub fn write_all(mappings: &Mappings<2>, w: &mut impl Write) -> Result<()> {
 
 pub(crate) fn write_all_for_each<W>(
 	mappings: &Mappings<2>,
+	options: EnigmaWriteOptions,
 	mut make_writer: impl FnMut(&str) -> Result<W>,
 ) -> Result<()>
 where
@@ -527,7 +601,7 @@ where
 		let mut writer = make_writer(file_name)
 			.with_context(|| anyhow!("failed to create writer for {file_name} (dst name)"))?;
 
-		write_one_tree_starting_at(node, &f.child_map, &mut writer)
+		write_one_tree_starting_at(node, &f.child_map, &mut writer, options)
 			.with_context(|| anyhow!("failed to write mappings to {file_name} (dst name)"))?;
 	}
 
@@ -558,7 +632,7 @@ where
 /// 	CLASS C AnInnerClass
 /// 		COMMENT A multiline
 /// 		COMMENT comment.
-/// 	METHOD methodB methodBSecondName ()
+/// 	METHOD methodB methodBSecondName ()V
 /// 	METHOD methodXa b (I)V
 /// 	METHOD methodXb a (I)V
 /// 	METHOD methodYa a (I)V
@@ -582,7 +656,7 @@ where
 /// 	FIELD bIsAfterAa d I
 /// 	FIELD bIsAfterAa d J
 /// 	METHOD methodA methodASecondName ()V
-/// 	METHOD methodB methodBSecondName ()
+/// 	METHOD methodB methodBSecondName ()V
 /// 	METHOD methodXa b (I)V
 /// 	METHOD methodXa b (J)V
 /// 	METHOD methodXb a (I)V
@@ -603,17 +677,18 @@ pub fn write_one(mappings: &Mappings<2>, dst_class_name: &str, w: &mut impl Writ
 		bail!("class {dst_class_name:?} (dst name) isn't parent-free");
 	};
 
-	write_one_tree_starting_at(node, &f.child_map, w)
+	write_one_tree_starting_at(node, &f.child_map, w, EnigmaWriteOptions::default())
 }
 
 fn write_one_tree_starting_at(
 	node: Node,
 	child_map: &IndexMap<&ClassNameSlice, Vec<Node>>,
-	w: &mut impl Write
+	w: &mut impl Write,
+	options: EnigmaWriteOptions,
 ) -> Result<()> {
 	let mut queue: VecDeque<_> = vec![ (node, 0) ].into();
 	while let Some((parent, depth)) = queue.pop_front() {
-		write_class(parent.src, parent.class, w, depth)?;
+		write_class(parent.src, parent.class, w, depth, options)?;
 
 		if let Some(children) = child_map.get(parent.src) {
 			for &child in children.iter().rev() {
@@ -394,6 +394,70 @@ impl<const N: usize> Mappings<N> {
 		}
 		Ok(BRemapperImpl { classes, inheritance })
 	}
+
+	/// Creates a remapper from the namespace named `from` to the namespace named `to`.
+	///
+	/// This works in any direction, so for mappings with the namespaces `official` and `named`, both
+	/// `remapper("official", "named", ..)` and `remapper("named", "official", ..)` work, without reordering the namespaces
+	/// first. The super classes given by `inheritance` must use the class names of the `from` namespace.
+	///
+	/// See [`Mappings::remapper_b`] for the version taking [`Namespace`]s.
+	pub fn remapper<'i, I>(&self, from: &str, to: &str, inheritance: &'i I) -> Result<BRemapperImpl<'_, 'i, N, I>> {
+		let from = self.get_namespace(from)?;
+		let to = self.get_namespace(to)?;
+		self.remapper_b(from, to, inheritance)
+	}
+
+	/// Creates a remapper from the names of `namespace` in these mappings to the names of `namespace` in the `other` mappings.
+	///
+	/// This is useful for going from the names before applying a [diff][crate::tree::mappings_diff::MappingsDiff] to the
+	/// names after it. Classes, fields and methods are paired up by their names in the first namespace, so `namespace` can't
+	/// be the first namespace. Entries missing a name in `namespace` in either of the mappings are not remapped. The super
+	/// classes given by `inheritance` must use the class names of `namespace` in these mappings.
+	pub fn remapper_to<'a, 'i, I>(&'a self, other: &'a Mappings<N>, namespace: Namespace<N>, inheritance: &'i I)
+			-> Result<BRemapperImpl<'a, 'i, N, I>> {
+		let first = Namespace::new(0)?;
+		if namespace == first {
+			bail!("cannot remap the first namespace between mappings, as the mappings are keyed by the names of it");
+		}
+
+		let remapper_a_from = self.remapper_a(first, namespace)?;
+		let remapper_a_to = other.remapper_a(first, namespace)?;
+
+		let mut classes = IndexMap::new();
+		for (key, class) in &self.classes {
+			let Some(other_class) = other.classes.get(key) else { continue };
+
+			if let (Some(name_from), Some(name_to)) = (&class.info.names[namespace], &other_class.info.names[namespace]) {
+				let mut fields = IndexMap::new();
+				for (key, field) in &class.fields {
+					let Some(other_field) = other_class.fields.get(key) else { continue };
+
+					if let (Some(name_from), Some(name_to)) = (&field.info.names[namespace], &other_field.info.names[namespace]) {
+						let desc_from = remapper_a_from.map_field_desc(&field.info.desc)?;
+						let desc_to = remapper_a_to.map_field_desc(&other_field.info.desc)?;
+
+						fields.insert(TupleKey(name_from.as_slice(), desc_from), TupleKey(name_to.as_slice(), desc_to));
+					}
+				}
+
+				let mut methods = IndexMap::new();
+				for (key, method) in &class.methods {
+					let Some(other_method) = other_class.methods.get(key) else { continue };
+
+					if let (Some(name_from), Some(name_to)) = (&method.info.names[namespace], &other_method.info.names[namespace]) {
+						let desc_from = remapper_a_from.map_method_desc(&method.info.desc)?;
+						let desc_to = remapper_a_to.map_method_desc(&other_method.info.desc)?;
+
+						methods.insert(TupleKey(name_from.as_slice(), desc_from), TupleKey(name_to.as_slice(), desc_to));
+					}
+				}
+
+				classes.insert(name_from.as_slice(), BRemapperClass { name: name_to, fields, methods });
+			}
+		}
+		Ok(BRemapperImpl { classes, inheritance })
+	}
 }
 
 impl Mappings<2> {
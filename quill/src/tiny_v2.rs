@@ -4,6 +4,8 @@
 //! You can read a `.tiny` file using the [`read_file`] method, by passing a path.
 //! If you already have a [`Read`]er, you can use the [`read`] method.
 //!
+//! For huge files, [`iter_entries`] allows reading the mappings entry by entry, without building the whole tree in memory.
+//!
 //! It's recommended to check that the namespaces are indeed the ones expected.
 //! See [`Namespaces::check_that`] for more info.
 //!
@@ -11,15 +13,19 @@
 //! For writing `.tiny` files, there are the [`write`][fn@write] as well as the [`write_vec`] and [`write_string`] methods.
 //!
 //! Note that all writing sorts the tiny files.
+//!
+//! To change how the mappings are written, use [`write_with_options`] with some [`WriteOptions`].
 
 use std::fmt::Display;
 use std::fs::File;
 use anyhow::{anyhow, bail, Context, Result};
-use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use indexmap::IndexMap;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Read, Write};
+use std::iter::Enumerate;
 use std::path::Path;
 use java_string::{JavaStr, JavaString};
 use crate::lines::tiny_line::TinyLine;
-use crate::lines::WithMoreIdentIter;
+use crate::lines::Line;
 use crate::tree::mappings::{ClassMapping, FieldMapping, JavadocMapping, MappingInfo, MethodMapping, ParameterMapping, ClassNowodeMapping, FieldNowodeMapping, Mappings, MethodNowodeMapping, ParameterNowodeMapping};
 use crate::tree::names::{Names, Namespaces};
 use crate::tree::NodeInfo;
@@ -76,94 +82,261 @@ pub fn read_file<const N: usize>(path: impl AsRef<Path>) -> Result<Mappings<N>>
 /// );
 /// ```
 pub fn read<const N: usize>(reader: impl Read) -> Result<Mappings<N>> {
-	if N < 2 {
-		bail!("must read at least two namespaces, {N} is less than that");
+	read_impl(reader, None)
+}
+
+#[allow(clippy::tabs_in_doc_comments)]
+/// Reads the tiny v2 format, from the given reader, and renames the namespaces `from` to `to`.
+///
+/// This errors if the namespaces of the file are not exactly `from`. It's the same as calling
+/// [`Mappings::rename_namespaces`] after [`read`], but the mappings don't need to be moved around for that.
+///
+/// ```
+/// use quill::tree::mappings::Mappings;
+/// let string = "\
+/// tiny	2	0	official	intermediary
+/// c	a	C_1
+/// ";
+///
+/// let mappings: Mappings<2> = quill::tiny_v2::read_renaming(string.as_bytes(), ["official", "intermediary"], ["obf", "calamus"]).unwrap();
+///
+/// mappings.info.namespaces.check_that(["obf", "calamus"]).unwrap();
+/// ```
+pub fn read_renaming<const N: usize>(reader: impl Read, from: [&str; N], to: [&str; N]) -> Result<Mappings<N>> {
+	read_impl(reader, Some((from, to)))
+}
+
+fn read_impl<const N: usize>(reader: impl Read, rename: Option<([&str; N], [&str; N])>) -> Result<Mappings<N>> {
+	let mut entries = iter_entries(reader)?;
+	if let Some((from, to)) = rename {
+		entries.namespaces.change_names(from, to)?;
+	}
+
+	let mut mappings = Mappings::new(MappingInfo { namespaces: entries.namespaces.clone() });
+
+	for entry in entries {
+		add_entry(&mut mappings, entry?)?;
+	}
+
+	Ok(mappings)
+}
+
+/// Adds an entry to the mappings, as the child of the last class, method, etc. added.
+///
+/// The [`Entries`] only ever yield entries that have such a parent.
+fn add_entry<const N: usize>(mappings: &mut Mappings<N>, entry: MappingEntry<N>) -> Result<()> {
+	fn last<T>(map: &mut IndexMap<impl Sized, T>) -> Result<&mut T> {
+		map.last_mut().map(|(_, value)| value).context("entry without a parent")
 	}
 
-	let mut lines = BufReader::new(reader)
-		.lines()
-		.enumerate()
-		.map(|(line_number, line)| -> Result<TinyLine> {
-			TinyLine::new(line_number + 1, &line?)
-		})
-		.peekable();
+	match entry {
+		MappingEntry::Class(mapping) => {
+			mappings.add_class(ClassNowodeMapping::new(mapping))?;
+		},
+		MappingEntry::Field(mapping) => {
+			last(&mut mappings.classes)?.add_field(FieldNowodeMapping::new(mapping))?;
+		},
+		MappingEntry::Method(mapping) => {
+			last(&mut mappings.classes)?.add_method(MethodNowodeMapping::new(mapping))?;
+		},
+		MappingEntry::Parameter(mapping) => {
+			last(&mut last(&mut mappings.classes)?.methods)?.add_parameter(ParameterNowodeMapping::new(mapping))?;
+		},
+		MappingEntry::Javadoc(target, comment) => {
+			let class = last(&mut mappings.classes)?;
+			let javadoc = match target {
+				JavadocTarget::Class => &mut class.javadoc,
+				JavadocTarget::Field => &mut last(&mut class.fields)?.javadoc,
+				JavadocTarget::Method => &mut last(&mut class.methods)?.javadoc,
+				JavadocTarget::Parameter => &mut last(&mut last(&mut class.methods)?.parameters)?.javadoc,
+			};
+			add_comment(javadoc, comment)?;
+		},
+	}
 
-	let mut header = lines.next().context("no header line")??;
+	Ok(())
+}
 
+fn read_header<const N: usize>(mut header: TinyLine) -> Result<Namespaces<N>> {
 	if header.first_field != "tiny" || header.next()? != "2" || header.next()? != "0" {
 		bail!("header version isn't tiny v2.0, in line {header:?}");
 	}
 
-	let namespaces = header.into_namespaces()?;
-
-	let mut mappings = Mappings::new(MappingInfo { namespaces });
-
-	WithMoreIdentIter::new(&mut lines).on_every_line(|iter, line| {
-		if line.first_field == "c" {
-			let names = line.into_names()?;
-			let mapping = ClassMapping { names };
-			let class: ClassNowodeMapping<N> = ClassNowodeMapping::new(mapping);
-			let class = mappings.add_class(class)?;
-
-			iter.next_level().on_every_line(|iter, mut line| {
-				if line.first_field == "f" {
-					let desc = JavaString::from(line.next()?).try_into()?;
-					let names = line.into_names()?;
-					let mapping = FieldMapping { desc, names };
-					let field: FieldNowodeMapping<N> = FieldNowodeMapping::new(mapping);
-					let field = class.add_field(field)?;
-
-					iter.next_level().on_every_line(|_, line| {
-						if line.first_field == "c" {
-							add_comment(&mut field.javadoc, line)
-						} else {
-							Ok(())
-						}
-					}).context("reading field sub-sections")
-				} else if line.first_field == "m" {
-					let desc = JavaString::from(line.next()?).try_into()?;
-					let names = line.into_names()?;
-					let mapping = MethodMapping { desc, names };
-					let method: MethodNowodeMapping<N> = MethodNowodeMapping::new(mapping);
-					let method = class.add_method(method)?;
-
-					iter.next_level().on_every_line(|iter, mut line| {
-						if line.first_field == "p" {
-							let index = line.next()?.parse()?;
-							let names = line.into_names()?;
-							let mapping = ParameterMapping { index, names };
-							let parameter: ParameterNowodeMapping<N> = ParameterNowodeMapping::new(mapping);
-							let parameter = method.add_parameter(parameter)?;
-
-							iter.next_level().on_every_line(|_, line| {
-								if line.first_field == "c" {
-									add_comment(&mut parameter.javadoc, line)
-								} else {
-									Ok(())
-								}
-							}).context("reading parameter sub-sections")
-						} else if line.first_field == "c" {
-							add_comment(&mut method.javadoc, line)
-						} else {
-							Ok(())
-						}
-					}).context("reading method sub-sections")
-				} else if line.first_field == "c" {
-					add_comment(&mut class.javadoc, line)
-				} else {
-					Ok(())
-				}
-			}).context("reading class sub-sections")
-		} else {
-			Ok(())
-		}
-	}).context("reading lines")?;
+	header.into_namespaces()
+}
+
+/// An entry of a tiny v2 file, as returned by [`iter_entries`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MappingEntry<const N: usize> {
+	/// A class. All entries until the next class belong to this class.
+	Class(ClassMapping<N>),
+	/// A field of the last class.
+	Field(FieldMapping<N>),
+	/// A method of the last class.
+	Method(MethodMapping<N>),
+	/// A parameter of the last method.
+	Parameter(ParameterMapping<N>),
+	/// A comment of the last entry of the given kind.
+	///
+	/// Note that the comment of a method may come after its parameters.
+	Javadoc(JavadocTarget, JavadocMapping),
+}
+
+/// What a [`MappingEntry::Javadoc`] belongs to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JavadocTarget {
+	Class,
+	Field,
+	Method,
+	Parameter,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Section {
+	Class,
+	Field,
+	Method,
+	Parameter,
+	Javadoc,
+	Unknown,
+}
+
+#[allow(clippy::tabs_in_doc_comments)]
+/// Reads the tiny v2 format entry by entry, from the given reader.
+///
+/// Unlike [`read`], this doesn't build a [`Mappings`] tree, but yields the entries in the order they appear in the file.
+/// This allows filtering huge files without holding all of them in memory. Note that this also means that duplicate entries
+/// aren't detected.
+///
+/// The header is read right away, use [`Entries::namespaces`] to get the namespaces.
+///
+/// ```
+/// use quill::tiny_v2::MappingEntry;
+/// let string = "\
+/// tiny	2	0	namespaceA	namespaceB
+/// c	A	B
+/// 	f	LA;	a	b
+/// c	C	D
+/// ";
+///
+/// let entries = quill::tiny_v2::iter_entries::<2>(string.as_bytes()).unwrap();
+/// let classes = entries
+///     .filter(|entry| matches!(entry, Ok(MappingEntry::Class(_))))
+///     .count();
+/// assert_eq!(classes, 2);
+/// ```
+pub fn iter_entries<const N: usize>(reader: impl Read) -> Result<Entries<impl Read, N>> {
+	if N < 2 {
+		bail!("must read at least two namespaces, {N} is less than that");
+	}
+
+	let mut lines = BufReader::new(reader).lines().enumerate();
+
+	let (line_number, header) = lines.next().context("no header line")?;
+	let namespaces = read_header(TinyLine::new(line_number + 1, &header?)?)?;
+
+	Ok(Entries {
+		namespaces,
+		lines,
+		sections: Vec::new(),
+		failed: false,
+	})
+}
+
+/// An iterator over the entries of a tiny v2 file, created by [`iter_entries`].
+///
+/// After the first error, this iterator doesn't return any more entries.
+pub struct Entries<R, const N: usize> {
+	namespaces: Namespaces<N>,
+	lines: Enumerate<Lines<BufReader<R>>>,
+	/// The kind of the section we're in, for each level of indentation.
+	sections: Vec<Section>,
+	failed: bool,
+}
 
-	if let Some(line) = lines.next() {
-		bail!("expected end of input, got: {line:?}");
+impl<R, const N: usize> Entries<R, N> {
+	/// Returns the namespaces read from the header.
+	pub fn namespaces(&self) -> &Namespaces<N> {
+		&self.namespaces
 	}
 
-	Ok(mappings)
+	/// Parses one line. Returns `None` for lines that don't produce an entry.
+	fn parse_line(&mut self, mut line: TinyLine) -> Result<Option<MappingEntry<N>>> {
+		let idents = line.get_idents();
+		if idents > self.sections.len() {
+			bail!("expected an indentation of at most {} for line {}: {line:?}", self.sections.len(), line.get_line_number());
+		}
+		self.sections.truncate(idents);
+
+		let parent = self.sections.last().copied();
+
+		let (section, entry) = match (parent, line.first_field.as_str()) {
+			(None, "c") => {
+				let names = line.into_names()?;
+				(Section::Class, Some(MappingEntry::Class(ClassMapping { names })))
+			},
+			(Some(Section::Class), "f") => {
+				let desc = JavaString::from(line.next()?).try_into()?;
+				let names = line.into_names()?;
+				(Section::Field, Some(MappingEntry::Field(FieldMapping { desc, names })))
+			},
+			(Some(Section::Class), "m") => {
+				let desc = JavaString::from(line.next()?).try_into()?;
+				let names = line.into_names()?;
+				(Section::Method, Some(MappingEntry::Method(MethodMapping { desc, names })))
+			},
+			(Some(Section::Method), "p") => {
+				let index = line.next()?.parse()?;
+				let names = line.into_names()?;
+				(Section::Parameter, Some(MappingEntry::Parameter(ParameterMapping { index, names })))
+			},
+			(Some(Section::Javadoc), _) => bail!("comments can't have sub-sections, got {line:?}"),
+			(Some(parent), "c") if parent != Section::Unknown => {
+				let target = match parent {
+					Section::Class => JavadocTarget::Class,
+					Section::Field => JavadocTarget::Field,
+					Section::Method => JavadocTarget::Method,
+					_ => JavadocTarget::Parameter,
+				};
+				let javadoc = JavadocMapping(unescape(line.end()?));
+				(Section::Javadoc, Some(MappingEntry::Javadoc(target, javadoc)))
+			},
+			// like `read`, ignore unknown sections, together with their sub-sections
+			_ => (Section::Unknown, None),
+		};
+
+		self.sections.push(section);
+
+		Ok(entry)
+	}
+}
+
+impl<R: Read, const N: usize> Iterator for Entries<R, N> {
+	type Item = Result<MappingEntry<N>>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.failed {
+			return None;
+		}
+
+		while let Some((line_number, line)) = self.lines.next() {
+			let result = line.map_err(anyhow::Error::from)
+				.and_then(|line| TinyLine::new(line_number + 1, &line))
+				.and_then(|line| self.parse_line(line))
+				.with_context(|| anyhow!("in line {}", line_number + 1));
+
+			match result {
+				Ok(Some(entry)) => return Some(Ok(entry)),
+				Ok(None) => {},
+				Err(e) => {
+					self.failed = true;
+					return Some(Err(e));
+				},
+			}
+		}
+
+		None
+	}
 }
 
 pub(crate) fn unescape(s: String) -> String {
@@ -173,8 +346,7 @@ pub(crate) fn escape(s: &str) -> String {
 	s.replace('\n', "\\n")
 }
 
-fn add_comment(javadoc: &mut Option<JavadocMapping>, line: TinyLine) -> Result<()> {
-	let comment = JavadocMapping(unescape(line.end()?));
+fn add_comment(javadoc: &mut Option<JavadocMapping>, comment: JavadocMapping) -> Result<()> {
 	if let Some(javadoc) = javadoc {
 		bail!("only one comment is allowed, got {javadoc:?} and {comment:?}")
 	} else {
@@ -287,6 +459,24 @@ fn write_names<const N: usize>(w: &mut impl Write, names: &Names<N, impl Display
 /// Note that there are also the helper methods [`write_vec`] for writing into a `Vec<u8>` directly,
 /// and the helper method [`write_string`] that also tries to convert that `Vec<u8>` into a `String`.
 pub fn write<const N: usize>(mappings: &Mappings<N>, w: &mut impl Write) -> Result<()> {
+	write_with_options(mappings, w, &WriteOptions::default())
+}
+
+/// Options for writing tiny v2 files, used by [`write_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+	/// Skip writing classes, fields, methods and parameters that don't change their name in any namespace.
+	///
+	/// A name doesn't change if it's either absent or equal to the name in the first namespace. Something is only skipped if
+	/// it has no javadoc, and if none of its children are written. So a class with an identity name but renamed members is
+	/// still written.
+	pub omit_identity: bool,
+}
+
+/// Writes the given mappings to the given writer, in the tiny v2 format, using the given options.
+///
+/// Calling this with the default [`WriteOptions`] is the same as calling [`write`][fn@write].
+pub fn write_with_options<const N: usize>(mappings: &Mappings<N>, w: &mut impl Write, options: &WriteOptions) -> Result<()> {
 	// the buffering makes it much faster
 	let mut w = BufWriter::new(w);
 	let w = &mut w;
@@ -301,6 +491,10 @@ pub fn write<const N: usize>(mappings: &Mappings<N>, w: &mut impl Write) -> Resu
 	let mut classes: Vec<_> = mappings.classes.values().collect();
 	classes.sort_by_key(|x| &x.info);
 	for class in classes {
+		if options.omit_identity && is_identity_class(class) {
+			continue;
+		}
+
 		write!(w, "c")?;
 		write_names(w, &class.info.names)?;
 
@@ -311,6 +505,10 @@ pub fn write<const N: usize>(mappings: &Mappings<N>, w: &mut impl Write) -> Resu
 		let mut fields: Vec<_> = class.fields.values().collect();
 		fields.sort_by_key(|x| &x.info);
 		for field in fields {
+			if options.omit_identity && is_identity_field(field) {
+				continue;
+			}
+
 			write!(w, "\tf\t{}", field.info.desc.as_inner())?;
 			write_names(w, &field.info.names)?;
 
@@ -322,6 +520,10 @@ pub fn write<const N: usize>(mappings: &Mappings<N>, w: &mut impl Write) -> Resu
 		let mut methods: Vec<_> = class.methods.values().collect();
 		methods.sort_by_key(|x| &x.info);
 		for method in methods {
+			if options.omit_identity && is_identity_method(method) {
+				continue;
+			}
+
 			write!(w, "\tm\t{}", method.info.desc.as_inner())?;
 			write_names(w, &method.info.names)?;
 
@@ -332,6 +534,10 @@ pub fn write<const N: usize>(mappings: &Mappings<N>, w: &mut impl Write) -> Resu
 			let mut parameters: Vec<_> = method.parameters.values().collect();
 			parameters.sort_by_key(|x| &x.info);
 			for parameter in parameters {
+				if options.omit_identity && is_identity_parameter(parameter) {
+					continue;
+				}
+
 				write!(w, "\t\tp\t{}", parameter.info.index)?;
 				write_names(w, &parameter.info.names)?;
 
@@ -343,4 +549,23 @@ pub fn write<const N: usize>(mappings: &Mappings<N>, w: &mut impl Write) -> Resu
 	}
 
 	Ok(())
-}
\ No newline at end of file
+}
+
+fn is_identity_class<const N: usize>(class: &ClassNowodeMapping<N>) -> bool {
+	class.info.names.is_identity() && class.javadoc.is_none() &&
+		class.fields.values().all(is_identity_field) &&
+		class.methods.values().all(is_identity_method)
+}
+
+fn is_identity_field<const N: usize>(field: &FieldNowodeMapping<N>) -> bool {
+	field.info.names.is_identity() && field.javadoc.is_none()
+}
+
+fn is_identity_method<const N: usize>(method: &MethodNowodeMapping<N>) -> bool {
+	method.info.names.is_identity() && method.javadoc.is_none() &&
+		method.parameters.values().all(is_identity_parameter)
+}
+
+fn is_identity_parameter<const N: usize>(parameter: &ParameterNowodeMapping<N>) -> bool {
+	parameter.info.names.is_identity() && parameter.javadoc.is_none()
+}
@@ -1,5 +1,7 @@
-use std::fmt::Debug;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
 use std::hash::Hash;
+use std::sync::OnceLock;
 use anyhow::{anyhow, bail, Context, Result};
 use indexmap::IndexMap;
 use indexmap::map::Entry;
@@ -28,6 +30,22 @@ pub struct Mappings<const N: usize> {
 	pub info: MappingInfo<N>,
 	pub classes: IndexMap<ClassName, ClassNowodeMapping<N>>,
 	pub javadoc: Option<JavadocMapping>,
+	/// The index used by [`class_by_name`][Mappings::class_by_name].
+	///
+	/// This isn't public, so that mappings can only be constructed outside of this crate with [`NodeInfo::new`], which starts
+	/// with an empty index.
+	pub(crate) class_index: ClassIndex<N>,
+}
+
+/// For each namespace, the key in [`Mappings::classes`] of each class name, built on the first use of
+/// [`Mappings::class_by_name`].
+#[derive(Clone, Default)]
+pub(crate) struct ClassIndex<const N: usize>(OnceLock<Vec<HashMap<ClassName, ClassName>>>);
+
+impl<const N: usize> Debug for ClassIndex<N> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str(if self.0.get().is_some() { "ClassIndex(built)" } else { "ClassIndex(not built)" })
+	}
 }
 
 impl<const N: usize> NodeInfo<MappingInfo<N>> for Mappings<N> {
@@ -44,6 +62,7 @@ impl<const N: usize> NodeInfo<MappingInfo<N>> for Mappings<N> {
 			info,
 			classes: IndexMap::new(),
 			javadoc: None,
+			class_index: ClassIndex::default(),
 		}
 	}
 }
@@ -65,10 +84,54 @@ impl<const N: usize> Mappings<N> {
 	}
 
 	pub(crate) fn add_class(&mut self, child: ClassNowodeMapping<N>) -> Result<&mut ClassNowodeMapping<N>> {
+		self.invalidate_class_index();
 		add_child(&mut self.classes, child)
 			.with_context(|| anyhow!("failed to add class to mappings {:?}", self.info))
 	}
 
+	/// Looks up a class by its name in any namespace.
+	///
+	/// The [`classes`][Mappings::classes] are keyed by the name in the first namespace, so looking up by the name in another
+	/// namespace uses an index from names to keys. That index is built on the first call, and is meant for read-mostly use,
+	/// for example in interactive tools.
+	///
+	/// Adding classes with the methods of the mappings drops the index, so that it's built again on the next call. As the
+	/// fields can be changed freely, changing the [`classes`][Mappings::classes] directly doesn't: call
+	/// [`invalidate_class_index`][Mappings::invalidate_class_index] after that. Until then, a class renamed or added since the
+	/// index was built isn't found by its new name.
+	pub fn class_by_name(&self, namespace: &str, name: &ClassNameSlice) -> Result<Option<&ClassNowodeMapping<N>>> {
+		let namespace = self.info.namespaces.get_namespace(namespace)?;
+
+		if namespace == Namespace::new(0)? {
+			return Ok(self.classes.get(name));
+		}
+
+		let has_name = |class: &&ClassNowodeMapping<N>| class.info.names[namespace].as_deref() == Some(name);
+
+		let index = self.class_index.0.get_or_init(|| {
+			let mut index = vec![HashMap::new(); N];
+			for (key, class) in &self.classes {
+				for (id, class_name) in class.info.names.names().iter().enumerate() {
+					if let Some(class_name) = class_name {
+						index[id].entry(class_name.clone()).or_insert_with(|| key.clone());
+					}
+				}
+			}
+			index
+		});
+
+		Ok(index[namespace.0].get(name)
+			.and_then(|key| self.classes.get(key))
+			.filter(has_name))
+	}
+
+	/// Drops the index used by [`class_by_name`][Mappings::class_by_name], so that it's built again on the next call.
+	///
+	/// Call this after changing the [`classes`][Mappings::classes] directly.
+	pub fn invalidate_class_index(&mut self) {
+		self.class_index.0.take();
+	}
+
 	pub(crate) fn get_class_name(&self, class: &ClassNameSlice, namespace: Namespace<N>) -> Result<&ClassNameSlice> {
 		self.classes.get(class)
 			.with_context(|| anyhow!("no entry for class {class:?}"))?
@@ -381,6 +444,42 @@ impl<const N: usize> GetNames<N, ParameterName> for ParameterMapping<N> {
 	}
 }
 
+/// The location of a node in [`Mappings`], given by its keys, so by its names in the first namespace.
+///
+/// The [`Display`][std::fmt::Display] implementation gives a human readable form, like `class a, method b(I)V`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Location {
+	Class(ClassName),
+	Field(ClassName, FieldNameAndDesc),
+	Method(ClassName, MethodNameAndDesc),
+	Parameter(ClassName, MethodNameAndDesc, ParameterKey),
+}
+
+impl std::fmt::Display for Location {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Location::Class(class) => write!(f, "class {class}"),
+			Location::Field(class, field) => write!(f, "class {class}, field {}:{}", field.name, field.desc),
+			Location::Method(class, method) => write!(f, "class {class}, method {}{}", method.name, method.desc),
+			Location::Parameter(class, method, parameter) => {
+				write!(f, "class {class}, method {}{}, parameter {}", method.name, method.desc, parameter.index)
+			},
+		}
+	}
+}
+
+/// Decides which side wins if two mappings give different names (or javadoc) for the same thing, see
+/// [`Mappings::merge_over`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+	/// Keep the name of the mappings `merge_over` is called on.
+	PreferSelf,
+	/// Take the name of the mappings given as argument to `merge_over`.
+	PreferOther,
+	/// Fail on the first conflict.
+	Error,
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct JavadocMapping(pub String);
 
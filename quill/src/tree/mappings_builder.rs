@@ -0,0 +1,175 @@
+//! Building [`Mappings`] from scratch.
+//!
+//! See [`MappingsBuilder`] for an example.
+
+use std::fmt::Debug;
+use anyhow::{anyhow, bail, Context, Error, Result};
+use java_string::JavaStr;
+use duke::tree::class::ClassName;
+use duke::tree::field::FieldDescriptor;
+use duke::tree::method::{MethodDescriptor, MethodName, MethodNameAndDesc};
+use crate::tree::mappings::{ClassMapping, ClassNowodeMapping, FieldMapping, FieldNowodeMapping, MappingInfo, Mappings, MethodMapping, MethodNowodeMapping, ParameterMapping, ParameterNowodeMapping};
+use crate::tree::names::{Names, Namespaces};
+use crate::tree::NodeInfo;
+
+/// A builder for [`Mappings`].
+///
+/// Names are given as one string for each namespace, where an empty string means that there's no name in that namespace.
+/// Classes and members are referred to by their names (and descriptors) in the first namespace, which must always be present.
+///
+/// Nothing is checked before calling [`build`][MappingsBuilder::build], which reports the first error encountered, like a
+/// member of a class that wasn't added, an invalid name, or an entry that was added twice.
+///
+/// ```
+/// use quill::tree::mappings_builder::MappingsBuilder;
+///
+/// let mappings = MappingsBuilder::new()
+///     .namespaces(["official", "named"])
+///     .add_class(["a", "org/example/Foo"])
+///     .add_field("a", "I", ["b", "count"])
+///     .add_method("a", "(I)V", ["c", "add"])
+///     .add_parameter("a", "c", "(I)V", 1, ["", "amount"])
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(mappings.classes.len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MappingsBuilder<const N: usize> {
+	namespaces: Option<[String; N]>,
+	classes: Vec<[String; N]>,
+	fields: Vec<(String, String, [String; N])>,
+	methods: Vec<(String, String, [String; N])>,
+	parameters: Vec<(String, String, String, usize, [String; N])>,
+}
+
+impl<const N: usize> Default for MappingsBuilder<N> {
+	fn default() -> Self {
+		MappingsBuilder {
+			namespaces: None,
+			classes: Vec::new(),
+			fields: Vec::new(),
+			methods: Vec::new(),
+			parameters: Vec::new(),
+		}
+	}
+}
+
+fn to_owned<const N: usize>(names: [&str; N]) -> [String; N] {
+	names.map(|name| name.to_owned())
+}
+
+fn make_names<const N: usize, T>(names: &[String; N]) -> Result<Names<N, T>>
+where
+	T: for<'a> TryFrom<&'a JavaStr, Error = Error> + AsRef<JavaStr> + Debug,
+{
+	let mut result = Vec::with_capacity(N);
+	for name in names {
+		result.push(if name.is_empty() {
+			None
+		} else {
+			Some(T::try_from(JavaStr::from_str(name)).with_context(|| anyhow!("invalid name {name:?}"))?)
+		});
+	}
+
+	let result: [Option<T>; N] = result.try_into()
+		.map_err(|_| anyhow!("expected exactly {N} names"))?;
+
+	Names::try_from(result)
+}
+
+impl<const N: usize> MappingsBuilder<N> {
+	pub fn new() -> MappingsBuilder<N> {
+		MappingsBuilder::default()
+	}
+
+	/// Sets the names of the namespaces. This must be called before [`build`][MappingsBuilder::build].
+	pub fn namespaces(mut self, namespaces: [&str; N]) -> Self {
+		self.namespaces = Some(to_owned(namespaces));
+		self
+	}
+
+	/// Adds a class with the given names.
+	pub fn add_class(mut self, names: [&str; N]) -> Self {
+		self.classes.push(to_owned(names));
+		self
+	}
+
+	/// Adds a field with the given names and descriptor to the class of the given name.
+	///
+	/// The descriptor is the one in the first namespace.
+	pub fn add_field(mut self, class: &str, desc: &str, names: [&str; N]) -> Self {
+		self.fields.push((class.to_owned(), desc.to_owned(), to_owned(names)));
+		self
+	}
+
+	/// Adds a method with the given names and descriptor to the class of the given name.
+	///
+	/// The descriptor is the one in the first namespace.
+	pub fn add_method(mut self, class: &str, desc: &str, names: [&str; N]) -> Self {
+		self.methods.push((class.to_owned(), desc.to_owned(), to_owned(names)));
+		self
+	}
+
+	/// Adds a parameter with the given local variable index and names to the method given by its name and descriptor, in
+	/// the class of the given name.
+	pub fn add_parameter(mut self, class: &str, method: &str, method_desc: &str, index: usize, names: [&str; N]) -> Self {
+		self.parameters.push((class.to_owned(), method.to_owned(), method_desc.to_owned(), index, to_owned(names)));
+		self
+	}
+
+	/// Builds the mappings, checking all the names and keys.
+	pub fn build(self) -> Result<Mappings<N>> {
+		if N < 2 {
+			bail!("mappings must have at least two namespaces, {N} is less than that");
+		}
+
+		let namespaces = self.namespaces.context("no namespaces given, call `namespaces` before building")?;
+		let namespaces = Namespaces::try_from(namespaces)?;
+
+		let mut mappings = Mappings::new(MappingInfo { namespaces });
+
+		for names in &self.classes {
+			let names = make_names(names)
+				.with_context(|| anyhow!("for class {names:?}"))?;
+			mappings.add_class(ClassNowodeMapping::new(ClassMapping { names }))?;
+		}
+
+		fn get_class<'a, const N: usize>(mappings: &'a mut Mappings<N>, class: &str) -> Result<&'a mut ClassNowodeMapping<N>> {
+			let key = ClassName::try_from(JavaStr::from_str(class))?;
+			mappings.classes.get_mut(&key)
+				.with_context(|| anyhow!("class {class:?} wasn't added"))
+		}
+
+		for (class, desc, names) in &self.fields {
+			let desc = FieldDescriptor::try_from(JavaStr::from_str(desc))?;
+			let names = make_names(names)
+				.with_context(|| anyhow!("for field {names:?} in class {class:?}"))?;
+			get_class(&mut mappings, class)?
+				.add_field(FieldNowodeMapping::new(FieldMapping { desc, names }))?;
+		}
+
+		for (class, desc, names) in &self.methods {
+			let desc = MethodDescriptor::try_from(JavaStr::from_str(desc))?;
+			let names = make_names(names)
+				.with_context(|| anyhow!("for method {names:?} in class {class:?}"))?;
+			get_class(&mut mappings, class)?
+				.add_method(MethodNowodeMapping::new(MethodMapping { desc, names }))?;
+		}
+
+		for (class, method, method_desc, index, names) in &self.parameters {
+			let key = MethodNameAndDesc {
+				name: MethodName::try_from(JavaStr::from_str(method))?,
+				desc: MethodDescriptor::try_from(JavaStr::from_str(method_desc))?,
+			};
+			let names = make_names(names)
+				.with_context(|| anyhow!("for parameter {index} of method {method:?} {method_desc:?} in class {class:?}"))?;
+			get_class(&mut mappings, class)?
+				.methods.get_mut(&key)
+				.with_context(|| anyhow!("method {method:?} {method_desc:?} in class {class:?} wasn't added"))?
+				.add_parameter(ParameterNowodeMapping::new(ParameterMapping { index: *index, names }))?;
+		}
+
+		Ok(mappings)
+	}
+}
@@ -236,3 +236,23 @@ impl NodeJavadocInfo<Action<JavadocMapping>> for ParameterNowodeDiff {
 		&mut self.javadoc
 	}
 }
+
+/// The result of [`Mappings::three_way_merge`][crate::tree::mappings::Mappings::three_way_merge].
+#[derive(Debug)]
+pub struct MergeResult {
+	/// The base mappings, with all changes from both sides applied, except for the conflicting ones.
+	pub mappings: crate::tree::mappings::Mappings<2>,
+	/// The changes that couldn't be merged. The mappings keep the base version for these.
+	pub conflicts: Vec<MergeConflict>,
+}
+
+/// A node that both sides of a [three way merge][crate::tree::mappings::Mappings::three_way_merge] changed differently.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+	/// Where the conflict is, like `class a, method c(I)V`.
+	pub location: String,
+	/// What the change on "their" side is.
+	pub theirs: String,
+	/// What the change on "my" side is.
+	pub mine: String,
+}
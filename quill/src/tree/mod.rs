@@ -3,6 +3,7 @@ use crate::tree::names::Names;
 
 pub mod mappings;
 pub mod mappings_diff;
+pub mod mappings_builder;
 
 pub trait NodeInfo<I> {
 	fn get_node_info(&self) -> &I;
@@ -199,6 +200,14 @@ pub mod names {
 			&self.names
 		}
 
+		/// Checks if all names are either absent or equal to the name in the first namespace.
+		pub(crate) fn is_identity(&self) -> bool where T: PartialEq {
+			match &self.names[..] {
+				[] => true,
+				[head, tail @ ..] => tail.iter().all(|name| name.is_none() || name == head),
+			}
+		}
+
 		pub(crate) fn get_mut_with_src(&mut self, namespace: Namespace<N>) -> Result<(Option<&T>, Option<&mut T>)> {
 			if namespace.0 == 0 {
 				bail!("can't make a mutable and immutable reference to the same member of the array at once");
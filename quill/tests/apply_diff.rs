@@ -1,2 +1,37 @@
+use anyhow::{Context, Result};
+use pretty_assertions::assert_eq;
+use quill::tree::mappings::Mappings;
+use quill::tree::mappings_builder::MappingsBuilder;
+use quill::tree::mappings_diff::MappingsDiff;
 
-// TODO: write tests, see other test cases for what to do
\ No newline at end of file
+fn mappings(method_desc: &str, method_name: &str) -> Result<Mappings<2>> {
+	MappingsBuilder::new()
+		.namespaces(["official", "named"])
+		.add_class(["a", "org/example/Foo"])
+		.add_method("a", method_desc, ["c", method_name])
+		.build()
+}
+
+#[test]
+fn apply_diff() -> Result<()> {
+	let a = mappings("(I)V", "add")?;
+	let b = mappings("(I)V", "addAll")?;
+
+	let diff = MappingsDiff::diff(&a, &b)?;
+	let applied = diff.apply_to(a, "named")?;
+	assert_eq!(quill::tiny_v2::write_string(&applied)?, quill::tiny_v2::write_string(&b)?);
+
+	Ok(())
+}
+
+#[test]
+fn apply_diff_changing_descriptor() -> Result<()> {
+	let diff = MappingsDiff::diff(&mappings("(I)V", "add")?, &mappings("(I)V", "addAll")?)?;
+
+	let target = mappings("(J)V", "add")?;
+	let error = diff.apply_to(target, "named").err().context("expected an error")?;
+	let error = format!("{error:?}");
+	assert!(error.contains("must not change the descriptor"), "{error}");
+
+	Ok(())
+}
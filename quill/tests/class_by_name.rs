@@ -0,0 +1,41 @@
+use anyhow::{Context, Result};
+use java_string::JavaStr;
+use pretty_assertions::assert_eq;
+use duke::tree::class::ClassName;
+
+#[test]
+fn class_by_name() -> Result<()> {
+	let input = "\
+tiny	2	0	official	intermediary	named
+c	a	C_1	com/example/Foo
+c	b	C_2	
+";
+
+	let mut mappings = quill::tiny_v2::read::<3>(input.as_bytes())?;
+
+	let class = |name: &str| ClassName::try_from(JavaStr::from_str(name));
+
+	let by_named = mappings.class_by_name("named", &class("com/example/Foo")?)?.context("no class com/example/Foo")?;
+	let by_official = mappings.class_by_name("official", &class("a")?)?.context("no class a")?;
+	assert!(std::ptr::eq(by_named, by_official));
+
+	let by_intermediary = mappings.class_by_name("intermediary", &class("C_2")?)?.context("no class C_2")?;
+	let official = mappings.get_namespace("official")?;
+	assert_eq!(by_intermediary.info.names[official].as_ref(), Some(&class("b")?));
+
+	assert!(mappings.class_by_name("named", &class("a")?)?.is_none());
+	assert!(mappings.class_by_name("unknown", &class("a")?).is_err());
+
+	// classes changed directly after the index was built are only found by their new name once the index is invalidated
+	let named = mappings.get_namespace("named")?;
+	let c_2 = mappings.classes.get_mut(&class("b")?).context("no class b")?;
+	c_2.info.names[named] = Some(class("com/example/Bar")?);
+	assert!(mappings.class_by_name("named", &class("com/example/Bar")?)?.is_none());
+
+	mappings.invalidate_class_index();
+	let renamed = mappings.class_by_name("named", &class("com/example/Bar")?)?.context("no class com/example/Bar")?;
+	let intermediary = mappings.get_namespace("intermediary")?;
+	assert_eq!(renamed.info.names[intermediary].as_ref(), Some(&class("C_2")?));
+
+	Ok(())
+}
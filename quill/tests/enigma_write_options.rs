@@ -0,0 +1,81 @@
+use std::path::Path;
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+use quill::enigma_file::{EnigmaWriteOptions, Indent, SortOrder};
+
+fn read_files(dir: &Path) -> Result<Vec<(String, String)>> {
+	let mut files = Vec::new();
+	for entry in std::fs::read_dir(dir)? {
+		let path = entry?.path();
+		let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+		files.push((name, std::fs::read_to_string(&path)?));
+	}
+	files.sort();
+	Ok(files)
+}
+
+#[test]
+fn enigma_write_options() -> Result<()> {
+	let input = "\
+tiny	2	0	namespaceA	namespaceB
+c	A	B
+	c	A class comment.
+	m	(I)V	b	method
+		p	1	a	arg
+			c	A parameter comment.
+	m	()V	a	first
+	f	I	a	field
+c	A$C	B$Inner
+	f	J	c	other
+";
+	let mappings = quill::tiny_v2::read::<2>(input.as_bytes())?;
+
+	let dir = std::env::temp_dir().join(format!("quill-enigma-write-options-{}", std::process::id()));
+	let tabs = dir.join("tabs");
+	let spaces = dir.join("spaces");
+
+	quill::enigma_dir::write(&mappings, &tabs)?;
+	quill::enigma_dir::write_with_options(&mappings, &spaces, EnigmaWriteOptions { indent: Indent::Spaces(2), ..EnigmaWriteOptions::default() })?;
+
+	let tabs_files = read_files(&tabs)?;
+	let spaces_files = read_files(&spaces)?;
+
+	assert_eq!(tabs_files, [
+		("B.mapping".to_owned(), "\
+CLASS A B
+	COMMENT A class comment.
+	FIELD a field I
+	METHOD a first ()V
+	METHOD b method (I)V
+		ARG 1 arg
+			COMMENT A parameter comment.
+	CLASS C Inner
+		FIELD c other J
+".to_owned()),
+	]);
+	assert_ne!(tabs_files, spaces_files);
+
+	// they only differ in the leading whitespace
+	let strip = |files: &[(String, String)]| -> Vec<(String, Vec<String>)> {
+		files.iter()
+			.map(|(name, content)| (name.clone(), content.lines().map(|line| line.trim_start().to_owned()).collect()))
+			.collect()
+	};
+	assert_eq!(strip(&tabs_files), strip(&spaces_files));
+
+	assert_eq!(spaces_files[0].1.lines().nth(6), Some("      COMMENT A parameter comment."));
+
+	// indenting with spaces can be read back
+	let tabs_read = quill::enigma_dir::read(&tabs, mappings.info.namespaces.clone())?;
+	let spaces_read = quill::enigma_dir::read(&spaces, mappings.info.namespaces.clone())?;
+	assert_eq!(quill::tiny_v2::write_string(&spaces_read)?, quill::tiny_v2::write_string(&tabs_read)?);
+
+	// keeping the order of the mappings writes the methods in the order of the input
+	let unsorted = dir.join("unsorted");
+	quill::enigma_dir::write_with_options(&mappings, &unsorted, EnigmaWriteOptions { sort: SortOrder::Mappings, ..EnigmaWriteOptions::default() })?;
+	let unsorted_files = read_files(&unsorted)?;
+	assert_eq!(unsorted_files[0].1.lines().nth(3), Some("\tMETHOD b method (I)V"));
+
+	std::fs::remove_dir_all(&dir)?;
+	Ok(())
+}
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+use quill::tiny_v2::{JavadocTarget, MappingEntry};
+use quill::tree::names::Namespace;
+
+#[test]
+fn iter_entries() -> Result<()> {
+	let input = include_str!("read_file_input_tiny_v2.txt");
+
+	let entries = quill::tiny_v2::iter_entries::<2>(input.as_bytes())?;
+	entries.namespaces().check_that(["namespaceA", "namespaceB"])?;
+
+	let entries = entries.collect::<Result<Vec<_>>>()?;
+
+	let namespace_a = Namespace::new(0)?;
+	let classes: Vec<_> = entries.iter()
+		.filter_map(|entry| match entry {
+			MappingEntry::Class(class) => class.names[namespace_a].as_ref().map(|name| name.as_inner().to_string()),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(classes, ["classA1", "classA2"]);
+
+	let kinds: Vec<_> = entries.iter()
+		.map(|entry| match entry {
+			MappingEntry::Class(_) => "c",
+			MappingEntry::Field(_) => "f",
+			MappingEntry::Method(_) => "m",
+			MappingEntry::Parameter(_) => "p",
+			MappingEntry::Javadoc(JavadocTarget::Class, _) => "class comment",
+			MappingEntry::Javadoc(JavadocTarget::Field, _) => "field comment",
+			MappingEntry::Javadoc(JavadocTarget::Method, _) => "method comment",
+			MappingEntry::Javadoc(JavadocTarget::Parameter, _) => "parameter comment",
+		})
+		.collect();
+	assert_eq!(kinds, [
+		"c",
+		"c", "f", "f", "field comment", "m", "m", "p", "p", "p", "parameter comment", "method comment", "class comment",
+	]);
+
+	Ok(())
+}
+
+#[test]
+fn iter_entries_stops_after_error() -> Result<()> {
+	let input = "\
+tiny	2	0	namespaceA	namespaceB
+c	A	B
+			f	I	a	b
+c	C	D
+";
+
+	let mut entries = quill::tiny_v2::iter_entries::<2>(input.as_bytes())?;
+	assert!(matches!(entries.next(), Some(Ok(MappingEntry::Class(_)))));
+	assert!(matches!(entries.next(), Some(Err(_))));
+	assert!(entries.next().is_none());
+
+	Ok(())
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+use quill::tree::mappings_builder::MappingsBuilder;
+
+#[test]
+fn mappings_builder() -> Result<()> {
+	let mappings = MappingsBuilder::new()
+		.namespaces(["official", "intermediary", "named"])
+		.add_class(["a", "C_1", "org/example/Foo"])
+		.add_class(["b", "C_2", ""])
+		.add_field("a", "Lb;", ["c", "f_1", "bar"])
+		.add_method("a", "(ILb;)V", ["d", "m_1", "setBar"])
+		.add_parameter("a", "d", "(ILb;)V", 2, ["", "", "bar"])
+		.add_method("b", "()La;", ["e", "m_2", ""])
+		.build()?;
+
+	let expected = "\
+tiny	2	0	official	intermediary	named
+c	a	C_1	org/example/Foo
+	f	Lb;	c	f_1	bar
+	m	(ILb;)V	d	m_1	setBar
+		p	2			bar
+c	b	C_2	
+	m	()La;	e	m_2	
+";
+
+	assert_eq!(quill::tiny_v2::write_string(&mappings)?, expected);
+
+	Ok(())
+}
+
+#[test]
+fn mappings_builder_invalid() {
+	// no namespaces
+	assert!(MappingsBuilder::<2>::new().build().is_err());
+	// empty namespace name
+	assert!(MappingsBuilder::new().namespaces(["official", ""]).build().is_err());
+	// no name in the first namespace
+	assert!(MappingsBuilder::new().namespaces(["official", "named"]).add_class(["", "Foo"]).build().is_err());
+	// duplicate class
+	assert!(MappingsBuilder::new().namespaces(["official", "named"]).add_class(["a", "Foo"]).add_class(["a", "Bar"]).build().is_err());
+	// member of a class not added
+	assert!(MappingsBuilder::new().namespaces(["official", "named"]).add_field("a", "I", ["b", "bar"]).build().is_err());
+	// invalid name
+	assert!(MappingsBuilder::new().namespaces(["official", "named"]).add_class(["a", "Foo"]).add_method("a", "()V", ["b", "in.valid"]).build().is_err());
+	// parameter of a method not added
+	assert!(MappingsBuilder::new().namespaces(["official", "named"]).add_class(["a", "Foo"]).add_parameter("a", "b", "()V", 1, ["", "x"]).build().is_err());
+}
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+use quill::tree::mappings::{ConflictPolicy, Mappings};
+
+fn generated() -> Result<Mappings<2>> {
+	let input = "\
+tiny	2	0	official	named
+c	a	pkg/Generated
+	c	A generated comment.
+	f	I	a	generatedField
+	m	()V	b	generatedMethod
+c	b	pkg/OnlyGenerated
+";
+	quill::tiny_v2::read(input.as_bytes())
+}
+
+fn hand_written() -> Result<Mappings<2>> {
+	let input = "\
+tiny	2	0	official	named
+c	a	pkg/HandWritten
+	m	()V	b	
+		c	A hand written comment.
+	m	()V	c	handWrittenMethod
+c	c	pkg/OnlyHandWritten
+";
+	quill::tiny_v2::read(input.as_bytes())
+}
+
+#[test]
+fn merge_over_prefer_self() -> Result<()> {
+	let expected = "\
+tiny	2	0	official	named
+c	a	pkg/HandWritten
+	c	A generated comment.
+	f	I	a	generatedField
+	m	()V	b	generatedMethod
+		c	A hand written comment.
+	m	()V	c	handWrittenMethod
+c	b	pkg/OnlyGenerated
+c	c	pkg/OnlyHandWritten
+";
+
+	let merged = hand_written()?.merge_over(generated()?, ConflictPolicy::PreferSelf)?;
+	let actual = quill::tiny_v2::write_string(&merged)?;
+
+	assert_eq!(actual, expected, "left: actual, right: expected");
+
+	Ok(())
+}
+
+#[test]
+fn merge_over_prefer_other() -> Result<()> {
+	let merged = hand_written()?.merge_over(generated()?, ConflictPolicy::PreferOther)?;
+	let actual = quill::tiny_v2::write_string(&merged)?;
+
+	assert!(actual.contains("c\ta\tpkg/Generated\n"), "{actual}");
+	assert!(actual.contains("c\tc\tpkg/OnlyHandWritten\n"), "{actual}");
+
+	Ok(())
+}
+
+#[test]
+fn merge_over_error() -> Result<()> {
+	assert!(hand_written()?.merge_over(generated()?, ConflictPolicy::Error).is_err());
+
+	// without conflicts, nothing fails
+	let merged = generated()?.merge_over(generated()?, ConflictPolicy::Error)?;
+	assert_eq!(quill::tiny_v2::write_string(&merged)?, quill::tiny_v2::write_string(&generated()?)?);
+
+	Ok(())
+}
+
+#[test]
+fn merge_over_namespaces_must_match() -> Result<()> {
+	let other = generated()?.rename_namespaces(["official", "named"], ["official", "intermediary"])?;
+
+	assert!(generated()?.merge_over(other, ConflictPolicy::PreferSelf).is_err());
+
+	Ok(())
+}
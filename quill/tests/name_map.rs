@@ -0,0 +1,52 @@
+use anyhow::{Context, Result};
+use java_string::JavaStr;
+use pretty_assertions::assert_eq;
+use duke::tree::class::ClassName;
+use duke::tree::field::{FieldDescriptor, FieldName, FieldRef};
+use duke::tree::method::{MethodDescriptor, MethodName, MethodRef};
+
+#[test]
+fn name_map() -> Result<()> {
+	let input = "\
+tiny	2	0	official	intermediary	named
+c	a	C_1	com/example/Foo
+	f	La;	b	f_1	self
+	f	I	c	f_2	
+	m	(La;)V	d	m_1	accept
+c	e	C_2	
+";
+
+	let mappings = quill::tiny_v2::read::<3>(input.as_bytes())?;
+
+	let class = |name: &str| ClassName::try_from(JavaStr::from_str(name));
+
+	let classes = mappings.name_map("official", "named")?;
+	assert_eq!(classes.len(), 1);
+	assert_eq!(classes.get(&class("a")?).context("no mapping for class a")?, &class("com/example/Foo")?);
+
+	let classes = mappings.name_map("intermediary", "official")?;
+	assert_eq!(classes.len(), 2);
+	assert_eq!(classes.get(&class("C_2")?).context("no mapping for class C_2")?, &class("e")?);
+
+	let fields = mappings.field_name_map("intermediary", "named")?;
+	let field_ref = FieldRef {
+		class: class("C_1")?,
+		name: FieldName::try_from(JavaStr::from_str("f_1"))?,
+		desc: FieldDescriptor::try_from(JavaStr::from_str("LC_1;"))?,
+	};
+	assert_eq!(fields.len(), 1);
+	assert_eq!(fields.get(&field_ref).context("no mapping for field f_1")?.as_inner(), "self");
+
+	let methods = mappings.method_name_map("named", "official")?;
+	let method_ref = MethodRef {
+		class: class("com/example/Foo")?,
+		name: MethodName::try_from(JavaStr::from_str("accept"))?,
+		desc: MethodDescriptor::try_from(JavaStr::from_str("(Lcom/example/Foo;)V"))?,
+	};
+	assert_eq!(methods.len(), 1);
+	assert_eq!(methods.get(&method_ref).context("no mapping for method accept")?.as_inner(), "d");
+
+	assert!(mappings.name_map("official", "unknown").is_err());
+
+	Ok(())
+}
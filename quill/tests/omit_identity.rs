@@ -0,0 +1,43 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+use quill::tiny_v2::WriteOptions;
+
+#[test]
+fn omit_identity() -> Result<()> {
+	let input = "\
+tiny	2	0	namespaceA	namespaceB	namespaceC
+c	IdentityOnly	IdentityOnly	
+	f	I	field	field	field
+	m	()V	method		method
+		p	1	arg	arg	
+c	IdentityName	IdentityName	IdentityName
+	f	I	identityField	identityField	
+	f	I	renamedField	renamedField	otherField
+	m	()V	identityMethod	identityMethod	
+	m	(I)V	methodWithParameter	methodWithParameter	
+		p	1	arg		renamedArg
+c	RenamedName	Renamed	
+";
+	let expected = "\
+tiny	2	0	namespaceA	namespaceB	namespaceC
+c	IdentityName	IdentityName	IdentityName
+	f	I	renamedField	renamedField	otherField
+	m	(I)V	methodWithParameter	methodWithParameter	
+		p	1	arg		renamedArg
+c	RenamedName	Renamed	
+";
+
+	let input = quill::tiny_v2::read::<3>(input.as_bytes())?;
+
+	let mut actual = Vec::new();
+	quill::tiny_v2::write_with_options(&input, &mut actual, &WriteOptions { omit_identity: true })?;
+	let actual = String::from_utf8(actual)?;
+
+	assert_eq!(actual, expected, "left: actual, right: expected");
+
+	// by default, everything is written
+	let actual = quill::tiny_v2::write_string(&input)?;
+	assert!(actual.contains("c\tIdentityOnly\tIdentityOnly\t\n"));
+
+	Ok(())
+}
@@ -0,0 +1,35 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn orphaned_javadoc() -> Result<()> {
+	let input = "\
+tiny	2	0	namespaceA	namespaceB
+c	A	B
+	c	A mapped class.
+	m	()V	a	run
+		c	A mapped method.
+	m	()V	b	b
+		c	A method that isn't mapped.
+	m	()V	<init>	<init>
+		c	A constructor.
+	m	(I)V	c	
+		c	A method without a name in the second namespace.
+		p	1	a	value
+			c	A mapped parameter.
+	f	I	d	d
+c	C	C
+	c	A class that isn't mapped.
+";
+
+	let mappings = quill::tiny_v2::read::<2>(input.as_bytes())?;
+
+	let orphaned: Vec<String> = mappings.find_orphaned_javadoc().iter().map(ToString::to_string).collect();
+	assert_eq!(orphaned, [
+		"class A, method b()V",
+		"class A, method c(I)V",
+		"class C",
+	]);
+
+	Ok(())
+}
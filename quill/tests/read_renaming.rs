@@ -0,0 +1,24 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn read_renaming() -> Result<()> {
+	let input = "\
+tiny	2	0	official	intermediary
+c	a	net/minecraft/unmapped/C_1
+	f	La;	b	f_2
+	m	(La;)V	c	m_3
+";
+
+	let renamed = quill::tiny_v2::read_renaming(input.as_bytes(), ["official", "intermediary"], ["obf", "calamus"])?;
+	renamed.info.namespaces.check_that(["obf", "calamus"])?;
+
+	let expected = quill::tiny_v2::read::<2>(input.as_bytes())?
+		.rename_namespaces(["official", "intermediary"], ["obf", "calamus"])?;
+	assert_eq!(quill::tiny_v2::write_string(&renamed)?, quill::tiny_v2::write_string(&expected)?);
+
+	// the header must match the namespaces to rename from
+	assert!(quill::tiny_v2::read_renaming(input.as_bytes(), ["intermediary", "official"], ["calamus", "obf"]).is_err());
+
+	Ok(())
+}
@@ -1,12 +1,12 @@
 
 use anyhow::Result;
 use indexmap::{IndexMap, IndexSet};
-use java_string::JavaString;
+use java_string::{JavaStr, JavaString};
 use pretty_assertions::assert_eq;
 use duke::tree::class::{ClassName, ClassNameSlice};
 use duke::tree::field::{FieldDescriptorSlice, FieldNameSlice};
 use duke::tree::method::{MethodDescriptorSlice, MethodNameSlice};
-use quill::remapper::{ARemapper, BRemapper, JarSuperProv};
+use quill::remapper::{ARemapper, BRemapper, JarSuperProv, NoSuperClassProvider};
 use quill::tree::mappings::Mappings;
 
 #[test]
@@ -183,4 +183,26 @@ fn remap() -> Result<()> {
 	// TODO: another test method: also test if failures are there
 
 	Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn remapper_by_namespace_names() -> Result<()> {
+	let mappings: Mappings<2> = quill::tiny_v2::read(include_str!("remap_input.tiny").as_bytes())?;
+
+	let forward = mappings.remapper("namespaceA", "namespaceB", NoSuperClassProvider::new())?;
+	let backward = mappings.remapper("namespaceB", "namespaceA", NoSuperClassProvider::new())?;
+
+	let class = ClassName::try_from_inner("classA1".into())?;
+	let remapped = forward.map_class(&class)?;
+	assert_eq!(remapped.as_inner(), "classB1");
+	assert_eq!(backward.map_class(&remapped)?, class);
+
+	let desc = MethodDescriptorSlice::try_from_inner(JavaStr::from_str("(LclassA1;)LclassA1;"))?;
+	let remapped = forward.map_method_desc(desc)?;
+	assert_eq!(remapped.as_inner(), "(LclassB1;)LclassB1;");
+	assert_eq!(backward.map_method_desc(&remapped)?, desc);
+
+	assert!(mappings.remapper("namespaceA", "namespaceC", NoSuperClassProvider::new()).is_err());
+
+	Ok(())
+}
@@ -0,0 +1,43 @@
+use anyhow::Result;
+use indexmap::IndexSet;
+use pretty_assertions::assert_eq;
+use duke::tree::class::ClassName;
+
+#[test]
+fn restrict_to_classes() -> Result<()> {
+	let input = "\
+tiny	2	0	official	named
+c	a	org/example/Client
+	f	I	a	count
+	m	()V	b	run
+		p	1	c	value
+c	b	org/example/Common
+	m	()V	a	tick
+c	c	org/example/Server
+	f	I	a	port
+";
+	let expected = "\
+tiny	2	0	official	named
+c	a	org/example/Client
+	f	I	a	count
+	m	()V	b	run
+		p	1	c	value
+c	b	org/example/Common
+	m	()V	a	tick
+";
+
+	let mappings = quill::tiny_v2::read::<2>(input.as_bytes())?;
+	let server = ClassName::try_from_inner("org/example/Server".into())?;
+	assert!(mappings.class_by_name("named", &server)?.is_some());
+
+	let present: IndexSet<ClassName> = ["a", "b", "d"].into_iter()
+		.map(|name| ClassName::try_from_inner(name.into()))
+		.collect::<Result<_>>()?;
+
+	let restricted = mappings.restrict_to_classes(&present);
+
+	assert_eq!(quill::tiny_v2::write_string(&restricted)?, expected);
+	assert!(restricted.class_by_name("named", &server)?.is_none());
+
+	Ok(())
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+use quill::tree::mappings::Mappings;
+use quill::tree::mappings_builder::MappingsBuilder;
+use quill::tree::mappings_diff::MergeConflict;
+
+fn mappings(add_name: &str, remove_name: &str) -> Result<Mappings<2>> {
+	MappingsBuilder::new()
+		.namespaces(["official", "named"])
+		.add_class(["a", "org/example/Foo"])
+		.add_method("a", "(I)V", ["b", add_name])
+		.add_method("a", "(I)V", ["c", remove_name])
+		.build()
+}
+
+#[test]
+fn three_way_merge_clean() -> Result<()> {
+	let base = mappings("add", "remove")?;
+	let theirs = mappings("addAll", "remove")?;
+	let mine = mappings("add", "removeAll")?;
+
+	let result = Mappings::three_way_merge(&base, &theirs, &mine)?;
+
+	assert_eq!(result.conflicts, []);
+	assert_eq!(quill::tiny_v2::write_string(&result.mappings)?, quill::tiny_v2::write_string(&mappings("addAll", "removeAll")?)?);
+
+	Ok(())
+}
+
+#[test]
+fn three_way_merge_conflict() -> Result<()> {
+	let base = mappings("add", "remove")?;
+	let theirs = mappings("addAll", "remove")?;
+	let mine = mappings("addEach", "removeAll")?;
+
+	let result = Mappings::three_way_merge(&base, &theirs, &mine)?;
+
+	assert_eq!(result.conflicts, [
+		MergeConflict {
+			location: "class a, method b(I)V".to_owned(),
+			theirs: "Edit(MethodName(\"add\"), MethodName(\"addAll\"))".to_owned(),
+			mine: "Edit(MethodName(\"add\"), MethodName(\"addEach\"))".to_owned(),
+		},
+	]);
+	// the conflicting method keeps the base name, the other change still applies
+	assert_eq!(quill::tiny_v2::write_string(&result.mappings)?, quill::tiny_v2::write_string(&mappings("add", "removeAll")?)?);
+
+	Ok(())
+}
@@ -0,0 +1,430 @@
+//! Reordering the constant pool into a canonical order.
+//!
+//! See [`ClassFile::canonicalize_constant_pool`].
+
+use crate::{AttributeInfo, Annotation, ClassFile, CpInfo, ElementValue, StackMapFrame, VerificationTypeInfo};
+
+fn error(message: String) -> std::io::Error {
+	std::io::Error::other(message)
+}
+
+impl ClassFile {
+	/// Reorders the constant pool into a stable, `javac`-like order, and updates all references into it.
+	///
+	/// The order mimics how `javac` fills the constant pool: first the entries referenced by the instructions of the methods,
+	/// in the order of the methods and instructions. Then the entries referenced from the class structure itself, in the
+	/// order they appear in the class file (the class, super class, interfaces, fields, methods and attributes). Last, the
+	/// entries only referenced by other constant pool entries are appended, in the order they're referenced from the pool.
+	/// Entries not referenced at all are kept at the end, in their previous order.
+	///
+	/// This is not guaranteed to give the same bytes as `javac` produces, it only aims to be stable and `javac`-like: two
+	/// classes that only differ in the order of their constant pool end up with the same constant pool after this. No
+	/// duplicate entries are removed.
+	///
+	/// Fails if the class contains [`AttributeInfo::Other`] or [`CpInfo::Unknown`], as these could refer to the constant
+	/// pool in ways not known to this crate. Also fails on invalid constant pool indices and if an `ldc` instruction would
+	/// need to refer to an index larger than `255`. The class isn't modified if this fails.
+	pub fn canonicalize_constant_pool(&mut self) -> std::io::Result<()> {
+		if let Some(index) = self.constant_pool.iter().position(|entry| matches!(entry, CpInfo::Unknown { .. })) {
+			return Err(error(format!("can't canonicalize unknown constant pool entry at index {}", index + 1)));
+		}
+
+		let len = self.constant_pool.len();
+		let mut order: Vec<u16> = Vec::with_capacity(len);
+		let mut seen = vec![false; len];
+		fn record(order: &mut Vec<u16>, seen: &mut [bool], index: u16) -> std::io::Result<()> {
+			if index == 0 {
+				return Ok(());
+			}
+			let len = seen.len();
+			let slot = seen.get_mut(index as usize - 1)
+				.ok_or_else(|| error(format!("constant pool index {index} out of bounds for pool of size {len}")))?;
+			if !*slot {
+				*slot = true;
+				order.push(index);
+			}
+			Ok(())
+		}
+
+		// walking needs `&mut`, so record on a copy
+		let mut copy = self.clone();
+		for method in &mut copy.methods {
+			for attribute in &mut method.attributes {
+				if let AttributeInfo::Code { code, .. } = attribute {
+					walk_code(code, &mut |index| record(&mut order, &mut seen, *index))?;
+				}
+			}
+		}
+		copy.walk(&mut |index| record(&mut order, &mut seen, *index))?;
+
+		// append the entries referenced by other pool entries, this also visits the newly appended entries
+		let mut i = 0;
+		while i < order.len() {
+			let mut entry = self.constant_pool[order[i] as usize - 1].clone();
+			walk_cp_info(&mut entry, &mut |index| record(&mut order, &mut seen, *index))?;
+			i += 1;
+		}
+
+		for index in 1..=len {
+			if !seen[index - 1] {
+				order.push(index as u16);
+			}
+		}
+
+		// `new_index[old - 1]` is the new index of the entry at `old`
+		let mut new_index = vec![0u16; len];
+		for (position, &old) in order.iter().enumerate() {
+			new_index[old as usize - 1] = position as u16 + 1;
+		}
+		let mut map = |index: &mut u16| -> std::io::Result<()> {
+			if *index != 0 {
+				*index = new_index[*index as usize - 1];
+			}
+			Ok(())
+		};
+
+		let mut constant_pool = Vec::with_capacity(len);
+		for &old in &order {
+			let mut entry = self.constant_pool[old as usize - 1].clone();
+			walk_cp_info(&mut entry, &mut map)?;
+			constant_pool.push(entry);
+		}
+
+		let mut class = self.clone();
+		class.walk(&mut map)?;
+		class.constant_pool = constant_pool;
+
+		*self = class;
+		Ok(())
+	}
+
+	/// Calls `f` on all constant pool indices outside the constant pool, in the order they appear in the class file.
+	fn walk(&mut self, f: &mut impl FnMut(&mut u16) -> std::io::Result<()>) -> std::io::Result<()> {
+		f(&mut self.this_class)?;
+		f(&mut self.super_class)?;
+		for interface in &mut self.interfaces {
+			f(interface)?;
+		}
+		for field in &mut self.fields {
+			f(&mut field.name_index)?;
+			f(&mut field.descriptor_index)?;
+			walk_attributes(&mut field.attributes, f)?;
+		}
+		for method in &mut self.methods {
+			f(&mut method.name_index)?;
+			f(&mut method.descriptor_index)?;
+			walk_attributes(&mut method.attributes, f)?;
+		}
+		walk_attributes(&mut self.attributes, f)
+	}
+}
+
+fn walk_cp_info(entry: &mut CpInfo, f: &mut impl FnMut(&mut u16) -> std::io::Result<()>) -> std::io::Result<()> {
+	match entry {
+		CpInfo::Class { name_index } => f(name_index),
+		CpInfo::Fieldref { class_index, name_and_type_index } |
+		CpInfo::Methodref { class_index, name_and_type_index } |
+		CpInfo::InterfaceMethodref { class_index, name_and_type_index } => {
+			f(class_index)?;
+			f(name_and_type_index)
+		},
+		CpInfo::String { string_index } => f(string_index),
+		CpInfo::NameAndType { name_index, descriptor_index } => {
+			f(name_index)?;
+			f(descriptor_index)
+		},
+		CpInfo::MethodHandle { reference_index, .. } => f(reference_index),
+		CpInfo::MethodType { descriptor_index } => f(descriptor_index),
+		// the bootstrap method attribute index isn't an index into the constant pool
+		CpInfo::Dynamic { name_and_type_index, .. } |
+		CpInfo::InvokeDynamic { name_and_type_index, .. } => f(name_and_type_index),
+		CpInfo::Module { name_index } |
+		CpInfo::Package { name_index } => f(name_index),
+		CpInfo::Integer { .. } | CpInfo::Float { .. } | CpInfo::Long { .. } | CpInfo::Double { .. } | CpInfo::Utf8 { .. } => Ok(()),
+		CpInfo::Unknown { tag, .. } => Err(error(format!("can't walk unknown constant pool entry with tag {tag}"))),
+	}
+}
+
+fn walk_attributes(attributes: &mut [AttributeInfo], f: &mut impl FnMut(&mut u16) -> std::io::Result<()>) -> std::io::Result<()> {
+	for attribute in attributes {
+		walk_attribute(attribute, f)?;
+	}
+	Ok(())
+}
+
+fn walk_attribute(attribute: &mut AttributeInfo, f: &mut impl FnMut(&mut u16) -> std::io::Result<()>) -> std::io::Result<()> {
+	match attribute {
+		AttributeInfo::ConstantValue { attribute_name_index, constantvalue_index } => {
+			f(attribute_name_index)?;
+			f(constantvalue_index)?;
+		},
+		AttributeInfo::Code { attribute_name_index, code, exception_table, attributes, .. } => {
+			f(attribute_name_index)?;
+			walk_code(code, f)?;
+			for entry in exception_table {
+				f(&mut entry.catch_type)?;
+			}
+			walk_attributes(attributes, f)?;
+		},
+		AttributeInfo::StackMapTable { attribute_name_index, entries } => {
+			f(attribute_name_index)?;
+			for entry in entries {
+				let infos: Vec<&mut VerificationTypeInfo> = match entry {
+					StackMapFrame::SameLocals1StackItemFrame { stack, .. } |
+					StackMapFrame::SameLocals1StackItemFrameExtended { stack, .. } => vec![stack],
+					StackMapFrame::AppendFrame { locals, .. } => locals.iter_mut().collect(),
+					StackMapFrame::FullFrame { locals, stack, .. } => locals.iter_mut().chain(stack.iter_mut()).collect(),
+					_ => Vec::new(),
+				};
+				for info in infos {
+					if let VerificationTypeInfo::Object { cpool_index } = info {
+						f(cpool_index)?;
+					}
+				}
+			}
+		},
+		AttributeInfo::Exceptions { attribute_name_index, exception_index_table } => {
+			f(attribute_name_index)?;
+			for index in exception_index_table {
+				f(index)?;
+			}
+		},
+		AttributeInfo::InnerClasses { attribute_name_index, classes } => {
+			f(attribute_name_index)?;
+			for entry in classes {
+				f(&mut entry.inner_class_info_index)?;
+				f(&mut entry.outer_class_info_index)?;
+				f(&mut entry.inner_name_index)?;
+			}
+		},
+		AttributeInfo::EnclosingMethod { attribute_name_index, class_index, method_index } => {
+			f(attribute_name_index)?;
+			f(class_index)?;
+			f(method_index)?;
+		},
+		AttributeInfo::Synthetic { attribute_name_index } |
+		AttributeInfo::Deprecated { attribute_name_index } |
+		AttributeInfo::SourceDebugExtension { attribute_name_index, .. } => f(attribute_name_index)?,
+		AttributeInfo::Signature { attribute_name_index, signature_index } => {
+			f(attribute_name_index)?;
+			f(signature_index)?;
+		},
+		AttributeInfo::SourceFile { attribute_name_index, sourcefile_index } => {
+			f(attribute_name_index)?;
+			f(sourcefile_index)?;
+		},
+		AttributeInfo::LineNumberTable { attribute_name_index, .. } => f(attribute_name_index)?,
+		AttributeInfo::LocalVariableTable { attribute_name_index, local_variable_table } => {
+			f(attribute_name_index)?;
+			for entry in local_variable_table {
+				f(&mut entry.name_index)?;
+				f(&mut entry.descriptor_index)?;
+			}
+		},
+		AttributeInfo::LocalVariableTypeTable { attribute_name_index, local_variable_type_table } => {
+			f(attribute_name_index)?;
+			for entry in local_variable_type_table {
+				f(&mut entry.name_index)?;
+				f(&mut entry.signature_index)?;
+			}
+		},
+		AttributeInfo::RuntimeVisibleAnnotations { attribute_name_index, annotations } |
+		AttributeInfo::RuntimeInvisibleAnnotations { attribute_name_index, annotations } => {
+			f(attribute_name_index)?;
+			for annotation in annotations {
+				walk_annotation(annotation, f)?;
+			}
+		},
+		AttributeInfo::RuntimeVisibleParameterAnnotations { attribute_name_index, parameter_annotations } |
+		AttributeInfo::RuntimeInvisibleParameterAnnotations { attribute_name_index, parameter_annotations } => {
+			f(attribute_name_index)?;
+			for parameter in parameter_annotations {
+				for annotation in &mut parameter.annotations {
+					walk_annotation(annotation, f)?;
+				}
+			}
+		},
+		AttributeInfo::AnnotationDefault { attribute_name_index, default_value } => {
+			f(attribute_name_index)?;
+			walk_element_value(default_value, f)?;
+		},
+		AttributeInfo::BootstrapMethods { attribute_name_index, bootstrap_methods } => {
+			f(attribute_name_index)?;
+			for entry in bootstrap_methods {
+				f(&mut entry.bootstrap_method_ref)?;
+				for argument in &mut entry.boostrap_arguments {
+					f(argument)?;
+				}
+			}
+		},
+		AttributeInfo::MethodParameters { attribute_name_index, parameters } => {
+			f(attribute_name_index)?;
+			for parameter in parameters {
+				f(&mut parameter.name_index)?;
+			}
+		},
+		AttributeInfo::Module {
+			attribute_name_index, module_name_index, module_version_index, requires, exports, opens, uses_index, provides, ..
+		} => {
+			f(attribute_name_index)?;
+			f(module_name_index)?;
+			f(module_version_index)?;
+			for entry in requires {
+				f(&mut entry.requires_index)?;
+				f(&mut entry.requires_version_index)?;
+			}
+			for entry in exports {
+				f(&mut entry.exports_index)?;
+				for index in &mut entry.exports_to_index {
+					f(index)?;
+				}
+			}
+			for entry in opens {
+				f(&mut entry.opens_index)?;
+				for index in &mut entry.opens_to_index {
+					f(index)?;
+				}
+			}
+			for index in uses_index {
+				f(index)?;
+			}
+			for entry in provides {
+				f(&mut entry.provides_index)?;
+				for index in &mut entry.provides_with_index {
+					f(index)?;
+				}
+			}
+		},
+		AttributeInfo::ModulePackages { attribute_name_index, package_index: classes } |
+		AttributeInfo::NestMembers { attribute_name_index, classes } |
+		AttributeInfo::PermittedSubclasses { attribute_name_index, classes } => {
+			f(attribute_name_index)?;
+			for index in classes {
+				f(index)?;
+			}
+		},
+		AttributeInfo::ModuleMainClass { attribute_name_index, main_class_index: class_index } |
+		AttributeInfo::NestHost { attribute_name_index, host_class_index: class_index } => {
+			f(attribute_name_index)?;
+			f(class_index)?;
+		},
+		AttributeInfo::Record { attribute_name_index, components } => {
+			f(attribute_name_index)?;
+			for component in components {
+				f(&mut component.name_index)?;
+				f(&mut component.descriptor_index)?;
+				walk_attributes(&mut component.attributes, f)?;
+			}
+		},
+		AttributeInfo::Other { attribute_name_index, .. } => {
+			return Err(error(format!("can't canonicalize the constant pool of a class with the unknown attribute at name index {attribute_name_index}")));
+		},
+	}
+	Ok(())
+}
+
+fn walk_annotation(annotation: &mut Annotation, f: &mut impl FnMut(&mut u16) -> std::io::Result<()>) -> std::io::Result<()> {
+	f(&mut annotation.type_index)?;
+	for pair in &mut annotation.element_value_pairs {
+		f(&mut pair.element_name_index)?;
+		walk_element_value(&mut pair.value, f)?;
+	}
+	Ok(())
+}
+
+fn walk_element_value(value: &mut ElementValue, f: &mut impl FnMut(&mut u16) -> std::io::Result<()>) -> std::io::Result<()> {
+	match value {
+		ElementValue::Byte { const_value_index } |
+		ElementValue::Char { const_value_index } |
+		ElementValue::Double { const_value_index } |
+		ElementValue::Float { const_value_index } |
+		ElementValue::Integer { const_value_index } |
+		ElementValue::Long { const_value_index } |
+		ElementValue::Short { const_value_index } |
+		ElementValue::Boolean { const_value_index } |
+		ElementValue::String { const_value_index } => f(const_value_index),
+		ElementValue::Enum { type_name_index, const_name_index } => {
+			f(type_name_index)?;
+			f(const_name_index)
+		},
+		ElementValue::Class { class_info_index } => f(class_info_index),
+		ElementValue::Annotation { annotation_value } => walk_annotation(annotation_value, f),
+		ElementValue::Array { values } => {
+			for value in values {
+				walk_element_value(value, f)?;
+			}
+			Ok(())
+		},
+	}
+}
+
+/// Calls `f` on the constant pool indices of the instructions, in order.
+fn walk_code(code: &mut [u8], f: &mut impl FnMut(&mut u16) -> std::io::Result<()>) -> std::io::Result<()> {
+	fn read_i32(code: &[u8], pc: usize) -> std::io::Result<i32> {
+		code.get(pc..pc + 4)
+			.and_then(|bytes| bytes.try_into().ok())
+			.map(i32::from_be_bytes)
+			.ok_or_else(|| error(format!("code ends in the middle of a switch at {pc}")))
+	}
+
+	let mut pc = 0;
+	while pc < code.len() {
+		let opcode = code[pc];
+		let length = match opcode {
+			// ldc
+			0x12 => 2,
+			// ldc_w, ldc2_w, get/put static/field, invoke virtual/special/static, new, anewarray, checkcast, instanceof
+			0x13 | 0x14 | 0xb2..=0xb8 | 0xbb | 0xbd | 0xc0 | 0xc1 => 3,
+			// invokeinterface, invokedynamic
+			0xb9 | 0xba => 5,
+			// multianewarray
+			0xc5 => 4,
+
+			0x00..=0x0f | 0x1a..=0x35 | 0x3b..=0x83 | 0x85..=0x98 | 0xac..=0xb1 | 0xbe | 0xbf | 0xc2 | 0xc3 => 1,
+			// bipush, loads, stores, ret, newarray
+			0x10 | 0x15..=0x19 | 0x36..=0x3a | 0xa9 | 0xbc => 2,
+			// sipush, iinc, jumps, ifnull, ifnonnull
+			0x11 | 0x84 | 0x99..=0xa8 | 0xc6 | 0xc7 => 3,
+			// goto_w, jsr_w
+			0xc8 | 0xc9 => 5,
+			// wide
+			0xc4 => if code.get(pc + 1) == Some(&0x84) { 6 } else { 4 },
+			// tableswitch, lookupswitch
+			0xaa | 0xab => {
+				// the operands are aligned to a multiple of four
+				let start = (pc + 4) & !3;
+				let operands = if opcode == 0xaa {
+					let low = read_i32(code, start + 4)? as i64;
+					let high = read_i32(code, start + 8)? as i64;
+					12 + 4 * (high - low + 1).max(0) as usize
+				} else {
+					8 + 8 * read_i32(code, start + 4)?.max(0) as usize
+				};
+				start - pc + operands
+			},
+			opcode => return Err(error(format!("unknown opcode {opcode:#x} at {pc}"))),
+		};
+
+		if pc + length > code.len() {
+			return Err(error(format!("instruction {opcode:#x} at {pc} goes past the end of the code")));
+		}
+
+		match opcode {
+			0x12 => {
+				let mut index = code[pc + 1] as u16;
+				f(&mut index)?;
+				code[pc + 1] = u8::try_from(index)
+					.map_err(|_| error(format!("ldc at {pc} would need to refer to constant pool index {index}, larger than 255")))?;
+			},
+			0x13 | 0x14 | 0xb2..=0xbb | 0xbd | 0xc0 | 0xc1 | 0xc5 => {
+				let mut index = u16::from_be_bytes([code[pc + 1], code[pc + 2]]);
+				f(&mut index)?;
+				code[pc + 1..pc + 3].copy_from_slice(&index.to_be_bytes());
+			},
+			_ => {},
+		}
+
+		pc += length;
+	}
+	Ok(())
+}
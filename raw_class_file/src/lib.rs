@@ -91,8 +91,13 @@
 //! The [`ClassFile::length`] function gives the computed length of a class file, useful for allocating sufficient memory for buffers.
 use macros::notation;
 
+mod canonical_pool;
+
 mod macros;
 
+/// The magic every class file starts with, as bytes.
+const MAGIC: [u8; 4] = 0xCAFEBABEu32.to_be_bytes();
+
 impl ClassFile {
 	/// Converts the class file to binary representation.
 	///
@@ -112,7 +117,57 @@ impl ClassFile {
 
 	/// Attempts to read the class file in binary representation from the reader.
 	pub fn read(reader: &mut impl std::io::Read) -> std::io::Result<ClassFile> {
-		ClassFile::_read(reader, None)
+		ClassFile::_read(reader, ReadContext::default())
+	}
+
+	/// Writes the class file in the binary representation to the writer, leaving out the leading `0xCAFEBABE` magic.
+	///
+	/// The output starts with the `minor_version`. This is for container formats that strip the magic from the class files
+	/// they store. Use [`Self::read_without_magic`] to read such a class file again.
+	///
+	/// No format checking of any kind is done.
+	pub fn write_without_magic(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+		struct SkipMagic<'a, W> {
+			inner: &'a mut W,
+			skip: usize,
+		}
+
+		impl<W: std::io::Write> std::io::Write for SkipMagic<'_, W> {
+			fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+				let skipped = self.skip.min(buf.len());
+				self.skip -= skipped;
+				if skipped == buf.len() {
+					Ok(skipped)
+				} else {
+					Ok(skipped + self.inner.write(&buf[skipped..])?)
+				}
+			}
+
+			fn flush(&mut self) -> std::io::Result<()> {
+				self.inner.flush()
+			}
+		}
+
+		self._write(&mut SkipMagic { inner: writer, skip: MAGIC.len() })
+	}
+
+	/// Attempts to read the class file in binary representation from the reader, where the leading `0xCAFEBABE` magic
+	/// is missing.
+	///
+	/// The reading starts at the `minor_version`. This reads class files written by [`Self::write_without_magic`].
+	pub fn read_without_magic(reader: &mut impl std::io::Read) -> std::io::Result<ClassFile> {
+		ClassFile::_read(&mut std::io::Read::chain(MAGIC.as_slice(), reader), ReadContext::default())
+	}
+
+	/// Attempts to read the class file in binary representation from the reader, keeping constant pool entries with
+	/// unknown tags as [`CpInfo::Unknown`].
+	///
+	/// The class file format doesn't store the length of constant pool entries, so `unknown_length` must give the length
+	/// (without the tag) of the entries for each unknown tag. If it returns `None`, reading fails just like with
+	/// [`Self::read`]. This is only usable if the lengths of the tags are known by other means, as there's no way to skip
+	/// over an entry of unknown length and continue reading the rest of the class.
+	pub fn read_permissive(reader: &mut impl std::io::Read, unknown_length: impl Fn(u8) -> Option<usize>) -> std::io::Result<ClassFile> {
+		ClassFile::_read(reader, ReadContext { pool: None, unknown_length: Some(&unknown_length) })
 	}
 
 	/// The length of the class file produced by [`Self::to_bytes`], in bytes.
@@ -298,6 +353,11 @@ notation!(
 		},
 		_ {
 			tag => Err(std::io::Error::other(format!("unexpected constant pool tag {}", tag))),
+			/// A constant pool entry with a tag not known to this crate.
+			///
+			/// The `raw` bytes are the contents of the entry following the `tag`. Since the length of an unknown entry can't
+			/// be figured out from the class file, these are only produced by [`ClassFile::read_permissive`].
+			unknown Unknown,
 		},
 	}
 );
@@ -323,8 +383,23 @@ notation!(
 	}
 );
 
-fn pool_has_utf8(pool: Option<&Vec<CpInfo>>, index: u16, value: &[u8]) -> Result<bool, std::io::Error> {
-	let Some(pool) = pool else {
+/// What's known while reading a class file.
+#[derive(Clone, Copy, Default)]
+struct ReadContext<'a> {
+	/// The constant pool, once it's read.
+	pool: Option<&'a Vec<CpInfo>>,
+	/// Gives the length of constant pool entries with unknown tags, see [`ClassFile::read_permissive`].
+	unknown_length: Option<&'a dyn Fn(u8) -> Option<usize>>,
+}
+
+impl ReadContext<'_> {
+	fn unknown_length(&self, tag: u8) -> Option<usize> {
+		self.unknown_length.and_then(|unknown_length| unknown_length(tag))
+	}
+}
+
+fn pool_has_utf8(context: ReadContext, index: u16, value: &[u8]) -> Result<bool, std::io::Error> {
+	let Some(pool) = context.pool else {
 		return Err(std::io::Error::other("expected to have constant pool at this point of reading"));
 	};
 	let Some(entry) = pool.get((index - 1) as usize) else {
@@ -337,16 +412,16 @@ fn pool_has_utf8(pool: Option<&Vec<CpInfo>>, index: u16, value: &[u8]) -> Result
 }
 
 notation!(
-	enum AttributeInfo [pool] {
+	enum AttributeInfo [context] {
 		attribute_name_index: u16,
 		ConstantValue {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"ConstantValue")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"ConstantValue")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 2,
 			mut constantvalue_index: u16,
 		},
 		Code this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"Code")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"Code")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			mut max_stack: u16,
@@ -359,105 +434,105 @@ notation!(
 			mut attributes: Vec<AttributeInfo> [u16],
 		},
 		StackMapTable this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"StackMapTable")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"StackMapTable")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//number_of_entries: u16,
 			mut entries: Vec<StackMapFrame> [u16],
 		},
 		Exceptions {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"Exceptions")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"Exceptions")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 2 + 2 * exception_index_table.len(),
 			//number_of_exceptions: u16,
 			mut exception_index_table: Vec<u16> [u16],
 		},
 		InnerClasses this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"InnerClasses")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"InnerClasses")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//number_of_classes: u16,
 			mut classes: Vec<InnerClassesEntry> [u16],
 		},
 		EnclosingMethod {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"EnclosingMethod")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"EnclosingMethod")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 4,
 			mut class_index: u16,
 			mut method_index: u16,
 		},
 		Synthetic {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"Synthetic")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"Synthetic")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 0,
 		},
 		Signature {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"Signature")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"Signature")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 2,
 			mut signature_index: u16,
 		},
 		SourceFile {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"SourceFile")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"SourceFile")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 2,
 			mut sourcefile_index: u16,
 		},
 		SourceDebugExtension {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"SourceDebugExtension")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"SourceDebugExtension")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			//attribute_length: u32,
 			mut debug_extension: Vec<u8> [u32],
 		},
 		LineNumberTable this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"LineNumberTable")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"LineNumberTable")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//line_number_table_length: u16,
 			mut line_number_table: Vec<LineNumberTableEntry> [u16],
 		},
 		LocalVariableTable this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"LocalVariableTable")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"LocalVariableTable")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//local_variable_table_length: u16,
 			mut local_variable_table: Vec<LocalVariableTableEntry> [u16],
 		},
 		LocalVariableTypeTable this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"LocalVariableTypeTable")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"LocalVariableTypeTable")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//local_variable_type_table_length: u16,
 			mut local_variable_type_table: Vec<LocalVariableTypeTableEntry> [u16],
 		},
 		Deprecated {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"Deprecated")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"Deprecated")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 0,
 		},
 		RuntimeVisibleAnnotations this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"RuntimeVisibleAnnotations")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"RuntimeVisibleAnnotations")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//num_annotations: u16,
 			mut annotations: Vec<Annotation> [u16],
 		},
 		RuntimeInvisibleAnnotations this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"RuntimeInvisibleAnnotations")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"RuntimeInvisibleAnnotations")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//num_annotations: u16,
 			mut annotations: Vec<Annotation> [u16],
 		},
 		RuntimeVisibleParameterAnnotations this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"RuntimeVisibleParameterAnnotations")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"RuntimeVisibleParameterAnnotations")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//num_parameters: u8,
 			mut parameter_annotations: Vec<ParameterAnnotationEntry> [u8],
 		},
 		RuntimeInvisibleParameterAnnotations this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"RuntimeInvisibleParameterAnnotations")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"RuntimeInvisibleParameterAnnotations")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//num_parameters: u8,
@@ -466,27 +541,27 @@ notation!(
 		// TODO: RuntimeVisibleTypeAnnotations_attribute
 		// TODO: RuntimeInvisibleTypeAnnotations_attribute
 		AnnotationDefault this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"AnnotationDefault")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"AnnotationDefault")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			mut default_value: ElementValue,
 		},
 		BootstrapMethods this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"BootstrapMethods")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"BootstrapMethods")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//num_bootstrap_methods: u16,
 			mut bootstrap_methods: Vec<BootstrapMethodsEntry> [u16],
 		},
 		MethodParameters this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"MethodParameters")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"MethodParameters")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//parameters_count: u16,
 			mut parameters: Vec<MethodParametersEntry> [u16],
 		},
 		Module this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"Module")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"Module")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 
@@ -510,40 +585,40 @@ notation!(
 			mut provides: Vec<ModuleProvidesEntry> [u16],
 		},
 		ModulePackages {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"ModulePackages")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"ModulePackages")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 2 + 2 * package_index.len(),
 			//package_count: u16,
 			mut package_index: Vec<u16> [u16],
 		},
 		ModuleMainClass {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"ModuleMainClass")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"ModuleMainClass")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 2,
 			mut main_class_index: u16,
 		},
 		NestHost {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"NestHost")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"NestHost")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 2,
 			mut host_class_index: u16,
 		},
 		NestMembers {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"NestMembers")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"NestMembers")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 2 * classes.len(),
 			//number_of_classes: u16,
 			mut classes: Vec<u16> [u16],
 		},
 		Record this {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"Record")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"Record")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = this._len() - 6,
 			//components_count: u16,
 			mut components: Vec<RecordComponentInfo> [u16],
 		},
 		PermittedSubclasses {
-			= *attribute_name_index => attribute_name_index if pool_has_utf8(pool, attribute_name_index, b"PermittedSubclasses")?,
+			= *attribute_name_index => attribute_name_index if pool_has_utf8(context, attribute_name_index, b"PermittedSubclasses")?,
 			mut attribute_name_index: u16 nowrite = attribute_name_index,
 			const attribute_length: u32 = 2 + 2 * classes.len(),
 			//number_of_classes: u16,
@@ -96,14 +96,14 @@ macro_rules! notation {
 				Ok(())
 			}
 
-			fn _read(reader: &mut impl std::io::Read, pool: Option<&Vec<CpInfo>>) -> std::io::Result<$n> {
-				$( let $c_0 = notation!(read, reader, pool, $ct_0); notation!(check, $c_0, $cv_0); )*
+			fn _read(reader: &mut impl std::io::Read, context: ReadContext) -> std::io::Result<$n> {
+				$( let $c_0 = notation!(read, reader, context, $ct_0); notation!(check, $c_0, $cv_0); )*
 				$(
-					let $i = notation!(read, reader, pool, $it $( <$iit> $([$iat])? $({$l})? )?);
-					$( let pool = $ps; )?
-					$( let $c_1 = notation!(read, reader, pool, $ct_1); notation!(check, $c_1, $cv_1); )*
+					let $i = notation!(read, reader, context, $it $( <$iit> $([$iat])? $({$l})? )?);
+					$( let context = ReadContext { pool: $ps, ..context }; )?
+					$( let $c_1 = notation!(read, reader, context, $ct_1); notation!(check, $c_1, $cv_1); )*
 				)*
-				let _ = pool;
+				let _ = context;
 				Ok($n {
 					$( $i, )*
 				})
@@ -137,7 +137,13 @@ macro_rules! notation {
 					)*
 				},
 			)*
-			$( _ { $fm:pat => $f:expr, }, )?
+			$( _ {
+				$fm:pat => $f:expr,
+				$(
+					$( #[$ud:meta] )*
+					unknown $u:ident,
+				)?
+			}, )?
 		}
 	) => {
 		$( #[$nd] )?
@@ -152,6 +158,13 @@ macro_rules! notation {
 					)*
 				},
 			)*
+			$( $(
+				$( #[$ud] )*
+				$u {
+					$t: $tt,
+					raw: Vec<u8>,
+				},
+			)? )?
 		}
 
 		impl $n {
@@ -168,27 +181,38 @@ macro_rules! notation {
 							$( notation!(write, writer, $cv_1 as $ct_1, $ct_1); )*
 						)*
 					}, )*
+					$( $( $n::$u { $t, raw } => {
+						notation!(write, writer, $t, $tt);
+						std::io::Write::write_all(writer, raw)?;
+					}, )? )?
 				}
 				Ok(())
 			}
 
 			#[allow(clippy::redundant_locals)]
-			fn _read(reader: &mut impl std::io::Read, pool: Option<&Vec<CpInfo>>) -> std::io::Result<$n> {
-				$( let $p = pool; )?
-				let $t = notation!(read, reader, pool, $tt);
+			fn _read(reader: &mut impl std::io::Read, context: ReadContext) -> std::io::Result<$n> {
+				$( let $p = context; )?
+				let $t = notation!(read, reader, context, $tt);
 				match $t {
 					$( $tm $( if $tme )?=> {
-						$( let $c_0 = notation!(read, reader, pool, $ct_0); notation!(check, $c_0, $cv_0); )*
+						$( let $c_0 = notation!(read, reader, context, $ct_0); notation!(check, $c_0, $cv_0); )*
 						$(
-							let $i = notation!(read, reader, pool, $it $( <$iit> $([$iat])? $({$l})? )? $(;$nw = $nwe)?);
-							$( let $c_1 = notation!(read, reader, pool, $ct_1); notation!(check, $c_1, $cv_1); )*
+							let $i = notation!(read, reader, context, $it $( <$iit> $([$iat])? $({$l})? )? $(;$nw = $nwe)?);
+							$( let $c_1 = notation!(read, reader, context, $ct_1); notation!(check, $c_1, $cv_1); )*
 						)*
-						let _ = pool;
+						let _ = context;
 						Ok($n::$v {
 							$( $i, )*
 						})
 					}, )*
-					$( $fm => { $f }, )?
+					$( $fm => {
+						$( if let Some(len) = context.unknown_length($t) {
+							let mut raw = vec![0; len];
+							reader.read_exact(&mut raw)?;
+							return Ok($n::$u { $t, raw });
+						} )?
+						$f
+					}, )?
 				}
 			}
 
@@ -205,6 +229,9 @@ macro_rules! notation {
 							$( + notation!(len, $cv_1, $ct_1) )*
 						)*
 					}, )*
+					$( $( $n::$u { $t: _, raw } => {
+						notation!(len, (), $tt) + raw.len() as u32
+					}, )? )?
 				}
 			}
 		}
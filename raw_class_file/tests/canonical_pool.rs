@@ -0,0 +1,148 @@
+use pretty_assertions::assert_eq;
+use raw_class_file::{AttributeInfo, ClassFile, CpInfo, flags, insn, MethodInfo};
+
+/// A class like the one from the crate documentation, with the constant pool order `javac` produces.
+fn javac_order() -> ClassFile {
+	ClassFile {
+		minor_version: 0,
+		major_version: 52,
+		constant_pool: vec![
+			CpInfo::String { string_index: 10 }, // 1
+			CpInfo::Methodref { class_index: 4, name_and_type_index: 11 }, // 2
+			CpInfo::Class { name_index: 12 }, // 3
+			CpInfo::Class { name_index: 13 }, // 4
+			CpInfo::Utf8 { bytes: b"<init>".to_vec() }, // 5
+			CpInfo::Utf8 { bytes: b"()V".to_vec() }, // 6
+			CpInfo::Utf8 { bytes: b"Code".to_vec() }, // 7
+			CpInfo::Utf8 { bytes: b"SourceFile".to_vec() }, // 8
+			CpInfo::Utf8 { bytes: b"Main.java".to_vec() }, // 9
+			CpInfo::Utf8 { bytes: b"hello".to_vec() }, // 10
+			CpInfo::NameAndType { name_index: 5, descriptor_index: 6 }, // 11
+			CpInfo::Utf8 { bytes: b"org/example/Main".to_vec() }, // 12
+			CpInfo::Utf8 { bytes: b"java/lang/Object".to_vec() }, // 13
+		],
+		access_flags: flags::ACC_SUPER,
+		this_class: 3,
+		super_class: 4,
+		interfaces: vec![],
+		fields: vec![],
+		methods: vec![
+			MethodInfo {
+				access_flags: 0,
+				name_index: 5,
+				descriptor_index: 6,
+				attributes: vec![
+					AttributeInfo::Code {
+						attribute_name_index: 7,
+						max_stack: 2,
+						max_locals: 1,
+						code: vec![
+							insn::ldc, 1,
+							insn::pop,
+							insn::aload_0,
+							insn::invokespecial, 0, 2,
+							insn::r#return,
+						],
+						exception_table: vec![],
+						attributes: vec![],
+					},
+				],
+			}
+		],
+		attributes: vec![
+			AttributeInfo::SourceFile {
+				attribute_name_index: 8,
+				sourcefile_index: 9,
+			}
+		],
+	}
+}
+
+/// The same class, built independently with the constant pool in a different order.
+fn other_order() -> ClassFile {
+	ClassFile {
+		minor_version: 0,
+		major_version: 52,
+		constant_pool: vec![
+			CpInfo::Utf8 { bytes: b"org/example/Main".to_vec() }, // 1
+			CpInfo::Class { name_index: 1 }, // 2
+			CpInfo::Utf8 { bytes: b"java/lang/Object".to_vec() }, // 3
+			CpInfo::Class { name_index: 3 }, // 4
+			CpInfo::Utf8 { bytes: b"SourceFile".to_vec() }, // 5
+			CpInfo::Utf8 { bytes: b"Main.java".to_vec() }, // 6
+			CpInfo::Utf8 { bytes: b"()V".to_vec() }, // 7
+			CpInfo::Utf8 { bytes: b"<init>".to_vec() }, // 8
+			CpInfo::NameAndType { name_index: 8, descriptor_index: 7 }, // 9
+			CpInfo::Methodref { class_index: 4, name_and_type_index: 9 }, // 10
+			CpInfo::Utf8 { bytes: b"hello".to_vec() }, // 11
+			CpInfo::String { string_index: 11 }, // 12
+			CpInfo::Utf8 { bytes: b"Code".to_vec() }, // 13
+		],
+		access_flags: flags::ACC_SUPER,
+		this_class: 2,
+		super_class: 4,
+		interfaces: vec![],
+		fields: vec![],
+		methods: vec![
+			MethodInfo {
+				access_flags: 0,
+				name_index: 8,
+				descriptor_index: 7,
+				attributes: vec![
+					AttributeInfo::Code {
+						attribute_name_index: 13,
+						max_stack: 2,
+						max_locals: 1,
+						code: vec![
+							insn::ldc, 12,
+							insn::pop,
+							insn::aload_0,
+							insn::invokespecial, 0, 10,
+							insn::r#return,
+						],
+						exception_table: vec![],
+						attributes: vec![],
+					},
+				],
+			}
+		],
+		attributes: vec![
+			AttributeInfo::SourceFile {
+				attribute_name_index: 5,
+				sourcefile_index: 6,
+			}
+		],
+	}
+}
+
+#[test]
+fn canonicalize_constant_pool() -> std::io::Result<()> {
+	let mut a = javac_order();
+	let mut b = other_order();
+	assert_ne!(a.to_bytes(), b.to_bytes());
+
+	a.canonicalize_constant_pool()?;
+	b.canonicalize_constant_pool()?;
+
+	assert_eq!(a, b);
+	assert_eq!(a.to_bytes(), b.to_bytes());
+
+	// the order `javac` uses is already canonical
+	assert_eq!(a, javac_order());
+
+	// canonicalizing twice doesn't change anything
+	b.canonicalize_constant_pool()?;
+	assert_eq!(a, b);
+
+	Ok(())
+}
+
+#[test]
+fn canonicalize_constant_pool_unknown_attribute() {
+	let mut class = javac_order();
+	class.attributes.push(AttributeInfo::Other { attribute_name_index: 13, info: vec![0, 1] });
+
+	let before = class.clone();
+	assert!(class.canonicalize_constant_pool().is_err());
+	assert_eq!(class, before);
+}
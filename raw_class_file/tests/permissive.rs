@@ -0,0 +1,37 @@
+use pretty_assertions::assert_eq;
+use raw_class_file::{ClassFile, CpInfo, flags};
+
+#[test]
+fn round_trip_unknown_constant_pool_tag() -> std::io::Result<()> {
+	let class = ClassFile {
+		minor_version: 0,
+		major_version: 52,
+		constant_pool: vec![
+			CpInfo::Utf8 { bytes: b"Main".to_vec() }, // 1
+			CpInfo::Class { name_index: 1 }, // 2
+			CpInfo::Unknown { tag: 99, raw: vec![0xde, 0xad, 0xbe, 0xef] }, // 3
+			CpInfo::Utf8 { bytes: b"java/lang/Object".to_vec() }, // 4
+			CpInfo::Class { name_index: 4 }, // 5
+		],
+		access_flags: flags::ACC_PUBLIC | flags::ACC_SUPER,
+		this_class: 2,
+		super_class: 5,
+		interfaces: Vec::new(),
+		fields: Vec::new(),
+		methods: Vec::new(),
+		attributes: Vec::new(),
+	};
+
+	let bytes = class.to_bytes();
+	assert_eq!(bytes.len(), class.length());
+
+	// the tag `99` isn't known
+	assert!(ClassFile::read(&mut bytes.as_slice()).is_err());
+	assert!(ClassFile::read_permissive(&mut bytes.as_slice(), |_| None).is_err());
+
+	let read = ClassFile::read_permissive(&mut bytes.as_slice(), |tag| (tag == 99).then_some(4))?;
+	assert_eq!(read, class);
+	assert_eq!(read.to_bytes(), bytes);
+
+	Ok(())
+}
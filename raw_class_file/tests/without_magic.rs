@@ -0,0 +1,21 @@
+use pretty_assertions::assert_eq;
+use raw_class_file::ClassFile;
+
+#[test]
+fn write_and_read_without_magic() -> std::io::Result<()> {
+	let expected = include_bytes!("simple_expected.class");
+	let class = ClassFile::read(&mut std::io::Cursor::new(expected))?;
+
+	let mut bytes = Vec::new();
+	class.write_without_magic(&mut bytes)?;
+
+	assert_eq!(bytes.as_slice(), &expected[4..]);
+
+	let read = ClassFile::read_without_magic(&mut std::io::Cursor::new(&bytes))?;
+	assert_eq!(class, read);
+
+	// the normal read still requires the magic
+	assert!(ClassFile::read(&mut std::io::Cursor::new(&bytes)).is_err());
+
+	Ok(())
+}
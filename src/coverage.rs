@@ -0,0 +1,228 @@
+//! Computing how much of a jar is actually named by the mappings.
+//!
+//! This is for tracking the mapping progress across versions: a class, method or field counts as named if its `named` name
+//! differs from its `calamus` name, so that the `C_1234` like names still in the mappings don't count.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use anyhow::Result;
+use duke::tree::class::{ClassFile, ClassNameSlice};
+use duke::tree::method::MethodName;
+use dukebox::storage::{Jar, OpenedJar};
+use quill::tree::mappings::Mappings;
+
+/// How many of some items are named.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct Count {
+	pub(crate) named: usize,
+	pub(crate) total: usize,
+}
+
+impl Count {
+	fn add(&mut self, named: bool) {
+		self.total += 1;
+		if named {
+			self.named += 1;
+		}
+	}
+
+	/// The fraction of named items, `1.0` if there are no items.
+	pub(crate) fn fraction(&self) -> f64 {
+		if self.total == 0 {
+			1.0
+		} else {
+			self.named as f64 / self.total as f64
+		}
+	}
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct Coverage {
+	pub(crate) classes: Count,
+	pub(crate) methods: Count,
+	pub(crate) fields: Count,
+}
+
+impl Coverage {
+	fn add(&mut self, other: &Coverage) {
+		for (a, b) in [
+			(&mut self.classes, &other.classes),
+			(&mut self.methods, &other.methods),
+			(&mut self.fields, &other.fields),
+		] {
+			a.named += b.named;
+			a.total += b.total;
+		}
+	}
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct CoverageReport {
+	pub(crate) total: Coverage,
+	/// The coverage for each package, with `/` as the separator, and the empty string for the default package.
+	pub(crate) packages: BTreeMap<String, Coverage>,
+}
+
+fn package(class: &ClassNameSlice) -> String {
+	class.as_inner().rsplit_once('/')
+		.map_or_else(String::new, |(package, _)| package.to_string())
+}
+
+/// Computes the coverage of the classes in the jar by the mappings from `calamus` to `named`.
+///
+/// The jar must use the `calamus` names. Only members that could be named by the mappings are counted, so this skips:
+/// - constructors and static initializers,
+/// - synthetic methods and fields, like lambda bodies, `this$0` or `$VALUES`, and bridge methods,
+/// - methods not having a `m_1234` like name: these keep their name because they override a method of the JDK or another
+///   library (like `toString` or `compareTo`), or are otherwise fixed (like `values` of an enum).
+///
+/// A class is counted in the package of its `named` name, or of its `calamus` name if it's not named.
+pub(crate) fn coverage(mappings: &Mappings<2>, jar: &impl Jar) -> Result<CoverageReport> {
+	let calamus = mappings.get_namespace("calamus")?;
+	let named = mappings.get_namespace("named")?;
+
+	let classes = jar.open()?.read_classes_into(Vec::<ClassFile>::new())?;
+
+	let mut report = CoverageReport::default();
+	for class in classes {
+		let mut coverage = Coverage::default();
+
+		let mapping = mappings.classes.get(&class.name);
+
+		let named_name = mapping
+			.and_then(|mapping| mapping.info.names[named].as_ref())
+			.filter(|&name| Some(name) != mapping.and_then(|mapping| mapping.info.names[calamus].as_ref()));
+		coverage.classes.add(named_name.is_some());
+
+		for method in &class.methods {
+			if method.name == MethodName::INIT || method.name == MethodName::CLINIT ||
+				method.access.is_synthetic || method.access.is_bridge ||
+				!method.name.as_inner().starts_with("m_") {
+				continue;
+			}
+			let is_named = mapping
+				.and_then(|mapping| mapping.methods.get(&method.as_name_and_desc()))
+				.is_some_and(|method| method.info.names[named].is_some() && method.info.names[named] != method.info.names[calamus]);
+			coverage.methods.add(is_named);
+		}
+
+		for field in &class.fields {
+			if field.access.is_synthetic {
+				continue;
+			}
+			let is_named = mapping
+				.and_then(|mapping| mapping.fields.get(&field.as_name_and_desc()))
+				.is_some_and(|field| field.info.names[named].is_some() && field.info.names[named] != field.info.names[calamus]);
+			coverage.fields.add(is_named);
+		}
+
+		report.total.add(&coverage);
+		report.packages.entry(package(named_name.unwrap_or(&class.name)))
+			.or_default()
+			.add(&coverage);
+	}
+
+	Ok(report)
+}
+
+fn write_coverage(out: &mut impl Write, coverage: &Coverage) -> std::fmt::Result {
+	for (kind, count) in [("classes", coverage.classes), ("methods", coverage.methods), ("fields", coverage.fields)] {
+		write!(out, "  {kind}: {}/{} ({:.2}%)", count.named, count.total, count.fraction() * 100.0)?;
+	}
+	writeln!(out)
+}
+
+/// Formats the report for printing, with a line for each package if `per_package` is set.
+pub(crate) fn format_report(report: &CoverageReport, per_package: bool) -> Result<String> {
+	let mut out = String::from("total:");
+	write_coverage(&mut out, &report.total)?;
+
+	if per_package {
+		for (package, coverage) in &report.packages {
+			write!(out, "{}:", if package.is_empty() { "(default package)" } else { package })?;
+			write_coverage(&mut out, coverage)?;
+		}
+	}
+
+	Ok(out)
+}
+
+#[cfg(test)]
+mod testing {
+	use anyhow::Result;
+	use indexmap::IndexMap;
+	use pretty_assertions::assert_eq;
+	use duke::tree::class::{ClassAccess, ClassFile, ClassName};
+	use duke::tree::field::{Field, FieldAccess, FieldDescriptor, FieldName};
+	use duke::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName};
+	use duke::tree::version::Version;
+	use dukebox::storage::{ClassRepr, JarEntryEnum, ParsedJar, ParsedJarEntry};
+	use crate::coverage::Count;
+
+	fn class(name: &str, methods: &[&str], fields: &[&str]) -> Result<(String, ParsedJarEntry<ClassRepr, Vec<u8>>)> {
+		let class_name = ClassName::try_from_inner(name.into())?;
+		let mut class = ClassFile::new(Version::V1_8, ClassAccess::from(0x0021), class_name, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+
+		let desc = MethodDescriptor::try_from_inner("()V".into())?;
+		class.methods.push(Method::new(MethodAccess::from(0x0001), MethodName::INIT.to_owned(), desc.clone()));
+		for method in methods {
+			let name = MethodName::try_from_inner((*method).into())?;
+			class.methods.push(Method::new(MethodAccess::from(0x0001), name, desc.clone()));
+		}
+		for field in fields {
+			let name = FieldName::try_from_inner((*field).into())?;
+			let desc = FieldDescriptor::try_from_inner("I".into())?;
+			class.fields.push(Field::new(FieldAccess::from(0x0001), name, desc));
+		}
+
+		Ok(ParsedJarEntry::from_class(class))
+	}
+
+	#[test]
+	fn coverage() -> Result<()> {
+		let mappings = quill::tiny_v2::read::<2>("\
+tiny	2	0	calamus	named
+c	net/minecraft/unmapped/C_1	net/minecraft/Foo
+	m	()V	m_1	run
+	m	()V	m_2	m_2
+	f	I	f_1	count
+c	net/minecraft/unmapped/C_2	net/minecraft/unmapped/C_2
+	f	I	f_2	size
+".as_bytes())?;
+
+		let mut c_1 = class("net/minecraft/unmapped/C_1", &["m_1", "m_2", "m_3", "toString"], &["f_1"])?;
+		let JarEntryEnum::Class(ClassRepr::Parsed { class: c_1_class }) = &mut c_1.1.content else {
+			panic!("`class` should create a parsed class entry");
+		};
+		// none of these are counted
+		let desc = MethodDescriptor::try_from_inner("()V".into())?;
+		c_1_class.methods.push(Method::new(MethodAccess::from(0x1041), MethodName::try_from_inner("m_5".into())?, desc.clone()));
+		c_1_class.methods.push(Method::new(MethodAccess::from(0x100a), MethodName::try_from_inner("lambda$m_1$0".into())?, desc));
+		let desc = FieldDescriptor::try_from_inner("Lnet/minecraft/unmapped/C_2;".into())?;
+		c_1_class.fields.push(Field::new(FieldAccess::from(0x1010), FieldName::try_from_inner("this$0".into())?, desc));
+
+		let jar = ParsedJar {
+			entries: IndexMap::from([
+				c_1,
+				class("net/minecraft/unmapped/C_2", &[], &["f_2", "f_3"])?,
+				class("net/minecraft/unmapped/C_3", &["m_4"], &[])?,
+			]),
+		};
+
+		let report = super::coverage(&mappings, &jar)?;
+
+		assert_eq!(report.total.classes, Count { named: 1, total: 3 });
+		assert_eq!(report.total.methods, Count { named: 1, total: 4 });
+		assert_eq!(report.total.fields, Count { named: 2, total: 3 });
+		assert_eq!(report.total.methods.fraction(), 0.25);
+
+		let packages: Vec<&str> = report.packages.keys().map(|x| x.as_str()).collect();
+		assert_eq!(packages, ["net/minecraft", "net/minecraft/unmapped"]);
+		assert_eq!(report.packages["net/minecraft"].classes, Count { named: 1, total: 1 });
+		assert_eq!(report.packages["net/minecraft/unmapped"].fields, Count { named: 1, total: 2 });
+
+		assert_eq!(super::format_report(&report, false)?, "total:  classes: 1/3 (33.33%)  methods: 1/4 (25.00%)  fields: 2/3 (66.67%)\n");
+
+		Ok(())
+	}
+}
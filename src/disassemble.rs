@@ -0,0 +1,190 @@
+//! Printing the disassembly of a class, similar to `javap -c`.
+//!
+//! This is a debugging aid for mapping work, to quickly look at what a class does without decompiling it.
+
+use std::fmt::Write;
+use anyhow::{anyhow, Context, Result};
+use duke::tree::class::{ClassAccess, ClassFile, ClassName};
+use duke::tree::field::FieldAccess;
+use duke::tree::method::MethodAccess;
+use dukebox::storage::{Jar, OpenedJar};
+
+fn class_access(access: ClassAccess) -> Vec<&'static str> {
+	[
+		(access.is_public, "public"),
+		(access.is_final, "final"),
+		(access.is_super, "super"),
+		(access.is_abstract, "abstract"),
+		(access.is_synthetic, "synthetic"),
+		(access.is_annotation, "annotation"),
+		(access.is_enum, "enum"),
+		(access.is_module, "module"),
+		(access.is_interface, "interface"),
+	].into_iter().filter_map(|(set, name)| set.then_some(name)).collect()
+}
+
+fn field_access(access: FieldAccess) -> Vec<&'static str> {
+	[
+		(access.is_public, "public"),
+		(access.is_private, "private"),
+		(access.is_protected, "protected"),
+		(access.is_static, "static"),
+		(access.is_final, "final"),
+		(access.is_volatile, "volatile"),
+		(access.is_transient, "transient"),
+		(access.is_synthetic, "synthetic"),
+		(access.is_enum, "enum"),
+	].into_iter().filter_map(|(set, name)| set.then_some(name)).collect()
+}
+
+fn method_access(access: MethodAccess) -> Vec<&'static str> {
+	[
+		(access.is_public, "public"),
+		(access.is_private, "private"),
+		(access.is_protected, "protected"),
+		(access.is_static, "static"),
+		(access.is_final, "final"),
+		(access.is_synchronized, "synchronized"),
+		(access.is_bridge, "bridge"),
+		(access.is_varargs, "varargs"),
+		(access.is_native, "native"),
+		(access.is_abstract, "abstract"),
+		(access.is_strict, "strict"),
+		(access.is_synthetic, "synthetic"),
+	].into_iter().filter_map(|(set, name)| set.then_some(name)).collect()
+}
+
+/// Writes a `javap` like listing of the class.
+///
+/// This contains the access flags, the super class and interfaces, the fields, and each method with its instructions. Labels
+/// are written on their own line before the instruction they belong to.
+pub(crate) fn disassemble(class: &ClassFile) -> Result<String> {
+	let mut out = String::new();
+
+	for flag in class_access(class.access) {
+		write!(out, "{flag} ")?;
+	}
+	writeln!(out, "class {}", class.name)?;
+	if let Some(super_class) = &class.super_class {
+		writeln!(out, "  extends {super_class}")?;
+	}
+	for interface in &class.interfaces {
+		writeln!(out, "  implements {interface}")?;
+	}
+	writeln!(out, "{{")?;
+
+	for field in &class.fields {
+		write!(out, "  ")?;
+		for flag in field_access(field.access) {
+			write!(out, "{flag} ")?;
+		}
+		writeln!(out, "{}:{}", field.name, field.descriptor)?;
+	}
+
+	for (index, method) in class.methods.iter().enumerate() {
+		if index > 0 || !class.fields.is_empty() {
+			writeln!(out)?;
+		}
+
+		write!(out, "  ")?;
+		for flag in method_access(method.access) {
+			write!(out, "{flag} ")?;
+		}
+		writeln!(out, "{}{}", method.name, method.descriptor)?;
+
+		if let Some(code) = &method.code {
+			for entry in &code.instructions {
+				if let Some(label) = entry.label {
+					writeln!(out, "    {label}:")?;
+				}
+				writeln!(out, "      {}", entry.instruction)?;
+			}
+			if let Some(label) = code.last_label {
+				writeln!(out, "    {label}:")?;
+			}
+			for exception in &code.exception_table {
+				let catch = exception.catch.as_ref().map_or("any".to_owned(), ToString::to_string);
+				writeln!(out, "    catch {catch} from {} to {} using {}", exception.start, exception.end, exception.handler)?;
+			}
+		}
+	}
+
+	writeln!(out, "}}")?;
+
+	Ok(out)
+}
+
+/// Finds the class with the given name in the jar, and returns its disassembly, see [`disassemble`].
+pub(crate) fn disassemble_from_jar(jar: &impl Jar, class_name: &str) -> Result<String> {
+	let class_name = ClassName::try_from_inner(class_name.into())?;
+
+	let class = jar.open()?
+		.find_class(|header| header.name == class_name)?
+		.with_context(|| anyhow!("no class {class_name} in jar"))?;
+
+	disassemble(&class)
+}
+
+#[cfg(test)]
+mod testing {
+	use anyhow::Result;
+	use indexmap::IndexMap;
+	use pretty_assertions::assert_eq;
+	use duke::tree::class::{ClassAccess, ClassFile, ClassName};
+	use duke::tree::method::{Method, MethodAccess, MethodDescriptor, MethodName, MethodRef};
+	use duke::tree::method::code::{Code, Instruction, InstructionListEntry, LvIndex};
+	use duke::tree::version::Version;
+	use dukebox::storage::{BasicFileAttributes, ClassRepr, JarEntryEnum, ParsedJar, ParsedJarEntry};
+
+	#[test]
+	fn disassemble_constructor() -> Result<()> {
+		let name = ClassName::try_from_inner("org/example/Foo".into())?;
+		let mut class = ClassFile::new(Version::V1_8, ClassAccess::from(0x0021), name, Some(ClassName::JAVA_LANG_OBJECT.to_owned()), Vec::new());
+
+		let init = MethodDescriptor::try_from_inner("()V".into())?;
+		let mut method = Method::new(MethodAccess::from(0x0001), MethodName::INIT.to_owned(), init.clone());
+		method.code = Some(Code {
+			max_stack: Some(1),
+			max_locals: Some(1),
+			instructions: [
+				Instruction::ALoad(LvIndex { index: 0 }),
+				Instruction::InvokeSpecial(MethodRef {
+					class: ClassName::JAVA_LANG_OBJECT.to_owned(),
+					name: MethodName::INIT.to_owned(),
+					desc: init,
+				}, false),
+				Instruction::Return,
+			].into_iter()
+				.map(|instruction| InstructionListEntry { label: None, frame: None, offset: None, instruction })
+				.collect(),
+			..Code::default()
+		});
+		class.methods.push(method);
+
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				("org/example/Foo.class".to_owned(), ParsedJarEntry {
+					attr: BasicFileAttributes::default(),
+					content: JarEntryEnum::Class(ClassRepr::Parsed { class }),
+				}),
+			]),
+		};
+
+		let disassembly = super::disassemble_from_jar(&jar, "org/example/Foo")?;
+		assert_eq!(disassembly, "\
+public super class org/example/Foo
+  extends java/lang/Object
+{
+  public <init>()V
+      aload 0
+      invokespecial java/lang/Object.<init>()V
+      return
+}
+");
+		assert!(disassembly.lines().any(|line| line.trim() == "invokespecial java/lang/Object.<init>()V"));
+
+		assert!(super::disassemble_from_jar(&jar, "org/example/Bar").is_err());
+
+		Ok(())
+	}
+}
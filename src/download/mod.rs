@@ -3,17 +3,19 @@ use std::fs;
 use std::fs::File;
 use std::future::Future;
 use std::io::{Cursor, Write};
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
+use std::time::Duration;
 use anyhow::{anyhow, bail, Context, Result};
 use bytes::{Buf, Bytes};
 use log::{info, trace};
-use reqwest::{Client, StatusCode};
+use reqwest::{Client, Response, StatusCode};
 use zip::ZipArchive;
 use dukebox::storage::FileJar;
 use crate::download::version_details::VersionDetails;
 use crate::download::version_manifest::VersionManifest;
 use crate::download::versions_manifest::VersionsManifest;
 use crate::download::maven_metadata::MavenMetadata;
+use crate::download::retry::RetryPolicy;
 use quill::tree::mappings::Mappings;
 use dukenest::Nests;
 use maven_dependency_resolver::maven_pom::MavenPom;
@@ -23,6 +25,7 @@ pub(crate) mod versions_manifest;
 pub(crate) mod version_manifest;
 pub(crate) mod version_details;
 pub(crate) mod maven_metadata;
+pub(crate) mod retry;
 
 /// A struct for downloading and optionally caching things
 ///
@@ -31,7 +34,32 @@ pub(crate) mod maven_metadata;
 #[derive(Debug, Clone)]
 pub(crate) struct Downloader {
 	cache: bool,
+	/// The directory the cache is stored in, `./download` by default.
+	cache_root: PathBuf,
 	client: Option<Client>,
+	retry: RetryPolicy,
+	timeouts: Timeouts,
+}
+
+/// How long to wait for a server before giving up on a request.
+///
+/// A request that times out counts as a failure for the [`RetryPolicy`], and may be retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Timeouts {
+	/// The time to wait for the connection to be established.
+	pub(crate) connect: Duration,
+	/// The time to wait for the whole request, from sending it until the whole body is received.
+	pub(crate) request: Duration,
+}
+
+impl Default for Timeouts {
+	fn default() -> Self {
+		Timeouts {
+			connect: Duration::from_secs(30),
+			// the jars and libraries can take a while on slow connections
+			request: Duration::from_secs(120),
+		}
+	}
 }
 
 struct DownloadResult<'a> {
@@ -117,29 +145,81 @@ impl DownloadResult<'_> {
 }
 
 impl Downloader {
-	pub(crate) fn new(no_cache: bool, offline: bool) -> Downloader {
-		Downloader {
+	/// Creates a downloader with the [default timeouts][Timeouts::default].
+	pub(crate) fn new(no_cache: bool, offline: bool, retry: RetryPolicy) -> Result<Downloader> {
+		Downloader::new_with_timeout(no_cache, offline, retry, Timeouts::default())
+	}
+
+	pub(crate) fn new_with_timeout(no_cache: bool, offline: bool, retry: RetryPolicy, timeouts: Timeouts) -> Result<Downloader> {
+		let client = if offline {
+			None
+		} else {
+			let client = Client::builder()
+				.connect_timeout(timeouts.connect)
+				.timeout(timeouts.request)
+				.build()
+				.context("failed to create http client")?;
+			Some(client)
+		};
+
+		Ok(Downloader {
 			cache: !no_cache,
-			client: (!offline).then(Client::new),
+			cache_root: PathBuf::from("./download"),
+			client,
+			retry,
+			timeouts,
+		})
+	}
+
+	/// Turns errors from timeouts into an error naming the url and the timeout.
+	fn explain_timeout(&self, url: &str, error: anyhow::Error) -> anyhow::Error {
+		match error.downcast_ref::<reqwest::Error>() {
+			Some(e) if e.is_timeout() => {
+				let timeout = if e.is_connect() { self.timeouts.connect } else { self.timeouts.request };
+				error.context(format!("request to {url:?} timed out after {timeout:?}"))
+			},
+			_ => error,
 		}
 	}
 
+	/// Sends a `GET` request to the url, retrying according to the [`RetryPolicy`].
+	async fn get(&self, client: &Client, url: &str) -> Result<Response> {
+		self.retry.send(url, || client.get(url).send()).await
+			.map_err(|e| self.explain_timeout(url, e))
+	}
+
+	async fn bytes(&self, url: &str, response: Response) -> Result<Bytes> {
+		response.bytes().await
+			.map_err(|e| self.explain_timeout(url, e.into()))
+	}
+
 	async fn download<'a>(&self, url: &'a str) -> Result<DownloadResult<'a>> {
 		self.download_with_special_404(url, false).await.map(|x| x.unwrap())
 	}
 
 	// TODO: let this also cache a 404 result if (another, yet to add) parameter "cache_404" is true
 	async fn download_with_special_404<'a>(&self, url: &'a str, do_special_404: bool) -> Result<Option<DownloadResult<'a>>> {
-		if self.cache {
-			let downloads = Path::new("./download");
+		// local files (like from a `<relativePath>` of a maven parent) are read directly, and never cached
+		if let Some(path) = url.strip_prefix("file://") {
+			let path = PathBuf::from(path);
+			if !path.try_exists()? {
+				if do_special_404 {
+					return Ok(None);
+				}
+				bail!("local file {path:?} doesn't exist, for {url:?}");
+			}
+			trace!("reading local file {path:?} for {url:?}");
+			return Ok(Some(DownloadResult { url, data: DownloadData::FileHit { path } }));
+		}
 
+		if self.cache {
 			let Some(url_stripped) = url.strip_prefix("https://") else {
 				bail!("url doesn't start with `https://`: {url:?}");
 			};
 
 			//TODO: reevaluate possible security vulnerabilities here
 			// - one thing could be something like https://evil.example.org/../../../../../../../../usr/bin/bla.jar and replaces a jar on our system
-			let cache_path = downloads.join(url_stripped);
+			let cache_path = self.cache_root.join(url_stripped);
 			// TODO: in theory our 404 stuff could fail if you'd also download a <other url>__404 file...
 			let cache_path_404: PathBuf = {
 				let mut x: OsString = cache_path.clone().into();
@@ -157,7 +237,7 @@ impl Downloader {
 				let Some(client) = &self.client else {
 					bail!("cannot download, as we're running offline");
 				};
-				let response = client.get(url).send().await?;
+				let response = self.get(client, url).await?;
 				info!("got {}", response.status());
 
 				if do_special_404 && response.status() == StatusCode::NOT_FOUND {
@@ -173,7 +253,7 @@ impl Downloader {
 					bail!("got a \"{}\" for {url:?}", response.status());
 				}
 
-				let bytes = response.bytes().await?;
+				let bytes = self.bytes(url, response).await?;
 				let mut src: &[u8] = &bytes;
 
 				if let Some(parent) = cache_path.parent() {
@@ -193,7 +273,7 @@ impl Downloader {
 			let Some(client) = &self.client else {
 				bail!("cannot download, as we're running offline");
 			};
-			let response = client.get(url).send().await?;
+			let response = self.get(client, url).await?;
 			info!("got {}", response.status());
 
 			if do_special_404 && response.status() == StatusCode::NOT_FOUND {
@@ -203,7 +283,7 @@ impl Downloader {
 				bail!("got a \"{}\" for {url:?}", response.status());
 			}
 
-			let bytes = response.bytes().await?;
+			let bytes = self.bytes(url, response).await?;
 
 			Ok(Some(DownloadResult { url, data: DownloadData::NotCached { bytes } }))
 		}
@@ -330,3 +410,70 @@ impl maven_dependency_resolver::Downloader for Downloader {
 		}
 	}
 }
+
+#[cfg(test)]
+mod testing {
+	use std::time::{Duration, Instant};
+	use anyhow::{Context, Result};
+	use pretty_assertions::assert_eq;
+	use tokio::net::TcpListener;
+	use crate::download::{Downloader, Timeouts};
+	use crate::download::retry::RetryPolicy;
+
+	#[tokio::test]
+	async fn request_times_out() -> Result<()> {
+		// a server that accepts connections, but never answers
+		let listener = TcpListener::bind("127.0.0.1:0").await?;
+		let address = listener.local_addr()?;
+		let server = tokio::spawn(async move {
+			let mut connections = Vec::new();
+			while let Ok((stream, _)) = listener.accept().await {
+				connections.push(stream);
+			}
+		});
+
+		let retry = RetryPolicy { retries: 0, base_delay: Duration::ZERO };
+		let timeouts = Timeouts { connect: Duration::from_secs(5), request: Duration::from_millis(200) };
+		let downloader = Downloader::new_with_timeout(true, false, retry, timeouts)?;
+
+		let url = format!("http://{address}/slow.json");
+		let start = Instant::now();
+		let error = downloader.download(&url).await.err().context("expected the request to time out")?;
+		let elapsed = start.elapsed();
+
+		server.abort();
+
+		assert_eq!(error.to_string(), format!("request to {url:?} timed out after 200ms"));
+		assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+		assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn offline_uses_cache() -> Result<()> {
+		let retry = RetryPolicy { retries: 3, base_delay: Duration::from_secs(60) };
+		let dir = tempfile::tempdir()?;
+		let mut downloader = Downloader::new(false, true, retry)?;
+		downloader.cache_root = dir.path().to_owned();
+
+		let host = "offline-uses-cache.invalid";
+		let cache_dir = dir.path().join(host);
+		std::fs::create_dir_all(&cache_dir)?;
+		std::fs::write(cache_dir.join("cached.json"), "[]")?;
+
+		let cached = downloader.download(&format!("https://{host}/cached.json")).await.and_then(|result| result.to_vec());
+		let missing = downloader.download(&format!("https://{host}/missing.json")).await.map(|_| ());
+
+		// a cache hit doesn't need a request, and a cache miss fails right away instead of retrying
+		assert_eq!(cached?, b"[]");
+		assert_eq!(missing.err().context("expected the cache miss to fail")?.to_string(), "cannot download, as we're running offline");
+
+		let mut downloader = Downloader::new(true, true, retry)?;
+		downloader.cache_root = dir.path().to_owned();
+		let error = downloader.download(&format!("https://{host}/cached.json")).await.err().context("expected the download to fail")?;
+		assert_eq!(error.to_string(), "cannot download, as we're running offline");
+
+		Ok(())
+	}
+}
\ No newline at end of file
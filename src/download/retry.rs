@@ -0,0 +1,186 @@
+use std::future::Future;
+use std::time::Duration;
+use anyhow::Result;
+use log::warn;
+use reqwest::{Response, StatusCode};
+
+/// How often and how long to wait before retrying a failed request.
+///
+/// A request is retried if the server responded with a `5xx` or `429 Too Many Requests` status, or if it failed to connect
+/// or timed out. Other failures, like a `404 Not Found` or `403 Forbidden`, are returned immediately.
+///
+/// The delay doubles with each retry, starting from `base_delay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RetryPolicy {
+	/// The number of retries after the first attempt.
+	pub(crate) retries: u32,
+	/// The delay before the first retry.
+	pub(crate) base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		RetryPolicy {
+			retries: 3,
+			base_delay: Duration::from_millis(500),
+		}
+	}
+}
+
+/// Something that has a HTTP status, like a [`Response`].
+pub(crate) trait HasStatus {
+	fn status(&self) -> StatusCode;
+}
+
+impl HasStatus for Response {
+	fn status(&self) -> StatusCode {
+		self.status()
+	}
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+	status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn is_retryable_error(error: &reqwest::Error) -> bool {
+	error.is_connect() || error.is_timeout()
+}
+
+impl RetryPolicy {
+	fn delay(&self, retry: u32) -> Duration {
+		self.base_delay.saturating_mul(2u32.saturating_pow(retry))
+	}
+
+	/// Calls `send` until it gives a response with a non-retryable status, or until the retries are used up.
+	///
+	/// The last response is returned, so that the caller can decide what to do with its status.
+	pub(crate) async fn send<T, F, Fut>(&self, url: &str, mut send: F) -> Result<T>
+	where
+		T: HasStatus,
+		F: FnMut() -> Fut,
+		Fut: Future<Output = reqwest::Result<T>>,
+	{
+		let mut retry = 0;
+		loop {
+			match send().await {
+				Ok(response) if retry < self.retries && is_retryable_status(response.status()) => {
+					warn!("got a \"{}\" for {url:?}, retrying ({}/{})", response.status(), retry + 1, self.retries);
+				},
+				Err(error) if retry < self.retries && is_retryable_error(&error) => {
+					warn!("failed to request {url:?}: {error}, retrying ({}/{})", retry + 1, self.retries);
+				},
+				result => return Ok(result?),
+			}
+
+			tokio::time::sleep(self.delay(retry)).await;
+			retry += 1;
+		}
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use std::cell::Cell;
+	use std::time::Duration;
+	use anyhow::{Context, Result};
+	use reqwest::StatusCode;
+	use crate::download::retry::{HasStatus, RetryPolicy};
+
+	impl HasStatus for StatusCode {
+		fn status(&self) -> StatusCode {
+			*self
+		}
+	}
+
+	const POLICY: RetryPolicy = RetryPolicy {
+		retries: 3,
+		base_delay: Duration::from_millis(1),
+	};
+
+	#[tokio::test]
+	async fn retries_server_errors() -> Result<()> {
+		let attempts = Cell::new(0);
+		let status = POLICY.send("https://example.org", || {
+			attempts.set(attempts.get() + 1);
+			let status = if attempts.get() <= 2 { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+			async move { Ok(status) }
+		}).await?;
+
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(attempts.get(), 3);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn doesnt_retry_not_found() -> Result<()> {
+		let attempts = Cell::new(0);
+		let status = POLICY.send("https://example.org", || {
+			attempts.set(attempts.get() + 1);
+			async { Ok(StatusCode::NOT_FOUND) }
+		}).await?;
+
+		assert_eq!(status, StatusCode::NOT_FOUND);
+		assert_eq!(attempts.get(), 1);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn gives_up_after_retries() -> Result<()> {
+		let attempts = Cell::new(0);
+		let status = POLICY.send("https://example.org", || {
+			attempts.set(attempts.get() + 1);
+			async { Ok(StatusCode::TOO_MANY_REQUESTS) }
+		}).await?;
+
+		assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+		assert_eq!(attempts.get(), 4);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn retries_connection_errors() -> Result<()> {
+		// nothing listens on this port anymore, so connecting to it fails
+		let address = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+		let url = format!("http://{address}/");
+
+		let client = reqwest::Client::new();
+		let attempts = Cell::new(0);
+		let status = POLICY.send(&url, || {
+			attempts.set(attempts.get() + 1);
+			let request = (attempts.get() <= 2).then(|| client.get(&url).send());
+			async move {
+				match request {
+					Some(request) => request.await.map(|response| response.status()),
+					None => Ok(StatusCode::OK),
+				}
+			}
+		}).await?;
+
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(attempts.get(), 3);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn gives_up_on_connection_errors_after_retries() -> Result<()> {
+		let address = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+		let url = format!("http://{address}/");
+
+		let client = reqwest::Client::new();
+		let attempts = Cell::new(0);
+		let error = POLICY.send(&url, || {
+			attempts.set(attempts.get() + 1);
+			let request = client.get(&url).send();
+			async move { request.await.map(|response| response.status()) }
+		}).await.err().context("expected connecting to fail")?;
+
+		assert!(error.downcast_ref::<reqwest::Error>().is_some_and(reqwest::Error::is_connect), "{error:?}");
+		assert_eq!(attempts.get(), 4);
+
+		Ok(())
+	}
+}
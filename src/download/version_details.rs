@@ -1,3 +1,4 @@
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 use crate::download::versions_manifest::MinecraftVersion;
 
@@ -23,6 +24,30 @@ pub(crate) struct VersionDetails {
 	pub(crate) world: WorldInfo,
 }
 
+impl VersionDetails {
+	/// Selects the jars that exist for this version.
+	///
+	/// Only if both the client and the server jar exist, they can be merged.
+	pub(crate) fn jars(&self) -> Result<Jars<'_>> {
+		match (self.client, self.server) {
+			(true, true) => Ok(Jars::Both { client: &self.downloads.client, server: &self.downloads.server }),
+			(true, false) => Ok(Jars::Client(&self.downloads.client)),
+			(false, true) => Ok(Jars::Server(&self.downloads.server)),
+			(false, false) => bail!("minecraft version {:?} has neither a client nor a server jar", self.id),
+		}
+	}
+}
+
+/// The jars of a version, see [`VersionDetails::jars`].
+#[derive(Debug, PartialEq)]
+pub(crate) enum Jars<'a> {
+	Both {
+		client: &'a DownloadInfo,
+		server: &'a DownloadInfo,
+	},
+	Client(&'a DownloadInfo),
+	Server(&'a DownloadInfo),
+}
 
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct DownloadsInfo {
@@ -30,7 +55,7 @@ pub(crate) struct DownloadsInfo {
 	pub(crate) server: DownloadInfo,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub(crate) struct DownloadInfo {
 	pub(crate) sha1: String,
 	pub(crate) size: usize,
@@ -100,4 +125,49 @@ pub(crate) enum WorldFormat {
 	Region,
 	#[serde(rename = "anvil")]
 	Anvil,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod testing {
+	use anyhow::Result;
+	use crate::download::version_details::{Jars, VersionDetails};
+
+	fn version_details(client: bool, server: bool) -> Result<VersionDetails> {
+		let json = format!(r#"{{
+			"id": "a0.2.8-server",
+			"client": {client},
+			"server": {server},
+			"sharedMappings": false,
+			"downloads": {{
+				"client": {{ "sha1": "c", "size": 1, "url": "https://example.org/client.jar" }},
+				"server": {{ "sha1": "s", "size": 2, "url": "https://example.org/server.jar" }}
+			}},
+			"libraries": [],
+			"manifests": [],
+			"normalizedVersion": "0.2.8-server",
+			"previous": [],
+			"next": [],
+			"releaseTarget": "a0.2.8",
+			"releaseTime": "2010-01-01T00:00:00+00:00",
+			"protocol": {{ "type": "classic", "version": 6 }},
+			"world": {{ "format": "alpha", "version": null }}
+		}}"#);
+		Ok(serde_json::from_str(&json)?)
+	}
+
+	#[test]
+	fn jars() -> Result<()> {
+		let server_only = version_details(false, true)?;
+		assert_eq!(server_only.jars()?, Jars::Server(&server_only.downloads.server));
+
+		let client_only = version_details(true, false)?;
+		assert_eq!(client_only.jars()?, Jars::Client(&client_only.downloads.client));
+
+		let both = version_details(true, true)?;
+		assert_eq!(both.jars()?, Jars::Both { client: &both.downloads.client, server: &both.downloads.server });
+
+		assert!(version_details(false, false)?.jars().is_err());
+
+		Ok(())
+	}
+}
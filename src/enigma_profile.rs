@@ -0,0 +1,181 @@
+//! Reading the `profile.json` passed to Enigma.
+//!
+//! Enigma only reads this file once it has started, and reports mistakes in it with a Java exception. To give a better
+//! error, we read and check it before launching Enigma.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use serde::Deserialize;
+
+/// The service types Enigma knows about.
+const KNOWN_SERVICE_TYPES: [&str; 4] = ["jar_indexer", "name_proposal", "obfuscation_test", "decompiler"];
+
+/// The profile, as far as it's understood.
+///
+/// Unknown keys are allowed, as newer versions of Enigma or plugins may add some. The ones at the top level are warned
+/// about when [validating][EnigmaProfile::validate], to catch typos.
+#[derive(Debug, Deserialize)]
+pub(crate) struct EnigmaProfile {
+	/// The services to use, by service type.
+	#[serde(default)]
+	pub(crate) services: BTreeMap<String, Services>,
+	// only read to check that it's well-formed
+	#[allow(unused)]
+	pub(crate) mapping_save_parameters: Option<MappingSaveParameters>,
+	/// All other top-level keys.
+	#[serde(flatten)]
+	pub(crate) unknown: BTreeMap<String, serde_json::Value>,
+}
+
+/// Enigma allows giving either a single service, or a list of them.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Services {
+	Single(Service),
+	Multiple(Vec<Service>),
+}
+
+impl Services {
+	fn as_slice(&self) -> &[Service] {
+		match self {
+			Services::Single(service) => std::slice::from_ref(service),
+			Services::Multiple(services) => services,
+		}
+	}
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Service {
+	/// The id of the service, like `enigma:enum_name_proposer`.
+	pub(crate) id: String,
+	/// Arguments for the service.
+	// only read to check that it's well-formed
+	#[allow(unused)]
+	pub(crate) args: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MappingSaveParameters {
+	#[allow(unused)]
+	pub(crate) file_name_format: FileNameFormat,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) enum FileNameFormat {
+	#[serde(rename = "by_obf")]
+	ByObf,
+	#[serde(rename = "by_deobf")]
+	ByDeobf,
+}
+
+impl EnigmaProfile {
+	pub(crate) fn parse(s: &str) -> Result<EnigmaProfile> {
+		serde_json::from_str(s).context("failed to parse enigma profile")
+	}
+
+	/// Reads the profile from the given path, and [validates][EnigmaProfile::validate] it.
+	pub(crate) fn read(path: &Path) -> Result<EnigmaProfile> {
+		let s = std::fs::read_to_string(path)
+			.with_context(|| anyhow!("failed to read enigma profile {path:?}"))?;
+
+		let profile = EnigmaProfile::parse(&s)
+			.with_context(|| anyhow!("in enigma profile {path:?}"))?;
+		profile.validate()
+			.with_context(|| anyhow!("invalid enigma profile {path:?}"))?;
+
+		Ok(profile)
+	}
+
+	/// Checks that the ids of the services look like something Enigma can load.
+	///
+	/// An id must have the form `namespace:name`, where both parts are non-empty and only consist of ascii letters, digits,
+	/// `_`, `-`, `.` and `/`. Service types Enigma doesn't know about are only warned about, as plugins may add them. The
+	/// same goes for unknown top-level keys.
+	pub(crate) fn validate(&self) -> Result<()> {
+		fn is_valid_part(s: &str) -> bool {
+			!s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'))
+		}
+
+		for key in self.unknown.keys() {
+			warn!("unknown key {key:?} in enigma profile, known are \"services\" and \"mapping_save_parameters\"");
+		}
+
+		for (service_type, services) in &self.services {
+			if !KNOWN_SERVICE_TYPES.contains(&service_type.as_str()) {
+				warn!("unknown enigma service type {service_type:?}, known are {KNOWN_SERVICE_TYPES:?}");
+			}
+
+			for service in services.as_slice() {
+				let valid = service.id.split_once(':')
+					.is_some_and(|(namespace, name)| is_valid_part(namespace) && is_valid_part(name));
+				if !valid {
+					bail!("invalid id {:?} of {service_type:?} service: expected the form `namespace:name`", service.id);
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use anyhow::{Context, Result};
+	use pretty_assertions::assert_eq;
+	use crate::enigma_profile::{EnigmaProfile, FileNameFormat};
+
+	#[test]
+	fn valid() -> Result<()> {
+		let profile = EnigmaProfile::parse(r#"{
+			"services": {
+				"jar_indexer": [
+					{ "id": "enigma:enum_initializer_indexer" },
+					{ "id": "enigma:specialized_bridge_method_indexer" }
+				],
+				"name_proposal": {
+					"id": "enigma:enum_name_proposer",
+					"args": { "foo": "bar" }
+				}
+			},
+			"mapping_save_parameters": {
+				"file_name_format": "by_deobf"
+			}
+		}"#)?;
+		profile.validate()?;
+
+		assert_eq!(profile.services.len(), 2);
+		assert_eq!(profile.services["jar_indexer"].as_slice().len(), 2);
+		assert_eq!(profile.services["name_proposal"].as_slice()[0].id, "enigma:enum_name_proposer");
+		let parameters = profile.mapping_save_parameters.context("no mapping save parameters")?;
+		assert!(matches!(parameters.file_name_format, FileNameFormat::ByDeobf));
+
+		EnigmaProfile::parse("{}")?.validate()?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn invalid() -> Result<()> {
+		// not json
+		assert!(EnigmaProfile::parse("{ \"services\": ").is_err());
+		// a typo in a top-level key is only warned about
+		let profile = EnigmaProfile::parse(r#"{ "service": {} }"#)?;
+		assert!(profile.services.is_empty());
+		assert_eq!(profile.unknown.keys().collect::<Vec<_>>(), ["service"]);
+		profile.validate()?;
+		// unknown file name format
+		assert!(EnigmaProfile::parse(r#"{ "mapping_save_parameters": { "file_name_format": "by_name" } }"#).is_err());
+
+		let profile = EnigmaProfile::parse(r#"{
+			"services": {
+				"jar_indexer": [ { "id": "enum_initializer_indexer" } ]
+			}
+		}"#)?;
+		let error = profile.validate().err().context("validation succeeded")?;
+		assert_eq!(error.to_string(), "invalid id \"enum_initializer_indexer\" of \"jar_indexer\" service: expected the form `namespace:name`");
+
+		Ok(())
+	}
+}
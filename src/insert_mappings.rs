@@ -14,7 +14,7 @@ use quill::tree::mappings::{ClassMapping, ClassNowodeMapping, Mappings, MethodMa
 use quill::tree::mappings_diff::{Action, ClassNowodeDiff, FieldNowodeDiff, MappingsDiff, MethodNowodeDiff};
 use quill::tree::{FromKey, GetNames, NodeInfo, NodeJavadocInfo};
 use quill::tree::names::Namespace;
-use crate::version_graph::{VersionEntry, VersionGraph};
+use crate::version_graph::{PendingChanges, VersionEntry, VersionGraph};
 
 
 #[derive(Clone, Copy)]
@@ -45,12 +45,16 @@ impl DiffSide {
 }
 
 
+/// Propagates the `changes` made in `version` through the version graph.
+///
+/// Returns the versions whose mappings or diffs are changed, sorted by their name, together with the changes to their files.
+/// If [`PropagationOptions::dry_run`] is set, nothing is written.
 pub(crate) fn insert_mappings<'version>(
 	options: PropagationOptions,
 	version_graph: &'version VersionGraph,
 	changes: MappingsDiff,
 	version: VersionEntry<'version>,
-) -> Result<()> {
+) -> Result<(Vec<VersionEntry<'version>>, PendingChanges)> {
 
 	let direction_is_up = matches!(&options.direction, PropagationDirection::Up | PropagationDirection::Both);
 	let direction_is_down = matches!(&options.direction, PropagationDirection::Down | PropagationDirection::Both);
@@ -70,6 +74,7 @@ pub(crate) fn insert_mappings<'version>(
 	queued_changes.insert(version, changes);
 
 	let mut dirty = HashSet::new();
+	let mut pending = PendingChanges::default();
 
 	while !queued_changes.is_empty() {
 		let working_changes = queued_changes;
@@ -80,6 +85,7 @@ pub(crate) fn insert_mappings<'version>(
 				propagate_change(
 					options.lenient,
 					&mut dirty,
+					&mut pending,
 					&barriers,
 					version_graph,
 					version,
@@ -219,13 +225,14 @@ pub(crate) fn insert_mappings<'version>(
 							}
 						}
 					},
-				);
+				)?;
 
 
 				for (field_key, change_field) in &change_class.fields {
 					propagate_change(
 						options.lenient,
 						&mut dirty,
+						&mut pending,
 						&barriers,
 						version_graph,
 						version,
@@ -302,7 +309,7 @@ pub(crate) fn insert_mappings<'version>(
 								}
 							}
 						},
-					);
+					)?;
 
 					// has no children
 				}
@@ -310,6 +317,7 @@ pub(crate) fn insert_mappings<'version>(
 					propagate_change(
 						options.lenient,
 						&mut dirty,
+						&mut pending,
 						&barriers,
 						version_graph,
 						version,
@@ -385,12 +393,13 @@ pub(crate) fn insert_mappings<'version>(
 								}
 							}
 						}
-					);
+					)?;
 
 					for (parameter_key, change_parameter) in &change_method.parameters {
 						propagate_change(
 							options.lenient,
 							&mut dirty,
+							&mut pending,
 							&barriers,
 							version_graph,
 							version,
@@ -413,7 +422,7 @@ pub(crate) fn insert_mappings<'version>(
 							|_diff, _side, _dir, _queue_sibling_change_version, _mode| {
 								// parameters don't queue siblings
 							},
-						);
+						)?;
 
 						// has no children
 					}
@@ -422,15 +431,21 @@ pub(crate) fn insert_mappings<'version>(
 		}
 	}
 
-	version_graph.write();
+	if !options.dry_run {
+		version_graph.write();
+	}
 
-	Ok(())
+	let mut changed_versions: Vec<_> = dirty.into_iter().collect();
+	changed_versions.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+	Ok((changed_versions, pending))
 }
 
 #[allow(clippy::too_many_arguments)]
 fn propagate_change<'version>(
 	options_lenient: bool,
 	dirty: &mut HashSet<VersionEntry<'version>>,
+	pending: &mut PendingChanges,
 	barriers: &IndexSet<VersionEntry<'version>>,
 	version_graph: &'version VersionGraph,
 	version: VersionEntry<'version>,
@@ -439,21 +454,22 @@ fn propagate_change<'version>(
 	apply_to_mappings: impl Fn(&mut Mappings<2>, Mode) -> bool,
 	apply_to_diffs: impl Fn(&mut MappingsDiff, bool, DiffSide, Mode) -> bool,
 	mut queue_sibling_changes: impl FnMut(&MappingsDiff, DiffSide, PropDir, VersionEntry<'version>, Mode),
-) {
-	let mut propagate = |mode: Mode| {
+) -> Result<()> {
+	let mut propagate = |mode: Mode| -> Result<()> {
 		let mut propagation = PropagationQueue::default();
 		propagation.offer_up(version);
 
 		while let Some((dir, n)) = propagation.poll() {
 			match dir {
 				PropDir::Up => {
-					if let Some(mappings) = version_graph.is_root_then_get_mappings(n) {
+					if let Some(mappings) = version_graph.is_root_then_get_mappings(pending, n) {
 						let mut mappings = mappings.clone();
-						// TODO: store mappings across multiple times
 
 						let success = apply_to_mappings(&mut mappings, mode);
 
 						if success {
+							pending.set_root_mapping(mappings);
+
 							// success, now propagate in opposite direction
 							propagation.offer_down(n);
 
@@ -466,14 +482,13 @@ fn propagate_change<'version>(
 						let queue_sibling_change_version = n;
 
 						for p in version_graph.parents(n) {
-							let mut diff = version_graph.get_diff(p, n)
-								.unwrap() // this unwrap needs to be replaced with ?
+							let mut diff = version_graph.get_diff(pending, p, n)?
 								.unwrap(); // this seems to be fine, bc we checked stuff?!
-							// TODO: store diffs across multiple times
 
 							let success = apply_to_diffs(&mut diff, insert, side, mode);
 
 							if success {
+								pending.set_diff(version_graph, p, n, diff.clone())?;
 								if options_lenient && !insert {
 									queue_sibling_changes(&diff, side, dir, queue_sibling_change_version, mode);
 								}
@@ -491,21 +506,19 @@ fn propagate_change<'version>(
 				},
 				PropDir::Down => {
 					for c in version_graph.children(n) {
-						if let Some(mappings) = version_graph.is_root_then_get_mappings(c) {
+						if let Some(mappings) = version_graph.is_root_then_get_mappings(pending, c) {
 							let mut mappings = mappings.clone();
 
 							let success = apply_to_mappings(&mut mappings, mode);
 
-							// TODO: store mappings
-
 							if success {
+								pending.set_root_mapping(mappings);
 								dirty.insert(c);
 							}
 						} else {
 							// loop could be removed since p is always equal to s, bc of parent/child symmetry
 
-							let mut diff = version_graph.get_diff(n, c)
-								.unwrap() // this unwrap needs to be replaced with ?
+							let mut diff = version_graph.get_diff(pending, n, c)?
 								.unwrap(); // this seems to be fine, bc we checked stuff?!
 
 							let insert = barriers.contains(&c);
@@ -515,9 +528,8 @@ fn propagate_change<'version>(
 
 							let success = apply_to_diffs(&mut diff, insert, side, mode);
 
-							// TODO: store diff
-
 							if success {
+								pending.set_diff(version_graph, n, c, diff.clone())?;
 								if options_lenient && !insert {
 									queue_sibling_changes(&diff, side, dir, queue_sibling_change_version, mode);
 								}
@@ -536,14 +548,18 @@ fn propagate_change<'version>(
 				},
 			}
 		}
+
+		Ok(())
 	};
 
 	if op_is_not_none_mappings {
-		propagate(Mode::Mappings);
+		propagate(Mode::Mappings)?;
 	}
 	if op_is_not_none_javadocs {
-		propagate(Mode::Javadocs)
+		propagate(Mode::Javadocs)?;
 	}
+
+	Ok(())
 }
 
 fn mappings_get_class_or_insert_dummy<'a>(
@@ -1125,4 +1141,69 @@ fn manually_select_item<T, F: Debug>(
 
 		println!("number out of range! - please try again");
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod testing {
+	use std::path::Path;
+	use anyhow::Result;
+	use pretty_assertions::assert_eq;
+	use quill::tree::mappings::Mappings;
+	use quill::tree::mappings_diff::MappingsDiff;
+	use crate::{PropagationDirection, PropagationOptions};
+	use crate::insert_mappings::insert_mappings;
+	use crate::version_graph::VersionGraph;
+
+	const ROOT: &str = "tiny\t2\t0\tcalamus\tnamed\nc\tC_1\tFoo\n";
+
+	fn read_dir_contents(dir: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+		let mut contents = Vec::new();
+		for entry in std::fs::read_dir(dir)? {
+			let path = entry?.path();
+			contents.push((path.display().to_string(), std::fs::read(&path)?));
+		}
+		contents.sort();
+		Ok(contents)
+	}
+
+	/// Propagates renaming `C_1` from `Foo` to `Bar` in version `1.1` as a dry run, in a version graph with the root `1.0`.
+	fn propagate_dry_run(dir: &Path) -> Result<String> {
+		let version_graph = VersionGraph::resolve(dir)?;
+		let version = version_graph.get("1.1")?;
+
+		let separated: Mappings<2> = quill::tiny_v2::read(ROOT.as_bytes())?;
+		let working: Mappings<2> = quill::tiny_v2::read("tiny\t2\t0\tcalamus\tnamed\nc\tC_1\tBar\n".as_bytes())?;
+		let changes = MappingsDiff::diff(&separated, &working)?;
+
+		let options = PropagationOptions {
+			direction: PropagationDirection::Both,
+			lenient: true,
+			dry_run: true,
+		};
+		let (changed_versions, pending) = insert_mappings(options, &version_graph, changes, version)?;
+
+		let changed_versions: Vec<_> = changed_versions.iter().map(|version| version.as_str()).collect();
+		assert_eq!(changed_versions, ["1.0"]);
+
+		let mut preview = Vec::new();
+		version_graph.write_pending_diff(&pending, &mut preview)?;
+		Ok(String::from_utf8(preview)?)
+	}
+
+	#[test]
+	fn dry_run_does_not_modify_files() -> Result<()> {
+		let dir = tempfile::tempdir()?;
+		std::fs::write(dir.path().join("1.0.tiny"), ROOT)?;
+		std::fs::write(dir.path().join("1.0#1.1.tinydiff"), "tiny\t2\t0\n")?;
+		let before = read_dir_contents(dir.path())?;
+
+		let preview = propagate_dry_run(dir.path())?;
+
+		assert_eq!(read_dir_contents(dir.path())?, before);
+
+		let root = dir.path().join("1.0.tiny");
+		assert_eq!(preview, format!("--- {root}\n+++ {root}\n-c\tC_1\tFoo\n+c\tC_1\tBar\n", root = root.display()));
+
+		Ok(())
+	}
+}
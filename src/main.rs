@@ -2,7 +2,7 @@ use std::ffi::OsStr;
 use anyhow::{anyhow, bail, Context, Result};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use clap::{ArgAction, Parser, Subcommand, ValueEnum};
 use log::{info, trace};
 use tokio::task::JoinSet;
@@ -10,11 +10,15 @@ use dukebox::storage::{ClassRepr, Jar, ParsedJar};
 use dukenest::{NesterOptions, Nests};
 use maven_dependency_resolver::coord::MavenCoord;
 use maven_dependency_resolver::{DependencyScope, FoundDependency};
+use maven_dependency_resolver::lockfile::Lockfile;
 use maven_dependency_resolver::resolver::Resolver;
 use quill::tree::mappings::Mappings;
 use quill::tree::mappings_diff::MappingsDiff;
-use crate::download::Downloader;
+use crate::download::{Downloader, Timeouts};
+use crate::download::version_details::Jars;
+use crate::download::retry::RetryPolicy;
 use crate::dukelaunch::JavaRunConfig;
+use crate::enigma_profile::EnigmaProfile;
 use crate::version_graph::{VersionEntry, VersionGraph};
 
 mod version_graph;
@@ -22,10 +26,13 @@ mod download;
 mod specialized_methods;
 
 mod build;
+mod coverage;
 // TODO: replace four spaces with tab, and click Replace all
 mod sus;
 
+mod disassemble;
 mod dukelaunch;
+mod enigma_profile;
 mod insert_mappings;
 
 /*
@@ -102,7 +109,21 @@ async fn main() -> Result<()> {
         x
     };
 
-    let downloader = Downloader::new(cli.no_cache, cli.offline);
+    let retry = RetryPolicy {
+        retries: cli.retries,
+        base_delay: Duration::from_millis(cli.retry_delay),
+    };
+    let downloader = match (cli.connect_timeout, cli.timeout) {
+        (None, None) => Downloader::new(cli.no_cache, cli.offline, retry)?,
+        (connect, request) => {
+            let default = Timeouts::default();
+            let timeouts = Timeouts {
+                connect: connect.map_or(default.connect, Duration::from_secs),
+                request: request.map_or(default.request, Duration::from_secs),
+            };
+            Downloader::new_with_timeout(cli.no_cache, cli.offline, retry, timeouts)?
+        },
+    };
 
     let project_enigma_version = "1.9.0";
     let project_quilt_enigma_plugin_version = "1.3.0";
@@ -160,7 +181,7 @@ async fn main() -> Result<()> {
 
             Ok(())
         },
-        Command::Feather { working_mappings_base_dir, enigma_prepared_jar, enigma_profile, version } => {
+        Command::Feather { working_mappings_base_dir, enigma_prepared_jar, enigma_profile, read_lockfile, write_lockfile, version } => {
             let java_launcher = dukelaunch::JavaLauncher::from_env_var()
                 //.unwrap_or_default();
                 .unwrap_or_else(|| dukelaunch::JavaLauncher::new("/usr/lib/jvm/java-17-openjdk/bin/java"));
@@ -172,9 +193,20 @@ async fn main() -> Result<()> {
                 downloader: &Downloader,
                 resolvers: &[Resolver<'_>],
                 dependencies: &[(MavenCoord, DependencyScope)],
-                cache: Option<&[&str]>
+                cache: Option<&[&str]>,
+                read_lockfile: Option<&Path>,
+                write_lockfile: Option<&Path>,
             ) -> Result<Vec<PathBuf>> {
-                let dependencies: Vec<FoundDependency> = if let Some(cached) = cache {
+                let lockfile_content;
+                let found_dependencies: Vec<FoundDependency> = if let Some(path) = read_lockfile {
+                    lockfile_content = std::fs::read_to_string(path)
+                        .with_context(|| anyhow!("failed to read lockfile {path:?}"))?;
+                    let lockfile = Lockfile::read(&lockfile_content)
+                        .with_context(|| anyhow!("failed to parse lockfile {path:?}"))?;
+                    lockfile.check_requested(dependencies)
+                        .with_context(|| anyhow!("cannot use lockfile {path:?}, consider writing it again with `--write-lockfile`"))?;
+                    lockfile.dependencies
+                } else if let Some(cached) = cache {
                     cached.iter().map(|&x| FoundDependency::try_from(x)).collect::<Result<_>>()?
                 } else {
                     let r = maven_dependency_resolver::get_maven_dependencies(downloader, resolvers, dependencies).await?;
@@ -194,8 +226,20 @@ async fn main() -> Result<()> {
                     r
                 };
 
-                let mut paths = Vec::with_capacity(dependencies.len());
-                for i in dependencies {
+                let found_dependencies = if let Some(path) = write_lockfile {
+                    let lockfile = Lockfile {
+                        requested: dependencies.to_vec(),
+                        dependencies: found_dependencies,
+                    };
+                    std::fs::write(path, lockfile.to_string())
+                        .with_context(|| anyhow!("failed to write lockfile {path:?}"))?;
+                    lockfile.dependencies
+                } else {
+                    found_dependencies
+                };
+
+                let mut paths = Vec::with_capacity(found_dependencies.len());
+                for i in found_dependencies {
                     let url = i.make_url();
                     let jar = downloader.get_jar(&url).await?;
                     let path = jar.path;
@@ -266,7 +310,7 @@ async fn main() -> Result<()> {
                 (enigma_plugin, DependencyScope::Runtime),
             ];
 
-            let classpath = make_classpath(&downloader, &resolvers, &dependencies, dependency_tree_cached).await?;
+            let classpath = make_classpath(&downloader, &resolvers, &dependencies, dependency_tree_cached, read_lockfile.as_deref(), write_lockfile.as_deref()).await?;
 
             let version_graph = VersionGraph::resolve(mappings_dir)?;
             let version = version_graph.get(&version)?;
@@ -288,6 +332,9 @@ async fn main() -> Result<()> {
             let profile_json_path = enigma_profile.as_deref()
                 .unwrap_or(default_enigma_profile_json);
 
+            // check the profile before launching enigma, as enigma's errors for it are hard to understand
+            EnigmaProfile::read(profile_json_path)?;
+
             let mappings = version_graph.apply_diffs(version)? // calamus -> named
                 .extend_inner_class_names("named")?;
 
@@ -316,7 +363,7 @@ async fn main() -> Result<()> {
 
             java_launcher.launch(&arg)
         },
-        Command::PropagateMappings { working_mappings_base_dir, keep_directory, direction, version } => {
+        Command::PropagateMappings { working_mappings_base_dir, keep_directory, direction, dry_run, version } => {
             let version_graph = VersionGraph::resolve(mappings_dir)?;
 
             let version = version_graph.get(&version)?;
@@ -352,9 +399,23 @@ async fn main() -> Result<()> {
             let options = PropagationOptions {
                 direction,
                 lenient: true,
+                dry_run,
             };
 
-            insert_mappings::insert_mappings(options, &version_graph, changes, version)?;
+            let changed_classes = changes.classes.len();
+
+            let (changed_versions, pending) = insert_mappings::insert_mappings(options, &version_graph, changes, version)?;
+
+            if dry_run {
+                println!("dry run: changes to {changed_classes} classes in {version:?} would change the mappings of {} versions:",
+                    changed_versions.len());
+                for changed_version in changed_versions {
+                    println!("  {}", changed_version.as_str());
+                }
+                println!();
+                version_graph.write_pending_diff(&pending, &mut std::io::stdout().lock())?;
+                return Ok(());
+            }
 
             if !keep_directory {
                 std::fs::remove_dir_all(&working_mappings_dir)
@@ -363,6 +424,21 @@ async fn main() -> Result<()> {
 
             Ok(())
         },
+        Command::Coverage { per_package, version } => {
+            let version_graph = VersionGraph::resolve(mappings_dir)?;
+            let version = version_graph.get(&version)?;
+
+            let calamus_jar = map_calamus_jar(&downloader, version).await?;
+
+            let mappings = version_graph.apply_diffs(version)? // calamus -> named
+                .remove_dummy("named")?;
+
+            let report = coverage::coverage(&mappings, &calamus_jar)?;
+
+            print!("{}", coverage::format_report(&report, per_package)?);
+
+            Ok(())
+        },
         Command::DumpVersionGraph { output } => {
             let version_graph = VersionGraph::resolve(mappings_dir)?;
 
@@ -371,6 +447,13 @@ async fn main() -> Result<()> {
 
             version_graph.write_as_dot(&mut f)?;
 
+            Ok(())
+        },
+        Command::Disassemble { jar, class } => {
+            let jar = dukebox::storage::FileJar { path: jar };
+
+            print!("{}", disassemble::disassemble_from_jar(&jar, &class)?);
+
             Ok(())
         },
     }
@@ -380,6 +463,8 @@ async fn main() -> Result<()> {
 struct PropagationOptions {
     direction: PropagationDirection,
     lenient: bool,
+    /// Only compute the changes, but don't write them.
+    dry_run: bool,
 }
 
 // TODO: implement these
@@ -399,28 +484,41 @@ async fn map_calamus_jar(downloader: &Downloader, version: VersionEntry<'_>) ->
     let versions_manifest = downloader.get_versions_manifest().await?;
     let version_details = downloader.version_details(&versions_manifest, version).await?;
 
-    let client = downloader.get_jar(&version_details.downloads.client.url).await?;
-    let server = downloader.get_jar(&version_details.downloads.server.url).await?;
+    let calamus = downloader.calamus_v2(version).await?;
 
-    // TODO: but don't merge for split versions
-    let start = Instant::now();
+    // versions with only a client or only a server jar can't be merged, so the single jar is used directly
+    let out_jar = match version_details.jars()? {
+        Jars::Both { client, server } => {
+            let client = downloader.get_jar(&client.url).await?;
+            let server = downloader.get_jar(&server.url).await?;
 
-    let main_jar = dukebox::merge::merge(client, server)
-        .with_context(|| anyhow!("failed to merge jars for version {version:?}"))?;
+            let start = Instant::now();
 
-    println!("jar merging took {:?}", start.elapsed());
+            let main_jar = dukebox::merge::merge(client, server)
+                .with_context(|| anyhow!("failed to merge jars for version {version:?}"))?;
 
-    let calamus = downloader.calamus_v2(version).await?;
+            println!("jar merging took {:?}", start.elapsed());
 
-    // TODO: should probably also add in the libraries here...
-    let inheritance = main_jar.get_super_classes_provider()?;
-    let out_jar = dukebox::remap::remap(main_jar, calamus.remapper_b_first_to_second(&inheritance)?)?;
+            remap_to_calamus(main_jar, &calamus)?
+        },
+        Jars::Client(jar) | Jars::Server(jar) => {
+            let main_jar = downloader.get_jar(&jar.url).await?;
+
+            remap_to_calamus(main_jar, &calamus)?
+        },
+    };
 
     println!("remapping done!");
 
     Ok(out_jar)
 }
 
+fn remap_to_calamus(main_jar: impl Jar, calamus: &Mappings<2>) -> Result<ParsedJar<ClassRepr, Vec<u8>>> {
+    // TODO: should probably also add in the libraries here...
+    let inheritance = main_jar.get_super_classes_provider()?;
+    dukebox::remap::remap(main_jar, calamus.remapper_b_first_to_second(&inheritance)?)
+}
+
 async fn nest_jar(downloader: &Downloader, version: VersionEntry<'_>, calamus_jar: &impl Jar) -> Result<Option<ParsedJar<ClassRepr, Vec<u8>>>> {
 
     let calamus_nests_file = patch_nests(downloader, version).await?;
@@ -471,6 +569,24 @@ struct Cli {
     #[arg(long = "offline")]
     offline: bool,
 
+    /// How often to retry a download failing with a server error or a connection error
+    #[arg(long = "retries", default_value_t = 3)]
+    retries: u32,
+
+    /// The delay in milliseconds before the first retry of a download
+    ///
+    /// The delay doubles with each further retry.
+    #[arg(long = "retry-delay", default_value_t = 500)]
+    retry_delay: u64,
+
+    /// The time in seconds to wait for a connection to a server to be established
+    #[arg(long = "connect-timeout")]
+    connect_timeout: Option<u64>,
+
+    /// The time in seconds to wait for a whole download, until it's considered failed
+    #[arg(long = "timeout")]
+    timeout: Option<u64>,
+
     /// The mappings directory, default is 'mappings'
     ///
     /// This directory contains the '.tinydiff' and one '.tiny' file.
@@ -519,6 +635,16 @@ enum Command {
         #[arg(long = "enigma-profile")]
         enigma_profile: Option<PathBuf>,
 
+        /// Read the resolved dependencies of enigma from this lockfile, instead of resolving them
+        ///
+        /// Fails if the lockfile was written for other dependencies.
+        #[arg(long = "read-lockfile")]
+        read_lockfile: Option<PathBuf>,
+
+        /// Write the resolved dependencies of enigma to this lockfile
+        #[arg(long = "write-lockfile")]
+        write_lockfile: Option<PathBuf>,
+
         /// The version to edit the mappings of
         version: String,
     },
@@ -546,6 +672,22 @@ enum Command {
         #[arg(short = 'd', long = "direction", value_enum, default_value_t)]
         direction: PropagationDirection,
 
+        /// Only print which versions would be changed, and how, without writing any files
+        ///
+        /// This also keeps the working mappings directory.
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+
+        version: String,
+    },
+
+    /// Print how many of the classes, methods and fields of a version are named
+    Coverage {
+        /// Also print the coverage of each package
+        #[arg(long = "per-package")]
+        per_package: bool,
+
+        /// The version to compute the coverage of
         version: String,
     },
 
@@ -553,6 +695,15 @@ enum Command {
     DumpVersionGraph {
         output: PathBuf,
     },
+
+    /// Print a 'javap' like listing of a class in a jar. This is intended for debugging.
+    Disassemble {
+        /// The jar to read the class from
+        jar: PathBuf,
+
+        /// The name of the class, like 'org/example/Foo'
+        class: String,
+    },
 }
 
 // TODO: doc
@@ -1,5 +1,5 @@
 use std::convert::Infallible;
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use std::ops::ControlFlow;
 use indexmap::{IndexMap, IndexSet};
 use indexmap::map::Entry;
@@ -11,10 +11,11 @@ use duke::tree::method::code::Instruction;
 use duke::tree::version::Version;
 use duke::visitor::MultiClassVisitor;
 use duke::visitor::simple::class::SimpleClassVisitor;
-use dukebox::storage::{Jar, OpenedJar};
+use dukebox::storage::{IsClass, Jar, JarEntry, JarEntryEnum, OpenedJar};
 use quill::remapper::{BRemapper, JarSuperProv};
 use quill::tree::mappings::{Mappings, MethodMapping, MethodNowodeMapping};
 use quill::tree::{NodeInfo, ToKey};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 /// Stores all known entries
 #[derive(Default)]
@@ -134,6 +135,26 @@ impl MultiClassVisitor for MultiClassVisitorImpl {
 }
 
 impl MultiClassVisitorImpl {
+	/// Adds the indexes of `other` to the ones of `self`.
+	///
+	/// Merging the indexes of classes visited one by one, in order, gives the same indexes (including the iteration order) as
+	/// visiting all these classes into one visitor.
+	fn merge(&mut self, other: MultiClassVisitorImpl) {
+		self.entry.classes.extend(other.entry.classes);
+		self.entry.methods.extend(other.entry.methods);
+
+		for (class, parents) in other.inheritance.parents {
+			self.inheritance.parents.entry(class).or_default().extend(parents);
+		}
+		for (class, children) in other.inheritance.children {
+			self.inheritance.children.entry(class).or_default().extend(children);
+		}
+
+		for (method, references) in other.reference.method_references {
+			self.reference.method_references.entry(method).or_default().extend(references);
+		}
+	}
+
 	fn get_specialized_methods(self) -> Result<SpecializedMethods> {
 		fn are_types_bridge_compatible(visitor: &MultiClassVisitorImpl, bridge_desc: &Type, specialized_desc: &Type) -> bool {
 			match (bridge_desc, specialized_desc) {
@@ -301,17 +322,9 @@ pub(crate) fn add_specialized_methods_to_mappings(
 		super_classes_provider.push(library.get_super_classes_provider()?);
 	}
 
-	let remapper_calamus = calamus.remapper_b(
-		calamus.get_namespace("official")?,
-		calamus.get_namespace("intermediary")?,
-		&super_classes_provider
-	)?;
+	let remapper_calamus = calamus.remapper("official", "intermediary", &super_classes_provider)?;
 	let x = JarSuperProv::remap(&remapper_calamus, &super_classes_provider)?;
-	let remapper_named = mappings.remapper_b(
-		mappings.get_namespace("calamus")?,
-		mappings.get_namespace("named")?,
-		&x
-	)?;
+	let remapper_named = mappings.remapper("calamus", "named", &x)?;
 
 	let specialized_methods =
 		main_jar.get_specialized_methods()? // official
@@ -347,13 +360,41 @@ pub(crate) trait GetSpecializedMethods {
 	fn get_specialized_methods(&self) -> Result<SpecializedMethods>;
 }
 
-impl<J: Jar> GetSpecializedMethods for J {
-	fn get_specialized_methods(&self) -> Result<SpecializedMethods> {
-		let visitor = MultiClassVisitorImpl::default();
+/// Reads the classes of the jar into the indexes, parsing the classes in parallel.
+///
+/// The classes are read from the jar sequentially, and then each parsed into its own indexes, or visited as they are if
+/// they're already parsed. These are then merged in the order of the entries of the jar, so that the result is the same
+/// as reading all classes into one visitor.
+fn read_classes_parallel(jar: &impl Jar) -> Result<MultiClassVisitorImpl> {
+	let mut opened = jar.open()?;
+
+	let mut classes = Vec::new();
+	for key in opened.entry_keys() {
+		let entry = opened.by_entry_key(key)?;
+		let name = entry.name().to_owned();
+
+		if let JarEntryEnum::Class(class) = entry.to_jar_entry_enum()? {
+			classes.push((name, class.into_class_repr()));
+		}
+	}
 
-		let visitor = self.open()?.read_classes_into(visitor)?;
+	let visitors: Vec<MultiClassVisitorImpl> = classes.into_par_iter()
+		.map(|(name, class)| {
+			class.visit(MultiClassVisitorImpl::default())
+				.with_context(|| anyhow!("while reading class entry {name:?}"))
+		})
+		.collect::<Result<_>>()?;
 
-		visitor.get_specialized_methods()
+	let mut visitor = MultiClassVisitorImpl::default();
+	for other in visitors {
+		visitor.merge(other);
+	}
+	Ok(visitor)
+}
+
+impl<J: Jar> GetSpecializedMethods for J {
+	fn get_specialized_methods(&self) -> Result<SpecializedMethods> {
+		read_classes_parallel(self)?.get_specialized_methods()
 	}
 }
 
@@ -364,8 +405,10 @@ mod testing {
 	use indexmap::IndexMap;
 	use duke::tree::class::ClassName;
 	use duke::tree::method::{MethodDescriptor, MethodName, MethodRef};
+	use dukebox::storage::{BasicFileAttributes, ClassRepr, JarEntryEnum, Jar, OpenedJar, ParsedJar, ParsedJarEntry};
+	use pretty_assertions::assert_eq;
 	use raw_class_file::{AttributeInfo, ClassFile, CpInfo, FieldInfo, flags, insn, MethodInfo};
-	use crate::specialized_methods::MultiClassVisitorImpl;
+	use crate::specialized_methods::{GetSpecializedMethods, MultiClassVisitorImpl};
 
 	#[test]
 	fn class_files() -> Result<()> {
@@ -390,13 +433,13 @@ mod testing {
 			specialized_methods.bridge_to_specialized,
 			IndexMap::from([
 				(MethodRef {
-					class: unsafe { ClassName::from_inner_unchecked("MyNode".to_owned().into()) },
-					name: unsafe { MethodName::from_inner_unchecked("setData".to_owned().into()) },
-					desc: unsafe { MethodDescriptor::from_inner_unchecked("(Ljava/lang/Object;)V".to_owned().into()) },
+					class: ClassName::try_from_inner("MyNode".to_owned().into())?,
+					name: MethodName::try_from_inner("setData".to_owned().into())?,
+					desc: MethodDescriptor::try_from_inner("(Ljava/lang/Object;)V".to_owned().into())?,
 				}, MethodRef {
-					class: unsafe { ClassName::from_inner_unchecked("MyNode".to_owned().into()) },
-					name: unsafe { MethodName::from_inner_unchecked("setData".to_owned().into()) },
-					desc: unsafe { MethodDescriptor::from_inner_unchecked("(Ljava/lang/Integer;)V".to_owned().into()) },
+					class: ClassName::try_from_inner("MyNode".to_owned().into())?,
+					name: MethodName::try_from_inner("setData".to_owned().into())?,
+					desc: MethodDescriptor::try_from_inner("(Ljava/lang/Integer;)V".to_owned().into())?,
 				}),
 			])
 		);
@@ -404,13 +447,13 @@ mod testing {
 			specialized_methods.specialized_to_bridge,
 			IndexMap::from([
 				(MethodRef {
-					class: unsafe { ClassName::from_inner_unchecked("MyNode".to_owned().into()) },
-					name: unsafe { MethodName::from_inner_unchecked("setData".to_owned().into()) },
-					desc: unsafe { MethodDescriptor::from_inner_unchecked("(Ljava/lang/Integer;)V".to_owned().into()) },
+					class: ClassName::try_from_inner("MyNode".to_owned().into())?,
+					name: MethodName::try_from_inner("setData".to_owned().into())?,
+					desc: MethodDescriptor::try_from_inner("(Ljava/lang/Integer;)V".to_owned().into())?,
 				}, MethodRef {
-					class: unsafe { ClassName::from_inner_unchecked("MyNode".to_owned().into()) },
-					name: unsafe { MethodName::from_inner_unchecked("setData".to_owned().into()) },
-					desc: unsafe { MethodDescriptor::from_inner_unchecked("(Ljava/lang/Object;)V".to_owned().into()) },
+					class: ClassName::try_from_inner("MyNode".to_owned().into())?,
+					name: MethodName::try_from_inner("setData".to_owned().into())?,
+					desc: MethodDescriptor::try_from_inner("(Ljava/lang/Object;)V".to_owned().into())?,
 				}),
 			])
 		);
@@ -617,13 +660,13 @@ mod testing {
 			specialized_methods.bridge_to_specialized,
 			IndexMap::from([
 				(MethodRef {
-					class: unsafe { ClassName::from_inner_unchecked("MyNode".to_owned().into()) },
-					name: unsafe { MethodName::from_inner_unchecked("setData".to_owned().into()) },
-					desc: unsafe { MethodDescriptor::from_inner_unchecked("(Ljava/lang/Object;)V".to_owned().into()) },
+					class: ClassName::try_from_inner("MyNode".to_owned().into())?,
+					name: MethodName::try_from_inner("setData".to_owned().into())?,
+					desc: MethodDescriptor::try_from_inner("(Ljava/lang/Object;)V".to_owned().into())?,
 				}, MethodRef {
-					class: unsafe { ClassName::from_inner_unchecked("MyNode".to_owned().into()) },
-					name: unsafe { MethodName::from_inner_unchecked("specialized".to_owned().into()) },
-					desc: unsafe { MethodDescriptor::from_inner_unchecked("(Ljava/lang/Integer;)V".to_owned().into()) },
+					class: ClassName::try_from_inner("MyNode".to_owned().into())?,
+					name: MethodName::try_from_inner("specialized".to_owned().into())?,
+					desc: MethodDescriptor::try_from_inner("(Ljava/lang/Integer;)V".to_owned().into())?,
 				}),
 			])
 		);
@@ -631,24 +674,24 @@ mod testing {
 			specialized_methods.specialized_to_bridge,
 			IndexMap::from([
 				(MethodRef {
-					class: unsafe { ClassName::from_inner_unchecked("MyNode".to_owned().into()) },
-					name: unsafe { MethodName::from_inner_unchecked("specialized".to_owned().into()) },
-					desc: unsafe { MethodDescriptor::from_inner_unchecked("(Ljava/lang/Integer;)V".to_owned().into()) },
+					class: ClassName::try_from_inner("MyNode".to_owned().into())?,
+					name: MethodName::try_from_inner("specialized".to_owned().into())?,
+					desc: MethodDescriptor::try_from_inner("(Ljava/lang/Integer;)V".to_owned().into())?,
 				}, MethodRef {
-					class: unsafe { ClassName::from_inner_unchecked("MyNode".to_owned().into()) },
-					name: unsafe { MethodName::from_inner_unchecked("setData".to_owned().into()) },
-					desc: unsafe { MethodDescriptor::from_inner_unchecked("(Ljava/lang/Object;)V".to_owned().into()) },
+					class: ClassName::try_from_inner("MyNode".to_owned().into())?,
+					name: MethodName::try_from_inner("setData".to_owned().into())?,
+					desc: MethodDescriptor::try_from_inner("(Ljava/lang/Object;)V".to_owned().into())?,
 				}),
 				/*
 				// TODO: see todo about putting more stuff into that map
 				(MethodRef {
-					class: unsafe { ClassName::from_inner_unchecked("MyNode".to_owned()) },
-					name: unsafe { MethodName::from_inner_unchecked("setData".to_owned()) },
-					desc: unsafe { MethodDescriptor::from_inner_unchecked("(Ljava/lang/Integer;)V".to_owned()) },
+					class: ClassName::try_from_inner("MyNode".to_owned())?,
+					name: MethodName::try_from_inner("setData".to_owned())?,
+					desc: MethodDescriptor::try_from_inner("(Ljava/lang/Integer;)V".to_owned())?,
 				}, MethodRef {
-					class: unsafe { ClassName::from_inner_unchecked("MyNode".to_owned()) },
-					name: unsafe { MethodName::from_inner_unchecked("setData".to_owned()) },
-					desc: unsafe { MethodDescriptor::from_inner_unchecked("(Ljava/lang/Object;)V".to_owned()) },
+					class: ClassName::try_from_inner("MyNode".to_owned())?,
+					name: MethodName::try_from_inner("setData".to_owned())?,
+					desc: MethodDescriptor::try_from_inner("(Ljava/lang/Object;)V".to_owned())?,
 				}),
 				 */
 			])
@@ -656,4 +699,41 @@ mod testing {
 
 		Ok(())
 	}
+
+	#[test]
+	fn parallel_equals_sequential() -> Result<()> {
+		let parsed = |data: &[u8]| -> Result<_> {
+			Ok(ParsedJarEntry::from_class(duke::read_class(&mut Cursor::new(data))?))
+		};
+		// one class stays unparsed, to also have a class that's parsed while visiting
+		let jar: ParsedJar<ClassRepr, Vec<u8>> = ParsedJar {
+			entries: IndexMap::from([
+				parsed(include_bytes!("test/MyNode.class"))?,
+				parsed(include_bytes!("test/Node.class"))?,
+				("SpecializedMethods.class".to_owned(), ParsedJarEntry {
+					attr: BasicFileAttributes::default(),
+					content: JarEntryEnum::Class(ClassRepr::Vec { data: include_bytes!("test/SpecializedMethods.class").to_vec() }),
+				}),
+			]),
+		};
+
+		let sequential = jar.open()?.read_classes_into(MultiClassVisitorImpl::default())?;
+		let parallel = super::read_classes_parallel(&jar)?;
+
+		// compare the iteration order as well, not just the contents
+		assert!(parallel.entry.classes.iter().eq(&sequential.entry.classes));
+		assert!(parallel.entry.methods.iter().eq(&sequential.entry.methods));
+		assert!(parallel.inheritance.parents.iter().eq(&sequential.inheritance.parents));
+		assert!(parallel.inheritance.children.iter().eq(&sequential.inheritance.children));
+		assert!(parallel.reference.method_references.iter().eq(&sequential.reference.method_references));
+
+		let sequential = sequential.get_specialized_methods()?;
+		let parallel = jar.get_specialized_methods()?;
+
+		assert_eq!(parallel.bridge_to_specialized, sequential.bridge_to_specialized);
+		assert!(parallel.specialized_to_bridge.iter().eq(&sequential.specialized_to_bridge));
+		assert_eq!(parallel.bridge_to_specialized.len(), 1);
+
+		Ok(())
+	}
 }
\ No newline at end of file
@@ -182,17 +182,9 @@ fn add_specialized_methods_to_mappings(
 		super_classes_provider.push(library.get_super_classes_provider()?);
 	}
 
-	let remapper_calamus = calamus.remapper_b(
-		calamus.get_namespace("official")?,
-		calamus.get_namespace("intermediary")?,
-		&super_classes_provider
-	)?;
+	let remapper_calamus = calamus.remapper("official", "intermediary", &super_classes_provider)?;
 	let x = JarSuperProv::remap(&remapper_calamus, &super_classes_provider)?;
-	let remapper_named = mappings.remapper_b(
-		mappings.get_namespace("calamus")?,
-		mappings.get_namespace("named")?,
-		&x
-	)?;
+	let remapper_named = mappings.remapper("calamus", "named", &x)?;
 
 	let specialized_methods =
 		main_jar.get_specialized_methods()? // official
@@ -6,9 +6,9 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use indexmap::IndexMap;
 use petgraph::{Direction, Graph};
-use petgraph::graph::NodeIndex;
+use petgraph::graph::{EdgeIndex, NodeIndex};
 use quill::tree::mappings::Mappings;
-use quill::tree::mappings_diff::MappingsDiff;
+use quill::tree::mappings_diff::{ClassNowodeDiff, MappingsDiff};
 use crate::download::versions_manifest::MinecraftVersion;
 
 const VERSION_SHORTCUTS: [(&str, &str); 53] = [
@@ -93,6 +93,7 @@ struct EdgeData {
 
 pub(crate) struct VersionGraph {
 	root: NodeIndex,
+	root_path: PathBuf,
 	root_mapping: Mappings<2>,
 
 	versions: IndexMap<String, NodeIndex>,
@@ -137,19 +138,29 @@ impl NodeData {
 }
 
 impl VersionGraph {
-	pub(crate) fn is_root_then_get_mappings(&self, version: VersionEntry<'_>) -> Option<&Mappings<2>> {
+	/// Gets the mappings of the root, if `version` is the root.
+	///
+	/// If the mappings were changed, the changed ones from `pending` are returned.
+	pub(crate) fn is_root_then_get_mappings<'a>(&'a self, pending: &'a PendingChanges, version: VersionEntry<'_>) -> Option<&'a Mappings<2>> {
 		if version.node_index == self.root {
-			Some(&self.root_mapping)
+			Some(pending.root_mapping.as_ref().unwrap_or(&self.root_mapping))
 		} else {
 			None
 		}
 	}
 
-	pub(crate) fn get_diff(&self, parent: VersionEntry<'_>, version: VersionEntry<'_>) -> Result<Option<MappingsDiff>> {
+	/// Gets the diff from `parent` to `version`, or [`None`] if there's no such diff.
+	///
+	/// If the diff was changed, the changed one from `pending` is returned.
+	pub(crate) fn get_diff(&self, pending: &PendingChanges, parent: VersionEntry<'_>, version: VersionEntry<'_>) -> Result<Option<MappingsDiff>> {
 		let Some(edge) = self.graph.find_edge(parent.node_index, version.node_index) else {
 			return Ok(None);
 		};
 
+		if let Some(diff) = pending.diffs.get(&edge) {
+			return Ok(Some(diff.clone()));
+		}
+
 		let path = &self.graph[edge].path;
 
 		quill::tiny_v2_diff::read_file(path)
@@ -163,6 +174,43 @@ impl VersionGraph {
 		}
 	}
 
+	/// Writes the changes in `pending` for each changed file.
+	///
+	/// For the root mappings, this is a line based diff of the mappings in the form they'd be written in, so that only the
+	/// changes are shown, and not any differences in formatting or order. For the version diffs, the classes whose diff
+	/// changes are listed.
+	pub(crate) fn write_pending_diff(&self, pending: &PendingChanges, w: &mut impl Write) -> Result<()> {
+		if let Some(root_mapping) = &pending.root_mapping {
+			let old = quill::tiny_v2::write_string(&self.root_mapping)?;
+			let new = quill::tiny_v2::write_string(root_mapping)?;
+			write_line_diff(w, &self.root_path, &old, &new)?;
+		}
+
+		for (&edge, diff) in &pending.diffs {
+			let path = &self.graph[edge].path;
+			let old = quill::tiny_v2_diff::read_file(path)
+				.with_context(|| anyhow!("failed to parse version diff from {path:?}"))?;
+
+			// the diffs can't be compared directly, so we compare their debug representation
+			let changed = |a: Option<&ClassNowodeDiff>, b: Option<&ClassNowodeDiff>| format!("{a:?}") != format!("{b:?}");
+
+			let changed_classes: Vec<_> = diff.classes.keys()
+				.chain(old.classes.keys().filter(|key| !diff.classes.contains_key(*key)))
+				.filter(|key| changed(old.classes.get(*key), diff.classes.get(*key)))
+				.collect();
+
+			if !changed_classes.is_empty() {
+				writeln!(w, "--- {}", path.display())?;
+				writeln!(w, "+++ {}", path.display())?;
+				for class in changed_classes {
+					writeln!(w, "~c\t{class}")?;
+				}
+			}
+		}
+
+		Ok(())
+	}
+
 	pub(crate) fn write_as_dot(&self, w: &mut impl Write) -> Result<()> {
 		let dot = petgraph::dot::Dot::new(&self.graph);
 		write!(w, "{:?}", dot)
@@ -246,7 +294,7 @@ impl VersionGraph {
 			}
 		}
 
-		Ok(VersionGraph { root, root_mapping, versions, graph })
+		Ok(VersionGraph { root, root_path, root_mapping, versions, graph })
 	}
 
 	pub(crate) fn versions(&self) -> impl Iterator<Item=VersionEntry<'_>> {
@@ -298,6 +346,102 @@ impl VersionGraph {
 	}
 }
 
+/// Changes to the mappings and diffs of a [`VersionGraph`], not yet written.
+///
+/// Later changes are applied on top of the earlier ones.
+#[derive(Default)]
+pub(crate) struct PendingChanges {
+	root_mapping: Option<Mappings<2>>,
+	diffs: IndexMap<EdgeIndex, MappingsDiff>,
+}
+
+impl PendingChanges {
+	pub(crate) fn set_root_mapping(&mut self, mappings: Mappings<2>) {
+		self.root_mapping = Some(mappings);
+	}
+
+	/// Sets the diff from `parent` to `version`.
+	pub(crate) fn set_diff(&mut self, version_graph: &VersionGraph, parent: VersionEntry<'_>, version: VersionEntry<'_>, diff: MappingsDiff) -> Result<()> {
+		let edge = version_graph.graph.find_edge(parent.node_index, version.node_index)
+			.with_context(|| anyhow!("there is no diff between {parent:?} and {version:?}"))?;
+		self.diffs.insert(edge, diff);
+		Ok(())
+	}
+}
+
+/// Writes the changes between the lines of `old` and `new`, prefixing removed lines with `-` and added ones with `+`.
+///
+/// Both are split into blocks, starting at each line without indentation, like the `c` lines of the tiny formats. Blocks are
+/// compared by their first two fields, and only changed blocks are written, with their unchanged lines prefixed with ` `.
+fn write_line_diff(w: &mut impl Write, path: &Path, old: &str, new: &str) -> Result<()> {
+	fn blocks(s: &str) -> IndexMap<&str, Vec<&str>> {
+		let mut blocks: IndexMap<&str, Vec<&str>> = IndexMap::new();
+		let mut key = "";
+		for line in s.lines() {
+			if !line.starts_with('\t') {
+				key = line.match_indices('\t').nth(1).map_or(line, |(end, _)| &line[..end]);
+			}
+			blocks.entry(key).or_default().push(line);
+		}
+		blocks
+	}
+
+	let old = blocks(old);
+	let new = blocks(new);
+	if old == new {
+		return Ok(());
+	}
+
+	writeln!(w, "--- {}", path.display())?;
+	writeln!(w, "+++ {}", path.display())?;
+
+	for (key, new_lines) in &new {
+		let old_lines = old.get(key).map_or(&[][..], Vec::as_slice);
+		if old_lines != new_lines.as_slice() {
+			write_lines_diff(w, old_lines, new_lines)?;
+		}
+	}
+	for (key, old_lines) in &old {
+		if !new.contains_key(key) {
+			write_lines_diff(w, old_lines, &[])?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Writes the lines of `old` and `new`, marking the ones not in their longest common subsequence as removed or added.
+fn write_lines_diff(w: &mut impl Write, old: &[&str], new: &[&str]) -> Result<()> {
+	// lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+	let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+	for i in (0..old.len()).rev() {
+		for j in (0..new.len()).rev() {
+			lcs[i][j] = if old[i] == new[j] {
+				lcs[i + 1][j + 1] + 1
+			} else {
+				lcs[i + 1][j].max(lcs[i][j + 1])
+			};
+		}
+	}
+
+	let (mut i, mut j) = (0, 0);
+	while i < old.len() || j < new.len() {
+		if i < old.len() && j < new.len() && old[i] == new[j] {
+			writeln!(w, " {}", old[i])?;
+			i += 1;
+			j += 1;
+		} else if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+			writeln!(w, "-{}", old[i])?;
+			i += 1;
+		} else {
+			writeln!(w, "+{}", new[j])?;
+			j += 1;
+		}
+	}
+
+	Ok(())
+}
+
 impl PartialEq<MinecraftVersionBorrowed<'_>> for MinecraftVersion {
 	fn eq(&self, other: &MinecraftVersionBorrowed<'_>) -> bool {
 		self.0 == other.0